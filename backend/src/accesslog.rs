@@ -0,0 +1,386 @@
+use actix_web::body::{BodySize, BoxBody, MessageBody};
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::StatusCode;
+use actix_web::web::Bytes;
+use actix_web::{web, Error, HttpMessage, HttpRequest};
+use std::future::{ready, Ready};
+use std::net::IpAddr;
+use std::pin::Pin;
+use std::rc::Rc;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::{Duration, Instant};
+
+use crate::auth::Claims;
+use crate::config::PanelConfig;
+use crate::registry::ServerRegistry;
+use crate::requestid::CorrelationId;
+
+/// Server id from /api/servers/{id}/... or /ws/{id}/... paths.
+fn server_id_from_path(path: &str) -> Option<&str> {
+    let rest = path
+        .strip_prefix("/api/servers/")
+        .or_else(|| path.strip_prefix("/ws/"))?;
+    let id = rest.split('/').next()?;
+    // Global routes that share the /api/servers/ prefix
+    match id {
+        "" | "summary" | "bulk" | "export" | "import-bundle" | "events" => None,
+        id => Some(id),
+    }
+}
+
+/// An address or CIDR range ("10.0.0.0/8", "::1") as its base address and prefix length.
+pub fn parse_network(network: &str) -> Option<(IpAddr, u32)> {
+    let (addr, prefix) = match network.split_once('/') {
+        Some((addr, prefix)) => (addr.parse::<IpAddr>().ok()?, Some(prefix.parse().ok()?)),
+        None => (network.parse::<IpAddr>().ok()?, None),
+    };
+    let bits = if addr.is_ipv4() { 32 } else { 128 };
+    let prefix = prefix.unwrap_or(bits);
+    (prefix <= bits).then_some((addr, prefix))
+}
+
+fn in_network(ip: IpAddr, network: &str) -> bool {
+    let Some((base, prefix)) = parse_network(network) else {
+        return false;
+    };
+    let (ip, base, bits) = match (ip.to_canonical(), base) {
+        (IpAddr::V4(ip), IpAddr::V4(base)) => (u32::from(ip) as u128, u32::from(base) as u128, 32),
+        (IpAddr::V6(ip), IpAddr::V6(base)) => (u128::from(ip), u128::from(base), 128),
+        _ => return false,
+    };
+    let shift = bits - prefix;
+    shift >= bits || ip >> shift == base >> shift
+}
+
+/// Client address. The proxy header is only believed when the peer is one of
+/// `panel.trusted_proxies`; the client is then the rightmost entry that isn't a trusted
+/// proxy, since everything left of it could have been sent by the client itself.
+/// Otherwise, and when the header is missing or malformed, the peer address.
+pub fn client_ip(req: &HttpRequest, panel: &PanelConfig) -> Option<String> {
+    let peer = req.peer_addr().map(|addr| addr.ip());
+    let trusted = |ip: IpAddr| panel.trusted_proxies.iter().any(|n| in_network(ip, n));
+    let forwarded = panel
+        .trusted_proxy_header
+        .as_deref()
+        .filter(|_| peer.is_some_and(trusted))
+        .and_then(|name| req.headers().get(name))
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            let hops = v
+                .split(',')
+                .map(|hop| hop.trim().parse().ok())
+                .collect::<Option<Vec<IpAddr>>>()?;
+            hops.iter()
+                .rev()
+                .find(|ip| !trusted(**ip))
+                .or(hops.first())
+                .copied()
+        });
+    forwarded.or(peer).map(|ip| ip.to_string())
+}
+
+/// What is known about a request once its response head exists; logged when the body
+/// has been sent (or the client went away).
+struct AccessRecord {
+    method: String,
+    path: String,
+    server_id: Option<String>,
+    status: StatusCode,
+    user: Option<String>,
+    client_ip: Option<String>,
+    request_id: Option<String>,
+    started: Instant,
+    slow_after: Duration,
+    /// LGSM action that held the server's lock when the request came in.
+    lock_holder: Option<String>,
+}
+
+impl AccessRecord {
+    fn emit(&self, bytes: u64) {
+        let latency = self.started.elapsed();
+        let latency_ms = latency.as_millis() as u64;
+        // A WebSocket's "latency" is its whole session; never call that slow
+        let slow = latency > self.slow_after && self.status != StatusCode::SWITCHING_PROTOCOLS;
+        if slow {
+            tracing::warn!(
+                target: "access",
+                method = %self.method,
+                path = %self.path,
+                server_id = self.server_id.as_deref(),
+                status = self.status.as_u16(),
+                latency_ms,
+                bytes,
+                user = self.user.as_deref(),
+                client_ip = self.client_ip.as_deref(),
+                request_id = self.request_id.as_deref(),
+                waited_on = self.lock_holder.as_deref(),
+                "Slow request"
+            );
+        } else {
+            tracing::info!(
+                target: "access",
+                method = %self.method,
+                path = %self.path,
+                server_id = self.server_id.as_deref(),
+                status = self.status.as_u16(),
+                latency_ms,
+                bytes,
+                user = self.user.as_deref(),
+                client_ip = self.client_ip.as_deref(),
+                request_id = self.request_id.as_deref(),
+                "Request"
+            );
+        }
+    }
+}
+
+/// Response body that counts the bytes sent and logs the request when dropped.
+pub struct LoggedBody {
+    body: BoxBody,
+    bytes: u64,
+    record: Option<AccessRecord>,
+}
+
+impl MessageBody for LoggedBody {
+    type Error = <BoxBody as MessageBody>::Error;
+
+    fn size(&self) -> BodySize {
+        self.body.size()
+    }
+
+    fn poll_next(
+        mut self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Bytes, Self::Error>>> {
+        let polled = Pin::new(&mut self.body).poll_next(cx);
+        if let Poll::Ready(Some(Ok(ref chunk))) = polled {
+            self.bytes += chunk.len() as u64;
+        }
+        polled
+    }
+}
+
+impl Drop for LoggedBody {
+    fn drop(&mut self) {
+        if let Some(record) = self.record.take() {
+            record.emit(self.bytes);
+        }
+    }
+}
+
+/// Actix-web middleware emitting one `access` event per request. Wrap it outside all but
+/// RequestId so it sees rejections from the other middleware and the compressed byte counts.
+pub struct AccessLog {
+    slow_after: Duration,
+    panel: Rc<PanelConfig>,
+    exclude: Rc<Vec<String>>,
+}
+
+impl AccessLog {
+    pub fn new(config: &PanelConfig) -> Self {
+        Self {
+            slow_after: Duration::from_millis(config.slow_request_ms),
+            panel: Rc::new(config.clone()),
+            exclude: Rc::new(config.access_log_exclude.clone()),
+        }
+    }
+}
+
+impl<S, B> Transform<S, ServiceRequest> for AccessLog
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<LoggedBody>;
+    type Error = Error;
+    type Transform = AccessLogMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AccessLogMiddleware {
+            service: Rc::new(service),
+            slow_after: self.slow_after,
+            panel: self.panel.clone(),
+            exclude: self.exclude.clone(),
+        }))
+    }
+}
+
+pub struct AccessLogMiddleware<S> {
+    service: Rc<S>,
+    slow_after: Duration,
+    panel: Rc<PanelConfig>,
+    exclude: Rc<Vec<String>>,
+}
+
+impl<S, B> Service<ServiceRequest> for AccessLogMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<LoggedBody>;
+    type Error = Error;
+    type Future = Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let excluded = self
+            .exclude
+            .iter()
+            .any(|p| req.path().starts_with(p.as_str()));
+        let slow_after = self.slow_after;
+        let client_ip = client_ip(req.request(), &self.panel);
+
+        Box::pin(async move {
+            let started = Instant::now();
+            let method = req.method().to_string();
+            // Transfer URLs are credentials; keep them out of the logs
+            let path = match req.path().strip_prefix("/api/transfer/") {
+                Some(_) => "/api/transfer/[token]".to_string(),
+                None => req.path().to_string(),
+            };
+            let server_id = server_id_from_path(&path).map(str::to_string);
+            let request_id = req
+                .extensions()
+                .get::<CorrelationId>()
+                .map(|id| id.0.clone());
+
+            // Whatever holds the server's LGSM lock now is what this request may wait on
+            let mut lock_holder = None;
+            let registry = req.app_data::<web::Data<Arc<ServerRegistry>>>();
+            if let (Some(id), Some(registry)) = (server_id.as_deref(), registry) {
+                if !excluded {
+                    if let Some(lock) = registry.get_lgsm_lock(id).await {
+                        lock_holder = lock.current().map(|h| h.action);
+                    }
+                }
+            }
+
+            let result = service.call(req).await;
+            let mut record = AccessRecord {
+                method,
+                path,
+                server_id,
+                status: StatusCode::OK,
+                user: None,
+                client_ip,
+                request_id,
+                started,
+                slow_after,
+                lock_holder,
+            };
+            match result {
+                Ok(res) => {
+                    record.status = res.status();
+                    record.user = res
+                        .request()
+                        .extensions()
+                        .get::<Claims>()
+                        .map(|c| c.sub.clone());
+                    let record = (!excluded).then_some(record);
+                    Ok(res.map_body(|_, body| LoggedBody {
+                        body: body.boxed(),
+                        bytes: 0,
+                        record,
+                    }))
+                }
+                // Rejected by another middleware (401, 429, ...); actix renders the error
+                Err(e) => {
+                    if !excluded {
+                        let response = e.error_response();
+                        record.status = response.status();
+                        let bytes = match response.body().size() {
+                            BodySize::Sized(n) => n,
+                            _ => 0,
+                        };
+                        record.emit(bytes);
+                    }
+                    Err(e)
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn panel(header: Option<&str>, proxies: &[&str]) -> PanelConfig {
+        let mut panel: PanelConfig = serde_yaml::from_str("{}").unwrap();
+        panel.trusted_proxy_header = header.map(str::to_string);
+        panel.trusted_proxies = proxies.iter().map(|p| p.to_string()).collect();
+        panel
+    }
+
+    fn ip(peer: &str, forwarded: Option<&str>, panel: &PanelConfig) -> Option<String> {
+        let mut req = TestRequest::default().peer_addr(format!("{}:40000", peer).parse().unwrap());
+        if let Some(forwarded) = forwarded {
+            req = req.insert_header(("X-Forwarded-For", forwarded));
+        }
+        client_ip(&req.to_http_request(), panel)
+    }
+
+    #[test]
+    fn direct_client_cannot_spoof_its_address() {
+        let panel = panel(Some("X-Forwarded-For"), &["127.0.0.1"]);
+        let got = ip("203.0.113.5", Some("198.51.100.1"), &panel);
+        assert_eq!(got.as_deref(), Some("203.0.113.5"));
+    }
+
+    #[test]
+    fn header_ignored_unless_configured() {
+        let panel = panel(None, &["127.0.0.1"]);
+        let got = ip("127.0.0.1", Some("198.51.100.1"), &panel);
+        assert_eq!(got.as_deref(), Some("127.0.0.1"));
+    }
+
+    #[test]
+    fn trusted_proxy_gives_the_rightmost_untrusted_hop() {
+        let panel = panel(Some("X-Forwarded-For"), &["127.0.0.1", "10.0.0.0/8"]);
+        // The client prepended a fake entry; the proxies appended what they saw
+        let got = ip("127.0.0.1", Some("6.6.6.6, 198.51.100.7, 10.1.2.3"), &panel);
+        assert_eq!(got.as_deref(), Some("198.51.100.7"));
+        let got = ip("10.0.0.1", Some("10.9.9.9, 10.1.2.3"), &panel);
+        assert_eq!(got.as_deref(), Some("10.9.9.9"));
+    }
+
+    #[test]
+    fn malformed_header_falls_back_to_the_peer() {
+        let panel = panel(Some("X-Forwarded-For"), &["127.0.0.1"]);
+        let got = ip("127.0.0.1", Some("198.51.100.7, not-an-ip"), &panel);
+        assert_eq!(got.as_deref(), Some("127.0.0.1"));
+    }
+
+    #[test]
+    fn networks_match_by_prefix() {
+        let cases = [
+            ("10.1.2.3", "10.0.0.0/8", true),
+            ("11.1.2.3", "10.0.0.0/8", false),
+            ("172.17.0.2", "172.16.0.0/12", true),
+            ("192.168.1.1", "192.168.1.1", true),
+            ("192.168.1.2", "192.168.1.1", false),
+            ("::ffff:127.0.0.1", "127.0.0.1", true),
+            ("fd00::1", "fd00::/8", true),
+            ("1.2.3.4", "0.0.0.0/0", true),
+            ("1.2.3.4", "::/0", false),
+            ("1.2.3.4", "10.0.0.0/33", false),
+        ];
+        for (ip, network, expected) in cases {
+            assert_eq!(
+                in_network(ip.parse().unwrap(), network),
+                expected,
+                "{} in {}",
+                ip,
+                network
+            );
+        }
+    }
+}
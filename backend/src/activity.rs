@@ -0,0 +1,260 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::apierror::ApiError;
+use crate::events::{PanelEvent, TimedEvent};
+use crate::pagination::{self, ListQuery, SortKey};
+use crate::registry::{ProvisioningStatus, ServerRegistry};
+use crate::scheduler::{JobRun, Scheduler};
+
+/// Items returned when the client doesn't page.
+const DEFAULT_LIMIT: usize = 50;
+
+const ACTIVITY_SORTS: &[SortKey<ActivityItem>] = &[
+    ("timestamp", |a, b| a.timestamp.cmp(&b.timestamp)),
+    ("serverId", |a, b| a.server_id.cmp(&b.server_id)),
+];
+
+/// What an activity item is about; one icon per kind.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ActivityKind {
+    LgsmAction,
+    LgsmActionFailed,
+    JobSucceeded,
+    JobFailed,
+    JobSkipped,
+    ServerOnline,
+    ServerOffline,
+    Provisioning,
+    ProvisioningReady,
+    ProvisioningFailed,
+    UpdateAvailable,
+    PlayerPeak,
+}
+
+/// One line of the activity feed.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ActivityItem {
+    pub timestamp: DateTime<Utc>,
+    pub kind: ActivityKind,
+    pub server_id: String,
+    /// Who caused it, when known: a panel user, or "scheduler (<job>)" for jobs.
+    pub actor: Option<String>,
+    pub summary: String,
+    /// Request that caused it, to search the logs by.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ActivityItem {
+    /// Feed item for a bus event; every event kind has one.
+    pub fn from_event(event: &TimedEvent) -> Self {
+        let (kind, actor, summary) = match &event.event {
+            PanelEvent::ServerOnline { .. } => {
+                (ActivityKind::ServerOnline, None, "Came online".to_string())
+            }
+            PanelEvent::ServerOffline { .. } => (
+                ActivityKind::ServerOffline,
+                None,
+                "Went offline".to_string(),
+            ),
+            PanelEvent::ProvisioningStatus {
+                status, message, ..
+            } => {
+                let kind = match status {
+                    ProvisioningStatus::Ready => ActivityKind::ProvisioningReady,
+                    ProvisioningStatus::Error => ActivityKind::ProvisioningFailed,
+                    _ => ActivityKind::Provisioning,
+                };
+                (kind, None, format!("Provisioning: {}", message))
+            }
+            PanelEvent::JobFinished {
+                job_name,
+                success,
+                skipped,
+                ..
+            } => job_outcome(job_name, *success, *skipped),
+            PanelEvent::LgsmAction {
+                action,
+                success,
+                initiated_by,
+                ..
+            } => {
+                if *success {
+                    (
+                        ActivityKind::LgsmAction,
+                        initiated_by.clone(),
+                        format!("{} finished", action),
+                    )
+                } else {
+                    (
+                        ActivityKind::LgsmActionFailed,
+                        initiated_by.clone(),
+                        format!("{} failed", action),
+                    )
+                }
+            }
+            PanelEvent::UpdateAvailable { .. } => (
+                ActivityKind::UpdateAvailable,
+                None,
+                "Server update available".to_string(),
+            ),
+            PanelEvent::PlayerPeak { players, .. } => (
+                ActivityKind::PlayerPeak,
+                None,
+                format!("New player peak: {}", players),
+            ),
+        };
+        Self {
+            timestamp: event.timestamp,
+            kind,
+            server_id: event.event.server_id().to_string(),
+            actor,
+            summary,
+            request_id: event.request_id.clone(),
+        }
+    }
+
+    fn from_run(run: &JobRun) -> Self {
+        let (kind, actor, summary) = job_outcome(&run.job_name, run.success, run.skipped);
+        Self {
+            timestamp: run.finished_at,
+            kind,
+            server_id: run.server_id.clone(),
+            actor,
+            summary,
+            request_id: run.request_id.clone(),
+        }
+    }
+}
+
+/// An item as sent on /ws/events to clients subscribed with `"activity": true`.
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ActivityFrame<'a> {
+    Activity(&'a ActivityItem),
+}
+
+fn job_outcome(
+    job_name: &str,
+    success: bool,
+    skipped: bool,
+) -> (ActivityKind, Option<String>, String) {
+    let (kind, outcome) = if skipped {
+        (ActivityKind::JobSkipped, "skipped")
+    } else if success {
+        (ActivityKind::JobSucceeded, "succeeded")
+    } else {
+        (ActivityKind::JobFailed, "failed")
+    };
+    (
+        kind,
+        Some(crate::scheduler::job_initiator(job_name)),
+        format!("Job '{}' {}", job_name, outcome),
+    )
+}
+
+/// Online/offline changes between consecutive monitor snapshots.
+async fn transitions(registry: &ServerRegistry, server_id: Option<&str>) -> Vec<ActivityItem> {
+    let monitors: Vec<_> = registry
+        .runtimes
+        .read()
+        .await
+        .iter()
+        .filter(|(id, _)| server_id.is_none_or(|s| s == id.as_str()))
+        .map(|(id, runtime)| (id.clone(), runtime.game_monitor.clone()))
+        .collect();
+
+    let mut items = Vec::new();
+    for (id, monitor) in monitors {
+        let history = monitor.history.read().await;
+        let mut previous: Option<bool> = None;
+        for snapshot in history.iter() {
+            if previous.is_some_and(|online| online != snapshot.online) {
+                let (kind, summary) = if snapshot.online {
+                    (ActivityKind::ServerOnline, "Came online")
+                } else {
+                    (ActivityKind::ServerOffline, "Went offline")
+                };
+                items.push(ActivityItem {
+                    timestamp: snapshot.timestamp,
+                    kind,
+                    server_id: id.clone(),
+                    actor: None,
+                    summary: summary.to_string(),
+                    request_id: None,
+                });
+            }
+            previous = Some(snapshot.online);
+        }
+    }
+    items
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ActivityQuery {
+    #[serde(alias = "serverId")]
+    pub server_id: Option<String>,
+}
+
+/// GET /api/activity?server_id=
+///
+/// Newest first; the newest 50 unless paged. Job results come from the scheduler's run history and online/offline
+/// changes from the monitor history; LGSM actions, provisioning, update checks and player
+/// peaks come from the events published since the panel started.
+#[utoipa::path(
+    get,
+    path = "/api/activity",
+    tag = "activity",
+    params(ActivityQuery, ListQuery),
+    responses(
+        (status = 200, description = "Activity items, or a `Page` of them when paging", body = [ActivityItem]),
+        (status = 422, description = "Unknown sort key or field, see `details.allowed`", body = ApiError),
+    )
+)]
+pub async fn get_activity(
+    query: web::Query<ActivityQuery>,
+    list: web::Query<ListQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> Result<HttpResponse, ApiError> {
+    let server_id = query.server_id.as_deref();
+    let wanted = |id: &str| server_id.is_none_or(|s| s == id);
+
+    let mut items: Vec<ActivityItem> = registry
+        .events
+        .recent()
+        .iter()
+        // Job runs and online/offline changes have longer-lived records
+        .filter(|e| {
+            !matches!(
+                e.event,
+                PanelEvent::JobFinished { .. }
+                    | PanelEvent::ServerOnline { .. }
+                    | PanelEvent::ServerOffline { .. }
+            )
+        })
+        .filter(|e| wanted(e.event.server_id()))
+        .map(ActivityItem::from_event)
+        .collect();
+    items.extend(
+        scheduler
+            .all_runs()
+            .await
+            .iter()
+            .filter(|run| !run.suppressed && wanted(&run.server_id))
+            .map(ActivityItem::from_run),
+    );
+    items.extend(transitions(&registry, server_id).await);
+
+    items.sort_by_key(|item| std::cmp::Reverse(item.timestamp));
+    if !list.paged() {
+        items.truncate(DEFAULT_LIMIT);
+    }
+    Ok(HttpResponse::Ok().json(pagination::apply(&list, items, ACTIVITY_SORTS)?))
+}
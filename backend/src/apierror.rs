@@ -0,0 +1,228 @@
+use actix_web::http::{header, StatusCode};
+use actix_web::{HttpResponse, ResponseError};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use utoipa::ToSchema;
+
+use crate::requestid;
+
+/// Machine-readable reason a request failed. Clients branch on this, not on the message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, ToSchema)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ErrorCode {
+    ServerNotFound,
+    /// The server exists but has no runtime yet (provisioning, pending attach or failed).
+    ServerNotReady,
+    /// A job, file, plugin or other resource named in the request doesn't exist.
+    NotFound,
+    /// RCON couldn't be reached or the command failed.
+    RconUnavailable,
+    /// The path resolves outside the server's directory, or may not be touched.
+    PathForbidden,
+    /// The console command policy refuses the command; `details.command` names it.
+    CommandBlocked,
+    ValidationFailed,
+    /// The upload is over the configured size limit; `details.maxBytes` gives it.
+    PayloadTooLarge,
+    /// Too many attempts; `details.retryAfterSecs` (and the Retry-After header) says when
+    /// to try again.
+    RateLimited,
+    /// The schedule doesn't parse (cron syntax errors included), never fires or is too
+    /// frequent; `details.formats` lists the accepted schedule formats.
+    InvalidSchedule,
+    /// Unknown sort key or field name in a list query; `details.allowed` lists the valid ones.
+    InvalidQuery,
+    /// The request clashes with current state: already running, duplicate id, wrong status.
+    Conflict,
+    Unauthorized,
+    /// The endpoint needs a token issued in the last few minutes; log in again.
+    ReauthRequired,
+    /// A service the panel depends on (uMod, ...) failed.
+    UpstreamFailed,
+    Internal,
+}
+
+impl ErrorCode {
+    /// The HTTP status each code is sent with.
+    pub fn status(self) -> StatusCode {
+        match self {
+            Self::ServerNotFound | Self::NotFound => StatusCode::NOT_FOUND,
+            Self::ServerNotReady | Self::Conflict => StatusCode::CONFLICT,
+            Self::RconUnavailable => StatusCode::SERVICE_UNAVAILABLE,
+            Self::PathForbidden | Self::CommandBlocked => StatusCode::FORBIDDEN,
+            Self::ValidationFailed => StatusCode::BAD_REQUEST,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            Self::InvalidSchedule | Self::InvalidQuery => StatusCode::UNPROCESSABLE_ENTITY,
+            Self::Unauthorized => StatusCode::UNAUTHORIZED,
+            Self::ReauthRequired => StatusCode::FORBIDDEN,
+            Self::UpstreamFailed => StatusCode::BAD_GATEWAY,
+            Self::Internal => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+}
+
+/// Error body shared by the API handlers.
+#[derive(Debug, Serialize, ToSchema, thiserror::Error)]
+#[error("{message}")]
+pub struct ApiError {
+    pub code: ErrorCode,
+    /// Human-readable message. Sent as `error`, the field clients read before codes existed.
+    #[serde(rename = "error")]
+    pub message: String,
+    /// Extra machine-readable context; omitted when empty.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    #[schema(value_type = Option<Object>)]
+    pub details: BTreeMap<String, serde_json::Value>,
+    /// Correlation id of the request, also in the X-Request-Id header and the logs.
+    #[serde(rename = "requestId", skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ApiError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            details: BTreeMap::new(),
+            request_id: requestid::current(),
+        }
+    }
+
+    pub fn server_not_found() -> Self {
+        Self::new(ErrorCode::ServerNotFound, "Server not found")
+    }
+
+    pub fn server_not_ready() -> Self {
+        Self::new(ErrorCode::ServerNotReady, "Server runtime not found")
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    pub fn validation(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::ValidationFailed, message)
+    }
+
+    pub fn payload_too_large(max_bytes: u64) -> Self {
+        Self::new(
+            ErrorCode::PayloadTooLarge,
+            format!("Upload exceeds {} bytes", max_bytes),
+        )
+        .with_detail("maxBytes", max_bytes)
+    }
+
+    pub fn rate_limited(message: impl Into<String>, retry_after_secs: u64) -> Self {
+        Self::new(ErrorCode::RateLimited, message).with_detail("retryAfterSecs", retry_after_secs)
+    }
+
+    pub fn conflict(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Conflict, message)
+    }
+
+    pub fn rcon(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::RconUnavailable, message)
+    }
+
+    pub fn path_forbidden(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::PathForbidden, message)
+    }
+
+    pub fn unauthorized(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Unauthorized, message)
+    }
+
+    pub fn upstream(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::UpstreamFailed, message)
+    }
+
+    pub fn internal(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::Internal, message)
+    }
+
+    /// Attach `value` under `key` in `details`.
+    pub fn with_detail(mut self, key: &str, value: impl Serialize) -> Self {
+        let value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+        self.details.insert(key.to_string(), value);
+        self
+    }
+}
+
+impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        self.code.status()
+    }
+
+    fn error_response(&self) -> HttpResponse {
+        let mut response = HttpResponse::build(self.status_code());
+        if let Some(secs) = self.details.get("retryAfterSecs").and_then(|v| v.as_u64()) {
+            response.insert_header((header::RETRY_AFTER, secs));
+        }
+        response.json(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::body::to_bytes;
+    use actix_web::http::header::HeaderMap;
+    use serde_json::{json, Value};
+
+    /// Status, headers and JSON body `error` is answered with.
+    async fn sent(error: ApiError) -> (u16, HeaderMap, Value) {
+        let response = error.error_response();
+        let (status, headers) = (response.status().as_u16(), response.headers().clone());
+        let body = to_bytes(response.into_body()).await.unwrap();
+        (status, headers, serde_json::from_slice(&body).unwrap())
+    }
+
+    #[actix_web::test]
+    async fn every_code_is_sent_with_its_status_and_shape() {
+        let cases = [
+            (ErrorCode::ServerNotFound, 404, "SERVER_NOT_FOUND"),
+            (ErrorCode::ServerNotReady, 409, "SERVER_NOT_READY"),
+            (ErrorCode::NotFound, 404, "NOT_FOUND"),
+            (ErrorCode::RconUnavailable, 503, "RCON_UNAVAILABLE"),
+            (ErrorCode::PathForbidden, 403, "PATH_FORBIDDEN"),
+            (ErrorCode::CommandBlocked, 403, "COMMAND_BLOCKED"),
+            (ErrorCode::ValidationFailed, 400, "VALIDATION_FAILED"),
+            (ErrorCode::PayloadTooLarge, 413, "PAYLOAD_TOO_LARGE"),
+            (ErrorCode::RateLimited, 429, "RATE_LIMITED"),
+            (ErrorCode::InvalidSchedule, 422, "INVALID_SCHEDULE"),
+            (ErrorCode::InvalidQuery, 422, "INVALID_QUERY"),
+            (ErrorCode::Conflict, 409, "CONFLICT"),
+            (ErrorCode::Unauthorized, 401, "UNAUTHORIZED"),
+            (ErrorCode::ReauthRequired, 403, "REAUTH_REQUIRED"),
+            (ErrorCode::UpstreamFailed, 502, "UPSTREAM_FAILED"),
+            (ErrorCode::Internal, 500, "INTERNAL"),
+        ];
+        for (code, status, name) in cases {
+            let (sent_status, _, body) = sent(ApiError::new(code, "Something failed")).await;
+            assert_eq!(sent_status, status, "{}", name);
+            // Without details or a request id, only the code and the message
+            assert_eq!(body, json!({ "code": name, "error": "Something failed" }));
+        }
+    }
+
+    #[actix_web::test]
+    async fn details_are_sent_and_rate_limits_set_retry_after() {
+        let (status, headers, body) = sent(ApiError::rate_limited("Slow down", 30)).await;
+        assert_eq!(status, 429);
+        assert_eq!(
+            body,
+            json!({
+                "code": "RATE_LIMITED",
+                "error": "Slow down",
+                "details": { "retryAfterSecs": 30 },
+            })
+        );
+        assert_eq!(headers.get(header::RETRY_AFTER).unwrap(), "30");
+
+        let (status, headers, body) = sent(ApiError::payload_too_large(1024)).await;
+        assert_eq!(status, 413);
+        assert_eq!(body["details"], json!({ "maxBytes": 1024 }));
+        assert!(headers.get(header::RETRY_AFTER).is_none());
+    }
+}
@@ -0,0 +1,408 @@
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::Method;
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::future::{ready, Ready};
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::apierror::ApiError;
+use crate::auth::Claims;
+use crate::config::PanelConfig;
+use crate::diagnostics;
+use crate::pagination::{self, ListQuery, SortKey};
+use crate::requestid::CorrelationId;
+use crate::shutdown::Shutdown;
+
+/// Current log; rotated copies are audit.jsonl.1 (newest) to audit.jsonl.<keep>.
+pub const AUDIT_FILE: &str = "audit.jsonl";
+const WRITER_TASK: &str = "audit writer";
+/// Entries waiting for the writer; past this they are dropped (and logged) rather than
+/// holding up a handler.
+const QUEUE_SIZE: usize = 1024;
+/// Entries returned when the client doesn't page.
+const DEFAULT_LIMIT: usize = 100;
+
+/// POSTs to these create an item, recorded as "<name>.create".
+const COLLECTIONS: &[&str] = &["servers", "schedule", "snippets"];
+
+const AUDIT_SORTS: &[SortKey<AuditEntry>] = &[
+    ("timestamp", |a, b| a.timestamp.cmp(&b.timestamp)),
+    ("user", |a, b| a.user.cmp(&b.user)),
+    ("serverId", |a, b| a.server_id.cmp(&b.server_id)),
+    ("action", |a, b| a.action.cmp(&b.action)),
+];
+
+/// One mutating API call.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub timestamp: DateTime<Utc>,
+    pub user: String,
+    pub server_id: Option<String>,
+    /// Dotted route name, e.g. "restart", "players.kick", "files.write", "schedule.delete".
+    pub action: String,
+    /// What the action touched, when the handler says: a path, a player, a job name.
+    pub detail: Option<String>,
+    /// HTTP status of the response; the attempt is recorded whether or not it succeeded.
+    pub status: u16,
+    pub request_id: Option<String>,
+}
+
+/// What a handler adds to its request's audit entry.
+#[derive(Debug, Clone, Default)]
+struct AuditNote {
+    server_id: Option<String>,
+    detail: Option<String>,
+}
+
+/// Set the detail of the request's audit entry.
+pub fn detail(req: &HttpRequest, detail: impl Into<String>) {
+    let mut extensions = req.extensions_mut();
+    let note = extensions.get_mut::<AuditNote>();
+    match note {
+        Some(note) => note.detail = Some(detail.into()),
+        None => {
+            extensions.insert(AuditNote {
+                server_id: None,
+                detail: Some(detail.into()),
+            });
+        }
+    }
+}
+
+/// Set the detail and server of an entry whose route doesn't name the server, such as a
+/// scheduled job's.
+pub fn detail_for(req: &HttpRequest, server_id: &str, detail: impl Into<String>) {
+    req.extensions_mut().insert(AuditNote {
+        server_id: Some(server_id.to_string()),
+        detail: Some(detail.into()),
+    });
+}
+
+/// Action name from the matched route: the literal segments after /api/ (and after the
+/// server id on per-server routes) joined with dots, plus the verb where the method
+/// carries it. POST .../restart is "restart", DELETE .../plugins/{name} "plugins.delete".
+fn action_name(method: &Method, pattern: &str) -> String {
+    let path = pattern.trim_start_matches("/api/");
+    let path = match path.strip_prefix("servers/{server_id}") {
+        Some("") => "servers",
+        Some(rest) => rest,
+        None => path,
+    };
+    let segments: Vec<&str> = path
+        .split('/')
+        .filter(|s| !s.is_empty() && !s.starts_with('{'))
+        .collect();
+    let last = segments.last().copied().unwrap_or_default();
+    let collection = !path.ends_with('}') && COLLECTIONS.contains(&last);
+    let verb = match *method {
+        Method::POST if collection => Some("create"),
+        Method::PUT | Method::PATCH if last != "write" => Some("update"),
+        Method::DELETE if last != "delete" => Some("delete"),
+        _ => None,
+    };
+    segments
+        .into_iter()
+        .chain(verb)
+        .collect::<Vec<_>>()
+        .join(".")
+}
+
+/// Append-only record of who changed what, kept as JSON lines in the working directory
+/// and rotated by size. Handlers only queue entries; `spawn_writer` writes them.
+pub struct AuditLog {
+    tx: mpsc::Sender<AuditEntry>,
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+}
+
+impl AuditLog {
+    pub fn new(config: &PanelConfig) -> (Self, mpsc::Receiver<AuditEntry>) {
+        let (tx, rx) = mpsc::channel(QUEUE_SIZE);
+        let log = Self {
+            tx,
+            path: PathBuf::from(AUDIT_FILE),
+            max_bytes: config.audit_log_max_bytes,
+            keep: config.audit_log_keep,
+        };
+        (log, rx)
+    }
+
+    /// Queue an entry without waiting for the writer.
+    pub fn record(&self, entry: AuditEntry) {
+        if let Err(e) = self.tx.try_send(entry) {
+            tracing::warn!("Audit entry dropped: {}", e);
+        }
+    }
+
+    fn rotated(&self, n: usize) -> PathBuf {
+        PathBuf::from(format!("{}.{}", self.path.display(), n))
+    }
+
+    /// Shift the rotated copies up by one, dropping the oldest, and start a new file.
+    fn rotate(&self) -> std::io::Result<()> {
+        if self.keep == 0 {
+            return std::fs::remove_file(&self.path);
+        }
+        let _ = std::fs::remove_file(self.rotated(self.keep));
+        for n in (1..self.keep).rev() {
+            let from = self.rotated(n);
+            if from.exists() {
+                std::fs::rename(&from, self.rotated(n + 1))?;
+            }
+        }
+        std::fs::rename(&self.path, self.rotated(1))
+    }
+
+    /// Every stored entry, oldest first. Lines that don't parse are skipped.
+    fn read_all(&self) -> Vec<AuditEntry> {
+        let files = (1..=self.keep)
+            .rev()
+            .map(|n| self.rotated(n))
+            .chain(std::iter::once(self.path.clone()));
+        let mut entries = Vec::new();
+        for path in files {
+            let Ok(file) = std::fs::File::open(&path) else {
+                continue;
+            };
+            entries.extend(
+                BufReader::new(file)
+                    .lines()
+                    .map_while(Result::ok)
+                    .filter_map(|line| serde_json::from_str(&line).ok()),
+            );
+        }
+        entries
+    }
+}
+
+async fn open(path: &Path) -> std::io::Result<(tokio::fs::File, u64)> {
+    let file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .await?;
+    let size = file.metadata().await?.len();
+    Ok((file, size))
+}
+
+/// Write one entry, rotating first if it would take the file past the size limit.
+async fn append(
+    log: &AuditLog,
+    file: &mut Option<(tokio::fs::File, u64)>,
+    entry: &AuditEntry,
+) -> std::io::Result<()> {
+    let mut line = serde_json::to_vec(entry).map_err(std::io::Error::other)?;
+    line.push(b'\n');
+    let len = line.len() as u64;
+    if matches!(file, Some((_, size)) if *size > 0 && *size + len > log.max_bytes) {
+        *file = None;
+        log.rotate()?;
+    }
+    let (handle, size) = match file {
+        Some(file) => file,
+        None => file.insert(open(&log.path).await?),
+    };
+    handle.write_all(&line).await?;
+    handle.flush().await?;
+    *size += len;
+    Ok(())
+}
+
+async fn write_entry(log: &AuditLog, file: &mut Option<(tokio::fs::File, u64)>, entry: AuditEntry) {
+    match append(log, file, &entry).await {
+        Ok(()) => diagnostics::report_ok(WRITER_TASK),
+        Err(e) => {
+            tracing::warn!("Failed to write audit entry: {}", e);
+            diagnostics::report_error(WRITER_TASK, &e);
+            // Reopen on the next entry in case the file was moved away
+            *file = None;
+        }
+    }
+}
+
+/// Single writer for the audit log. On shutdown whatever is still queued is written
+/// before the task ends.
+pub fn spawn_writer(
+    log: Arc<AuditLog>,
+    mut rx: mpsc::Receiver<AuditEntry>,
+    shutdown: Shutdown,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut file = None;
+        loop {
+            tokio::select! {
+                entry = rx.recv() => match entry {
+                    Some(entry) => write_entry(&log, &mut file, entry).await,
+                    None => return,
+                },
+                _ = shutdown.signal() => break,
+            }
+        }
+        while let Ok(entry) = rx.try_recv() {
+            write_entry(&log, &mut file, entry).await;
+        }
+    })
+}
+
+/// Actix-web middleware recording every POST, PUT, PATCH and DELETE made with a login
+/// token. Requests rejected by the middleware inside it (auth, rate limits) aren't
+/// recorded; they never reached a handler.
+pub struct AuditTrail(pub Arc<AuditLog>);
+
+impl<S, B> Transform<S, ServiceRequest> for AuditTrail
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = AuditTrailMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(AuditTrailMiddleware {
+            service: Rc::new(service),
+            log: self.0.clone(),
+        }))
+    }
+}
+
+pub struct AuditTrailMiddleware<S> {
+    service: Rc<S>,
+    log: Arc<AuditLog>,
+}
+
+impl<S, B> Service<ServiceRequest> for AuditTrailMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let log = self.log.clone();
+        let mutating = matches!(
+            *req.method(),
+            Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+        );
+        let method = req.method().clone();
+
+        Box::pin(async move {
+            let res = service.call(req).await?;
+            if !mutating || !res.request().path().starts_with("/api/") {
+                return Ok(res);
+            }
+            let request = res.request();
+            let Some(user) = request.extensions().get::<Claims>().map(|c| c.sub.clone()) else {
+                return Ok(res);
+            };
+            let Some(pattern) = request.match_pattern() else {
+                return Ok(res);
+            };
+            let note = request
+                .extensions()
+                .get::<AuditNote>()
+                .cloned()
+                .unwrap_or_default();
+            log.record(AuditEntry {
+                timestamp: Utc::now(),
+                user,
+                server_id: note
+                    .server_id
+                    .or_else(|| request.match_info().get("server_id").map(str::to_string)),
+                action: action_name(&method, &pattern),
+                // Else the route's own parameters: a plugin, snippet or job id
+                detail: note.detail.or_else(|| {
+                    let params: Vec<&str> = request
+                        .match_info()
+                        .iter()
+                        .filter(|(name, _)| *name != "server_id")
+                        .map(|(_, value)| value)
+                        .collect();
+                    (!params.is_empty()).then(|| params.join(", "))
+                }),
+                status: res.status().as_u16(),
+                request_id: request
+                    .extensions()
+                    .get::<CorrelationId>()
+                    .map(|id| id.0.clone()),
+            });
+            Ok(res)
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct AuditQuery {
+    #[serde(alias = "serverId")]
+    pub server_id: Option<String>,
+    /// An action, or a group of them: "players" matches "players.kick", "players.ban", ...
+    pub action: Option<String>,
+    pub user: Option<String>,
+}
+
+/// GET /api/audit?server_id=&action=&user=
+///
+/// Newest first; the newest 100 unless paged.
+#[utoipa::path(
+    get,
+    path = "/api/audit",
+    tag = "audit",
+    params(AuditQuery, ListQuery),
+    responses(
+        (status = 200, description = "Audit entries, or a `Page` of them when paging", body = [AuditEntry]),
+        (status = 422, description = "Unknown sort key or field, see `details.allowed`", body = ApiError),
+    )
+)]
+pub async fn get_audit(
+    query: web::Query<AuditQuery>,
+    list: web::Query<ListQuery>,
+    log: web::Data<Arc<AuditLog>>,
+) -> Result<HttpResponse, ApiError> {
+    let log = log.get_ref().clone();
+    let mut entries = web::block(move || log.read_all())
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to read audit log: {}", e)))?;
+
+    let query = query.into_inner();
+    entries.retain(|entry| {
+        query
+            .server_id
+            .as_deref()
+            .is_none_or(|id| entry.server_id.as_deref() == Some(id))
+            && query.user.as_deref().is_none_or(|user| entry.user == user)
+            && query.action.as_deref().is_none_or(|action| {
+                entry.action == action
+                    || entry
+                        .action
+                        .strip_prefix(action)
+                        .is_some_and(|rest| rest.starts_with('.'))
+            })
+    });
+    entries.reverse();
+    if !list.paged() {
+        entries.truncate(DEFAULT_LIMIT);
+    }
+    Ok(HttpResponse::Ok().json(pagination::apply(&list, entries, AUDIT_SORTS)?))
+}
@@ -1,49 +1,164 @@
 use actix_web::{dev::ServiceRequest, web, Error, HttpMessage, HttpRequest, HttpResponse};
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Duration, Utc};
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use std::future::{ready, Ready};
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+use utoipa::ToSchema;
 
-use crate::config::AppConfig;
+use crate::accesslog::client_ip;
+use crate::apierror::{ApiError, ErrorCode};
+use crate::config::{AppConfig, PanelConfig};
+use crate::loginguard::LoginGuard;
+use crate::persistence::{write_atomic, SessionStore};
+use crate::validation::ValidationErrors;
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Claims {
     pub sub: String, // username
     pub exp: usize,  // expiry timestamp
     pub iat: usize,  // issued at
+    /// When the password was entered; refreshed tokens keep it, so refreshing doesn't
+    /// count as logging in again. 0 in tokens from before refresh tokens existed.
+    #[serde(default)]
+    pub auth_time: usize,
 }
 
-#[derive(Debug, Deserialize)]
+/// What a refresh token carries. Signed with a key derived from the JWT secret, so it
+/// can't pass as an access token (nor the other way round).
+#[derive(Debug, Serialize, Deserialize)]
+struct RefreshClaims {
+    jti: String,
+    sub: String,
+    exp: usize,
+    iat: usize,
+}
+
+/// A refresh token as the panel stores it. Each refresh replaces the token with a new
+/// one in the same family; presenting a replaced token again revokes the family.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshToken {
+    pub jti: String,
+    /// Shared by every token descended from one login.
+    pub family: String,
+    pub username: String,
+    /// When the login that started the family happened, as a timestamp.
+    pub auth_time: usize,
+    pub issued_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+    /// Id of the token this one was exchanged for.
+    #[serde(default)]
+    pub replaced_by: Option<String>,
+}
+
+/// The admin password hash: config.yaml's, or the one last set through change-password.
+pub struct Credentials {
+    password_hash: RwLock<String>,
+    /// Where a changed hash is written, normally `config::password_path()`.
+    path: PathBuf,
+}
+
+impl Credentials {
+    pub fn new(password_hash: String, path: PathBuf) -> Self {
+        Self {
+            password_hash: RwLock::new(password_hash),
+            path,
+        }
+    }
+
+    fn verify(&self, password: &str) -> Result<bool, ApiError> {
+        let hash = self.password_hash.read().unwrap().clone();
+        bcrypt::verify(password, &hash).map_err(|e| {
+            tracing::error!("Bcrypt verification error: {}", e);
+            ApiError::internal("Authentication error")
+        })
+    }
+
+    /// Store a new hash in the password file and use it from now on.
+    fn set(&self, hash: String) -> std::io::Result<()> {
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        write_atomic(&self.path, &format!("{}\n", hash))?;
+        *self.password_hash.write().unwrap() = hash;
+        Ok(())
+    }
+}
+
+/// Refresh tokens handed out by login and refresh. Access tokens stay stateless; only
+/// refreshing and logging out look here.
+pub struct Sessions {
+    store: Arc<dyn SessionStore>,
+    /// Held while a token is exchanged, so it can't be exchanged twice at once.
+    lock: tokio::sync::Mutex<()>,
+}
+
+impl Sessions {
+    pub fn new(store: Arc<dyn SessionStore>) -> Self {
+        Self {
+            store,
+            lock: tokio::sync::Mutex::new(()),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct LoginRequest {
     pub username: String,
     pub password: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct LoginResponse {
+    /// Access token, sent as `Authorization: Bearer`.
     pub token: String,
     pub username: String,
     pub expires_at: String,
+    /// Exchanged at POST /api/auth/refresh for a new pair; works once.
+    pub refresh_token: String,
+    pub refresh_expires_at: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangePasswordRequest {
+    pub current_password: String,
+    /// At least 8 characters and at most 72 bytes, bcrypt's limit.
+    pub new_password: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RefreshRequest {
+    #[serde(alias = "refresh_token")]
+    pub refresh_token: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct MeResponse {
     pub username: String,
     pub role: String,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
-    error: String,
-}
+/// Role of the panel's one account; every authenticated user has it.
+pub const ADMIN_ROLE: &str = "admin";
 
-/// Create a JWT token for the given username.
-fn create_token(username: &str, secret: &str) -> anyhow::Result<(String, chrono::DateTime<Utc>)> {
-    let expires_at = Utc::now() + Duration::hours(24);
+/// Create an access token for the given username, expiring after `ttl_mins`.
+fn create_token(
+    username: &str,
+    auth_time: usize,
+    secret: &str,
+    ttl_mins: u64,
+) -> anyhow::Result<(String, DateTime<Utc>)> {
+    let now = Utc::now();
+    let expires_at = now + Duration::minutes(ttl_mins as i64);
     let claims = Claims {
         sub: username.to_string(),
         exp: expires_at.timestamp() as usize,
-        iat: Utc::now().timestamp() as usize,
+        iat: now.timestamp() as usize,
+        auth_time,
     };
     let token = encode(
         &Header::default(),
@@ -53,6 +168,95 @@ fn create_token(username: &str, secret: &str) -> anyhow::Result<(String, chrono:
     Ok((token, expires_at))
 }
 
+fn refresh_signing_key(config: &AppConfig) -> Vec<u8> {
+    format!("{}:refresh", config.auth.jwt_secret).into_bytes()
+}
+
+/// Issue an access token and a new refresh token in `family`, storing the refresh token.
+fn issue_tokens(
+    config: &AppConfig,
+    sessions: &Sessions,
+    username: &str,
+    auth_time: usize,
+    family: String,
+) -> Result<(LoginResponse, RefreshToken), ApiError> {
+    let (token, expires_at) = create_token(
+        username,
+        auth_time,
+        &config.auth.jwt_secret,
+        config.auth.access_token_mins,
+    )
+    .map_err(|e| {
+        tracing::error!("Token creation error: {}", e);
+        ApiError::internal("Token creation failed")
+    })?;
+
+    let now = Utc::now();
+    let record = RefreshToken {
+        jti: uuid::Uuid::new_v4().to_string(),
+        family,
+        username: username.to_string(),
+        auth_time,
+        issued_at: now,
+        expires_at: now + Duration::days(config.auth.refresh_token_days as i64),
+        replaced_by: None,
+    };
+    let claims = RefreshClaims {
+        jti: record.jti.clone(),
+        sub: username.to_string(),
+        exp: record.expires_at.timestamp() as usize,
+        iat: now.timestamp() as usize,
+    };
+    let refresh_token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&refresh_signing_key(config)),
+    )
+    .map_err(|e| ApiError::internal(format!("Token creation failed: {}", e)))?;
+    sessions
+        .store
+        .save_refresh_token(&record)
+        .map_err(|e| ApiError::internal(format!("Failed to save session: {}", e)))?;
+
+    let response = LoginResponse {
+        token,
+        username: username.to_string(),
+        expires_at: expires_at.to_rfc3339(),
+        refresh_token,
+        refresh_expires_at: record.expires_at.to_rfc3339(),
+    };
+    Ok((response, record))
+}
+
+/// The stored record for a refresh token, if the token is genuine, unexpired and known.
+/// Replaced tokens are returned too; the caller decides what reuse means.
+fn lookup_refresh_token(
+    token: &str,
+    config: &AppConfig,
+    sessions: &Sessions,
+) -> Result<RefreshToken, ApiError> {
+    let invalid = || ApiError::unauthorized("Invalid or expired refresh token");
+    let claims = decode::<RefreshClaims>(
+        token,
+        &DecodingKey::from_secret(&refresh_signing_key(config)),
+        &Validation::default(),
+    )
+    .map_err(|e| {
+        tracing::debug!("Refresh token rejected: {}", e);
+        invalid()
+    })?
+    .claims;
+    let record = sessions
+        .store
+        .load_refresh_token(&claims.jti)
+        .map_err(|e| ApiError::internal(format!("Failed to load session: {}", e)))?
+        .ok_or_else(invalid)?;
+    if record.expires_at < Utc::now() {
+        return Err(invalid());
+    }
+    Ok(record)
+}
+
 /// Validate a JWT token and return the claims.
 pub fn validate_token(token: &str, secret: &str) -> Result<Claims, jsonwebtoken::errors::Error> {
     let token_data = decode::<Claims>(
@@ -63,62 +267,273 @@ pub fn validate_token(token: &str, secret: &str) -> Result<Claims, jsonwebtoken:
     Ok(token_data.claims)
 }
 
+/// The address failed logins are counted against: the peer, or the client behind a
+/// trusted proxy. Never a header a direct client sent, which it could rotate to dodge the
+/// lockout or set to a victim's address to lock them out.
+fn lockout_key(req: &HttpRequest, panel: &PanelConfig) -> String {
+    client_ip(req, panel).unwrap_or_else(|| "unknown".to_string())
+}
+
 /// POST /api/auth/login
+#[utoipa::path(
+    post,
+    path = "/api/auth/login",
+    tag = "auth",
+    security(),
+    request_body = LoginRequest,
+    responses(
+        (status = 200, body = LoginResponse),
+        (status = 401, description = "Invalid credentials", body = ApiError),
+        (status = 429, description = "Too many failed logins from this address; see Retry-After", body = ApiError),
+    )
+)]
 pub async fn login(
+    req: HttpRequest,
     body: web::Json<LoginRequest>,
     config: web::Data<AppConfig>,
-) -> HttpResponse {
-    // Verify username
-    if body.username != config.auth.admin_username {
-        return HttpResponse::Unauthorized().json(ErrorBody {
-            error: "Invalid credentials".to_string(),
-        });
-    }
-
-    // Verify password against bcrypt hash
-    match bcrypt::verify(&body.password, &config.auth.password_hash) {
-        Ok(true) => {}
-        Ok(false) => {
-            return HttpResponse::Unauthorized().json(ErrorBody {
-                error: "Invalid credentials".to_string(),
-            });
-        }
-        Err(e) => {
-            tracing::error!("Bcrypt verification error: {}", e);
-            return HttpResponse::InternalServerError().json(ErrorBody {
-                error: "Authentication error".to_string(),
-            });
-        }
+    sessions: web::Data<Arc<Sessions>>,
+    guard: web::Data<Arc<LoginGuard>>,
+    credentials: web::Data<Arc<Credentials>>,
+) -> Result<HttpResponse, ApiError> {
+    let client = lockout_key(&req, &config.panel);
+    if let Err(retry_after) = guard.check(&client) {
+        tracing::warn!(
+            target: "audit",
+            client_ip = %client,
+            username = %body.username,
+            retry_after,
+            "Login attempt while locked out"
+        );
+        return Err(ApiError::rate_limited(
+            format!("Too many failed logins, retry in {} seconds", retry_after),
+            retry_after,
+        ));
     }
 
-    // Create JWT
-    match create_token(&body.username, &config.auth.jwt_secret) {
-        Ok((token, expires_at)) => HttpResponse::Ok().json(LoginResponse {
-            token,
-            username: body.username.clone(),
-            expires_at: expires_at.to_rfc3339(),
-        }),
-        Err(e) => {
-            tracing::error!("Token creation error: {}", e);
-            HttpResponse::InternalServerError().json(ErrorBody {
-                error: "Token creation failed".to_string(),
-            })
-        }
+    // Verify username, then the password against the bcrypt hash
+    let valid =
+        body.username == config.auth.admin_username && credentials.verify(&body.password)?;
+    if !valid {
+        let lockout = guard.record_failure(&client);
+        tracing::warn!(
+            target: "audit",
+            client_ip = %client,
+            username = %body.username,
+            lockout_secs = lockout,
+            "Failed login"
+        );
+        return Err(ApiError::unauthorized("Invalid credentials"));
+    }
+    guard.record_success(&client);
+
+    let auth_time = Utc::now().timestamp() as usize;
+    let family = uuid::Uuid::new_v4().to_string();
+    let (response, _) = issue_tokens(&config, &sessions, &body.username, auth_time, family)?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// POST /api/auth/refresh — exchange a refresh token for a new access and refresh token.
+/// The old refresh token stops working; presenting it again ends the whole session.
+#[utoipa::path(
+    post,
+    path = "/api/auth/refresh",
+    tag = "auth",
+    security(),
+    request_body = RefreshRequest,
+    responses(
+        (status = 200, body = LoginResponse),
+        (status = 401, description = "Refresh token invalid, expired, revoked or already used", body = ApiError),
+    )
+)]
+pub async fn refresh(
+    body: web::Json<RefreshRequest>,
+    config: web::Data<AppConfig>,
+    sessions: web::Data<Arc<Sessions>>,
+) -> Result<HttpResponse, ApiError> {
+    let _guard = sessions.lock.lock().await;
+    let mut current = lookup_refresh_token(&body.refresh_token, &config, &sessions)?;
+
+    if current.replaced_by.is_some() {
+        // Only a copy of the token could still be presenting it: end the session for both
+        tracing::warn!(
+            target: "audit",
+            user = %current.username,
+            family = %current.family,
+            "Refresh token reused; revoking the session"
+        );
+        sessions
+            .store
+            .delete_refresh_family(&current.family)
+            .map_err(|e| ApiError::internal(format!("Failed to revoke session: {}", e)))?;
+        return Err(ApiError::unauthorized("Invalid or expired refresh token"));
+    }
+    if current.username != config.auth.admin_username {
+        return Err(ApiError::unauthorized("Invalid or expired refresh token"));
+    }
+
+    let (response, next) = issue_tokens(
+        &config,
+        &sessions,
+        &current.username,
+        current.auth_time,
+        current.family.clone(),
+    )?;
+    current.replaced_by = Some(next.jti);
+    sessions
+        .store
+        .save_refresh_token(&current)
+        .map_err(|e| ApiError::internal(format!("Failed to save session: {}", e)))?;
+    Ok(HttpResponse::Ok().json(response))
+}
+
+/// POST /api/auth/logout — revoke the session a refresh token belongs to. Access tokens
+/// already issued stay valid until they expire.
+#[utoipa::path(
+    post,
+    path = "/api/auth/logout",
+    tag = "auth",
+    security(),
+    request_body = RefreshRequest,
+    responses(
+        (status = 204, description = "Session revoked"),
+        (status = 401, description = "Refresh token invalid, expired or already revoked", body = ApiError),
+    )
+)]
+pub async fn logout(
+    body: web::Json<RefreshRequest>,
+    config: web::Data<AppConfig>,
+    sessions: web::Data<Arc<Sessions>>,
+) -> Result<HttpResponse, ApiError> {
+    let _guard = sessions.lock.lock().await;
+    let current = lookup_refresh_token(&body.refresh_token, &config, &sessions)?;
+    sessions
+        .store
+        .delete_refresh_family(&current.family)
+        .map_err(|e| ApiError::internal(format!("Failed to revoke session: {}", e)))?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// POST /api/auth/change-password — replace the admin password. Every session of the user
+/// is revoked; the response is a new token pair for the caller. Access tokens already
+/// issued keep working until they expire.
+#[utoipa::path(
+    post,
+    path = "/api/auth/change-password",
+    tag = "auth",
+    request_body = ChangePasswordRequest,
+    responses(
+        (status = 200, description = "Password changed; a fresh session for the caller", body = LoginResponse),
+        (status = 400, description = "Current password wrong or new password unusable, per `details.fields`", body = ApiError),
+        (status = 429, description = "Too many wrong passwords from this address; see Retry-After", body = ApiError),
+    )
+)]
+pub async fn change_password(
+    req: HttpRequest,
+    body: web::Json<ChangePasswordRequest>,
+    config: web::Data<AppConfig>,
+    sessions: web::Data<Arc<Sessions>>,
+    guard: web::Data<Arc<LoginGuard>>,
+    credentials: web::Data<Arc<Credentials>>,
+) -> Result<HttpResponse, ApiError> {
+    let username = req
+        .extensions()
+        .get::<Claims>()
+        .map(|c| c.sub.clone())
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+    // Wrong current passwords count like failed logins, so a stolen token can't be used
+    // to guess the password
+    let client = lockout_key(&req, &config.panel);
+    if let Err(retry_after) = guard.check(&client) {
+        return Err(ApiError::rate_limited(
+            format!("Too many failed logins, retry in {} seconds", retry_after),
+            retry_after,
+        ));
+    }
+    if !credentials.verify(&body.current_password)? {
+        let lockout = guard.record_failure(&client);
+        tracing::warn!(
+            target: "audit",
+            client_ip = %client,
+            username = %username,
+            lockout_secs = lockout,
+            "Password change with wrong current password"
+        );
+        let mut errors = ValidationErrors::new();
+        errors.add("currentPassword", "Incorrect password");
+        return Err(errors.into());
+    }
+
+    let mut errors = ValidationErrors::new();
+    if body.new_password.chars().count() < 8 {
+        errors.add("newPassword", "Must be at least 8 characters");
+    } else if body.new_password.len() > 72 {
+        errors.add("newPassword", "Must be at most 72 bytes");
+    } else if body.new_password == body.current_password {
+        errors.add("newPassword", "Must differ from the current password");
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
     }
+
+    let hash = bcrypt::hash(&body.new_password, config.auth.bcrypt_cost)
+        .map_err(|e| ApiError::internal(format!("Failed to hash password: {}", e)))?;
+    credentials
+        .set(hash)
+        .map_err(|e| ApiError::internal(format!("Failed to save password: {}", e)))?;
+    sessions
+        .store
+        .delete_refresh_tokens_for(&username)
+        .map_err(|e| ApiError::internal(format!("Failed to revoke sessions: {}", e)))?;
+    tracing::info!(target: "audit", user = %username, "Admin password changed");
+
+    let auth_time = Utc::now().timestamp() as usize;
+    let family = uuid::Uuid::new_v4().to_string();
+    let (response, _) = issue_tokens(&config, &sessions, &username, auth_time, family)?;
+    Ok(HttpResponse::Ok().json(response))
 }
 
 /// GET /api/auth/me
-pub async fn me(req: HttpRequest) -> HttpResponse {
+#[utoipa::path(
+    get,
+    path = "/api/auth/me",
+    tag = "auth",
+    responses((status = 200, body = MeResponse))
+)]
+pub async fn me(req: HttpRequest) -> Result<HttpResponse, ApiError> {
     if let Some(claims) = req.extensions().get::<Claims>() {
-        HttpResponse::Ok().json(MeResponse {
+        Ok(HttpResponse::Ok().json(MeResponse {
             username: claims.sub.clone(),
-            role: "admin".to_string(),
-        })
+            role: ADMIN_ROLE.to_string(),
+        }))
     } else {
-        HttpResponse::Unauthorized().json(ErrorBody {
-            error: "Not authenticated".to_string(),
-        })
+        Err(ApiError::unauthorized("Not authenticated"))
+    }
+}
+
+/// Step-up check for sensitive endpoints: the caller must have entered their password
+/// within the last `window_mins` minutes.
+pub fn require_recent_auth(req: &HttpRequest, window_mins: u64) -> Result<Claims, ApiError> {
+    let claims = req
+        .extensions()
+        .get::<Claims>()
+        .cloned()
+        .ok_or_else(|| ApiError::unauthorized("Not authenticated"))?;
+    let auth_time = if claims.auth_time > 0 {
+        claims.auth_time
+    } else {
+        claims.iat
+    };
+    let age_secs = Utc::now().timestamp() - auth_time as i64;
+    if age_secs > (window_mins * 60) as i64 {
+        return Err(ApiError::new(
+            ErrorCode::ReauthRequired,
+            format!(
+                "Recent authentication required: log in again (within {} minutes) to continue",
+                window_mins
+            ),
+        ));
     }
+    Ok(claims)
 }
 
 /// Extract Bearer token from Authorization header.
@@ -132,7 +547,7 @@ fn extract_bearer_token(req: &ServiceRequest) -> Option<String> {
 }
 
 /// Actix-web middleware for JWT authentication.
-/// Protects all routes except /api/auth/login.
+/// Protects all routes except the login, refresh and logout endpoints.
 pub struct JwtAuth;
 
 impl<S, B> actix_web::dev::Transform<S, ServiceRequest> for JwtAuth
@@ -181,8 +596,17 @@ where
         Box::pin(async move {
             let path = req.path().to_string();
 
-            // Skip auth for login endpoint, WebSocket upgrades, position updates (uses RCON token), and static files
+            // Skip auth for login, refresh and logout (they take a refresh token), WebSocket
+            // upgrades, position updates (uses RCON token), file transfers (use their own
+            // token), and static files
+            let public_status_enabled = req
+                .app_data::<web::Data<AppConfig>>()
+                .is_some_and(|c| c.panel.public_status.enabled);
             let is_public = path == "/api/auth/login"
+                || path == "/api/auth/refresh"
+                || path == "/api/auth/logout"
+                || (path == "/api/public/status" && public_status_enabled)
+                || path.starts_with("/api/transfer/")
                 || path.starts_with("/ws/")
                 || !path.starts_with("/api/")
                 || (req.method() == actix_web::http::Method::POST && path.ends_with("/positions"));
@@ -195,9 +619,7 @@ where
             let token = match extract_bearer_token(&req) {
                 Some(t) => t,
                 None => {
-                    return Err(actix_web::error::ErrorUnauthorized(
-                        r#"{"error":"Missing authorization token"}"#,
-                    ));
+                    return Err(ApiError::unauthorized("Missing authorization token").into());
                 }
             };
 
@@ -205,9 +627,7 @@ where
             let config = match req.app_data::<web::Data<AppConfig>>() {
                 Some(c) => c.clone(),
                 None => {
-                    return Err(actix_web::error::ErrorInternalServerError(
-                        r#"{"error":"Server configuration error"}"#,
-                    ));
+                    return Err(ApiError::internal("Server configuration error").into());
                 }
             };
 
@@ -218,11 +638,71 @@ where
                 }
                 Err(e) => {
                     tracing::debug!("JWT validation failed: {}", e);
-                    Err(actix_web::error::ErrorUnauthorized(
-                        r#"{"error":"Invalid or expired token"}"#,
-                    ))
+                    Err(ApiError::unauthorized("Invalid or expired token").into())
                 }
             }
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::test::TestRequest;
+
+    fn login_from(peer: &str, forwarded: &str, panel: &PanelConfig) -> String {
+        let req = TestRequest::default()
+            .peer_addr(format!("{}:40000", peer).parse().unwrap())
+            .insert_header(("X-Forwarded-For", forwarded))
+            .to_http_request();
+        lockout_key(&req, panel)
+    }
+
+    #[test]
+    fn direct_clients_cannot_steer_the_lockout_with_forwarded_headers() {
+        let mut panel: PanelConfig = serde_yaml::from_str("{}").unwrap();
+        panel.trusted_proxy_header = Some("X-Forwarded-For".to_string());
+        let auth: crate::config::AuthConfig =
+            serde_yaml::from_str("login_max_failures: 3").unwrap();
+        let guard = LoginGuard::new(&auth);
+
+        // A new fake address on every attempt still counts against the attacker
+        for n in 0..3 {
+            let key = login_from("203.0.113.5", &format!("198.51.100.{}", n), &panel);
+            assert!(guard.check(&key).is_ok());
+            guard.record_failure(&key);
+        }
+        assert!(guard
+            .check(&login_from("203.0.113.5", "198.51.100.99", &panel))
+            .is_err());
+
+        // Naming the victim's address locked out the attacker, not the victim
+        assert!(guard.check(&login_from("198.51.100.1", "", &panel)).is_ok());
+    }
+
+    #[test]
+    fn changed_password_is_kept_in_the_data_directory() {
+        let root =
+            std::env::temp_dir().join(format!("auth-test-{}", uuid::Uuid::new_v4().simple()));
+        let current = root
+            .join(crate::config::DATA_DIR)
+            .join(crate::config::PASSWORD_FILE);
+        let legacy = root.join(crate::config::PASSWORD_FILE);
+
+        // Older panels kept the hash next to config.yaml; it is still read
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(&legacy, "$2b$04$legacy\n").unwrap();
+        let stored = crate::config::stored_password_hash(&current, &legacy).unwrap();
+        assert_eq!(stored.as_deref(), Some("$2b$04$legacy"));
+
+        // Changing it writes the data directory, created on demand, which wins from then on
+        let credentials = Credentials::new("$2b$04$config".to_string(), current.clone());
+        let hash = bcrypt::hash("new password", 4).unwrap();
+        credentials.set(hash.clone()).unwrap();
+        assert!(credentials.verify("new password").unwrap());
+        let stored = crate::config::stored_password_hash(&current, &legacy).unwrap();
+        assert_eq!(stored, Some(hash));
+
+        let _ = std::fs::remove_dir_all(&root);
+    }
+}
@@ -0,0 +1,531 @@
+use actix_files::NamedFile;
+use actix_web::http::header::{ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::sync::oneshot;
+use utoipa::ToSchema;
+
+use crate::apierror::ApiError;
+use crate::audit;
+use crate::config::GameServerConfig;
+use crate::events::PanelEvent;
+use crate::filemanager::safe_resolve;
+use crate::lgsm::{self, ActionRecord};
+use crate::registry::{DesiredState, ServerRegistry};
+use crate::requestid;
+use crate::scheduler::{JobType, Scheduler};
+
+/// Retention options for Backup jobs (the job's payload).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RetentionPolicy {
+    /// Keep at most this many archives.
+    #[serde(default, alias = "keep_last")]
+    pub keep_last: Option<usize>,
+    /// Delete archives older than this.
+    #[serde(default, alias = "max_age_days")]
+    pub max_age_days: Option<u64>,
+    /// Keep the total size of all archives under this.
+    #[serde(default, alias = "max_total_gb")]
+    pub max_total_gb: Option<f64>,
+}
+
+impl RetentionPolicy {
+    pub fn is_empty(&self) -> bool {
+        self.keep_last.is_none() && self.max_age_days.is_none() && self.max_total_gb.is_none()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.keep_last == Some(0) {
+            return Err("keepLast must be at least 1".to_string());
+        }
+        if self.max_age_days == Some(0) {
+            return Err("maxAgeDays must be at least 1".to_string());
+        }
+        if self.max_total_gb.is_some_and(|gb| gb <= 0.0) {
+            return Err("maxTotalGb must be positive".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupArchive {
+    pub name: String,
+    pub size_bytes: u64,
+    pub modified: DateTime<Utc>,
+    #[serde(skip)]
+    pub path: PathBuf,
+}
+
+/// LinuxGSM writes backups to `<base_dir>/lgsm/backup`.
+pub fn backup_dir(config: &GameServerConfig) -> PathBuf {
+    Path::new(&config.paths.base_dir).join("lgsm").join("backup")
+}
+
+/// Archives in `dir`, newest first.
+pub fn list_archives(dir: &Path) -> Vec<BackupArchive> {
+    let mut archives: Vec<BackupArchive> = match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .flatten()
+            .filter_map(|entry| {
+                let meta = entry.metadata().ok()?;
+                if !meta.is_file() {
+                    return None;
+                }
+                Some(BackupArchive {
+                    name: entry.file_name().to_string_lossy().to_string(),
+                    size_bytes: meta.len(),
+                    modified: meta.modified().ok()?.into(),
+                    path: entry.path(),
+                })
+            })
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    archives.sort_by_key(|a| std::cmp::Reverse(a.modified));
+    archives
+}
+
+/// The archive `name` in `dir`. Only a file directly in the backup directory is
+/// accepted; `safe_resolve` rejects anything (a symlink, say) that leads out of it.
+pub fn resolve_archive(dir: &Path, name: &str) -> Result<PathBuf, ApiError> {
+    if name.is_empty() || name.starts_with('.') || name.contains(['/', '\\']) {
+        return Err(ApiError::validation("Invalid backup name"));
+    }
+    let path = safe_resolve(&dir.to_string_lossy(), name).map_err(ApiError::path_forbidden)?;
+    if !path.is_file() {
+        return Err(ApiError::not_found(format!("Backup '{}' not found", name)));
+    }
+    Ok(path)
+}
+
+/// Delete archives beyond the policy, oldest first. Archives named in `protected`
+/// (the one just created) are never deleted. Returns the names that were removed.
+pub fn prune(dir: &Path, policy: &RetentionPolicy, protected: &HashSet<String>) -> Vec<String> {
+    let now = Utc::now();
+    let max_total = policy
+        .max_total_gb
+        .map(|gb| (gb * 1024.0 * 1024.0 * 1024.0) as u64);
+
+    let mut kept = 0usize;
+    let mut kept_bytes = 0u64;
+    let mut pruned = Vec::new();
+    for archive in list_archives(dir) {
+        if protected.contains(&archive.name) {
+            kept += 1;
+            kept_bytes += archive.size_bytes;
+            continue;
+        }
+        let too_many = policy.keep_last.is_some_and(|n| kept >= n);
+        let too_old = policy
+            .max_age_days
+            .is_some_and(|days| now - archive.modified > chrono::Duration::days(days as i64));
+        let too_big = max_total.is_some_and(|max| kept_bytes + archive.size_bytes > max);
+
+        if too_many || too_old || too_big {
+            match std::fs::remove_file(&archive.path) {
+                Ok(()) => pruned.push(archive.name),
+                Err(e) => tracing::warn!("Failed to prune backup '{}': {}", archive.name, e),
+            }
+        } else {
+            kept += 1;
+            kept_bytes += archive.size_bytes;
+        }
+    }
+    pruned
+}
+
+/// GET /api/servers/{server_id}/backups — archives on disk and the retention policy in effect.
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/backups",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Backup directory, retention policy, archives and total size", body = Object),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn list_backups(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> Result<HttpResponse, ApiError> {
+    let config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+
+    // The policy comes from the server's enabled Backup job, if it has one
+    let policy = {
+        let jobs = scheduler.jobs.read().await;
+        jobs.iter()
+            .filter(|j| j.enabled && j.server_id == *server_id && j.job_type == JobType::Backup)
+            .find_map(|j| {
+                j.payload
+                    .as_deref()
+                    .and_then(|p| serde_json::from_str::<RetentionPolicy>(p).ok())
+                    .filter(|p| !p.is_empty())
+            })
+    };
+
+    let dir = backup_dir(&config);
+    let archives = list_archives(&dir);
+    let total_bytes: u64 = archives.iter().map(|a| a.size_bytes).sum();
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "directory": dir.display().to_string(),
+        "policy": policy,
+        "archives": archives,
+        "totalBytes": total_bytes,
+    })))
+}
+
+/// DELETE /api/servers/{server_id}/backups/{name}
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{server_id}/backups/{name}",
+    tag = "backups",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Archive file name"),
+    ),
+    responses(
+        (status = 204, description = "Archive deleted"),
+        (status = 400, description = "Not a plain file name", body = ApiError),
+        (status = 403, description = "Resolves outside the backup directory", body = ApiError),
+        (status = 404, description = "Server or archive not found", body = ApiError),
+        (status = 409, description = "A backup is being written", body = ApiError),
+    )
+)]
+pub async fn delete_backup(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    let config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let archive = resolve_archive(&backup_dir(&config), &name)?;
+
+    // The archive being written may be this one (pipelines can include a backup step)
+    let backing_up = registry
+        .get_lgsm_lock(&server_id)
+        .await
+        .and_then(|lock| lock.current())
+        .is_some_and(|holder| matches!(holder.action.as_str(), "backup" | "pipeline"));
+    if backing_up {
+        return Err(ApiError::conflict(
+            "A backup is running; try again when it has finished",
+        ));
+    }
+
+    std::fs::remove_file(&archive)
+        .map_err(|e| ApiError::internal(format!("Failed to delete backup: {}", e)))?;
+    tracing::info!("Deleted backup '{}' of server '{}'", name, server_id);
+    audit::detail(&req, name);
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// GET /api/servers/{server_id}/backups/{name}/download
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/backups/{name}/download",
+    tag = "backups",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Archive file name"),
+    ),
+    responses(
+        (status = 200, description = "The archive", content_type = "application/octet-stream"),
+        (status = 206, description = "Requested range of the archive"),
+        (status = 400, description = "Not a plain file name", body = ApiError),
+        (status = 403, description = "Resolves outside the backup directory", body = ApiError),
+        (status = 404, description = "Server or archive not found", body = ApiError),
+    )
+)]
+pub async fn download_backup(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    let config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let archive = resolve_archive(&backup_dir(&config), &name)?;
+    let file = NamedFile::open_async(&archive)
+        .await
+        .map_err(|_| ApiError::not_found(format!("Backup '{}' not found", name)))?;
+    Ok(file
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(name)],
+        })
+        .into_response(&req))
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RestoreState {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Progress of a server's latest restore.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreStatus {
+    pub archive: String,
+    pub state: RestoreState,
+    pub initiated_by: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// One line per step, like a provisioning log.
+    pub log: Vec<String>,
+}
+
+/// The latest restore of each server. In memory: a restart forgets them.
+pub struct Restores {
+    status: Mutex<HashMap<String, RestoreStatus>>,
+}
+
+impl Restores {
+    pub fn new() -> Self {
+        Self {
+            status: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, server_id: &str) -> Option<RestoreStatus> {
+        self.status.lock().unwrap().get(server_id).cloned()
+    }
+
+    fn begin(&self, server_id: &str, archive: &str, initiated_by: Option<String>) {
+        let status = RestoreStatus {
+            archive: archive.to_string(),
+            state: RestoreState::Running,
+            initiated_by,
+            started_at: Utc::now(),
+            finished_at: None,
+            log: Vec::new(),
+        };
+        self.status
+            .lock()
+            .unwrap()
+            .insert(server_id.to_string(), status);
+    }
+
+    fn log(&self, server_id: &str, message: impl Into<String>) {
+        let message = message.into();
+        tracing::info!("Restore of '{}': {}", server_id, message);
+        if let Some(status) = self.status.lock().unwrap().get_mut(server_id) {
+            status.log.push(message);
+        }
+    }
+
+    fn finish(&self, server_id: &str, success: bool) {
+        if let Some(status) = self.status.lock().unwrap().get_mut(server_id) {
+            status.state = if success {
+                RestoreState::Succeeded
+            } else {
+                RestoreState::Failed
+            };
+            status.finished_at = Some(Utc::now());
+        }
+    }
+}
+
+/// Unpack `archive` over `dest` with the system tar, which also reads the zstd archives
+/// newer LinuxGSM versions write. GNU tar drops leading `/` and skips `..` members, so
+/// nothing lands outside `dest`.
+async fn extract(archive: &Path, dest: &str) -> anyhow::Result<()> {
+    let output = tokio::process::Command::new("tar")
+        .arg("-xf")
+        .arg(archive)
+        .arg("-C")
+        .arg(dest)
+        .kill_on_drop(true)
+        .output()
+        .await?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        anyhow::bail!("{}", stderr.lines().next().unwrap_or("tar failed"));
+    }
+    Ok(())
+}
+
+/// Stop the server, unpack the archive into its base directory and start it again.
+/// The caller holds the server's LGSM lock. Returns whether every step succeeded.
+async fn run_restore(
+    registry: &ServerRegistry,
+    restores: &Restores,
+    config: &GameServerConfig,
+    server_id: &str,
+    archive: &Path,
+) -> bool {
+    // LinuxGSM archives the whole install relative to its root, serverfiles included
+    let base_dir = &config.paths.base_dir;
+
+    let previous = registry
+        .get_definition(server_id)
+        .await
+        .map(|def| def.desired_state)
+        .unwrap_or_default();
+    registry
+        .set_desired_state(server_id, DesiredState::Stopped)
+        .await;
+    restores.log(server_id, "Stopping server");
+    let stopped = match lgsm::run_lgsm_command(config, "stop").await {
+        Err(e) => Err(format!("Failed to stop the server: {}", e)),
+        Ok(_) if !lgsm::find_server_pids(base_dir).is_empty() => {
+            Err("The server is still running after the stop".to_string())
+        }
+        Ok(_) => Ok(()),
+    };
+    // Never unpack over the files of a running server
+    if let Err(e) = stopped {
+        restores.log(server_id, e);
+        restores.log(server_id, "Restore aborted; nothing was extracted");
+        registry.set_desired_state(server_id, previous).await;
+        return false;
+    }
+
+    restores.log(server_id, format!("Extracting into {}", base_dir));
+    if let Err(e) = extract(archive, base_dir).await {
+        restores.log(server_id, format!("Extraction failed: {}", e));
+        restores.log(
+            server_id,
+            "Server left stopped; check its files before starting it",
+        );
+        return false;
+    }
+
+    registry
+        .set_desired_state(server_id, DesiredState::Running)
+        .await;
+    restores.log(server_id, "Starting server");
+    if let Err(e) = lgsm::run_lgsm_command(config, "start").await {
+        restores.log(server_id, format!("Failed to start the server: {}", e));
+        return false;
+    }
+    restores.log(server_id, "Restore complete");
+    true
+}
+
+/// POST /api/servers/{server_id}/backups/{name}/restore — stop the server, unpack the
+/// archive over its files and start it again. Runs in the background; poll
+/// GET /api/servers/{server_id}/restore-status for progress.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/backups/{name}/restore",
+    tag = "backups",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Archive file name"),
+    ),
+    responses(
+        (status = 202, description = "Restore started", body = RestoreStatus),
+        (status = 400, description = "Not a plain file name", body = ApiError),
+        (status = 403, description = "Resolves outside the backup directory", body = ApiError),
+        (status = 404, description = "Server or archive not found", body = ApiError),
+        (status = 409, description = "Another LGSM operation is running, or the server runtime is not loaded", body = ApiError),
+    )
+)]
+pub async fn restore_backup(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    restores: web::Data<Arc<Restores>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    let config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let archive = resolve_archive(&backup_dir(&config), &name)?;
+    let lock = registry
+        .get_lgsm_lock(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_ready)?;
+    let user = lgsm::initiator(&req);
+
+    // The task takes the lock itself, so it is held until the server is back up; it
+    // reports whether it got the lock before the request answers.
+    let (locked_tx, locked_rx) = oneshot::channel();
+    let registry = registry.into_inner().as_ref().clone();
+    let restores_task = restores.get_ref().clone();
+    let lock_task = lock.clone();
+    let id = server_id.clone();
+    let archive_name = name.clone();
+    tokio::spawn(requestid::propagate(async move {
+        let Some(_guard) = lock_task.try_acquire("restore", user.as_deref()) else {
+            let _ = locked_tx.send(false);
+            return;
+        };
+        restores_task.begin(&id, &archive_name, user.clone());
+        let _ = locked_tx.send(true);
+
+        let started_at = Utc::now();
+        let success = run_restore(&registry, &restores_task, &config, &id, &archive).await;
+        restores_task.finish(&id, success);
+        registry.events.publish(PanelEvent::LgsmAction {
+            server_id: id.clone(),
+            action: "restore".to_string(),
+            success,
+            initiated_by: user.clone(),
+        });
+        registry.record_action(
+            &id,
+            ActionRecord::finished("restore", user, started_at, success),
+        );
+    }));
+
+    if !locked_rx.await.unwrap_or(false) {
+        let busy = lock
+            .current()
+            .map(|holder| format!("'{}' is running", holder.action))
+            .unwrap_or_else(|| "another LGSM operation is waiting".to_string());
+        return Err(ApiError::conflict(format!(
+            "Cannot restore while {}; try again when it has finished",
+            busy
+        )));
+    }
+    audit::detail(&req, name);
+    Ok(HttpResponse::Accepted().json(restores.get(&server_id)))
+}
+
+/// GET /api/servers/{server_id}/restore-status
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/restore-status",
+    tag = "backups",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "State and log of the server's latest restore", body = RestoreStatus),
+        (status = 404, description = "Server not found, or not restored since the panel started", body = ApiError),
+    )
+)]
+pub async fn restore_status(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    restores: web::Data<Arc<Restores>>,
+) -> Result<HttpResponse, ApiError> {
+    registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let status = restores
+        .get(&server_id)
+        .ok_or_else(|| ApiError::not_found("No restore since the panel started"))?;
+    Ok(HttpResponse::Ok().json(status))
+}
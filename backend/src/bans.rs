@@ -0,0 +1,117 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use std::time::Duration;
+use utoipa::ToSchema;
+
+use crate::apierror::ApiError;
+use crate::registry::ServerRegistry;
+use crate::shutdown::Shutdown;
+
+/// How often temporary bans are checked for expiry.
+const EXPIRY_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Longest temporary ban, in hours (ten years); longer ones should be permanent.
+pub const MAX_BAN_HOURS: u32 = 24 * 365 * 10;
+
+/// A ban issued through the panel. The game's own banlist only has the id, name and
+/// reason; the panel keeps who banned the player and when the ban ends.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BanRecord {
+    pub steam_id: String,
+    pub name: Option<String>,
+    pub reason: String,
+    /// Panel user who issued the ban.
+    pub banned_by: Option<String>,
+    pub created_at: DateTime<Utc>,
+    /// Null for permanent bans.
+    pub expires_at: Option<DateTime<Utc>>,
+    /// The panel lifted the ban because it ran out.
+    #[serde(default)]
+    pub expired: bool,
+}
+
+impl BanRecord {
+    /// When the panel still has to lift this ban.
+    pub fn lift_at(&self) -> Option<DateTime<Utc>> {
+        self.expires_at.filter(|_| !self.expired)
+    }
+}
+
+/// Lift the temporary bans that have run out. A ban whose server can't be reached stays
+/// due and is tried again on the next check.
+async fn lift_expired(registry: &ServerRegistry) {
+    let due = match registry.bans_due(Utc::now()) {
+        Ok(due) => due,
+        Err(e) => {
+            tracing::error!("Failed to load expiring bans: {}", e);
+            return;
+        }
+    };
+    for (server_id, mut ban) in due {
+        let Some(rcon) = registry.get_rcon(&server_id).await else {
+            continue;
+        };
+        if let Err(e) = rcon.unban(&ban.steam_id).await {
+            tracing::debug!(
+                "Could not lift expired ban of {} on '{}' yet: {}",
+                ban.steam_id,
+                server_id,
+                e
+            );
+            continue;
+        }
+        tracing::info!(
+            target: "audit",
+            server_id = %server_id,
+            steam_id = %ban.steam_id,
+            "Temporary ban expired; unbanned"
+        );
+        ban.expired = true;
+        registry.save_ban(&server_id, &ban);
+    }
+}
+
+/// Background task: unban players whose temporary ban has expired.
+pub fn spawn_expiry(
+    registry: Arc<ServerRegistry>,
+    shutdown: Shutdown,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EXPIRY_CHECK_INTERVAL);
+        loop {
+            tokio::select! {
+                _ = interval.tick() => lift_expired(&registry).await,
+                _ = shutdown.signal() => break,
+            }
+        }
+    })
+}
+
+/// GET /api/servers/{server_id}/bans
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/bans",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Bans issued through the panel, newest first, including expired ones", body = [BanRecord]),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn list_bans(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let bans = registry
+        .bans(&server_id)
+        .map_err(|e| ApiError::internal(format!("Failed to load bans: {}", e)))?;
+    Ok(HttpResponse::Ok().json(bans))
+}
@@ -0,0 +1,288 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::apierror::ApiError;
+use crate::config::{AppConfig, MonitorConfig, ProvisioningConfig};
+use crate::registry::{ProvisioningStatus, ServerDefinition, ServerRegistry, ServerSource};
+use crate::scheduler::{compute_next_run, ScheduledJob, Scheduler};
+use crate::validation::{validate_settings, SettingsInput};
+
+const BUNDLE_VERSION: u32 = 1;
+const REDACTED: &str = "********";
+
+/// Portable snapshot of the panel's dynamic servers, for moving to a new host.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerBundle {
+    pub version: u32,
+    pub exported_at: DateTime<Utc>,
+    pub servers: Vec<ServerDefinition>,
+    #[serde(default)]
+    pub jobs: Option<Vec<ScheduledJob>>,
+    #[serde(default)]
+    pub settings: Option<BundleSettings>,
+}
+
+/// Panel settings included for reference; they are not applied on import.
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BundleSettings {
+    pub provisioning: ProvisioningConfig,
+    pub monitor: MonitorConfig,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportQuery {
+    #[serde(default)]
+    pub include_jobs: bool,
+    #[serde(default)]
+    pub include_settings: bool,
+    #[serde(default)]
+    pub redact_passwords: bool,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ImportBundleRequest {
+    pub bundle: ServerBundle,
+    /// Replace each server's base_path (e.g. "/home" -> "/srv").
+    pub base_path: Option<String>,
+    /// Also import scheduler jobs for the servers that were imported.
+    #[serde(default)]
+    pub import_jobs: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ImportResult {
+    id: String,
+    name: String,
+    /// "imported", "conflict" or "invalid"
+    status: String,
+    message: String,
+}
+
+/// GET /api/servers/export — bundle all dynamic definitions (optionally jobs and settings).
+#[utoipa::path(
+    get,
+    path = "/api/servers/export",
+    tag = "servers",
+    params(ExportQuery),
+    responses((status = 200, description = "Bundle as a JSON attachment", body = ServerBundle))
+)]
+pub async fn export_servers(
+    query: web::Query<ExportQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+    config: web::Data<AppConfig>,
+) -> HttpResponse {
+    let mut servers: Vec<ServerDefinition> = registry
+        .all_definitions()
+        .await
+        .into_iter()
+        .filter(|d| d.source == ServerSource::Dynamic)
+        .collect();
+    if query.redact_passwords {
+        for def in &mut servers {
+            def.rcon_password = REDACTED.to_string();
+        }
+    }
+
+    let jobs = if query.include_jobs {
+        let jobs = scheduler.jobs.read().await;
+        Some(
+            jobs.iter()
+                .filter(|j| servers.iter().any(|d| d.id == j.server_id))
+                .cloned()
+                .collect(),
+        )
+    } else {
+        None
+    };
+
+    let settings = query.include_settings.then(|| BundleSettings {
+        provisioning: config.provisioning.clone(),
+        monitor: config.monitor.clone(),
+    });
+
+    let bundle = ServerBundle {
+        version: BUNDLE_VERSION,
+        exported_at: Utc::now(),
+        servers,
+        jobs,
+        settings,
+    };
+    let filename = format!(
+        "servers-export-{}.json",
+        bundle.exported_at.format("%Y%m%d-%H%M%S")
+    );
+    HttpResponse::Ok()
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", filename),
+        ))
+        .json(bundle)
+}
+
+/// POST /api/servers/import-bundle — insert exported definitions as `pending_attach`.
+/// Each definition is checked on its own; conflicts are reported per server.
+#[utoipa::path(
+    post,
+    path = "/api/servers/import-bundle",
+    tag = "servers",
+    request_body = ImportBundleRequest,
+    responses(
+        (status = 200, description = "Counts plus a per-server `results` list of ImportResult", body = Object),
+        (status = 400, description = "Bundle from a newer panel", body = ApiError),
+    )
+)]
+pub async fn import_bundle(
+    body: web::Json<ImportBundleRequest>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let body = body.into_inner();
+    if body.bundle.version > BUNDLE_VERSION {
+        return Err(ApiError::validation(format!(
+            "Unsupported bundle version {}",
+            body.bundle.version
+        )));
+    }
+
+    let mut results: Vec<ImportResult> = Vec::new();
+    let mut imported_ids: Vec<String> = Vec::new();
+
+    for mut def in body.bundle.servers {
+        let mut result = ImportResult {
+            id: def.id.clone(),
+            name: def.name.clone(),
+            status: "imported".to_string(),
+            message: String::new(),
+        };
+
+        // Re-read each time so servers imported earlier in this bundle count too
+        let existing = registry.all_definitions().await;
+        if let Some(message) = check_conflicts(&def, &existing, config.provisioning.max_servers) {
+            result.status = "conflict".to_string();
+            result.message = message;
+            results.push(result);
+            continue;
+        }
+
+        let input = SettingsInput {
+            name: Some(&def.name),
+            hostname: Some(&def.hostname),
+            max_players: Some(def.max_players),
+            world_size: Some(def.world_size),
+            seed: Some(def.seed),
+            tags: Some(&def.tags),
+        };
+        let valid = match validate_settings(&input, &existing, None) {
+            Ok(v) => v,
+            Err(errors) => {
+                result.status = "invalid".to_string();
+                result.message = errors.summary();
+                results.push(result);
+                continue;
+            }
+        };
+
+        if let Some(name) = valid.name {
+            def.name = name;
+        }
+        if let Some(hostname) = valid.hostname {
+            def.hostname = hostname;
+        }
+        if let Some(tags) = valid.tags {
+            def.tags = tags;
+        }
+        if let Some(ref base_path) = body.base_path {
+            def.base_path = base_path.trim_end_matches('/').to_string();
+        }
+        if def.rcon_password.is_empty() || def.rcon_password == REDACTED {
+            def.rcon_password = crate::servers::generate_rcon_password();
+            result.message = "RCON password was redacted; a new one was generated".to_string();
+        }
+        def.source = ServerSource::Dynamic;
+        def.provisioning_status = ProvisioningStatus::PendingAttach;
+        def.provisioning_log = vec![format!(
+            "Imported from bundle exported at {}",
+            body.bundle.exported_at.to_rfc3339()
+        )];
+
+        match registry.insert_definition(def.clone()).await {
+            Ok(()) => {
+                imported_ids.push(def.id.clone());
+                results.push(result);
+            }
+            Err(e) => {
+                result.status = "conflict".to_string();
+                result.message = e;
+                results.push(result);
+            }
+        }
+    }
+
+    if !imported_ids.is_empty() {
+        registry.persist().await;
+    }
+
+    let mut jobs_imported = 0;
+    if body.import_jobs {
+        if let Some(bundle_jobs) = body.bundle.jobs {
+            let mut jobs = scheduler.jobs.write().await;
+            for mut job in bundle_jobs {
+                if imported_ids.contains(&job.server_id) && !jobs.iter().any(|j| j.id == job.id) {
+                    // Don't fire overdue runs from the old host immediately
+                    job.last_run = None;
+                    job.next_run = compute_next_run(&job.schedule).ok();
+                    jobs.push(job);
+                    jobs_imported += 1;
+                }
+            }
+            drop(jobs);
+            if jobs_imported > 0 {
+                scheduler.mark_dirty();
+            }
+        }
+    }
+
+    tracing::info!(
+        "Imported {} server(s) and {} job(s) from bundle",
+        imported_ids.len(),
+        jobs_imported
+    );
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "imported": imported_ids.len(),
+        "jobsImported": jobs_imported,
+        "results": results,
+    })))
+}
+
+/// Id, port and capacity checks against the current registry.
+fn check_conflicts(
+    def: &ServerDefinition,
+    existing: &[ServerDefinition],
+    max_servers: usize,
+) -> Option<String> {
+    if existing.iter().any(|d| d.id == def.id) {
+        return Some(format!("Server id '{}' already exists", def.id));
+    }
+    if existing.len() >= max_servers {
+        return Some(format!("Maximum of {} servers reached", max_servers));
+    }
+    let ports = [def.game_port, def.rcon_port, def.query_port];
+    for other in existing {
+        let taken = [other.game_port, other.rcon_port, other.query_port];
+        if let Some(port) = ports.iter().find(|p| taken.contains(p)) {
+            return Some(format!("Port {} is already used by '{}'", port, other.id));
+        }
+    }
+    None
+}
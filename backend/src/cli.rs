@@ -0,0 +1,112 @@
+use clap::{Parser, Subcommand};
+use rand::distributions::{Alphanumeric, DistString};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+
+use crate::config::{AppConfig, CONFIG_FILE};
+use crate::registry::load_definitions;
+use crate::storage::{SqliteStore, DB_FILE};
+
+const SECRET_LENGTH: usize = 64;
+
+/// Web panel for Rust game servers. Without a subcommand, starts the panel.
+#[derive(Parser)]
+#[command(version, about)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Read a password from stdin and print its bcrypt hash for auth.password_hash
+    HashPassword {
+        #[arg(long, default_value_t = bcrypt::DEFAULT_COST)]
+        cost: u32,
+    },
+    /// Load a config file and report problems without starting the panel
+    CheckConfig {
+        /// Defaults to config.yaml in the working directory
+        path: Option<PathBuf>,
+    },
+    /// List the servers from config.yaml and the panel database
+    ListServers,
+    /// Print a random string for auth.jwt_secret
+    GenerateSecret,
+}
+
+/// Run an admin subcommand to completion.
+pub fn run(command: Command) -> anyhow::Result<()> {
+    match command {
+        Command::HashPassword { cost } => hash_password(cost),
+        Command::CheckConfig { path } => {
+            check_config(path.as_deref().unwrap_or(Path::new(CONFIG_FILE)))
+        }
+        Command::ListServers => list_servers(),
+        Command::GenerateSecret => {
+            println!(
+                "{}",
+                Alphanumeric.sample_string(&mut rand::thread_rng(), SECRET_LENGTH)
+            );
+            Ok(())
+        }
+    }
+}
+
+fn hash_password(cost: u32) -> anyhow::Result<()> {
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Password: ");
+        std::io::stderr().flush()?;
+    }
+    let mut password = String::new();
+    stdin.lock().read_line(&mut password)?;
+    let password = password.trim_end_matches(['\r', '\n']);
+    if password.is_empty() {
+        anyhow::bail!("empty password");
+    }
+    println!("{}", bcrypt::hash(password, cost)?);
+    Ok(())
+}
+
+fn check_config(path: &Path) -> anyhow::Result<()> {
+    if !path.exists() {
+        anyhow::bail!("{} not found", path.display());
+    }
+    let config = AppConfig::load_from(path)?;
+    let problems = config.problems();
+    if problems.is_empty() {
+        println!(
+            "{}: OK ({} server(s))",
+            path.display(),
+            config.servers.len()
+        );
+        return Ok(());
+    }
+    for problem in &problems {
+        println!("{}: {}", path.display(), problem);
+    }
+    anyhow::bail!("{} problem(s) found", problems.len())
+}
+
+fn list_servers() -> anyhow::Result<()> {
+    let config = AppConfig::load()?;
+    let store = SqliteStore::open(Path::new(DB_FILE))?;
+    let definitions = load_definitions(&config.servers, &store);
+
+    println!(
+        "{:<20} {:<8} {:<16} {:<12} NAME",
+        "ID", "SOURCE", "STATUS", "GAME/RCON"
+    );
+    for def in &definitions {
+        println!(
+            "{:<20} {:<8} {:<16} {:<12} {}",
+            def.id,
+            format!("{:?}", def.source).to_lowercase(),
+            format!("{:?}", def.provisioning_status),
+            format!("{}/{}", def.game_port, def.rcon_port),
+            def.name
+        );
+    }
+    Ok(())
+}
@@ -0,0 +1,75 @@
+use crate::config::{ConsoleConfig, ConsoleMode};
+
+/// The name a console command is matched by: its first word, lowercased, without the
+/// "global." prefix the game accepts on its own commands.
+fn command_name(command: &str) -> String {
+    let name = command
+        .split_whitespace()
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    match name.strip_prefix("global.") {
+        Some(rest) => rest.to_string(),
+        None => name,
+    }
+}
+
+fn matches(entries: &[String], name: &str) -> bool {
+    entries.iter().any(|entry| {
+        let entry = command_name(entry);
+        match entry.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => entry == name,
+        }
+    })
+}
+
+/// Ok if `command` may be sent to RCON. `role` is the panel user's role, or None for
+/// commands the panel sends on its own (scheduled jobs), which never bypass the blacklist.
+fn check(config: &ConsoleConfig, command: &str, role: Option<&str>) -> Result<(), String> {
+    let name = command_name(command);
+    match config.mode {
+        ConsoleMode::Whitelist if !matches(&config.whitelist, &name) => Err(format!(
+            "Command '{}' is not on the console whitelist",
+            name
+        )),
+        ConsoleMode::Blacklist if matches(&config.blacklist, &name) => {
+            let bypass = role.is_some_and(|role| {
+                config
+                    .blacklist_bypass_roles
+                    .iter()
+                    .any(|r| r.eq_ignore_ascii_case(role))
+            });
+            if bypass {
+                Ok(())
+            } else {
+                Err(format!(
+                    "Command '{}' is blocked by the console blacklist",
+                    name
+                ))
+            }
+        }
+        _ => Ok(()),
+    }
+}
+
+/// `check`, writing refused commands to the audit log. `user` is the panel user or job
+/// that sent the command.
+pub fn enforce(
+    config: &ConsoleConfig,
+    server_id: &str,
+    command: &str,
+    user: &str,
+    role: Option<&str>,
+) -> Result<(), String> {
+    check(config, command, role).inspect_err(|reason| {
+        tracing::warn!(
+            target: "audit",
+            user = %user,
+            server_id = %server_id,
+            command = %command,
+            "Console command blocked: {}",
+            reason
+        );
+    })
+}
@@ -1,9 +1,10 @@
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
+use utoipa::ToSchema;
 
 #[derive(Debug, Clone, Deserialize)]
 pub struct AppConfig {
-    #[serde(default = "default_panel_config", alias = "server")]
+    #[serde(default = "default_panel_config")]
     pub panel: PanelConfig,
     #[serde(default = "default_auth_config")]
     pub auth: AuthConfig,
@@ -11,6 +12,16 @@ pub struct AppConfig {
     pub monitor: MonitorConfig,
     #[serde(default)]
     pub provisioning: ProvisioningConfig,
+    #[serde(default)]
+    pub scheduler: SchedulerConfig,
+    #[serde(default)]
+    pub websocket: WebSocketConfig,
+    #[serde(default)]
+    pub limits: LimitsConfig,
+    #[serde(default)]
+    pub updates: UpdatesConfig,
+    #[serde(default)]
+    pub console: ConsoleConfig,
     /// Multi-server list. If absent, falls back to legacy top-level rcon/paths.
     #[serde(default)]
     pub servers: Vec<GameServerConfig>,
@@ -28,6 +39,66 @@ pub struct PanelConfig {
     pub host: String,
     #[serde(default = "default_port")]
     pub port: u16,
+    /// Delay between auto-started servers on panel boot.
+    #[serde(default = "default_autostart_delay")]
+    pub autostart_delay_secs: u64,
+    #[serde(default)]
+    pub log_format: LogFormat,
+    /// Header carrying the client address when behind a reverse proxy (e.g.
+    /// "X-Forwarded-For"). Ignored unless set; clients could spoof it otherwise.
+    #[serde(default)]
+    pub trusted_proxy_header: Option<String>,
+    /// Peers (addresses or CIDR ranges) whose `trusted_proxy_header` is believed.
+    #[serde(default = "default_trusted_proxies")]
+    pub trusted_proxies: Vec<String>,
+    /// Requests slower than this are logged at warn.
+    #[serde(default = "default_slow_request_ms")]
+    pub slow_request_ms: u64,
+    /// Path prefixes left out of the access log.
+    #[serde(default = "default_access_log_exclude")]
+    pub access_log_exclude: Vec<String>,
+    /// The audit log (audit.jsonl) is rotated once it would grow past this.
+    #[serde(default = "default_audit_log_max_bytes")]
+    pub audit_log_max_bytes: u64,
+    /// Rotated audit logs kept besides the current one.
+    #[serde(default = "default_audit_log_keep")]
+    pub audit_log_keep: usize,
+    #[serde(default)]
+    pub public_status: PublicStatusConfig,
+}
+
+/// Unauthenticated GET /api/public/status for community status pages.
+#[derive(Debug, Clone, Deserialize)]
+pub struct PublicStatusConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// How long one answer is reused, so polling visitors never reach the servers.
+    #[serde(default = "default_public_status_cache_secs")]
+    pub cache_secs: u64,
+    /// Sites allowed to fetch the status from a browser (CORS), e.g.
+    /// "https://community.example.com".
+    #[serde(default)]
+    pub allowed_origins: Vec<String>,
+}
+
+impl Default for PublicStatusConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cache_secs: default_public_status_cache_secs(),
+            allowed_origins: Vec::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum LogFormat {
+    /// Human-readable lines.
+    #[default]
+    Pretty,
+    /// One JSON object per line, for log shippers.
+    Json,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -40,6 +111,46 @@ pub struct GameServerConfig {
     pub rcon: RconConfig,
     #[serde(default = "default_paths_config")]
     pub paths: PathsConfig,
+    /// Start this server when the panel boots if it is not already running.
+    #[serde(default)]
+    pub auto_start: bool,
+    /// List this server on the public status page under this slug.
+    #[serde(default)]
+    pub public_slug: Option<String>,
+    /// CPU/memory caps for the game server process, applied when LGSM starts it.
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
+}
+
+/// Caps for one game server's process tree. Unset fields are unlimited.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceLimits {
+    /// CPU time as a number of cores, e.g. 1.5.
+    #[serde(default, alias = "cpu_cores")]
+    pub cpu_cores: Option<f64>,
+    #[serde(default, alias = "memory_mb")]
+    pub memory_mb: Option<u64>,
+}
+
+impl ResourceLimits {
+    pub fn is_empty(&self) -> bool {
+        self.cpu_cores.is_none() && self.memory_mb.is_none()
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if let Some(cores) = self.cpu_cores {
+            if !(0.1..=256.0).contains(&cores) {
+                return Err("cpuCores must be between 0.1 and 256".to_string());
+            }
+        }
+        if let Some(mb) = self.memory_mb {
+            if mb < 512 {
+                return Err("memoryMb must be at least 512".to_string());
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -50,6 +161,45 @@ pub struct RconConfig {
     pub port: u16,
     #[serde(default = "default_rcon_password")]
     pub password: String,
+    /// Use wss:// (e.g. WebRcon behind a TLS reverse proxy). Implied by a wss:// host.
+    #[serde(default)]
+    pub tls: bool,
+    /// Accept self-signed or mismatched certificates on wss:// connections.
+    #[serde(default)]
+    pub insecure_skip_verify: bool,
+}
+
+impl RconConfig {
+    /// Resolve the WebSocket base URL (`scheme://host:port`) for this endpoint.
+    /// `host` may be a bare hostname/IP or a full `ws://` / `wss://` URL.
+    pub fn base_url(&self) -> anyhow::Result<String> {
+        let (scheme, rest) = if let Some(rest) = self.host.strip_prefix("wss://") {
+            ("wss", rest)
+        } else if let Some(rest) = self.host.strip_prefix("ws://") {
+            ("ws", rest)
+        } else if self.host.contains("://") {
+            anyhow::bail!("unsupported RCON URL scheme in '{}' (use ws:// or wss://)", self.host);
+        } else if self.tls {
+            ("wss", self.host.as_str())
+        } else {
+            ("ws", self.host.as_str())
+        };
+
+        let rest = rest.trim_end_matches('/');
+        if rest.is_empty() || rest.contains('/') {
+            anyhow::bail!("invalid RCON host '{}'", self.host);
+        }
+
+        let has_port = rest
+            .rsplit_once(':')
+            .map(|(_, port)| port.parse::<u16>().is_ok())
+            .unwrap_or(false);
+        if has_port {
+            Ok(format!("{}://{}", scheme, rest))
+        } else {
+            Ok(format!("{}://{}:{}", scheme, rest, self.port))
+        }
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -60,6 +210,31 @@ pub struct AuthConfig {
     pub password_hash: String,
     #[serde(default = "default_jwt_secret")]
     pub jwt_secret: String,
+    /// Sensitive reveals require a token issued within this many minutes.
+    #[serde(default = "default_reauth_window_mins")]
+    pub reauth_window_mins: u64,
+    /// Lifetime of an access token; clients renew it with their refresh token.
+    #[serde(default = "default_access_token_mins")]
+    pub access_token_mins: u64,
+    /// Lifetime of a refresh token. Each refresh issues a new one, so this is how long
+    /// a session may sit idle.
+    #[serde(default = "default_refresh_token_days")]
+    pub refresh_token_days: u64,
+    /// Failed logins a client address may make within `login_failure_window_secs`
+    /// before it has to wait; 0 turns the limit off.
+    #[serde(default = "default_login_max_failures")]
+    pub login_max_failures: u32,
+    #[serde(default = "default_login_failure_window_secs")]
+    pub login_failure_window_secs: u64,
+    /// Lockout for a client that runs into the limit again before it is forgotten;
+    /// doubles with every further burst, up to `login_lockout_max_secs`.
+    #[serde(default = "default_login_lockout_secs")]
+    pub login_lockout_secs: u64,
+    #[serde(default = "default_login_lockout_max_secs")]
+    pub login_lockout_max_secs: u64,
+    /// bcrypt cost for passwords set through POST /api/auth/change-password.
+    #[serde(default = "default_bcrypt_cost")]
+    pub bcrypt_cost: u32,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
@@ -72,6 +247,9 @@ pub struct PathsConfig {
     pub oxide_plugins: String,
     #[serde(default = "default_oxide_config")]
     pub oxide_config: String,
+    /// Where plugins keep their state; defaults to `<server_files>/oxide/data`.
+    #[serde(default)]
+    pub oxide_data: String,
     #[serde(default = "default_server_cfg")]
     pub server_cfg: String,
     #[serde(default = "default_server_log")]
@@ -80,15 +258,28 @@ pub struct PathsConfig {
     pub base_dir: String,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct MonitorConfig {
     #[serde(default = "default_poll_interval")]
     pub poll_interval_secs: u64,
     #[serde(default = "default_history_size")]
     pub history_size: usize,
+    /// Bounds for the monitor WebSocket's ?interval= (seconds).
+    #[serde(default = "default_ws_min_interval")]
+    pub ws_min_interval_secs: u64,
+    #[serde(default = "default_ws_max_interval")]
+    pub ws_max_interval_secs: u64,
+    /// Player positions pushed by the map plugin are dropped after this long without an
+    /// update.
+    #[serde(default = "default_position_stale")]
+    pub position_stale_secs: u64,
+    /// Mount point behind the headline disk figures (e.g. the one holding the
+    /// serverfiles). Unset: all disks added up.
+    #[serde(default)]
+    pub primary_disk: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, Serialize, ToSchema)]
 pub struct ProvisioningConfig {
     #[serde(default = "default_provisioning_base_path")]
     pub base_path: String,
@@ -98,6 +289,222 @@ pub struct ProvisioningConfig {
     pub port_offset: u16,
     #[serde(default = "default_max_servers")]
     pub max_servers: usize,
+    /// Address players use to reach this host. Defaults to the panel's request host.
+    #[serde(default)]
+    pub public_host: Option<String>,
+    /// Longest a single provisioning step (LinuxGSM setup, the game download) may run
+    /// before the server is marked failed.
+    #[serde(default = "default_step_timeout")]
+    pub step_timeout_secs: u64,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SchedulerConfig {
+    /// Shortest allowed "every ..." interval.
+    #[serde(default = "default_min_interval")]
+    pub min_interval_secs: u64,
+    /// Webhook that gets a summary whenever a scheduled job fails.
+    #[serde(default)]
+    pub notify_on_failure_url: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebSocketConfig {
+    /// How often the panel pings each socket.
+    #[serde(default = "default_ws_ping_interval")]
+    pub ping_interval_secs: u64,
+    /// Close sockets that sent nothing (not even a pong) for this long.
+    #[serde(default = "default_ws_idle_timeout")]
+    pub idle_timeout_secs: u64,
+    #[serde(default = "default_ws_max_sessions")]
+    pub max_sessions_per_user: usize,
+}
+
+impl Default for WebSocketConfig {
+    fn default() -> Self {
+        Self {
+            ping_interval_secs: default_ws_ping_interval(),
+            idle_timeout_secs: default_ws_idle_timeout(),
+            max_sessions_per_user: default_ws_max_sessions(),
+        }
+    }
+}
+
+/// Which commands may be sent to RCON from the console WebSocket, snippets and scheduled
+/// RCON command jobs. Commands are matched by name (the first word, without a leading
+/// "global."), case-insensitively; an entry ending in "*" matches a prefix ("oxide.*").
+#[derive(Debug, Clone, Deserialize)]
+pub struct ConsoleConfig {
+    #[serde(default)]
+    pub mode: ConsoleMode,
+    /// Commands refused in blacklist mode.
+    #[serde(default = "default_console_blacklist")]
+    pub blacklist: Vec<String>,
+    /// The only commands allowed in whitelist mode.
+    #[serde(default)]
+    pub whitelist: Vec<String>,
+    /// Roles that may run blacklisted commands anyway. Whitelist mode binds everyone.
+    #[serde(default)]
+    pub blacklist_bypass_roles: Vec<String>,
+    /// Console WebSocket commands and replies kept per server for
+    /// GET /console/history; 0 keeps none.
+    #[serde(default = "default_console_history_size")]
+    pub history_size: usize,
+}
+
+impl Default for ConsoleConfig {
+    fn default() -> Self {
+        Self {
+            mode: ConsoleMode::default(),
+            blacklist: default_console_blacklist(),
+            whitelist: Vec::new(),
+            blacklist_bypass_roles: Vec::new(),
+            history_size: default_console_history_size(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ConsoleMode {
+    /// Everything but the blacklist.
+    #[default]
+    Blacklist,
+    /// Only the whitelist.
+    Whitelist,
+}
+
+fn default_console_history_size() -> usize {
+    200
+}
+fn default_console_blacklist() -> Vec<String> {
+    ["quit", "server.writecfg", "ownerid", "removeowner"]
+        .into_iter()
+        .map(String::from)
+        .collect()
+}
+
+/// Per-client request budgets and request body caps.
+#[derive(Debug, Clone, Deserialize)]
+pub struct LimitsConfig {
+    /// Requests per minute per client (user, or IP when unauthenticated) across /api.
+    #[serde(default = "default_global_per_minute")]
+    pub global_per_minute: u32,
+    /// Routes that do an RCON round-trip (players, moderation, broadcasts, plugin reloads).
+    #[serde(default = "default_rcon_per_minute")]
+    pub rcon_per_minute: u32,
+    /// Routes that start LGSM actions, provisioning or scheduled jobs.
+    #[serde(default = "default_action_per_minute")]
+    pub action_per_minute: u32,
+    /// Largest accepted JSON body.
+    #[serde(default = "default_json_body_bytes")]
+    pub json_body_bytes: usize,
+    /// Largest accepted raw (non-JSON) body.
+    #[serde(default = "default_payload_bytes")]
+    pub payload_bytes: usize,
+    /// Requests announcing a larger Content-Length (e.g. uploads) are refused up front.
+    #[serde(default = "default_max_request_bytes")]
+    pub max_request_bytes: u64,
+    /// Largest file accepted through an upload transfer token (/api/transfer/...), which
+    /// is streamed to disk instead of buffered; replaces max_request_bytes there.
+    #[serde(default = "default_transfer_max_bytes")]
+    pub transfer_max_bytes: u64,
+    /// Largest multipart upload to the file manager or plugin endpoints, streamed to disk;
+    /// replaces max_request_bytes there.
+    #[serde(default = "default_upload_max_bytes")]
+    pub upload_max_bytes: u64,
+    /// Largest directory (total file size) downloadable as a zip; at most 4 GiB.
+    #[serde(default = "default_archive_max_bytes")]
+    pub archive_max_bytes: u64,
+}
+
+impl Default for LimitsConfig {
+    fn default() -> Self {
+        Self {
+            global_per_minute: default_global_per_minute(),
+            rcon_per_minute: default_rcon_per_minute(),
+            action_per_minute: default_action_per_minute(),
+            json_body_bytes: default_json_body_bytes(),
+            payload_bytes: default_payload_bytes(),
+            max_request_bytes: default_max_request_bytes(),
+            transfer_max_bytes: default_transfer_max_bytes(),
+            upload_max_bytes: default_upload_max_bytes(),
+            archive_max_bytes: default_archive_max_bytes(),
+        }
+    }
+}
+
+fn default_global_per_minute() -> u32 {
+    600
+}
+fn default_rcon_per_minute() -> u32 {
+    120
+}
+fn default_action_per_minute() -> u32 {
+    30
+}
+fn default_json_body_bytes() -> usize {
+    2 * 1024 * 1024
+}
+fn default_payload_bytes() -> usize {
+    1024 * 1024
+}
+fn default_max_request_bytes() -> u64 {
+    256 * 1024 * 1024
+}
+fn default_transfer_max_bytes() -> u64 {
+    8 * 1024 * 1024 * 1024
+}
+fn default_upload_max_bytes() -> u64 {
+    4 * 1024 * 1024 * 1024
+}
+fn default_archive_max_bytes() -> u64 {
+    2 * 1024 * 1024 * 1024
+}
+
+fn default_ws_ping_interval() -> u64 {
+    20
+}
+fn default_ws_idle_timeout() -> u64 {
+    60
+}
+fn default_ws_max_sessions() -> usize {
+    20
+}
+
+/// Background watcher comparing installed server builds with the latest on Steam.
+#[derive(Debug, Clone, Deserialize)]
+pub struct UpdatesConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_update_interval")]
+    pub interval_mins: u64,
+    /// steamcmd binary used to ask Steam for the current build id.
+    #[serde(default = "default_steamcmd_path")]
+    pub steamcmd_path: String,
+    /// Steam branch the servers are installed from.
+    #[serde(default = "default_update_branch")]
+    pub branch: String,
+}
+
+impl Default for UpdatesConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            interval_mins: default_update_interval(),
+            steamcmd_path: default_steamcmd_path(),
+            branch: default_update_branch(),
+        }
+    }
+}
+
+impl Default for SchedulerConfig {
+    fn default() -> Self {
+        Self {
+            min_interval_secs: default_min_interval(),
+            notify_on_failure_url: None,
+        }
+    }
 }
 
 impl Default for ProvisioningConfig {
@@ -107,6 +514,8 @@ impl Default for ProvisioningConfig {
             port_range_start: default_port_range_start(),
             port_offset: default_port_offset(),
             max_servers: default_max_servers(),
+            public_host: None,
+            step_timeout_secs: default_step_timeout(),
         }
     }
 }
@@ -116,6 +525,15 @@ fn default_panel_config() -> PanelConfig {
     PanelConfig {
         host: default_host(),
         port: default_port(),
+        autostart_delay_secs: default_autostart_delay(),
+        log_format: LogFormat::default(),
+        trusted_proxy_header: None,
+        trusted_proxies: default_trusted_proxies(),
+        slow_request_ms: default_slow_request_ms(),
+        access_log_exclude: default_access_log_exclude(),
+        audit_log_max_bytes: default_audit_log_max_bytes(),
+        audit_log_keep: default_audit_log_keep(),
+        public_status: PublicStatusConfig::default(),
     }
 }
 
@@ -124,6 +542,8 @@ fn default_rcon_config() -> RconConfig {
         host: default_rcon_host(),
         port: default_rcon_port(),
         password: default_rcon_password(),
+        tls: false,
+        insecure_skip_verify: false,
     }
 }
 
@@ -132,6 +552,14 @@ fn default_auth_config() -> AuthConfig {
         admin_username: default_admin_username(),
         password_hash: default_password_hash(),
         jwt_secret: default_jwt_secret(),
+        reauth_window_mins: default_reauth_window_mins(),
+        access_token_mins: default_access_token_mins(),
+        refresh_token_days: default_refresh_token_days(),
+        login_max_failures: default_login_max_failures(),
+        login_failure_window_secs: default_login_failure_window_secs(),
+        login_lockout_secs: default_login_lockout_secs(),
+        login_lockout_max_secs: default_login_lockout_max_secs(),
+        bcrypt_cost: default_bcrypt_cost(),
     }
 }
 
@@ -141,6 +569,7 @@ fn default_paths_config() -> PathsConfig {
         server_files: default_server_files(),
         oxide_plugins: default_oxide_plugins(),
         oxide_config: default_oxide_config(),
+        oxide_data: String::new(),
         server_cfg: default_server_cfg(),
         server_log: default_server_log(),
         base_dir: default_base_dir(),
@@ -151,6 +580,10 @@ fn default_monitor_config() -> MonitorConfig {
     MonitorConfig {
         poll_interval_secs: default_poll_interval(),
         history_size: default_history_size(),
+        ws_min_interval_secs: default_ws_min_interval(),
+        ws_max_interval_secs: default_ws_max_interval(),
+        position_stale_secs: default_position_stale(),
+        primary_disk: None,
     }
 }
 
@@ -160,6 +593,32 @@ fn default_host() -> String {
 fn default_port() -> u16 {
     8443
 }
+fn default_autostart_delay() -> u64 {
+    30
+}
+fn default_slow_request_ms() -> u64 {
+    2000
+}
+fn default_audit_log_max_bytes() -> u64 {
+    5 * 1024 * 1024
+}
+fn default_audit_log_keep() -> usize {
+    4
+}
+fn default_trusted_proxies() -> Vec<String> {
+    vec!["127.0.0.1".to_string(), "::1".to_string()]
+}
+
+fn default_access_log_exclude() -> Vec<String> {
+    vec![
+        "/healthz".to_string(),
+        "/assets/".to_string(),
+        "/favicon.ico".to_string(),
+    ]
+}
+fn default_public_status_cache_secs() -> u64 {
+    5
+}
 fn default_rcon_host() -> String {
     "127.0.0.1".to_string()
 }
@@ -178,6 +637,30 @@ fn default_password_hash() -> String {
 fn default_jwt_secret() -> String {
     "change-this-to-a-random-secret-string".to_string()
 }
+fn default_reauth_window_mins() -> u64 {
+    15
+}
+fn default_access_token_mins() -> u64 {
+    60
+}
+fn default_refresh_token_days() -> u64 {
+    30
+}
+fn default_login_max_failures() -> u32 {
+    5
+}
+fn default_login_failure_window_secs() -> u64 {
+    60
+}
+fn default_login_lockout_secs() -> u64 {
+    300
+}
+fn default_login_lockout_max_secs() -> u64 {
+    3600
+}
+fn default_bcrypt_cost() -> u32 {
+    bcrypt::DEFAULT_COST
+}
 fn default_lgsm_script() -> String {
     "/home/rustserver/rustserver".to_string()
 }
@@ -205,6 +688,15 @@ fn default_poll_interval() -> u64 {
 fn default_history_size() -> usize {
     720
 }
+fn default_ws_min_interval() -> u64 {
+    1
+}
+fn default_ws_max_interval() -> u64 {
+    60
+}
+fn default_position_stale() -> u64 {
+    60
+}
 fn default_server_id() -> String {
     "main".to_string()
 }
@@ -212,6 +704,22 @@ fn default_server_name() -> String {
     "Main Server".to_string()
 }
 
+fn default_min_interval() -> u64 {
+    60
+}
+
+fn default_update_interval() -> u64 {
+    10
+}
+
+fn default_steamcmd_path() -> String {
+    "steamcmd".to_string()
+}
+
+fn default_update_branch() -> String {
+    "public".to_string()
+}
+
 fn default_provisioning_base_path() -> String {
     "/home".to_string()
 }
@@ -224,16 +732,57 @@ fn default_port_offset() -> u16 {
 fn default_max_servers() -> usize {
     10
 }
+fn default_step_timeout() -> u64 {
+    7200
+}
+
+/// Config file read by the panel, in its working directory.
+pub const CONFIG_FILE: &str = "config.yaml";
+/// State the panel writes at runtime that has to survive a new container; the compose
+/// file persists ./data here.
+pub const DATA_DIR: &str = "data";
+/// Admin password hash set through POST /api/auth/change-password, kept in `DATA_DIR`
+/// and used instead of auth.password_hash.
+pub const PASSWORD_FILE: &str = "password.bcrypt";
+
+/// Where change-password stores the admin password hash.
+pub fn password_path() -> PathBuf {
+    Path::new(DATA_DIR).join(PASSWORD_FILE)
+}
+
+/// The hash last stored by change-password: `current`, else `legacy`, where panels kept
+/// it before it moved to the data directory. Empty files count as not set.
+pub fn stored_password_hash(current: &Path, legacy: &Path) -> anyhow::Result<Option<String>> {
+    for path in [current, legacy] {
+        match std::fs::read_to_string(path) {
+            Ok(hash) if !hash.trim().is_empty() => return Ok(Some(hash.trim().to_string())),
+            Ok(_) => {}
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => anyhow::bail!("{}: {}", path.display(), e),
+        }
+    }
+    Ok(None)
+}
+
+/// Secrets shipped in config.example.yaml and the defaults; fine for a first start, never
+/// for a panel reachable from the internet.
+const EXAMPLE_JWT_SECRETS: &[&str] = &[
+    "change-this-to-a-random-string-in-production",
+    "change-this-to-a-random-secret-string",
+];
 
 impl AppConfig {
     pub fn load() -> anyhow::Result<Self> {
-        let config_path = Path::new("config.yaml");
+        Self::load_from(Path::new(CONFIG_FILE))
+    }
+
+    pub fn load_from(config_path: &Path) -> anyhow::Result<Self> {
         let mut config = if config_path.exists() {
             let contents = std::fs::read_to_string(config_path)?;
             let config: AppConfig = serde_yaml::from_str(&contents)?;
             config
         } else {
-            tracing::warn!("config.yaml not found, using defaults");
+            tracing::warn!("{} not found, using defaults", config_path.display());
             AppConfig {
                 panel: default_panel_config(),
                 auth: default_auth_config(),
@@ -242,9 +791,19 @@ impl AppConfig {
                 rcon: None,
                 paths: None,
                 provisioning: ProvisioningConfig::default(),
+                scheduler: SchedulerConfig::default(),
+                websocket: WebSocketConfig::default(),
+                limits: LimitsConfig::default(),
+                updates: UpdatesConfig::default(),
+                console: ConsoleConfig::default(),
             }
         };
 
+        let legacy = config_path.with_file_name(PASSWORD_FILE);
+        if let Some(hash) = stored_password_hash(&password_path(), &legacy)? {
+            config.auth.password_hash = hash;
+        }
+
         // Backward compatibility: if no servers defined but legacy rcon/paths exist,
         // wrap them into a single server entry.
         if config.servers.is_empty() {
@@ -255,10 +814,114 @@ impl AppConfig {
                 name: default_server_name(),
                 rcon,
                 paths,
+                auto_start: false,
+                public_slug: None,
+                resource_limits: ResourceLimits::default(),
             });
             tracing::info!("Migrated legacy config to single-server format");
         }
 
+        for server in &mut config.servers {
+            if server.paths.oxide_data.is_empty() {
+                server.paths.oxide_data = format!(
+                    "{}/oxide/data",
+                    server.paths.server_files.trim_end_matches('/')
+                );
+            }
+            server
+                .rcon
+                .base_url()
+                .map_err(|e| anyhow::anyhow!("server '{}': {}", server.id, e))?;
+        }
+
         Ok(config)
     }
+
+    /// Settings that load but will not work or are unsafe. The panel starts anyway;
+    /// `check-config` prints these.
+    pub fn problems(&self) -> Vec<String> {
+        let mut problems = Vec::new();
+        if EXAMPLE_JWT_SECRETS.contains(&self.auth.jwt_secret.as_str()) {
+            problems.push(
+                "auth.jwt_secret is the example value; run `generate-secret` for a real one"
+                    .to_string(),
+            );
+        } else if self.auth.jwt_secret.len() < 32 {
+            problems.push("auth.jwt_secret is shorter than 32 characters".to_string());
+        }
+        if bcrypt::verify("", &self.auth.password_hash).is_err() {
+            problems.push(
+                "auth.password_hash is not a bcrypt hash; create one with `hash-password`"
+                    .to_string(),
+            );
+        }
+        if !(4..=31).contains(&self.auth.bcrypt_cost) {
+            problems.push("auth.bcrypt_cost must be between 4 and 31".to_string());
+        }
+        if self.monitor.poll_interval_secs == 0 {
+            problems.push("monitor.poll_interval_secs must be at least 1".to_string());
+        }
+        if self.monitor.history_size == 0 {
+            problems.push("monitor.history_size must be at least 1".to_string());
+        }
+        if self.monitor.position_stale_secs == 0 {
+            problems.push("monitor.position_stale_secs must be at least 1".to_string());
+        }
+        if self.monitor.ws_min_interval_secs > self.monitor.ws_max_interval_secs {
+            problems.push(
+                "monitor.ws_min_interval_secs is larger than ws_max_interval_secs".to_string(),
+            );
+        }
+        if self.updates.interval_mins == 0 {
+            problems.push("updates.interval_mins must be at least 1".to_string());
+        }
+        for proxy in &self.panel.trusted_proxies {
+            if crate::accesslog::parse_network(proxy).is_none() {
+                problems.push(format!(
+                    "panel.trusted_proxies: '{}' is not an address or CIDR range (ignored)",
+                    proxy
+                ));
+            }
+        }
+        if self.provisioning.step_timeout_secs == 0 {
+            problems.push("provisioning.step_timeout_secs must be at least 1".to_string());
+        }
+        for (index, server) in self.servers.iter().enumerate() {
+            if self.servers[..index].iter().any(|s| s.id == server.id) {
+                problems.push(format!(
+                    "servers: duplicate id '{}' (only the first is used)",
+                    server.id
+                ));
+            }
+            if server.rcon.port == 0 {
+                problems.push(format!("servers.{}: rcon.port must not be 0", server.id));
+            }
+            if let Err(e) = server.resource_limits.validate() {
+                problems.push(format!("servers.{}: resource_limits: {}", server.id, e));
+            }
+            if let Some(slug) = &server.public_slug {
+                if !crate::publicstatus::is_valid_slug(slug) {
+                    problems.push(format!(
+                        "servers.{}: public_slug '{}' should be lowercase letters, digits and dashes",
+                        server.id, slug
+                    ));
+                } else if self.servers[..index]
+                    .iter()
+                    .any(|s| s.public_slug.as_deref() == Some(slug.as_str()))
+                {
+                    problems.push(format!(
+                        "servers.{}: public_slug '{}' is used twice",
+                        server.id, slug
+                    ));
+                }
+            }
+            if !Path::new(&server.paths.lgsm_script).exists() {
+                problems.push(format!(
+                    "servers.{}: lgsm_script {} does not exist",
+                    server.id, server.paths.lgsm_script
+                ));
+            }
+        }
+        problems
+    }
 }
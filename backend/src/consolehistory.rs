@@ -0,0 +1,109 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use utoipa::{IntoParams, ToSchema};
+
+use crate::apierror::ApiError;
+use crate::monitor::RingBuffer;
+use crate::registry::ServerRegistry;
+
+/// Entries returned when the client gives no limit.
+const DEFAULT_LIMIT: usize = 200;
+/// Longer replies are cut in the history, like scrollback lines.
+const MAX_RESPONSE_LEN: usize = 4096;
+
+/// A command sent from the console WebSocket and what came back.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsoleExchange {
+    pub command: String,
+    /// The reply, or why the command failed or was refused.
+    pub response: String,
+    pub success: bool,
+    pub username: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// The last console exchanges of each server, so a reloaded console gets its command
+/// history back. In memory: a panel restart forgets it.
+pub struct ConsoleHistory {
+    capacity: usize,
+    servers: Mutex<HashMap<String, RingBuffer<ConsoleExchange>>>,
+}
+
+impl ConsoleHistory {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            servers: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn record(&self, server_id: &str, mut exchange: ConsoleExchange) {
+        if self.capacity == 0 {
+            return;
+        }
+        if exchange.response.len() > MAX_RESPONSE_LEN {
+            let mut end = MAX_RESPONSE_LEN;
+            while !exchange.response.is_char_boundary(end) {
+                end -= 1;
+            }
+            exchange.response.truncate(end);
+            exchange.response.push_str(" [...]");
+        }
+        self.servers
+            .lock()
+            .unwrap()
+            .entry(server_id.to_string())
+            .or_insert_with(|| RingBuffer::new(self.capacity))
+            .push(exchange);
+    }
+
+    /// The server's last `limit` exchanges, oldest first.
+    pub fn recent(&self, server_id: &str, limit: usize) -> Vec<ConsoleExchange> {
+        let servers = self.servers.lock().unwrap();
+        let Some(history) = servers.get(server_id) else {
+            return Vec::new();
+        };
+        let skip = history.iter().count().saturating_sub(limit);
+        history.iter().skip(skip).cloned().collect()
+    }
+
+    /// Drop a deleted server's history.
+    pub fn forget(&self, server_id: &str) {
+        self.servers.lock().unwrap().remove(server_id);
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ConsoleHistoryQuery {
+    /// Most recent entries to return (default 200, at most `console.history_size`).
+    pub limit: Option<usize>,
+}
+
+/// GET /api/servers/{server_id}/console/history?limit=
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/console/history",
+    tag = "console",
+    params(("server_id" = String, Path, description = "Server id"), ConsoleHistoryQuery),
+    responses(
+        (status = 200, description = "Commands sent from the console and their replies, oldest first", body = [ConsoleExchange]),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn console_history(
+    server_id: web::Path<String>,
+    query: web::Query<ConsoleHistoryQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    history: web::Data<Arc<ConsoleHistory>>,
+) -> Result<HttpResponse, ApiError> {
+    registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let limit = query.limit.unwrap_or(DEFAULT_LIMIT);
+    Ok(HttpResponse::Ok().json(history.recent(&server_id, limit)))
+}
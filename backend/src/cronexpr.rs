@@ -0,0 +1,310 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Timelike, Utc};
+
+/// How far ahead `next_after` searches before giving up (e.g. "0 0 30 2 *").
+const MAX_LOOKAHEAD_DAYS: i64 = 366 * 5;
+
+/// A standard 5-field cron expression: minute hour day-of-month month day-of-week.
+///
+/// Supports `*`, lists (`1,15`), ranges (`1-5`), steps (`*/6`, `0-30/10`), month and
+/// weekday names (`jan`, `mon`), `7` as Sunday, and `weekday#n` for the n-th weekday of
+/// the month (`0 19 * * thu#1`). Like cron, when both day fields are restricted a day
+/// matches if either does; a day field starting with `*` (`*`, `*/2`) is unrestricted, so
+/// `0 0 */2 * mon` runs on odd days that are Mondays, not on odd days or Mondays. Times
+/// are UTC.
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// (weekday, n) for `weekday#n`.
+    nth_weekday: Option<(u32, u32)>,
+    dom_restricted: bool,
+    dow_restricted: bool,
+}
+
+const MONTH_NAMES: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAY_NAMES: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+impl CronSchedule {
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = expr.split_whitespace().collect();
+        if fields.len() != 5 {
+            return Err(format!(
+                "Cron expressions need 5 fields (minute hour day month weekday), got {}",
+                fields.len()
+            ));
+        }
+
+        let minutes = parse_field(fields[0], 0, 59, &[]).map_err(|e| format!("minute: {}", e))?;
+        let hours = parse_field(fields[1], 0, 23, &[]).map_err(|e| format!("hour: {}", e))?;
+        let days = parse_field(fields[2], 1, 31, &[]).map_err(|e| format!("day: {}", e))?;
+        let months =
+            parse_field(fields[3], 1, 12, &MONTH_NAMES).map_err(|e| format!("month: {}", e))?;
+
+        let (weekdays, nth_weekday) = match fields[4].split_once('#') {
+            Some((day, n)) => {
+                let day = parse_value(day, 0, 7, &WEEKDAY_NAMES)
+                    .map_err(|e| format!("weekday: {}", e))?
+                    % 7;
+                let n: u32 = n
+                    .parse()
+                    .ok()
+                    .filter(|n| (1..=5).contains(n))
+                    .ok_or_else(|| format!("weekday: invalid occurrence '#{}' (use 1-5)", n))?;
+                (1u64 << day, Some((day, n)))
+            }
+            None => {
+                let mut mask = parse_field(fields[4], 0, 7, &WEEKDAY_NAMES)
+                    .map_err(|e| format!("weekday: {}", e))?;
+                // 7 is an alias for Sunday
+                if mask & (1 << 7) != 0 {
+                    mask = (mask & !(1 << 7)) | 1;
+                }
+                (mask, None)
+            }
+        };
+
+        Ok(Self {
+            minutes,
+            hours: hours as u32,
+            days: days as u32,
+            months: months as u16,
+            weekdays: weekdays as u8,
+            nth_weekday,
+            // Vixie cron's rule: only a field starting with '*' is unrestricted
+            dom_restricted: !fields[2].starts_with('*'),
+            dow_restricted: !fields[4].starts_with('*'),
+        })
+    }
+
+    /// The first matching minute strictly after `after`.
+    pub fn next_after(&self, after: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let start = after.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let start_date = start.date_naive();
+
+        for offset in 0..MAX_LOOKAHEAD_DAYS {
+            let date = start_date + Duration::days(offset);
+            if !self.matches_date(date) {
+                continue;
+            }
+            let first_day = offset == 0;
+            for hour in 0..24u32 {
+                if self.hours & (1 << hour) == 0 || (first_day && hour < start.hour()) {
+                    continue;
+                }
+                for minute in 0..60u32 {
+                    if self.minutes & (1 << minute) == 0 {
+                        continue;
+                    }
+                    if first_day && hour == start.hour() && minute < start.minute() {
+                        continue;
+                    }
+                    let naive = date.and_hms_opt(hour, minute, 0)?;
+                    return Some(Utc.from_utc_datetime(&naive));
+                }
+            }
+        }
+        None
+    }
+
+    fn matches_date(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let dom = self.days & (1 << date.day()) != 0;
+        let weekday = date.weekday().num_days_from_sunday();
+        let dow = match self.nth_weekday {
+            Some((day, n)) => weekday == day && (date.day() - 1) / 7 + 1 == n,
+            None => self.weekdays & (1 << weekday) != 0,
+        };
+        if self.dom_restricted && self.dow_restricted {
+            dom || dow
+        } else {
+            dom && dow
+        }
+    }
+}
+
+/// Parse one cron field into a bitmask of allowed values.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64, String> {
+    let mut mask = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .ok_or_else(|| format!("invalid step '{}'", step))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (lo, hi) = if range == "*" {
+            (min, max)
+        } else if let Some((lo, hi)) = range.split_once('-') {
+            (
+                parse_value(lo, min, max, names)?,
+                parse_value(hi, min, max, names)?,
+            )
+        } else {
+            let value = parse_value(range, min, max, names)?;
+            // "5/15" means "from 5 to the end, every 15"
+            if step > 1 {
+                (value, max)
+            } else {
+                (value, value)
+            }
+        };
+        if lo > hi {
+            return Err(format!("range '{}' is backwards", range));
+        }
+        let mut value = lo;
+        while value <= hi {
+            mask |= 1 << value;
+            value += step;
+        }
+    }
+    Ok(mask)
+}
+
+fn parse_value(value: &str, min: u32, max: u32, names: &[&str]) -> Result<u32, String> {
+    let lower = value.to_lowercase();
+    if let Some(idx) = names.iter().position(|n| lower.starts_with(n)) {
+        // Month names are 1-based, weekday names 0-based
+        return Ok(idx as u32 + min);
+    }
+    match value.parse::<u32>() {
+        Ok(v) if (min..=max).contains(&v) => Ok(v),
+        _ => Err(format!("'{}' is not in {}-{}", value, min, max)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn next_after_follows_the_fields() {
+        // (expression, after, next)
+        let cases = [
+            ("* * * * *", "2026-03-10T12:00:30Z", "2026-03-10T12:01:00Z"),
+            ("30 4 * * *", "2026-03-10T04:30:00Z", "2026-03-11T04:30:00Z"),
+            (
+                "0 9-17 * * *",
+                "2026-03-10T17:10:00Z",
+                "2026-03-11T09:00:00Z",
+            ),
+            (
+                "*/15 * * * *",
+                "2026-03-10T12:16:00Z",
+                "2026-03-10T12:30:00Z",
+            ),
+            (
+                "0-30/10 * * * *",
+                "2026-03-10T12:31:00Z",
+                "2026-03-10T13:00:00Z",
+            ),
+            (
+                "5/20 * * * *",
+                "2026-03-10T12:26:00Z",
+                "2026-03-10T12:45:00Z",
+            ),
+            (
+                "0 0,12 * * *",
+                "2026-03-10T00:00:00Z",
+                "2026-03-10T12:00:00Z",
+            ),
+            (
+                "0 0 1,15 * *",
+                "2026-03-02T00:00:00Z",
+                "2026-03-15T00:00:00Z",
+            ),
+            (
+                "0 6 * * mon-fri",
+                "2026-03-13T07:00:00Z",
+                "2026-03-16T06:00:00Z",
+            ),
+            ("0 6 * * 7", "2026-03-10T00:00:00Z", "2026-03-15T06:00:00Z"),
+            (
+                "0 19 * * thu#1",
+                "2026-03-06T00:00:00Z",
+                "2026-04-02T19:00:00Z",
+            ),
+            // Month rollover, and year rollover through a month list
+            ("0 0 31 * *", "2026-04-01T00:00:00Z", "2026-05-31T00:00:00Z"),
+            (
+                "0 0 1 jan,jul *",
+                "2026-07-01T00:00:00Z",
+                "2027-01-01T00:00:00Z",
+            ),
+            (
+                "0 0 29 feb *",
+                "2026-03-01T00:00:00Z",
+                "2028-02-29T00:00:00Z",
+            ),
+            // Both day fields restricted: either matches
+            (
+                "0 0 13 * fri",
+                "2026-03-01T00:00:00Z",
+                "2026-03-06T00:00:00Z",
+            ),
+            (
+                "0 0 13 * fri",
+                "2026-03-07T00:00:00Z",
+                "2026-03-13T00:00:00Z",
+            ),
+            // A day field starting with '*' is unrestricted: both must match
+            (
+                "0 0 */2 * mon",
+                "2026-03-01T00:00:00Z",
+                "2026-03-09T00:00:00Z",
+            ),
+            (
+                "0 0 1 * */2",
+                "2026-03-02T00:00:00Z",
+                "2026-08-01T00:00:00Z",
+            ),
+        ];
+        for (expr, after, next) in cases {
+            let schedule = CronSchedule::parse(expr).unwrap();
+            assert_eq!(
+                schedule.next_after(at(after)),
+                Some(at(next)),
+                "{expr} after {after}"
+            );
+        }
+    }
+
+    #[test]
+    fn impossible_dates_never_match() {
+        let schedule = CronSchedule::parse("0 0 30 2 *").unwrap();
+        assert_eq!(schedule.next_after(at("2026-01-01T00:00:00Z")), None);
+    }
+
+    #[test]
+    fn malformed_expressions_are_rejected() {
+        for expr in [
+            "* * * *",
+            "60 * * * *",
+            "* 24 * * *",
+            "* * 0 * *",
+            "* * * 13 *",
+            "* * * * 8",
+            "*/0 * * * *",
+            "10-5 * * * *",
+            "* * * * mon#6",
+            "* * * * x",
+        ] {
+            assert!(CronSchedule::parse(expr).is_err(), "{expr}");
+        }
+    }
+}
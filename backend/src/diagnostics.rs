@@ -0,0 +1,247 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::sync::{Arc, LazyLock, Mutex, OnceLock};
+use std::time::Instant;
+use utoipa::ToSchema;
+
+use crate::lgsm::LgsmHolder;
+use crate::registry::{ProvisioningStatus, ServerRegistry};
+use crate::scheduler::Scheduler;
+use crate::websocket::WsSessions;
+
+/// What a background task last reported about itself.
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskHealth {
+    /// Last iteration that went through without an error.
+    pub last_ok_at: Option<DateTime<Utc>>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<DateTime<Utc>>,
+    pub error_count: u64,
+}
+
+struct Diagnostics {
+    started: Instant,
+    started_at: DateTime<Utc>,
+    tasks: Mutex<BTreeMap<String, TaskHealth>>,
+}
+
+/// Process-wide, so any task can report without having it threaded through.
+static DIAGNOSTICS: LazyLock<Diagnostics> = LazyLock::new(|| Diagnostics {
+    started: Instant::now(),
+    started_at: Utc::now(),
+    tasks: Mutex::new(BTreeMap::new()),
+});
+
+/// Runtime the background tasks were spawned on. Handlers run on actix's per-worker
+/// runtimes, so `Handle::current()` there would only count that worker's tasks.
+static MAIN_RUNTIME: OnceLock<tokio::runtime::Handle> = OnceLock::new();
+
+/// Start the uptime clock and remember the main runtime; call once at startup.
+pub fn init() {
+    LazyLock::force(&DIAGNOSTICS);
+    let _ = MAIN_RUNTIME.set(tokio::runtime::Handle::current());
+}
+
+/// Record that `task` completed an iteration.
+pub fn report_ok(task: &str) {
+    if let Ok(mut tasks) = DIAGNOSTICS.tasks.lock() {
+        tasks.entry(task.to_string()).or_default().last_ok_at = Some(Utc::now());
+    }
+}
+
+/// Record an error in `task`. Callers still log it; this keeps the latest for the API.
+pub fn report_error(task: &str, error: impl Display) {
+    if let Ok(mut tasks) = DIAGNOSTICS.tasks.lock() {
+        let health = tasks.entry(task.to_string()).or_default();
+        health.last_error = Some(error.to_string());
+        health.last_error_at = Some(Utc::now());
+        health.error_count += 1;
+    }
+}
+
+/// Forget a task that no longer exists (e.g. a deleted server's collector).
+pub fn forget(task: &str) {
+    if let Ok(mut tasks) = DIAGNOSTICS.tasks.lock() {
+        tasks.remove(task);
+    }
+}
+
+/// Diagnostics name of a server's game collector.
+pub fn collector_task(server_id: &str) -> String {
+    format!("game collector ({})", server_id)
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskCounts {
+    /// Tasks alive on the main runtime: background loops, RCON readers, job runs.
+    pub alive_tasks: usize,
+    /// Per-server game collectors that are still running.
+    pub game_collectors: usize,
+    /// Scheduled jobs executing right now.
+    pub running_jobs: usize,
+    /// Open WebSockets by kind ("console", "monitor", ...).
+    pub websocket_sessions: BTreeMap<&'static str, usize>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DataDirUsage {
+    pub path: String,
+    pub available_bytes: u64,
+    pub total_bytes: u64,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerDiagnostics {
+    pub server_id: String,
+    pub provisioning_status: ProvisioningStatus,
+    /// Whether the server has RCON/monitor/lock state loaded (Ready servers should).
+    pub runtime: bool,
+    pub monitoring_paused: bool,
+    pub collector_running: bool,
+    pub rcon_connected: bool,
+    /// Commands waiting for RCON to come back.
+    pub rcon_queued: usize,
+    /// LGSM action holding the server's lock, if any.
+    pub lgsm_holder: Option<LgsmHolder>,
+    /// Requests waiting for that lock; a growing number means a stuck action.
+    pub lgsm_waiters: usize,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsResponse {
+    pub started_at: DateTime<Utc>,
+    pub uptime_secs: u64,
+    pub pid: u32,
+    /// Resident memory of the panel process.
+    pub memory_bytes: u64,
+    pub virtual_memory_bytes: u64,
+    pub tasks: TaskCounts,
+    /// Free space where the panel keeps its database; null if the disk isn't found.
+    pub data_dir: Option<DataDirUsage>,
+    pub servers: Vec<ServerDiagnostics>,
+    /// Background tasks by name, with their last success and last error.
+    pub background: BTreeMap<String, TaskHealth>,
+}
+
+/// Memory (resident, virtual) of this process.
+fn own_memory() -> (u64, u64) {
+    use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+    let pid = Pid::from_u32(std::process::id());
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::Some(&[pid]),
+        true,
+        ProcessRefreshKind::nothing().with_memory(),
+    );
+    sys.process(pid)
+        .map_or((0, 0), |p| (p.memory(), p.virtual_memory()))
+}
+
+/// The disk holding the working directory, where panel.db lives.
+fn data_dir_usage() -> Option<DataDirUsage> {
+    let dir = std::env::current_dir().ok()?.canonicalize().ok()?;
+    let disks = sysinfo::Disks::new_with_refreshed_list();
+    let disk = disks
+        .list()
+        .iter()
+        .filter(|d| dir.starts_with(d.mount_point()))
+        .max_by_key(|d| d.mount_point().as_os_str().len())?;
+    Some(DataDirUsage {
+        path: dir.display().to_string(),
+        available_bytes: disk.available_space(),
+        total_bytes: disk.total_space(),
+    })
+}
+
+/// GET /api/diagnostics
+///
+/// The panel's own health: process usage, background tasks and their last errors, and
+/// per-server runtime state.
+#[utoipa::path(
+    get,
+    path = "/api/diagnostics",
+    tag = "diagnostics",
+    responses((status = 200, body = DiagnosticsResponse))
+)]
+pub async fn get_diagnostics(
+    registry: web::Data<Arc<ServerRegistry>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+    sessions: web::Data<Arc<WsSessions>>,
+) -> HttpResponse {
+    let (memory_bytes, virtual_memory_bytes) = tokio::task::spawn_blocking(own_memory)
+        .await
+        .unwrap_or_default();
+    let data_dir = tokio::task::spawn_blocking(data_dir_usage)
+        .await
+        .ok()
+        .flatten();
+
+    let mut websocket_sessions = BTreeMap::new();
+    for session in sessions.list() {
+        *websocket_sessions.entry(session.kind).or_insert(0) += 1;
+    }
+
+    let mut servers = Vec::new();
+    let mut game_collectors = 0;
+    let definitions = registry.all_definitions().await;
+    {
+        let runtimes = registry.runtimes.read().await;
+        for def in definitions {
+            let runtime = runtimes.get(&def.id);
+            let collector_running = runtime
+                .and_then(|r| r.collector_handle.as_ref())
+                .is_some_and(|h| !h.is_finished());
+            if collector_running {
+                game_collectors += 1;
+            }
+            let (rcon_connected, rcon_queued) = match runtime {
+                Some(r) => (r.rcon.is_connected().await, r.rcon.queued_count().await),
+                None => (false, 0),
+            };
+            servers.push(ServerDiagnostics {
+                server_id: def.id.clone(),
+                provisioning_status: def.provisioning_status.clone(),
+                runtime: runtime.is_some(),
+                monitoring_paused: def.monitoring_paused,
+                collector_running,
+                rcon_connected,
+                rcon_queued,
+                lgsm_holder: runtime.and_then(|r| r.lgsm_lock.current()),
+                lgsm_waiters: runtime.map_or(0, |r| r.lgsm_lock.waiters()),
+            });
+        }
+    }
+
+    let background = DIAGNOSTICS
+        .tasks
+        .lock()
+        .map(|tasks| tasks.clone())
+        .unwrap_or_default();
+    HttpResponse::Ok().json(DiagnosticsResponse {
+        started_at: DIAGNOSTICS.started_at,
+        uptime_secs: DIAGNOSTICS.started.elapsed().as_secs(),
+        pid: std::process::id(),
+        memory_bytes,
+        virtual_memory_bytes,
+        tasks: TaskCounts {
+            alive_tasks: MAIN_RUNTIME
+                .get()
+                .map_or(0, |rt| rt.metrics().num_alive_tasks()),
+            game_collectors,
+            running_jobs: scheduler.running_jobs(),
+            websocket_sessions,
+        },
+        data_dir,
+        servers,
+        background,
+    })
+}
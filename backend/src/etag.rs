@@ -0,0 +1,117 @@
+use actix_web::http::header::{self, EntityTag, Header, IfNoneMatch};
+use actix_web::{HttpRequest, HttpResponse, ResponseError};
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::apierror::ApiError;
+
+/// Strong entity tag from a cheap version key (e.g. latest snapshot time and count).
+/// Tags only need to be stable for one panel process, so the std hasher is enough.
+pub fn from_key<K: Hash + ?Sized>(key: &K) -> EntityTag {
+    let mut hasher = DefaultHasher::new();
+    key.hash(&mut hasher);
+    EntityTag::new_strong(format!("{:016x}", hasher.finish()))
+}
+
+/// Whether the client's If-None-Match already names `etag`.
+pub fn is_fresh(req: &HttpRequest, etag: &EntityTag) -> bool {
+    match IfNoneMatch::parse(req) {
+        Ok(IfNoneMatch::Any) => true,
+        Ok(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(etag)),
+        Err(_) => false,
+    }
+}
+
+pub fn not_modified(etag: EntityTag) -> HttpResponse {
+    HttpResponse::NotModified()
+        .insert_header(header::ETag(etag))
+        .finish()
+}
+
+/// `body` as JSON tagged with a hash of its bytes, or 304 if the client has it. For
+/// responses without a cheap version key; it still saves sending the body.
+pub fn json<T: Serialize>(req: &HttpRequest, body: &T) -> HttpResponse {
+    let bytes = match serde_json::to_vec(body) {
+        Ok(b) => b,
+        Err(e) => {
+            return ApiError::internal(format!("Failed to serialize response: {}", e))
+                .error_response()
+        }
+    };
+    let etag = from_key(&bytes);
+    if is_fresh(req, &etag) {
+        return not_modified(etag);
+    }
+    HttpResponse::Ok()
+        .insert_header(header::ETag(etag))
+        .content_type(header::ContentType::json())
+        .body(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use actix_web::{middleware, test, web, App};
+
+    async fn snapshots(req: HttpRequest) -> HttpResponse {
+        let rows: Vec<_> = (0..200)
+            .map(|i| serde_json::json!({"timestamp": i, "players": 42, "fps": 60.0}))
+            .collect();
+        json(&req, &rows)
+    }
+
+    #[actix_web::test]
+    async fn compressed_bodies_are_smaller_and_fresh_tags_get_an_empty_304() {
+        let app = test::init_service(
+            App::new()
+                .wrap(middleware::Compress::default())
+                .route("/snapshots", web::get().to(snapshots)),
+        )
+        .await;
+
+        let req = test::TestRequest::get().uri("/snapshots").to_request();
+        let response = test::call_service(&app, req).await;
+        assert!(response.headers().get(header::CONTENT_ENCODING).is_none());
+        let etag = response.headers().get(header::ETAG).unwrap().clone();
+        let raw = test::read_body(response).await;
+
+        for encoding in ["gzip", "br", "zstd"] {
+            let req = test::TestRequest::get()
+                .uri("/snapshots")
+                .insert_header((header::ACCEPT_ENCODING, encoding))
+                .to_request();
+            let response = test::call_service(&app, req).await;
+            assert_eq!(response.status(), 200);
+            assert_eq!(
+                response.headers().get(header::CONTENT_ENCODING).unwrap(),
+                encoding
+            );
+            assert_eq!(response.headers().get(header::ETAG), Some(&etag));
+            let compressed = test::read_body(response).await;
+            assert!(
+                compressed.len() < raw.len(),
+                "{}: {} >= {}",
+                encoding,
+                compressed.len(),
+                raw.len()
+            );
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/snapshots")
+            .insert_header((header::ACCEPT_ENCODING, "gzip"))
+            .insert_header((header::IF_NONE_MATCH, etag.clone()))
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), 304);
+        assert_eq!(response.headers().get(header::ETAG), Some(&etag));
+        assert!(test::read_body(response).await.is_empty());
+
+        let req = test::TestRequest::get()
+            .uri("/snapshots")
+            .insert_header((header::IF_NONE_MATCH, "\"stale\""))
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 200);
+    }
+}
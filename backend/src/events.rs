@@ -0,0 +1,137 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use tokio::sync::broadcast;
+
+use crate::registry::ProvisioningStatus;
+use crate::requestid;
+
+/// Events buffered per subscriber; slower clients miss events instead of growing memory.
+const EVENT_CHANNEL_CAPACITY: usize = 256;
+/// Events kept for the activity feed (LGSM actions and provisioning have no other record).
+const RECENT_EVENTS: usize = 500;
+
+/// Something that happened on the panel, pushed to /ws/events.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PanelEvent {
+    #[serde(rename_all = "camelCase")]
+    ServerOnline { server_id: String },
+    #[serde(rename_all = "camelCase")]
+    ServerOffline { server_id: String },
+    #[serde(rename_all = "camelCase")]
+    ProvisioningStatus {
+        server_id: String,
+        status: ProvisioningStatus,
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    JobFinished {
+        server_id: String,
+        job_id: String,
+        job_name: String,
+        success: bool,
+        skipped: bool,
+        message: String,
+    },
+    #[serde(rename_all = "camelCase")]
+    LgsmAction {
+        server_id: String,
+        action: String,
+        success: bool,
+        /// Panel user who ran it.
+        initiated_by: Option<String>,
+    },
+    /// LinuxGSM's check-update found a newer server build.
+    #[serde(rename_all = "camelCase")]
+    UpdateAvailable { server_id: String },
+    /// More players online than at any point in the retained monitor history.
+    #[serde(rename_all = "camelCase")]
+    PlayerPeak { server_id: String, players: u32 },
+}
+
+impl PanelEvent {
+    pub fn server_id(&self) -> &str {
+        match self {
+            PanelEvent::ServerOnline { server_id }
+            | PanelEvent::ServerOffline { server_id }
+            | PanelEvent::ProvisioningStatus { server_id, .. }
+            | PanelEvent::JobFinished { server_id, .. }
+            | PanelEvent::LgsmAction { server_id, .. }
+            | PanelEvent::UpdateAvailable { server_id }
+            | PanelEvent::PlayerPeak { server_id, .. } => server_id,
+        }
+    }
+
+    /// The serialized `type` tag, for subscription filters.
+    pub fn kind(&self) -> &'static str {
+        match self {
+            PanelEvent::ServerOnline { .. } => "server_online",
+            PanelEvent::ServerOffline { .. } => "server_offline",
+            PanelEvent::ProvisioningStatus { .. } => "provisioning_status",
+            PanelEvent::JobFinished { .. } => "job_finished",
+            PanelEvent::LgsmAction { .. } => "lgsm_action",
+            PanelEvent::UpdateAvailable { .. } => "update_available",
+            PanelEvent::PlayerPeak { .. } => "player_peak",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimedEvent {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub event: PanelEvent,
+    /// Request that caused the event, when it came from one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+/// In-process broadcast bus. Collectors, the provisioner, the scheduler and LGSM
+/// actions publish; each events socket subscribes.
+#[derive(Clone)]
+pub struct EventBus {
+    tx: broadcast::Sender<TimedEvent>,
+    /// The last RECENT_EVENTS events, oldest first; lost on restart.
+    recent: Arc<Mutex<VecDeque<TimedEvent>>>,
+}
+
+impl EventBus {
+    pub fn new() -> Self {
+        let (tx, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+        Self {
+            tx,
+            recent: Arc::new(Mutex::new(VecDeque::with_capacity(RECENT_EVENTS))),
+        }
+    }
+
+    pub fn publish(&self, event: PanelEvent) {
+        let event = TimedEvent {
+            timestamp: Utc::now(),
+            event,
+            request_id: requestid::current(),
+        };
+        if let Ok(mut recent) = self.recent.lock() {
+            if recent.len() == RECENT_EVENTS {
+                recent.pop_front();
+            }
+            recent.push_back(event.clone());
+        }
+        // No subscribers is fine
+        let _ = self.tx.send(event);
+    }
+
+    /// Events published since startup, oldest first (at most RECENT_EVENTS).
+    pub fn recent(&self) -> Vec<TimedEvent> {
+        self.recent
+            .lock()
+            .map(|recent| recent.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<TimedEvent> {
+        self.tx.subscribe()
+    }
+}
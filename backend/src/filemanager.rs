@@ -1,16 +1,29 @@
-use actix_multipart::Multipart;
-use actix_web::{web, HttpResponse};
+use actix_multipart::{Field, Multipart};
+use actix_web::http::header::{
+    Charset, ContentDisposition, DispositionParam, DispositionType, ExtendedValue,
+};
+use actix_web::web::Bytes;
+use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::{DateTime, Utc};
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
+use tokio::io::AsyncWriteExt;
+use tokio::sync::mpsc;
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
 
+use crate::apierror::ApiError;
+use crate::audit;
+use crate::config::AppConfig;
 use crate::registry::ServerRegistry;
+use crate::zipstream::{self, ZipWriter};
 
 const MAX_FILE_SIZE: u64 = 1_048_576; // 1 MB for text reads
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct FileEntry {
     pub name: String,
@@ -21,64 +34,70 @@ pub struct FileEntry {
     pub is_text: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct ListQuery {
     pub path: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct ReadQuery {
     pub path: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct DownloadQuery {
     pub path: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct WriteBody {
     pub path: String,
     pub content: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct MkdirBody {
     pub path: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ArchiveQuery {
+    /// Directory to archive; the whole server directory when empty.
+    pub path: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct DeleteQuery {
     pub path: String,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
-    error: String,
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct RenameBody {
+    pub from: String,
+    pub to: String,
+    /// Replace an existing file or directory at `to`.
+    #[serde(default)]
+    pub overwrite: bool,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct SuccessBody {
     success: bool,
     message: String,
 }
 
-async fn get_base_dir(
+pub async fn get_base_dir(
     server_id: &str,
     registry: &Arc<ServerRegistry>,
-) -> Result<String, HttpResponse> {
+) -> Result<String, ApiError> {
     registry
         .get_config(server_id)
         .await
         .map(|c| c.paths.base_dir)
-        .ok_or_else(|| {
-            HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        })
+        .ok_or_else(ApiError::server_not_found)
 }
 
-fn safe_resolve(base_dir: &str, relative_path: &str) -> Result<PathBuf, String> {
+pub fn safe_resolve(base_dir: &str, relative_path: &str) -> Result<PathBuf, String> {
     let base = PathBuf::from(base_dir);
     let cleaned = relative_path.trim_start_matches('/');
     let requested = if cleaned.is_empty() {
@@ -115,6 +134,76 @@ fn safe_resolve(base_dir: &str, relative_path: &str) -> Result<PathBuf, String>
     Ok(canonical)
 }
 
+/// Like `safe_resolve`, but only the parent directory is canonicalized: a symlink at
+/// `relative_path` resolves to the link itself, not to what it points at.
+fn safe_resolve_entry(base_dir: &str, relative_path: &str) -> Result<PathBuf, String> {
+    let cleaned = relative_path.trim_matches('/');
+    let requested = Path::new(cleaned);
+    let file_name = match requested.components().next_back() {
+        Some(std::path::Component::Normal(name)) => name.to_owned(),
+        _ => return Err("Invalid path: no filename".to_string()),
+    };
+    let parent = requested
+        .parent()
+        .map(|p| p.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let canonical_parent = safe_resolve(base_dir, &parent)?;
+    if !canonical_parent.is_dir() {
+        return Err("Parent directory does not exist".to_string());
+    }
+    Ok(canonical_parent.join(file_name))
+}
+
+/// Listing entry for `path`, with its path relative to `base_dir`.
+fn file_entry(path: &Path, base_dir: &str) -> FileEntry {
+    let metadata = std::fs::metadata(path).ok();
+    let rel_path = path
+        .strip_prefix(base_dir)
+        .map(|p| p.display().to_string())
+        .unwrap_or_else(|_| path.display().to_string());
+    FileEntry {
+        name: path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        path: rel_path,
+        is_dir: path.is_dir(),
+        size: metadata.as_ref().map(|m| m.len()).unwrap_or(0),
+        modified: metadata
+            .as_ref()
+            .and_then(|m| m.modified().ok())
+            .map(DateTime::<Utc>::from),
+        is_text: is_text_file(path),
+    }
+}
+
+/// Content-Disposition for a download: `filename` with anything unsafe in a quoted string
+/// replaced, plus the exact UTF-8 name in `filename*`.
+fn attachment(filename: &str) -> ContentDisposition {
+    let fallback: String = filename
+        .chars()
+        .map(|c| {
+            if c == ' ' || (c.is_ascii_graphic() && c != '"' && c != '\\') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    let mut parameters = vec![DispositionParam::Filename(fallback.clone())];
+    if fallback != filename {
+        parameters.push(DispositionParam::FilenameExt(ExtendedValue {
+            charset: Charset::Ext("UTF-8".to_string()),
+            language_tag: None,
+            value: filename.as_bytes().to_vec(),
+        }));
+    }
+    ContentDisposition {
+        disposition: DispositionType::Attachment,
+        parameters,
+    }
+}
+
 fn is_text_file(path: &Path) -> bool {
     let text_extensions = [
         "txt", "cfg", "json", "yaml", "yml", "toml", "xml", "ini", "conf", "log", "cs", "lua",
@@ -127,59 +216,46 @@ fn is_text_file(path: &Path) -> bool {
 }
 
 /// GET /api/servers/{server_id}/files/list
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/files/list",
+    tag = "files",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ListQuery,
+    ),
+    responses(
+        (status = 200, body = [FileEntry]),
+        (status = 400, body = ApiError),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn list_files(
     server_id: web::Path<String>,
     query: web::Query<ListQuery>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let base_dir = match get_base_dir(&server_id, &registry).await {
-        Ok(d) => d,
-        Err(e) => return e,
-    };
+) -> Result<HttpResponse, ApiError> {
+    let base_dir = get_base_dir(&server_id, &registry).await?;
     let relative = query.path.as_deref().unwrap_or("");
-    let dir_path = match safe_resolve(&base_dir, relative) {
-        Ok(p) => p,
-        Err(e) => return HttpResponse::Forbidden().json(ErrorBody { error: e }),
-    };
+    let dir_path = safe_resolve(&base_dir, relative).map_err(ApiError::path_forbidden)?;
 
     if !dir_path.is_dir() {
-        return HttpResponse::BadRequest().json(ErrorBody {
-            error: "Path is not a directory".to_string(),
-        });
+        return Err(ApiError::validation("Path is not a directory"));
     }
 
     let mut entries = Vec::new();
     match std::fs::read_dir(&dir_path) {
         Ok(read_dir) => {
             for entry in read_dir.flatten() {
-                let metadata = entry.metadata().ok();
-                let path = entry.path();
-                let is_dir = path.is_dir();
-                let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-                let modified = metadata
-                    .as_ref()
-                    .and_then(|m| m.modified().ok())
-                    .map(|t| DateTime::<Utc>::from(t));
-
-                let rel_path = path
-                    .strip_prefix(&base_dir)
-                    .map(|p| p.display().to_string())
-                    .unwrap_or_else(|_| path.display().to_string());
-
-                entries.push(FileEntry {
-                    name: entry.file_name().to_string_lossy().to_string(),
-                    path: rel_path,
-                    is_dir,
-                    size,
-                    modified,
-                    is_text: is_text_file(&path),
-                });
+                entries.push(file_entry(&entry.path(), &base_dir));
             }
         }
         Err(e) => {
-            return HttpResponse::InternalServerError().json(ErrorBody {
-                error: format!("Failed to read directory: {}", e),
-            });
+            return Err(ApiError::internal(format!(
+                "Failed to read directory: {}",
+                e
+            )));
         }
     }
 
@@ -189,68 +265,80 @@ pub async fn list_files(
             .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
     });
 
-    HttpResponse::Ok().json(entries)
+    Ok(HttpResponse::Ok().json(entries))
 }
 
 /// GET /api/servers/{server_id}/files/read
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/files/read",
+    tag = "files",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ReadQuery,
+    ),
+    responses(
+        (status = 200, description = "`path` and text `content` of the file", body = Object),
+        (status = 400, description = "Binary or larger than 1 MB", body = ApiError),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn read_file(
     server_id: web::Path<String>,
     query: web::Query<ReadQuery>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let base_dir = match get_base_dir(&server_id, &registry).await {
-        Ok(d) => d,
-        Err(e) => return e,
-    };
-    let file_path = match safe_resolve(&base_dir, &query.path) {
-        Ok(p) => p,
-        Err(e) => return HttpResponse::Forbidden().json(ErrorBody { error: e }),
-    };
+) -> Result<HttpResponse, ApiError> {
+    let base_dir = get_base_dir(&server_id, &registry).await?;
+    let file_path = safe_resolve(&base_dir, &query.path).map_err(ApiError::path_forbidden)?;
 
     if !file_path.is_file() {
-        return HttpResponse::NotFound().json(ErrorBody {
-            error: "File not found".to_string(),
-        });
+        return Err(ApiError::not_found("File not found"));
     }
 
     if let Ok(metadata) = std::fs::metadata(&file_path) {
         if metadata.len() > MAX_FILE_SIZE {
-            return HttpResponse::BadRequest().json(ErrorBody {
-                error: format!(
-                    "File too large ({} bytes, max {} bytes)",
-                    metadata.len(),
-                    MAX_FILE_SIZE
-                ),
-            });
+            return Err(ApiError::validation(format!(
+                "File too large ({} bytes, max {} bytes)",
+                metadata.len(),
+                MAX_FILE_SIZE
+            )));
         }
     }
 
     match std::fs::read_to_string(&file_path) {
-        Ok(content) => HttpResponse::Ok().json(serde_json::json!({
+        Ok(content) => Ok(HttpResponse::Ok().json(serde_json::json!({
             "path": query.path,
             "content": content,
             "size": content.len(),
-        })),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to read file: {}", e),
-        }),
+        }))),
+        Err(e) => Err(ApiError::internal(format!("Failed to read file: {}", e))),
     }
 }
 
 /// PUT /api/servers/{server_id}/files/write
+#[utoipa::path(
+    put,
+    path = "/api/servers/{server_id}/files/write",
+    tag = "files",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = WriteBody,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn write_file(
+    req: HttpRequest,
     server_id: web::Path<String>,
     body: web::Json<WriteBody>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let base_dir = match get_base_dir(&server_id, &registry).await {
-        Ok(d) => d,
-        Err(e) => return e,
-    };
-    let file_path = match safe_resolve(&base_dir, &body.path) {
-        Ok(p) => p,
-        Err(e) => return HttpResponse::Forbidden().json(ErrorBody { error: e }),
-    };
+) -> Result<HttpResponse, ApiError> {
+    audit::detail(&req, body.path.clone());
+    let base_dir = get_base_dir(&server_id, &registry).await?;
+    let file_path = safe_resolve(&base_dir, &body.path).map_err(ApiError::path_forbidden)?;
 
     if file_path.exists() {
         let backup_path = format!("{}.bak", file_path.display());
@@ -260,39 +348,47 @@ pub async fn write_file(
     }
 
     match std::fs::write(&file_path, &body.content) {
-        Ok(()) => HttpResponse::Ok().json(SuccessBody {
+        Ok(()) => Ok(HttpResponse::Ok().json(SuccessBody {
             success: true,
             message: format!("File written: {}", body.path),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to write file: {}", e),
-        }),
+        })),
+        Err(e) => Err(ApiError::internal(format!("Failed to write file: {}", e))),
     }
 }
 
 /// POST /api/servers/{server_id}/files/upload
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/files/upload",
+    tag = "files",
+    request_body(content_type = "multipart/form-data", description = "Target `path` field followed by one or more files"),
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 400, body = ApiError),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+        (status = 413, description = "Over `limits.upload_max_bytes`", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn upload_file(
+    req: HttpRequest,
     server_id: web::Path<String>,
     mut payload: Multipart,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let base_dir = match get_base_dir(&server_id, &registry).await {
-        Ok(d) => d,
-        Err(e) => return e,
-    };
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let base_dir = get_base_dir(&server_id, &registry).await?;
+    let max_bytes = config.limits.upload_max_bytes;
 
     let mut target_dir: Option<String> = None;
     let mut uploaded_files = Vec::new();
+    let mut paths = Vec::new();
+    let mut received = 0;
 
     while let Some(item) = payload.next().await {
-        let mut field = match item {
-            Ok(f) => f,
-            Err(e) => {
-                return HttpResponse::BadRequest().json(ErrorBody {
-                    error: format!("Multipart error: {}", e),
-                })
-            }
-        };
+        let mut field =
+            item.map_err(|e| ApiError::validation(format!("Multipart error: {}", e)))?;
 
         let field_name = field.name().map(|n| n.to_string()).unwrap_or_default();
 
@@ -314,56 +410,107 @@ pub async fn upload_file(
                 .unwrap_or_else(|| "uploaded_file".to_string());
 
             let dir = target_dir.as_deref().unwrap_or("");
-            let target_path = match safe_resolve(&base_dir, &format!("{}/{}", dir, filename)) {
-                Ok(p) => p,
-                Err(e) => return HttpResponse::Forbidden().json(ErrorBody { error: e }),
-            };
-
-            let mut file_data = Vec::new();
-            while let Some(chunk) = field.next().await {
-                if let Ok(bytes) = chunk {
-                    file_data.extend_from_slice(&bytes);
-                }
-            }
+            let target_path = safe_resolve(&base_dir, &format!("{}/{}", dir, filename))
+                .map_err(ApiError::path_forbidden)?;
+            paths.push(format!("{}/{}", dir.trim_end_matches('/'), filename));
+            audit::detail(&req, paths.join(", "));
 
-            match std::fs::write(&target_path, &file_data) {
-                Ok(()) => {
-                    uploaded_files.push(filename);
-                }
-                Err(e) => {
-                    return HttpResponse::InternalServerError().json(ErrorBody {
-                        error: format!("Failed to write uploaded file: {}", e),
-                    });
-                }
-            }
+            save_field(&mut field, &target_path, &mut received, max_bytes).await?;
+            uploaded_files.push(filename);
         }
     }
 
-    HttpResponse::Ok().json(SuccessBody {
+    Ok(HttpResponse::Ok().json(SuccessBody {
         success: true,
         message: format!("Uploaded: {}", uploaded_files.join(", ")),
-    })
+    }))
+}
+
+/// An upload being written; removed when dropped unless it was moved into place, which
+/// covers errors and the client going away (actix drops the handler future).
+struct PartialUpload {
+    path: PathBuf,
+    done: bool,
+}
+
+impl Drop for PartialUpload {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+/// Stream one multipart file field to `target` through a temporary file beside it, renamed
+/// into place once complete. `received` is the request's running total across fields;
+/// going past `max_bytes` aborts with 413.
+pub async fn save_field(
+    field: &mut Field,
+    target: &Path,
+    received: &mut u64,
+    max_bytes: u64,
+) -> Result<(), ApiError> {
+    let name = target
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut partial = PartialUpload {
+        path: target.with_file_name(format!(".{}.{}.upload", name, Uuid::new_v4().simple())),
+        done: false,
+    };
+    let mut file = tokio::fs::File::create(&partial.path)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to write uploaded file: {}", e)))?;
+
+    while let Some(chunk) = field.next().await {
+        let chunk =
+            chunk.map_err(|e| ApiError::validation(format!("Upload interrupted: {}", e)))?;
+        *received += chunk.len() as u64;
+        if *received > max_bytes {
+            return Err(ApiError::payload_too_large(max_bytes));
+        }
+        file.write_all(&chunk)
+            .await
+            .map_err(|e| ApiError::internal(format!("Failed to write uploaded file: {}", e)))?;
+    }
+    file.flush()
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to write uploaded file: {}", e)))?;
+    drop(file);
+
+    tokio::fs::rename(&partial.path, target)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to move upload into place: {}", e)))?;
+    partial.done = true;
+    Ok(())
 }
 
 /// GET /api/servers/{server_id}/files/download
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/files/download",
+    tag = "files",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        DownloadQuery,
+    ),
+    responses(
+        (status = 200, description = "File contents as an attachment", content_type = "application/octet-stream"),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn download_file(
     server_id: web::Path<String>,
     query: web::Query<DownloadQuery>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let base_dir = match get_base_dir(&server_id, &registry).await {
-        Ok(d) => d,
-        Err(e) => return e,
-    };
-    let file_path = match safe_resolve(&base_dir, &query.path) {
-        Ok(p) => p,
-        Err(e) => return HttpResponse::Forbidden().json(ErrorBody { error: e }),
-    };
+) -> Result<HttpResponse, ApiError> {
+    let base_dir = get_base_dir(&server_id, &registry).await?;
+    let file_path = safe_resolve(&base_dir, &query.path).map_err(ApiError::path_forbidden)?;
 
     if !file_path.is_file() {
-        return HttpResponse::NotFound().json(ErrorBody {
-            error: "File not found".to_string(),
-        });
+        return Err(ApiError::not_found("File not found"));
     }
 
     let filename = file_path
@@ -376,68 +523,80 @@ pub async fn download_file(
             let mime = mime_guess::from_path(&file_path)
                 .first_or_octet_stream()
                 .to_string();
-            HttpResponse::Ok()
+            Ok(HttpResponse::Ok()
                 .insert_header(("Content-Type", mime))
-                .insert_header((
-                    "Content-Disposition",
-                    format!("attachment; filename=\"{}\"", filename),
-                ))
-                .body(data)
+                .insert_header(attachment(filename))
+                .body(data))
         }
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to read file: {}", e),
-        }),
+        Err(e) => Err(ApiError::internal(format!("Failed to read file: {}", e))),
     }
 }
 
 /// POST /api/servers/{server_id}/files/mkdir
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/files/mkdir",
+    tag = "files",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = MkdirBody,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn mkdir(
+    req: HttpRequest,
     server_id: web::Path<String>,
     body: web::Json<MkdirBody>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let base_dir = match get_base_dir(&server_id, &registry).await {
-        Ok(d) => d,
-        Err(e) => return e,
-    };
-    let dir_path = match safe_resolve(&base_dir, &body.path) {
-        Ok(p) => p,
-        Err(e) => return HttpResponse::Forbidden().json(ErrorBody { error: e }),
-    };
+) -> Result<HttpResponse, ApiError> {
+    audit::detail(&req, body.path.clone());
+    let base_dir = get_base_dir(&server_id, &registry).await?;
+    let dir_path = safe_resolve(&base_dir, &body.path).map_err(ApiError::path_forbidden)?;
 
     match std::fs::create_dir_all(&dir_path) {
-        Ok(()) => HttpResponse::Ok().json(SuccessBody {
+        Ok(()) => Ok(HttpResponse::Ok().json(SuccessBody {
             success: true,
             message: format!("Directory created: {}", body.path),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to create directory: {}", e),
-        }),
+        })),
+        Err(e) => Err(ApiError::internal(format!(
+            "Failed to create directory: {}",
+            e
+        ))),
     }
 }
 
 /// DELETE /api/servers/{server_id}/files/delete
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{server_id}/files/delete",
+    tag = "files",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        DeleteQuery,
+    ),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn delete_file(
+    req: HttpRequest,
     server_id: web::Path<String>,
     query: web::Query<DeleteQuery>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let base_dir = match get_base_dir(&server_id, &registry).await {
-        Ok(d) => d,
-        Err(e) => return e,
-    };
-    let target_path = match safe_resolve(&base_dir, &query.path) {
-        Ok(p) => p,
-        Err(e) => return HttpResponse::Forbidden().json(ErrorBody { error: e }),
-    };
+) -> Result<HttpResponse, ApiError> {
+    audit::detail(&req, query.path.clone());
+    let base_dir = get_base_dir(&server_id, &registry).await?;
+    let target_path = safe_resolve(&base_dir, &query.path).map_err(ApiError::path_forbidden)?;
 
     let canonical_base = PathBuf::from(&base_dir)
         .canonicalize()
         .unwrap_or_else(|_| PathBuf::from(&base_dir));
     if target_path == canonical_base {
-        return HttpResponse::Forbidden().json(ErrorBody {
-            error: "Cannot delete the base directory".to_string(),
-        });
+        return Err(ApiError::path_forbidden("Cannot delete the base directory"));
     }
 
     let result = if target_path.is_dir() {
@@ -447,12 +606,428 @@ pub async fn delete_file(
     };
 
     match result {
-        Ok(()) => HttpResponse::Ok().json(SuccessBody {
+        Ok(()) => Ok(HttpResponse::Ok().json(SuccessBody {
             success: true,
             message: format!("Deleted: {}", query.path),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to delete: {}", e),
-        }),
+        })),
+        Err(e) => Err(ApiError::internal(format!("Failed to delete: {}", e))),
+    }
+}
+
+/// Move `from` to `to`, copying and deleting when they are on different filesystems.
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            let file_type = std::fs::symlink_metadata(from)?.file_type();
+            if file_type.is_symlink() {
+                std::os::unix::fs::symlink(std::fs::read_link(from)?, to)?;
+                std::fs::remove_file(from)
+            } else if file_type.is_dir() {
+                copy_dir(from, to)?;
+                std::fs::remove_dir_all(from)
+            } else {
+                std::fs::copy(from, to)?;
+                std::fs::remove_file(from)
+            }
+        }
+        result => result,
+    }
+}
+
+/// Move `from` over the existing `to`. The old target is set aside first and only deleted
+/// once the move succeeded; if the move fails it is put back.
+fn replace_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    let name = to.file_name().unwrap_or_default().to_string_lossy();
+    let aside = to.with_file_name(format!(".{}.{}.replaced", name, Uuid::new_v4().simple()));
+    std::fs::rename(to, &aside)?;
+    if let Err(e) = move_path(from, to) {
+        if let Err(restore) = std::fs::rename(&aside, to) {
+            tracing::error!(
+                "Failed to put {} back after a failed move: {}",
+                to.display(),
+                restore
+            );
+        }
+        return Err(e);
+    }
+    let removed = match std::fs::symlink_metadata(&aside) {
+        Ok(m) if m.is_dir() => std::fs::remove_dir_all(&aside),
+        _ => std::fs::remove_file(&aside),
+    };
+    if let Err(e) = removed {
+        tracing::warn!("Failed to remove replaced {}: {}", aside.display(), e);
+    }
+    Ok(())
+}
+
+/// Copy a directory tree; symlinks are recreated rather than followed.
+pub fn copy_dir(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::fs::create_dir(to)?;
+    for entry in std::fs::read_dir(from)? {
+        let entry = entry?;
+        let target = to.join(entry.file_name());
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            copy_dir(&entry.path(), &target)?;
+        } else if file_type.is_symlink() {
+            std::os::unix::fs::symlink(std::fs::read_link(entry.path())?, &target)?;
+        } else {
+            std::fs::copy(entry.path(), &target)?;
+        }
+    }
+    Ok(())
+}
+
+/// POST /api/servers/{server_id}/files/rename
+///
+/// Rename or move a file or directory within the server directory.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/files/rename",
+    tag = "files",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = RenameBody,
+    responses(
+        (status = 200, description = "The moved item at its new path", body = FileEntry),
+        (status = 400, description = "Moving the base directory or a directory into itself", body = ApiError),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 409, description = "Target exists and overwrite is not set", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn rename_file(
+    req: HttpRequest,
+    server_id: web::Path<String>,
+    body: web::Json<RenameBody>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    audit::detail(&req, format!("{} -> {}", body.from, body.to));
+    let base_dir = get_base_dir(&server_id, &registry).await?;
+    let from = safe_resolve_entry(&base_dir, &body.from).map_err(ApiError::path_forbidden)?;
+    let to = safe_resolve_entry(&base_dir, &body.to).map_err(ApiError::path_forbidden)?;
+
+    if std::fs::symlink_metadata(&from).is_err() {
+        return Err(ApiError::not_found("File not found"));
+    }
+    let canonical_base = PathBuf::from(&base_dir)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(&base_dir));
+    if from == canonical_base || to == canonical_base {
+        return Err(ApiError::validation("Cannot move the base directory"));
+    }
+    if from == to {
+        return Err(ApiError::validation("Source and target are the same"));
+    }
+    if to.starts_with(&from) {
+        return Err(ApiError::validation("Cannot move a directory into itself"));
+    }
+    let replace = std::fs::symlink_metadata(&to).is_ok();
+    if replace && !body.overwrite {
+        return Err(
+            ApiError::conflict(format!("{} already exists", body.to)).with_detail("path", &body.to)
+        );
+    }
+
+    let (source, target) = (from.clone(), to.clone());
+    tokio::task::spawn_blocking(move || {
+        if replace {
+            replace_path(&source, &target)
+        } else {
+            move_path(&source, &target)
+        }
+    })
+        .await
+        .map_err(|e| ApiError::internal(format!("Move task failed: {}", e)))?
+        .map_err(|e| ApiError::internal(format!("Failed to move: {}", e)))?;
+
+    Ok(HttpResponse::Ok().json(file_entry(&to, &base_dir)))
+}
+
+/// A file or directory going into a zip download.
+struct ArchiveItem {
+    path: PathBuf,
+    /// Path inside the archive, starting with the archived directory's name.
+    name: String,
+    is_dir: bool,
+    size: u64,
+    modified: DateTime<Utc>,
+}
+
+/// Everything under `dir`, directories before their contents. Symlinks are followed
+/// unless they lead outside `base`; unreadable entries and special files are skipped.
+fn archive_items(dir: &Path, base: &Path, root_name: &str) -> Vec<ArchiveItem> {
+    let walker = walkdir::WalkDir::new(dir)
+        .follow_links(true)
+        .sort_by_file_name()
+        .into_iter()
+        .filter_entry(|e| {
+            !e.path_is_symlink()
+                || e.path()
+                    .canonicalize()
+                    .is_ok_and(|target| target.starts_with(base))
+        });
+
+    let mut items = Vec::new();
+    for entry in walker {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                tracing::debug!("Skipping archive entry: {}", e);
+                continue;
+            }
+        };
+        let file_type = entry.file_type();
+        if !file_type.is_dir() && !file_type.is_file() {
+            continue;
+        }
+        let Ok(metadata) = entry.metadata() else {
+            continue;
+        };
+        let relative = entry.path().strip_prefix(dir).unwrap_or(entry.path());
+        let mut name = root_name.to_string();
+        for part in relative.components() {
+            name.push('/');
+            name.push_str(&part.as_os_str().to_string_lossy());
+        }
+        items.push(ArchiveItem {
+            path: entry.path().to_path_buf(),
+            name,
+            is_dir: file_type.is_dir(),
+            size: if file_type.is_file() {
+                metadata.len()
+            } else {
+                0
+            },
+            modified: metadata
+                .modified()
+                .map(DateTime::<Utc>::from)
+                .unwrap_or_else(|_| Utc::now()),
+        });
+    }
+    items
+}
+
+/// Hands what is written to the response body, a chunk at a time.
+struct ChannelWriter {
+    tx: mpsc::Sender<std::io::Result<Bytes>>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.tx
+            .blocking_send(Ok(Bytes::copy_from_slice(buf)))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "client went away"))?;
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+fn write_archive(items: Vec<ArchiveItem>, out: ChannelWriter) -> std::io::Result<()> {
+    let mut zip = ZipWriter::new(std::io::BufWriter::with_capacity(64 * 1024, out));
+    for item in items {
+        if item.is_dir {
+            zip.add_dir(&item.name, item.modified)?;
+            continue;
+        }
+        // Removed or made unreadable since the walk
+        let file = match std::fs::File::open(&item.path) {
+            Ok(file) => file,
+            Err(e) => {
+                tracing::warn!("Leaving {} out of the archive: {}", item.path.display(), e);
+                continue;
+            }
+        };
+        // Capped at the listed size, so a file growing meanwhile can't push the archive
+        // past the bound checked before streaming started
+        zip.add_file(&item.name, item.modified, file.take(item.size))?;
+    }
+    zip.finish()?.flush()
+}
+
+/// GET /api/servers/{server_id}/files/archive
+///
+/// Stream a directory as a zip, built while it is sent.
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/files/archive",
+    tag = "files",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ArchiveQuery,
+    ),
+    responses(
+        (status = 200, description = "Zip of the directory, named after it", content_type = "application/zip"),
+        (status = 400, description = "Not a directory, or larger than `limits.archive_max_bytes`; `details.size` is the directory's size", body = ApiError),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+    )
+)]
+pub async fn download_archive(
+    server_id: web::Path<String>,
+    query: web::Query<ArchiveQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let base_dir = get_base_dir(&server_id, &registry).await?;
+    let relative = query.path.as_deref().unwrap_or("");
+    let dir = safe_resolve(&base_dir, relative).map_err(ApiError::path_forbidden)?;
+    if !dir.is_dir() {
+        return Err(ApiError::validation("Path is not a directory"));
+    }
+
+    let root_name = dir
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("files")
+        .to_string();
+    let base = PathBuf::from(&base_dir)
+        .canonicalize()
+        .unwrap_or_else(|_| PathBuf::from(&base_dir));
+    let name = root_name.clone();
+    let items = tokio::task::spawn_blocking(move || archive_items(&dir, &base, &name))
+        .await
+        .map_err(|e| ApiError::internal(format!("Listing the directory failed: {}", e)))?;
+
+    let size: u64 = items.iter().map(|i| i.size).sum();
+    let max = config.limits.archive_max_bytes;
+    if size > max {
+        return Err(ApiError::validation(format!(
+            "Directory is {} bytes, over the {} byte archive limit",
+            size, max
+        ))
+        .with_detail("size", size)
+        .with_detail("maxBytes", max));
+    }
+    // The writer has no ZIP64, so refuse here rather than fail partway through the body
+    let bound = zipstream::archive_bound(
+        items
+            .iter()
+            .map(|i| (i.name.as_str(), (!i.is_dir).then_some(i.size))),
+    );
+    if items.len() > zipstream::MAX_ENTRIES || bound > zipstream::MAX_ARCHIVE_BYTES {
+        return Err(ApiError::validation(format!(
+            "Directory is too large for a zip download ({} entries, up to {} bytes; \
+             the limit is {} entries and {} bytes)",
+            items.len(),
+            bound,
+            zipstream::MAX_ENTRIES,
+            zipstream::MAX_ARCHIVE_BYTES
+        ))
+        .with_detail("entries", items.len())
+        .with_detail("maxBytes", zipstream::MAX_ARCHIVE_BYTES));
+    }
+
+    let (tx, mut rx) = mpsc::channel(8);
+    let errors = tx.clone();
+    let label = relative.to_string();
+    tokio::task::spawn_blocking(move || {
+        if let Err(e) = write_archive(items, ChannelWriter { tx }) {
+            if e.kind() != std::io::ErrorKind::BrokenPipe {
+                tracing::warn!("Zip download of '{}' failed: {}", label, e);
+                // Ends the response early, so the client sees a truncated download
+                let _ = errors.blocking_send(Err(e));
+            }
+        }
+    });
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header(attachment(&format!("{}.zip", root_name)))
+        .streaming(futures_util::stream::poll_fn(move |cx| rx.poll_recv(cx))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scratch directory removed when dropped.
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("fm-test-{}", Uuid::new_v4().simple()));
+            std::fs::create_dir(&dir).unwrap();
+            Self(dir.canonicalize().unwrap())
+        }
+
+        fn base(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn attachment_names_are_escaped() {
+        assert_eq!(
+            attachment("server files.zip").to_string(),
+            "attachment; filename=\"server files.zip\""
+        );
+        let header = attachment("a\"b\r\nX-Evil: 1 карта.zip").to_string();
+        assert!(!header.contains('\r') && !header.contains('\n'), "{header}");
+        assert!(header.starts_with("attachment; filename=\"a_b__X-Evil: 1 _____.zip\""));
+        assert!(header.contains("filename*=UTF-8''"), "{header}");
+    }
+
+    #[test]
+    fn entries_resolve_to_the_symlink_itself() {
+        let scratch = Scratch::new();
+        std::fs::create_dir(scratch.0.join("oxide")).unwrap();
+        std::os::unix::fs::symlink(scratch.0.join("oxide"), scratch.0.join("link")).unwrap();
+
+        assert_eq!(
+            safe_resolve_entry(scratch.base(), "/link/").unwrap(),
+            scratch.0.join("link")
+        );
+        assert_eq!(
+            safe_resolve_entry(scratch.base(), "link/plugins").unwrap(),
+            scratch.0.join("oxide/plugins")
+        );
+        assert!(safe_resolve_entry(scratch.base(), "").is_err());
+        assert!(safe_resolve_entry(scratch.base(), "../outside").is_err());
+        assert!(safe_resolve_entry(scratch.base(), "missing/file").is_err());
+    }
+
+    #[test]
+    fn replacing_moves_a_symlink_as_a_link() {
+        let scratch = Scratch::new();
+        std::fs::create_dir(scratch.0.join("oxide")).unwrap();
+        std::fs::write(scratch.0.join("oxide/config.json"), "{}").unwrap();
+        std::os::unix::fs::symlink("oxide", scratch.0.join("link")).unwrap();
+        std::fs::create_dir(scratch.0.join("old")).unwrap();
+        std::fs::write(scratch.0.join("old/file"), "old").unwrap();
+
+        replace_path(&scratch.0.join("link"), &scratch.0.join("old")).unwrap();
+
+        let moved = std::fs::symlink_metadata(scratch.0.join("old")).unwrap();
+        assert!(moved.file_type().is_symlink());
+        assert!(scratch.0.join("oxide/config.json").exists());
+        let names: Vec<_> = std::fs::read_dir(&scratch.0)
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(names.len(), 2, "leftovers: {names:?}");
+    }
+
+    #[test]
+    fn a_failed_replace_keeps_the_old_target() {
+        let scratch = Scratch::new();
+        std::fs::create_dir(scratch.0.join("old")).unwrap();
+        std::fs::write(scratch.0.join("old/file"), "old").unwrap();
+
+        assert!(replace_path(&scratch.0.join("missing"), &scratch.0.join("old")).is_err());
+
+        assert_eq!(
+            std::fs::read_to_string(scratch.0.join("old/file")).unwrap(),
+            "old"
+        );
+        assert_eq!(std::fs::read_dir(&scratch.0).unwrap().count(), 1);
     }
 }
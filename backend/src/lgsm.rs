@@ -1,37 +1,170 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
-use tokio::process::Command;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, MutexGuard};
+use utoipa::ToSchema;
 
+use crate::apierror::ApiError;
+use crate::auth::Claims;
+use crate::config::{AppConfig, GameServerConfig, ResourceLimits};
+use crate::events::PanelEvent;
+use crate::map::MapImageCache;
 use crate::monitor::SystemMonitor;
-use crate::registry::ServerRegistry;
+use crate::pagination::{self, ListQuery, SortKey};
+use crate::provisioner;
+use crate::registry::{DesiredState, Maintenance, ProvisioningStatus, ServerRegistry};
+use crate::resources::{self, LimitMechanism};
+use crate::validation::{self, ValidationErrors};
+use crate::wipes::{WipeRecord, WipeType};
 
 /// Mutex to prevent concurrent LinuxGSM operations per server.
 pub struct LgsmLock {
     pub lock: Mutex<()>,
+    /// The action currently holding the lock, if any.
+    holder: std::sync::Mutex<Option<LgsmHolder>>,
+    /// Callers blocked in `acquire`.
+    waiters: AtomicUsize,
+}
+
+/// Description of the LGSM action currently running on a server.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LgsmHolder {
+    pub action: String,
+    pub started_at: DateTime<Utc>,
+    /// Panel user, "scheduler (<job>)" or "autostart"; null when not known.
+    pub initiated_by: Option<String>,
+}
+
+/// Held while an LGSM action runs; clears the holder info on drop.
+pub struct LgsmGuard<'a> {
+    _guard: MutexGuard<'a, ()>,
+    lock: &'a LgsmLock,
+}
+
+impl Drop for LgsmGuard<'_> {
+    fn drop(&mut self) {
+        if let Ok(mut holder) = self.lock.holder.lock() {
+            *holder = None;
+        }
+    }
 }
 
 impl LgsmLock {
     pub fn new() -> Self {
         Self {
             lock: Mutex::new(()),
+            holder: std::sync::Mutex::new(None),
+            waiters: AtomicUsize::new(0),
         }
     }
+
+    /// Wait for the lock and record `action`, started by `initiated_by`, as the holder.
+    pub async fn acquire(&self, action: &str, initiated_by: Option<&str>) -> LgsmGuard<'_> {
+        self.waiters.fetch_add(1, Ordering::SeqCst);
+        let guard = self.lock.lock().await;
+        self.waiters.fetch_sub(1, Ordering::SeqCst);
+        self.hold(guard, action, initiated_by)
+    }
+
+    /// Take the lock only if it is free and nobody is waiting for it.
+    pub fn try_acquire(&self, action: &str, initiated_by: Option<&str>) -> Option<LgsmGuard<'_>> {
+        if self.waiters() > 0 {
+            return None;
+        }
+        let guard = self.lock.try_lock().ok()?;
+        Some(self.hold(guard, action, initiated_by))
+    }
+
+    fn hold<'a>(
+        &'a self,
+        guard: MutexGuard<'a, ()>,
+        action: &str,
+        initiated_by: Option<&str>,
+    ) -> LgsmGuard<'a> {
+        if let Ok(mut holder) = self.holder.lock() {
+            *holder = Some(LgsmHolder {
+                action: action.to_string(),
+                started_at: Utc::now(),
+                initiated_by: initiated_by.map(str::to_string),
+            });
+        }
+        LgsmGuard {
+            _guard: guard,
+            lock: self,
+        }
+    }
+
+    /// Number of callers waiting for the lock.
+    pub fn waiters(&self) -> usize {
+        self.waiters.load(Ordering::SeqCst)
+    }
+
+    /// The action currently holding the lock, if any.
+    pub fn current(&self) -> Option<LgsmHolder> {
+        self.holder.lock().ok().and_then(|h| h.clone())
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct CommandResult {
     success: bool,
     output: String,
     action: String,
+    /// Panel user who ran it.
+    initiated_by: Option<String>,
+    /// When it got the server's LGSM lock and started.
+    started_at: DateTime<Utc>,
+}
+
+/// Most actions kept per server; older ones are dropped as new ones are recorded.
+pub const MAX_ACTIONS_PER_SERVER: usize = 200;
+
+/// One finished LGSM action, wipe or save, in the server's action history.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionRecord {
+    pub action: String,
+    /// Panel user, "scheduler (<job>)" or "autostart"; null when not known.
+    pub initiated_by: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub success: bool,
+    /// Request that started it, to search the logs by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+}
+
+impl ActionRecord {
+    /// Record of `action`, finishing now.
+    pub fn finished(
+        action: &str,
+        initiated_by: Option<String>,
+        started_at: DateTime<Utc>,
+        success: bool,
+    ) -> Self {
+        Self {
+            action: action.to_string(),
+            initiated_by,
+            started_at,
+            finished_at: Utc::now(),
+            success,
+            request_id: crate::requestid::current(),
+        }
+    }
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct ServerStatus {
     online: bool,
+    /// online, offline, stopped (on purpose) or maintenance
+    state: String,
+    desired_state: DesiredState,
+    maintenance: Option<Maintenance>,
     players: u32,
     max_players: u32,
     fps: f64,
@@ -39,28 +172,63 @@ struct ServerStatus {
     map: String,
     entities: u64,
     uptime: u64,
-    cpu_percent: f32,
-    mem_used: u64,
+    /// Usage of this server's RustDedicated process(es); null when none is running.
+    /// Percent of one core.
+    cpu_percent: Option<f32>,
+    mem_used: Option<u64>,
+    /// Host memory; `mem_percent` is the process's share of it.
     mem_total: u64,
-    mem_percent: f32,
+    mem_percent: Option<f32>,
+    threads: Option<u32>,
+    /// Host-wide: the disk the server's files live on.
     disk_used: u64,
     disk_total: u64,
     disk_percent: f32,
+    resource_limits: ResourceLimits,
+    /// How the limits are enforced; null when they can't be on this host.
+    limit_mechanism: Option<LimitMechanism>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct WipeRequest {
     #[serde(rename = "type")]
     pub wipe_type: String,
+    /// New map seed; the current one is kept when absent.
     pub seed: Option<String>,
+    /// New world size; the current one is kept when absent.
+    #[serde(rename = "worldSize")]
+    pub world_size: Option<u32>,
 }
 
-/// Run a LinuxGSM command and capture output.
-async fn run_lgsm_command(script: &str, action: &str) -> anyhow::Result<String> {
-    tracing::info!("Running LGSM command: {} {}", script, action);
+/// Run a LinuxGSM command and capture output. Actions that start the server run inside
+/// its resource limits, when it has some.
+pub async fn run_lgsm_command(config: &GameServerConfig, action: &str) -> anyhow::Result<String> {
+    tracing::info!(
+        "Running LGSM command: {} {}",
+        config.paths.lgsm_script,
+        action
+    );
 
-    let output = Command::new(script).arg(action).output().await?;
+    let output = resources::lgsm_command(config, action)
+        .kill_on_drop(true)
+        .output()
+        .await?;
 
+    command_output(action, output)
+}
+
+/// Stop the server with LinuxGSM as the game user that installed it. Fails when the
+/// script exits non-zero.
+pub async fn stop_as_game_user(config: &GameServerConfig) -> anyhow::Result<String> {
+    tracing::info!("Running LGSM command: {} stop", config.paths.lgsm_script);
+    let cmd = format!("cd '{}' && ./rustserver stop", config.paths.base_dir);
+    let output = provisioner::output_as_game_user(&cmd).await?;
+    command_output("stop", output)
+}
+
+/// Stdout and stderr of an LGSM command, or an error carrying them when it exited
+/// non-zero.
+fn command_output(action: &str, output: std::process::Output) -> anyhow::Result<String> {
     let stdout = String::from_utf8_lossy(&output.stdout).to_string();
     let stderr = String::from_utf8_lossy(&output.stderr).to_string();
 
@@ -76,189 +244,595 @@ async fn run_lgsm_command(script: &str, action: &str) -> anyhow::Result<String>
             action,
             output.status
         );
+        anyhow::bail!(
+            "LGSM {} exited with {}\n{}",
+            action,
+            output.status,
+            combined
+        );
     }
 
     Ok(combined)
 }
 
+/// PIDs of RustDedicated processes running out of `base_dir` (matched on exe path or cwd).
+pub fn find_server_pids(base_dir: &str) -> Vec<sysinfo::Pid> {
+    use sysinfo::{ProcessRefreshKind, ProcessesToUpdate, System, UpdateKind};
+
+    let mut sys = System::new();
+    sys.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing()
+            .with_exe(UpdateKind::Always)
+            .with_cwd(UpdateKind::Always),
+    );
+    let base = std::path::Path::new(base_dir);
+    sys.processes()
+        .iter()
+        // Threads are listed as processes too, often under the same name
+        .filter(|(_, p)| p.thread_kind().is_none())
+        .filter(|(_, p)| p.name().to_string_lossy().starts_with("RustDedicated"))
+        .filter(|(_, p)| {
+            p.exe().map(|e| e.starts_with(base)).unwrap_or(false)
+                || p.cwd().map(|c| c.starts_with(base)).unwrap_or(false)
+        })
+        .map(|(pid, _)| *pid)
+        .collect()
+}
+
+/// SIGKILL every RustDedicated process running out of `base_dir`.
+/// Returns the number of processes signalled.
+pub fn kill_server_processes(base_dir: &str) -> usize {
+    use sysinfo::{ProcessesToUpdate, System};
+
+    let pids = find_server_pids(base_dir);
+    if pids.is_empty() {
+        return 0;
+    }
+    let mut sys = System::new();
+    sys.refresh_processes(ProcessesToUpdate::Some(&pids), true);
+    let mut killed = 0;
+    for pid in &pids {
+        if let Some(process) = sys.process(*pid) {
+            tracing::warn!("Sending SIGKILL to RustDedicated (pid {})", pid);
+            if process.kill() {
+                killed += 1;
+            }
+        }
+    }
+    killed
+}
+
+/// `initiated_by` of the starts done by `autostart_servers`.
+const AUTOSTART: &str = "autostart";
+
+/// Launch every Ready server flagged `auto_start` that is not already running,
+/// waiting `delay_secs` between launches so the servers don't cold-start at once.
+pub async fn autostart_servers(registry: Arc<ServerRegistry>, delay_secs: u64) {
+    let candidates: Vec<_> = registry
+        .all_definitions()
+        .await
+        .into_iter()
+        .filter(|d| d.auto_start && d.provisioning_status == ProvisioningStatus::Ready)
+        .filter(|d| {
+            let down = d.expected_down();
+            if down {
+                tracing::info!(
+                    "Auto-start: '{}' is stopped on purpose or in maintenance",
+                    d.id
+                );
+            }
+            !down
+        })
+        .collect();
+    if candidates.is_empty() {
+        return;
+    }
+    tracing::info!("Auto-start: checking {} server(s)", candidates.len());
+
+    let mut launched = 0;
+    for def in candidates {
+        let (config, lock) = match (
+            registry.get_config(&def.id).await,
+            registry.get_lgsm_lock(&def.id).await,
+        ) {
+            (Some(c), Some(l)) => (c, l),
+            _ => continue,
+        };
+        if !find_server_pids(&config.paths.base_dir).is_empty() {
+            tracing::info!("Auto-start: '{}' is already running", def.id);
+            continue;
+        }
+
+        if launched > 0 {
+            tokio::time::sleep(std::time::Duration::from_secs(delay_secs)).await;
+        }
+        launched += 1;
+
+        let _guard = lock.acquire("start", Some(AUTOSTART)).await;
+        let started_at = Utc::now();
+        let result = run_lgsm_command(&config, "start").await;
+        match result {
+            Ok(_) => tracing::info!("Auto-start: started '{}'", def.id),
+            Err(ref e) => tracing::error!("Auto-start: failed to start '{}': {}", def.id, e),
+        }
+        let record = ActionRecord::finished(
+            "start",
+            Some(AUTOSTART.to_string()),
+            started_at,
+            result.is_ok(),
+        );
+        registry.record_action(&def.id, record);
+    }
+}
+
+/// The desired state an action leaves the server in, for the actions that decide it.
+pub fn desired_state_after(action: &str) -> Option<DesiredState> {
+    match action {
+        "start" | "restart" => Some(DesiredState::Running),
+        "stop" => Some(DesiredState::Stopped),
+        _ => None,
+    }
+}
+
+/// LinuxGSM's check-update prints "Update available" or "No update available".
+pub fn update_available(output: &str) -> bool {
+    let output = output.to_lowercase();
+    output.contains("update available") && !output.contains("no update available")
+}
+
 async fn lgsm_action(
+    req: &HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
     action: &str,
-) -> HttpResponse {
-    let config = match registry.get_config(&server_id).await {
-        Some(c) => c,
-        None => {
-            return HttpResponse::NotFound()
-                .json(serde_json::json!({"error": "Server not found"}))
-        }
-    };
-    let lgsm_lock = match registry.get_lgsm_lock(&server_id).await {
-        Some(l) => l,
-        None => {
-            return HttpResponse::NotFound()
-                .json(serde_json::json!({"error": "Server runtime not found"}))
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    let config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let lgsm_lock = registry
+        .get_lgsm_lock(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_ready)?;
 
-    let _guard = lgsm_lock.lock.lock().await;
-    match run_lgsm_command(&config.paths.lgsm_script, action).await {
-        Ok(output) => HttpResponse::Ok().json(CommandResult {
+    let user = initiator(req);
+    let _guard = lgsm_lock.acquire(action, user.as_deref()).await;
+    // Before running, so the collector already expects a stopping server to go quiet
+    if let Some(state) = desired_state_after(action) {
+        registry.set_desired_state(&server_id, state).await;
+    }
+    let started_at = Utc::now();
+    let result = run_lgsm_command(&config, action).await;
+    registry.events.publish(PanelEvent::LgsmAction {
+        server_id: server_id.to_string(),
+        action: action.to_string(),
+        success: result.is_ok(),
+        initiated_by: user.clone(),
+    });
+    let record = ActionRecord::finished(action, user.clone(), started_at, result.is_ok());
+    registry.record_action(&server_id, record);
+    if result.is_ok() && matches!(action, "full-wipe" | "map-wipe") {
+        let wipe_type = if action == "full-wipe" {
+            WipeType::Full
+        } else {
+            WipeType::Map
+        };
+        // LinuxGSM wipes keep the configured seed
+        let seed = current_seed(&registry, &server_id).await;
+        let wipe = WipeRecord::now(Some(wipe_type), seed, user.clone()).with_previous_seed(seed);
+        registry.record_wipe(&server_id, wipe).await;
+    }
+    if action == "check-update" && matches!(result, Ok(ref output) if update_available(output)) {
+        registry.events.publish(PanelEvent::UpdateAvailable {
+            server_id: server_id.to_string(),
+        });
+    }
+    match result {
+        Ok(output) => Ok(HttpResponse::Ok().json(CommandResult {
             success: true,
             output,
             action: action.to_string(),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(CommandResult {
+            initiated_by: user,
+            started_at,
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(CommandResult {
             success: false,
             output: e.to_string(),
             action: action.to_string(),
-        }),
+            initiated_by: user,
+            started_at,
+        })),
     }
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/start",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `start`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_start(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "start").await
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    // Starting a server implies we want to see it again
+    let paused = registry
+        .get_definition(&server_id)
+        .await
+        .map(|d| d.monitoring_paused)
+        .unwrap_or(false);
+    if paused {
+        tracing::info!("Resuming monitoring for '{}' on start", server_id);
+        registry.resume_monitoring(&server_id, &config.monitor).await;
+    }
+    lgsm_action(&req, server_id, registry, "start").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/stop",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `stop`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_stop(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "stop").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "stop").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/restart",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `restart`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_restart(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "restart").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "restart").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/update",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `update`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_update(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "update").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "update").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/backup",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `backup`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_backup(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "backup").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "backup").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/force-update",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `force-update`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_force_update(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "force-update").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "force-update").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/validate",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `validate`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_validate(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "validate").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "validate").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/check-update",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `check-update`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_check_update(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "check-update").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "check-update").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/monitor-check",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `monitor`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded, or stopped on purpose or in maintenance", body = ApiError),
+    )
+)]
 pub async fn server_monitor_check(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "monitor").await
+) -> Result<HttpResponse, ApiError> {
+    // LinuxGSM's monitor starts a server it finds down
+    let expected_down = registry
+        .get_definition(&server_id)
+        .await
+        .is_some_and(|d| d.expected_down());
+    if expected_down {
+        return Err(ApiError::conflict(
+            "Server is stopped on purpose or in maintenance; start it instead",
+        ));
+    }
+    lgsm_action(&req, server_id, registry, "monitor").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/details",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `details`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_details(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "details").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "details").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/update-lgsm",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `update-lgsm`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_update_lgsm(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "update-lgsm").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "update-lgsm").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/full-wipe",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `full-wipe`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_full_wipe(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "full-wipe").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "full-wipe").await
 }
 
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/map-wipe",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Output of LinuxGSM `map-wipe`", body = CommandResult),
+        (status = 500, description = "LinuxGSM failed", body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+    )
+)]
 pub async fn server_map_wipe(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    lgsm_action(server_id, registry, "map-wipe").await
+) -> Result<HttpResponse, ApiError> {
+    lgsm_action(&req, server_id, registry, "map-wipe").await
 }
 
 /// POST /api/servers/{server_id}/save - RCON server.save
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/save",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = CommandResult),
+        (status = 500, body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
 pub async fn server_save(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound()
-                .json(serde_json::json!({"error": "Server not found"}))
-        }
-    };
-    match rcon.save().await {
-        Ok(output) => HttpResponse::Ok().json(CommandResult {
+) -> Result<HttpResponse, ApiError> {
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let user = initiator(&req);
+    let started_at = Utc::now();
+    let result = rcon.save().await;
+    let record = ActionRecord::finished("save", user.clone(), started_at, result.is_ok());
+    registry.record_action(&server_id, record);
+    match result {
+        Ok(output) => Ok(HttpResponse::Ok().json(CommandResult {
             success: true,
             output,
             action: "save".to_string(),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(CommandResult {
+            initiated_by: user,
+            started_at,
+        })),
+        Err(e) => Ok(HttpResponse::InternalServerError().json(CommandResult {
             success: false,
             output: e.to_string(),
             action: "save".to_string(),
-        }),
+            initiated_by: user,
+            started_at,
+        })),
     }
 }
 
 /// POST /api/servers/{server_id}/wipe
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/wipe",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = WipeRequest,
+    responses(
+        (status = 200, body = CommandResult),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server runtime not loaded", body = ApiError),
+        (status = 422, description = "Invalid seed or world size", body = ApiError),
+    )
+)]
 pub async fn server_wipe(
+    req: HttpRequest,
     server_id: web::Path<String>,
     body: web::Json<WipeRequest>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let config = match registry.get_config(&server_id).await {
-        Some(c) => c,
-        None => {
-            return HttpResponse::NotFound()
-                .json(serde_json::json!({"error": "Server not found"}))
+    map_cache: web::Data<Arc<MapImageCache>>,
+) -> Result<HttpResponse, ApiError> {
+    let config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+
+    // Checked up front: a bad value must not leave the server stopped and half wiped
+    let mut invalid = ValidationErrors::new();
+    let mut new_seed = None;
+    if let Some(seed) = body.seed.as_deref() {
+        let parsed = seed
+            .trim()
+            .parse::<u32>()
+            .map_err(|_| format!("Must be between 1 and {}", validation::MAX_SEED))
+            .and_then(|seed| validation::validate_seed(seed).map(|_| seed));
+        match parsed {
+            Ok(seed) => new_seed = Some(seed),
+            Err(e) => invalid.add("seed", e),
         }
-    };
-    let lgsm_lock = match registry.get_lgsm_lock(&server_id).await {
-        Some(l) => l,
-        None => {
-            return HttpResponse::NotFound()
-                .json(serde_json::json!({"error": "Server runtime not found"}))
+    }
+    if let Some(world_size) = body.world_size {
+        if let Err(e) = validation::validate_world_size(world_size) {
+            invalid.add("worldSize", e);
         }
-    };
+    }
+    if !invalid.is_empty() {
+        return Err(invalid.into());
+    }
+
+    let lgsm_lock = registry
+        .get_lgsm_lock(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_ready)?;
 
-    let _guard = lgsm_lock.lock.lock().await;
+    let user = initiator(&req);
+    let _guard = lgsm_lock.acquire("wipe", user.as_deref()).await;
+    let started_at = Utc::now();
 
     let server_dir = format!("{}/server/rustserver", config.paths.server_files);
 
-    if let Err(e) = run_lgsm_command(&config.paths.lgsm_script, "stop").await {
+    if let Err(e) = run_lgsm_command(&config, "stop").await {
         tracing::warn!("Failed to stop server before wipe: {}", e);
     }
 
@@ -287,13 +861,39 @@ pub async fn server_wipe(
         }
     }
 
-    if let Some(ref seed) = body.seed {
-        if let Err(e) = update_server_seed(&config.paths.server_cfg, seed) {
-            errors.push(format!("Failed to update seed: {}", e));
+    let previous = registry
+        .get_definition(&server_id)
+        .await
+        .map(|def| (def.seed, def.world_size));
+    let mut convars = Vec::new();
+    if let Some(seed) = new_seed {
+        convars.push(("server.seed", seed.to_string()));
+    }
+    if let Some(world_size) = body.world_size {
+        convars.push(("server.worldsize", world_size.to_string()));
+    }
+    let mut map_changed = false;
+    if !convars.is_empty() {
+        match crate::servercfg::set_convars(&config.paths.server_cfg, &convars) {
+            Ok(()) => map_changed = true,
+            Err(e) => errors.push(format!("Failed to update server.cfg: {}", e)),
+        }
+    }
+    // Keep the definition in sync and drop the old map image
+    if map_changed {
+        registry
+            .update_definition(&server_id, |def| {
+                def.seed = new_seed.unwrap_or(def.seed);
+                def.world_size = body.world_size.unwrap_or(def.world_size);
+            })
+            .await;
+        registry.persist().await;
+        if let Some((seed, world_size)) = previous {
+            map_cache.invalidate(world_size, seed).await;
         }
     }
 
-    let start_output = run_lgsm_command(&config.paths.lgsm_script, "start")
+    let start_output = run_lgsm_command(&config, "start")
         .await
         .unwrap_or_else(|e| format!("Failed to start server: {}", e));
 
@@ -312,48 +912,111 @@ pub async fn server_wipe(
         },
         start_output
     );
+    if !deleted_files.is_empty() {
+        let wipe_type = if body.wipe_type == "full" {
+            WipeType::Full
+        } else {
+            WipeType::Map
+        };
+        let previous_seed = previous.map(|(seed, _)| seed).filter(|&seed| seed != 0);
+        let seed = match new_seed.filter(|_| map_changed) {
+            Some(seed) => Some(seed),
+            None => previous_seed,
+        };
+        let wipe =
+            WipeRecord::now(Some(wipe_type), seed, user.clone()).with_previous_seed(previous_seed);
+        registry.record_wipe(&server_id, wipe).await;
+    }
+    let record = ActionRecord::finished("wipe", user.clone(), started_at, errors.is_empty());
+    registry.record_action(&server_id, record);
 
-    HttpResponse::Ok().json(CommandResult {
+    Ok(HttpResponse::Ok().json(CommandResult {
         success: errors.is_empty(),
         output,
         action: "wipe".to_string(),
-    })
+        initiated_by: user,
+        started_at,
+    }))
 }
 
-fn update_server_seed(cfg_path: &str, seed: &str) -> anyhow::Result<()> {
-    let content = std::fs::read_to_string(cfg_path)?;
-    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+const ACTION_SORTS: &[SortKey<ActionRecord>] = &[
+    ("startedAt", |a, b| a.started_at.cmp(&b.started_at)),
+    ("finishedAt", |a, b| a.finished_at.cmp(&b.finished_at)),
+    ("action", |a, b| a.action.cmp(&b.action)),
+];
 
-    let mut found = false;
-    for line in &mut lines {
-        if line.starts_with("server.seed") {
-            *line = format!("server.seed \"{}\"", seed);
-            found = true;
-            break;
-        }
-    }
-    if !found {
-        lines.push(format!("server.seed \"{}\"", seed));
+/// GET /api/servers/{server_id}/actions
+///
+/// LGSM actions, wipes and saves on the server, newest first, with who started them.
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/actions",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id"), ListQuery),
+    responses(
+        (status = 200, body = Vec<ActionRecord>),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 422, description = "Unknown sort key or field", body = ApiError),
+        (status = 500, description = "Action history couldn't be read", body = ApiError),
+    )
+)]
+pub async fn list_actions(
+    server_id: web::Path<String>,
+    list: web::Query<ListQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    if registry.get_definition(&server_id).await.is_none() {
+        return Err(ApiError::server_not_found());
     }
+    let actions = registry
+        .actions(&server_id)
+        .map_err(|e| ApiError::internal(format!("Failed to read action history: {}", e)))?;
+    Ok(HttpResponse::Ok().json(pagination::apply(&list, actions, ACTION_SORTS)?))
+}
+
+/// The panel user behind a request, for the action and wipe history.
+pub fn initiator(req: &HttpRequest) -> Option<String> {
+    req.extensions().get::<Claims>().map(|c| c.sub.clone())
+}
 
-    std::fs::write(cfg_path, lines.join("\n"))?;
-    Ok(())
+/// The seed the server is configured with; 0 means the panel doesn't know it.
+async fn current_seed(registry: &ServerRegistry, server_id: &str) -> Option<u32> {
+    registry
+        .get_definition(server_id)
+        .await
+        .map(|def| def.seed)
+        .filter(|&seed| seed != 0)
 }
 
 /// GET /api/servers/{server_id}/status
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/status",
+    tag = "lgsm",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = ServerStatus),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
 pub async fn server_status(
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
     sys_monitor: web::Data<Arc<SystemMonitor>>,
-) -> HttpResponse {
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound()
-                .json(serde_json::json!({"error": "Server not found"}))
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
+    let config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
     let sys_history = sys_monitor.history.read().await;
     let sys = sys_history.latest().cloned();
     drop(sys_history);
@@ -366,6 +1029,26 @@ pub async fn server_status(
         None
     };
 
+    // The collector samples the process with each snapshot; without one, sample it here
+    let sampled = game.as_ref().and_then(|g| {
+        Some(resources::ProcessUsage {
+            cpu_percent: g.cpu_percent?,
+            memory_bytes: g.memory_bytes?,
+            threads: g.threads?,
+        })
+    });
+    let usage = match sampled {
+        Some(usage) => Some(usage),
+        None => {
+            let base_dir = config.paths.base_dir.clone();
+            tokio::task::spawn_blocking(move || resources::process_usage(&base_dir))
+                .await
+                .ok()
+                .flatten()
+        }
+    };
+    let mem_total = sys.as_ref().map(|s| s.mem_total).unwrap_or(0);
+
     let (online, players, max_players, fps, hostname, map, entities, uptime) =
         if let Some(ref g) = game {
             (
@@ -396,6 +1079,9 @@ pub async fn server_status(
 
     let status = ServerStatus {
         online,
+        state: def.state(online).to_string(),
+        desired_state: def.desired_state,
+        maintenance: def.active_maintenance().cloned(),
         players,
         max_players,
         fps,
@@ -403,14 +1089,19 @@ pub async fn server_status(
         map,
         entities,
         uptime,
-        cpu_percent: sys.as_ref().map(|s| s.cpu_percent).unwrap_or(0.0),
-        mem_used: sys.as_ref().map(|s| s.mem_used).unwrap_or(0),
-        mem_total: sys.as_ref().map(|s| s.mem_total).unwrap_or(0),
-        mem_percent: sys.as_ref().map(|s| s.mem_percent).unwrap_or(0.0),
+        cpu_percent: usage.map(|u| u.cpu_percent),
+        mem_used: usage.map(|u| u.memory_bytes),
+        mem_total,
+        mem_percent: usage
+            .filter(|_| mem_total > 0)
+            .map(|u| u.memory_bytes as f32 / mem_total as f32 * 100.0),
+        threads: usage.map(|u| u.threads),
         disk_used: sys.as_ref().map(|s| s.disk_used).unwrap_or(0),
         disk_total: sys.as_ref().map(|s| s.disk_total).unwrap_or(0),
         disk_percent: sys.as_ref().map(|s| s.disk_percent).unwrap_or(0.0),
+        resource_limits: config.resource_limits,
+        limit_mechanism: resources::mechanism(),
     };
 
-    HttpResponse::Ok().json(status)
+    Ok(HttpResponse::Ok().json(status))
 }
@@ -0,0 +1,126 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::AuthConfig;
+
+/// Forget idle clients at most this often.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Failed logins from one client address.
+struct Attempts {
+    /// Failures inside the current window.
+    failures: VecDeque<Instant>,
+    /// Times the client ran into the limit; every lockout after the first is longer.
+    strikes: u32,
+    blocked_until: Option<Instant>,
+    last_failure: Instant,
+}
+
+/// Counts failed logins per client address and locks out addresses that keep failing.
+/// In memory: a restart forgets every count.
+pub struct LoginGuard {
+    max_failures: u32,
+    window: Duration,
+    lockout: Duration,
+    lockout_max: Duration,
+    state: Mutex<GuardState>,
+}
+
+struct GuardState {
+    clients: HashMap<String, Attempts>,
+    last_prune: Instant,
+}
+
+impl LoginGuard {
+    pub fn new(auth: &AuthConfig) -> Self {
+        Self {
+            max_failures: auth.login_max_failures,
+            window: Duration::from_secs(auth.login_failure_window_secs),
+            lockout: Duration::from_secs(auth.login_lockout_secs),
+            lockout_max: Duration::from_secs(auth.login_lockout_max_secs),
+            state: Mutex::new(GuardState {
+                clients: HashMap::new(),
+                last_prune: Instant::now(),
+            }),
+        }
+    }
+
+    /// How long a client that has hit the limit `strikes` times waits: the rest of the
+    /// window the first time, then the lockout, doubled for each burst after that.
+    fn block_for(&self, strikes: u32) -> Duration {
+        if strikes <= 1 {
+            return self.window;
+        }
+        let doublings = (strikes - 2).min(16);
+        self.lockout
+            .saturating_mul(1 << doublings)
+            .min(self.lockout_max.max(self.lockout))
+    }
+
+    /// Ok if `client` may try to log in, else the seconds until it may.
+    pub fn check(&self, client: &str) -> Result<(), u64> {
+        let now = Instant::now();
+        let state = self.state.lock().unwrap();
+        match state.clients.get(client).and_then(|a| a.blocked_until) {
+            Some(until) if until > now => Err(until.duration_since(now).as_secs().max(1)),
+            _ => Ok(()),
+        }
+    }
+
+    /// Count a failed login. Returns the lockout in seconds when this failure started one.
+    pub fn record_failure(&self, client: &str) -> Option<u64> {
+        if self.max_failures == 0 {
+            return None;
+        }
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        if now.duration_since(state.last_prune) >= PRUNE_INTERVAL {
+            self.prune(&mut state, now);
+        }
+
+        let attempts = state
+            .clients
+            .entry(client.to_string())
+            .or_insert_with(|| Attempts {
+                failures: VecDeque::new(),
+                strikes: 0,
+                blocked_until: None,
+                last_failure: now,
+            });
+        attempts.last_failure = now;
+        while attempts
+            .failures
+            .front()
+            .is_some_and(|at| now.duration_since(*at) >= self.window)
+        {
+            attempts.failures.pop_front();
+        }
+        attempts.failures.push_back(now);
+        if attempts.failures.len() < self.max_failures as usize {
+            return None;
+        }
+
+        attempts.failures.clear();
+        attempts.strikes += 1;
+        let block = self.block_for(attempts.strikes);
+        attempts.blocked_until = Some(now + block);
+        Some(block.as_secs())
+    }
+
+    /// A successful login clears the client's record.
+    pub fn record_success(&self, client: &str) {
+        self.state.lock().unwrap().clients.remove(client);
+    }
+
+    /// Drop clients that are not locked out and have not failed for longer than the
+    /// longest lockout; their strikes are forgiven.
+    fn prune(&self, state: &mut GuardState, now: Instant) {
+        let forget_after = self.window.max(self.lockout_max);
+        state.clients.retain(|_, attempts| {
+            attempts.blocked_until.is_some_and(|until| until > now)
+                || now.duration_since(attempts.last_failure) < forget_after
+        });
+        state.last_prune = now;
+    }
+}
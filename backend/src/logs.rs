@@ -2,19 +2,22 @@ use actix_web::{web, HttpResponse};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::io::{BufRead, BufReader, Seek, SeekFrom};
+use std::os::unix::fs::MetadataExt;
 use std::path::PathBuf;
 use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
 
+use crate::apierror::ApiError;
 use crate::config::GameServerConfig;
 use crate::registry::ServerRegistry;
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct TailQuery {
     pub file: Option<String>,
     pub lines: Option<usize>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct LogResponse {
     file: String,
@@ -22,12 +25,7 @@ struct LogResponse {
     total_lines: usize,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
-    error: String,
-}
-
-fn allowed_log_files(config: &GameServerConfig) -> HashMap<String, PathBuf> {
+pub fn allowed_log_files(config: &GameServerConfig) -> HashMap<String, PathBuf> {
     let mut map = HashMap::new();
     map.insert(
         "console".to_string(),
@@ -44,6 +42,85 @@ fn allowed_log_files(config: &GameServerConfig) -> HashMap<String, PathBuf> {
     map
 }
 
+/// Follows a log file from its end, like `tail -F`: a new inode (rotated) or a shorter
+/// file (truncated) restarts reading from the top of the new file.
+pub struct LogFollower {
+    path: PathBuf,
+    inode: Option<u64>,
+    pos: u64,
+    /// Trailing text without a newline yet.
+    partial: String,
+}
+
+/// New lines read by one `LogFollower::poll`.
+pub struct FollowedLines {
+    pub lines: Vec<String>,
+    /// Lines dropped because more than `max_lines` arrived since the last poll.
+    pub skipped: usize,
+}
+
+impl LogFollower {
+    /// Start following at the current end of `path` (which may not exist yet).
+    pub fn new(path: PathBuf) -> Self {
+        let meta = std::fs::metadata(&path).ok();
+        Self {
+            inode: meta.as_ref().map(|m| m.ino()),
+            pos: meta.map_or(0, |m| m.len()),
+            path,
+            partial: String::new(),
+        }
+    }
+
+    /// Read lines appended since the last poll, keeping at most the last `max_lines`.
+    pub fn poll(&mut self, max_lines: usize) -> std::io::Result<FollowedLines> {
+        let mut result = FollowedLines {
+            lines: Vec::new(),
+            skipped: 0,
+        };
+        let meta = match std::fs::metadata(&self.path) {
+            Ok(m) => m,
+            // Between rotation and the new file appearing
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(result),
+            Err(e) => return Err(e),
+        };
+        if self.inode != Some(meta.ino()) || meta.len() < self.pos {
+            self.inode = Some(meta.ino());
+            self.pos = 0;
+            self.partial.clear();
+        }
+        if meta.len() == self.pos {
+            return Ok(result);
+        }
+
+        let mut file = std::fs::File::open(&self.path)?;
+        file.seek(SeekFrom::Start(self.pos))?;
+        let mut reader = BufReader::new(file);
+        let mut buf = Vec::new();
+        let mut kept = std::collections::VecDeque::new();
+        loop {
+            buf.clear();
+            let read = reader.read_until(b'\n', &mut buf)?;
+            if read == 0 {
+                break;
+            }
+            self.pos += read as u64;
+            self.partial.push_str(&String::from_utf8_lossy(&buf));
+            if !self.partial.ends_with('\n') {
+                continue;
+            }
+            let line = std::mem::take(&mut self.partial);
+            let line = line.trim_end_matches('\n').trim_end_matches('\r');
+            kept.push_back(line.to_string());
+            if kept.len() > max_lines {
+                kept.pop_front();
+                result.skipped += 1;
+            }
+        }
+        result.lines = kept.into();
+        Ok(result)
+    }
+}
+
 fn tail_file(path: &PathBuf, n: usize) -> anyhow::Result<Vec<String>> {
     let file = std::fs::File::open(path)?;
     let file_size = file.metadata()?.len();
@@ -114,56 +191,60 @@ fn tail_file(path: &PathBuf, n: usize) -> anyhow::Result<Vec<String>> {
 }
 
 /// GET /api/servers/{server_id}/logs/tail
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/logs/tail",
+    tag = "logs",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        TailQuery,
+    ),
+    responses(
+        (status = 200, body = LogResponse),
+        (status = 400, description = "Unknown log file", body = ApiError),
+        (status = 404, description = "Server or log file not found", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn tail_log(
     server_id: web::Path<String>,
     query: web::Query<TailQuery>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let config = match registry.get_config(&server_id).await {
-        Some(c) => c,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    let config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     let file_alias = query.file.as_deref().unwrap_or("console");
     let num_lines = query.lines.unwrap_or(100).min(5000);
 
     let allowed = allowed_log_files(&config);
 
-    let log_path = match allowed.get(file_alias) {
-        Some(p) => p,
-        None => {
-            let available: Vec<&str> = allowed.keys().map(|k| k.as_str()).collect();
-            return HttpResponse::BadRequest().json(ErrorBody {
-                error: format!(
-                    "Unknown log file '{}'. Available: {}",
-                    file_alias,
-                    available.join(", ")
-                ),
-            });
-        }
+    let Some(log_path) = allowed.get(file_alias) else {
+        let mut available: Vec<&str> = allowed.keys().map(|k| k.as_str()).collect();
+        available.sort_unstable();
+        return Err(ApiError::validation(format!(
+            "Unknown log file '{}'. Available: {}",
+            file_alias,
+            available.join(", ")
+        ))
+        .with_detail("available", &available));
     };
 
     if !log_path.exists() {
-        return HttpResponse::NotFound().json(ErrorBody {
-            error: format!("Log file not found: {}", log_path.display()),
-        });
+        return Err(ApiError::not_found(format!(
+            "Log file not found: {}",
+            log_path.display()
+        )));
     }
 
-    match tail_file(log_path, num_lines) {
-        Ok(lines) => {
-            let total = lines.len();
-            HttpResponse::Ok().json(LogResponse {
-                file: file_alias.to_string(),
-                lines,
-                total_lines: total,
-            })
-        }
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to read log: {}", e),
-        }),
-    }
+    let lines = tail_file(log_path, num_lines)
+        .map_err(|e| ApiError::internal(format!("Failed to read log: {}", e)))?;
+    let total = lines.len();
+    Ok(HttpResponse::Ok().json(LogResponse {
+        file: file_alias.to_string(),
+        lines,
+        total_lines: total,
+    }))
 }
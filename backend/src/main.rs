@@ -1,44 +1,81 @@
+mod accesslog;
+mod activity;
+mod apierror;
+mod audit;
 mod auth;
+mod backups;
+mod bans;
+mod bundle;
+mod cli;
+mod commandpolicy;
 mod config;
+mod consolehistory;
+mod cronexpr;
+mod diagnostics;
+mod etag;
+mod events;
 mod filemanager;
 mod lgsm;
+mod logging;
+mod loginguard;
 mod logs;
 mod map;
 mod monitor;
+mod notifications;
+mod openapi;
+mod pagination;
+mod panelbackup;
 mod persistence;
 mod players;
+mod playertracker;
 mod plugins;
 mod provisioner;
+mod publicstatus;
+mod ratelimit;
 mod rcon;
 mod registry;
+mod requestid;
+mod resources;
+mod rollup;
 mod scheduler;
+mod servercfg;
 mod servers;
+mod shutdown;
+mod snippets;
+mod storage;
+mod transfer;
+mod updates;
+mod validation;
+mod webhook;
 mod websocket;
+mod wipes;
+mod zipstream;
 
 use actix_cors::Cors;
 use actix_files::Files;
-use actix_web::{web, App, HttpServer};
+use actix_web::{middleware, web, App, HttpServer};
+use clap::Parser;
 use std::collections::HashMap;
+use std::path::Path;
 use std::sync::Arc;
 
 use crate::config::AppConfig;
 use crate::map::{MapImageCache, PositionStore};
 use crate::monitor::SystemMonitor;
-use crate::registry::{
-    ServerDefinition, ServerRegistry, ServerRuntime, ServerSource, ProvisioningStatus,
-};
+use crate::persistence::{MetricsStore, ScheduleStore, ServerStore, SessionStore, SettingsStore};
+use crate::registry::{ServerRegistry, ServerRuntime, ServerSource, ProvisioningStatus};
 use crate::scheduler::Scheduler;
 
 #[actix_web::main]
 async fn main() -> anyhow::Result<()> {
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info")),
-        )
-        .init();
-
-    let config = AppConfig::load()?;
+    if let Some(command) = cli::Cli::parse().command {
+        return cli::run(command);
+    }
+
+    // The log format is part of the config, so load it under a temporary subscriber
+    let config = tracing::subscriber::with_default(logging::bootstrap(), AppConfig::load)?;
+    logging::init(config.panel.log_format);
+    diagnostics::init();
     tracing::info!(
         "Starting server on {}:{} with {} game server(s)",
         config.panel.host,
@@ -46,23 +83,16 @@ async fn main() -> anyhow::Result<()> {
         config.servers.len()
     );
 
-    // Build server definitions from static config + dynamic persistence
-    let mut definitions: Vec<ServerDefinition> = Vec::new();
-    let mut static_configs: HashMap<String, config::GameServerConfig> = HashMap::new();
-
-    for server in &config.servers {
-        let def = ServerDefinition::from_static_config(server);
-        definitions.push(def);
-        static_configs.insert(server.id.clone(), server.clone());
-    }
+    // Panel database; imports the old JSON state files on first start
+    let store = Arc::new(storage::SqliteStore::open(Path::new(storage::DB_FILE))?);
 
-    // Load dynamically created servers
-    let dynamic_servers = persistence::load_servers();
-    for ds in dynamic_servers {
-        if !definitions.iter().any(|d| d.id == ds.id) {
-            definitions.push(ds);
-        }
-    }
+    // Build server definitions from static config + dynamic persistence
+    let definitions = registry::load_definitions(&config.servers, &*store);
+    let static_configs: HashMap<String, config::GameServerConfig> = config
+        .servers
+        .iter()
+        .map(|server| (server.id.clone(), server.clone()))
+        .collect();
 
     tracing::info!(
         "Loaded {} total server definitions ({} static, {} dynamic)",
@@ -71,22 +101,45 @@ async fn main() -> anyhow::Result<()> {
         definitions.iter().filter(|d| d.source == ServerSource::Dynamic).count()
     );
 
+    // Before anything can start a server, so autostart runs inside its limits too
+    resources::detect(
+        definitions
+            .iter()
+            .filter(|d| !d.resource_limits.is_empty())
+            .count(),
+    );
+
     // Create the shared registry
-    let registry = Arc::new(ServerRegistry::new(definitions.clone(), static_configs));
+    let registry = Arc::new(ServerRegistry::new(
+        definitions,
+        static_configs,
+        store.clone() as Arc<dyn ServerStore>,
+        store.clone() as Arc<dyn MetricsStore>,
+    ));
 
-    // Global system monitor
+    // Global system monitor, seeded with the history saved at the last shutdown
     let sys_monitor = Arc::new(SystemMonitor::new(config.monitor.history_size));
+    let mut saved_history = monitor::load_history();
+    sys_monitor
+        .history
+        .write()
+        .await
+        .extend(std::mem::take(&mut saved_history.system));
 
     // Initialize runtimes for all Ready servers
-    for def in &definitions {
+    for def in &registry.all_definitions().await {
         if def.provisioning_status != ProvisioningStatus::Ready {
             tracing::info!("Skipping runtime init for '{}' (status: {:?})", def.id, def.provisioning_status);
             continue;
         }
 
         let server_config = registry.get_config(&def.id).await.unwrap();
-        let rcon_client = Arc::new(rcon::RconClient::new(server_config.rcon.clone()));
+        let rcon_client = rcon::RconClient::new(server_config.rcon.clone());
         let game_monitor = Arc::new(monitor::GameMonitor::new(config.monitor.history_size));
+        *game_monitor.expected_down_until.lock().unwrap() = def.expected_down_until();
+        if let Some(history) = saved_history.games.remove(&def.id) {
+            game_monitor.history.write().await.extend(history);
+        }
         let lgsm_lock = Arc::new(lgsm::LgsmLock::new());
 
         // Try initial RCON connection (non-fatal)
@@ -107,46 +160,164 @@ async fn main() -> anyhow::Result<()> {
             }
         }
 
-        // Spawn per-server game collector
-        let collector_handle = monitor::spawn_game_collector(
-            game_monitor.clone(),
-            rcon_client.clone(),
-            config.monitor.clone(),
-            def.id.clone(),
-        );
+        // Spawn per-server game collector (unless monitoring was paused)
+        let collector_handle = if def.monitoring_paused {
+            tracing::info!("Monitoring paused for '{}', not starting collector", def.id);
+            None
+        } else {
+            Some(monitor::spawn_game_collector(
+                game_monitor.clone(),
+                rcon_client.clone(),
+                config.monitor.clone(),
+                def.id.clone(),
+                server_config.paths.base_dir.clone(),
+                registry.events.clone(),
+                registry.metrics.clone(),
+            ))
+        };
 
         let runtime = ServerRuntime {
             rcon: rcon_client,
             game_monitor,
             lgsm_lock,
-            collector_handle: Some(collector_handle),
+            collector_handle,
         };
 
         registry.runtimes.write().await.insert(def.id.clone(), runtime);
     }
 
+    // Start auto_start servers that are down, staggered in the background
+    tokio::spawn(lgsm::autostart_servers(
+        registry.clone(),
+        config.panel.autostart_delay_secs,
+    ));
+
+    // Background loops stop through this instead of being killed on exit
+    let shutdown = shutdown::Shutdown::new();
+
     // Spawn global system collector
-    let _sys_collector =
-        monitor::spawn_system_collector(sys_monitor.clone(), config.monitor.clone());
+    let sys_collector = monitor::spawn_system_collector(
+        sys_monitor.clone(),
+        config.monitor.clone(),
+        registry.metrics.clone(),
+        shutdown.clone(),
+    );
+
+    // Map image URL cache
+    let map_image_cache = Arc::new(MapImageCache::new());
 
     // Global scheduler
-    let scheduler = Arc::new(Scheduler::new());
-    let _scheduler_handle = scheduler::spawn_scheduler(
+    let scheduler = Arc::new(Scheduler::new(
+        config.scheduler.notify_on_failure_url.clone(),
+        config.console.clone(),
+        registry.events.clone(),
+        store.clone() as Arc<dyn ScheduleStore>,
+    ));
+    let scheduler_writer = scheduler::spawn_writer(scheduler.clone(), shutdown.clone());
+    let scheduler_handle = scheduler::spawn_scheduler(
         scheduler.clone(),
         registry.clone(),
+        map_image_cache.clone(),
+        shutdown.clone(),
+    );
+
+    // Discord / webhook notifications for panel events
+    let notifier = Arc::new(notifications::Notifier::new(
+        store.clone() as Arc<dyn SettingsStore>
+    ));
+    let notification_sender =
+        notifications::spawn_sender(notifier.clone(), registry.clone(), shutdown.clone());
+
+    // Steam release watcher; starts opted-in AutoUpdate jobs on a new build
+    let update_watcher = Arc::new(updates::UpdateWatcher::new());
+    let update_watcher_handle = updates::spawn_watcher(
+        update_watcher.clone(),
+        config.updates.clone(),
+        registry.clone(),
+        scheduler.clone(),
+        map_image_cache.clone(),
+        shutdown.clone(),
+    );
+
+    // Records wipes done outside the panel, from the server's save time
+    let wipe_detector = wipes::spawn_wipe_detector(registry.clone(), shutdown.clone());
+
+    // Lifts temporary bans once they expire
+    let ban_expiry = bans::spawn_expiry(registry.clone(), shutdown.clone());
+
+    // Per-player history built from the collectors' playerlists
+    let poll_interval = std::time::Duration::from_secs(config.monitor.poll_interval_secs);
+    let player_tracker = playertracker::spawn_tracker(
+        registry.clone(),
+        Arc::new(playertracker::PlayerTracker::new(poll_interval)),
+        poll_interval,
+        shutdown.clone(),
     );
 
+    // Saved console command lists
+    let snippets = Arc::new(snippets::Snippets::new(
+        store.clone() as Arc<dyn SettingsStore>
+    ));
+
+    // Panel backup/restore; reloads these after a restore
+    let panel_data = Arc::new(panelbackup::PanelData::new(
+        store.clone(),
+        registry.clone(),
+        scheduler.clone(),
+        notifier.clone(),
+        snippets.clone(),
+    ));
+
+    // Failed logins per client address
+    let login_guard = Arc::new(loginguard::LoginGuard::new(&config.auth));
+    let restores = Arc::new(backups::Restores::new());
+    // Files of deleted servers being removed, for GET /delete-status
+    let file_removals = Arc::new(servers::FileRemovals::new());
+    // Console commands and their replies per server, for GET /console/history
+    let console_history = Arc::new(consolehistory::ConsoleHistory::new(
+        config.console.history_size,
+    ));
+
+    // Admin password, changeable at runtime
+    let credentials = Arc::new(auth::Credentials::new(
+        config.auth.password_hash.clone(),
+        crate::config::password_path(),
+    ));
+
+    // Refresh tokens issued at login
+    let sessions = Arc::new(auth::Sessions::new(store.clone() as Arc<dyn SessionStore>));
+
+    // Open WebSocket sessions
+    let ws_sessions = Arc::new(websocket::WsSessions::new());
+    let transfer_tokens = Arc::new(transfer::TransferTokens::new());
+
     // Position store for live map
-    let position_store = Arc::new(PositionStore::new());
+    let position_store = Arc::new(PositionStore::new(config.monitor.position_stale_secs));
+    let server_cleanup = Arc::new(servers::ServerCleanup::new(
+        console_history.clone(),
+        position_store.clone(),
+        file_removals.clone(),
+    ));
 
-    // Map image URL cache
-    let map_image_cache = Arc::new(MapImageCache::new());
+    // Who changed what through the API
+    let (audit_log, audit_rx) = audit::AuditLog::new(&config.panel);
+    let audit_log = Arc::new(audit_log);
+    let audit_writer = audit::spawn_writer(audit_log.clone(), audit_rx, shutdown.clone());
+
+    // Per-client request budgets
+    let rate_limiter = Arc::new(ratelimit::RateLimiter::new(config.limits.clone()));
 
     let bind_host = config.panel.host.clone();
     let bind_port = config.panel.port;
+    let app_registry = registry.clone();
+    let public_status_cache = Arc::new(publicstatus::PublicStatusCache::new(
+        &config.panel.public_status,
+    ));
+    let app_sys_monitor = sys_monitor.clone();
+    let app_ws_sessions = ws_sessions.clone();
 
-    HttpServer::new(move || {
-        let cors = Cors::default()
+    let server = HttpServer::new(move || {
+        let mut cors = Cors::default()
             .allowed_origin("http://localhost:5173")
             .allowed_origin(&format!(
                 "http://{}:{}",
@@ -162,25 +333,134 @@ async fn main() -> anyhow::Result<()> {
                 actix_web::http::header::AUTHORIZATION,
                 actix_web::http::header::CONTENT_TYPE,
                 actix_web::http::header::ACCEPT,
+                actix_web::http::header::HeaderName::from_static(requestid::HEADER),
             ])
+            .expose_headers(vec![requestid::HEADER])
             .max_age(3600);
+        // Community sites that embed the public status page
+        if config.panel.public_status.enabled {
+            for origin in &config.panel.public_status.allowed_origins {
+                cors = cors.allowed_origin(origin);
+            }
+        }
 
         App::new()
+            // gzip/brotli/zstd per Accept-Encoding; WebSocket upgrades are left alone
+            .wrap(middleware::Compress::default())
             .wrap(cors)
+            // Inside JwtAuth, so requests are counted per user once claims are known
+            .wrap(ratelimit::RateLimit(rate_limiter.clone()))
             .wrap(auth::JwtAuth)
+            // Outside JwtAuth, so the entries carry the user
+            .wrap(audit::AuditTrail(audit_log.clone()))
+            .wrap(accesslog::AccessLog::new(&config.panel))
+            .wrap(requestid::RequestId)
+            // Body size limits (import-bundle sets its own below)
+            .app_data(web::JsonConfig::default().limit(config.limits.json_body_bytes))
+            .app_data(web::PayloadConfig::default().limit(config.limits.payload_bytes))
             // Shared state
             .app_data(web::Data::new(config.clone()))
-            .app_data(web::Data::new(sys_monitor.clone()))
+            .app_data(web::Data::new(app_sys_monitor.clone()))
             .app_data(web::Data::new(scheduler.clone()))
-            .app_data(web::Data::new(registry.clone()))
+            .app_data(web::Data::new(app_registry.clone()))
             .app_data(web::Data::new(position_store.clone()))
             .app_data(web::Data::new(map_image_cache.clone()))
+            .app_data(web::Data::new(app_ws_sessions.clone()))
+            .app_data(web::Data::new(rate_limiter.clone()))
+            .app_data(web::Data::new(notifier.clone()))
+            .app_data(web::Data::new(snippets.clone()))
+            .app_data(web::Data::new(public_status_cache.clone()))
+            .app_data(web::Data::new(update_watcher.clone()))
+            .app_data(web::Data::new(transfer_tokens.clone()))
+            .app_data(web::Data::new(panel_data.clone()))
+            .app_data(web::Data::new(audit_log.clone()))
+            .app_data(web::Data::new(sessions.clone()))
+            .app_data(web::Data::new(login_guard.clone()))
+            .app_data(web::Data::new(restores.clone()))
+            .app_data(web::Data::new(file_removals.clone()))
+            .app_data(web::Data::new(console_history.clone()))
+            .app_data(web::Data::new(server_cleanup.clone()))
+            .app_data(web::Data::new(credentials.clone()))
             // Auth routes (global)
             .route("/api/auth/login", web::post().to(auth::login))
+            .route("/api/auth/refresh", web::post().to(auth::refresh))
+            .route("/api/auth/logout", web::post().to(auth::logout))
+            .route("/api/auth/change-password", web::post().to(auth::change_password))
             .route("/api/auth/me", web::get().to(auth::me))
+            // API description
+            .route("/api/openapi.json", web::get().to(openapi::openapi_json))
+            .route("/docs", web::get().to(openapi::docs_page))
+            .route("/api/limits", web::get().to(ratelimit::get_limits))
+            .route("/api/audit", web::get().to(audit::get_audit))
+            // Token-scoped file transfers (no login; the token is the credential)
+            .route("/api/transfer/{token}", web::get().to(transfer::download))
+            .route("/api/transfer/{token}", web::put().to(transfer::upload))
+            .route(
+                "/api/transfer/{token}",
+                web::head().to(transfer::transfer_status),
+            )
+            // Public status page (no login when enabled)
+            .route(
+                "/api/public/status",
+                web::get().to(publicstatus::public_status),
+            )
+            // Notification channels and routing
+            .route(
+                "/api/notifications",
+                web::get().to(notifications::get_settings),
+            )
+            .route(
+                "/api/notifications",
+                web::put().to(notifications::set_settings),
+            )
+            .route(
+                "/api/notifications/test",
+                web::post().to(notifications::test_notification),
+            )
+            // Console snippets offered on every server
+            .route(
+                "/api/console/snippets",
+                web::get().to(snippets::list_global),
+            )
+            .route(
+                "/api/console/snippets",
+                web::post().to(snippets::create_global),
+            )
+            .route(
+                "/api/console/snippets/{name}",
+                web::put().to(snippets::update_global),
+            )
+            .route(
+                "/api/console/snippets/{name}",
+                web::delete().to(snippets::delete_global),
+            )
+            // Recent activity across servers
+            .route("/api/activity", web::get().to(activity::get_activity))
+            // The panel's own health
+            .route(
+                "/api/diagnostics",
+                web::get().to(diagnostics::get_diagnostics),
+            )
+            // Panel data backup and restore
+            .route("/api/panel/backup", web::get().to(panelbackup::download_backup))
+            .route("/api/panel/restore", web::post().to(panelbackup::restore_backup))
+            // Installed vs. latest server builds
+            .route("/api/updates", web::get().to(updates::get_updates))
             // Server list + CRUD (global)
             .route("/api/servers", web::get().to(servers::list_servers))
             .route("/api/servers", web::post().to(servers::create_server))
+            .route(
+                "/api/servers/summary",
+                web::get().to(servers::servers_summary),
+            )
+            .route("/api/servers/bulk", web::post().to(servers::bulk_action))
+            .route("/api/servers/export", web::get().to(bundle::export_servers))
+            .service(
+                web::resource("/api/servers/import-bundle")
+                    // Bundles carry provisioning logs and jobs; allow more than the 32KB default
+                    .app_data(web::JsonConfig::default().limit(4 * 1024 * 1024))
+                    .route(web::post().to(bundle::import_bundle)),
+            )
             // System monitor (global)
             .route(
                 "/api/monitor/system",
@@ -194,6 +474,16 @@ async fn main() -> anyhow::Result<()> {
             // Scheduler routes (global scope, jobs have server_id field)
             .route("/api/schedule", web::get().to(scheduler::list_jobs))
             .route("/api/schedule", web::post().to(scheduler::create_job))
+            .route(
+                "/api/schedule/history",
+                web::get().to(scheduler::all_history),
+            )
+            .route(
+                "/api/schedule/preview",
+                web::get().to(scheduler::preview_schedule),
+            )
+            .route("/api/schedule/pause", web::get().to(scheduler::get_pauses))
+            .route("/api/schedule/pause", web::put().to(scheduler::set_pauses))
             .route(
                 "/api/schedule/{id}",
                 web::put().to(scheduler::update_job),
@@ -206,6 +496,14 @@ async fn main() -> anyhow::Result<()> {
                 "/api/schedule/{id}/toggle",
                 web::post().to(scheduler::toggle_job),
             )
+            .route(
+                "/api/schedule/{id}/run",
+                web::post().to(scheduler::run_job_now),
+            )
+            .route(
+                "/api/schedule/{id}/history",
+                web::get().to(scheduler::job_history),
+            )
             // Per-server routes
             .service(
                 web::scope("/api/servers/{server_id}")
@@ -215,8 +513,16 @@ async fn main() -> anyhow::Result<()> {
                     .route("/restart", web::post().to(lgsm::server_restart))
                     .route("/update", web::post().to(lgsm::server_update))
                     .route("/backup", web::post().to(lgsm::server_backup))
+                    .route("/backups", web::get().to(backups::list_backups))
+                    .route("/backups/{name}", web::delete().to(backups::delete_backup))
+                    .route("/backups/{name}/download", web::get().to(backups::download_backup))
+                    .route("/backups/{name}/restore", web::post().to(backups::restore_backup))
+                    .route("/restore-status", web::get().to(backups::restore_status))
                     .route("/save", web::post().to(lgsm::server_save))
                     .route("/wipe", web::post().to(lgsm::server_wipe))
+                    .route("/wipes", web::get().to(wipes::list_wipes))
+                    .route("/wipe-info", web::get().to(wipes::wipe_info))
+                    .route("/actions", web::get().to(lgsm::list_actions))
                     .route("/force-update", web::post().to(lgsm::server_force_update))
                     .route("/validate", web::post().to(lgsm::server_validate))
                     .route("/check-update", web::post().to(lgsm::server_check_update))
@@ -225,19 +531,54 @@ async fn main() -> anyhow::Result<()> {
                     .route("/update-lgsm", web::post().to(lgsm::server_update_lgsm))
                     .route("/full-wipe", web::post().to(lgsm::server_full_wipe))
                     .route("/map-wipe", web::post().to(lgsm::server_map_wipe))
+                    .route(
+                        "/console/scrollback",
+                        web::get().to(websocket::console_scrollback),
+                    )
+                    .route(
+                        "/console/history",
+                        web::get().to(consolehistory::console_history),
+                    )
+                    .route("/console/snippets", web::get().to(snippets::list_server))
+                    .route("/console/snippets", web::post().to(snippets::create_server))
+                    .route(
+                        "/console/snippets/{name}",
+                        web::put().to(snippets::update_server),
+                    )
+                    .route(
+                        "/console/snippets/{name}",
+                        web::delete().to(snippets::delete_server),
+                    )
+                    .route(
+                        "/console/snippets/{name}/run",
+                        web::post().to(snippets::run_snippet),
+                    )
                     // Players
                     .route("/players", web::get().to(players::list_players))
+                    .route("/players/history", web::get().to(playertracker::player_history))
+                    .route("/players/known", web::get().to(playertracker::known_players))
                     .route("/players/kick", web::post().to(players::kick_player))
                     .route("/players/ban", web::post().to(players::ban_player))
                     .route("/players/unban", web::post().to(players::unban_player))
+                    .route("/bans", web::get().to(bans::list_bans))
                     .route("/players/moderator", web::post().to(players::add_moderator))
                     .route("/players/remove-moderator", web::post().to(players::remove_moderator))
                     .route("/players/give", web::post().to(players::give_item))
+                    .route("/players/message", web::post().to(players::message_player))
+                    .route("/broadcast", web::post().to(players::broadcast))
                     // Game monitor
                     .route(
                         "/monitor/game",
                         web::get().to(monitor::get_game_metrics),
                     )
+                    .route(
+                        "/config/server-cfg",
+                        web::get().to(servercfg::get_server_cfg),
+                    )
+                    .route(
+                        "/config/server-cfg",
+                        web::put().to(servercfg::put_server_cfg),
+                    )
                     // Files
                     .route("/files/list", web::get().to(filemanager::list_files))
                     .route("/files/read", web::get().to(filemanager::read_file))
@@ -248,6 +589,15 @@ async fn main() -> anyhow::Result<()> {
                         web::get().to(filemanager::download_file),
                     )
                     .route("/files/mkdir", web::post().to(filemanager::mkdir))
+                    .route("/files/rename", web::post().to(filemanager::rename_file))
+                    .route(
+                        "/files/archive",
+                        web::get().to(filemanager::download_archive),
+                    )
+                    .route(
+                        "/files/transfer-token",
+                        web::post().to(transfer::create_transfer_token),
+                    )
                     .route(
                         "/files/delete",
                         web::delete().to(filemanager::delete_file),
@@ -258,6 +608,7 @@ async fn main() -> anyhow::Result<()> {
                         "/plugins/upload",
                         web::post().to(plugins::upload_plugin),
                     )
+                    .route("/plugins/bulk", web::post().to(plugins::bulk_plugin_action))
                     .route(
                         "/plugins/umod/install",
                         web::post().to(plugins::umod_install),
@@ -274,6 +625,10 @@ async fn main() -> anyhow::Result<()> {
                         "/plugins/{name}/config",
                         web::put().to(plugins::save_plugin_config),
                     )
+                    .route("/plugins/{name}/enable", web::post().to(plugins::enable_plugin))
+                    .route("/plugins/{name}/disable", web::post().to(plugins::disable_plugin))
+                    .route("/plugins/{name}/data", web::get().to(plugins::get_plugin_data))
+                    .route("/plugins/{name}/data", web::put().to(plugins::save_plugin_data))
                     .route(
                         "/plugins/{name}/reload",
                         web::post().to(plugins::reload_plugin),
@@ -282,21 +637,75 @@ async fn main() -> anyhow::Result<()> {
                     .route("/logs/tail", web::get().to(logs::tail_log))
                     // Map & Positions
                     .route("/map", web::get().to(map::get_map_info))
+                    .route("/map/preview", web::get().to(map::preview_map))
                     .route("/positions", web::get().to(map::get_positions))
                     .route("/positions", web::post().to(map::update_positions))
                     // Provisioning
+                    .route(
+                        "/monitoring/pause",
+                        web::post().to(servers::pause_monitoring),
+                    )
+                    .route(
+                        "/monitoring/resume",
+                        web::post().to(servers::resume_monitoring),
+                    )
+                    .route("/maintenance", web::put().to(servers::set_maintenance))
+                    .route(
+                        "/maintenance",
+                        web::delete().to(servers::clear_maintenance),
+                    )
+                    .route("/clone", web::post().to(servers::clone_server))
+                    .route("/attach", web::post().to(servers::attach_server))
+                    .route(
+                        "/provision",
+                        web::post().to(servers::provision_imported),
+                    )
+                    .route(
+                        "/provision/cancel",
+                        web::post().to(servers::cancel_provisioning),
+                    )
+                    .route(
+                        "/provision/retry",
+                        web::post().to(servers::retry_provisioning),
+                    )
+                    .route(
+                        "/connection-info",
+                        web::get().to(servers::connection_info),
+                    )
+                    .route(
+                        "/connection-info/regenerate-rcon-password",
+                        web::post().to(servers::regenerate_rcon_password),
+                    )
                     .route(
                         "/provision-status",
                         web::get().to(servers::provision_status),
                     )
-                    // Delete server
+                    .route("/delete-status", web::get().to(servers::delete_status))
+                    // Update / delete server
+                    .route("", web::put().to(servers::update_server))
                     .route("", web::delete().to(servers::delete_server)),
             )
+            // WebSocket session admin
+            .route("/api/ws/sessions", web::get().to(websocket::list_sessions))
+            .route(
+                "/api/ws/sessions/{id}",
+                web::delete().to(websocket::close_session),
+            )
             // WebSocket routes (per-server)
+            .route("/ws/events", web::get().to(websocket::ws_events))
             .route(
                 "/ws/{server_id}/console",
                 web::get().to(websocket::ws_console),
             )
+            .route(
+                "/ws/{server_id}/players",
+                web::get().to(websocket::ws_players),
+            )
+            .route("/ws/{server_id}/logs", web::get().to(websocket::ws_logs))
+            .route(
+                "/ws/{server_id}/provision",
+                web::get().to(websocket::ws_provision),
+            )
             .route(
                 "/ws/{server_id}/monitor",
                 web::get().to(websocket::ws_monitor),
@@ -318,9 +727,40 @@ async fn main() -> anyhow::Result<()> {
     })
     .bind(format!("{}:{}", bind_host, bind_port))?
     .shutdown_timeout(10)
-    .run()
-    .await?;
+    .disable_signals()
+    .run();
+
+    // Stop accepting requests on SIGINT/SIGTERM; open WebSockets are closed so the
+    // graceful stop doesn't sit out the whole timeout waiting for them
+    let server_handle = server.handle();
+    tokio::spawn(async move {
+        shutdown::wait_for_signal().await;
+        tracing::info!("Shutting down: no longer accepting requests");
+        ws_sessions.close_all();
+        server_handle.stop(true).await;
+    });
+    server.await?;
+
+    // Let in-flight writes finish, then stop the background tasks
+    shutdown.trigger();
+    for (name, task) in [
+        ("scheduler", scheduler_handle),
+        ("scheduler writer", scheduler_writer),
+        ("system collector", sys_collector),
+        ("notification sender", notification_sender),
+        ("update watcher", update_watcher_handle),
+        ("wipe detector", wipe_detector),
+        ("ban expiry", ban_expiry),
+        ("player tracker", player_tracker),
+        ("audit writer", audit_writer),
+    ] {
+        if let Err(e) = task.await {
+            tracing::warn!("{} task ended abnormally: {}", name, e);
+        }
+    }
+    registry.shutdown_runtimes().await;
+    monitor::save_history(&sys_monitor, &registry).await;
 
-    tracing::info!("Server shutdown complete");
+    tracing::info!("Shutdown complete");
     Ok(())
 }
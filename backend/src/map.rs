@@ -1,12 +1,21 @@
 use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::RwLock;
+use utoipa::{IntoParams, ToSchema};
 
+use crate::apierror::{ApiError, ErrorCode};
 use crate::registry::ServerRegistry;
+use crate::validation::{self, ValidationErrors, MAX_PLAYERS_LIMIT};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Positions may be this far outside the map (in metres), which the ocean around it
+/// allows.
+const MAP_MARGIN: f64 = 1000.0;
+const MAX_NAME_LEN: usize = 64;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PlayerPosition {
     pub steam_id: String,
@@ -14,28 +23,105 @@ pub struct PlayerPosition {
     pub x: f64,
     pub y: f64,
     pub z: f64,
+    /// The fields below are pushed by newer plugin versions only.
+    #[serde(default)]
+    pub is_sleeping: Option<bool>,
+    #[serde(default)]
+    pub is_dead: Option<bool>,
+    /// Null when not in a team. A push without it keeps the team last pushed, so the
+    /// player's colour on the map doesn't flicker; 0 clears it.
+    #[serde(default)]
+    pub team_id: Option<u64>,
+    #[serde(default)]
+    pub health: Option<f32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UpdatePositionsBody {
     pub players: Vec<PlayerPosition>,
     pub token: String,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
-    error: String,
+/// A player's position and when the plugin last pushed it.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TrackedPosition {
+    #[serde(flatten)]
+    pub position: PlayerPosition,
+    pub last_updated: DateTime<Utc>,
 }
 
+#[derive(Default)]
+struct ServerPositions {
+    /// When the plugin last pushed anything.
+    last_update: Option<DateTime<Utc>>,
+    /// By steam id.
+    players: HashMap<String, TrackedPosition>,
+}
+
+impl ServerPositions {
+    fn prune(&mut self, stale_before: DateTime<Utc>) {
+        self.players.retain(|_, p| p.last_updated >= stale_before);
+    }
+}
+
+/// Positions pushed by the map plugin. Players not pushed for `stale_after` are dropped,
+/// so a plugin that stops reporting doesn't leave them frozen on the map.
 pub struct PositionStore {
-    pub positions: RwLock<HashMap<String, Vec<PlayerPosition>>>,
+    stale_after: chrono::Duration,
+    servers: RwLock<HashMap<String, ServerPositions>>,
 }
 
 impl PositionStore {
-    pub fn new() -> Self {
+    pub fn new(stale_after_secs: u64) -> Self {
         Self {
-            positions: RwLock::new(HashMap::new()),
+            stale_after: chrono::Duration::seconds(stale_after_secs as i64),
+            servers: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Merge a batch into the server's positions; players missing from it are kept, since
+    /// the plugin may split its pushes.
+    async fn update(&self, server_id: &str, players: Vec<PlayerPosition>) {
+        let now = Utc::now();
+        let mut servers = self.servers.write().await;
+        let server = servers.entry(server_id.to_string()).or_default();
+        server.last_update = Some(now);
+        for mut position in players {
+            position.team_id = match position.team_id {
+                Some(0) => None,
+                Some(team) => Some(team),
+                None => server
+                    .players
+                    .get(&position.steam_id)
+                    .and_then(|old| old.position.team_id),
+            };
+            let tracked = TrackedPosition {
+                position,
+                last_updated: now,
+            };
+            server
+                .players
+                .insert(tracked.position.steam_id.clone(), tracked);
         }
+        server.prune(now - self.stale_after);
+    }
+
+    /// The server's fresh positions and when the plugin last pushed any.
+    async fn current(&self, server_id: &str) -> (Vec<TrackedPosition>, Option<DateTime<Utc>>) {
+        let mut servers = self.servers.write().await;
+        let Some(server) = servers.get_mut(server_id) else {
+            return (Vec::new(), None);
+        };
+        server.prune(Utc::now() - self.stale_after);
+        let mut players: Vec<TrackedPosition> = server.players.values().cloned().collect();
+        players.sort_by(|a, b| a.position.display_name.cmp(&b.position.display_name));
+        (players, server.last_update)
+    }
+
+    /// Drop a deleted server's positions.
+    pub async fn forget(&self, server_id: &str) {
+        self.servers.write().await.remove(server_id);
     }
 }
 
@@ -50,11 +136,35 @@ impl MapImageCache {
             cache: RwLock::new(HashMap::new()),
         }
     }
+
+    /// The RustMaps image URL of a map, fetched on first use. None while RustMaps hasn't
+    /// generated the map; that isn't cached, so a later call can find it.
+    async fn image_url(&self, world_size: u32, seed: u32) -> Option<String> {
+        let key = format!("{}_{}", world_size, seed);
+        if let Some(url) = self.cache.read().await.get(&key) {
+            return Some(url.clone());
+        }
+        let url = fetch_rustmaps_image_url(world_size, seed).await?;
+        self.cache.write().await.insert(key, url.clone());
+        Some(url)
+    }
+
+    /// Forget the image for a map that no longer exists (e.g. after a seed change).
+    pub async fn invalidate(&self, world_size: u32, seed: u32) {
+        self.cache
+            .write()
+            .await
+            .remove(&format!("{}_{}", world_size, seed));
+    }
+}
+
+fn rustmaps_page_url(world_size: u32, seed: u32) -> String {
+    format!("https://rustmaps.com/map/{}_{}", world_size, seed)
 }
 
 /// Fetch the map image URL from the RustMaps page HTML.
 async fn fetch_rustmaps_image_url(world_size: u32, seed: u32) -> Option<String> {
-    let page_url = format!("https://rustmaps.com/map/{}_{}", world_size, seed);
+    let page_url = rustmaps_page_url(world_size, seed);
     let html = reqwest::get(&page_url).await.ok()?.text().await.ok()?;
     // Look for the map_icons.png URL in the HTML
     // Pattern: https://content.rustmaps.com/maps/{ver}/{hash}/map_icons.png
@@ -71,19 +181,25 @@ async fn fetch_rustmaps_image_url(world_size: u32, seed: u32) -> Option<String>
 }
 
 /// GET /api/servers/{server_id}/map
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/map",
+    tag = "map",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "World size, seed and RustMaps image URL", body = Object),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
 pub async fn get_map_info(
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
     map_cache: web::Data<Arc<MapImageCache>>,
-) -> HttpResponse {
-    let def = match registry.get_definition(&server_id).await {
-        Some(d) => d,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     // Try to get live seed/worldSize from RCON convar queries
     let (seed, world_size) = if let Some(rcon) = registry.get_rcon(&server_id).await {
@@ -101,86 +217,225 @@ pub async fn get_map_info(
         (def.seed, def.world_size)
     };
 
-    // Look up cached image URL or fetch from RustMaps
-    let cache_key = format!("{}_{}", world_size, seed);
-    let image_url = {
-        let cache = map_cache.cache.read().await;
-        cache.get(&cache_key).cloned()
-    };
-
-    let image_url = match image_url {
-        Some(url) => url,
-        None => {
-            let url = fetch_rustmaps_image_url(world_size, seed)
-                .await
-                .unwrap_or_default();
-            if !url.is_empty() {
-                let mut cache = map_cache.cache.write().await;
-                cache.insert(cache_key, url.clone());
-            }
-            url
-        }
-    };
+    let image_url = map_cache
+        .image_url(world_size, seed)
+        .await
+        .unwrap_or_default();
 
-    HttpResponse::Ok().json(serde_json::json!({
+    Ok(HttpResponse::Ok().json(serde_json::json!({
         "seed": seed,
         "worldSize": world_size,
         "imageUrl": image_url,
+    })))
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct MapPreviewQuery {
+    pub seed: u32,
+    /// Defaults to the server's current world size.
+    pub world_size: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MapPreview {
+    pub seed: u32,
+    pub world_size: u32,
+    /// Null until RustMaps has generated the map; `page_url` starts that.
+    pub image_url: Option<String>,
+    pub page_url: String,
+}
+
+/// GET /api/servers/{server_id}/map/preview?seed=&worldSize=
+///
+/// The map a wipe to another seed or world size would give.
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/map/preview",
+    tag = "map",
+    params(("server_id" = String, Path, description = "Server id"), MapPreviewQuery),
+    responses(
+        (status = 200, body = MapPreview),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 422, description = "Invalid seed or world size", body = ApiError),
+    )
+)]
+pub async fn preview_map(
+    server_id: web::Path<String>,
+    query: web::Query<MapPreviewQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    map_cache: web::Data<Arc<MapImageCache>>,
+) -> Result<HttpResponse, ApiError> {
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let world_size = query.world_size.unwrap_or(def.world_size);
+    let mut invalid = ValidationErrors::new();
+    if let Err(e) = validation::validate_seed(query.seed) {
+        invalid.add("seed", e);
+    }
+    if let Err(e) = validation::validate_world_size(world_size) {
+        invalid.add("worldSize", e);
+    }
+    if !invalid.is_empty() {
+        return Err(invalid.into());
+    }
+
+    Ok(HttpResponse::Ok().json(MapPreview {
+        seed: query.seed,
+        world_size,
+        image_url: map_cache.image_url(world_size, query.seed).await,
+        page_url: rustmaps_page_url(world_size, query.seed),
     }))
 }
 
+/// Rejects pushes no working plugin would send: too many players, or positions far off
+/// the map.
+fn validate_positions(players: &[PlayerPosition], world_size: u32) -> Result<(), ApiError> {
+    let mut invalid = ValidationErrors::new();
+    if players.len() > MAX_PLAYERS_LIMIT as usize {
+        invalid.add(
+            "players",
+            format!("At most {} players per push", MAX_PLAYERS_LIMIT),
+        );
+        return Err(invalid.into());
+    }
+    let bound = world_size as f64 / 2.0 + MAP_MARGIN;
+    for (i, p) in players.iter().enumerate() {
+        if p.steam_id.trim().is_empty() || p.steam_id.len() > MAX_NAME_LEN {
+            invalid.add(&format!("players[{}].steamId", i), "Missing or too long");
+        }
+        if p.display_name.chars().count() > MAX_NAME_LEN {
+            invalid.add(
+                &format!("players[{}].displayName", i),
+                format!("At most {} characters", MAX_NAME_LEN),
+            );
+        }
+        for (axis, value) in [("x", p.x), ("z", p.z)] {
+            if !value.is_finite() || value.abs() > bound {
+                invalid.add(
+                    &format!("players[{}].{}", i, axis),
+                    format!("Must be within ±{} for a {} map", bound, world_size),
+                );
+            }
+        }
+        if !p.y.is_finite() {
+            invalid.add(&format!("players[{}].y", i), "Must be a number");
+        }
+        if p.health.is_some_and(|h| !h.is_finite() || h < 0.0) {
+            invalid.add(&format!("players[{}].health", i), "Must not be negative");
+        }
+    }
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(invalid.into())
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PositionsQuery {
+    /// Comma-separated: `sleepers` and/or `dead`, which are left out by default.
+    pub include: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PositionsResponse {
+    /// Players pushed within `monitor.position_stale_secs`.
+    pub players: Vec<TrackedPosition>,
+    /// When the plugin last pushed positions; null if it never has since the panel started.
+    pub last_update: Option<DateTime<Utc>>,
+}
+
 /// GET /api/servers/{server_id}/positions
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/positions",
+    tag = "map",
+    params(("server_id" = String, Path, description = "Server id"), PositionsQuery),
+    responses(
+        (status = 200, body = PositionsResponse),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 422, description = "Unknown include value", body = ApiError),
+    )
+)]
 pub async fn get_positions(
     server_id: web::Path<String>,
+    query: web::Query<PositionsQuery>,
     store: web::Data<Arc<PositionStore>>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     // Verify server exists
     if registry.get_definition(&server_id).await.is_none() {
-        return HttpResponse::NotFound().json(ErrorBody {
-            error: "Server not found".to_string(),
-        });
+        return Err(ApiError::server_not_found());
     }
 
-    let positions = store.positions.read().await;
-    let players = positions
-        .get(server_id.as_str())
-        .cloned()
-        .unwrap_or_default();
+    let (mut sleepers, mut dead) = (false, false);
+    for part in query.include.as_deref().unwrap_or_default().split(',') {
+        match part.trim() {
+            "" => {}
+            "sleepers" => sleepers = true,
+            "dead" => dead = true,
+            other => {
+                return Err(ApiError::validation(format!(
+                    "Unknown include '{}'; use sleepers or dead",
+                    other
+                )))
+            }
+        }
+    }
 
-    HttpResponse::Ok().json(serde_json::json!({
-        "players": players,
+    let (mut players, last_update) = store.current(&server_id).await;
+    players.retain(|p| {
+        (sleepers || p.position.is_sleeping != Some(true))
+            && (dead || p.position.is_dead != Some(true))
+    });
+    Ok(HttpResponse::Ok().json(PositionsResponse {
+        players,
+        last_update,
     }))
 }
 
 /// POST /api/servers/{server_id}/positions
 /// Authenticated via RCON password in body (not JWT).
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/positions",
+    tag = "map",
+    security(),
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = UpdatePositionsBody,
+    responses(
+        (status = 200, body = Object),
+        (status = 401, description = "Token does not match the RCON password", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 422, description = "Too many players or positions off the map", body = ApiError),
+    )
+)]
 pub async fn update_positions(
     server_id: web::Path<String>,
     body: web::Json<UpdatePositionsBody>,
     store: web::Data<Arc<PositionStore>>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     // Verify server exists and token matches RCON password
-    let def = match registry.get_definition(&server_id).await {
-        Some(d) => d,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     if body.token != def.rcon_password {
-        return HttpResponse::Unauthorized().json(ErrorBody {
-            error: "Invalid token".to_string(),
-        });
+        return Err(ApiError::new(ErrorCode::Unauthorized, "Invalid token"));
     }
+    validate_positions(&body.players, def.world_size)?;
 
-    let mut positions = store.positions.write().await;
-    positions.insert(server_id.into_inner(), body.players.clone());
+    let body = body.into_inner();
+    store.update(&server_id, body.players).await;
 
-    HttpResponse::Ok().json(serde_json::json!({
+    Ok(HttpResponse::Ok().json(serde_json::json!({
         "success": true,
-    }))
+    })))
 }
@@ -1,18 +1,29 @@
-use actix_web::{web, HttpResponse};
+use actix_web::http::header::ETag;
+use actix_web::{web, HttpRequest, HttpResponse};
 use chrono::{DateTime, Utc};
-use serde::Serialize;
-use std::collections::VecDeque;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
 use std::sync::Arc;
 use sysinfo::System;
-use tokio::sync::RwLock;
+use tokio::sync::{broadcast, Notify, RwLock};
 use tokio::time::{interval, Duration};
+use utoipa::ToSchema;
 
+use crate::apierror::ApiError;
 use crate::config::MonitorConfig;
-use crate::rcon::RconClient;
+use crate::diagnostics;
+use crate::etag;
+use crate::events::{EventBus, PanelEvent};
+use crate::persistence::{read_json, write_atomic, MetricsStore};
+use crate::rcon::{Player, RconClient};
 use crate::registry::ServerRegistry;
+use crate::resources::ProcessSampler;
+use crate::rollup::{game_source, query_range, RangeQuery, RollupWriter, SYSTEM_SOURCE};
+use crate::shutdown::Shutdown;
 
 /// A single system metrics snapshot.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct SystemSnapshot {
     pub timestamp: DateTime<Utc>,
@@ -20,13 +31,40 @@ pub struct SystemSnapshot {
     pub mem_total: u64,
     pub mem_used: u64,
     pub mem_percent: f32,
+    /// The primary disk (`monitor.primary_disk`), or all disks added up.
     pub disk_total: u64,
     pub disk_used: u64,
     pub disk_percent: f32,
+    #[serde(default)]
+    pub disks: Vec<DiskSnapshot>,
+    /// Network throughput since the previous snapshot over all interfaces but loopback,
+    /// in bytes per second.
+    #[serde(default)]
+    pub rx_bytes_per_sec: u64,
+    #[serde(default)]
+    pub tx_bytes_per_sec: u64,
+}
+
+/// One mounted disk in a system snapshot.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct DiskSnapshot {
+    pub mount: String,
+    pub total: u64,
+    pub used: u64,
+    pub percent: f32,
+}
+
+fn percent(used: u64, total: u64) -> f32 {
+    if total > 0 {
+        (used as f32 / total as f32) * 100.0
+    } else {
+        0.0
+    }
 }
 
 /// A single game server metrics snapshot.
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GameSnapshot {
     pub timestamp: DateTime<Utc>,
@@ -39,6 +77,80 @@ pub struct GameSnapshot {
     pub uptime: u64,
     pub map: String,
     pub hostname: String,
+    /// Usage of the server's RustDedicated process(es); null when none is running.
+    pub cpu_percent: Option<f32>,
+    pub memory_bytes: Option<u64>,
+    pub threads: Option<u32>,
+}
+
+/// Last playerlist fetched by the game collector.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerListSnapshot {
+    pub timestamp: DateTime<Utc>,
+    pub players: Vec<Player>,
+}
+
+/// A change to a server's player list, pushed over /ws/{server_id}/players.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PlayerUpdate {
+    Joined {
+        player: Player,
+    },
+    #[serde(rename_all = "camelCase")]
+    Left {
+        steam_id: String,
+        display_name: String,
+    },
+    /// The full list; sent whenever anything (including ping or health) changed.
+    List {
+        players: Vec<Player>,
+    },
+}
+
+/// Player updates buffered per subscriber.
+const PLAYER_CHANNEL_CAPACITY: usize = 64;
+
+/// Monitor history saved on shutdown and restored on the next start.
+const HISTORY_FILE: &str = "monitor_history.json";
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct SavedHistory {
+    pub system: Vec<SystemSnapshot>,
+    /// Game snapshots by server id.
+    pub games: HashMap<String, Vec<GameSnapshot>>,
+}
+
+/// Load the history saved by the last shutdown; empty if there is none.
+pub fn load_history() -> SavedHistory {
+    match read_json(Path::new(HISTORY_FILE)) {
+        Ok(history) => history.unwrap_or_default(),
+        Err(e) => {
+            tracing::error!("Failed to load {}: {}", HISTORY_FILE, e);
+            SavedHistory::default()
+        }
+    }
+}
+
+/// Write the system history and every server's game history to disk.
+pub async fn save_history(sys_monitor: &SystemMonitor, registry: &ServerRegistry) {
+    let mut saved = SavedHistory {
+        system: sys_monitor.history.read().await.to_vec(),
+        games: HashMap::new(),
+    };
+    for (id, runtime) in registry.runtimes.read().await.iter() {
+        let history = runtime.game_monitor.history.read().await.to_vec();
+        saved.games.insert(id.clone(), history);
+    }
+
+    let written = serde_json::to_string(&saved)
+        .map_err(anyhow::Error::from)
+        .and_then(|c| Ok(write_atomic(Path::new(HISTORY_FILE), &c)?));
+    match written {
+        Ok(()) => tracing::info!("Saved monitor history to {}", HISTORY_FILE),
+        Err(e) => tracing::error!("Failed to save monitor history: {}", e),
+    }
 }
 
 /// Ring buffer for metric history.
@@ -63,10 +175,21 @@ impl<T: Clone> RingBuffer<T> {
         self.data.push_back(item);
     }
 
+    /// Push items oldest first, e.g. history restored from disk.
+    pub fn extend(&mut self, items: impl IntoIterator<Item = T>) {
+        for item in items {
+            self.push(item);
+        }
+    }
+
     pub fn latest(&self) -> Option<&T> {
         self.data.back()
     }
 
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.data.iter()
+    }
+
     pub fn to_vec(&self) -> Vec<T> {
         self.data.iter().cloned().collect()
     }
@@ -75,25 +198,85 @@ impl<T: Clone> RingBuffer<T> {
 /// Shared state for system monitoring.
 pub struct SystemMonitor {
     pub history: RwLock<RingBuffer<SystemSnapshot>>,
+    /// Woken whenever a new snapshot is pushed.
+    pub updated: Notify,
 }
 
 /// Shared state for game monitoring.
 pub struct GameMonitor {
     pub history: RwLock<RingBuffer<GameSnapshot>>,
+    /// Woken whenever a new snapshot is pushed.
+    pub updated: Notify,
+    /// Cached playerlist, so viewers don't each issue their own RCON call.
+    pub players: RwLock<Option<PlayerListSnapshot>>,
+    pub player_updates: broadcast::Sender<PlayerUpdate>,
+    /// While set and in the future the server is stopped on purpose or in maintenance, so
+    /// failed polls aren't reported as an outage. Kept in sync by the registry.
+    pub expected_down_until: std::sync::Mutex<Option<DateTime<Utc>>>,
 }
 
 impl SystemMonitor {
     pub fn new(history_size: usize) -> Self {
         Self {
             history: RwLock::new(RingBuffer::new(history_size)),
+            updated: Notify::new(),
         }
     }
 }
 
 impl GameMonitor {
     pub fn new(history_size: usize) -> Self {
+        let (player_updates, _) = broadcast::channel(PLAYER_CHANNEL_CAPACITY);
         Self {
             history: RwLock::new(RingBuffer::new(history_size)),
+            updated: Notify::new(),
+            players: RwLock::new(None),
+            player_updates,
+            expected_down_until: std::sync::Mutex::new(None),
+        }
+    }
+
+    fn expected_down(&self) -> bool {
+        self.expected_down_until
+            .lock()
+            .unwrap()
+            .is_some_and(|until| until > Utc::now())
+    }
+
+    /// Replace the cached playerlist and broadcast joins, leaves and the new list.
+    async fn update_players(&self, players: Vec<Player>) {
+        let mut cached = self.players.write().await;
+        let previous = cached.as_ref().map(|s| s.players.as_slice()).unwrap_or(&[]);
+        if cached.is_some() && previous == players.as_slice() {
+            return;
+        }
+
+        let mut updates: Vec<PlayerUpdate> = players
+            .iter()
+            .filter(|p| !previous.iter().any(|old| old.steam_id == p.steam_id))
+            .map(|p| PlayerUpdate::Joined { player: p.clone() })
+            .collect();
+        updates.extend(
+            previous
+                .iter()
+                .filter(|old| !players.iter().any(|p| p.steam_id == old.steam_id))
+                .map(|old| PlayerUpdate::Left {
+                    steam_id: old.steam_id.clone(),
+                    display_name: old.display_name.clone(),
+                }),
+        );
+        updates.push(PlayerUpdate::List {
+            players: players.clone(),
+        });
+
+        *cached = Some(PlayerListSnapshot {
+            timestamp: Utc::now(),
+            players,
+        });
+        drop(cached);
+        for update in updates {
+            // No subscribers is fine
+            let _ = self.player_updates.send(update);
         }
     }
 }
@@ -102,13 +285,24 @@ impl GameMonitor {
 pub fn spawn_system_collector(
     monitor: Arc<SystemMonitor>,
     config: MonitorConfig,
+    metrics: Arc<dyn MetricsStore>,
+    shutdown: Shutdown,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
         let mut sys = System::new_all();
+        let mut rollups = RollupWriter::new(SYSTEM_SOURCE.to_string(), metrics);
+        // Counters are deltas since the previous refresh
+        let mut networks = sysinfo::Networks::new_with_refreshed_list();
+        let mut last_refresh = std::time::Instant::now();
+        // Warn once, not on every tick
+        let mut warned_primary = false;
         let mut tick = interval(Duration::from_secs(config.poll_interval_secs));
 
         loop {
-            tick.tick().await;
+            tokio::select! {
+                _ = tick.tick() => {}
+                _ = shutdown.signal() => break,
+            }
 
             sys.refresh_all();
 
@@ -122,22 +316,50 @@ pub fn spawn_system_collector(
                 0.0
             };
 
-            let disks = sysinfo::Disks::new_with_refreshed_list();
-            let (disk_total, disk_used) =
-                disks
-                    .list()
+            let disks: Vec<DiskSnapshot> = sysinfo::Disks::new_with_refreshed_list()
+                .list()
+                .iter()
+                .map(|d| {
+                    let used = d.total_space() - d.available_space();
+                    DiskSnapshot {
+                        mount: d.mount_point().to_string_lossy().into_owned(),
+                        total: d.total_space(),
+                        used,
+                        percent: percent(used, d.total_space()),
+                    }
+                })
+                .collect();
+            let primary = config
+                .primary_disk
+                .as_deref()
+                .and_then(|mount| disks.iter().find(|d| d.mount == mount));
+            if let Some(mount) = config.primary_disk.as_deref() {
+                if primary.is_none() && !warned_primary {
+                    tracing::warn!(
+                        "monitor.primary_disk '{}' is not mounted; adding up all disks",
+                        mount
+                    );
+                    warned_primary = true;
+                }
+            }
+            let (disk_total, disk_used) = match primary {
+                Some(d) => (d.total, d.used),
+                None => disks
                     .iter()
-                    .fold((0u64, 0u64), |(t, u), d| {
-                        (
-                            t + d.total_space(),
-                            u + (d.total_space() - d.available_space()),
-                        )
-                    });
-            let disk_percent = if disk_total > 0 {
-                (disk_used as f32 / disk_total as f32) * 100.0
-            } else {
-                0.0
+                    .fold((0u64, 0u64), |(t, u), d| (t + d.total, u + d.used)),
             };
+            let disk_percent = percent(disk_used, disk_total);
+
+            networks.refresh(true);
+            let elapsed = last_refresh.elapsed().as_secs_f64().max(0.001);
+            last_refresh = std::time::Instant::now();
+            let (rx, tx) = networks
+                .list()
+                .iter()
+                .filter(|(name, _)| name.as_str() != "lo")
+                .fold((0u64, 0u64), |(rx, tx), (_, data)| {
+                    (rx + data.received(), tx + data.transmitted())
+                });
 
             let snapshot = SystemSnapshot {
                 timestamp: Utc::now(),
@@ -148,10 +370,15 @@ pub fn spawn_system_collector(
                 disk_total,
                 disk_used,
                 disk_percent,
+                disks,
+                rx_bytes_per_sec: (rx as f64 / elapsed) as u64,
+                tx_bytes_per_sec: (tx as f64 / elapsed) as u64,
             };
 
-            let mut history = monitor.history.write().await;
-            history.push(snapshot);
+            rollups.add(&snapshot);
+            monitor.history.write().await.push(snapshot);
+            monitor.updated.notify_waiters();
+            diagnostics::report_ok("system collector");
         }
     })
 }
@@ -162,13 +389,37 @@ pub fn spawn_game_collector(
     rcon: Arc<RconClient>,
     config: MonitorConfig,
     server_id: String,
+    base_dir: String,
+    events: EventBus,
+    metrics: Arc<dyn MetricsStore>,
 ) -> tokio::task::JoinHandle<()> {
     tokio::spawn(async move {
+        let task = diagnostics::collector_task(&server_id);
+        let mut rollups = RollupWriter::new(game_source(&server_id), metrics);
+        let sampler = Arc::new(std::sync::Mutex::new(ProcessSampler::new(base_dir)));
         let mut tick = interval(Duration::from_secs(config.poll_interval_secs));
+        // Seeded from the last snapshot so restarting the collector doesn't re-announce
+        let mut was_online = monitor.history.read().await.latest().map(|s| s.online);
+        // Highest player count in the history; beating it publishes a player_peak event
+        let mut peak = monitor
+            .history
+            .read()
+            .await
+            .iter()
+            .filter(|s| s.online)
+            .map(|s| s.players)
+            .max();
 
         loop {
             tick.tick().await;
 
+            let usage = {
+                let sampler = sampler.clone();
+                tokio::task::spawn_blocking(move || sampler.lock().unwrap().sample())
+                    .await
+                    .ok()
+                    .flatten()
+            };
             let snapshot = match rcon.server_info().await {
                 Ok(info) => GameSnapshot {
                     timestamp: Utc::now(),
@@ -181,9 +432,18 @@ pub fn spawn_game_collector(
                     uptime: info.uptime,
                     map: info.map,
                     hostname: info.hostname,
+                    cpu_percent: usage.map(|u| u.cpu_percent),
+                    memory_bytes: usage.map(|u| u.memory_bytes),
+                    threads: usage.map(|u| u.threads),
                 },
                 Err(e) => {
                     tracing::debug!("Game server '{}' poll failed: {}", server_id, e);
+                    // Down on purpose: the collector itself is fine
+                    if monitor.expected_down() {
+                        diagnostics::report_ok(&task);
+                    } else {
+                        diagnostics::report_error(&task, &e);
+                    }
                     GameSnapshot {
                         timestamp: Utc::now(),
                         online: false,
@@ -195,18 +455,57 @@ pub fn spawn_game_collector(
                         uptime: 0,
                         map: String::new(),
                         hostname: String::new(),
+                        cpu_percent: usage.map(|u| u.cpu_percent),
+                        memory_bytes: usage.map(|u| u.memory_bytes),
+                        threads: usage.map(|u| u.threads),
                     }
                 }
             };
 
-            let mut history = monitor.history.write().await;
-            history.push(snapshot);
+            let online = snapshot.online;
+            if online {
+                diagnostics::report_ok(&task);
+            }
+            let players = snapshot.players;
+            rollups.add(&snapshot);
+            monitor.history.write().await.push(snapshot);
+            monitor.updated.notify_waiters();
+
+            if online {
+                match rcon.player_list().await {
+                    Ok(players) => monitor.update_players(players).await,
+                    Err(e) => tracing::debug!("Playerlist for '{}' failed: {}", server_id, e),
+                }
+            } else {
+                monitor.update_players(Vec::new()).await;
+            }
+
+            if was_online != Some(online) {
+                let server_id = server_id.clone();
+                if online {
+                    events.publish(PanelEvent::ServerOnline { server_id });
+                } else if !monitor.expected_down() {
+                    events.publish(PanelEvent::ServerOffline { server_id });
+                }
+                was_online = Some(online);
+            }
+
+            if online && peak.is_none_or(|p| players > p) {
+                // The first reading only sets the baseline
+                if peak.is_some() {
+                    events.publish(PanelEvent::PlayerPeak {
+                        server_id: server_id.clone(),
+                        players,
+                    });
+                }
+                peak = Some(players);
+            }
         }
     })
 }
 
 /// API response for system monitoring.
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct SystemMonitorResponse {
     current: Option<SystemSnapshot>,
@@ -214,44 +513,93 @@ struct SystemMonitorResponse {
 }
 
 /// API response for game monitoring.
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct GameMonitorResponse {
     current: Option<GameSnapshot>,
     history: Vec<GameSnapshot>,
 }
 
-/// GET /api/monitor/system
-pub async fn get_system_metrics(monitor: web::Data<Arc<SystemMonitor>>) -> HttpResponse {
+/// GET /api/monitor/system?from=&to=&resolution=
+#[utoipa::path(
+    get,
+    path = "/api/monitor/system",
+    tag = "monitor",
+    params(RangeQuery),
+    responses(
+        (status = 200, description = "The in-memory history; with from, to or resolution a MetricRange of avg/min/max buckets instead", body = SystemMonitorResponse),
+        (status = 304, description = "History unchanged since the If-None-Match ETag"),
+        (status = 400, description = "Invalid range", body = ApiError),
+    )
+)]
+pub async fn get_system_metrics(
+    req: HttpRequest,
+    query: web::Query<RangeQuery>,
+    monitor: web::Data<Arc<SystemMonitor>>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
     let history = monitor.history.read().await;
+    if !query.is_empty() {
+        let range = query_range(&query, &history, registry.metrics.as_ref(), SYSTEM_SOURCE)?;
+        return Ok(HttpResponse::Ok().json(range));
+    }
+    // Snapshots are only appended, so the newest timestamp identifies the contents
+    let tag = etag::from_key(&history.latest().map(|s| s.timestamp));
+    if etag::is_fresh(&req, &tag) {
+        return Ok(etag::not_modified(tag));
+    }
     let current = history.latest().cloned();
     let all = history.to_vec();
 
-    HttpResponse::Ok().json(SystemMonitorResponse {
-        current,
-        history: all,
-    })
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(tag))
+        .json(SystemMonitorResponse {
+            current,
+            history: all,
+        }))
 }
 
-/// GET /api/servers/{server_id}/monitor/game
+/// GET /api/servers/{server_id}/monitor/game?from=&to=&resolution=
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/monitor/game",
+    tag = "monitor",
+    params(("server_id" = String, Path, description = "Server id"), RangeQuery),
+    responses(
+        (status = 200, description = "The in-memory history; with from, to or resolution a MetricRange of avg/min/max buckets instead", body = GameMonitorResponse),
+        (status = 304, description = "History unchanged since the If-None-Match ETag"),
+        (status = 400, description = "Invalid range", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
 pub async fn get_game_metrics(
+    req: HttpRequest,
     server_id: web::Path<String>,
+    query: web::Query<RangeQuery>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let monitor = match registry.get_game_monitor(&server_id).await {
-        Some(m) => m,
-        None => {
-            return HttpResponse::NotFound()
-                .json(serde_json::json!({"error": "Server not found"}))
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    let monitor = registry
+        .get_game_monitor(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     let history = monitor.history.read().await;
+    if !query.is_empty() {
+        let source = game_source(&server_id);
+        let range = query_range(&query, &history, registry.metrics.as_ref(), &source)?;
+        return Ok(HttpResponse::Ok().json(range));
+    }
+    let tag = etag::from_key(&history.latest().map(|s| s.timestamp));
+    if etag::is_fresh(&req, &tag) {
+        return Ok(etag::not_modified(tag));
+    }
     let current = history.latest().cloned();
     let all = history.to_vec();
 
-    HttpResponse::Ok().json(GameMonitorResponse {
-        current,
-        history: all,
-    })
+    Ok(HttpResponse::Ok()
+        .insert_header(ETag(tag))
+        .json(GameMonitorResponse {
+            current,
+            history: all,
+        }))
 }
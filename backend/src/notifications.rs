@@ -0,0 +1,535 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::sync::{broadcast, RwLock};
+use tokio::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::apierror::ApiError;
+use crate::diagnostics;
+use crate::events::{PanelEvent, TimedEvent};
+use crate::persistence::SettingsStore;
+use crate::registry::{ProvisioningStatus, ServerRegistry};
+use crate::shutdown::Shutdown;
+
+const SETTINGS_KEY: &str = "notifications";
+/// All-time player count records per server id.
+const RECORDS_KEY: &str = "player_records";
+/// Minimum gap between two messages to one channel. Discord allows about 30 per minute
+/// per webhook; this stays well under that even while retries are in flight.
+const CHANNEL_SPACING: Duration = Duration::from_secs(2);
+const BOT_NAME: &str = "Rust Server Panel";
+
+/// What a notification is about; each kind is routed separately.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize, ToSchema,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum NotificationKind {
+    ServerOffline,
+    UpdateAvailable,
+    ProvisioningFinished,
+    JobFailed,
+    PlayerRecord,
+}
+
+impl NotificationKind {
+    const ALL: [NotificationKind; 5] = [
+        NotificationKind::ServerOffline,
+        NotificationKind::UpdateAvailable,
+        NotificationKind::ProvisioningFinished,
+        NotificationKind::JobFailed,
+        NotificationKind::PlayerRecord,
+    ];
+
+    fn as_str(self) -> &'static str {
+        match self {
+            NotificationKind::ServerOffline => "server_offline",
+            NotificationKind::UpdateAvailable => "update_available",
+            NotificationKind::ProvisioningFinished => "provisioning_finished",
+            NotificationKind::JobFailed => "job_failed",
+            NotificationKind::PlayerRecord => "player_record",
+        }
+    }
+
+    /// Discord embed sidebar color.
+    fn color(self) -> u32 {
+        match self {
+            NotificationKind::ServerOffline | NotificationKind::JobFailed => 0xe74c3c,
+            NotificationKind::UpdateAvailable => 0xf1c40f,
+            NotificationKind::ProvisioningFinished => 0x3498db,
+            NotificationKind::PlayerRecord => 0x2ecc71,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ChannelKind {
+    /// Discord webhook; messages are sent as embeds.
+    Discord,
+    /// Any endpoint accepting a JSON POST of the notification's fields.
+    Webhook,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Channel {
+    /// Referenced by routes.
+    pub id: String,
+    pub name: String,
+    pub kind: ChannelKind,
+    pub url: String,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Route {
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+    /// Channel ids to send to; empty means every enabled channel.
+    #[serde(default)]
+    pub channels: Vec<String>,
+}
+
+impl Default for Route {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            channels: Vec::new(),
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Channels and per-kind routing, managed through /api/notifications.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct NotificationSettings {
+    #[serde(default)]
+    pub channels: Vec<Channel>,
+    /// Kinds without an entry use the default route (enabled, every channel).
+    #[serde(default)]
+    pub routes: BTreeMap<NotificationKind, Route>,
+}
+
+impl NotificationSettings {
+    fn validate(&self) -> Result<(), String> {
+        for (index, channel) in self.channels.iter().enumerate() {
+            if channel.id.trim().is_empty() {
+                return Err(format!("Channel {} needs an id", index + 1));
+            }
+            if self.channels[..index].iter().any(|c| c.id == channel.id) {
+                return Err(format!("Duplicate channel id '{}'", channel.id));
+            }
+            crate::webhook::validate_url(&channel.url)
+                .map_err(|e| format!("Channel '{}': {}", channel.id, e))?;
+        }
+        for (kind, route) in &self.routes {
+            if let Some(unknown) = route
+                .channels
+                .iter()
+                .find(|id| !self.channels.iter().any(|c| &c.id == *id))
+            {
+                return Err(format!(
+                    "Route {} refers to unknown channel '{}'",
+                    kind.as_str(),
+                    unknown
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Enabled channels a notification of `kind` goes to.
+    fn targets(&self, kind: NotificationKind) -> Vec<Channel> {
+        let route = self.routes.get(&kind).cloned().unwrap_or_default();
+        if !route.enabled {
+            return Vec::new();
+        }
+        self.channels
+            .iter()
+            .filter(|c| c.enabled)
+            .filter(|c| route.channels.is_empty() || route.channels.contains(&c.id))
+            .cloned()
+            .collect()
+    }
+
+    /// Every kind with its effective route, for the API.
+    fn with_default_routes(mut self) -> Self {
+        for kind in NotificationKind::ALL {
+            self.routes.entry(kind).or_default();
+        }
+        self
+    }
+}
+
+/// One message, rendered per channel type when sent.
+#[derive(Debug, Clone)]
+pub struct Notification {
+    pub kind: NotificationKind,
+    pub server_id: Option<String>,
+    pub server_name: Option<String>,
+    pub title: String,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl Notification {
+    fn discord_body(&self) -> serde_json::Value {
+        let mut embed = serde_json::json!({
+            "title": self.title,
+            "description": self.message,
+            "color": self.kind.color(),
+            "timestamp": self.timestamp.to_rfc3339(),
+        });
+        if let Some(ref name) = self.server_name {
+            embed["footer"] = serde_json::json!({ "text": name });
+        }
+        serde_json::json!({ "username": BOT_NAME, "embeds": [embed] })
+    }
+
+    fn webhook_body(&self) -> serde_json::Value {
+        serde_json::json!({
+            "event": self.kind,
+            "serverId": self.server_id,
+            "serverName": self.server_name,
+            "title": self.title,
+            "message": self.message,
+            "timestamp": self.timestamp,
+        })
+    }
+}
+
+/// Routes panel events to the configured channels.
+pub struct Notifier {
+    settings: RwLock<NotificationSettings>,
+    /// Highest player count ever seen, per server id.
+    records: Mutex<HashMap<String, u32>>,
+    /// Earliest time the next message may go to each channel id.
+    next_slot: Mutex<HashMap<String, Instant>>,
+    store: Arc<dyn SettingsStore>,
+}
+
+impl Notifier {
+    pub fn new(store: Arc<dyn SettingsStore>) -> Self {
+        let settings = load(&*store, SETTINGS_KEY).unwrap_or_default();
+        let records = load(&*store, RECORDS_KEY).unwrap_or_default();
+        Self {
+            settings: RwLock::new(settings),
+            records: Mutex::new(records),
+            next_slot: Mutex::new(HashMap::new()),
+            store,
+        }
+    }
+
+    /// Re-read settings and player records from the store, after a panel restore.
+    pub async fn reload(&self) {
+        *self.settings.write().await = load(&*self.store, SETTINGS_KEY).unwrap_or_default();
+        *self.records.lock().unwrap() = load(&*self.store, RECORDS_KEY).unwrap_or_default();
+    }
+
+    /// Turn a panel event into a notification, if it is one people get told about.
+    async fn notification_for(
+        &self,
+        event: &PanelEvent,
+        registry: &ServerRegistry,
+    ) -> Option<Notification> {
+        let server_id = event.server_id();
+        let def = registry.get_definition(server_id).await;
+        let name = def
+            .as_ref()
+            .map_or_else(|| server_id.to_string(), |d| d.name.clone());
+
+        let (kind, title, message) = match event {
+            // Stopped on purpose or in maintenance: not an outage
+            PanelEvent::ServerOffline { .. } if def.as_ref().is_some_and(|d| d.expected_down()) => {
+                return None
+            }
+            PanelEvent::ServerOffline { .. } => (
+                NotificationKind::ServerOffline,
+                "Server offline".to_string(),
+                format!("{} stopped answering RCON.", name),
+            ),
+            PanelEvent::UpdateAvailable { .. } => (
+                NotificationKind::UpdateAvailable,
+                "Update available".to_string(),
+                format!("A new Rust server build is available for {}.", name),
+            ),
+            PanelEvent::ProvisioningStatus {
+                status, message, ..
+            } => match status {
+                ProvisioningStatus::Ready => (
+                    NotificationKind::ProvisioningFinished,
+                    "Provisioning finished".to_string(),
+                    format!("{} is ready: {}", name, message),
+                ),
+                ProvisioningStatus::Error => (
+                    NotificationKind::ProvisioningFinished,
+                    "Provisioning failed".to_string(),
+                    format!("{}: {}", name, message),
+                ),
+                _ => return None,
+            },
+            PanelEvent::JobFinished {
+                job_name,
+                success: false,
+                skipped: false,
+                message,
+                ..
+            } => (
+                NotificationKind::JobFailed,
+                "Scheduled job failed".to_string(),
+                format!("Job '{}' on {} failed: {}", job_name, name, message),
+            ),
+            PanelEvent::PlayerPeak { players, .. } => {
+                let previous = self.record_players(server_id, *players)?;
+                (
+                    NotificationKind::PlayerRecord,
+                    "New player record".to_string(),
+                    format!(
+                        "{} players online on {} (previous record {}).",
+                        players, name, previous
+                    ),
+                )
+            }
+            _ => return None,
+        };
+        Some(Notification {
+            kind,
+            server_id: Some(server_id.to_string()),
+            server_name: Some(name),
+            title,
+            message,
+            timestamp: Utc::now(),
+        })
+    }
+
+    /// Store `players` if it beats the server's all-time record; returns the old record.
+    /// The first count seen for a server only sets the record.
+    fn record_players(&self, server_id: &str, players: u32) -> Option<u32> {
+        let mut records = self.records.lock().unwrap();
+        let previous = records.get(server_id).copied();
+        if previous.is_some_and(|p| players <= p) {
+            return None;
+        }
+        records.insert(server_id.to_string(), players);
+        let value = serde_json::to_value(&*records).unwrap_or_default();
+        drop(records);
+        if let Err(e) = self.store.save_setting(RECORDS_KEY, &value) {
+            tracing::error!("Failed to save player records: {}", e);
+        }
+        previous
+    }
+
+    /// Send to every channel routed for the notification's kind; failures are logged.
+    async fn deliver(&self, notification: &Notification) {
+        let targets = self.settings.read().await.targets(notification.kind);
+        for channel in targets {
+            match self.send_to(&channel, notification).await {
+                Ok(_) => diagnostics::report_ok(SENDER_TASK),
+                Err(e) => {
+                    tracing::warn!(
+                        "Notification {} to channel '{}' failed: {}",
+                        notification.kind.as_str(),
+                        channel.id,
+                        e
+                    );
+                    diagnostics::report_error(
+                        SENDER_TASK,
+                        format!("Channel '{}': {}", channel.id, e),
+                    );
+                }
+            }
+        }
+    }
+
+    /// Send one notification, waiting for the channel's rate limit first.
+    async fn send_to(
+        &self,
+        channel: &Channel,
+        notification: &Notification,
+    ) -> Result<String, String> {
+        let wait_until = {
+            let mut slots = self.next_slot.lock().unwrap();
+            let now = Instant::now();
+            let slot = slots.get(&channel.id).copied().unwrap_or(now).max(now);
+            slots.insert(channel.id.clone(), slot + CHANNEL_SPACING);
+            slot
+        };
+        tokio::time::sleep_until(wait_until).await;
+
+        let body = match channel.kind {
+            ChannelKind::Discord => notification.discord_body(),
+            ChannelKind::Webhook => notification.webhook_body(),
+        };
+        crate::webhook::post_json(&channel.url, &body).await
+    }
+}
+
+fn load<T: serde::de::DeserializeOwned>(store: &dyn SettingsStore, key: &str) -> Option<T> {
+    let value = match store.load_setting(key) {
+        Ok(value) => value?,
+        Err(e) => {
+            tracing::warn!("Failed to load {} settings: {}", key, e);
+            return None;
+        }
+    };
+    serde_json::from_value(value)
+        .map_err(|e| tracing::warn!("Ignoring unreadable {} settings: {}", key, e))
+        .ok()
+}
+
+const SENDER_TASK: &str = "notification sender";
+
+/// Background task: turn panel events into notifications and send them, one at a time,
+/// so each channel's messages arrive in order.
+pub fn spawn_sender(
+    notifier: Arc<Notifier>,
+    registry: Arc<ServerRegistry>,
+    shutdown: Shutdown,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut events = registry.events.subscribe();
+        loop {
+            let event: TimedEvent = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(missed)) => {
+                        tracing::warn!("Notifications fell behind; {} events not sent", missed);
+                        diagnostics::report_error(
+                            SENDER_TASK,
+                            format!("Fell behind; {} events not sent", missed),
+                        );
+                        continue;
+                    }
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = shutdown.signal() => break,
+            };
+            if let Some(notification) = notifier.notification_for(&event.event, &registry).await {
+                notifier.deliver(&notification).await;
+            }
+        }
+    })
+}
+
+/// GET /api/notifications — channels and the route for every notification kind.
+#[utoipa::path(
+    get,
+    path = "/api/notifications",
+    tag = "notifications",
+    responses((status = 200, body = NotificationSettings))
+)]
+pub async fn get_settings(notifier: web::Data<Arc<Notifier>>) -> HttpResponse {
+    let settings = notifier.settings.read().await.clone();
+    HttpResponse::Ok().json(settings.with_default_routes())
+}
+
+/// PUT /api/notifications — replace channels and routes.
+#[utoipa::path(
+    put,
+    path = "/api/notifications",
+    tag = "notifications",
+    request_body = NotificationSettings,
+    responses(
+        (status = 200, description = "The stored settings", body = NotificationSettings),
+        (status = 400, body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn set_settings(
+    body: web::Json<NotificationSettings>,
+    notifier: web::Data<Arc<Notifier>>,
+) -> Result<HttpResponse, ApiError> {
+    let settings = body.into_inner();
+    settings.validate().map_err(ApiError::validation)?;
+    serde_json::to_value(&settings)
+        .map_err(anyhow::Error::from)
+        .and_then(|value| notifier.store.save_setting(SETTINGS_KEY, &value))
+        .map_err(|e| ApiError::internal(format!("Failed to save notification settings: {}", e)))?;
+    *notifier.settings.write().await = settings.clone();
+    tracing::info!(
+        "Notification settings updated ({} channels)",
+        settings.channels.len()
+    );
+    Ok(HttpResponse::Ok().json(settings.with_default_routes()))
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TestRequest {
+    /// Channel id; omitted tests every channel, including disabled ones.
+    #[serde(default)]
+    channel: Option<String>,
+    /// Kind to format the test message as (default server_offline).
+    #[serde(default)]
+    kind: Option<NotificationKind>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct TestResult {
+    channel: String,
+    success: bool,
+    message: String,
+}
+
+/// POST /api/notifications/test — send a test message now and report each channel's result.
+#[utoipa::path(
+    post,
+    path = "/api/notifications/test",
+    tag = "notifications",
+    request_body = TestRequest,
+    responses(
+        (status = 200, description = "Per-channel delivery `results`", body = Object),
+        (status = 404, description = "Channel not found, or none configured", body = ApiError),
+    )
+)]
+pub async fn test_notification(
+    body: web::Json<TestRequest>,
+    notifier: web::Data<Arc<Notifier>>,
+) -> Result<HttpResponse, ApiError> {
+    let channels: Vec<Channel> = {
+        let settings = notifier.settings.read().await;
+        settings
+            .channels
+            .iter()
+            .filter(|c| body.channel.as_ref().is_none_or(|id| &c.id == id))
+            .cloned()
+            .collect()
+    };
+    if channels.is_empty() {
+        let error = match body.channel {
+            Some(ref id) => format!("Channel '{}' not found", id),
+            None => "No channels configured".to_string(),
+        };
+        return Err(ApiError::not_found(error));
+    }
+
+    let notification = Notification {
+        kind: body.kind.unwrap_or(NotificationKind::ServerOffline),
+        server_id: None,
+        server_name: None,
+        title: "Test notification".to_string(),
+        message: "Notifications from the panel reach this channel.".to_string(),
+        timestamp: Utc::now(),
+    };
+    let mut results = Vec::new();
+    for channel in channels {
+        let delivery = notifier.send_to(&channel, &notification).await;
+        results.push(TestResult {
+            channel: channel.id,
+            success: delivery.is_ok(),
+            message: delivery.unwrap_or_else(|e| e),
+        });
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "results": results })))
+}
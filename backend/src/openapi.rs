@@ -0,0 +1,413 @@
+use actix_web::{
+    http::header::{self, ContentType},
+    HttpResponse,
+};
+use utoipa::openapi::security::{HttpAuthScheme, HttpBuilder, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+
+use crate::{
+    activity, audit, auth, backups, bans, bundle, consolehistory, diagnostics, filemanager, lgsm,
+    logs, map, monitor, notifications, pagination, panelbackup, players, playertracker, plugins,
+    publicstatus, ratelimit, rollup, scheduler, servercfg, servers, snippets, transfer, updates,
+    websocket, wipes,
+};
+
+/// REST API description. WebSocket routes (/ws/...) aren't listed; OpenAPI can't describe
+/// their frames.
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Rust Server Panel API",
+        description = "Manage Rust game servers: LGSM actions, RCON, files, plugins and schedules."
+    ),
+    components(schemas(pagination::Page, rollup::MetricRange)),
+    paths(
+        auth::login,
+        auth::refresh,
+        auth::logout,
+        auth::change_password,
+        auth::me,
+        servers::list_servers,
+        servers::create_server,
+        servers::clone_server,
+        servers::servers_summary,
+        servers::bulk_action,
+        servers::update_server,
+        servers::delete_server,
+        servers::delete_status,
+        servers::pause_monitoring,
+        servers::resume_monitoring,
+        servers::set_maintenance,
+        servers::clear_maintenance,
+        servers::attach_server,
+        servers::provision_imported,
+        servers::cancel_provisioning,
+        servers::retry_provisioning,
+        servers::connection_info,
+        servers::regenerate_rcon_password,
+        servercfg::get_server_cfg,
+        servercfg::put_server_cfg,
+        servers::provision_status,
+        bundle::export_servers,
+        bundle::import_bundle,
+        lgsm::server_status,
+        lgsm::server_start,
+        lgsm::server_stop,
+        lgsm::server_restart,
+        lgsm::server_update,
+        lgsm::server_backup,
+        lgsm::server_save,
+        lgsm::server_wipe,
+        wipes::list_wipes,
+        wipes::wipe_info,
+        lgsm::list_actions,
+        lgsm::server_force_update,
+        lgsm::server_validate,
+        lgsm::server_check_update,
+        lgsm::server_monitor_check,
+        lgsm::server_details,
+        lgsm::server_update_lgsm,
+        lgsm::server_full_wipe,
+        lgsm::server_map_wipe,
+        backups::list_backups,
+        backups::delete_backup,
+        backups::download_backup,
+        backups::restore_backup,
+        backups::restore_status,
+        monitor::get_system_metrics,
+        monitor::get_game_metrics,
+        players::list_players,
+        players::kick_player,
+        players::ban_player,
+        players::unban_player,
+        bans::list_bans,
+        playertracker::player_history,
+        playertracker::known_players,
+        players::add_moderator,
+        players::remove_moderator,
+        players::give_item,
+        players::message_player,
+        players::broadcast,
+        filemanager::list_files,
+        filemanager::read_file,
+        filemanager::write_file,
+        filemanager::upload_file,
+        filemanager::download_file,
+        filemanager::download_archive,
+        filemanager::mkdir,
+        filemanager::rename_file,
+        filemanager::delete_file,
+        transfer::create_transfer_token,
+        transfer::download,
+        transfer::transfer_status,
+        transfer::upload,
+        plugins::list_plugins,
+        plugins::upload_plugin,
+        plugins::umod_install,
+        plugins::umod_search,
+        plugins::delete_plugin,
+        plugins::get_plugin_config,
+        plugins::save_plugin_config,
+        plugins::get_plugin_data,
+        plugins::save_plugin_data,
+        plugins::reload_plugin,
+        plugins::bulk_plugin_action,
+        plugins::enable_plugin,
+        plugins::disable_plugin,
+        logs::tail_log,
+        map::get_map_info,
+        map::preview_map,
+        map::get_positions,
+        map::update_positions,
+        scheduler::list_jobs,
+        scheduler::create_job,
+        scheduler::update_job,
+        scheduler::delete_job,
+        scheduler::toggle_job,
+        scheduler::run_job_now,
+        scheduler::get_pauses,
+        scheduler::set_pauses,
+        scheduler::preview_schedule,
+        scheduler::job_history,
+        scheduler::all_history,
+        websocket::console_scrollback,
+        consolehistory::console_history,
+        websocket::list_sessions,
+        websocket::close_session,
+        snippets::list_global,
+        snippets::create_global,
+        snippets::update_global,
+        snippets::delete_global,
+        snippets::list_server,
+        snippets::create_server,
+        snippets::update_server,
+        snippets::delete_server,
+        snippets::run_snippet,
+        ratelimit::get_limits,
+        notifications::get_settings,
+        notifications::set_settings,
+        notifications::test_notification,
+        publicstatus::public_status,
+        activity::get_activity,
+        audit::get_audit,
+        updates::get_updates,
+        diagnostics::get_diagnostics,
+        panelbackup::download_backup,
+        panelbackup::restore_backup,
+    ),
+    modifiers(&BearerAuth),
+    security(("bearer" = [])),
+    tags(
+        (name = "auth", description = "Login and the current user"),
+        (name = "servers", description = "Server registry, provisioning and bundles"),
+        (name = "lgsm", description = "LinuxGSM actions"),
+        (name = "backups", description = "LGSM backup archives"),
+        (name = "monitor", description = "System and game metrics"),
+        (name = "players", description = "Player list and moderation over RCON"),
+        (name = "console", description = "RCON console"),
+        (name = "files", description = "File manager, confined to the server directory"),
+        (name = "plugins", description = "Oxide/uMod plugins"),
+        (name = "logs", description = "Server and LGSM logs"),
+        (name = "map", description = "Map info and player positions"),
+        (name = "schedule", description = "Scheduled jobs, run history and pause windows"),
+        (name = "websockets", description = "Open WebSocket sessions"),
+        (name = "limits", description = "Rate limits and body size caps"),
+        (name = "notifications", description = "Discord and webhook notification channels"),
+        (name = "public", description = "Unauthenticated status for community sites"),
+        (name = "activity", description = "Recent panel and server activity"),
+        (name = "audit", description = "Changes made through the API, by user"),
+        (name = "updates", description = "Installed vs. released server builds"),
+        (name = "diagnostics", description = "Panel process health and background tasks"),
+        (name = "panel", description = "Backup and restore of the panel's own data"),
+    )
+)]
+pub struct ApiDoc;
+
+/// Registers the JWT bearer scheme that `security(("bearer" = []))` refers to.
+struct BearerAuth;
+
+impl Modify for BearerAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        let components = openapi.components.get_or_insert_with(Default::default);
+        components.add_security_scheme(
+            "bearer",
+            SecurityScheme::Http(
+                HttpBuilder::new()
+                    .scheme(HttpAuthScheme::Bearer)
+                    .bearer_format("JWT")
+                    .build(),
+            ),
+        );
+    }
+}
+
+/// GET /api/openapi.json
+pub async fn openapi_json() -> HttpResponse {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+/// Read-only API reference rendered from /api/openapi.json. Everything is inline and the
+/// CSP forbids loading anything else, so no third-party script ever sees the panel's token.
+/// The token is used for the spec request only; there is no "Try it out" console.
+const DOCS_PAGE: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+  <meta charset="utf-8">
+  <title>Rust Server Panel API</title>
+  <style>
+    body { font-family: system-ui, sans-serif; margin: 2rem auto; max-width: 960px; color: #222; }
+    h2 { margin-top: 2rem; border-bottom: 1px solid #ccc; }
+    details { border: 1px solid #ddd; border-radius: 4px; margin: .4rem 0; padding: .4rem .6rem; }
+    summary { cursor: pointer; }
+    code { background: #f4f4f4; padding: 0 .2rem; }
+    .method { display: inline-block; width: 4.5rem; font-weight: bold; text-transform: uppercase; }
+    pre { background: #f4f4f4; padding: .5rem; overflow-x: auto; }
+  </style>
+</head>
+<body>
+  <h1>Rust Server Panel API</h1>
+  <div id="docs">Loading&hellip;</div>
+  <script>
+    const root = document.getElementById('docs');
+    const el = (tag, text) => {
+      const node = document.createElement(tag);
+      if (text !== undefined) node.textContent = text;
+      return node;
+    };
+    const schemaName = (schema) =>
+      schema && schema.$ref ? schema.$ref.split('/').pop() : JSON.stringify(schema || {});
+
+    function renderOperation(path, method, op) {
+      const details = el('details');
+      const summary = el('summary');
+      summary.append(el('span', method), el('code', path), ' ' + (op.summary || ''));
+      summary.firstChild.className = 'method';
+      details.append(summary);
+      if (op.description) details.append(el('p', op.description));
+      for (const param of op.parameters || []) {
+        details.append(el('div', `${param.in} ${param.name}${param.required ? ' (required)' : ''}: ${schemaName(param.schema)}`));
+      }
+      const body = op.requestBody && op.requestBody.content;
+      for (const [type, media] of Object.entries(body || {})) {
+        details.append(el('div', `body ${type}: ${schemaName(media.schema)}`));
+      }
+      for (const [status, response] of Object.entries(op.responses || {})) {
+        details.append(el('div', `${status}: ${response.description || ''}`));
+      }
+      return details;
+    }
+
+    function render(spec) {
+      root.textContent = '';
+      const byTag = new Map();
+      for (const [path, item] of Object.entries(spec.paths || {})) {
+        for (const [method, op] of Object.entries(item)) {
+          const tag = (op.tags && op.tags[0]) || 'other';
+          if (!byTag.has(tag)) byTag.set(tag, []);
+          byTag.get(tag).push(renderOperation(path, method, op));
+        }
+      }
+      for (const [tag, ops] of [...byTag].sort(([a], [b]) => a.localeCompare(b))) {
+        root.append(el('h2', tag), ...ops);
+      }
+      const schemas = (spec.components && spec.components.schemas) || {};
+      root.append(el('h2', 'schemas'));
+      for (const name of Object.keys(schemas).sort()) {
+        const details = el('details');
+        details.append(el('summary', name), el('pre', JSON.stringify(schemas[name], null, 2)));
+        root.append(details);
+      }
+    }
+
+    const token = localStorage.getItem('jwt_token');
+    if (!token) {
+      window.location.replace('/login');
+    } else {
+      fetch('/api/openapi.json', { headers: { Authorization: 'Bearer ' + token } })
+        .then((res) => (res.ok ? res.json() : Promise.reject(new Error(res.status + ' ' + res.statusText))))
+        .then(render)
+        .catch((err) => { root.textContent = 'Could not load the API spec: ' + err.message; });
+    }
+  </script>
+</body>
+</html>
+"#;
+
+/// GET /docs
+pub async fn docs_page() -> HttpResponse {
+    HttpResponse::Ok()
+        .content_type(ContentType::html())
+        .insert_header((
+            header::CONTENT_SECURITY_POLICY,
+            "default-src 'none'; script-src 'unsafe-inline'; style-src 'unsafe-inline'; connect-src 'self'",
+        ))
+        .body(DOCS_PAGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Reads a string literal starting right after its opening quote.
+    fn literal(rest: &str) -> &str {
+        &rest[..rest.find('"').unwrap()]
+    }
+
+    /// Every `(method, path)` pair registered in main.rs, with scope and resource
+    /// prefixes applied. Tracks parenthesis depth so a prefix ends with its `.service(`.
+    fn registered_routes() -> Vec<(String, String)> {
+        let src: String = include_str!("main.rs")
+            .lines()
+            .map(|line| match line.find("//") {
+                Some(at) if line[..at].trim().is_empty() || line[..at].ends_with(' ') => {
+                    &line[..at]
+                }
+                _ => line,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+
+        let mut routes = Vec::new();
+        let mut prefixes: Vec<(i32, String)> = Vec::new();
+        let mut depth = 0;
+        for (i, c) in src.char_indices() {
+            let rest = &src[i..];
+            for opener in ["web::scope(\"", "web::resource(\""] {
+                if let Some(tail) = rest.strip_prefix(opener) {
+                    prefixes.push((depth, literal(tail).to_string()));
+                }
+            }
+            if let Some(tail) = rest.strip_prefix(".route(") {
+                let tail = tail.trim_start();
+                let (path, tail) = match tail.strip_prefix('"') {
+                    Some(quoted) => {
+                        let path = literal(quoted);
+                        let after = &quoted[path.len() + 1..];
+                        (path, after.trim_start().trim_start_matches(',').trim_start())
+                    }
+                    None => ("", tail),
+                };
+                let method = tail.strip_prefix("web::").unwrap();
+                let method = &method[..method.find('(').unwrap()];
+                let prefix: String = prefixes.iter().map(|(_, p)| p.as_str()).collect();
+                routes.push((method.to_string(), format!("{prefix}{path}")));
+            }
+            match c {
+                '(' => depth += 1,
+                ')' => {
+                    depth -= 1;
+                    while prefixes.last().is_some_and(|(d, _)| *d > depth) {
+                        prefixes.pop();
+                    }
+                }
+                _ => {}
+            }
+        }
+        routes
+    }
+
+    #[test]
+    fn every_api_route_is_in_the_spec() {
+        let spec = ApiDoc::openapi();
+        let routes = registered_routes();
+        assert!(routes.len() > 50, "route scan found only {routes:?}");
+
+        let missing: Vec<_> = routes
+            .iter()
+            .filter(|(_, path)| path.starts_with("/api/") && path != "/api/openapi.json")
+            .filter(|(method, path)| {
+                let Some(item) = spec.paths.paths.get(path) else {
+                    return true;
+                };
+                let operation = match method.as_str() {
+                    "get" => &item.get,
+                    "post" => &item.post,
+                    "put" => &item.put,
+                    "delete" => &item.delete,
+                    "patch" => &item.patch,
+                    "head" => &item.head,
+                    other => panic!("unexpected method {other} for {path}"),
+                };
+                operation.is_none()
+            })
+            .collect();
+        assert!(missing.is_empty(), "routes missing from ApiDoc: {missing:?}");
+    }
+
+    #[test]
+    fn every_spec_path_is_routed() {
+        let routes = registered_routes();
+        let stale: Vec<_> = ApiDoc::openapi()
+            .paths
+            .paths
+            .into_keys()
+            .filter(|path| !routes.iter().any(|(_, routed)| routed == path))
+            .collect();
+        assert!(stale.is_empty(), "ApiDoc paths with no route: {stale:?}");
+    }
+
+    #[test]
+    fn docs_page_loads_nothing_from_other_origins() {
+        assert!(!DOCS_PAGE.contains("http://") && !DOCS_PAGE.contains("https://"));
+    }
+}
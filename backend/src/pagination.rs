@@ -0,0 +1,145 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use utoipa::openapi::schema::Schema;
+use utoipa::openapi::RefOr;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::apierror::{ApiError, ErrorCode};
+
+/// Page size when the client pages without giving a limit.
+const DEFAULT_PAGE: usize = 50;
+const MAX_PAGE: usize = 500;
+
+/// Paging, sorting and field selection shared by the list endpoints.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListQuery {
+    /// Items per page (default 50, at most 500). Setting limit, offset or cursor returns
+    /// a `{items, total, nextCursor}` page instead of the bare list.
+    pub limit: Option<usize>,
+    pub offset: Option<usize>,
+    /// `nextCursor` of the previous page; takes precedence over offset.
+    pub cursor: Option<String>,
+    /// Sort key; prefix with '-' for descending. An unknown key lists the valid ones.
+    pub sort: Option<String>,
+    /// Comma-separated list of item fields to return (camelCase).
+    pub fields: Option<String>,
+}
+
+impl ListQuery {
+    /// Whether the client asked for a page rather than the whole list.
+    pub fn paged(&self) -> bool {
+        self.limit.is_some() || self.offset.is_some() || self.cursor.is_some()
+    }
+}
+
+/// One page of a list.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Page {
+    #[schema(value_type = Vec<Object>)]
+    pub items: Vec<serde_json::Value>,
+    /// Items in the whole list.
+    pub total: usize,
+    /// Pass as `cursor` for the next page; null on the last page.
+    pub next_cursor: Option<String>,
+}
+
+/// A key a list can be sorted by.
+pub type SortKey<T> = (&'static str, fn(&T, &T) -> Ordering);
+
+fn invalid(message: String, allowed: Vec<&str>) -> ApiError {
+    ApiError::new(ErrorCode::InvalidQuery, message).with_detail("allowed", allowed)
+}
+
+/// Top-level field names of `T` as serialized, from its OpenAPI schema.
+fn field_names<T: ToSchema>() -> Vec<String> {
+    match T::schema() {
+        RefOr::T(Schema::Object(object)) => object.properties.into_keys().collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// Sort, page and project `items` (already filtered, in the endpoint's default order) as
+/// `query` asks. Without paging parameters the result is the bare list, as before.
+pub fn apply<T: Serialize + ToSchema>(
+    query: &ListQuery,
+    mut items: Vec<T>,
+    sorts: &[SortKey<T>],
+) -> Result<serde_json::Value, ApiError> {
+    let fields: Option<Vec<&str>> = query.fields.as_deref().map(|fields| {
+        fields
+            .split(',')
+            .map(|f| f.trim())
+            .filter(|f| !f.is_empty())
+            .collect()
+    });
+    if let Some(ref fields) = fields {
+        let known = field_names::<T>();
+        if let Some(unknown) = fields.iter().find(|f| !known.iter().any(|k| k == *f)) {
+            return Err(invalid(
+                format!("Unknown field '{}'", unknown),
+                known.iter().map(String::as_str).collect(),
+            ));
+        }
+    }
+
+    if let Some(ref sort) = query.sort {
+        let (key, descending) = match sort.strip_prefix('-') {
+            Some(key) => (key, true),
+            None => (sort.as_str(), false),
+        };
+        let Some((_, compare)) = sorts.iter().find(|(name, _)| *name == key) else {
+            return Err(invalid(
+                format!("Invalid sort '{}' (prefix '-' for descending)", key),
+                sorts.iter().map(|(name, _)| *name).collect(),
+            ));
+        };
+        if descending {
+            items.sort_by(|a, b| compare(b, a));
+        } else {
+            items.sort_by(compare);
+        }
+    }
+
+    let total = items.len();
+    let (offset, limit) = if query.paged() {
+        let offset = match query.cursor {
+            Some(ref cursor) => cursor
+                .parse()
+                .map_err(|_| ApiError::new(ErrorCode::InvalidQuery, "Invalid cursor"))?,
+            None => query.offset.unwrap_or(0),
+        };
+        (
+            offset,
+            query.limit.unwrap_or(DEFAULT_PAGE).clamp(1, MAX_PAGE),
+        )
+    } else {
+        (0, total)
+    };
+
+    let items: Vec<serde_json::Value> = items
+        .iter()
+        .skip(offset)
+        .take(limit)
+        .filter_map(|item| serde_json::to_value(item).ok())
+        .map(|value| match (value, &fields) {
+            (serde_json::Value::Object(map), Some(fields)) => serde_json::Value::Object(
+                map.into_iter()
+                    .filter(|(k, _)| fields.contains(&k.as_str()))
+                    .collect(),
+            ),
+            (value, _) => value,
+        })
+        .collect();
+
+    if !query.paged() {
+        return Ok(serde_json::Value::Array(items));
+    }
+    let end = offset.saturating_add(limit);
+    let page = Page {
+        items,
+        total,
+        next_cursor: (end < total).then(|| end.to_string()),
+    };
+    Ok(serde_json::to_value(page).unwrap_or_default())
+}
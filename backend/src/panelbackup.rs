@@ -0,0 +1,605 @@
+use actix_web::http::header;
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use futures_util::StreamExt;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hasher};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::apierror::ApiError;
+use crate::audit;
+use crate::auth::require_recent_auth;
+use crate::config::{password_path, AppConfig, CONFIG_FILE, PASSWORD_FILE};
+use crate::notifications::Notifier;
+use crate::registry::ServerRegistry;
+use crate::scheduler::Scheduler;
+use crate::snippets::Snippets;
+use crate::storage::{self, SqliteStore, DB_FILE};
+
+/// Bumped when the archive layout changes in a way older panels can't restore.
+const FORMAT_VERSION: u32 = 1;
+const MANIFEST_FILE: &str = "manifest.json";
+/// How long the token from a first restore request stays valid.
+const CONFIRM_TTL: Duration = Duration::from_secs(600);
+const BLOCK: usize = 512;
+
+/// First entry of every backup archive.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupManifest {
+    pub format: u32,
+    /// Version of the panel that made the backup.
+    pub panel_version: String,
+    pub created_at: DateTime<Utc>,
+    /// Database schema version; backups from newer panels are refused.
+    pub schema_version: usize,
+    pub files: Vec<BackupFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupFile {
+    pub name: String,
+    pub size: u64,
+}
+
+/// Temp directory removed with everything in it when dropped.
+struct Scratch(PathBuf);
+
+impl Scratch {
+    fn new() -> std::io::Result<Self> {
+        let dir = std::env::temp_dir().join(format!("panel-backup-{}", Uuid::new_v4().simple()));
+        std::fs::create_dir(&dir)?;
+        Ok(Self(dir))
+    }
+}
+
+impl Drop for Scratch {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+pub fn archive_name(created_at: DateTime<Utc>) -> String {
+    format!("panel-backup-{}.tar.gz", created_at.format("%Y%m%d-%H%M%S"))
+}
+
+/// Build a backup: a consistent snapshot of the panel database plus config.yaml and the
+/// changed admin password, if any, as a tar.gz with the manifest first. Monitor history is left out; it is rewritten from
+/// memory at shutdown, and the map image cache lives only in memory.
+pub fn create_archive() -> anyhow::Result<(BackupManifest, Vec<u8>)> {
+    let scratch = Scratch::new()?;
+    let snapshot = scratch.0.join(DB_FILE);
+    storage::snapshot(Path::new(DB_FILE), &snapshot)?;
+
+    let mut files = vec![(DB_FILE, std::fs::read(&snapshot)?)];
+    match std::fs::read(CONFIG_FILE) {
+        Ok(config) => files.push((CONFIG_FILE, config)),
+        Err(e) => tracing::warn!("Panel backup without {}: {}", CONFIG_FILE, e),
+    }
+    match std::fs::read(password_path()) {
+        Ok(hash) => files.push((PASSWORD_FILE, hash)),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+        Err(e) => tracing::warn!("Panel backup without {}: {}", PASSWORD_FILE, e),
+    }
+
+    let manifest = BackupManifest {
+        format: FORMAT_VERSION,
+        panel_version: env!("CARGO_PKG_VERSION").to_string(),
+        created_at: Utc::now(),
+        schema_version: storage::SCHEMA_VERSION,
+        files: files
+            .iter()
+            .map(|(name, data)| BackupFile {
+                name: name.to_string(),
+                size: data.len() as u64,
+            })
+            .collect(),
+    };
+    let mtime = manifest.created_at.timestamp().max(0) as u64;
+
+    let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+    write_entry(
+        &mut gz,
+        MANIFEST_FILE,
+        &serde_json::to_vec_pretty(&manifest)?,
+        mtime,
+    )?;
+    for (name, data) in &files {
+        write_entry(&mut gz, name, data, mtime)?;
+    }
+    gz.write_all(&[0; 2 * BLOCK])?;
+    Ok((manifest, gz.finish()?))
+}
+
+/// Append one regular file to a ustar stream.
+fn write_entry(out: &mut impl Write, name: &str, data: &[u8], mtime: u64) -> std::io::Result<()> {
+    fn octal(field: &mut [u8], value: u64) {
+        let digits = format!("{:0width$o}", value, width = field.len() - 1);
+        field[..digits.len()].copy_from_slice(digits.as_bytes());
+    }
+
+    let mut head = [0u8; BLOCK];
+    head[..name.len()].copy_from_slice(name.as_bytes());
+    octal(&mut head[100..108], 0o600);
+    octal(&mut head[108..116], 0);
+    octal(&mut head[116..124], 0);
+    octal(&mut head[124..136], data.len() as u64);
+    octal(&mut head[136..148], mtime);
+    head[156] = b'0';
+    head[257..263].copy_from_slice(b"ustar\0");
+    head[263..265].copy_from_slice(b"00");
+    head[148..156].fill(b' ');
+    let sum: u64 = head.iter().map(|&b| b as u64).sum();
+    octal(&mut head[148..155], sum);
+
+    out.write_all(&head)?;
+    out.write_all(data)?;
+    out.write_all(&vec![0; (BLOCK - data.len() % BLOCK) % BLOCK])
+}
+
+fn parse_octal(field: &[u8]) -> Option<u64> {
+    let text = std::str::from_utf8(field).ok()?;
+    let text = text.trim_matches(|c: char| c == '\0' || c == ' ');
+    u64::from_str_radix(text, 8).ok()
+}
+
+/// Unpack a backup made by `create_archive` and check it against its manifest. Only the
+/// files a backup can hold are accepted, so nothing in it names a path to write to.
+fn read_archive(
+    data: &[u8],
+    max_bytes: u64,
+) -> Result<(BackupManifest, HashMap<String, Vec<u8>>), String> {
+    let mut tar = Vec::new();
+    GzDecoder::new(data)
+        .take(max_bytes + 1)
+        .read_to_end(&mut tar)
+        .map_err(|e| format!("Not a gzip archive: {}", e))?;
+    if tar.len() as u64 > max_bytes {
+        return Err(format!("Backup unpacks to more than {} bytes", max_bytes));
+    }
+
+    let mut entries: HashMap<String, Vec<u8>> = HashMap::new();
+    let mut order = Vec::new();
+    let mut pos = 0;
+    while pos + BLOCK <= tar.len() {
+        let head = &tar[pos..pos + BLOCK];
+        if head.iter().all(|&b| b == 0) {
+            break;
+        }
+        let stored = parse_octal(&head[148..156]).ok_or("Corrupt tar header")?;
+        let sum: u64 = head
+            .iter()
+            .enumerate()
+            .map(|(i, &b)| {
+                if (148..156).contains(&i) {
+                    b' ' as u64
+                } else {
+                    b as u64
+                }
+            })
+            .sum();
+        if sum != stored {
+            return Err("Tar header checksum mismatch".to_string());
+        }
+        let name_end = head[..100].iter().position(|&b| b == 0).unwrap_or(100);
+        let name = String::from_utf8_lossy(&head[..name_end]).into_owned();
+        let size = parse_octal(&head[124..136]).ok_or("Corrupt tar header")? as usize;
+        let start = pos + BLOCK;
+        let end = start.checked_add(size).filter(|&end| end <= tar.len());
+        let end = end.ok_or_else(|| format!("'{}' is truncated", name))?;
+        if !matches!(head[156], b'0' | 0) {
+            return Err(format!("'{}' is not a regular file", name));
+        }
+        if ![MANIFEST_FILE, DB_FILE, CONFIG_FILE, PASSWORD_FILE].contains(&name.as_str()) {
+            return Err(format!("Unexpected file '{}' in backup", name));
+        }
+        if entries
+            .insert(name.clone(), tar[start..end].to_vec())
+            .is_some()
+        {
+            return Err(format!("'{}' appears twice", name));
+        }
+        order.push(name);
+        pos = start + size.div_ceil(BLOCK) * BLOCK;
+    }
+
+    if order.first().map(String::as_str) != Some(MANIFEST_FILE) {
+        return Err("Not a panel backup: the manifest is missing".to_string());
+    }
+    let manifest: BackupManifest = serde_json::from_slice(&entries.remove(MANIFEST_FILE).unwrap())
+        .map_err(|e| format!("Invalid manifest: {}", e))?;
+    if manifest.format != FORMAT_VERSION {
+        return Err(format!(
+            "Backup format {} is not supported (expected {})",
+            manifest.format, FORMAT_VERSION
+        ));
+    }
+    if manifest.schema_version > storage::SCHEMA_VERSION {
+        return Err(format!(
+            "Backup is from a newer panel ({}, schema {}); upgrade before restoring",
+            manifest.panel_version, manifest.schema_version
+        ));
+    }
+    if entries.len() != manifest.files.len() {
+        return Err("Backup contents don't match its manifest".to_string());
+    }
+    for file in &manifest.files {
+        match entries.get(&file.name) {
+            Some(data) if data.len() as u64 == file.size => {}
+            Some(_) => return Err(format!("'{}' has the wrong size", file.name)),
+            None => return Err(format!("'{}' is listed but missing", file.name)),
+        }
+    }
+    match entries.get(DB_FILE) {
+        Some(db) if db.starts_with(b"SQLite format 3\0") => {}
+        Some(_) => return Err(format!("{} is not a SQLite database", DB_FILE)),
+        None => return Err(format!("Backup has no {}", DB_FILE)),
+    }
+    Ok((manifest, entries))
+}
+
+/// GET /api/panel/backup
+///
+/// Streams a tar.gz of the panel database, config.yaml and a changed admin password, with
+/// a manifest.
+#[utoipa::path(
+    get,
+    path = "/api/panel/backup",
+    tag = "panel",
+    responses(
+        (status = 200, description = "tar.gz archive", content_type = "application/gzip"),
+        (status = 500, description = "Snapshot failed", body = ApiError),
+    )
+)]
+pub async fn download_backup() -> Result<HttpResponse, ApiError> {
+    let (manifest, archive) = web::block(create_archive)
+        .await
+        .map_err(|e| ApiError::internal(e.to_string()))?
+        .map_err(|e| ApiError::internal(format!("Failed to create panel backup: {}", e)))?;
+    Ok(HttpResponse::Ok()
+        .content_type("application/gzip")
+        .insert_header((
+            header::CONTENT_DISPOSITION,
+            format!(
+                "attachment; filename=\"{}\"",
+                archive_name(manifest.created_at)
+            ),
+        ))
+        .body(archive))
+}
+
+/// A restore token, handed to one user for one archive.
+struct PendingRestore {
+    user: String,
+    digest: u64,
+    issued: Instant,
+}
+
+/// What a restore replaces and reloads, and the tokens confirming one.
+pub struct PanelData {
+    pub store: Arc<SqliteStore>,
+    pub registry: Arc<ServerRegistry>,
+    pub scheduler: Arc<Scheduler>,
+    pub notifier: Arc<Notifier>,
+    pub snippets: Arc<Snippets>,
+    pending: Mutex<HashMap<String, PendingRestore>>,
+    /// Held while a restore runs, so two can't interleave.
+    restoring: tokio::sync::Mutex<()>,
+}
+
+impl PanelData {
+    pub fn new(
+        store: Arc<SqliteStore>,
+        registry: Arc<ServerRegistry>,
+        scheduler: Arc<Scheduler>,
+        notifier: Arc<Notifier>,
+        snippets: Arc<Snippets>,
+    ) -> Self {
+        Self {
+            store,
+            registry,
+            scheduler,
+            notifier,
+            snippets,
+            pending: Mutex::new(HashMap::new()),
+            restoring: tokio::sync::Mutex::new(()),
+        }
+    }
+
+    fn issue_token(&self, user: &str, digest: u64) -> String {
+        let token = Uuid::new_v4().to_string();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, p| p.issued.elapsed() < CONFIRM_TTL);
+        pending.insert(
+            token.clone(),
+            PendingRestore {
+                user: user.to_string(),
+                digest,
+                issued: Instant::now(),
+            },
+        );
+        token
+    }
+
+    /// Use up `token` if this user got it for this archive and it hasn't expired.
+    fn redeem_token(&self, token: &str, user: &str, digest: u64) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get(token) {
+            Some(p) if p.user == user && p.digest == digest && p.issued.elapsed() < CONFIRM_TTL => {
+                pending.remove(token);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreQuery {
+    /// Token from the 409 answer to the first upload of the same archive.
+    pub confirm_token: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RestoreResult {
+    pub manifest: BackupManifest,
+    /// Servers, schedules, job history, wipes, notification settings and snippets were
+    /// reloaded in place.
+    pub reloaded: bool,
+    /// config.yaml or the admin password was replaced; they take effect when the panel
+    /// restarts.
+    pub restart_required: bool,
+}
+
+/// POST /api/panel/restore
+///
+/// The body is an archive from GET /api/panel/backup. The first upload is only checked:
+/// it answers 409 with the manifest and a `confirmToken` in `details`. Uploading the same
+/// archive again with that token (within 10 minutes) restores it.
+#[utoipa::path(
+    post,
+    path = "/api/panel/restore",
+    tag = "panel",
+    params(RestoreQuery),
+    request_body(content = Vec<u8>, content_type = "application/gzip"),
+    responses(
+        (status = 200, body = RestoreResult),
+        (status = 400, description = "Not a usable panel backup", body = ApiError),
+        (status = 403, description = "Token too old; log in again", body = ApiError),
+        (status = 409, description = "Confirmation required, or a restore is running", body = ApiError),
+        (status = 500, description = "Restore failed; the database is unchanged", body = ApiError),
+    )
+)]
+pub async fn restore_backup(
+    req: HttpRequest,
+    query: web::Query<RestoreQuery>,
+    mut payload: web::Payload,
+    config: web::Data<AppConfig>,
+    panel: web::Data<Arc<PanelData>>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = require_recent_auth(&req, config.auth.reauth_window_mins)?;
+
+    let mut archive = web::BytesMut::new();
+    while let Some(chunk) = payload.next().await {
+        let chunk = chunk.map_err(|e| ApiError::validation(format!("Upload failed: {}", e)))?;
+        if (archive.len() + chunk.len()) as u64 > config.limits.max_request_bytes {
+            return Err(ApiError::validation(format!(
+                "Backup exceeds {} bytes",
+                config.limits.max_request_bytes
+            )));
+        }
+        archive.extend_from_slice(&chunk);
+    }
+    let mut hasher = DefaultHasher::new();
+    hasher.write(&archive);
+    let digest = hasher.finish();
+
+    let (manifest, mut files) =
+        read_archive(&archive, config.limits.max_request_bytes).map_err(ApiError::validation)?;
+
+    let confirmed = query
+        .confirm_token
+        .as_deref()
+        .is_some_and(|token| panel.redeem_token(token, &claims.sub, digest));
+    if !confirmed {
+        return Err(ApiError::conflict(
+            "Restoring replaces all panel data; upload the same archive again with confirmToken",
+        )
+        .with_detail("confirmToken", panel.issue_token(&claims.sub, digest))
+        .with_detail("manifest", &manifest));
+    }
+    let _restoring = panel
+        .restoring
+        .try_lock()
+        .map_err(|_| ApiError::conflict("A restore is already running"))?;
+
+    tracing::info!(
+        target: "audit",
+        user = %claims.sub,
+        created_at = %manifest.created_at,
+        panel_version = %manifest.panel_version,
+        "Restoring panel backup"
+    );
+    audit::detail(
+        &req,
+        format!(
+            "backup of {} (panel {})",
+            manifest.created_at, manifest.panel_version
+        ),
+    );
+    let db = files.remove(DB_FILE).unwrap_or_default();
+    let config_file = files.remove(CONFIG_FILE);
+    let password_file = files.remove(PASSWORD_FILE);
+    let store = panel.store.clone();
+    let restart_required = web::block(move || -> anyhow::Result<bool> {
+        // Checked before anything is replaced, so a bad config can't leave a half restore
+        let config_text = match config_file {
+            Some(data) if std::fs::read(CONFIG_FILE).is_ok_and(|current| current == data) => None,
+            Some(data) => {
+                let text = String::from_utf8(data)?;
+                serde_yaml::from_str::<AppConfig>(&text).map_err(|e| {
+                    anyhow::anyhow!("{} in the backup is invalid: {}", CONFIG_FILE, e)
+                })?;
+                Some(text)
+            }
+            None => None,
+        };
+        let password = password_path();
+        let password_text = match password_file {
+            Some(data) if std::fs::read(&password).is_ok_and(|current| current == data) => None,
+            Some(data) => Some(String::from_utf8(data)?),
+            None => None,
+        };
+
+        let scratch = Scratch::new()?;
+        let staged = scratch.0.join(DB_FILE);
+        std::fs::write(&staged, db)?;
+        store.restore_from(&staged)?;
+
+        let mut restart_required = false;
+        if let Some(text) = config_text {
+            crate::persistence::write_atomic(Path::new(CONFIG_FILE), &text)?;
+            restart_required = true;
+        }
+        // Like config.yaml, the restored password is read at startup
+        if let Some(hash) = password_text {
+            if let Some(dir) = password.parent() {
+                std::fs::create_dir_all(dir)?;
+            }
+            crate::persistence::write_atomic(&password, &hash)?;
+            restart_required = true;
+        }
+        Ok(restart_required)
+    })
+    .await
+    .map_err(|e| ApiError::internal(e.to_string()))?
+    .map_err(|e| ApiError::internal(format!("Restore failed: {}", e)))?;
+
+    panel
+        .registry
+        .reload(&config.servers, &config.monitor)
+        .await;
+    panel.scheduler.reload().await;
+    panel.notifier.reload().await;
+    panel.snippets.reload().await;
+    tracing::info!(
+        "Restored panel backup from {}{}",
+        manifest.created_at,
+        if restart_required {
+            "; restart the panel to apply config.yaml and the admin password"
+        } else {
+            ""
+        }
+    );
+
+    Ok(HttpResponse::Ok().json(RestoreResult {
+        manifest,
+        reloaded: true,
+        restart_required,
+    }))
+}
+
+/// Payload for PanelBackup jobs: where to put each backup. At least one of the two.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub struct PanelBackupTarget {
+    /// Directory the archive is written to.
+    pub path: Option<String>,
+    /// Keep only this many panel backups in `path` (0 keeps all).
+    #[serde(default)]
+    pub keep: usize,
+    /// URL the archive is POSTed to, as an application/gzip body.
+    pub url: Option<String>,
+}
+
+impl PanelBackupTarget {
+    pub fn parse(payload: Option<&str>) -> Result<Self, String> {
+        let payload = payload.ok_or("Panel backup jobs need a payload with path and/or url")?;
+        let target: Self = serde_json::from_str(payload)
+            .map_err(|e| format!("Invalid panel backup target: {}", e))?;
+        if target.path.is_none() && target.url.is_none() {
+            return Err("Panel backup jobs need a path, a url, or both".to_string());
+        }
+        if target
+            .path
+            .as_deref()
+            .is_some_and(|p| !Path::new(p).is_absolute())
+        {
+            return Err("Panel backup path must be absolute".to_string());
+        }
+        if let Some(ref url) = target.url {
+            crate::webhook::validate_url(url)?;
+        }
+        Ok(target)
+    }
+}
+
+/// Scheduled backup: write the archive to the target directory and/or POST it.
+pub async fn run_scheduled(payload: Option<&str>) -> Result<String, String> {
+    let target = PanelBackupTarget::parse(payload)?;
+    let (manifest, archive) = web::block(create_archive)
+        .await
+        .map_err(|e| e.to_string())?
+        .map_err(|e| format!("Failed to create panel backup: {}", e))?;
+    let name = archive_name(manifest.created_at);
+    let archive = bytes::Bytes::from(archive);
+    let mut output = vec![format!("Created {} ({} bytes)", name, archive.len())];
+
+    if let Some(dir) = target.path.clone() {
+        let (name, archive) = (name.clone(), archive.clone());
+        let saved = web::block(move || save_to_dir(Path::new(&dir), &name, &archive, target.keep))
+            .await
+            .map_err(|e| e.to_string())?
+            .map_err(|e| format!("Failed to save panel backup: {}", e))?;
+        output.push(saved);
+    }
+    if let Some(ref url) = target.url {
+        output.push(crate::webhook::post_file(url, &name, "application/gzip", archive).await?);
+    }
+    Ok(output.join("\n"))
+}
+
+/// Write the archive into `dir` (via a temp file), then prune older panel backups there.
+fn save_to_dir(dir: &Path, name: &str, archive: &[u8], keep: usize) -> std::io::Result<String> {
+    std::fs::create_dir_all(dir)?;
+    let path = dir.join(name);
+    let part = dir.join(format!("{}.part", name));
+    {
+        let mut file = std::fs::File::create(&part)?;
+        file.write_all(archive)?;
+        file.sync_all()?;
+    }
+    std::fs::rename(&part, &path)?;
+    let mut saved = format!("Saved to {}", path.display());
+    if keep == 0 {
+        return Ok(saved);
+    }
+
+    // The timestamped names sort oldest first
+    let mut existing: Vec<String> = std::fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .map(|e| e.file_name().to_string_lossy().into_owned())
+        .filter(|n| n.starts_with("panel-backup-") && n.ends_with(".tar.gz"))
+        .collect();
+    existing.sort();
+    let excess = existing.len().saturating_sub(keep);
+    for old in &existing[..excess] {
+        match std::fs::remove_file(dir.join(old)) {
+            Ok(()) => saved.push_str(&format!("\nPruned {}", old)),
+            Err(e) => tracing::warn!("Failed to prune panel backup {}: {}", old, e),
+        }
+    }
+    Ok(saved)
+}
@@ -1,30 +1,262 @@
-use std::path::Path;
-
-use crate::registry::ServerDefinition;
-
-const SERVERS_FILE: &str = "servers.json";
-
-/// Load dynamically created servers from servers.json.
-pub fn load_servers() -> Vec<ServerDefinition> {
-    let path = Path::new(SERVERS_FILE);
-    if !path.exists() {
-        return Vec::new();
-    }
-    match std::fs::read_to_string(path) {
-        Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
-            tracing::warn!("Failed to parse {}: {}", SERVERS_FILE, e);
-            Vec::new()
-        }),
-        Err(e) => {
-            tracing::warn!("Failed to read {}: {}", SERVERS_FILE, e);
-            Vec::new()
+use chrono::{DateTime, Utc};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
+
+use crate::auth::RefreshToken;
+use crate::bans::BanRecord;
+use crate::lgsm::ActionRecord;
+use crate::playertracker::PlayerRecord;
+use crate::registry::{DesiredState, Maintenance, ServerDefinition};
+use crate::rollup::{Bucket, Tier};
+use crate::scheduler::{JobRun, PauseWindow, ScheduledJob};
+use crate::wipes::WipeRecord;
+
+/// Previous generation of `path`, kept by `write_atomic`.
+fn backup_path(path: &Path) -> PathBuf {
+    let mut backup = path.as_os_str().to_owned();
+    backup.push(".bak.1");
+    PathBuf::from(backup)
+}
+
+/// Write `content` to a temp file next to `path`, fsync it, then rename it over `path`,
+/// so readers and crashes never see a half-written file. The file being replaced is kept
+/// as `<path>.bak.1` for `read_json` to fall back to.
+pub fn write_atomic(path: &Path, content: &str) -> std::io::Result<()> {
+    let mut tmp = path.as_os_str().to_owned();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    {
+        let mut file = std::fs::File::create(&tmp)?;
+        std::io::Write::write_all(&mut file, content.as_bytes())?;
+        file.sync_all()?;
+    }
+    if path.exists() {
+        std::fs::rename(path, backup_path(path))?;
+    }
+    std::fs::rename(&tmp, path)?;
+    // Make the renames themselves durable
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    std::fs::File::open(dir)?.sync_all()
+}
+
+/// Read JSON written by `write_atomic`. None if neither the file nor its backup exists.
+/// If the file is missing or unreadable (e.g. truncated by a crash before atomic writes),
+/// the backup is used instead and the failure is logged as an error, never swallowed.
+pub fn read_json<T: DeserializeOwned>(path: &Path) -> anyhow::Result<Option<T>> {
+    fn parse<T: DeserializeOwned>(path: &Path) -> anyhow::Result<T> {
+        Ok(serde_json::from_str(&std::fs::read_to_string(path)?)?)
+    }
+
+    let backup = backup_path(path);
+    if !path.exists() && !backup.exists() {
+        return Ok(None);
+    }
+    let primary_err = match parse(path) {
+        Ok(value) => return Ok(Some(value)),
+        Err(e) => e,
+    };
+    if !backup.exists() {
+        return Err(primary_err);
+    }
+    tracing::error!(
+        "Failed to read {}: {}; falling back to {}",
+        path.display(),
+        primary_err,
+        backup.display()
+    );
+    // Set the bad file aside so the next write does not rotate it over the good backup
+    if path.exists() {
+        let mut corrupt = path.as_os_str().to_owned();
+        corrupt.push(".corrupt");
+        if let Err(e) = std::fs::rename(path, &corrupt) {
+            tracing::warn!("Failed to move {} aside: {}", path.display(), e);
         }
     }
+    parse(&backup)
+        .map(Some)
+        .map_err(|e| anyhow::anyhow!("{} and its backup are unreadable: {}", path.display(), e))
+}
+
+/// Panel-side settings for a static server that config.yaml cannot express.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StaticOverride {
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub monitoring_paused: bool,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Only set when it differs from config.yaml.
+    #[serde(default)]
+    pub auto_start: Option<bool>,
+    #[serde(default)]
+    pub last_wipe_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub desired_state: DesiredState,
+    #[serde(default)]
+    pub maintenance: Option<Maintenance>,
+}
+
+/// Where the registry keeps dynamically created servers and static-server overrides.
+/// `storage::SqliteStore` is the real one; anything else (an in-memory database in tests)
+/// only has to implement these.
+pub trait ServerStore: Send + Sync {
+    fn load_servers(&self) -> anyhow::Result<Vec<ServerDefinition>>;
+    fn save_servers(&self, defs: &[ServerDefinition]) -> anyhow::Result<()>;
+    /// Overrides for static servers, keyed by server id.
+    fn load_static_overrides(&self) -> anyhow::Result<HashMap<String, StaticOverride>>;
+    fn save_static_overrides(
+        &self,
+        overrides: &HashMap<String, StaticOverride>,
+    ) -> anyhow::Result<()>;
+    /// Append to a server's wipe history.
+    fn add_wipe(&self, server_id: &str, wipe: &WipeRecord) -> anyhow::Result<()>;
+    /// A server's wipe history, newest first.
+    fn load_wipes(&self, server_id: &str) -> anyhow::Result<Vec<WipeRecord>>;
+    fn delete_wipes(&self, server_id: &str) -> anyhow::Result<()>;
+    /// Append to a server's action history, dropping its oldest entries past
+    /// `lgsm::MAX_ACTIONS_PER_SERVER`.
+    fn add_action(&self, server_id: &str, action: &ActionRecord) -> anyhow::Result<()>;
+    /// A server's action history, newest first.
+    fn load_actions(&self, server_id: &str) -> anyhow::Result<Vec<ActionRecord>>;
+    fn delete_actions(&self, server_id: &str) -> anyhow::Result<()>;
+    /// Insert or replace the server's ban of `ban.steam_id`.
+    fn save_ban(&self, server_id: &str, ban: &BanRecord) -> anyhow::Result<()>;
+    /// A server's bans, newest first.
+    fn load_bans(&self, server_id: &str) -> anyhow::Result<Vec<BanRecord>>;
+    /// Bans on any server that should have been lifted by `now`, with their server ids.
+    fn load_bans_due(&self, now: DateTime<Utc>) -> anyhow::Result<Vec<(String, BanRecord)>>;
+    fn delete_ban(&self, server_id: &str, steam_id: &str) -> anyhow::Result<()>;
+    fn delete_bans(&self, server_id: &str) -> anyhow::Result<()>;
+    /// Insert or replace player records, keyed by Steam ID.
+    fn save_players(&self, server_id: &str, players: &[PlayerRecord]) -> anyhow::Result<()>;
+    fn load_player(&self, server_id: &str, steam_id: &str) -> anyhow::Result<Option<PlayerRecord>>;
+    /// Every player seen on a server, most recently seen first.
+    fn load_players(&self, server_id: &str) -> anyhow::Result<Vec<PlayerRecord>>;
+    fn delete_players(&self, server_id: &str) -> anyhow::Result<()>;
+}
+
+/// Long-term monitor history: rollups of a source's snapshots (the host, or one game
+/// server), one per bucket and tier.
+pub trait MetricsStore: Send + Sync {
+    /// Insert or replace the rollup starting at `bucket.timestamp`.
+    fn save_rollup(&self, source: &str, tier: Tier, bucket: &Bucket) -> anyhow::Result<()>;
+    /// Rollups starting in [from, to), oldest first.
+    fn load_rollups(
+        &self,
+        source: &str,
+        tier: Tier,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Bucket>>;
+    /// Drop rollups starting before `before`.
+    fn prune_rollups(&self, source: &str, tier: Tier, before: DateTime<Utc>) -> anyhow::Result<()>;
+    fn delete_rollups(&self, source: &str) -> anyhow::Result<()>;
+}
+
+/// Where the scheduler keeps its jobs, run history (keyed by job id) and pause windows.
+pub trait ScheduleStore: Send + Sync {
+    fn load_jobs(&self) -> anyhow::Result<Vec<ScheduledJob>>;
+    fn save_jobs(&self, jobs: &[ScheduledJob]) -> anyhow::Result<()>;
+    fn load_job_history(&self) -> anyhow::Result<HashMap<String, VecDeque<JobRun>>>;
+    fn save_job_history(&self, history: &HashMap<String, VecDeque<JobRun>>) -> anyhow::Result<()>;
+    fn load_pauses(&self) -> anyhow::Result<Vec<PauseWindow>>;
+    fn save_pauses(&self, pauses: &[PauseWindow]) -> anyhow::Result<()>;
+}
+
+/// Small panel-wide settings documents (notification channels, ...), stored as JSON under
+/// a key. Owners deserialize them into their own types.
+pub trait SettingsStore: Send + Sync {
+    fn load_setting(&self, key: &str) -> anyhow::Result<Option<serde_json::Value>>;
+    fn save_setting(&self, key: &str, value: &serde_json::Value) -> anyhow::Result<()>;
+}
+
+/// Refresh tokens handed out at login, keyed by id. Tokens from one login share a family,
+/// so a whole session can be revoked at once.
+pub trait SessionStore: Send + Sync {
+    fn load_refresh_token(&self, jti: &str) -> anyhow::Result<Option<RefreshToken>>;
+    /// Insert or update a token, dropping any that have expired.
+    fn save_refresh_token(&self, token: &RefreshToken) -> anyhow::Result<()>;
+    fn delete_refresh_family(&self, family: &str) -> anyhow::Result<()>;
+    /// Revoke every session of `username`.
+    fn delete_refresh_tokens_for(&self, username: &str) -> anyhow::Result<()>;
 }
 
-/// Save dynamically created servers to servers.json.
-pub fn save_servers(defs: &[ServerDefinition]) -> anyhow::Result<()> {
-    let content = serde_json::to_string_pretty(defs)?;
-    std::fs::write(SERVERS_FILE, content)?;
-    Ok(())
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Scratch directory removed when dropped.
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("persist-test-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn write_atomic_keeps_the_previous_generation() {
+        let scratch = Scratch::new();
+        let path = scratch.0.join("state.json");
+        write_atomic(&path, "[1]").unwrap();
+        write_atomic(&path, "[1,2]").unwrap();
+
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "[1,2]");
+        assert_eq!(std::fs::read_to_string(backup_path(&path)).unwrap(), "[1]");
+        assert!(!scratch.0.join("state.json.tmp").exists());
+        assert_eq!(read_json::<Vec<u32>>(&path).unwrap(), Some(vec![1, 2]));
+    }
+
+    #[test]
+    fn a_truncated_file_falls_back_to_the_backup_and_is_kept_aside() {
+        let scratch = Scratch::new();
+        let path = scratch.0.join("state.json");
+        write_atomic(&path, "[1]").unwrap();
+        write_atomic(&path, "[1,2]").unwrap();
+        std::fs::write(&path, "[1,").unwrap();
+
+        assert_eq!(read_json::<Vec<u32>>(&path).unwrap(), Some(vec![1]));
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::read_to_string(scratch.0.join("state.json.corrupt")).unwrap(),
+            "[1,"
+        );
+
+        // The next write must not rotate the bad file over the good backup
+        write_atomic(&path, "[3]").unwrap();
+        assert_eq!(read_json::<Vec<u32>>(&path).unwrap(), Some(vec![3]));
+        assert_eq!(std::fs::read_to_string(backup_path(&path)).unwrap(), "[1]");
+    }
+
+    #[test]
+    fn unreadable_files_without_a_backup_are_errors() {
+        let scratch = Scratch::new();
+        let path = scratch.0.join("state.json");
+        assert!(read_json::<Vec<u32>>(&path).unwrap().is_none());
+
+        std::fs::write(&path, "").unwrap();
+        assert!(read_json::<Vec<u32>>(&path).is_err());
+        assert!(
+            path.exists(),
+            "nothing to fall back to, so the file stays put"
+        );
+
+        std::fs::write(backup_path(&path), "{").unwrap();
+        assert!(read_json::<Vec<u32>>(&path).is_err());
+    }
 }
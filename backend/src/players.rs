@@ -1,54 +1,63 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
+use chrono::Utc;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
+use tokio::time::Duration;
+use utoipa::ToSchema;
 
+use crate::apierror::ApiError;
+use crate::audit;
+use crate::bans::{BanRecord, MAX_BAN_HOURS};
+use crate::lgsm;
+use crate::rcon::{format_rich_text, quote_arg};
 use crate::registry::ServerRegistry;
+use crate::requestid;
+use crate::validation::ValidationErrors;
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
-    error: String,
-}
-
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct SuccessBody {
     success: bool,
     message: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct KickRequest {
     pub steam_id: String,
     pub reason: Option<String>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct BanRequest {
     pub steam_id: String,
     pub reason: Option<String>,
+    /// Player name for the banlist.
+    pub name: Option<String>,
+    /// Lift the ban after this many hours; permanent if omitted.
+    pub duration_hours: Option<u32>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UnbanRequest {
     pub steam_id: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ModeratorRequest {
     pub steam_id: String,
     pub display_name: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct RemoveModeratorRequest {
     pub steam_id: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct GiveItemRequest {
     pub steam_id: String,
@@ -56,196 +65,419 @@ pub struct GiveItemRequest {
     pub amount: u32,
 }
 
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MessagePlayerRequest {
+    pub steam_id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BroadcastRequest {
+    pub message: String,
+    pub color: Option<String>,
+    pub size: Option<u32>,
+    /// Send the message this many times (default once).
+    pub repeat: Option<u32>,
+    /// Seconds between repeated sends (default 60).
+    pub interval_secs: Option<u64>,
+}
+
 /// GET /api/servers/{server_id}/players
+///
+/// Served from the game collector's cached playerlist; falls back to RCON while the
+/// cache is empty (collector paused or not polled yet).
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/players",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "`players` from the collector's cached playerlist (plus `updatedAt`), or from RCON while the cache is empty", body = Object),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
 pub async fn list_players(
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
+) -> Result<HttpResponse, ApiError> {
+    if let Some(monitor) = registry.get_game_monitor(&server_id).await {
+        if let Some(snapshot) = monitor.players.read().await.clone() {
+            return Ok(HttpResponse::Ok().json(serde_json::json!({
+                "players": snapshot.players,
+                "updatedAt": snapshot.timestamp,
+            })));
         }
-    };
+    }
+
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     match rcon.player_list().await {
-        Ok(players) => HttpResponse::Ok().json(serde_json::json!({ "players": players })),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to get player list: {}", e),
-        }),
+        Ok(players) => Ok(HttpResponse::Ok().json(serde_json::json!({ "players": players }))),
+        Err(e) => Err(ApiError::rcon(format!("Failed to get player list: {}", e))),
+    }
+}
+
+/// Audit detail for a kick or ban.
+fn player_detail(steam_id: &str, reason: Option<&str>) -> String {
+    match reason {
+        Some(reason) => format!("{} ({})", steam_id, reason),
+        None => steam_id.to_string(),
     }
 }
 
 /// POST /api/servers/{server_id}/players/kick
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/players/kick",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = KickRequest,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
 pub async fn kick_player(
+    req: HttpRequest,
     server_id: web::Path<String>,
     body: web::Json<KickRequest>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    audit::detail(&req, player_detail(&body.steam_id, body.reason.as_deref()));
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     let reason = body.reason.as_deref().unwrap_or("Kicked by admin");
     match rcon.kick(&body.steam_id, reason).await {
-        Ok(msg) => HttpResponse::Ok().json(SuccessBody {
+        Ok(msg) => Ok(HttpResponse::Ok().json(SuccessBody {
             success: true,
             message: format!("Kicked {}: {}", body.steam_id, msg),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to kick player: {}", e),
-        }),
+        })),
+        Err(e) => Err(ApiError::rcon(format!("Failed to kick player: {}", e))),
     }
 }
 
-/// POST /api/servers/{server_id}/players/ban
+/// POST /api/servers/{server_id}/players/ban — ban by Steam ID and record the ban, with
+/// its expiry for temporary bans, in the panel's ban list.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/players/ban",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = BanRequest,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 400, description = "Invalid Steam ID or duration", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
 pub async fn ban_player(
+    req: HttpRequest,
     server_id: web::Path<String>,
     body: web::Json<BanRequest>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    audit::detail(&req, player_detail(&body.steam_id, body.reason.as_deref()));
+    let mut errors = ValidationErrors::new();
+    if body.steam_id.is_empty() || !body.steam_id.chars().all(|c| c.is_ascii_digit()) {
+        errors.add("steamId", "Must be a numeric Steam ID");
+    }
+    if body
+        .duration_hours
+        .is_some_and(|hours| hours == 0 || hours > MAX_BAN_HOURS)
+    {
+        errors.add(
+            "durationHours",
+            format!("Must be from 1 to {} hours", MAX_BAN_HOURS),
+        );
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     let reason = body.reason.as_deref().unwrap_or("Banned by admin");
-    match rcon.ban(&body.steam_id, reason).await {
-        Ok(msg) => HttpResponse::Ok().json(SuccessBody {
-            success: true,
-            message: format!("Banned {}: {}", body.steam_id, msg),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to ban player: {}", e),
-        }),
-    }
+    let name = body.name.as_deref().unwrap_or_default();
+    let msg = rcon
+        .ban_id(&body.steam_id, name, reason)
+        .await
+        .map_err(|e| ApiError::rcon(format!("Failed to ban player: {}", e)))?;
+
+    let created_at = Utc::now();
+    let expires_at = body
+        .duration_hours
+        .map(|hours| created_at + chrono::Duration::hours(hours as i64));
+    let ban = BanRecord {
+        steam_id: body.steam_id.clone(),
+        name: body.name.clone().filter(|n| !n.is_empty()),
+        reason: reason.to_string(),
+        banned_by: lgsm::initiator(&req),
+        created_at,
+        expires_at,
+        expired: false,
+    };
+    registry.save_ban(&server_id, &ban);
+
+    let until = match expires_at {
+        Some(at) => format!(" until {}", at.to_rfc3339()),
+        None => String::new(),
+    };
+    Ok(HttpResponse::Ok().json(SuccessBody {
+        success: true,
+        message: format!("Banned {}{}: {}", body.steam_id, until, msg),
+    }))
 }
 
-/// POST /api/servers/{server_id}/players/unban
+/// POST /api/servers/{server_id}/players/unban — also drops the ban from the panel's list.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/players/unban",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = UnbanRequest,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
 pub async fn unban_player(
+    req: HttpRequest,
     server_id: web::Path<String>,
     body: web::Json<UnbanRequest>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    audit::detail(&req, body.steam_id.clone());
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     match rcon.unban(&body.steam_id).await {
-        Ok(msg) => HttpResponse::Ok().json(SuccessBody {
-            success: true,
-            message: format!("Unbanned {}: {}", body.steam_id, msg),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to unban player: {}", e),
-        }),
+        Ok(msg) => {
+            registry.remove_ban(&server_id, &body.steam_id);
+            Ok(HttpResponse::Ok().json(SuccessBody {
+                success: true,
+                message: format!("Unbanned {}: {}", body.steam_id, msg),
+            }))
+        }
+        Err(e) => Err(ApiError::rcon(format!("Failed to unban player: {}", e))),
     }
 }
 
 /// POST /api/servers/{server_id}/players/moderator
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/players/moderator",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = ModeratorRequest,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
 pub async fn add_moderator(
     server_id: web::Path<String>,
     body: web::Json<ModeratorRequest>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     let cmd = format!(
-        "moderatorid {} \"{}\" \"Added via panel\"",
-        body.steam_id, body.display_name
+        "moderatorid {} {} \"Added via panel\"",
+        quote_arg(&body.steam_id),
+        quote_arg(&body.display_name)
     );
     match rcon.execute(&cmd).await {
         Ok(msg) => {
             let _ = rcon.execute("server.writecfg").await;
-            HttpResponse::Ok().json(SuccessBody {
+            Ok(HttpResponse::Ok().json(SuccessBody {
                 success: true,
                 message: format!("Added moderator {}: {}", body.steam_id, msg),
-            })
+            }))
         }
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to add moderator: {}", e),
-        }),
+        Err(e) => Err(ApiError::rcon(format!("Failed to add moderator: {}", e))),
     }
 }
 
 /// POST /api/servers/{server_id}/players/remove-moderator
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/players/remove-moderator",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = RemoveModeratorRequest,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
 pub async fn remove_moderator(
     server_id: web::Path<String>,
     body: web::Json<RemoveModeratorRequest>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
-    match rcon.execute(&format!("removemoderator {}", body.steam_id)).await {
+    match rcon
+        .execute(&format!("removemoderator {}", quote_arg(&body.steam_id)))
+        .await
+    {
         Ok(msg) => {
             let _ = rcon.execute("server.writecfg").await;
-            HttpResponse::Ok().json(SuccessBody {
+            Ok(HttpResponse::Ok().json(SuccessBody {
                 success: true,
                 message: format!("Removed moderator {}: {}", body.steam_id, msg),
-            })
+            }))
         }
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to remove moderator: {}", e),
-        }),
+        Err(e) => Err(ApiError::rcon(format!("Failed to remove moderator: {}", e))),
     }
 }
 
 /// POST /api/servers/{server_id}/players/give
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/players/give",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = GiveItemRequest,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
 pub async fn give_item(
     server_id: web::Path<String>,
     body: web::Json<GiveItemRequest>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     let cmd = format!(
         "inventory.giveto {} {} {}",
         body.steam_id, body.item, body.amount
     );
     match rcon.execute(&cmd).await {
-        Ok(msg) => HttpResponse::Ok().json(SuccessBody {
+        Ok(msg) => Ok(HttpResponse::Ok().json(SuccessBody {
             success: true,
             message: format!("Gave {} x{} to {}: {}", body.item, body.amount, body.steam_id, msg),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to give item: {}", e),
-        }),
+        })),
+        Err(e) => Err(ApiError::rcon(format!("Failed to give item: {}", e))),
     }
 }
+
+/// POST /api/servers/{server_id}/players/message - private message to one player
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/players/message",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = MessagePlayerRequest,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
+pub async fn message_player(
+    server_id: web::Path<String>,
+    body: web::Json<MessagePlayerRequest>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+
+    match rcon.say_to(&body.steam_id, &body.message).await {
+        Ok(msg) => Ok(HttpResponse::Ok().json(SuccessBody {
+            success: true,
+            message: format!("Message sent to {}: {}", body.steam_id, msg),
+        })),
+        Err(e) => Err(ApiError::rcon(format!("Failed to message player: {}", e))),
+    }
+}
+
+/// POST /api/servers/{server_id}/broadcast - global chat with optional formatting/repeat
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/broadcast",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = BroadcastRequest,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 202, description = "Repeated broadcast scheduled", body = SuccessBody),
+        (status = 400, body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
+pub async fn broadcast(
+    server_id: web::Path<String>,
+    body: web::Json<BroadcastRequest>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+
+    let message = format_rich_text(&body.message, body.color.as_deref(), body.size)
+        .map_err(|e| ApiError::validation(e.to_string()))?;
+
+    let repeat = body.repeat.unwrap_or(1).clamp(1, 60);
+    if repeat == 1 {
+        return match rcon.say(&message).await {
+            Ok(msg) => Ok(HttpResponse::Ok().json(SuccessBody {
+                success: true,
+                message: format!("Broadcast sent: {}", msg),
+            })),
+            Err(e) => Err(ApiError::rcon(format!("Failed to broadcast: {}", e))),
+        };
+    }
+
+    // Repeated announcements run in the background so the request returns immediately
+    let every = Duration::from_secs(body.interval_secs.unwrap_or(60).max(5));
+    let server_id = server_id.into_inner();
+    tokio::spawn(requestid::propagate(async move {
+        if let Err(e) = rcon.announce_repeat(&message, repeat, every).await {
+            tracing::warn!("Repeated broadcast on '{}' stopped: {}", server_id, e);
+        }
+    }));
+
+    Ok(HttpResponse::Accepted().json(SuccessBody {
+        success: true,
+        message: format!(
+            "Broadcast scheduled {} times every {}s",
+            repeat,
+            every.as_secs()
+        ),
+    }))
+}
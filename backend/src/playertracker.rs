@@ -0,0 +1,226 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::apierror::ApiError;
+use crate::pagination::{self, ListQuery, SortKey};
+use crate::rcon::Player;
+use crate::registry::ServerRegistry;
+use crate::shutdown::Shutdown;
+
+/// What the panel knows about one player on one server.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerRecord {
+    pub steam_id: String,
+    /// Name at the last sighting.
+    pub name: String,
+    pub first_seen: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+    /// Time seen online, to the precision of the monitor's poll interval.
+    pub total_playtime_secs: u64,
+    pub session_count: u32,
+}
+
+/// When a server's players were observed, and the Steam IDs online then.
+type Observation = (DateTime<Utc>, HashSet<String>);
+
+/// Turns successive playerlists into per-player records: a player missing from the
+/// previous list has started a session, and time between two lists that both have the
+/// player counts as playtime.
+pub struct PlayerTracker {
+    /// Each server's latest observation.
+    online: Mutex<HashMap<String, Observation>>,
+    /// After a panel restart, a player seen this recently is taken to still be in the
+    /// session they were in before it.
+    resume_within: chrono::Duration,
+}
+
+impl PlayerTracker {
+    pub fn new(poll_interval: Duration) -> Self {
+        let resume_within = (poll_interval * 3).max(Duration::from_secs(60));
+        Self {
+            online: Mutex::new(HashMap::new()),
+            resume_within: chrono::Duration::from_std(resume_within)
+                .unwrap_or(chrono::Duration::minutes(1)),
+        }
+    }
+
+    /// Record the players online on `server_id` at `now`.
+    pub fn observe(
+        &self,
+        registry: &ServerRegistry,
+        server_id: &str,
+        players: &[Player],
+        now: DateTime<Utc>,
+    ) {
+        let present: HashSet<String> = players
+            .iter()
+            .filter(|p| !p.steam_id.is_empty())
+            .map(|p| p.steam_id.clone())
+            .collect();
+        let previous = self
+            .online
+            .lock()
+            .unwrap()
+            .insert(server_id.to_string(), (now, present));
+        if players.is_empty() {
+            return;
+        }
+
+        let mut records = Vec::with_capacity(players.len());
+        for player in players.iter().filter(|p| !p.steam_id.is_empty()) {
+            let mut record = match registry.player_record(server_id, &player.steam_id) {
+                Ok(Some(record)) => record,
+                Ok(None) => PlayerRecord {
+                    steam_id: player.steam_id.clone(),
+                    name: String::new(),
+                    first_seen: now,
+                    last_seen: now,
+                    total_playtime_secs: 0,
+                    session_count: 0,
+                },
+                Err(e) => {
+                    tracing::error!("Failed to load player {}: {}", player.steam_id, e);
+                    continue;
+                }
+            };
+            let continued_since = match previous {
+                Some((at, ref ids)) => ids.contains(&player.steam_id).then_some(at),
+                None => Some(record.last_seen).filter(|seen| now - *seen <= self.resume_within),
+            };
+            match continued_since {
+                Some(since) => {
+                    record.total_playtime_secs += (now - since).num_seconds().max(0) as u64
+                }
+                None => record.session_count += 1,
+            }
+            record.name = player.display_name.clone();
+            record.last_seen = now;
+            records.push(record);
+        }
+        registry.save_player_records(server_id, &records);
+    }
+}
+
+/// Background task: feed the tracker each server's playerlist as the game collector
+/// caches it, at the monitor's poll interval.
+pub fn spawn_tracker(
+    registry: Arc<ServerRegistry>,
+    tracker: Arc<PlayerTracker>,
+    poll_interval: Duration,
+    shutdown: Shutdown,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(poll_interval);
+        loop {
+            tokio::select! {
+                _ = tick.tick() => {}
+                _ = shutdown.signal() => break,
+            }
+            let monitors: Vec<_> = registry
+                .runtimes
+                .read()
+                .await
+                .iter()
+                .map(|(id, runtime)| (id.clone(), runtime.game_monitor.clone()))
+                .collect();
+            let now = Utc::now();
+            for (server_id, monitor) in monitors {
+                let players = match monitor.players.read().await.as_ref() {
+                    // A list with players in it changes every poll (their connected time
+                    // grows); an old one means the collector has stopped
+                    Some(snapshot) if now - snapshot.timestamp <= tracker.resume_within => {
+                        snapshot.players.clone()
+                    }
+                    Some(_) => Vec::new(),
+                    // Not polled yet since the panel started
+                    None => continue,
+                };
+                tracker.observe(&registry, &server_id, &players, now);
+            }
+        }
+    })
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PlayerHistoryQuery {
+    #[serde(alias = "steamId")]
+    pub steam_id: String,
+}
+
+/// GET /api/servers/{server_id}/players/history?steam_id=
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/players/history",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id"), PlayerHistoryQuery),
+    responses(
+        (status = 200, body = PlayerRecord),
+        (status = 404, description = "Server not found, or the player was never seen on it", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn player_history(
+    server_id: web::Path<String>,
+    query: web::Query<PlayerHistoryQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let record = registry
+        .player_record(&server_id, &query.steam_id)
+        .map_err(|e| ApiError::internal(format!("Failed to read player history: {}", e)))?
+        .ok_or_else(|| {
+            ApiError::not_found(format!("Player {} has not been seen", query.steam_id))
+        })?;
+    Ok(HttpResponse::Ok().json(record))
+}
+
+const PLAYER_SORTS: &[SortKey<PlayerRecord>] = &[
+    ("lastSeen", |a, b| a.last_seen.cmp(&b.last_seen)),
+    ("firstSeen", |a, b| a.first_seen.cmp(&b.first_seen)),
+    ("name", |a, b| {
+        a.name.to_lowercase().cmp(&b.name.to_lowercase())
+    }),
+    ("totalPlaytimeSecs", |a, b| {
+        a.total_playtime_secs.cmp(&b.total_playtime_secs)
+    }),
+    ("sessionCount", |a, b| a.session_count.cmp(&b.session_count)),
+];
+
+/// GET /api/servers/{server_id}/players/known
+///
+/// Every player seen on the server, most recently seen first.
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/players/known",
+    tag = "players",
+    params(("server_id" = String, Path, description = "Server id"), ListQuery),
+    responses(
+        (status = 200, body = Vec<PlayerRecord>),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 422, description = "Unknown sort key or field", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn known_players(
+    server_id: web::Path<String>,
+    list: web::Query<ListQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let records = registry
+        .player_records(&server_id)
+        .map_err(|e| ApiError::internal(format!("Failed to read player history: {}", e)))?;
+    Ok(HttpResponse::Ok().json(pagination::apply(&list, records, PLAYER_SORTS)?))
+}
@@ -1,13 +1,22 @@
 use actix_multipart::Multipart;
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
-
+use utoipa::{IntoParams, ToSchema};
+
+use crate::apierror::ApiError;
+use crate::audit;
+use crate::config::AppConfig;
+use crate::etag;
+use crate::filemanager;
+use crate::rcon::RconClient;
 use crate::registry::ServerRegistry;
+use crate::validation::ValidationErrors;
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PluginInfo {
     pub name: String,
@@ -15,25 +24,22 @@ pub struct PluginInfo {
     pub size: u64,
     pub modified: Option<String>,
     pub has_config: bool,
+    /// False when the file sits in oxide/plugins_disabled, where Oxide doesn't load it.
+    pub enabled: bool,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
-    error: String,
-}
-
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct SuccessBody {
     success: bool,
     message: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
 pub struct UmodSearchQuery {
     pub q: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct UmodInstallBody {
     pub url: String,
     pub filename: String,
@@ -50,138 +56,178 @@ fn plugin_name_from_file(filename: &str) -> String {
 async fn get_server_paths(
     server_id: &str,
     registry: &Arc<ServerRegistry>,
-) -> Result<(String, String), HttpResponse> {
-    let config = registry.get_config(server_id).await.ok_or_else(|| {
-        HttpResponse::NotFound().json(ErrorBody {
-            error: "Server not found".to_string(),
-        })
-    })?;
+) -> Result<(String, String), ApiError> {
+    let config = registry
+        .get_config(server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
     Ok((config.paths.oxide_plugins, config.paths.oxide_config))
 }
 
+/// Disabled plugins are moved next to oxide/plugins, out of Oxide's sight.
+fn disabled_dir(plugins_dir: &Path) -> PathBuf {
+    plugins_dir
+        .parent()
+        .unwrap_or(plugins_dir)
+        .join("plugins_disabled")
+}
+
+/// Add the .cs files in `dir` to `plugins`. A missing directory has no plugins.
+fn scan_plugins(
+    dir: &Path,
+    config_dir: &Path,
+    enabled: bool,
+    plugins: &mut Vec<PluginInfo>,
+) -> Result<(), ApiError> {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(e) => {
+            return Err(ApiError::internal(format!(
+                "Failed to read plugins directory: {}",
+                e
+            )));
+        }
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.extension().and_then(|e| e.to_str()) == Some("cs") {
+            let filename = entry.file_name().to_string_lossy().to_string();
+            let name = plugin_name_from_file(&filename);
+            let metadata = entry.metadata().ok();
+            let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
+            let modified = metadata.as_ref().and_then(|m| m.modified().ok()).map(|t| {
+                let dt: chrono::DateTime<chrono::Utc> = t.into();
+                dt.to_rfc3339()
+            });
+            let config_file = config_dir.join(format!("{}.json", name));
+            let has_config = config_file.exists();
+
+            plugins.push(PluginInfo {
+                name,
+                filename,
+                size,
+                modified,
+                has_config,
+                enabled,
+            });
+        }
+    }
+    Ok(())
+}
+
 /// GET /api/servers/{server_id}/plugins
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/plugins",
+    tag = "plugins",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = [PluginInfo]),
+        (status = 304, description = "List unchanged since the If-None-Match ETag"),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn list_plugins(
+    req: HttpRequest,
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let (plugins_dir_str, config_dir_str) = match get_server_paths(&server_id, &registry).await {
-        Ok(p) => p,
-        Err(e) => return e,
-    };
+) -> Result<HttpResponse, ApiError> {
+    let (plugins_dir_str, config_dir_str) = get_server_paths(&server_id, &registry).await?;
     let plugins_dir = Path::new(&plugins_dir_str);
     let config_dir = Path::new(&config_dir_str);
 
-    if !plugins_dir.exists() {
-        return HttpResponse::Ok().json(Vec::<PluginInfo>::new());
-    }
-
     let mut plugins = Vec::new();
-    match std::fs::read_dir(plugins_dir) {
-        Ok(entries) => {
-            for entry in entries.flatten() {
-                let path = entry.path();
-                if path.extension().and_then(|e| e.to_str()) == Some("cs") {
-                    let filename = entry.file_name().to_string_lossy().to_string();
-                    let name = plugin_name_from_file(&filename);
-                    let metadata = entry.metadata().ok();
-                    let size = metadata.as_ref().map(|m| m.len()).unwrap_or(0);
-                    let modified = metadata
-                        .as_ref()
-                        .and_then(|m| m.modified().ok())
-                        .map(|t| {
-                            let dt: chrono::DateTime<chrono::Utc> = t.into();
-                            dt.to_rfc3339()
-                        });
-                    let config_file = config_dir.join(format!("{}.json", name));
-                    let has_config = config_file.exists();
-
-                    plugins.push(PluginInfo {
-                        name,
-                        filename,
-                        size,
-                        modified,
-                        has_config,
-                    });
-                }
-            }
-        }
-        Err(e) => {
-            return HttpResponse::InternalServerError().json(ErrorBody {
-                error: format!("Failed to read plugins directory: {}", e),
-            });
-        }
-    }
+    scan_plugins(plugins_dir, config_dir, true, &mut plugins)?;
+    scan_plugins(&disabled_dir(plugins_dir), config_dir, false, &mut plugins)?;
 
     plugins.sort_by(|a, b| a.name.to_lowercase().cmp(&b.name.to_lowercase()));
-    HttpResponse::Ok().json(plugins)
+    Ok(etag::json(&req, &plugins))
 }
 
 /// GET /api/servers/{server_id}/plugins/{name}/config
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/plugins/{name}/config",
+    tag = "plugins",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Plugin name (file stem)"),
+    ),
+    responses(
+        (status = 200, description = "The plugin's JSON config", body = Object),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn get_plugin_config(
     path: web::Path<(String, String)>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     let (server_id, name) = path.into_inner();
-    let (_, config_dir_str) = match get_server_paths(&server_id, &registry).await {
-        Ok(p) => p,
-        Err(e) => return e,
-    };
+    let (_, config_dir_str) = get_server_paths(&server_id, &registry).await?;
     let config_path = PathBuf::from(&config_dir_str).join(format!("{}.json", name));
 
     if !config_path.exists() {
-        return HttpResponse::NotFound().json(ErrorBody {
-            error: format!("Config file not found for plugin '{}'", name),
-        });
+        return Err(ApiError::not_found(format!(
+            "Config file not found for plugin '{}'",
+            name
+        )));
     }
 
     match std::fs::read_to_string(&config_path) {
         Ok(content) => match serde_json::from_str::<serde_json::Value>(&content) {
-            Ok(json) => HttpResponse::Ok().json(serde_json::json!({
+            Ok(json) => Ok(HttpResponse::Ok().json(serde_json::json!({
                 "plugin": name,
                 "config": json,
-            })),
-            Err(_) => HttpResponse::Ok().json(serde_json::json!({
+            }))),
+            Err(_) => Ok(HttpResponse::Ok().json(serde_json::json!({
                 "plugin": name,
                 "raw_config": content,
-            })),
+            }))),
         },
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to read config: {}", e),
-        }),
+        Err(e) => Err(ApiError::internal(format!("Failed to read config: {}", e))),
     }
 }
 
 /// PUT /api/servers/{server_id}/plugins/{name}/config
+#[utoipa::path(
+    put,
+    path = "/api/servers/{server_id}/plugins/{name}/config",
+    tag = "plugins",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Plugin name (file stem)"),
+    ),
+    request_body = Object,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 400, body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn save_plugin_config(
     path: web::Path<(String, String)>,
     body: web::Json<serde_json::Value>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     let (server_id, name) = path.into_inner();
-    let (_, config_dir_str) = match get_server_paths(&server_id, &registry).await {
-        Ok(p) => p,
-        Err(e) => return e,
-    };
+    let (_, config_dir_str) = get_server_paths(&server_id, &registry).await?;
     let config_path = PathBuf::from(&config_dir_str).join(format!("{}.json", name));
 
     if let Some(parent) = config_path.parent() {
         if !parent.exists() {
             if let Err(e) = std::fs::create_dir_all(parent) {
-                return HttpResponse::InternalServerError().json(ErrorBody {
-                    error: format!("Failed to create config directory: {}", e),
-                });
+                return Err(ApiError::internal(format!(
+                    "Failed to create config directory: {}",
+                    e
+                )));
             }
         }
     }
 
-    let json_str = match serde_json::to_string_pretty(&body.into_inner()) {
-        Ok(s) => s,
-        Err(e) => {
-            return HttpResponse::BadRequest().json(ErrorBody {
-                error: format!("Invalid JSON: {}", e),
-            })
-        }
-    };
+    let json_str = serde_json::to_string_pretty(&body.into_inner())
+        .map_err(|e| ApiError::validation(format!("Invalid JSON: {}", e)))?;
 
     if config_path.exists() {
         let backup = format!("{}.bak", config_path.display());
@@ -189,9 +235,7 @@ pub async fn save_plugin_config(
     }
 
     if let Err(e) = std::fs::write(&config_path, &json_str) {
-        return HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to write config: {}", e),
-        });
+        return Err(ApiError::internal(format!("Failed to write config: {}", e)));
     }
 
     let reload_result = if let Some(rcon) = registry.get_rcon(&server_id).await {
@@ -203,67 +247,272 @@ pub async fn save_plugin_config(
         "RCON not available".to_string()
     };
 
-    HttpResponse::Ok().json(SuccessBody {
+    Ok(HttpResponse::Ok().json(SuccessBody {
         success: true,
         message: format!("Config saved for '{}'. Reload: {}", name, reload_result),
-    })
+    }))
+}
+
+/// A data file under oxide/data, parsed as JSON when it is valid JSON.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginDataFile {
+    pub name: String,
+    pub size: u64,
+    pub modified: Option<String>,
+    #[schema(value_type = Object)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    /// The file's text, when it isn't valid JSON.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub raw: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PluginDataUpdate {
+    /// File name -> new contents. Each name must start with the plugin name and end in .json.
+    #[schema(value_type = Object)]
+    pub files: BTreeMap<String, serde_json::Value>,
+}
+
+/// Oxide plugin names are C# class names; anything else could reach outside oxide/data.
+fn validate_plugin_name(name: &str) -> Result<(), ApiError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(ApiError::validation(format!(
+            "Invalid plugin name '{}'",
+            name
+        )));
+    }
+    Ok(())
+}
+
+/// Data files belong to a plugin when their name starts with the plugin's name
+/// (`Kits.json`, `Kits_Players.json`).
+fn owns_data_file(plugin: &str, filename: &str) -> bool {
+    filename.starts_with(plugin) && filename.ends_with(".json") && !filename.contains(['/', '\\'])
+}
+
+async fn get_data_dir(server_id: &str, registry: &ServerRegistry) -> Result<PathBuf, ApiError> {
+    registry
+        .get_config(server_id)
+        .await
+        .map(|c| PathBuf::from(c.paths.oxide_data))
+        .ok_or_else(ApiError::server_not_found)
+}
+
+/// GET /api/servers/{server_id}/plugins/{name}/data
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/plugins/{name}/data",
+    tag = "plugins",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Plugin name (file stem)"),
+    ),
+    responses(
+        (status = 200, description = "The plugin's data files; empty if it has none", body = [PluginDataFile]),
+        (status = 400, description = "Invalid plugin name", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn get_plugin_data(
+    path: web::Path<(String, String)>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    validate_plugin_name(&name)?;
+    let data_dir = get_data_dir(&server_id, &registry).await?;
+
+    let entries = match std::fs::read_dir(&data_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            return Ok(HttpResponse::Ok().json(Vec::<PluginDataFile>::new()))
+        }
+        Err(e) => {
+            return Err(ApiError::internal(format!(
+                "Failed to read data directory: {}",
+                e
+            )))
+        }
+    };
+
+    let mut files = Vec::new();
+    for entry in entries.flatten() {
+        let filename = entry.file_name().to_string_lossy().to_string();
+        let metadata = match entry.metadata() {
+            Ok(m) if m.is_file() && owns_data_file(&name, &filename) => m,
+            _ => continue,
+        };
+        let content = std::fs::read_to_string(entry.path())
+            .map_err(|e| ApiError::internal(format!("Failed to read {}: {}", filename, e)))?;
+        let data = serde_json::from_str::<serde_json::Value>(&content).ok();
+        files.push(PluginDataFile {
+            name: filename,
+            size: metadata.len(),
+            modified: metadata.modified().ok().map(|t| {
+                let dt: chrono::DateTime<chrono::Utc> = t.into();
+                dt.to_rfc3339()
+            }),
+            raw: data.is_none().then_some(content),
+            data,
+        });
+    }
+
+    files.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(HttpResponse::Ok().json(files))
+}
+
+/// PUT /api/servers/{server_id}/plugins/{name}/data — replace some of the plugin's data
+/// files, keeping each previous file as `<file>.bak`. The plugin is unloaded first, since
+/// it saves its in-memory data when unloading, and loaded again afterwards.
+#[utoipa::path(
+    put,
+    path = "/api/servers/{server_id}/plugins/{name}/data",
+    tag = "plugins",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Plugin name (file stem)"),
+    ),
+    request_body = PluginDataUpdate,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 400, description = "Invalid plugin or file names, listed in `details.fields`", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn save_plugin_data(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: web::Json<PluginDataUpdate>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    validate_plugin_name(&name)?;
+    let data_dir = get_data_dir(&server_id, &registry).await?;
+    if body.files.is_empty() {
+        return Err(ApiError::validation("No data files given"));
+    }
+
+    let mut errors = ValidationErrors::new();
+    for filename in body.files.keys() {
+        if !owns_data_file(&name, filename) {
+            errors.add(
+                filename,
+                format!("Must be a .json file whose name starts with '{}'", name),
+            );
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    if let Err(e) = std::fs::create_dir_all(&data_dir) {
+        return Err(ApiError::internal(format!(
+            "Failed to create data directory: {}",
+            e
+        )));
+    }
+
+    let rcon = registry.get_rcon(&server_id).await;
+    if let Some(ref rcon) = rcon {
+        if let Err(e) = rcon.oxide_unload(&name).await {
+            tracing::warn!("Failed to unload '{}' before writing its data: {}", name, e);
+        }
+    }
+
+    let data_dir_str = data_dir.to_string_lossy();
+    for (filename, value) in &body.files {
+        let target =
+            filemanager::safe_resolve(&data_dir_str, filename).map_err(ApiError::path_forbidden)?;
+        let json_str = serde_json::to_string_pretty(value)
+            .map_err(|e| ApiError::validation(format!("Invalid JSON: {}", e)))?;
+        if target.exists() {
+            let _ = std::fs::copy(&target, format!("{}.bak", target.display()));
+        }
+        if let Err(e) = std::fs::write(&target, &json_str) {
+            return Err(ApiError::internal(format!(
+                "Failed to write {}: {}",
+                filename, e
+            )));
+        }
+    }
+
+    let load_result = match rcon {
+        Some(rcon) => match rcon.oxide_load(&name).await {
+            Ok(msg) => msg,
+            Err(e) => format!("Load failed (server may be offline): {}", e),
+        },
+        None => "RCON not available".to_string(),
+    };
+
+    let written: Vec<&str> = body.files.keys().map(String::as_str).collect();
+    audit::detail(&req, written.join(", "));
+    Ok(HttpResponse::Ok().json(SuccessBody {
+        success: true,
+        message: format!("Data saved for '{}'. Load: {}", name, load_result),
+    }))
 }
 
 /// POST /api/servers/{server_id}/plugins/upload
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/plugins/upload",
+    tag = "plugins",
+    request_body(content_type = "multipart/form-data", description = "One or more .cs plugin files"),
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 400, body = ApiError),
+        (status = 413, description = "Over `limits.upload_max_bytes`", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn upload_plugin(
+    req: HttpRequest,
     server_id: web::Path<String>,
     mut payload: Multipart,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let (plugins_dir_str, _) = match get_server_paths(&server_id, &registry).await {
-        Ok(p) => p,
-        Err(e) => return e,
-    };
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let (plugins_dir_str, _) = get_server_paths(&server_id, &registry).await?;
     let plugins_dir = PathBuf::from(&plugins_dir_str);
 
     if !plugins_dir.exists() {
         if let Err(e) = std::fs::create_dir_all(&plugins_dir) {
-            return HttpResponse::InternalServerError().json(ErrorBody {
-                error: format!("Failed to create plugins directory: {}", e),
-            });
+            return Err(ApiError::internal(format!(
+                "Failed to create plugins directory: {}",
+                e
+            )));
         }
     }
 
+    // Counted across every field, so the upload limit holds for the whole request
+    let mut received = 0;
     while let Some(item) = payload.next().await {
-        let mut field = match item {
-            Ok(f) => f,
-            Err(e) => {
-                return HttpResponse::BadRequest().json(ErrorBody {
-                    error: format!("Multipart error: {}", e),
-                })
-            }
-        };
+        let mut field =
+            item.map_err(|e| ApiError::validation(format!("Multipart error: {}", e)))?;
 
         let filename = field
             .content_disposition()
             .and_then(|cd| cd.get_filename().map(|f| f.to_string()))
             .unwrap_or_else(|| "plugin.cs".to_string());
 
+        audit::detail(&req, filename.clone());
         if !filename.ends_with(".cs") {
-            return HttpResponse::BadRequest().json(ErrorBody {
-                error: "Only .cs plugin files are allowed".to_string(),
-            });
+            return Err(ApiError::validation("Only .cs plugin files are allowed"));
         }
 
         let target_path = plugins_dir.join(&filename);
-
-        let mut file_data = Vec::new();
-        while let Some(chunk) = field.next().await {
-            if let Ok(bytes) = chunk {
-                file_data.extend_from_slice(&bytes);
-            }
-        }
-
-        if let Err(e) = std::fs::write(&target_path, &file_data) {
-            return HttpResponse::InternalServerError().json(ErrorBody {
-                error: format!("Failed to write plugin: {}", e),
-            });
-        }
+        filemanager::save_field(
+            &mut field,
+            &target_path,
+            &mut received,
+            config.limits.upload_max_bytes,
+        )
+        .await?;
 
         let plugin_name = plugin_name_from_file(&filename);
 
@@ -276,33 +525,54 @@ pub async fn upload_plugin(
             "RCON not available".to_string()
         };
 
-        return HttpResponse::Ok().json(SuccessBody {
+        return Ok(HttpResponse::Ok().json(SuccessBody {
             success: true,
             message: format!("Plugin '{}' uploaded. Load: {}", plugin_name, load_result),
-        });
+        }));
     }
 
-    HttpResponse::BadRequest().json(ErrorBody {
-        error: "No file provided".to_string(),
-    })
+    Err(ApiError::validation("No file provided"))
 }
 
 /// DELETE /api/servers/{server_id}/plugins/{name}
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{server_id}/plugins/{name}",
+    tag = "plugins",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Plugin name (file stem)"),
+    ),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
 pub async fn delete_plugin(
     path: web::Path<(String, String)>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     let (server_id, name) = path.into_inner();
-    let (plugins_dir_str, _) = match get_server_paths(&server_id, &registry).await {
-        Ok(p) => p,
-        Err(e) => return e,
-    };
+    let (plugins_dir_str, _) = get_server_paths(&server_id, &registry).await?;
     let plugin_file = PathBuf::from(&plugins_dir_str).join(format!("{}.cs", name));
 
     if !plugin_file.exists() {
-        return HttpResponse::NotFound().json(ErrorBody {
-            error: format!("Plugin '{}' not found", name),
-        });
+        // A disabled plugin isn't loaded, so there is nothing to unload
+        let disabled_file = disabled_dir(Path::new(&plugins_dir_str)).join(format!("{}.cs", name));
+        if !disabled_file.exists() {
+            return Err(ApiError::not_found(format!("Plugin '{}' not found", name)));
+        }
+        if let Err(e) = std::fs::remove_file(&disabled_file) {
+            return Err(ApiError::internal(format!(
+                "Failed to delete plugin file: {}",
+                e
+            )));
+        }
+        return Ok(HttpResponse::Ok().json(SuccessBody {
+            success: true,
+            message: format!("Disabled plugin '{}' deleted", name),
+        }));
     }
 
     let unload_result = if let Some(rcon) = registry.get_rcon(&server_id).await {
@@ -315,45 +585,332 @@ pub async fn delete_plugin(
     };
 
     if let Err(e) = std::fs::remove_file(&plugin_file) {
-        return HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to delete plugin file: {}", e),
-        });
+        return Err(ApiError::internal(format!(
+            "Failed to delete plugin file: {}",
+            e
+        )));
     }
 
-    HttpResponse::Ok().json(SuccessBody {
+    Ok(HttpResponse::Ok().json(SuccessBody {
         success: true,
         message: format!("Plugin '{}' deleted. Unload: {}", name, unload_result),
-    })
+    }))
 }
 
 /// POST /api/servers/{server_id}/plugins/{name}/reload
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/plugins/{name}/reload",
+    tag = "plugins",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Plugin name (file stem)"),
+    ),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 503, description = "RCON unavailable", body = ApiError),
+    )
+)]
 pub async fn reload_plugin(
     path: web::Path<(String, String)>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     let (server_id, name) = path.into_inner();
-    let rcon = match registry.get_rcon(&server_id).await {
-        Some(r) => r,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
     match rcon.oxide_reload(&name).await {
-        Ok(msg) => HttpResponse::Ok().json(SuccessBody {
+        Ok(msg) => Ok(HttpResponse::Ok().json(SuccessBody {
             success: true,
             message: format!("Plugin '{}' reloaded: {}", name, msg),
-        }),
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to reload plugin '{}': {}", name, e),
-        }),
+        })),
+        Err(e) => Err(ApiError::rcon(format!(
+            "Failed to reload plugin '{}': {}",
+            name, e
+        ))),
+    }
+}
+
+/// Move a plugin's file from one directory to the other; `enable` picks the direction.
+fn move_plugin(plugins_dir: &Path, name: &str, enable: bool) -> Result<(), ApiError> {
+    let filename = format!("{}.cs", name);
+    let disabled = disabled_dir(plugins_dir);
+    let (from, to) = if enable {
+        (disabled.join(&filename), plugins_dir.join(&filename))
+    } else {
+        (plugins_dir.join(&filename), disabled.join(&filename))
+    };
+
+    if !from.exists() {
+        if to.exists() {
+            let state = if enable { "enabled" } else { "disabled" };
+            return Err(ApiError::conflict(format!(
+                "Plugin '{}' is already {}",
+                name, state
+            )));
+        }
+        return Err(ApiError::not_found(format!("Plugin '{}' not found", name)));
+    }
+    if to.exists() {
+        return Err(ApiError::conflict(format!(
+            "Both {} and {} exist; remove one first",
+            from.display(),
+            to.display()
+        )));
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| {
+            ApiError::internal(format!("Failed to create {}: {}", parent.display(), e))
+        })?;
+    }
+    std::fs::rename(&from, &to)
+        .map_err(|e| ApiError::internal(format!("Failed to move plugin file: {}", e)))
+}
+
+/// POST /api/servers/{server_id}/plugins/{name}/disable — unload the plugin and move it to
+/// oxide/plugins_disabled. Its config and data stay where they are.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/plugins/{name}/disable",
+    tag = "plugins",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Plugin name (file stem)"),
+    ),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 400, description = "Invalid plugin name", body = ApiError),
+        (status = 404, description = "Server or plugin not found", body = ApiError),
+        (status = 409, description = "Plugin is already disabled", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn disable_plugin(
+    path: web::Path<(String, String)>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    validate_plugin_name(&name)?;
+    let (plugins_dir_str, _) = get_server_paths(&server_id, &registry).await?;
+    move_plugin(Path::new(&plugins_dir_str), &name, false)?;
+
+    let unload_result = if let Some(rcon) = registry.get_rcon(&server_id).await {
+        match rcon.oxide_unload(&name).await {
+            Ok(msg) => msg,
+            Err(e) => format!("Unload failed (server may be offline): {}", e),
+        }
+    } else {
+        "RCON not available".to_string()
+    };
+
+    Ok(HttpResponse::Ok().json(SuccessBody {
+        success: true,
+        message: format!("Plugin '{}' disabled. Unload: {}", name, unload_result),
+    }))
+}
+
+/// POST /api/servers/{server_id}/plugins/{name}/enable — move the plugin back to
+/// oxide/plugins and load it.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/plugins/{name}/enable",
+    tag = "plugins",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Plugin name (file stem)"),
+    ),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 400, description = "Invalid plugin name", body = ApiError),
+        (status = 404, description = "Server or plugin not found", body = ApiError),
+        (status = 409, description = "Plugin is already enabled", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn enable_plugin(
+    path: web::Path<(String, String)>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    validate_plugin_name(&name)?;
+    let (plugins_dir_str, _) = get_server_paths(&server_id, &registry).await?;
+    move_plugin(Path::new(&plugins_dir_str), &name, true)?;
+
+    let load_result = if let Some(rcon) = registry.get_rcon(&server_id).await {
+        match rcon.oxide_load(&name).await {
+            Ok(msg) => msg,
+            Err(e) => format!("Load failed (server may be offline): {}", e),
+        }
+    } else {
+        "RCON not available".to_string()
+    };
+
+    Ok(HttpResponse::Ok().json(SuccessBody {
+        success: true,
+        message: format!("Plugin '{}' enabled. Load: {}", name, load_result),
+    }))
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkPluginRequest {
+    /// reload, unload, load or delete
+    pub action: String,
+    /// Plugin names (file stems)
+    #[serde(default)]
+    pub names: Vec<String>,
+    /// Apply to every installed plugin; `names` must then be empty
+    #[serde(default)]
+    pub all: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct BulkPluginResult {
+    name: String,
+    success: bool,
+    message: String,
+}
+
+/// Names of the .cs files in the plugins directory.
+fn installed_plugins(plugins_dir: &Path) -> Result<Vec<String>, ApiError> {
+    let entries = match std::fs::read_dir(plugins_dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(ApiError::internal(format!(
+                "Failed to read plugins directory: {}",
+                e
+            )))
+        }
+    };
+    let mut names: Vec<String> = entries
+        .flatten()
+        .filter(|entry| entry.path().extension().and_then(|e| e.to_str()) == Some("cs"))
+        .map(|entry| plugin_name_from_file(&entry.file_name().to_string_lossy()))
+        .collect();
+    names.sort_by_key(|name| name.to_lowercase());
+    Ok(names)
+}
+
+async fn run_bulk_action(
+    action: &str,
+    name: &str,
+    plugins_dir: &Path,
+    rcon: Option<&RconClient>,
+) -> Result<String, String> {
+    validate_plugin_name(name).map_err(|e| e.message)?;
+    if action == "delete" {
+        let plugin_file = plugins_dir.join(format!("{}.cs", name));
+        if !plugin_file.exists() {
+            return Err(format!("Plugin '{}' not found", name));
+        }
+        let unload_result = match rcon {
+            Some(rcon) => match rcon.oxide_unload(name).await {
+                Ok(msg) => msg,
+                Err(e) => format!("Unload failed (server may be offline): {}", e),
+            },
+            None => "RCON not available".to_string(),
+        };
+        std::fs::remove_file(&plugin_file)
+            .map_err(|e| format!("Failed to delete plugin file: {}", e))?;
+        return Ok(format!("Deleted. Unload: {}", unload_result));
     }
+
+    let rcon = rcon.ok_or("RCON not available")?;
+    let result = match action {
+        "reload" => rcon.oxide_reload(name).await,
+        "load" => rcon.oxide_load(name).await,
+        _ => rcon.oxide_unload(name).await,
+    };
+    result.map_err(|e| e.to_string())
+}
+
+/// POST /api/servers/{server_id}/plugins/bulk — reload, unload, load or delete several
+/// plugins. Commands go to the server one at a time; a failure doesn't stop the rest.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/plugins/bulk",
+    tag = "plugins",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = BulkPluginRequest,
+    responses(
+        (status = 200, description = "One result per plugin", body = [BulkPluginResult]),
+        (status = 400, body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn bulk_plugin_action(
+    req: HttpRequest,
+    server_id: web::Path<String>,
+    body: web::Json<BulkPluginRequest>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    if !matches!(
+        body.action.as_str(),
+        "reload" | "unload" | "load" | "delete"
+    ) {
+        return Err(ApiError::validation(
+            "Invalid action. Use reload, unload, load or delete",
+        ));
+    }
+    let (plugins_dir_str, _) = get_server_paths(&server_id, &registry).await?;
+    let plugins_dir = PathBuf::from(&plugins_dir_str);
+
+    let names = match (body.all, body.names.is_empty()) {
+        (true, true) => installed_plugins(&plugins_dir)?,
+        (false, false) => body.names.clone(),
+        (true, false) => {
+            return Err(ApiError::validation(
+                "Give either names or all: true, not both",
+            ))
+        }
+        (false, true) => return Err(ApiError::validation("No plugins given")),
+    };
+
+    let selection = if body.all {
+        "all".to_string()
+    } else {
+        names.join(", ")
+    };
+    audit::detail(&req, format!("{} {}", body.action, selection));
+    let rcon = registry.get_rcon(&server_id).await;
+    let mut results = Vec::with_capacity(names.len());
+    for name in names {
+        let result = run_bulk_action(&body.action, &name, &plugins_dir, rcon.as_deref()).await;
+        results.push(match result {
+            Ok(message) => BulkPluginResult {
+                name,
+                success: true,
+                message,
+            },
+            Err(message) => BulkPluginResult {
+                name,
+                success: false,
+                message,
+            },
+        });
+    }
+
+    Ok(HttpResponse::Ok().json(results))
 }
 
 /// GET /api/plugins/umod/search - global, not per-server
-pub async fn umod_search(query: web::Query<UmodSearchQuery>) -> HttpResponse {
+#[utoipa::path(
+    get,
+    path = "/api/plugins/umod/search",
+    tag = "plugins",
+    params(UmodSearchQuery),
+    responses(
+        (status = 200, description = "uMod search results, passed through", body = Object),
+        (status = 502, description = "uMod unreachable", body = ApiError),
+    )
+)]
+pub async fn umod_search(query: web::Query<UmodSearchQuery>) -> Result<HttpResponse, ApiError> {
     let url = format!(
         "https://umod.org/plugins/search.json?query={}&page=1&sort=title&sortdir=asc&categories%5B%5D=rust",
         urlencoded(&query.q)
@@ -362,41 +919,51 @@ pub async fn umod_search(query: web::Query<UmodSearchQuery>) -> HttpResponse {
     let client = reqwest::Client::new();
     match client.get(&url).send().await {
         Ok(response) => match response.json::<serde_json::Value>().await {
-            Ok(json) => HttpResponse::Ok().json(json),
-            Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-                error: format!("Failed to parse uMod response: {}", e),
-            }),
+            Ok(json) => Ok(HttpResponse::Ok().json(json)),
+            Err(e) => Err(ApiError::upstream(format!(
+                "Failed to parse uMod response: {}",
+                e
+            ))),
         },
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to search uMod: {}", e),
-        }),
+        Err(e) => Err(ApiError::upstream(format!("Failed to search uMod: {}", e))),
     }
 }
 
 /// POST /api/servers/{server_id}/plugins/umod/install
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/plugins/umod/install",
+    tag = "plugins",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = UmodInstallBody,
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 400, body = ApiError),
+        (status = 500, body = ApiError),
+        (status = 502, description = "uMod unreachable", body = ApiError),
+    )
+)]
 pub async fn umod_install(
+    req: HttpRequest,
     server_id: web::Path<String>,
     body: web::Json<UmodInstallBody>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let (plugins_dir_str, _) = match get_server_paths(&server_id, &registry).await {
-        Ok(p) => p,
-        Err(e) => return e,
-    };
+) -> Result<HttpResponse, ApiError> {
+    audit::detail(&req, body.filename.clone());
+    let (plugins_dir_str, _) = get_server_paths(&server_id, &registry).await?;
     let plugins_dir = PathBuf::from(&plugins_dir_str);
 
     if !plugins_dir.exists() {
         if let Err(e) = std::fs::create_dir_all(&plugins_dir) {
-            return HttpResponse::InternalServerError().json(ErrorBody {
-                error: format!("Failed to create plugins directory: {}", e),
-            });
+            return Err(ApiError::internal(format!(
+                "Failed to create plugins directory: {}",
+                e
+            )));
         }
     }
 
     if !body.filename.ends_with(".cs") {
-        return HttpResponse::BadRequest().json(ErrorBody {
-            error: "Filename must end with .cs".to_string(),
-        });
+        return Err(ApiError::validation("Filename must end with .cs"));
     }
 
     let client = reqwest::Client::new();
@@ -405,9 +972,7 @@ pub async fn umod_install(
             Ok(bytes) => {
                 let target_path = plugins_dir.join(&body.filename);
                 if let Err(e) = std::fs::write(&target_path, &bytes) {
-                    return HttpResponse::InternalServerError().json(ErrorBody {
-                        error: format!("Failed to write plugin: {}", e),
-                    });
+                    return Err(ApiError::internal(format!("Failed to write plugin: {}", e)));
                 }
 
                 let plugin_name = plugin_name_from_file(&body.filename);
@@ -424,21 +989,23 @@ pub async fn umod_install(
                         "RCON not available".to_string()
                     };
 
-                HttpResponse::Ok().json(SuccessBody {
+                Ok(HttpResponse::Ok().json(SuccessBody {
                     success: true,
                     message: format!(
                         "Plugin '{}' installed from uMod. Load: {}",
                         plugin_name, load_result
                     ),
-                })
+                }))
             }
-            Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-                error: format!("Failed to download plugin: {}", e),
-            }),
+            Err(e) => Err(ApiError::upstream(format!(
+                "Failed to download plugin: {}",
+                e
+            ))),
         },
-        Err(e) => HttpResponse::InternalServerError().json(ErrorBody {
-            error: format!("Failed to fetch from uMod: {}", e),
-        }),
+        Err(e) => Err(ApiError::upstream(format!(
+            "Failed to fetch from uMod: {}",
+            e
+        ))),
     }
 }
 
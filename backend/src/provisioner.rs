@@ -1,21 +1,110 @@
+use std::collections::{HashMap, HashSet};
+use std::net::{TcpListener, UdpSocket};
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
 use std::sync::Arc;
 
-use crate::config::{AppConfig, ProvisioningConfig};
-use crate::lgsm::LgsmLock;
-use crate::monitor::GameMonitor;
-use crate::rcon::RconClient;
+use tokio::io::{AsyncBufReadExt, AsyncRead, BufReader};
+use tokio::sync::mpsc;
+use tokio::time::{Duration, Instant};
+
+use crate::config::{AppConfig, GameServerConfig, ProvisioningConfig};
+use crate::events::PanelEvent;
+use crate::filemanager;
 use crate::registry::{
-    ProvisioningStatus, ServerDefinition, ServerRegistry, ServerRuntime, ServerSource, ServerType,
+    CloneSource, ProvisioningStatus, ServerDefinition, ServerRegistry, ServerRuntime, ServerType,
 };
+use crate::requestid;
+use crate::servercfg;
 
 /// The non-root user that runs LinuxGSM commands inside the container.
 const GAME_USER: &str = "gameserver";
 
-/// Allocate the next free ports based on existing definitions.
+/// Least time between two command output lines copied to the provisioning log.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Port slots tried past the highest one in use before giving up.
+const MAX_SLOT_PROBES: u16 = 100;
+
+/// Ports a static server may listen on. `from_static_config` assumes the game defaults,
+/// but LinuxGSM's instance config or server.cfg can move them, so every one found counts.
+fn static_ports(config: &GameServerConfig) -> Vec<u16> {
+    let mut ports = vec![28015, 27015, config.rcon.port];
+    let lgsm_dir = format!("{}/lgsm/config-lgsm/rustserver", config.paths.base_dir);
+    for file in ["common.cfg", "rustserver.cfg"] {
+        if let Ok(content) = std::fs::read_to_string(format!("{}/{}", lgsm_dir, file)) {
+            ports.extend(content.lines().filter_map(|line| {
+                let (key, value) = line.trim().split_once('=')?;
+                if !matches!(key, "port" | "queryport" | "rconport") {
+                    return None;
+                }
+                value.trim_matches('"').parse::<u16>().ok()
+            }));
+        }
+    }
+    if let Ok(content) = std::fs::read_to_string(&config.paths.server_cfg) {
+        let convars = servercfg::parse(&content);
+        ports.extend(
+            ["server.port", "server.queryport", "rcon.port"]
+                .iter()
+                .filter_map(|key| convars.get(*key)?.parse::<u16>().ok()),
+        );
+    }
+    ports
+}
+
+/// Ports taken by other servers: the game, RCON and query ports of every definition,
+/// plus whatever the static servers are really configured with.
+pub fn ports_in_use(
+    existing: &[ServerDefinition],
+    static_configs: &HashMap<String, GameServerConfig>,
+) -> HashSet<u16> {
+    let mut ports: HashSet<u16> = existing
+        .iter()
+        .flat_map(|def| [def.game_port, def.rcon_port, def.query_port])
+        .collect();
+    ports.extend(static_configs.values().flat_map(static_ports));
+    ports
+}
+
+/// Whether nothing on the host holds `port`, by binding it the way the game will.
+fn free_on_host(port: u16, tcp: bool) -> bool {
+    if tcp {
+        TcpListener::bind(("0.0.0.0", port)).is_ok()
+    } else {
+        UdpSocket::bind(("0.0.0.0", port)).is_ok()
+    }
+}
+
+/// Why the (game, RCON, query) ports can't be used, if they can't. Game and query
+/// ports are probed as UDP, RCON as TCP.
+pub fn port_conflicts(ports: (u16, u16, u16), in_use: &HashSet<u16>) -> Vec<String> {
+    let (game_port, rcon_port, query_port) = ports;
+    let mut conflicts = Vec::new();
+    if game_port == rcon_port || game_port == query_port || rcon_port == query_port {
+        conflicts.push("gamePort, rconPort and queryPort must all differ".to_string());
+    }
+    for (name, port, tcp) in [
+        ("gamePort", game_port, false),
+        ("rconPort", rcon_port, true),
+        ("queryPort", query_port, false),
+    ] {
+        if in_use.contains(&port) {
+            conflicts.push(format!("{} {} is used by another server", name, port));
+        } else if !free_on_host(port, tcp) {
+            conflicts.push(format!("{} {} is already in use on the host", name, port));
+        }
+    }
+    conflicts
+}
+
+/// Allocate the next free ports: the slot after the highest one in use, moving on past
+/// slots that another server or a process on the host already holds.
 pub fn allocate_ports(
     existing: &[ServerDefinition],
+    in_use: &HashSet<u16>,
     config: &ProvisioningConfig,
-) -> (u16, u16, u16) {
+) -> Option<(u16, u16, u16)> {
     let mut max_slot: u16 = 0;
     for def in existing {
         if def.game_port >= config.port_range_start {
@@ -25,19 +114,189 @@ pub fn allocate_ports(
             }
         }
     }
-    let game_port = config.port_range_start + max_slot * config.port_offset;
-    let rcon_port = game_port + 1;
-    let query_port = game_port - 1000; // e.g., 28015 -> 27015
+    (max_slot..max_slot.saturating_add(MAX_SLOT_PROBES)).find_map(|slot| {
+        let game_port = slot
+            .checked_mul(config.port_offset)?
+            .checked_add(config.port_range_start)?;
+        let rcon_port = game_port.checked_add(1)?;
+        let query_port = game_port.checked_sub(1000)?; // e.g., 28015 -> 27015
+        let ports = (game_port, rcon_port, query_port);
+        port_conflicts(ports, in_use).is_empty().then_some(ports)
+    })
+}
+
+/// SIGKILLs a command's process group when dropped before the command finished, so a
+/// cancelled provisioning task doesn't leave SteamCMD downloading in the background.
+struct ProcessGroupGuard(Option<u32>);
 
-    (game_port, rcon_port, query_port)
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Some(pgid) = self.0 {
+            tracing::info!("Killing provisioning command (process group {})", pgid);
+            let _ = std::process::Command::new("kill")
+                .args(["-KILL", "--", &format!("-{}", pgid)])
+                .status();
+        }
+    }
 }
 
-/// Helper: run a shell command as the game user and return (success, stdout, stderr).
-async fn run_as_user(cmd: &str) -> Result<std::process::Output, std::io::Error> {
-    tokio::process::Command::new("su")
-        .args(["-", GAME_USER, "-c", cmd])
-        .output()
-        .await
+/// A login shell running `cmd` as `user`, in a process group of its own. setpriv
+/// switches user in place: `su -c` would start the command in a new session, out of
+/// reach of `ProcessGroupGuard`.
+fn user_command(user: &str, cmd: &str) -> tokio::process::Command {
+    let mut command = tokio::process::Command::new("setpriv");
+    command
+        .arg(format!("--reuid={}", user))
+        .arg(format!("--regid={}", user))
+        .args(["--init-groups", "--reset-env", "bash", "-lc", cmd])
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .process_group(0);
+    command
+}
+
+/// Spawn `command` (from `user_command`) with a guard that kills it and everything it
+/// started.
+fn spawn_guarded(
+    command: &mut tokio::process::Command,
+) -> std::io::Result<(tokio::process::Child, ProcessGroupGuard)> {
+    let child = command.spawn()?;
+    let guard = ProcessGroupGuard(child.id());
+    Ok((child, guard))
+}
+
+/// Run a shell command as the game user and collect its output. Dropping the future
+/// (a timeout) kills the command and everything it started.
+pub async fn output_as_game_user(cmd: &str) -> std::io::Result<std::process::Output> {
+    let (child, mut guard) = spawn_guarded(&mut user_command(GAME_USER, cmd))?;
+    let output = child.wait_with_output().await?;
+    guard.0 = None;
+    Ok(output)
+}
+
+/// Picks the command output lines worth copying to the provisioning log: the latest
+/// line at most every `PROGRESS_INTERVAL`, plus each whole-percent SteamCMD step.
+#[derive(Default)]
+struct ProgressFilter {
+    last: Option<Instant>,
+    percent: Option<u32>,
+}
+
+impl ProgressFilter {
+    fn accept(&mut self, line: &str) -> bool {
+        if let Some(percent) = steamcmd_percent(line) {
+            if self.percent == Some(percent) {
+                return false;
+            }
+            self.percent = Some(percent);
+        } else if self.last.is_some_and(|t| t.elapsed() < PROGRESS_INTERVAL) {
+            return false;
+        }
+        self.last = Some(Instant::now());
+        true
+    }
+}
+
+/// The whole percent of a SteamCMD status line such as
+/// "Update state (0x61) downloading, progress: 45.67 (4567 / 10000)".
+fn steamcmd_percent(line: &str) -> Option<u32> {
+    let (_, rest) = line.split_once("progress: ")?;
+    let value: f64 = rest.split_whitespace().next()?.parse().ok()?;
+    Some(value as u32)
+}
+
+/// Drop the terminal color codes LinuxGSM prints.
+fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars();
+    while let Some(c) = chars.next() {
+        if c == '\u{1b}' {
+            // CSI sequences end with a letter
+            for c in chars.by_ref() {
+                if c.is_ascii_alphabetic() {
+                    break;
+                }
+            }
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Read `reader` to the end, sending each line (the text after its last carriage return,
+/// for progress bars that redraw in place) to `lines`. Returns everything read.
+async fn forward_lines(
+    reader: impl AsyncRead + Unpin,
+    lines: mpsc::UnboundedSender<String>,
+) -> std::io::Result<Vec<u8>> {
+    let mut reader = BufReader::new(reader);
+    let mut all = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        if reader.read_until(b'\n', &mut line).await? == 0 {
+            return Ok(all);
+        }
+        all.extend_from_slice(&line);
+        let text = String::from_utf8_lossy(&line);
+        let text = text
+            .split('\r')
+            .map(str::trim)
+            .rfind(|s| !s.is_empty())
+            .unwrap_or_default();
+        if !text.is_empty() {
+            let _ = lines.send(strip_ansi(text));
+        }
+    }
+}
+
+/// Run a shell command as the game user, copying its output to the provisioning log
+/// under `status` as it runs. Fails with `TimedOut` (killing the command) after `timeout`.
+async fn run_as_user(
+    registry: &ServerRegistry,
+    server_id: &str,
+    status: ProvisioningStatus,
+    cmd: &str,
+    timeout: Duration,
+) -> Result<std::process::Output, std::io::Error> {
+    let (mut child, mut guard) = spawn_guarded(&mut user_command(GAME_USER, cmd))?;
+    let stdout = child.stdout.take().expect("stdout is piped");
+    let stderr = child.stderr.take().expect("stderr is piped");
+
+    let run = async {
+        let (tx, mut rx) = mpsc::unbounded_channel::<String>();
+        let report = async {
+            let mut filter = ProgressFilter::default();
+            while let Some(line) = rx.recv().await {
+                if filter.accept(&line) {
+                    update_status(registry, server_id, status.clone(), &line).await;
+                }
+            }
+        };
+        let (stdout, stderr, ()) = tokio::join!(
+            forward_lines(stdout, tx.clone()),
+            forward_lines(stderr, tx),
+            report
+        );
+        Ok(std::process::Output {
+            status: child.wait().await?,
+            stdout: stdout?,
+            stderr: stderr?,
+        })
+    };
+    let output = match tokio::time::timeout(timeout, run).await {
+        Ok(output) => output,
+        Err(_) => Err(std::io::Error::new(
+            std::io::ErrorKind::TimedOut,
+            format!("timed out after {}s", timeout.as_secs()),
+        )),
+    };
+    if output.is_ok() {
+        guard.0 = None;
+    }
+    output
 }
 
 /// Format command output for logging.
@@ -73,7 +332,21 @@ fn format_output(output: &std::process::Output) -> String {
     msg
 }
 
-/// Run the full provisioning pipeline for a new server.
+/// Run `provision_server` in the background, keeping its handle on the registry so it
+/// can be cancelled.
+pub fn spawn_provisioning(def: ServerDefinition, registry: Arc<ServerRegistry>, config: AppConfig) {
+    let server_id = def.id.clone();
+    let handle = tokio::spawn(requestid::propagate(provision_server(
+        def,
+        registry.clone(),
+        config,
+    )));
+    registry.track_provisioning(&server_id, handle);
+}
+
+/// Run the full provisioning pipeline for a new server. Steps whose result is already
+/// on disk (the directory, the LinuxGSM script) are skipped, so a retry after a failure
+/// or cancel resumes instead of starting over.
 pub async fn provision_server(
     def: ServerDefinition,
     registry: Arc<ServerRegistry>,
@@ -81,17 +354,17 @@ pub async fn provision_server(
 ) {
     let server_id = def.id.clone();
     let base_dir = format!("{}/rustserver-{}", def.base_path, def.id);
+    let step_timeout = Duration::from_secs(config.provisioning.step_timeout_secs);
 
     tracing::info!("Starting provisioning for server '{}'", server_id);
 
     // Step 1: Create directory and set ownership
-    update_status(
-        &registry,
-        &server_id,
-        ProvisioningStatus::Installing,
-        "Creating server directory...",
-    )
-    .await;
+    let step = if Path::new(&base_dir).is_dir() {
+        "Server directory already exists"
+    } else {
+        "Creating server directory..."
+    };
+    update_status(&registry, &server_id, ProvisioningStatus::Installing, step).await;
 
     if let Err(e) = std::fs::create_dir_all(&base_dir) {
         update_status(
@@ -122,53 +395,69 @@ pub async fn provision_server(
     }
 
     // Step 1b: Download and set up LinuxGSM
-    update_status(
-        &registry,
-        &server_id,
-        ProvisioningStatus::Installing,
-        "Downloading LinuxGSM...",
-    )
-    .await;
+    if Path::new(&format!("{}/rustserver", base_dir)).exists() {
+        update_status(
+            &registry,
+            &server_id,
+            ProvisioningStatus::Installing,
+            "LinuxGSM already installed, skipping download",
+        )
+        .await;
+    } else {
+        update_status(
+            &registry,
+            &server_id,
+            ProvisioningStatus::Installing,
+            "Downloading LinuxGSM...",
+        )
+        .await;
 
-    let lgsm_cmd = format!(
-        "cd '{}' && curl -Lo linuxgsm.sh https://linuxgsm.sh && chmod +x linuxgsm.sh && bash linuxgsm.sh rustserver",
-        base_dir
-    );
+        let lgsm_cmd = format!(
+            "cd '{}' && curl -Lo linuxgsm.sh https://linuxgsm.sh && chmod +x linuxgsm.sh && bash linuxgsm.sh rustserver",
+            base_dir
+        );
 
-    let download_result = run_as_user(&lgsm_cmd).await;
+        let download_result = run_as_user(
+            &registry,
+            &server_id,
+            ProvisioningStatus::Installing,
+            &lgsm_cmd,
+            step_timeout,
+        )
+        .await;
 
-    match download_result {
-        Ok(ref output) if output.status.success() => {
-            update_status(
-                &registry,
-                &server_id,
-                ProvisioningStatus::Installing,
-                "LinuxGSM installed",
-            )
-            .await;
-        }
-        Ok(ref output) => {
-            update_status(
-                &registry,
-                &server_id,
-                ProvisioningStatus::Error,
-                &format!("LinuxGSM install failed\n{}", format_output(output)),
-            )
-            .await;
-            return;
-        }
-        Err(e) => {
-            update_status(
-                &registry,
-                &server_id,
-                ProvisioningStatus::Error,
-                &format!("Failed to run LinuxGSM setup: {}", e),
-            )
-            .await;
-            return;
+        match download_result {
+            Ok(ref output) if output.status.success() => {
+                update_status(
+                    &registry,
+                    &server_id,
+                    ProvisioningStatus::Installing,
+                    "LinuxGSM installed",
+                )
+                .await;
+            }
+            Ok(ref output) => {
+                update_status(
+                    &registry,
+                    &server_id,
+                    ProvisioningStatus::Error,
+                    &format!("LinuxGSM install failed\n{}", format_output(output)),
+                )
+                .await;
+                return;
+            }
+            Err(e) => {
+                update_status(
+                    &registry,
+                    &server_id,
+                    ProvisioningStatus::Error,
+                    &format!("Failed to run LinuxGSM setup: {}", e),
+                )
+                .await;
+                return;
+            }
         }
     }
-
     // Step 2: Install the game server
     update_status(
         &registry,
@@ -179,7 +468,14 @@ pub async fn provision_server(
     .await;
 
     let install_cmd = format!("cd '{}' && ./rustserver auto-install", base_dir);
-    let install_result = run_as_user(&install_cmd).await;
+    let install_result = run_as_user(
+        &registry,
+        &server_id,
+        ProvisioningStatus::Downloading,
+        &install_cmd,
+        step_timeout,
+    )
+    .await;
 
     match install_result {
         Ok(ref output) if output.status.success() => {
@@ -227,7 +523,14 @@ pub async fn provision_server(
             "cd '{}/serverfiles' && curl -Lo Oxide.Rust.zip https://umod.org/games/rust/download && unzip -o Oxide.Rust.zip && rm -f Oxide.Rust.zip",
             base_dir
         );
-        let oxide_result = run_as_user(&oxide_cmd).await;
+        let oxide_result = run_as_user(
+            &registry,
+            &server_id,
+            ProvisioningStatus::InstallingOxide,
+            &oxide_cmd,
+            step_timeout,
+        )
+        .await;
 
         match oxide_result {
             Ok(ref output) if output.status.success() => {
@@ -303,7 +606,30 @@ server.port {game_port}
         .output()
         .await;
 
-    // Step 5: Mark as Ready and initialize runtime
+    // Step 5: Copy plugins and configs over from the server this one is a clone of
+    if let Some(ref source) = def.clone_of {
+        update_status(
+            &registry,
+            &server_id,
+            ProvisioningStatus::CopyingFiles,
+            &format!("Copying plugins and configs from '{}'...", source.server_id),
+        )
+        .await;
+        if let Err(e) = copy_from_source(&registry, &def, source).await {
+            update_status(&registry, &server_id, ProvisioningStatus::Error, &e).await;
+            return;
+        }
+        let _ = tokio::process::Command::new("chown")
+            .args([
+                "-R",
+                &format!("{}:{}", GAME_USER, GAME_USER),
+                &format!("{}/serverfiles/oxide", base_dir),
+            ])
+            .output()
+            .await;
+    }
+
+    // Step 6: Mark as Ready and initialize runtime
     update_status(
         &registry,
         &server_id,
@@ -313,25 +639,13 @@ server.port {game_port}
     .await;
 
     // Initialize runtime
-    let game_server_config = def.to_game_server_config();
-    let rcon_client = Arc::new(RconClient::new(game_server_config.rcon.clone()));
-    let game_monitor = Arc::new(GameMonitor::new(config.monitor.history_size));
-    let lgsm_lock = Arc::new(LgsmLock::new());
-
-    let collector_handle = crate::monitor::spawn_game_collector(
-        game_monitor.clone(),
-        rcon_client.clone(),
-        config.monitor.clone(),
-        server_id.clone(),
+    let runtime = ServerRuntime::start(
+        &def.to_game_server_config(),
+        &config.monitor,
+        &registry.events,
+        &registry.metrics,
     );
 
-    let runtime = ServerRuntime {
-        rcon: rcon_client,
-        game_monitor,
-        lgsm_lock,
-        collector_handle: Some(collector_handle),
-    };
-
     registry
         .runtimes
         .write()
@@ -339,31 +653,377 @@ server.port {game_port}
         .insert(server_id.clone(), runtime);
 
     // Save updated definitions
-    {
-        let defs = registry.definitions.read().await;
-        let dynamic: Vec<_> = defs
-            .iter()
-            .filter(|d| d.source == ServerSource::Dynamic)
-            .cloned()
-            .collect();
-        if let Err(e) = crate::persistence::save_servers(&dynamic) {
-            tracing::error!("Failed to save servers after provisioning: {}", e);
+    registry.persist().await;
+
+    tracing::info!("Server '{}' provisioning complete!", server_id);
+}
+
+/// Copy the source's Oxide plugins and configs, and its server.cfg convars when asked,
+/// into the clone `def`, replacing what the clone has. A source deleted since the clone
+/// was made leaves nothing to copy, which isn't an error.
+async fn copy_from_source(
+    registry: &ServerRegistry,
+    def: &ServerDefinition,
+    source: &CloneSource,
+) -> Result<(), String> {
+    let Some(from) = registry.get_config(&source.server_id).await else {
+        update_status(
+            registry,
+            &def.id,
+            ProvisioningStatus::CopyingFiles,
+            &format!(
+                "Server '{}' no longer exists, nothing copied",
+                source.server_id
+            ),
+        )
+        .await;
+        return Ok(());
+    };
+    let to = def.to_game_server_config();
+
+    let dirs = [
+        (
+            "plugins",
+            &from.paths.oxide_plugins,
+            &to.paths.oxide_plugins,
+        ),
+        (
+            "plugin configs",
+            &from.paths.oxide_config,
+            &to.paths.oxide_config,
+        ),
+    ];
+    for (label, from_dir, to_dir) in dirs {
+        let (from_dir, to_dir) = (PathBuf::from(from_dir), PathBuf::from(to_dir));
+        // Plugin folders can be large, so the copy stays off the async workers
+        let copied = tokio::task::spawn_blocking(move || {
+            if !from_dir.is_dir() {
+                return Ok(None);
+            }
+            replace_dir(&from_dir, &to_dir).map(Some)
+        })
+        .await
+        .map_err(|e| format!("Failed to copy {}: {}", label, e))?
+        .map_err(|e| format!("Failed to copy {}: {}", label, e))?;
+        let message = match copied {
+            Some(copied) => format!("Copied {} {}", copied, label),
+            None => format!("No {} to copy", label),
+        };
+        update_status(
+            registry,
+            &def.id,
+            ProvisioningStatus::CopyingFiles,
+            &message,
+        )
+        .await;
+    }
+
+    if source.copy_server_cfg {
+        let (from_cfg, to_cfg) = (from.paths.server_cfg.clone(), to.paths.server_cfg.clone());
+        let message = tokio::task::spawn_blocking(move || copy_convars(&from_cfg, &to_cfg))
+            .await
+            .map_err(|e| format!("Failed to copy server.cfg convars: {}", e))??;
+        update_status(
+            registry,
+            &def.id,
+            ProvisioningStatus::CopyingFiles,
+            &message,
+        )
+        .await;
+    }
+    Ok(())
+}
+
+/// Copy the clonable convars of the server.cfg at `from` into the one at `to`,
+/// returning the status line. A missing source is reported, not an error.
+fn copy_convars(from: &str, to: &str) -> Result<String, String> {
+    match std::fs::read_to_string(from) {
+        Ok(content) => {
+            let convars = servercfg::clonable(&content);
+            let values: Vec<(&str, String)> = convars
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.clone()))
+                .collect();
+            servercfg::set_convars(to, &values)
+                .map_err(|e| format!("Failed to copy server.cfg convars: {}", e))?;
+            Ok(format!("Copied {} server.cfg convars", values.len()))
         }
+        Err(e) => Ok(format!("Source server.cfg not copied: {}", e)),
     }
+}
 
-    tracing::info!("Server '{}' provisioning complete!", server_id);
+/// Replace `to` with a copy of `from`, returning how many entries it holds.
+fn replace_dir(from: &Path, to: &Path) -> std::io::Result<usize> {
+    if to.exists() {
+        std::fs::remove_dir_all(to)?;
+    }
+    if let Some(parent) = to.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    filemanager::copy_dir(from, to)?;
+    Ok(std::fs::read_dir(to)?.count())
 }
 
-async fn update_status(
+/// Set a server's provisioning status, add `message` to its log and announce both.
+pub async fn update_status(
     registry: &ServerRegistry,
     server_id: &str,
     status: ProvisioningStatus,
     message: &str,
 ) {
     tracing::info!("Provisioning '{}': {:?} - {}", server_id, status, message);
-    let mut defs = registry.definitions.write().await;
-    if let Some(def) = defs.iter_mut().find(|d| d.id == server_id) {
-        def.provisioning_status = status;
-        def.provisioning_log.push(message.to_string());
+    registry
+        .update_definition(server_id, |def| {
+            def.provisioning_status = status.clone();
+            def.provisioning_log.push(message.to_string());
+        })
+        .await;
+    // After the update, so provision sockets woken by the event read the new line
+    registry.events.publish(PanelEvent::ProvisioningStatus {
+        server_id: server_id.to_string(),
+        status,
+        message: message.to_string(),
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::MetadataExt;
+
+    /// Whether `pid` exists and isn't a zombie.
+    fn running(pid: u32) -> bool {
+        match std::fs::read_to_string(format!("/proc/{}/stat", pid)) {
+            Ok(stat) => !stat
+                .rsplit_once(')')
+                .is_some_and(|(_, rest)| rest.starts_with(" Z")),
+            Err(_) => false,
+        }
+    }
+
+    #[test]
+    fn steamcmd_percent_reads_progress_lines() {
+        let cases = [
+            (
+                " Update state (0x61) downloading, progress: 45.67 (1234567890 / 2703456789)",
+                Some(45),
+            ),
+            (
+                " Update state (0x5) verifying install, progress: 3.21 (86796288 / 2703456789)",
+                Some(3),
+            ),
+            (
+                " Update state (0x61) downloading, progress: 0.00 (0 / 0)",
+                Some(0),
+            ),
+            ("Success! App '258550' fully installed.", None),
+            ("Loading Steam API...OK", None),
+            ("progress: n/a", None),
+        ];
+        for (line, percent) in cases {
+            assert_eq!(steamcmd_percent(line), percent, "{}", line);
+        }
+    }
+
+    #[test]
+    fn strip_ansi_drops_linuxgsm_colors() {
+        let cases = [
+            (
+                "[\u{1b}[32m  OK  \u{1b}[0m] Installing rustserver: SteamCMD",
+                "[  OK  ] Installing rustserver: SteamCMD",
+            ),
+            (
+                "\u{1b}[1;31mFAIL\u{1b}[0m Checking rustserver",
+                "FAIL Checking rustserver",
+            ),
+            ("\u{1b}[K Installing SteamCMD", " Installing SteamCMD"),
+            ("no colors", "no colors"),
+        ];
+        for (line, plain) in cases {
+            assert_eq!(strip_ansi(line), plain);
+        }
+    }
+
+    #[test]
+    fn progress_filter_keeps_percent_steps_and_throttles_other_lines() {
+        let mut filter = ProgressFilter::default();
+        assert!(filter.accept("Downloading LinuxGSM"));
+        assert!(!filter.accept("Checking dependencies"));
+
+        let line = |p: &str| format!(" Update state (0x61) downloading, progress: {} (1 / 2)", p);
+        assert!(filter.accept(&line("10.02")));
+        assert!(!filter.accept(&line("10.95")));
+        assert!(filter.accept(&line("11.00")));
+
+        filter.last = Some(Instant::now() - PROGRESS_INTERVAL);
+        assert!(filter.accept("Installing rustserver"));
+    }
+
+    #[tokio::test]
+    async fn dropping_the_guard_kills_the_commands_children() {
+        // Switching user needs root, as the panel has in its container
+        if !std::fs::metadata("/proc/self").is_ok_and(|m| m.uid() == 0) {
+            return;
+        }
+        let mut command = user_command("root", "sleep 300 & echo sleep=$!; wait");
+        let (mut child, guard) = spawn_guarded(&mut command).unwrap();
+        let mut stdout = BufReader::new(child.stdout.take().unwrap()).lines();
+        // The login shell's profile may print first
+        let sleep_pid: u32 = loop {
+            let line = stdout
+                .next_line()
+                .await
+                .unwrap()
+                .expect("shell exited early");
+            if let Some(pid) = line.strip_prefix("sleep=") {
+                break pid.parse().unwrap();
+            }
+        };
+        assert!(running(sleep_pid));
+
+        drop(guard);
+        child.wait().await.unwrap();
+        for _ in 0..50 {
+            if !running(sleep_pid) {
+                break;
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        assert!(
+            !running(sleep_pid),
+            "sleep {} outlived the cancel",
+            sleep_pid
+        );
+    }
+
+    /// A static server living in a temp dir, removed on drop.
+    struct StaticServer(PathBuf);
+
+    impl StaticServer {
+        /// `lgsm` goes into LinuxGSM's instance config, `cfg` into server.cfg.
+        fn new(lgsm: &str, cfg: &str) -> Self {
+            let dir =
+                std::env::temp_dir().join(format!("prov-test-{}", uuid::Uuid::new_v4().simple()));
+            let lgsm_dir = dir.join("lgsm/config-lgsm/rustserver");
+            std::fs::create_dir_all(&lgsm_dir).unwrap();
+            std::fs::write(lgsm_dir.join("rustserver.cfg"), lgsm).unwrap();
+            std::fs::write(dir.join("server.cfg"), cfg).unwrap();
+            Self(dir)
+        }
+
+        fn configs(&self) -> HashMap<String, GameServerConfig> {
+            let dir = self.0.display();
+            let config = serde_yaml::from_str(&format!(
+                "paths:\n  base_dir: {}\n  server_cfg: {}/server.cfg\n",
+                dir, dir
+            ))
+            .unwrap();
+            HashMap::from([("main".to_string(), config)])
+        }
+    }
+
+    impl Drop for StaticServer {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn dynamic(game_port: u16) -> ServerDefinition {
+        serde_json::from_value(serde_json::json!({
+            "id": format!("srv-{}", game_port),
+            "name": "Dynamic",
+            "serverType": "vanilla",
+            "source": "dynamic",
+            "provisioningStatus": "ready",
+            "provisioningLog": [],
+            "gamePort": game_port,
+            "rconPort": game_port + 1,
+            "queryPort": game_port - 1000,
+            "maxPlayers": 100,
+            "worldSize": 4000,
+            "seed": 1,
+            "hostname": "Dynamic",
+            "rconPassword": "secret",
+            "basePath": "/tmp/unused",
+            "createdAt": "2026-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn static_servers_count_the_ports_they_are_configured_with() {
+        let server = StaticServer::new(
+            "port=\"41115\"\nqueryport=\"40115\"\n",
+            "server.port 41215\nrcon.port \"41216\"\n",
+        );
+        let in_use = ports_in_use(&[dynamic(41035)], &server.configs());
+
+        // Game defaults and the configured RCON port, whatever the files say
+        for port in [28015, 27015, 28016] {
+            assert!(in_use.contains(&port), "default {} missing", port);
+        }
+        for port in [41115, 40115, 41215, 41216] {
+            assert!(in_use.contains(&port), "static {} missing", port);
+        }
+        for port in [41035, 41036, 40035] {
+            assert!(in_use.contains(&port), "dynamic {} missing", port);
+        }
+    }
+
+    #[test]
+    fn conflicts_name_other_servers_before_the_host() {
+        let server = StaticServer::new("port=\"41115\"\n", "server.queryport 40115\n");
+        let in_use = ports_in_use(&[dynamic(41035)], &server.configs());
+
+        let taken = port_conflicts((41115, 41036, 40115), &in_use);
+        assert_eq!(
+            taken,
+            [
+                "gamePort 41115 is used by another server",
+                "rconPort 41036 is used by another server",
+                "queryPort 40115 is used by another server",
+            ]
+        );
+
+        // Held by something that isn't a panel server
+        let udp = UdpSocket::bind(("0.0.0.0", 0)).unwrap();
+        let tcp = TcpListener::bind(("0.0.0.0", 0)).unwrap();
+        let (game, rcon) = (
+            udp.local_addr().unwrap().port(),
+            tcp.local_addr().unwrap().port(),
+        );
+        let held = port_conflicts((game, rcon, 40035), &in_use);
+        assert_eq!(
+            held,
+            [
+                format!("gamePort {} is already in use on the host", game),
+                format!("rconPort {} is already in use on the host", rcon),
+                "queryPort 40035 is used by another server".to_string(),
+            ]
+        );
+
+        let same = port_conflicts((41115, 41115, 40115), &in_use);
+        assert_eq!(same[0], "gamePort, rconPort and queryPort must all differ");
+    }
+
+    #[test]
+    fn allocation_skips_slots_a_static_server_holds() {
+        let server = StaticServer::new("", "server.port 41015\n");
+        let config: ProvisioningConfig =
+            serde_yaml::from_str("port_range_start: 41015\nport_offset: 10\n").unwrap();
+        let in_use = ports_in_use(&[], &server.configs());
+
+        assert_eq!(
+            allocate_ports(&[], &in_use, &config),
+            Some((41025, 41026, 40025))
+        );
+
+        // Dynamic servers move the search past their own slots
+        let existing = [dynamic(41025)];
+        let in_use = ports_in_use(&existing, &server.configs());
+        assert_eq!(
+            allocate_ports(&existing, &in_use, &config),
+            Some((41035, 41036, 40035))
+        );
     }
 }
@@ -0,0 +1,170 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use utoipa::ToSchema;
+
+use crate::apierror::ApiError;
+use crate::config::{AppConfig, PublicStatusConfig};
+use crate::registry::ServerRegistry;
+use crate::scheduler::Scheduler;
+
+/// One server as shown to the public: no ids, addresses or paths.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicServer {
+    pub slug: String,
+    pub name: String,
+    pub online: bool,
+    /// online, offline or maintenance
+    pub status: String,
+    pub maintenance_note: Option<String>,
+    /// When the maintenance is planned to end, if known.
+    pub maintenance_until: Option<DateTime<Utc>>,
+    pub players: u32,
+    pub max_players: u32,
+    pub map: Option<String>,
+    pub last_wipe: Option<DateTime<Utc>>,
+    /// Next scheduled wipe, if a wipe job is set up.
+    pub next_wipe: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PublicStatusResponse {
+    pub servers: Vec<PublicServer>,
+    pub updated_at: DateTime<Utc>,
+}
+
+/// Last answer and when it was built. Requests inside the TTL share it, so however many
+/// visitors poll the page, the registry is read at most once per `cache_secs`.
+pub struct PublicStatusCache {
+    ttl: Duration,
+    cached: Mutex<Option<(Instant, PublicStatusResponse)>>,
+}
+
+impl PublicStatusCache {
+    pub fn new(config: &PublicStatusConfig) -> Self {
+        Self {
+            ttl: Duration::from_secs(config.cache_secs),
+            cached: Mutex::new(None),
+        }
+    }
+
+    async fn get(&self, registry: &ServerRegistry, scheduler: &Scheduler) -> PublicStatusResponse {
+        let mut cached = self.cached.lock().await;
+        if let Some((built, response)) = cached.as_ref() {
+            if built.elapsed() < self.ttl {
+                return response.clone();
+            }
+        }
+        let response = build(registry, scheduler).await;
+        *cached = Some((Instant::now(), response.clone()));
+        response
+    }
+}
+
+async fn build(registry: &ServerRegistry, scheduler: &Scheduler) -> PublicStatusResponse {
+    let next_wipes = scheduler.next_wipes().await;
+    let mut servers = Vec::new();
+    for def in registry.all_definitions().await {
+        let Some(slug) = def.public_slug.clone() else {
+            continue;
+        };
+        let latest = match registry.get_game_monitor(&def.id).await {
+            Some(monitor) => monitor.history.read().await.latest().cloned(),
+            None => None,
+        };
+        let online = latest.as_ref().is_some_and(|s| s.online);
+        let maintenance = def.active_maintenance();
+        // Visitors don't need to tell a deliberate stop from an outage
+        let status = match def.state(online) {
+            "stopped" => "offline",
+            state => state,
+        };
+        servers.push(PublicServer {
+            slug,
+            name: def.name.clone(),
+            online,
+            status: status.to_string(),
+            maintenance_note: maintenance.and_then(|m| m.note.clone()),
+            maintenance_until: maintenance.and_then(|m| m.until),
+            players: latest.as_ref().map_or(0, |s| s.players),
+            max_players: latest.as_ref().map_or(0, |s| s.max_players),
+            map: latest.map(|s| s.map).filter(|m| !m.is_empty()),
+            last_wipe: def.last_wipe_at,
+            next_wipe: next_wipes.get(&def.id).copied(),
+        });
+    }
+    PublicStatusResponse {
+        servers,
+        updated_at: Utc::now(),
+    }
+}
+
+/// Lowercase ASCII letters and digits joined by single dashes; "server" if nothing is left.
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            slug.push(c.to_ascii_lowercase());
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        "server".to_string()
+    } else {
+        slug.to_string()
+    }
+}
+
+/// Whether `slug` is already in the form `slugify` produces.
+pub fn is_valid_slug(slug: &str) -> bool {
+    slugify(slug) == slug
+}
+
+/// Slug for `name` that isn't in `taken`, adding -2, -3, ... when needed.
+pub fn unique_slug(name: &str, taken: &[&str]) -> String {
+    let base = slugify(name);
+    let mut slug = base.clone();
+    let mut n = 2;
+    while taken.contains(&slug.as_str()) {
+        slug = format!("{}-{}", base, n);
+        n += 1;
+    }
+    slug
+}
+
+/// GET /api/public/status
+///
+/// Unauthenticated when `panel.public_status.enabled`; lists only servers with a public slug.
+#[utoipa::path(
+    get,
+    path = "/api/public/status",
+    tag = "public",
+    security(()),
+    responses(
+        (status = 200, body = PublicStatusResponse),
+        (status = 404, description = "Public status page is disabled", body = ApiError)
+    )
+)]
+pub async fn public_status(
+    config: web::Data<AppConfig>,
+    cache: web::Data<Arc<PublicStatusCache>>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> Result<HttpResponse, ApiError> {
+    if !config.panel.public_status.enabled {
+        return Err(ApiError::not_found("Public status page is disabled"));
+    }
+    Ok(HttpResponse::Ok()
+        .insert_header((
+            "Cache-Control",
+            format!("public, max-age={}", config.panel.public_status.cache_secs),
+        ))
+        .json(cache.get(&registry, &scheduler).await))
+}
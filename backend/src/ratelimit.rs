@@ -0,0 +1,371 @@
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::http::{header, Method};
+use actix_web::{web, Error, HttpMessage, HttpRequest, HttpResponse};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::{ready, Ready};
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use utoipa::ToSchema;
+
+use crate::apierror::{ApiError, ErrorCode};
+use crate::auth::Claims;
+use crate::config::LimitsConfig;
+
+/// Drop idle buckets at most this often.
+const PRUNE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// LGSM commands exposed as POST /api/servers/{id}/<action>.
+const LGSM_ACTIONS: &[&str] = &[
+    "start",
+    "stop",
+    "restart",
+    "update",
+    "backup",
+    "save",
+    "wipe",
+    "force-update",
+    "validate",
+    "check-update",
+    "monitor-check",
+    "details",
+    "update-lgsm",
+    "full-wipe",
+    "map-wipe",
+    "provision",
+];
+
+/// A per-minute request budget. Every /api request spends from `Global`; some routes also
+/// spend from a stricter class budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Budget {
+    Global,
+    Rcon,
+    Action,
+}
+
+/// Stricter budget for routes that do an RCON round-trip or start long-running work.
+fn route_budget(method: &Method, path: &str) -> Option<Budget> {
+    if *method == Method::POST
+        && (path == "/api/servers" || path == "/api/servers/bulk" || is_job_run(path))
+    {
+        return Some(Budget::Action);
+    }
+    let rest = path.strip_prefix("/api/servers/")?;
+    let (_, route) = rest.split_once('/')?;
+    // Player history is read from the panel database, not RCON
+    let stored = matches!(route, "players/history" | "players/known");
+    if route == "players"
+        || (route.starts_with("players/") && !stored)
+        || route == "broadcast"
+        || (route.starts_with("plugins/")
+            && (route.ends_with("/reload")
+                || route.ends_with("/enable")
+                || route.ends_with("/disable")))
+        || route == "plugins/bulk"
+        || (route.starts_with("console/snippets/") && route.ends_with("/run"))
+    {
+        return Some(Budget::Rcon);
+    }
+    if *method == Method::POST && LGSM_ACTIONS.contains(&route) {
+        return Some(Budget::Action);
+    }
+    None
+}
+
+fn is_job_run(path: &str) -> bool {
+    path.strip_prefix("/api/schedule/")
+        .and_then(|rest| rest.strip_suffix("/run"))
+        .is_some_and(|id| !id.is_empty() && !id.contains('/'))
+}
+
+/// Multipart upload endpoints, which stream to disk under `upload_max_bytes`.
+fn is_upload_path(path: &str) -> bool {
+    path.starts_with("/api/servers/")
+        && (path.ends_with("/files/upload") || path.ends_with("/plugins/upload"))
+}
+
+/// Who a request is counted against: the logged-in user, else the peer address.
+fn client_key(req: &HttpRequest) -> String {
+    if let Some(claims) = req.extensions().get::<Claims>() {
+        return format!("user:{}", claims.sub);
+    }
+    match req.peer_addr() {
+        Some(addr) => format!("ip:{}", addr.ip()),
+        None => "ip:unknown".to_string(),
+    }
+}
+
+/// Token bucket holding up to a minute's worth of requests.
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl Bucket {
+    fn refill(&mut self, per_minute: u32, now: Instant) {
+        let elapsed = now.duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * per_minute as f64 / 60.0).min(per_minute as f64);
+        self.updated = now;
+    }
+
+    /// Seconds until one request fits again.
+    fn wait_secs(&self, per_minute: u32) -> u64 {
+        ((1.0 - self.tokens) * 60.0 / per_minute as f64)
+            .ceil()
+            .max(1.0) as u64
+    }
+}
+
+struct LimiterState {
+    buckets: HashMap<(String, Budget), Bucket>,
+    last_prune: Instant,
+}
+
+/// One client's spend against one budget, as reported by GET /api/limits.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BudgetUsage {
+    pub client: String,
+    pub budget: Budget,
+    /// Requests counted in the last minute (approximate, the bucket refills continuously).
+    pub used: u32,
+    pub limit: u32,
+}
+
+pub struct RateLimiter {
+    limits: LimitsConfig,
+    state: Mutex<LimiterState>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: LimitsConfig) -> Self {
+        Self {
+            limits,
+            state: Mutex::new(LimiterState {
+                buckets: HashMap::new(),
+                last_prune: Instant::now(),
+            }),
+        }
+    }
+
+    /// Requests per minute for a budget; 0 means unlimited.
+    fn per_minute(&self, budget: Budget) -> u32 {
+        match budget {
+            Budget::Global => self.limits.global_per_minute,
+            Budget::Rcon => self.limits.rcon_per_minute,
+            Budget::Action => self.limits.action_per_minute,
+        }
+    }
+
+    /// Spend one request from the global budget and `class` (if any). Nothing is spent when
+    /// either is exhausted; the error is the Retry-After in seconds.
+    fn check(&self, client: &str, class: Option<Budget>) -> Result<(), u64> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        if now.duration_since(state.last_prune) >= PRUNE_INTERVAL {
+            self.prune(&mut state, now);
+        }
+
+        let budgets: Vec<(Budget, u32)> = std::iter::once(Budget::Global)
+            .chain(class)
+            .map(|b| (b, self.per_minute(b)))
+            .filter(|(_, per_minute)| *per_minute > 0)
+            .collect();
+
+        let mut retry_after = 0;
+        for &(budget, per_minute) in &budgets {
+            let bucket = state
+                .buckets
+                .entry((client.to_string(), budget))
+                .or_insert_with(|| Bucket {
+                    tokens: per_minute as f64,
+                    updated: now,
+                });
+            bucket.refill(per_minute, now);
+            if bucket.tokens < 1.0 {
+                retry_after = retry_after.max(bucket.wait_secs(per_minute));
+            }
+        }
+        if retry_after > 0 {
+            return Err(retry_after);
+        }
+
+        for &(budget, _) in &budgets {
+            if let Some(bucket) = state.buckets.get_mut(&(client.to_string(), budget)) {
+                bucket.tokens -= 1.0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Forget buckets that have refilled completely; they hold no state.
+    fn prune(&self, state: &mut LimiterState, now: Instant) {
+        state.buckets.retain(|(_, budget), bucket| {
+            let per_minute = self.per_minute(*budget);
+            bucket.refill(per_minute, now);
+            bucket.tokens < per_minute as f64
+        });
+        state.last_prune = now;
+    }
+
+    fn usage(&self) -> Vec<BudgetUsage> {
+        let now = Instant::now();
+        let mut state = self.state.lock().unwrap();
+        self.prune(&mut state, now);
+        let mut usage: Vec<BudgetUsage> = state
+            .buckets
+            .iter()
+            .map(|((client, budget), bucket)| {
+                let limit = self.per_minute(*budget);
+                BudgetUsage {
+                    client: client.clone(),
+                    budget: *budget,
+                    used: (limit as f64 - bucket.tokens).round().max(0.0) as u32,
+                    limit,
+                }
+            })
+            .collect();
+        usage.sort_by(|a, b| a.client.cmp(&b.client).then(a.budget.cmp(&b.budget)));
+        usage
+    }
+}
+
+/// Actix-web middleware enforcing `RateLimiter` budgets and the Content-Length cap on /api.
+/// Wrap it inside `JwtAuth` so authenticated requests are counted per user.
+pub struct RateLimit(pub Arc<RateLimiter>);
+
+impl<S, B> Transform<S, ServiceRequest> for RateLimit
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RateLimitMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RateLimitMiddleware {
+            service: Rc::new(service),
+            limiter: self.0.clone(),
+        }))
+    }
+}
+
+pub struct RateLimitMiddleware<S> {
+    service: Rc<S>,
+    limiter: Arc<RateLimiter>,
+}
+
+impl<S, B> Service<ServiceRequest> for RateLimitMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future =
+        std::pin::Pin<Box<dyn std::future::Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(
+        &self,
+        ctx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let limiter = self.limiter.clone();
+
+        Box::pin(async move {
+            if !req.path().starts_with("/api/") {
+                return service.call(req).await;
+            }
+
+            let max_bytes = if req.path().starts_with("/api/transfer/") {
+                limiter.limits.transfer_max_bytes
+            } else if is_upload_path(req.path()) {
+                limiter.limits.upload_max_bytes
+            } else {
+                limiter.limits.max_request_bytes
+            };
+            let declared = req
+                .headers()
+                .get(header::CONTENT_LENGTH)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<u64>().ok());
+            if declared.is_some_and(|len| len > max_bytes) {
+                return Err(ApiError::new(
+                    ErrorCode::PayloadTooLarge,
+                    format!("Request body exceeds {} bytes", max_bytes),
+                )
+                .with_detail("maxBytes", max_bytes)
+                .into());
+            }
+
+            let client = client_key(req.request());
+            let class = route_budget(req.method(), req.path());
+            if let Err(retry_after) = limiter.check(&client, class) {
+                tracing::debug!(
+                    "Rate limited {} on {} {} (retry in {}s)",
+                    client,
+                    req.method(),
+                    req.path(),
+                    retry_after
+                );
+                return Err(ApiError::rate_limited(
+                    format!("Too many requests, retry in {} seconds", retry_after),
+                    retry_after,
+                )
+                .into());
+            }
+
+            service.call(req).await
+        })
+    }
+}
+
+/// Configured limits plus every client's current spend.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct LimitsResponse {
+    pub global_per_minute: u32,
+    pub rcon_per_minute: u32,
+    pub action_per_minute: u32,
+    pub json_body_bytes: usize,
+    pub payload_bytes: usize,
+    pub max_request_bytes: u64,
+    pub transfer_max_bytes: u64,
+    pub upload_max_bytes: u64,
+    /// The caller's client key, as used in `usage`.
+    pub client: String,
+    pub usage: Vec<BudgetUsage>,
+}
+
+/// GET /api/limits
+#[utoipa::path(
+    get,
+    path = "/api/limits",
+    tag = "limits",
+    responses((status = 200, body = LimitsResponse))
+)]
+pub async fn get_limits(req: HttpRequest, limiter: web::Data<Arc<RateLimiter>>) -> HttpResponse {
+    let limits = &limiter.limits;
+    HttpResponse::Ok().json(LimitsResponse {
+        global_per_minute: limits.global_per_minute,
+        rcon_per_minute: limits.rcon_per_minute,
+        action_per_minute: limits.action_per_minute,
+        json_body_bytes: limits.json_body_bytes,
+        payload_bytes: limits.payload_bytes,
+        max_request_bytes: limits.max_request_bytes,
+        transfer_max_bytes: limits.transfer_max_bytes,
+        upload_max_bytes: limits.upload_max_bytes,
+        client: client_key(&req),
+        usage: limiter.usage(),
+    })
+}
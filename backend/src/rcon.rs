@@ -1,8 +1,10 @@
+use chrono::{DateTime, Utc};
 use futures_util::{SinkExt, StreamExt};
 use serde::{Deserialize, Serialize};
-use std::sync::atomic::{AtomicI32, Ordering};
-use std::sync::Arc;
-use tokio::sync::{Mutex, oneshot};
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+use std::sync::{Arc, Weak};
+use tokio::sync::{broadcast, oneshot, Mutex};
 use tokio::time::{timeout, Duration};
 use tokio_tungstenite::tungstenite::Message;
 
@@ -31,6 +33,70 @@ pub struct RconResponse {
     pub msg_type: String,
 }
 
+/// A line of console output: sent by the server on its own (chat, log, warnings, errors),
+/// or a command echo ("Command") recorded for the scrollback.
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConsoleLine {
+    /// RCON message type: "Generic", "Log", "Warning", "Error", "Chat", ...
+    #[serde(rename = "type")]
+    pub msg_type: String,
+    pub message: String,
+    pub timestamp: DateTime<Utc>,
+}
+
+/// Console lines kept as scrollback for new subscribers and the REST fallback.
+const CONSOLE_SCROLLBACK: usize = 500;
+/// Longer lines are cut in the scrollback, which caps its memory per server.
+const MAX_SCROLLBACK_LINE: usize = 4096;
+/// Lines a slow subscriber may fall behind before it starts missing some.
+const CONSOLE_CHANNEL_CAPACITY: usize = 512;
+
+/// Fans unsolicited console output out to every subscriber over a single RCON connection,
+/// and keeps the scrollback shared by all console sessions.
+struct ConsoleFeed {
+    tx: broadcast::Sender<ConsoleLine>,
+    backlog: std::sync::Mutex<VecDeque<ConsoleLine>>,
+}
+
+impl ConsoleFeed {
+    fn new() -> Self {
+        let (tx, _) = broadcast::channel(CONSOLE_CHANNEL_CAPACITY);
+        Self {
+            tx,
+            backlog: std::sync::Mutex::new(VecDeque::with_capacity(CONSOLE_SCROLLBACK)),
+        }
+    }
+
+    fn publish(&self, line: ConsoleLine) {
+        // Hold the backlog lock while sending so subscribe() never sees a line twice
+        let mut backlog = self.backlog.lock().unwrap();
+        push_scrollback(&mut backlog, line.clone());
+        // No receivers is fine
+        let _ = self.tx.send(line);
+    }
+
+    /// Add a line to the scrollback only; live sessions already showed it.
+    fn record(&self, line: ConsoleLine) {
+        push_scrollback(&mut self.backlog.lock().unwrap(), line);
+    }
+}
+
+fn push_scrollback(backlog: &mut VecDeque<ConsoleLine>, mut line: ConsoleLine) {
+    if line.message.len() > MAX_SCROLLBACK_LINE {
+        let mut end = MAX_SCROLLBACK_LINE;
+        while !line.message.is_char_boundary(end) {
+            end -= 1;
+        }
+        line.message.truncate(end);
+        line.message.push_str(" [...]");
+    }
+    if backlog.len() >= CONSOLE_SCROLLBACK {
+        backlog.pop_front();
+    }
+    backlog.push_back(line);
+}
+
 /// Parsed server info from the "serverinfo" RCON command.
 #[derive(Debug, Serialize, Deserialize, Clone, Default)]
 #[serde(rename_all = "camelCase")]
@@ -64,7 +130,7 @@ pub struct ServerInfo {
 }
 
 /// Parsed player entry from the "playerlist" RCON command.
-#[derive(Debug, Serialize, Deserialize, Clone)]
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
 #[serde(rename_all = "camelCase")]
 pub struct Player {
     #[serde(default, alias = "SteamID")]
@@ -83,6 +149,87 @@ pub struct Player {
     pub violation_level: f64,
 }
 
+/// Console command used for direct messages to one player.
+const SAY_TO_COMMAND: &str = "sayto";
+
+/// Quote a value as a single RCON argument. Quotes and backslashes are escaped and
+/// line breaks removed so user input can never terminate the argument early and
+/// smuggle a second command into the line.
+pub fn quote_arg(value: &str) -> String {
+    let mut out = String::with_capacity(value.len() + 2);
+    out.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' | '\r' => out.push(' '),
+            c if c.is_control() => {}
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Build a global chat command for `message`.
+pub fn say_command(message: &str) -> String {
+    format!("say {}", quote_arg(message))
+}
+
+/// Build a direct message command. Steam IDs must be all digits; they are sent unquoted.
+fn say_to_command(steam_id: &str, message: &str) -> anyhow::Result<String> {
+    if steam_id.is_empty() || !steam_id.chars().all(|c| c.is_ascii_digit()) {
+        anyhow::bail!("Invalid Steam ID '{}'", steam_id);
+    }
+    Ok(format!(
+        "{} {} {}",
+        SAY_TO_COMMAND,
+        steam_id,
+        quote_arg(message)
+    ))
+}
+
+fn kick_command(target: &str, reason: &str) -> String {
+    format!("kick {} {}", quote_arg(target), quote_arg(reason))
+}
+
+fn ban_command(steam_id: &str, name: &str, reason: &str) -> String {
+    format!(
+        "banid {} {} {}",
+        quote_arg(steam_id),
+        quote_arg(name),
+        quote_arg(reason)
+    )
+}
+
+/// Wrap a message in Rust rich-text tags. Colors may be a name (`red`) or hex (`#ff0000`).
+pub fn format_rich_text(
+    message: &str,
+    color: Option<&str>,
+    size: Option<u32>,
+) -> anyhow::Result<String> {
+    let mut text = message.to_string();
+    if let Some(size) = size {
+        if !(8..=64).contains(&size) {
+            anyhow::bail!("Text size must be between 8 and 64");
+        }
+        text = format!("<size={}>{}</size>", size, text);
+    }
+    if let Some(color) = color {
+        let valid = match color.strip_prefix('#') {
+            Some(hex) => {
+                (hex.len() == 6 || hex.len() == 8) && hex.chars().all(|c| c.is_ascii_hexdigit())
+            }
+            None => !color.is_empty() && color.chars().all(|c| c.is_ascii_alphabetic()),
+        };
+        if !valid {
+            anyhow::bail!("Invalid color '{}'", color);
+        }
+        text = format!("<color={}>{}</color>", color, text);
+    }
+    Ok(text)
+}
+
 type WsSink =
     futures_util::stream::SplitSink<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, Message>;
 
@@ -95,19 +242,55 @@ struct RconInner {
     pending: std::collections::HashMap<i32, PendingRequest>,
 }
 
+/// First wait before reconnecting after the link drops; doubled per failed attempt.
+const RECONNECT_INITIAL_DELAY: Duration = Duration::from_secs(1);
+const RECONNECT_MAX_DELAY: Duration = Duration::from_secs(60);
+
+/// Maximum number of commands held while the RCON link is down.
+const MAX_QUEUED_COMMANDS: usize = 50;
+/// Queued commands older than this are dropped instead of flushed.
+const QUEUED_COMMAND_TTL_SECS: i64 = 600;
+
+/// A command waiting for the RCON connection to come back.
+struct QueuedCommand {
+    command: String,
+    label: String,
+    queued_at: DateTime<Utc>,
+}
+
+/// Outcome of a queued command once it was flushed after a reconnect.
+#[derive(Debug, Clone)]
+pub struct FlushedCommand {
+    pub command: String,
+    /// Caller-supplied tag (the scheduler uses the id of the run that queued it).
+    pub label: String,
+    pub result: Result<String, String>,
+}
+
 /// WebSocket RCON client for the Rust game server.
 /// The Rust game server uses WebSocket RCON on port 28016.
-/// Protocol: connect to ws://{host}:{port}/{password}
+/// Protocol: connect to ws://{host}:{port}/{password} (or wss:// behind a TLS proxy)
 pub struct RconClient {
     config: RconConfig,
+    /// Current password; replaced on rotation so reconnects use the new one.
+    password: std::sync::RwLock<String>,
     inner: Arc<Mutex<RconInner>>,
     next_id: AtomicI32,
     reader_handle: Mutex<Option<tokio::task::JoinHandle<()>>>,
+    queue: Mutex<VecDeque<QueuedCommand>>,
+    flushed: Mutex<Vec<FlushedCommand>>,
+    console: Arc<ConsoleFeed>,
+    /// Handed to the reader and reconnect tasks, so they don't keep the client alive.
+    this: Weak<RconClient>,
+    /// Set while the link is down and the reconnect task is retrying it.
+    reconnecting: AtomicBool,
 }
 
 impl RconClient {
-    pub fn new(config: RconConfig) -> Self {
-        Self {
+    /// Returned in an Arc: a dropped link is retried by a task holding a weak handle to it.
+    pub fn new(config: RconConfig) -> Arc<Self> {
+        Arc::new_cyclic(|this| Self {
+            password: std::sync::RwLock::new(config.password.clone()),
             config,
             inner: Arc::new(Mutex::new(RconInner {
                 sink: None,
@@ -115,9 +298,65 @@ impl RconClient {
             })),
             next_id: AtomicI32::new(1),
             reader_handle: Mutex::new(None),
+            queue: Mutex::new(VecDeque::new()),
+            flushed: Mutex::new(Vec::new()),
+            console: Arc::new(ConsoleFeed::new()),
+            this: this.clone(),
+            reconnecting: AtomicBool::new(false),
+        })
+    }
+
+    /// Recent console lines plus a receiver for everything after them.
+    pub fn subscribe_console(&self) -> (Vec<ConsoleLine>, broadcast::Receiver<ConsoleLine>) {
+        let backlog = self.console.backlog.lock().unwrap();
+        let rx = self.console.tx.subscribe();
+        (backlog.iter().cloned().collect(), rx)
+    }
+
+    /// The shared console scrollback, oldest first.
+    pub fn console_scrollback(&self) -> Vec<ConsoleLine> {
+        self.console.backlog.lock().unwrap().iter().cloned().collect()
+    }
+
+    /// Add a console command and its reply to the scrollback, so sessions opened later
+    /// see them too.
+    pub fn record_console_command(&self, command: &str, reply: &Result<String, String>) {
+        let now = Utc::now();
+        self.console.record(ConsoleLine {
+            msg_type: "Command".to_string(),
+            message: format!("> {}", command),
+            timestamp: now,
+        });
+        let (msg_type, message) = match reply {
+            Ok(text) => ("Generic", text.clone()),
+            Err(e) => ("Error", format!("Error: {}", e)),
+        };
+        if !message.is_empty() {
+            self.console.record(ConsoleLine {
+                msg_type: msg_type.to_string(),
+                message,
+                timestamp: now,
+            });
         }
     }
 
+    /// Run commands one after another, like typing them into the console: each is recorded
+    /// in the scrollback and a failure doesn't stop the rest. Replies are in command order.
+    pub async fn execute_batch(&self, commands: &[String]) -> Vec<Result<String, String>> {
+        let mut replies = Vec::with_capacity(commands.len());
+        for cmd in commands {
+            let reply = self.execute(cmd).await.map_err(|e| e.to_string());
+            self.record_console_command(cmd, &reply);
+            replies.push(reply);
+        }
+        replies
+    }
+
+    /// Use a new password for future connections (the current socket stays open).
+    pub fn set_password(&self, password: &str) {
+        *self.password.write().unwrap() = password.to_string();
+    }
+
     /// Connect (or reconnect) to the RCON WebSocket.
     pub async fn connect(&self) -> anyhow::Result<()> {
         // Close existing connection
@@ -135,13 +374,23 @@ impl RconClient {
             }
         }
 
-        let url = format!(
-            "ws://{}:{}/{}",
-            self.config.host, self.config.port, self.config.password
-        );
-        tracing::info!("Connecting to RCON at ws://{}:{}/***", self.config.host, self.config.port);
+        let base_url = self.config.base_url()?;
+        let password = self.password.read().unwrap().clone();
+        let url = format!("{}/{}", base_url, password);
+        tracing::info!("Connecting to RCON at {}/***", base_url);
+
+        let connector = if base_url.starts_with("wss://") && self.config.insecure_skip_verify {
+            let tls = native_tls::TlsConnector::builder()
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true)
+                .build()?;
+            Some(tokio_tungstenite::Connector::NativeTls(tls))
+        } else {
+            None
+        };
 
-        let (ws_stream, _) = tokio_tungstenite::connect_async(&url).await?;
+        let (ws_stream, _) =
+            tokio_tungstenite::connect_async_tls_with_config(&url, None, false, connector).await?;
         let (sink, stream) = ws_stream.split();
 
         {
@@ -149,10 +398,16 @@ impl RconClient {
             inner.sink = Some(sink);
         }
 
-        // Spawn reader task to route responses to pending requests
+        // Spawn reader task to route responses to pending requests. It is aborted on a
+        // deliberate disconnect, so running past the loop means the link dropped.
         let inner_clone = self.inner.clone();
+        let console = self.console.clone();
+        let this = self.this.clone();
         let handle = tokio::spawn(async move {
-            Self::reader_loop(stream, inner_clone).await;
+            Self::reader_loop(stream, inner_clone, console).await;
+            if let Some(client) = this.upgrade() {
+                client.link_lost();
+            }
         });
 
         {
@@ -161,9 +416,113 @@ impl RconClient {
         }
 
         tracing::info!("RCON connected successfully");
+        self.reconnecting.store(false, Ordering::SeqCst);
+        self.flush_queue().await;
         Ok(())
     }
 
+    /// The link went down on its own: retry it in the background, waiting 1s, 2s, 4s, ...
+    /// up to a minute between attempts. Commands fail fast meanwhile. Does nothing if a
+    /// reconnect is already under way.
+    fn link_lost(&self) {
+        if self.reconnecting.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        let target = self.target();
+        tracing::warn!("RCON link to {} lost, reconnecting", target);
+
+        let this = self.this.clone();
+        tokio::spawn(async move {
+            let mut delay = RECONNECT_INITIAL_DELAY;
+            let mut attempt = 1u32;
+            loop {
+                tokio::time::sleep(delay).await;
+                // Client dropped (server deleted) or reconnected by a caller meanwhile
+                let Some(client) = this.upgrade() else {
+                    return;
+                };
+                if !client.reconnecting.load(Ordering::SeqCst) || client.is_connected().await {
+                    return;
+                }
+                match client.connect().await {
+                    Ok(()) => {
+                        tracing::info!(
+                            "RCON link to {} restored after {} attempt(s)",
+                            target,
+                            attempt
+                        );
+                        return;
+                    }
+                    Err(e) => {
+                        delay = (delay * 2).min(RECONNECT_MAX_DELAY);
+                        tracing::info!(
+                            "RCON reconnect to {} failed (attempt {}): {}; next try in {}s",
+                            target,
+                            attempt,
+                            e,
+                            delay.as_secs()
+                        );
+                        attempt += 1;
+                    }
+                }
+            }
+        });
+    }
+
+    /// host:port of the RCON endpoint, for logs.
+    fn target(&self) -> String {
+        format!("{}:{}", self.config.host, self.config.port)
+    }
+
+    /// Whether the link dropped and is being retried in the background.
+    pub fn is_reconnecting(&self) -> bool {
+        self.reconnecting.load(Ordering::SeqCst)
+    }
+
+    /// Send every queued command in order now that the connection is back.
+    async fn flush_queue(&self) {
+        let queued: Vec<QueuedCommand> = {
+            let mut queue = self.queue.lock().await;
+            queue.drain(..).collect()
+        };
+        if queued.is_empty() {
+            return;
+        }
+
+        let cutoff = Utc::now() - chrono::Duration::seconds(QUEUED_COMMAND_TTL_SECS);
+        let mut results = Vec::with_capacity(queued.len());
+        for item in queued {
+            let result = if item.queued_at < cutoff {
+                Err("Expired while RCON was offline".to_string())
+            } else {
+                self.send_command(&item.command).await.map_err(|e| e.to_string())
+            };
+            tracing::info!(
+                "Flushed queued RCON command '{}' ({}): {}",
+                item.command,
+                item.label,
+                if result.is_ok() { "ok" } else { "failed" }
+            );
+            results.push(FlushedCommand {
+                command: item.command,
+                label: item.label,
+                result,
+            });
+        }
+
+        self.flushed.lock().await.extend(results);
+    }
+
+    /// Number of commands queued for the next reconnect.
+    pub async fn queued_count(&self) -> usize {
+        self.queue.lock().await.len()
+    }
+
+    /// Take the results of commands flushed since the last call.
+    pub async fn take_flushed(&self) -> Vec<FlushedCommand> {
+        std::mem::take(&mut *self.flushed.lock().await)
+    }
+
     async fn reader_loop(
         mut stream: futures_util::stream::SplitStream<
             tokio_tungstenite::WebSocketStream<
@@ -171,6 +530,7 @@ impl RconClient {
             >,
         >,
         inner: Arc<Mutex<RconInner>>,
+        console: Arc<ConsoleFeed>,
     ) {
         while let Some(msg) = stream.next().await {
             match &msg {
@@ -179,21 +539,11 @@ impl RconClient {
             }
             match msg {
                 Ok(Message::Text(text)) => {
-                    if let Ok(response) = serde_json::from_str::<RconResponse>(&text) {
-                        let mut guard = inner.lock().await;
-                        if let Some(pending) = guard.pending.remove(&response.identifier) {
-                            let _ = pending.sender.send(response.message);
-                        }
-                    }
+                    Self::route_response(&text, &inner, &console).await;
                 }
                 Ok(Message::Binary(data)) => {
                     if let Ok(text) = String::from_utf8(data.to_vec()) {
-                        if let Ok(response) = serde_json::from_str::<RconResponse>(&text) {
-                            let mut guard = inner.lock().await;
-                            if let Some(pending) = guard.pending.remove(&response.identifier) {
-                                let _ = pending.sender.send(response.message);
-                            }
-                        }
+                        Self::route_response(&text, &inner, &console).await;
                     }
                 }
                 Ok(Message::Close(_)) => {
@@ -214,19 +564,98 @@ impl RconClient {
         guard.pending.clear();
     }
 
+    /// Hand a response to the request waiting for it, or publish it as console output.
+    async fn route_response(text: &str, inner: &Mutex<RconInner>, console: &ConsoleFeed) {
+        let Ok(response) = serde_json::from_str::<RconResponse>(text) else {
+            return;
+        };
+        let pending = inner.lock().await.pending.remove(&response.identifier);
+        match pending {
+            Some(pending) => {
+                let _ = pending.sender.send(response.message);
+            }
+            None => console.publish(ConsoleLine {
+                msg_type: response.msg_type,
+                message: response.message,
+                timestamp: Utc::now(),
+            }),
+        }
+    }
+
+    /// Close the connection with a WebSocket close frame and stop the reader task. Also
+    /// ends a reconnect in progress.
+    pub async fn disconnect(&self) {
+        self.reconnecting.store(false, Ordering::SeqCst);
+        let sink = {
+            let mut inner = self.inner.lock().await;
+            inner.pending.clear();
+            inner.sink.take()
+        };
+        if let Some(mut sink) = sink {
+            if let Err(e) = sink.close().await {
+                tracing::debug!("RCON close failed: {}", e);
+            }
+        }
+        if let Some(handle) = self.reader_handle.lock().await.take() {
+            handle.abort();
+        }
+    }
+
     /// Check if connected (has an active sink).
     pub async fn is_connected(&self) -> bool {
         let inner = self.inner.lock().await;
         inner.sink.is_some()
     }
 
-    /// Execute an RCON command and wait for the response.
+    /// Execute an RCON command and wait for the response. Fails at once while a dropped
+    /// link is being reconnected.
     pub async fn execute(&self, cmd: &str) -> anyhow::Result<String> {
-        // Try to connect if not connected
+        if self.is_reconnecting() {
+            anyhow::bail!("RCON disconnected, reconnecting");
+        }
+        // Never connected yet (or disconnected on purpose): connect on demand
         if !self.is_connected().await {
             self.connect().await?;
         }
 
+        self.send_command(cmd).await
+    }
+
+    /// Execute a command, or queue it for the next reconnect if the server is unreachable.
+    /// Only for fire-and-forget commands (announcements, scheduled commands) — interactive
+    /// callers should use `execute` so they fail fast.
+    pub async fn execute_queued(&self, cmd: &str, label: &str) -> anyhow::Result<String> {
+        let offline =
+            self.is_reconnecting() || (!self.is_connected().await && self.connect().await.is_err());
+        if offline {
+            let cutoff = Utc::now() - chrono::Duration::seconds(QUEUED_COMMAND_TTL_SECS);
+            let mut queue = self.queue.lock().await;
+            queue.retain(|q| q.queued_at >= cutoff);
+            if queue.len() >= MAX_QUEUED_COMMANDS {
+                if let Some(dropped) = queue.pop_front() {
+                    tracing::warn!(
+                        "RCON queue full, dropping oldest command '{}' ({})",
+                        dropped.command,
+                        dropped.label
+                    );
+                }
+            }
+            queue.push_back(QueuedCommand {
+                command: cmd.to_string(),
+                label: label.to_string(),
+                queued_at: Utc::now(),
+            });
+            return Ok(format!(
+                "RCON offline, command queued ({} pending)",
+                queue.len()
+            ));
+        }
+
+        self.execute(cmd).await
+    }
+
+    /// Send a command over the current connection without attempting to reconnect.
+    async fn send_command(&self, cmd: &str) -> anyhow::Result<String> {
         let id = self.next_id.fetch_add(1, Ordering::SeqCst);
         let request = RconRequest {
             identifier: id,
@@ -242,7 +671,14 @@ impl RconClient {
             inner.pending.insert(id, PendingRequest { sender: tx });
             if let Some(ref mut sink) = inner.sink {
                 tracing::info!("RCON sending command id={}: {}", id, cmd);
-                sink.send(Message::Text(json)).await?;
+                if let Err(e) = sink.send(Message::Text(json)).await {
+                    // The socket is dead even if the reader hasn't noticed yet
+                    inner.sink = None;
+                    inner.pending.clear();
+                    drop(inner);
+                    self.link_lost();
+                    return Err(e.into());
+                }
                 tracing::info!("RCON send complete, waiting for response id={}", id);
             } else {
                 anyhow::bail!("RCON not connected");
@@ -280,22 +716,45 @@ impl RconClient {
 
     /// Kick a player by Steam ID or name.
     pub async fn kick(&self, target: &str, reason: &str) -> anyhow::Result<String> {
-        self.execute(&format!("kick {} \"{}\"", target, reason)).await
+        self.execute(&kick_command(target, reason)).await
     }
 
-    /// Ban a player by Steam ID or name.
-    pub async fn ban(&self, target: &str, reason: &str) -> anyhow::Result<String> {
-        self.execute(&format!("ban {} \"{}\"", target, reason)).await
+    /// Ban a player by Steam ID, online or not. `name` is what the banlist shows.
+    pub async fn ban_id(&self, steam_id: &str, name: &str, reason: &str) -> anyhow::Result<String> {
+        self.execute(&ban_command(steam_id, name, reason)).await
     }
 
     /// Unban a player by Steam ID.
     pub async fn unban(&self, steam_id: &str) -> anyhow::Result<String> {
-        self.execute(&format!("unban {}", steam_id)).await
+        self.execute(&format!("unban {}", quote_arg(steam_id))).await
     }
 
     /// Send a message to all players.
     pub async fn say(&self, message: &str) -> anyhow::Result<String> {
-        self.execute(&format!("say \"{}\"", message)).await
+        self.execute(&say_command(message)).await
+    }
+
+    /// Send a private chat message to a single player.
+    pub async fn say_to(&self, steam_id: &str, message: &str) -> anyhow::Result<String> {
+        self.execute(&say_to_command(steam_id, message)?).await
+    }
+
+    /// Broadcast a message `repeat` times, waiting `every` between sends.
+    /// Used for countdown-style warnings; stops at the first failed send.
+    pub async fn announce_repeat(
+        &self,
+        message: &str,
+        repeat: u32,
+        every: Duration,
+    ) -> anyhow::Result<String> {
+        let mut last = String::new();
+        for i in 0..repeat.max(1) {
+            if i > 0 {
+                tokio::time::sleep(every).await;
+            }
+            last = self.say(message).await?;
+        }
+        Ok(last)
     }
 
     /// Trigger a world save.
@@ -318,3 +777,130 @@ impl RconClient {
         self.execute(&format!("oxide.unload {}", plugin_name)).await
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Split a console line into arguments the way the game does: whitespace separates,
+    /// double quotes group, and a backslash escapes the next character inside quotes.
+    /// None if a quote is left open.
+    fn console_args(line: &str) -> Option<Vec<String>> {
+        let mut args = Vec::new();
+        let mut chars = line.chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+                continue;
+            }
+            let mut arg = String::new();
+            if c == '"' {
+                chars.next();
+                loop {
+                    match chars.next()? {
+                        '"' => break,
+                        '\\' => arg.push(chars.next()?),
+                        c => arg.push(c),
+                    }
+                }
+            } else {
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    arg.push(c);
+                    chars.next();
+                }
+            }
+            args.push(arg);
+        }
+        Some(args)
+    }
+
+    const HOSTILE: &[&str] = &[
+        "plain",
+        "say \"hi\"",
+        "back\\slash\\",
+        "trailing \\",
+        "\\\"; quit",
+        "one;two; quit",
+        "line\nquit",
+        "cr\r\nquit",
+        "\"\"\"",
+        "",
+    ];
+
+    /// What `quote_arg` keeps of `value`: line breaks become spaces, other controls go.
+    fn kept(value: &str) -> String {
+        value
+            .chars()
+            .filter_map(|c| match c {
+                '\n' | '\r' => Some(' '),
+                c if c.is_control() => None,
+                c => Some(c),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn quoted_args_stay_one_argument() {
+        for value in HOSTILE {
+            let quoted = quote_arg(value);
+            assert!(!quoted.contains(['\n', '\r']), "{:?}", quoted);
+            assert_eq!(
+                console_args(&quoted),
+                Some(vec![kept(value)]),
+                "{:?}",
+                value
+            );
+        }
+    }
+
+    #[test]
+    fn chat_kick_and_ban_commands_keep_their_arguments_apart() {
+        for value in HOSTILE {
+            let say = console_args(&say_command(value)).unwrap();
+            assert_eq!(say, ["say".to_string(), kept(value)]);
+
+            let kick = console_args(&kick_command(value, value)).unwrap();
+            assert_eq!(kick, ["kick".to_string(), kept(value), kept(value)]);
+
+            let ban = console_args(&ban_command("76561198000000001", value, value)).unwrap();
+            assert_eq!(
+                ban,
+                [
+                    "banid".to_string(),
+                    "76561198000000001".to_string(),
+                    kept(value),
+                    kept(value)
+                ]
+            );
+
+            let say_to = console_args(&say_to_command("76561198000000001", value).unwrap());
+            assert_eq!(
+                say_to.unwrap(),
+                [
+                    SAY_TO_COMMAND.to_string(),
+                    "76561198000000001".to_string(),
+                    kept(value)
+                ]
+            );
+        }
+    }
+
+    #[test]
+    fn say_to_rejects_ids_that_are_not_all_digits() {
+        for id in [
+            "",
+            "7656119800000000a",
+            "76561198000000001 quit",
+            "76561198000000001;quit",
+            "\"76561198000000001\"",
+            "-1",
+            "７６５",
+        ] {
+            assert!(say_to_command(id, "hello").is_err(), "{:?}", id);
+        }
+        assert!(say_to_command("76561198000000001", "hello").is_ok());
+    }
+}
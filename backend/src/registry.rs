@@ -1,17 +1,26 @@
 use std::collections::HashMap;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 
 use chrono::{DateTime, Utc};
+use indexmap::IndexMap;
 use serde::{Deserialize, Serialize};
 use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use utoipa::ToSchema;
 
-use crate::config::{GameServerConfig, PathsConfig, RconConfig};
-use crate::lgsm::LgsmLock;
+use crate::bans::BanRecord;
+use crate::config::{GameServerConfig, MonitorConfig, PathsConfig, RconConfig, ResourceLimits};
+use crate::events::EventBus;
+use crate::lgsm::{ActionRecord, LgsmLock};
 use crate::monitor::GameMonitor;
+use crate::persistence::{MetricsStore, ServerStore, StaticOverride};
+use crate::playertracker::PlayerRecord;
 use crate::rcon::RconClient;
+use crate::rollup::game_source;
+use crate::wipes::WipeRecord;
 
 /// Source of a server definition: either from config.yaml or dynamically created.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ServerSource {
     Static,
@@ -19,7 +28,7 @@ pub enum ServerSource {
 }
 
 /// Provisioning status for dynamically created servers.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ProvisioningStatus {
     Ready,
@@ -27,19 +36,88 @@ pub enum ProvisioningStatus {
     Downloading,
     InstallingOxide,
     Configuring,
+    /// Copying plugins and configs from the server a clone was made from.
+    CopyingFiles,
     Error,
+    /// Imported from a bundle: waiting to be re-provisioned or attached to copied files.
+    PendingAttach,
+}
+
+impl ProvisioningStatus {
+    /// Rough overall progress for each provisioning stage (None once failed).
+    pub fn percent(&self) -> Option<u8> {
+        match self {
+            ProvisioningStatus::Installing => Some(10),
+            ProvisioningStatus::Downloading => Some(40),
+            ProvisioningStatus::InstallingOxide => Some(75),
+            ProvisioningStatus::Configuring => Some(90),
+            ProvisioningStatus::CopyingFiles => Some(95),
+            ProvisioningStatus::Ready => Some(100),
+            ProvisioningStatus::Error | ProvisioningStatus::PendingAttach => None,
+        }
+    }
+
+    /// Whether an install is under way (not finished, failed or waiting to be attached).
+    pub fn in_progress(&self) -> bool {
+        !matches!(
+            self,
+            ProvisioningStatus::Ready
+                | ProvisioningStatus::Error
+                | ProvisioningStatus::PendingAttach
+        )
+    }
 }
 
 /// Server type: vanilla or modded.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ServerType {
     Vanilla,
     Modded,
 }
 
+/// Whether the server is meant to be up. Stopping it from the panel sets `stopped`, so going
+/// quiet afterwards isn't treated as an outage.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DesiredState {
+    #[default]
+    Running,
+    Stopped,
+}
+
+/// A maintenance window: the server may be down and is shown as "maintenance" publicly.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Maintenance {
+    /// Shown on the public status page.
+    #[serde(default)]
+    pub note: Option<String>,
+    pub started_at: DateTime<Utc>,
+    /// Ends on its own at this time; until cleared when unset.
+    #[serde(default)]
+    pub until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub set_by: Option<String>,
+}
+
+impl Maintenance {
+    pub fn active(&self) -> bool {
+        self.until.is_none_or(|until| until > Utc::now())
+    }
+}
+
+/// The server a clone copies its plugins and configs from once installed.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CloneSource {
+    pub server_id: String,
+    /// Also copy the source's server.cfg convars, except ports, RCON and world settings.
+    pub copy_server_cfg: bool,
+}
+
 /// Extended server definition with provisioning info.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ServerDefinition {
     pub id: String,
@@ -58,9 +136,68 @@ pub struct ServerDefinition {
     pub rcon_password: String,
     pub base_path: String,
     pub created_at: DateTime<Utc>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Game collector polling is suspended for this server.
+    #[serde(default)]
+    pub monitoring_paused: bool,
+    /// Start on panel boot if not already running.
+    #[serde(default)]
+    pub auto_start: bool,
+    /// Listed on the public status page under this slug when set.
+    #[serde(default)]
+    pub public_slug: Option<String>,
+    /// Newest entry of the wipe history.
+    #[serde(default)]
+    pub last_wipe_at: Option<DateTime<Utc>>,
+    /// Applied from the next LGSM start or restart.
+    #[serde(default)]
+    pub resource_limits: ResourceLimits,
+    /// Set by the start and stop endpoints.
+    #[serde(default)]
+    pub desired_state: DesiredState,
+    #[serde(default)]
+    pub maintenance: Option<Maintenance>,
+    /// Set on clones; the copy runs as the last provisioning stage.
+    #[serde(default)]
+    pub clone_of: Option<CloneSource>,
 }
 
 impl ServerDefinition {
+    /// The maintenance window, if one is set and hasn't expired.
+    pub fn active_maintenance(&self) -> Option<&Maintenance> {
+        self.maintenance.as_ref().filter(|m| m.active())
+    }
+
+    /// Until when the server being down is expected rather than an outage: `MAX_UTC` while
+    /// stopped on purpose or in open-ended maintenance, None when it should be up.
+    pub fn expected_down_until(&self) -> Option<DateTime<Utc>> {
+        if self.desired_state == DesiredState::Stopped {
+            return Some(DateTime::<Utc>::MAX_UTC);
+        }
+        self.active_maintenance()
+            .map(|m| m.until.unwrap_or(DateTime::<Utc>::MAX_UTC))
+    }
+
+    pub fn expected_down(&self) -> bool {
+        self.expected_down_until().is_some()
+    }
+
+    /// What the lists show: "maintenance", "stopped" (on purpose), "online" or "offline".
+    pub fn state(&self, online: bool) -> &'static str {
+        if self.active_maintenance().is_some() {
+            "maintenance"
+        } else if online {
+            "online"
+        } else if self.desired_state == DesiredState::Stopped {
+            "stopped"
+        } else {
+            "offline"
+        }
+    }
+
     /// Convert to a GameServerConfig for compatibility with existing handler code.
     pub fn to_game_server_config(&self) -> GameServerConfig {
         let base_dir = format!("{}/rustserver-{}", self.base_path, self.id);
@@ -71,12 +208,15 @@ impl ServerDefinition {
                 host: "127.0.0.1".to_string(),
                 port: self.rcon_port,
                 password: self.rcon_password.clone(),
+                tls: false,
+                insecure_skip_verify: false,
             },
             paths: PathsConfig {
                 lgsm_script: format!("{}/rustserver", base_dir),
                 server_files: format!("{}/serverfiles", base_dir),
                 oxide_plugins: format!("{}/serverfiles/oxide/plugins", base_dir),
                 oxide_config: format!("{}/serverfiles/oxide/config", base_dir),
+                oxide_data: format!("{}/serverfiles/oxide/data", base_dir),
                 server_cfg: format!(
                     "{}/serverfiles/server/rustserver/cfg/server.cfg",
                     base_dir
@@ -84,6 +224,9 @@ impl ServerDefinition {
                 server_log: format!("{}/log/console/rustserver-console.log", base_dir),
                 base_dir,
             },
+            auto_start: self.auto_start,
+            public_slug: self.public_slug.clone(),
+            resource_limits: self.resource_limits.clone(),
         }
     }
 
@@ -115,8 +258,59 @@ impl ServerDefinition {
                 .collect::<Vec<_>>()
                 .join("/"),
             created_at: Utc::now(),
+            description: None,
+            tags: Vec::new(),
+            monitoring_paused: false,
+            auto_start: config.auto_start,
+            public_slug: config.public_slug.clone(),
+            last_wipe_at: None,
+            resource_limits: config.resource_limits.clone(),
+            desired_state: DesiredState::Running,
+            maintenance: None,
+            clone_of: None,
+        }
+    }
+}
+
+/// Definitions for the config.yaml servers, with their panel-side overrides applied,
+/// followed by the dynamically created servers from `store`.
+pub fn load_definitions(
+    servers: &[GameServerConfig],
+    store: &dyn ServerStore,
+) -> Vec<ServerDefinition> {
+    let static_overrides = store.load_static_overrides().unwrap_or_else(|e| {
+        tracing::warn!("Failed to load static server overrides: {}", e);
+        HashMap::new()
+    });
+    let mut definitions: Vec<ServerDefinition> = Vec::new();
+    for server in servers {
+        let mut def = ServerDefinition::from_static_config(server);
+        if let Some(overrides) = static_overrides.get(&server.id) {
+            if let Some(ref name) = overrides.name {
+                def.name = name.clone();
+            }
+            def.monitoring_paused = overrides.monitoring_paused;
+            def.tags = overrides.tags.clone();
+            if let Some(auto_start) = overrides.auto_start {
+                def.auto_start = auto_start;
+            }
+            def.last_wipe_at = overrides.last_wipe_at;
+            def.desired_state = overrides.desired_state;
+            def.maintenance = overrides.maintenance.clone();
+        }
+        definitions.push(def);
+    }
+
+    let dynamic_servers = store.load_servers().unwrap_or_else(|e| {
+        tracing::warn!("Failed to load dynamic servers: {}", e);
+        Vec::new()
+    });
+    for ds in dynamic_servers {
+        if !definitions.iter().any(|d| d.id == ds.id) {
+            definitions.push(ds);
         }
     }
+    definitions
 }
 
 /// Per-server runtime state: RCON client, game monitor, LGSM lock, collector handle.
@@ -127,23 +321,74 @@ pub struct ServerRuntime {
     pub collector_handle: Option<tokio::task::JoinHandle<()>>,
 }
 
+impl ServerRuntime {
+    /// Build a fresh runtime for a Ready server and start its game collector.
+    pub fn start(
+        config: &GameServerConfig,
+        monitor: &MonitorConfig,
+        events: &EventBus,
+        metrics: &Arc<dyn MetricsStore>,
+    ) -> Self {
+        let rcon = RconClient::new(config.rcon.clone());
+        let game_monitor = Arc::new(GameMonitor::new(monitor.history_size));
+        let collector_handle = crate::monitor::spawn_game_collector(
+            game_monitor.clone(),
+            rcon.clone(),
+            monitor.clone(),
+            config.id.clone(),
+            config.paths.base_dir.clone(),
+            events.clone(),
+            metrics.clone(),
+        );
+        Self {
+            rcon,
+            game_monitor,
+            lgsm_lock: Arc::new(LgsmLock::new()),
+            collector_handle: Some(collector_handle),
+        }
+    }
+}
+
 /// Central shared registry replacing the separate HashMaps.
 pub struct ServerRegistry {
-    pub definitions: RwLock<Vec<ServerDefinition>>,
+    /// Definitions keyed by server id, in insertion order (static servers first).
+    pub definitions: RwLock<IndexMap<String, ServerDefinition>>,
     pub runtimes: RwLock<HashMap<String, ServerRuntime>>,
     /// Original static configs from config.yaml, keyed by server id.
     pub static_configs: HashMap<String, GameServerConfig>,
+    /// Panel-wide event bus behind /ws/events.
+    pub events: EventBus,
+    /// Where `persist` writes dynamic servers and static overrides.
+    store: Arc<dyn ServerStore>,
+    /// Monitor rollups, written by the collectors.
+    pub metrics: Arc<dyn MetricsStore>,
+    /// Provisioning tasks by server id, kept so they can be cancelled.
+    provisioning: Mutex<HashMap<String, JoinHandle<()>>>,
 }
 
 impl ServerRegistry {
     pub fn new(
         definitions: Vec<ServerDefinition>,
         static_configs: HashMap<String, GameServerConfig>,
+        store: Arc<dyn ServerStore>,
+        metrics: Arc<dyn MetricsStore>,
     ) -> Self {
+        let mut indexed = IndexMap::with_capacity(definitions.len());
+        for def in definitions {
+            if indexed.contains_key(&def.id) {
+                tracing::warn!("Duplicate server id '{}' ignored", def.id);
+                continue;
+            }
+            indexed.insert(def.id.clone(), def);
+        }
         Self {
-            definitions: RwLock::new(definitions),
+            definitions: RwLock::new(indexed),
             runtimes: RwLock::new(HashMap::new()),
             static_configs,
+            events: EventBus::new(),
+            store,
+            metrics,
+            provisioning: Mutex::new(HashMap::new()),
         }
     }
 
@@ -157,15 +402,13 @@ impl ServerRegistry {
         }
         // Fall back to dynamic definition
         let defs = self.definitions.read().await;
-        defs.iter()
-            .find(|d| d.id == server_id)
-            .map(|d| d.to_game_server_config())
+        defs.get(server_id).map(|d| d.to_game_server_config())
     }
 
     /// Get all GameServerConfigs.
     pub async fn all_configs(&self) -> Vec<GameServerConfig> {
         let defs = self.definitions.read().await;
-        defs.iter()
+        defs.values()
             .map(|d| {
                 self.static_configs
                     .get(&d.id)
@@ -178,13 +421,77 @@ impl ServerRegistry {
     /// Get all server definitions.
     pub async fn all_definitions(&self) -> Vec<ServerDefinition> {
         let defs = self.definitions.read().await;
-        defs.clone()
+        defs.values().cloned().collect()
     }
 
     /// Get a specific server definition.
     pub async fn get_definition(&self, server_id: &str) -> Option<ServerDefinition> {
         let defs = self.definitions.read().await;
-        defs.iter().find(|d| d.id == server_id).cloned()
+        defs.get(server_id).cloned()
+    }
+
+    /// Add a new definition. Fails if a server with the same id already exists.
+    pub async fn insert_definition(&self, def: ServerDefinition) -> Result<(), String> {
+        let mut defs = self.definitions.write().await;
+        if defs.contains_key(&def.id) {
+            return Err(format!("Server id '{}' already exists", def.id));
+        }
+        defs.insert(def.id.clone(), def);
+        Ok(())
+    }
+
+    /// Remember a server's provisioning task, replacing its previous (finished) one.
+    pub fn track_provisioning(&self, server_id: &str, handle: JoinHandle<()>) {
+        self.provisioning
+            .lock()
+            .unwrap()
+            .insert(server_id.to_string(), handle);
+    }
+
+    /// Abort a server's provisioning task and wait for it to stop. False if none was
+    /// running (it finished, or the panel restarted since it was spawned).
+    pub async fn abort_provisioning(&self, server_id: &str) -> bool {
+        let handle = self.provisioning.lock().unwrap().remove(server_id);
+        match handle {
+            Some(handle) if !handle.is_finished() => {
+                handle.abort();
+                let _ = handle.await;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Remove a definition, returning it if it existed.
+    pub async fn remove_definition(&self, server_id: &str) -> Option<ServerDefinition> {
+        crate::diagnostics::forget(&crate::diagnostics::collector_task(server_id));
+        if let Err(e) = self.store.delete_wipes(server_id) {
+            tracing::error!("Failed to delete wipe history of '{}': {}", server_id, e);
+        }
+        if let Err(e) = self.store.delete_actions(server_id) {
+            tracing::error!("Failed to delete action history of '{}': {}", server_id, e);
+        }
+        if let Err(e) = self.store.delete_bans(server_id) {
+            tracing::error!("Failed to delete bans of '{}': {}", server_id, e);
+        }
+        if let Err(e) = self.store.delete_players(server_id) {
+            tracing::error!("Failed to delete player history of '{}': {}", server_id, e);
+        }
+        if let Err(e) = self.metrics.delete_rollups(&game_source(server_id)) {
+            tracing::error!("Failed to delete metric rollups of '{}': {}", server_id, e);
+        }
+        let mut defs = self.definitions.write().await;
+        defs.shift_remove(server_id)
+    }
+
+    /// Apply `f` to a definition in place, returning its result (None if not found).
+    pub async fn update_definition<R>(
+        &self,
+        server_id: &str,
+        f: impl FnOnce(&mut ServerDefinition) -> R,
+    ) -> Option<R> {
+        let mut defs = self.definitions.write().await;
+        defs.get_mut(server_id).map(f)
     }
 
     /// Get the RCON client for a server.
@@ -204,4 +511,399 @@ impl ServerRegistry {
         let runtimes = self.runtimes.read().await;
         runtimes.get(server_id).map(|r| r.lgsm_lock.clone())
     }
+
+    /// Persist registry state: dynamic definitions and the panel-side settings of
+    /// static servers.
+    pub async fn persist(&self) {
+        let defs = self.definitions.read().await;
+        let dynamic: Vec<_> = defs
+            .values()
+            .filter(|d| d.source == ServerSource::Dynamic)
+            .cloned()
+            .collect();
+        if let Err(e) = self.store.save_servers(&dynamic) {
+            tracing::error!("Failed to save servers: {}", e);
+        }
+
+        let overrides: HashMap<String, StaticOverride> = defs
+            .values()
+            .filter(|d| d.source == ServerSource::Static)
+            .map(|d| {
+                let static_config = self.static_configs.get(&d.id);
+                let name = static_config
+                    .filter(|c| c.name != d.name)
+                    .map(|_| d.name.clone());
+                let auto_start = static_config
+                    .filter(|c| c.auto_start != d.auto_start)
+                    .map(|_| d.auto_start);
+                (
+                    d.id.clone(),
+                    StaticOverride {
+                        name,
+                        monitoring_paused: d.monitoring_paused,
+                        tags: d.tags.clone(),
+                        auto_start,
+                        last_wipe_at: d.last_wipe_at,
+                        desired_state: d.desired_state,
+                        maintenance: d.maintenance.clone(),
+                    },
+                )
+            })
+            .collect();
+        if let Err(e) = self.store.save_static_overrides(&overrides) {
+            tracing::error!("Failed to save static server overrides: {}", e);
+        }
+    }
+
+    /// Add a wipe to the server's history, moving its last_wipe_at if it's the newest.
+    pub async fn record_wipe(&self, server_id: &str, wipe: WipeRecord) {
+        let at = wipe.timestamp;
+        let newest = self
+            .update_definition(server_id, |def| {
+                let newest = def.last_wipe_at.is_none_or(|last| last < at);
+                if newest {
+                    def.last_wipe_at = Some(at);
+                }
+                newest
+            })
+            .await;
+        let Some(newest) = newest else {
+            return;
+        };
+        if let Err(e) = self.store.add_wipe(server_id, &wipe) {
+            tracing::error!("Failed to save wipe of '{}': {}", server_id, e);
+        }
+        if newest {
+            self.persist().await;
+        }
+    }
+
+    /// The server's wipe history, newest first.
+    pub fn wipes(&self, server_id: &str) -> anyhow::Result<Vec<WipeRecord>> {
+        self.store.load_wipes(server_id)
+    }
+
+    /// Add a finished action to the server's history.
+    pub fn record_action(&self, server_id: &str, action: ActionRecord) {
+        if let Err(e) = self.store.add_action(server_id, &action) {
+            tracing::error!("Failed to save action on '{}': {}", server_id, e);
+        }
+    }
+
+    /// The server's action history, newest first.
+    pub fn actions(&self, server_id: &str) -> anyhow::Result<Vec<ActionRecord>> {
+        self.store.load_actions(server_id)
+    }
+
+    /// Record a ban issued (or lifted) through the panel.
+    pub fn save_ban(&self, server_id: &str, ban: &BanRecord) {
+        if let Err(e) = self.store.save_ban(server_id, ban) {
+            tracing::error!(
+                "Failed to save ban of {} on '{}': {}",
+                ban.steam_id,
+                server_id,
+                e
+            );
+        }
+    }
+
+    /// The server's bans, newest first.
+    pub fn bans(&self, server_id: &str) -> anyhow::Result<Vec<BanRecord>> {
+        self.store.load_bans(server_id)
+    }
+
+    /// Temporary bans on any server that have run out but are still in force.
+    pub fn bans_due(&self, now: DateTime<Utc>) -> anyhow::Result<Vec<(String, BanRecord)>> {
+        self.store.load_bans_due(now)
+    }
+
+    pub fn remove_ban(&self, server_id: &str, steam_id: &str) {
+        if let Err(e) = self.store.delete_ban(server_id, steam_id) {
+            tracing::error!(
+                "Failed to delete ban of {} on '{}': {}",
+                steam_id,
+                server_id,
+                e
+            );
+        }
+    }
+
+    pub fn save_player_records(&self, server_id: &str, players: &[PlayerRecord]) {
+        if let Err(e) = self.store.save_players(server_id, players) {
+            tracing::error!("Failed to save player history of '{}': {}", server_id, e);
+        }
+    }
+
+    pub fn player_record(
+        &self,
+        server_id: &str,
+        steam_id: &str,
+    ) -> anyhow::Result<Option<PlayerRecord>> {
+        self.store.load_player(server_id, steam_id)
+    }
+
+    /// Every player seen on the server, most recently seen first.
+    pub fn player_records(&self, server_id: &str) -> anyhow::Result<Vec<PlayerRecord>> {
+        self.store.load_players(server_id)
+    }
+
+    /// Record whether the server is meant to be running, and tell its collector.
+    /// Returns false if the server does not exist.
+    pub async fn set_desired_state(&self, server_id: &str, state: DesiredState) -> bool {
+        let changed = self
+            .update_definition(server_id, |def| {
+                let changed = def.desired_state != state;
+                def.desired_state = state;
+                changed
+            })
+            .await;
+        let Some(changed) = changed else {
+            return false;
+        };
+        if changed {
+            self.sync_expected_down(server_id).await;
+            self.persist().await;
+        }
+        true
+    }
+
+    /// Set or (with None) clear the maintenance window. Returns false if the server does
+    /// not exist.
+    pub async fn set_maintenance(&self, server_id: &str, maintenance: Option<Maintenance>) -> bool {
+        if self
+            .update_definition(server_id, |def| def.maintenance = maintenance)
+            .await
+            .is_none()
+        {
+            return false;
+        }
+        self.sync_expected_down(server_id).await;
+        self.persist().await;
+        true
+    }
+
+    /// Copy the definition's expected-down window to its game monitor.
+    pub async fn sync_expected_down(&self, server_id: &str) {
+        let Some(until) = self
+            .get_definition(server_id)
+            .await
+            .map(|d| d.expected_down_until())
+        else {
+            return;
+        };
+        if let Some(monitor) = self.get_game_monitor(server_id).await {
+            *monitor.expected_down_until.lock().unwrap() = until;
+        }
+    }
+
+    /// Stop the game collector for a server and mark monitoring as paused.
+    /// Returns false if the server does not exist.
+    pub async fn pause_monitoring(&self, server_id: &str) -> bool {
+        if self
+            .update_definition(server_id, |def| def.monitoring_paused = true)
+            .await
+            .is_none()
+        {
+            return false;
+        }
+
+        let mut runtimes = self.runtimes.write().await;
+        if let Some(runtime) = runtimes.get_mut(server_id) {
+            if let Some(handle) = runtime.collector_handle.take() {
+                handle.abort();
+            }
+            // Nothing refreshes the cached playerlist any more
+            *runtime.game_monitor.players.write().await = None;
+        }
+        drop(runtimes);
+
+        self.persist().await;
+        true
+    }
+
+    /// Reload the definitions from the store after a panel restore replaced it, then stop
+    /// the runtimes of servers that are gone or no longer Ready and start the missing ones.
+    /// `servers` is the running config's static servers; the restore doesn't change those.
+    pub async fn reload(&self, servers: &[GameServerConfig], monitor: &MonitorConfig) {
+        let definitions = load_definitions(servers, &*self.store);
+        let mut indexed = IndexMap::with_capacity(definitions.len());
+        for def in definitions {
+            indexed.entry(def.id.clone()).or_insert(def);
+        }
+        *self.definitions.write().await = indexed.clone();
+
+        let mut runtimes = self.runtimes.write().await;
+        let stale: Vec<String> = runtimes
+            .keys()
+            .filter(|id| {
+                indexed
+                    .get(*id)
+                    .is_none_or(|d| d.provisioning_status != ProvisioningStatus::Ready)
+            })
+            .cloned()
+            .collect();
+        for id in stale {
+            if let Some(mut runtime) = runtimes.remove(&id) {
+                if let Some(handle) = runtime.collector_handle.take() {
+                    handle.abort();
+                }
+                runtime.rcon.disconnect().await;
+                tracing::info!("Stopped runtime for '{}' after restore", id);
+            }
+        }
+        for def in indexed.values() {
+            if def.provisioning_status != ProvisioningStatus::Ready {
+                continue;
+            }
+            let config = self
+                .static_configs
+                .get(&def.id)
+                .cloned()
+                .unwrap_or_else(|| def.to_game_server_config());
+            let runtime = runtimes.entry(def.id.clone()).or_insert_with(|| {
+                ServerRuntime::start(&config, monitor, &self.events, &self.metrics)
+            });
+            *runtime.game_monitor.expected_down_until.lock().unwrap() = def.expected_down_until();
+            let running = runtime
+                .collector_handle
+                .as_ref()
+                .is_some_and(|h| !h.is_finished());
+            if def.monitoring_paused && running {
+                if let Some(handle) = runtime.collector_handle.take() {
+                    handle.abort();
+                }
+            } else if !def.monitoring_paused && !running {
+                runtime.collector_handle = Some(crate::monitor::spawn_game_collector(
+                    runtime.game_monitor.clone(),
+                    runtime.rcon.clone(),
+                    monitor.clone(),
+                    def.id.clone(),
+                    config.paths.base_dir.clone(),
+                    self.events.clone(),
+                    self.metrics.clone(),
+                ));
+            }
+        }
+    }
+
+    /// Stop every game collector and close every RCON connection, for panel shutdown.
+    pub async fn shutdown_runtimes(&self) {
+        let mut runtimes = self.runtimes.write().await;
+        for (id, runtime) in runtimes.iter_mut() {
+            if let Some(handle) = runtime.collector_handle.take() {
+                handle.abort();
+                let _ = handle.await;
+            }
+            runtime.rcon.disconnect().await;
+            tracing::debug!("Runtime for '{}' stopped", id);
+        }
+    }
+
+    /// Respawn the game collector for a server and clear the paused flag.
+    /// Returns false if the server does not exist.
+    pub async fn resume_monitoring(&self, server_id: &str, monitor_config: &MonitorConfig) -> bool {
+        if self
+            .update_definition(server_id, |def| def.monitoring_paused = false)
+            .await
+            .is_none()
+        {
+            return false;
+        }
+
+        let Some(config) = self.get_config(server_id).await else {
+            return false;
+        };
+        let mut runtimes = self.runtimes.write().await;
+        if let Some(runtime) = runtimes.get_mut(server_id) {
+            let running = runtime
+                .collector_handle
+                .as_ref()
+                .map(|h| !h.is_finished())
+                .unwrap_or(false);
+            if !running {
+                runtime.collector_handle = Some(crate::monitor::spawn_game_collector(
+                    runtime.game_monitor.clone(),
+                    runtime.rcon.clone(),
+                    monitor_config.clone(),
+                    server_id.to_string(),
+                    config.paths.base_dir,
+                    self.events.clone(),
+                    self.metrics.clone(),
+                ));
+            }
+        }
+        drop(runtimes);
+
+        self.persist().await;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SqliteStore;
+
+    fn definition(id: &str, name: &str) -> ServerDefinition {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": name,
+            "serverType": "vanilla",
+            "source": "dynamic",
+            "provisioningStatus": "ready",
+            "provisioningLog": [],
+            "gamePort": 28015,
+            "rconPort": 28016,
+            "queryPort": 27015,
+            "maxPlayers": 100,
+            "worldSize": 4000,
+            "seed": 1,
+            "hostname": name,
+            "rconPassword": "secret",
+            "basePath": "/tmp/unused",
+            "createdAt": "2026-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn parallel_inserts_of_one_id_let_exactly_one_through() {
+        let store = Arc::new(SqliteStore::in_memory().unwrap());
+        let registry = Arc::new(ServerRegistry::new(
+            vec![definition("first", "First")],
+            HashMap::new(),
+            store.clone(),
+            store,
+        ));
+
+        let tasks: Vec<_> = (0..32)
+            .map(|i| {
+                let registry = registry.clone();
+                tokio::spawn(async move {
+                    let name = format!("Attempt {}", i);
+                    let result = registry.insert_definition(definition("dup", &name)).await;
+                    (name, result)
+                })
+            })
+            .collect();
+        let mut winners = Vec::new();
+        for task in tasks {
+            match task.await.unwrap() {
+                (name, Ok(())) => winners.push(name),
+                (_, Err(e)) => assert_eq!(e, "Server id 'dup' already exists"),
+            }
+        }
+        assert_eq!(winners.len(), 1);
+        assert_eq!(
+            registry.get_definition("dup").await.unwrap().name,
+            winners[0]
+        );
+
+        registry
+            .insert_definition(definition("last", "Last"))
+            .await
+            .unwrap();
+        let ids: Vec<_> = registry.definitions.read().await.keys().cloned().collect();
+        assert_eq!(ids, ["first", "dup", "last"]);
+    }
 }
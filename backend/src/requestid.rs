@@ -0,0 +1,130 @@
+use actix_web::body::MessageBody;
+use actix_web::dev::{Service, ServiceRequest, ServiceResponse, Transform};
+use actix_web::error::InternalError;
+use actix_web::http::header::{HeaderName, HeaderValue};
+use actix_web::{Error, HttpMessage};
+use std::future::{ready, Future, Ready};
+use std::pin::Pin;
+use std::rc::Rc;
+use std::task::{Context, Poll};
+use tracing::Instrument;
+
+/// Header a client (or proxy) may set to pick the id; always echoed on the response.
+pub const HEADER: &str = "x-request-id";
+
+/// Longer client-supplied ids are replaced, so they can't bloat every log line.
+const MAX_LEN: usize = 64;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// A request's correlation id, in its extensions.
+#[derive(Debug, Clone)]
+pub struct CorrelationId(pub String);
+
+/// Id of the request (or request-spawned task) running on this task, if any.
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Run `fut` under the current request's id and tracing span. Wrap tasks a handler
+/// spawns in this so their logs, events and job runs carry the id too.
+pub fn propagate<F: Future>(fut: F) -> impl Future<Output = F::Output> {
+    let id = current();
+    let fut = fut.instrument(tracing::Span::current());
+    async move {
+        match id {
+            Some(id) => REQUEST_ID.scope(id, fut).await,
+            None => fut.await,
+        }
+    }
+}
+
+fn valid(id: &str) -> bool {
+    !id.is_empty()
+        && id.len() <= MAX_LEN
+        && id
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b':'))
+}
+
+/// Actix-web middleware giving every request a correlation id: taken from X-Request-Id
+/// when the client sent a usable one, else generated. Wrap it outermost so the access log
+/// and the other middleware's rejections carry the id.
+pub struct RequestId;
+
+impl<S, B> Transform<S, ServiceRequest> for RequestId
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Transform = RequestIdMiddleware<S>;
+    type InitError = ();
+    type Future = Ready<Result<Self::Transform, Self::InitError>>;
+
+    fn new_transform(&self, service: S) -> Self::Future {
+        ready(Ok(RequestIdMiddleware {
+            service: Rc::new(service),
+        }))
+    }
+}
+
+pub struct RequestIdMiddleware<S> {
+    service: Rc<S>,
+}
+
+impl<S, B> Service<ServiceRequest> for RequestIdMiddleware<S>
+where
+    S: Service<ServiceRequest, Response = ServiceResponse<B>, Error = Error> + 'static,
+    B: MessageBody + 'static,
+{
+    type Response = ServiceResponse<B>;
+    type Error = Error;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>>>>;
+
+    fn poll_ready(&self, ctx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.service.poll_ready(ctx)
+    }
+
+    fn call(&self, req: ServiceRequest) -> Self::Future {
+        let service = self.service.clone();
+        let id = req
+            .headers()
+            .get(HEADER)
+            .and_then(|v| v.to_str().ok())
+            .filter(|v| valid(v))
+            .map(str::to_string)
+            .unwrap_or_else(|| uuid::Uuid::new_v4().simple().to_string());
+        req.extensions_mut().insert(CorrelationId(id.clone()));
+        let span = tracing::info_span!("request", request_id = %id);
+
+        let handled = {
+            let id = id.clone();
+            async move {
+                let header = HeaderValue::from_str(&id).ok();
+                match service.call(req).await {
+                    Ok(mut res) => {
+                        if let Some(value) = header {
+                            res.headers_mut()
+                                .insert(HeaderName::from_static(HEADER), value);
+                        }
+                        Ok(res)
+                    }
+                    // Rejected by other middleware: render it here to add the header
+                    Err(e) => {
+                        let mut res = e.error_response();
+                        if let Some(value) = header {
+                            res.headers_mut()
+                                .insert(HeaderName::from_static(HEADER), value);
+                        }
+                        Err(InternalError::from_response(e, res).into())
+                    }
+                }
+            }
+        };
+        Box::pin(REQUEST_ID.scope(id, handled.instrument(span)))
+    }
+}
@@ -0,0 +1,296 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tokio::process::Command;
+use utoipa::ToSchema;
+
+use crate::config::{GameServerConfig, ResourceLimits};
+
+const CGROUP_ROOT: &str = "/sys/fs/cgroup";
+/// cpu.max period in microseconds; the quota is cores times this.
+const CPU_PERIOD_US: u64 = 100_000;
+
+/// LGSM actions that may (re)start the game server, and so run inside its limits.
+const STARTS_SERVER: &[&str] = &[
+    "start",
+    "restart",
+    "update",
+    "force-update",
+    "validate",
+    "monitor",
+    "full-wipe",
+    "map-wipe",
+];
+
+/// How resource limits are applied on this host.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LimitMechanism {
+    /// `systemd-run --scope` with CPUQuota/MemoryMax.
+    SystemdRun,
+    /// A child cgroup per server under the panel's delegated cgroup v2 subtree.
+    Cgroup,
+}
+
+#[derive(Debug)]
+struct Detected {
+    mechanism: LimitMechanism,
+    /// The delegated group holding the per-server children, for `Cgroup`.
+    cgroup_parent: PathBuf,
+}
+
+static DETECTED: OnceLock<Option<Detected>> = OnceLock::new();
+
+/// Find out once, at startup, whether limits can be applied, and log the answer. A missing
+/// mechanism is a warning only when some server has limits configured.
+pub fn detect(servers_with_limits: usize) {
+    let found = match probe() {
+        Ok(detected) => {
+            tracing::info!("Resource limits: applied with {:?}", detected.mechanism);
+            Some(detected)
+        }
+        Err(reason) if servers_with_limits > 0 => {
+            tracing::warn!(
+                "Resource limits are configured for {} server(s) but can't be applied: {}",
+                servers_with_limits,
+                reason
+            );
+            None
+        }
+        Err(reason) => {
+            tracing::info!("Resource limits unavailable: {}", reason);
+            None
+        }
+    };
+    let _ = DETECTED.set(found);
+}
+
+/// The mechanism found by `detect`, if any.
+pub fn mechanism() -> Option<LimitMechanism> {
+    DETECTED.get().and_then(Option::as_ref).map(|d| d.mechanism)
+}
+
+fn probe() -> Result<Detected, String> {
+    // Creating a transient scope is the only reliable test of polkit/root permissions
+    let systemd_run = std::process::Command::new("systemd-run")
+        .args(["--scope", "--quiet", "--no-ask-password", "true"])
+        .output();
+    if matches!(systemd_run, Ok(ref output) if output.status.success()) {
+        return Ok(Detected {
+            mechanism: LimitMechanism::SystemdRun,
+            cgroup_parent: PathBuf::new(),
+        });
+    }
+    let systemd_error = match systemd_run {
+        Ok(output) => String::from_utf8_lossy(&output.stderr)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" "),
+        Err(e) => e.to_string(),
+    };
+    delegated_cgroup()
+        .map(|cgroup_parent| Detected {
+            mechanism: LimitMechanism::Cgroup,
+            cgroup_parent,
+        })
+        .map_err(|e| format!("systemd-run: {}; cgroup v2: {}", systemd_error, e))
+}
+
+/// Set up the panel's own cgroup (delegated to it, e.g. by a unit with Delegate=yes) to
+/// hold per-server children. cgroup v2 only allows controllers to be enabled for children
+/// of a group without processes, so the panel first moves itself into a `panel` leaf.
+fn delegated_cgroup() -> Result<PathBuf, String> {
+    let own = std::fs::read_to_string("/proc/self/cgroup")
+        .map_err(|e| format!("can't read /proc/self/cgroup: {}", e))?;
+    let relative = own
+        .lines()
+        .find_map(|line| line.strip_prefix("0::"))
+        .ok_or("not a cgroup v2 (unified) hierarchy")?;
+    let mut group = PathBuf::from(CGROUP_ROOT).join(relative.trim_start_matches('/'));
+    // Already set up by an earlier start of this process tree
+    if group.file_name().is_some_and(|name| name == "panel") {
+        group.pop();
+    }
+
+    let controllers = std::fs::read_to_string(group.join("cgroup.controllers"))
+        .map_err(|e| format!("can't read {}: {}", group.display(), e))?;
+    for controller in ["cpu", "memory"] {
+        if !controllers.split_whitespace().any(|c| c == controller) {
+            return Err(format!(
+                "the {} controller isn't delegated to {}",
+                controller,
+                group.display()
+            ));
+        }
+    }
+
+    let leaf = group.join("panel");
+    write_cgroup(&leaf, None, "")?;
+    std::fs::write(leaf.join("cgroup.procs"), std::process::id().to_string())
+        .map_err(|e| format!("can't move the panel into {}: {}", leaf.display(), e))?;
+    std::fs::write(group.join("cgroup.subtree_control"), "+cpu +memory")
+        .map_err(|e| format!("can't enable cpu/memory in {}: {}", group.display(), e))?;
+    Ok(group)
+}
+
+/// Create `dir` if needed and write `contents` to its `file`.
+fn write_cgroup(dir: &Path, file: Option<&str>, contents: &str) -> Result<(), String> {
+    std::fs::create_dir_all(dir).map_err(|e| format!("can't create {}: {}", dir.display(), e))?;
+    if let Some(file) = file {
+        std::fs::write(dir.join(file), contents)
+            .map_err(|e| format!("can't write {}/{}: {}", dir.display(), file, e))?;
+    }
+    Ok(())
+}
+
+/// The command for `action` on this server: the LGSM script itself, wrapped so the server
+/// it starts stays inside its limits when it has some and they can be applied.
+pub fn lgsm_command(config: &GameServerConfig, action: &str) -> Command {
+    let script = &config.paths.lgsm_script;
+    let limits = &config.resource_limits;
+    let wrapped = match DETECTED.get().and_then(Option::as_ref) {
+        Some(detected) if !limits.is_empty() && STARTS_SERVER.contains(&action) => {
+            wrap(detected, &config.id, limits, script, action)
+        }
+        _ => None,
+    };
+    wrapped.unwrap_or_else(|| {
+        let mut command = Command::new(script);
+        command.arg(action);
+        command
+    })
+}
+
+fn wrap(
+    detected: &Detected,
+    server_id: &str,
+    limits: &ResourceLimits,
+    script: &str,
+    action: &str,
+) -> Option<Command> {
+    match detected.mechanism {
+        LimitMechanism::SystemdRun => {
+            let mut command = Command::new("systemd-run");
+            command.args(["--scope", "--quiet", "--no-ask-password"]);
+            if let Some(cores) = limits.cpu_cores {
+                command.arg(format!("--property=CPUQuota={}%", (cores * 100.0).round()));
+            }
+            if let Some(mb) = limits.memory_mb {
+                command.arg(format!("--property=MemoryMax={}M", mb));
+            }
+            command.args(["--", script, action]);
+            Some(command)
+        }
+        LimitMechanism::Cgroup => {
+            let group = detected.cgroup_parent.join(format!("server-{}", server_id));
+            let cpu_max = match limits.cpu_cores {
+                Some(cores) => format!(
+                    "{} {}",
+                    (cores * CPU_PERIOD_US as f64).round() as u64,
+                    CPU_PERIOD_US
+                ),
+                None => format!("max {}", CPU_PERIOD_US),
+            };
+            let memory_max = match limits.memory_mb {
+                Some(mb) => (mb * 1024 * 1024).to_string(),
+                None => "max".to_string(),
+            };
+            let written = write_cgroup(&group, Some("cpu.max"), &cpu_max)
+                .and_then(|_| write_cgroup(&group, Some("memory.max"), &memory_max));
+            if let Err(e) = written {
+                tracing::warn!("Starting '{}' without resource limits: {}", server_id, e);
+                return None;
+            }
+            // The shell joins the group, then becomes the script; children inherit it
+            let mut command = Command::new("sh");
+            command
+                .arg("-c")
+                .arg(r#"echo $$ > "$0" && exec "$@""#)
+                .arg(group.join("cgroup.procs"))
+                .args([script, action]);
+            Some(command)
+        }
+    }
+}
+
+/// What a server's RustDedicated process(es) use.
+#[derive(Debug, Clone, Copy)]
+pub struct ProcessUsage {
+    /// Percent of one core.
+    pub cpu_percent: f32,
+    pub memory_bytes: u64,
+    pub threads: u32,
+}
+
+/// Samples one server's RustDedicated process(es) again and again, keeping the process
+/// table between samples so the CPU figure covers the time since the previous one.
+pub struct ProcessSampler {
+    base_dir: String,
+    sys: sysinfo::System,
+    pids: Vec<sysinfo::Pid>,
+}
+
+impl ProcessSampler {
+    pub fn new(base_dir: String) -> Self {
+        Self {
+            base_dir,
+            sys: sysinfo::System::new(),
+            pids: Vec::new(),
+        }
+    }
+
+    /// None when the server isn't running. A process found by this sample reports 0% CPU
+    /// until the next one.
+    pub fn sample(&mut self) -> Option<ProcessUsage> {
+        use sysinfo::{ProcessRefreshKind, ProcessesToUpdate};
+
+        let refresh = ProcessRefreshKind::nothing().with_cpu().with_memory();
+        if !self.pids.is_empty() {
+            self.sys.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&self.pids),
+                true,
+                refresh,
+            );
+            // Restarted (or stopped): look the process up again
+            if self.pids.iter().any(|pid| self.sys.process(*pid).is_none()) {
+                self.pids.clear();
+            }
+        }
+        if self.pids.is_empty() {
+            self.pids = crate::lgsm::find_server_pids(&self.base_dir);
+            if self.pids.is_empty() {
+                return None;
+            }
+            self.sys.refresh_processes_specifics(
+                ProcessesToUpdate::Some(&self.pids),
+                true,
+                refresh,
+            );
+        }
+        let processes: Vec<_> = self
+            .pids
+            .iter()
+            .filter_map(|pid| self.sys.process(*pid))
+            .collect();
+        Some(ProcessUsage {
+            cpu_percent: processes.iter().map(|p| p.cpu_usage()).sum(),
+            memory_bytes: processes.iter().map(|p| p.memory()).sum(),
+            // The main thread isn't listed among a process's tasks
+            threads: processes
+                .iter()
+                .map(|p| p.tasks().map_or(1, |tasks| tasks.len() as u32 + 1))
+                .sum(),
+        })
+    }
+}
+
+/// One-off `ProcessSampler` reading, for when no collector is sampling the server. Blocks
+/// for a CPU sampling interval.
+pub fn process_usage(base_dir: &str) -> Option<ProcessUsage> {
+    let mut sampler = ProcessSampler::new(base_dir.to_string());
+    sampler.sample()?;
+    std::thread::sleep(sysinfo::MINIMUM_CPU_UPDATE_INTERVAL);
+    sampler.sample()
+}
@@ -0,0 +1,346 @@
+use chrono::{DateTime, Duration, DurationRound, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::apierror::ApiError;
+use crate::monitor::{GameSnapshot, RingBuffer, SystemSnapshot};
+use crate::persistence::MetricsStore;
+
+/// Rollup source of the host's system metrics; game servers use `game_source`.
+pub const SYSTEM_SOURCE: &str = "system";
+
+/// Buckets a range query returns when it gives no resolution.
+const DEFAULT_BUCKETS: i64 = 300;
+/// Most buckets a range query may ask for.
+const MAX_BUCKETS: i64 = 10_000;
+
+pub fn game_source(server_id: &str) -> String {
+    format!("game:{}", server_id)
+}
+
+/// Long-term history tiers, each kept on disk for a while.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Tier {
+    Minute,
+    Hour,
+}
+
+impl Tier {
+    pub fn name(self) -> &'static str {
+        match self {
+            Tier::Minute => "1m",
+            Tier::Hour => "1h",
+        }
+    }
+
+    pub fn width(self) -> Duration {
+        match self {
+            Tier::Minute => Duration::minutes(1),
+            Tier::Hour => Duration::hours(1),
+        }
+    }
+
+    /// How long rollups of this tier are kept.
+    fn retention(self) -> Duration {
+        match self {
+            Tier::Minute => Duration::days(7),
+            Tier::Hour => Duration::days(90),
+        }
+    }
+}
+
+/// One metric over a bucket.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct Stat {
+    pub avg: f64,
+    pub min: f64,
+    pub max: f64,
+    /// Samples behind the figures.
+    pub count: u32,
+}
+
+impl Stat {
+    fn merge(&mut self, other: &Stat) {
+        let total = self.count + other.count;
+        if total > 0 {
+            self.avg =
+                (self.avg * self.count as f64 + other.avg * other.count as f64) / total as f64;
+        }
+        self.min = self.min.min(other.min);
+        self.max = self.max.max(other.max);
+        self.count = total;
+    }
+}
+
+/// Metrics over the time from `timestamp` to the next bucket, keyed by snapshot field.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Bucket {
+    pub timestamp: DateTime<Utc>,
+    pub values: BTreeMap<String, Stat>,
+}
+
+impl Bucket {
+    fn from_sample(sample: &impl Sampled, width: Duration) -> Self {
+        let values = sample
+            .values()
+            .into_iter()
+            .map(|(name, value)| {
+                let stat = Stat {
+                    avg: value,
+                    min: value,
+                    max: value,
+                    count: 1,
+                };
+                (name.to_string(), stat)
+            })
+            .collect();
+        Self {
+            timestamp: align(sample.timestamp(), width),
+            values,
+        }
+    }
+
+    fn merge(&mut self, other: &Bucket) {
+        for (name, stat) in &other.values {
+            self.values
+                .entry(name.clone())
+                .and_modify(|s| s.merge(stat))
+                .or_insert(*stat);
+        }
+    }
+}
+
+fn align(timestamp: DateTime<Utc>, width: Duration) -> DateTime<Utc> {
+    timestamp.duration_trunc(width).unwrap_or(timestamp)
+}
+
+/// A snapshot that can be rolled up.
+pub trait Sampled {
+    fn timestamp(&self) -> DateTime<Utc>;
+    /// The numeric fields, by their JSON name.
+    fn values(&self) -> Vec<(&'static str, f64)>;
+}
+
+impl Sampled for SystemSnapshot {
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        vec![
+            ("cpuPercent", self.cpu_percent as f64),
+            ("memPercent", self.mem_percent as f64),
+            ("memUsed", self.mem_used as f64),
+            ("diskPercent", self.disk_percent as f64),
+            ("diskUsed", self.disk_used as f64),
+            ("rxBytesPerSec", self.rx_bytes_per_sec as f64),
+            ("txBytesPerSec", self.tx_bytes_per_sec as f64),
+        ]
+    }
+}
+
+impl Sampled for GameSnapshot {
+    fn timestamp(&self) -> DateTime<Utc> {
+        self.timestamp
+    }
+
+    /// Only `online` (1 or 0) and process usage while the server is down, so outages
+    /// don't drag the player and fps averages to zero.
+    fn values(&self) -> Vec<(&'static str, f64)> {
+        let mut values = if self.online {
+            vec![
+                ("online", 1.0),
+                ("players", self.players as f64),
+                ("queued", self.queued as f64),
+                ("fps", self.fps),
+                ("entities", self.entities as f64),
+            ]
+        } else {
+            vec![("online", 0.0)]
+        };
+        values.extend(self.cpu_percent.map(|v| ("cpuPercent", v as f64)));
+        values.extend(self.memory_bytes.map(|v| ("memoryBytes", v as f64)));
+        values.extend(self.threads.map(|v| ("threads", v as f64)));
+        values
+    }
+}
+
+/// Group buckets (oldest first) into `width`-wide ones.
+fn downsample(buckets: impl IntoIterator<Item = Bucket>, width: Duration) -> Vec<Bucket> {
+    let mut out: Vec<Bucket> = Vec::new();
+    for mut bucket in buckets {
+        bucket.timestamp = align(bucket.timestamp, width);
+        match out.last_mut() {
+            Some(last) if last.timestamp == bucket.timestamp => last.merge(&bucket),
+            _ => out.push(bucket),
+        }
+    }
+    out
+}
+
+/// Rolls one source's snapshots up into the minute tier as they come in, and each
+/// finished hour's minutes into the hour tier. Runs inside the source's collector; only
+/// the current minute is held in memory, so a restart loses at most that.
+pub struct RollupWriter {
+    source: String,
+    store: Arc<dyn MetricsStore>,
+    minute: Option<Bucket>,
+}
+
+impl RollupWriter {
+    pub fn new(source: String, store: Arc<dyn MetricsStore>) -> Self {
+        Self {
+            source,
+            store,
+            minute: None,
+        }
+    }
+
+    pub fn add(&mut self, sample: &impl Sampled) {
+        let bucket = Bucket::from_sample(sample, Tier::Minute.width());
+        match self.minute {
+            Some(ref mut current) if current.timestamp == bucket.timestamp => {
+                current.merge(&bucket)
+            }
+            _ => {
+                if let Some(finished) = self.minute.replace(bucket) {
+                    self.finish_minute(&finished);
+                }
+            }
+        }
+    }
+
+    fn finish_minute(&self, minute: &Bucket) {
+        if let Err(e) = self.store.save_rollup(&self.source, Tier::Minute, minute) {
+            tracing::error!("Failed to save {} minute rollup: {}", self.source, e);
+            return;
+        }
+        let hour = align(minute.timestamp, Tier::Hour.width());
+        let next = self
+            .minute
+            .as_ref()
+            .map(|m| align(m.timestamp, Tier::Hour.width()));
+        if next != Some(hour) {
+            if let Err(e) = self.finish_hour(hour) {
+                tracing::error!("Failed to save {} hour rollup: {}", self.source, e);
+            }
+        }
+    }
+
+    /// Roll the hour starting at `hour` up from its minutes (including those saved before
+    /// a restart), and drop rollups past their retention.
+    fn finish_hour(&self, hour: DateTime<Utc>) -> anyhow::Result<()> {
+        let width = Tier::Hour.width();
+        let minutes = self
+            .store
+            .load_rollups(&self.source, Tier::Minute, hour, hour + width)?;
+        if let Some(rollup) = downsample(minutes, width).pop() {
+            self.store.save_rollup(&self.source, Tier::Hour, &rollup)?;
+        }
+        for tier in [Tier::Minute, Tier::Hour] {
+            self.store
+                .prune_rollups(&self.source, tier, Utc::now() - tier.retention())?;
+        }
+        Ok(())
+    }
+}
+
+/// ?from=&to=&resolution= on the monitor endpoints.
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct RangeQuery {
+    /// Start of the range (RFC 3339); defaults to an hour before `to`.
+    pub from: Option<DateTime<Utc>>,
+    /// End of the range (RFC 3339); defaults to now.
+    pub to: Option<DateTime<Utc>>,
+    /// Bucket width in seconds; defaults to the range split into 300 buckets.
+    pub resolution: Option<i64>,
+}
+
+impl RangeQuery {
+    pub fn is_empty(&self) -> bool {
+        self.from.is_none() && self.to.is_none() && self.resolution.is_none()
+    }
+}
+
+/// Downsampled history for a time range.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MetricRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+    pub resolution_secs: i64,
+    /// Where the buckets came from: "raw" (the in-memory history) or the coarsest rollup
+    /// tier used, with finer tiers and then the raw history filling in the time it
+    /// doesn't cover yet.
+    pub tier: &'static str,
+    pub buckets: Vec<Bucket>,
+}
+
+/// Answer a range query from the in-memory `history` when it reaches back far enough,
+/// otherwise from the rollups of `source` plus whatever `history` has after them.
+pub fn query_range<T: Sampled + Clone>(
+    query: &RangeQuery,
+    history: &RingBuffer<T>,
+    store: &dyn MetricsStore,
+    source: &str,
+) -> Result<MetricRange, ApiError> {
+    let to = query.to.unwrap_or_else(Utc::now);
+    let from = query.from.unwrap_or(to - Duration::hours(1));
+    if from >= to {
+        return Err(ApiError::validation("from must be before to"));
+    }
+    let span = (to - from).num_seconds().max(1);
+    let resolution = query
+        .resolution
+        .unwrap_or_else(|| (span + DEFAULT_BUCKETS - 1) / DEFAULT_BUCKETS);
+    if resolution < 1 {
+        return Err(ApiError::validation("resolution must be at least 1 second"));
+    }
+    if span / resolution > MAX_BUCKETS {
+        return Err(ApiError::validation(format!(
+            "At most {} buckets per query; raise resolution",
+            MAX_BUCKETS
+        )));
+    }
+
+    let raw = |since: DateTime<Utc>| {
+        history
+            .iter()
+            .filter(move |s| s.timestamp() >= since && s.timestamp() < to)
+            .map(|s| Bucket::from_sample(s, Duration::seconds(1)))
+    };
+    let in_memory = history.iter().next().is_some_and(|s| s.timestamp() <= from);
+    let (tier, buckets): (&'static str, Vec<Bucket>) = if in_memory {
+        ("raw", raw(from).collect())
+    } else {
+        let tiers: &[Tier] = if resolution >= 3600 {
+            &[Tier::Hour, Tier::Minute]
+        } else {
+            &[Tier::Minute]
+        };
+        let mut buckets = Vec::new();
+        let mut covered = from;
+        for tier in tiers {
+            let rollups = store
+                .load_rollups(source, *tier, covered, to)
+                .map_err(|e| ApiError::internal(format!("Failed to load metric rollups: {}", e)))?;
+            if let Some(last) = rollups.last() {
+                covered = last.timestamp + tier.width();
+            }
+            buckets.extend(rollups);
+        }
+        buckets.extend(raw(covered));
+        (tiers[0].name(), buckets)
+    };
+
+    Ok(MetricRange {
+        from,
+        to,
+        resolution_secs: resolution,
+        tier,
+        buckets: downsample(buckets, Duration::seconds(resolution)),
+    })
+}
@@ -1,17 +1,33 @@
-use actix_web::{web, HttpResponse};
-use chrono::{DateTime, Datelike, NaiveTime, Utc, Weekday};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, Datelike, NaiveDateTime, NaiveTime, TimeZone, Utc, Weekday};
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
+use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::{Notify, RwLock};
 use tokio::time::{interval, Duration};
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
-use crate::lgsm::LgsmLock;
-use crate::rcon::RconClient;
-use crate::registry::ServerRegistry;
+use crate::apierror::{ApiError, ErrorCode};
+use crate::audit;
+use crate::auth::Claims;
+use crate::commandpolicy;
+use crate::config::{AppConfig, ConsoleConfig};
+use crate::cronexpr::CronSchedule;
+use crate::diagnostics;
+use crate::events::{EventBus, PanelEvent};
+use crate::lgsm::{ActionRecord, LgsmLock};
+use crate::map::MapImageCache;
+use crate::pagination::{self, ListQuery, SortKey};
+use crate::persistence::ScheduleStore;
+use crate::rcon::{FlushedCommand, RconClient};
+use crate::registry::{ProvisioningStatus, ServerRegistry};
+use crate::requestid;
+use crate::shutdown::Shutdown;
+use crate::wipes::{ScheduledWipe, WipeRecord, WipeType};
 
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum JobType {
     Restart,
@@ -21,9 +37,17 @@ pub enum JobType {
     WipeFull,
     RconCommand,
     Announce,
+    /// Ordered list of the other job types, run under one LGSM lock.
+    Pipeline,
+    /// POST a templated message to a webhook (Discord, Slack, ...).
+    Webhook,
+    /// Check for a game update and apply it, waiting for a quiet moment if configured.
+    AutoUpdate,
+    /// Back up the panel's own data to a directory and/or URL (see `PanelBackupTarget`).
+    PanelBackup,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct ScheduledJob {
     pub id: String,
@@ -36,9 +60,350 @@ pub struct ScheduledJob {
     pub next_run: Option<DateTime<Utc>>,
     pub created_at: DateTime<Utc>,
     pub server_id: String,
+    /// Queue RconCommand/Announce jobs while the server is unreachable and send them on reconnect.
+    #[serde(default)]
+    pub queue_when_offline: bool,
+    /// Why the scheduler disabled this job (e.g. its server was deleted).
+    #[serde(default)]
+    pub disabled_reason: Option<String>,
+    /// Outcome of the most recent run.
+    #[serde(default)]
+    pub last_result: Option<JobResult>,
+    #[serde(default)]
+    pub misfire_policy: MisfirePolicy,
+    /// Position in the wipe payload's seed list for the next rotation.
+    #[serde(default)]
+    pub seed_index: usize,
+    /// Announce jobs with a message set: index of the message sent last.
+    #[serde(default)]
+    pub last_announcement: Option<usize>,
+    /// Ranges during which this job's runs are suppressed (in addition to global ones).
+    #[serde(default)]
+    pub pause_windows: Vec<PauseWindow>,
+    /// AutoUpdate jobs: when a deferred update was first found.
+    #[serde(default)]
+    pub update_pending_since: Option<DateTime<Utc>>,
+    /// In-game countdown before runs of jobs that take the server down.
+    #[serde(default)]
+    pub warnings: Option<RestartWarnings>,
+}
+
+/// Chat warnings sent ahead of a Restart, Update or wipe run (or a pipeline with one).
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RestartWarnings {
+    /// Minutes before the run to warn at, e.g. [15, 5, 1]. Empty in an update removes
+    /// the warnings.
+    #[serde(alias = "warn_minutes")]
+    pub warn_minutes: Vec<u32>,
+    /// `{minutes}` is replaced with the minutes left.
+    #[serde(default = "default_warning_message")]
+    pub message: String,
+}
+
+fn default_warning_message() -> String {
+    "Server restart in {minutes} minute(s)".to_string()
+}
+
+/// A date/time range in which due jobs are skipped and recorded as suppressed.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct PauseWindow {
+    /// Local start, e.g. "2026-11-06T18:00:00".
+    pub start: NaiveDateTime,
+    pub end: NaiveDateTime,
+    /// IANA zone name for `start`/`end`.
+    #[serde(default = "default_pause_timezone")]
+    pub timezone: String,
+    #[serde(default)]
+    pub reason: Option<String>,
+}
+
+fn default_pause_timezone() -> String {
+    "UTC".to_string()
+}
+
+impl PauseWindow {
+    /// Start and end in UTC (None if the timezone is unknown).
+    fn bounds(&self) -> Option<(DateTime<Utc>, DateTime<Utc>)> {
+        let tz: chrono_tz::Tz = self.timezone.parse().ok()?;
+        let start = tz.from_local_datetime(&self.start).earliest()?;
+        let end = tz.from_local_datetime(&self.end).latest()?;
+        Some((start.with_timezone(&Utc), end.with_timezone(&Utc)))
+    }
+
+    fn contains(&self, at: DateTime<Utc>) -> bool {
+        self.bounds()
+            .is_some_and(|(start, end)| at >= start && at < end)
+    }
+
+    fn end_utc(&self) -> Option<DateTime<Utc>> {
+        self.bounds().map(|(_, end)| end)
+    }
+
+    fn validate(&self) -> Result<(), String> {
+        let (start, end) = self
+            .bounds()
+            .ok_or_else(|| format!("Unknown timezone '{}'", self.timezone))?;
+        if end <= start {
+            return Err("Pause window must end after it starts".to_string());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SetPausesRequest {
+    pub windows: Vec<PauseWindow>,
 }
 
-#[derive(Debug, Deserialize)]
+/// Optional payload for WipeMap/WipeFull jobs (and pipeline wipe steps).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WipeOptions {
+    /// Pick a fresh random seed on every wipe.
+    #[serde(default, alias = "random_seed")]
+    pub random_seed: bool,
+    /// Walk through these seeds round-robin.
+    #[serde(default, alias = "seed_list")]
+    pub seed_list: Vec<u32>,
+}
+
+/// Announce payload for a rotating set; a payload that isn't a JSON object with
+/// `messages` is sent as one plain message, as before.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AnnouncementSet {
+    pub messages: Vec<Announcement>,
+    #[serde(default)]
+    pub rotation: AnnounceRotation,
+    /// Don't announce while the game monitor's latest snapshot shows nobody online.
+    #[serde(default, alias = "skip_when_empty")]
+    pub skip_when_empty: bool,
+    /// Defaults for messages that don't set their own.
+    #[serde(default)]
+    pub prefix: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+}
+
+/// One message of a set: plain text, or text with its own formatting.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum Announcement {
+    Text(String),
+    Formatted {
+        text: String,
+        #[serde(default)]
+        prefix: Option<String>,
+        #[serde(default)]
+        color: Option<String>,
+        #[serde(default)]
+        size: Option<u32>,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum AnnounceRotation {
+    /// In order, continuing from the job's last_announcement.
+    #[default]
+    Sequential,
+    /// Random, never the same message twice in a row.
+    Random,
+}
+
+impl AnnounceRotation {
+    fn next(self, count: usize, last: Option<usize>) -> usize {
+        match self {
+            AnnounceRotation::Sequential => last.map_or(0, |i| (i + 1) % count),
+            AnnounceRotation::Random => {
+                let last = last.filter(|&i| i < count && count > 1);
+                let choices = count - usize::from(last.is_some());
+                let pick = rand::random::<usize>() % choices;
+                match last {
+                    Some(last) if pick >= last => pick + 1,
+                    _ => pick,
+                }
+            }
+        }
+    }
+}
+
+impl Announcement {
+    fn text(&self) -> &str {
+        match self {
+            Announcement::Text(text) | Announcement::Formatted { text, .. } => text,
+        }
+    }
+}
+
+impl AnnouncementSet {
+    /// Chat text for message `index`, with its prefix and rich-text formatting applied.
+    fn render(&self, index: usize) -> Result<String, String> {
+        let (text, prefix, color, size) = match &self.messages[index] {
+            Announcement::Text(text) => (text, None, None, None),
+            Announcement::Formatted {
+                text,
+                prefix,
+                color,
+                size,
+            } => (text, prefix.as_ref(), color.as_ref(), *size),
+        };
+        let text = match prefix.or(self.prefix.as_ref()) {
+            Some(prefix) => format!("{} {}", prefix, text),
+            None => text.clone(),
+        };
+        crate::rcon::format_rich_text(
+            &text,
+            color.or(self.color.as_ref()).map(String::as_str),
+            size,
+        )
+        .map_err(|e| e.to_string())
+    }
+}
+
+/// Optional payload for AutoUpdate jobs. Without a player threshold updates apply at once.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AutoUpdateOptions {
+    /// Only update while fewer than this many players are online...
+    #[serde(default, alias = "player_threshold")]
+    pub player_threshold: Option<u32>,
+    /// ...unless the update has been waiting this long.
+    #[serde(default = "default_max_delay_mins", alias = "max_delay_mins")]
+    pub max_delay_mins: u64,
+    /// In-game warning before the server goes down (0 to skip).
+    #[serde(default = "default_warning_secs", alias = "warning_secs")]
+    pub warning_secs: u64,
+    /// Post update decisions here.
+    #[serde(default, alias = "webhook_url")]
+    pub webhook_url: Option<String>,
+    /// Also run as soon as the update watcher sees a new build, not only on schedule.
+    #[serde(default, alias = "on_release")]
+    pub on_release: bool,
+}
+
+impl Default for AutoUpdateOptions {
+    fn default() -> Self {
+        Self {
+            player_threshold: None,
+            max_delay_mins: default_max_delay_mins(),
+            warning_secs: default_warning_secs(),
+            webhook_url: None,
+            on_release: false,
+        }
+    }
+}
+
+fn default_max_delay_mins() -> u64 {
+    120
+}
+
+fn default_warning_secs() -> u64 {
+    60
+}
+
+/// What to do with a run that was missed by more than MISFIRE_GRACE_SECS (panel downtime).
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MisfirePolicy {
+    /// Run once as soon as possible, then continue on schedule.
+    #[default]
+    RunOnceLate,
+    /// Drop the missed run and wait for the next slot.
+    Skip,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JobResult {
+    pub success: bool,
+    pub message: String,
+    pub at: DateTime<Utc>,
+}
+
+/// How a run was started.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum RunTrigger {
+    Schedule,
+    Manual,
+    /// The update watcher saw a new server build (AutoUpdate jobs with `onRelease`).
+    Release,
+}
+
+/// One recorded execution of a job.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct JobRun {
+    pub run_id: String,
+    pub job_id: String,
+    pub job_name: String,
+    pub server_id: String,
+    pub trigger: RunTrigger,
+    /// Request that started a manual run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_id: Option<String>,
+    pub started_at: DateTime<Utc>,
+    pub finished_at: DateTime<Utc>,
+    pub success: bool,
+    /// The run was due but did not execute (still running, or missed and skipped).
+    #[serde(default)]
+    pub skipped: bool,
+    /// Skipped because a pause window was active.
+    #[serde(default)]
+    pub suppressed: bool,
+    /// Output (or error), truncated to the last MAX_RUN_OUTPUT bytes.
+    pub output: String,
+    /// Per-step results for pipeline jobs.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub steps: Vec<StepResult>,
+    /// Delivery result of the failure notification, if one was sent.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notification: Option<String>,
+}
+
+/// One step of a Pipeline job; the job's payload is a JSON list of these.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PipelineStep {
+    pub job_type: JobType,
+    #[serde(default)]
+    pub payload: Option<String>,
+    /// Keep going if this step fails (default: stop the pipeline).
+    #[serde(default)]
+    pub continue_on_failure: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct StepResult {
+    pub index: usize,
+    pub job_type: JobType,
+    pub success: bool,
+    pub output: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct HistoryQuery {
+    pub since: Option<DateTime<Utc>>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PreviewQuery {
+    pub schedule: String,
+    /// IANA zone name (e.g. "Europe/Berlin") to render the run times in; default UTC.
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListJobsQuery {
+    #[serde(alias = "serverId")]
+    pub server_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateJobRequest {
     pub name: String,
@@ -47,9 +412,13 @@ pub struct CreateJobRequest {
     pub payload: Option<String>,
     pub enabled: Option<bool>,
     pub server_id: Option<String>,
+    pub queue_when_offline: Option<bool>,
+    pub misfire_policy: Option<MisfirePolicy>,
+    pub pause_windows: Option<Vec<PauseWindow>>,
+    pub warnings: Option<RestartWarnings>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UpdateJobRequest {
     pub name: Option<String>,
@@ -57,70 +426,461 @@ pub struct UpdateJobRequest {
     pub schedule: Option<String>,
     pub payload: Option<String>,
     pub enabled: Option<bool>,
+    pub server_id: Option<String>,
+    pub queue_when_offline: Option<bool>,
+    pub misfire_policy: Option<MisfirePolicy>,
+    pub pause_windows: Option<Vec<PauseWindow>>,
+    pub warnings: Option<RestartWarnings>,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
-    error: String,
+/// A job plus a preview of its next runs, returned by create/update.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct JobWithPreview {
+    #[serde(flatten)]
+    job: ScheduledJob,
+    next_runs: Vec<DateTime<Utc>>,
 }
 
-#[derive(Debug, Serialize)]
+impl JobWithPreview {
+    fn new(job: ScheduledJob, pauses: &[PauseWindow]) -> Self {
+        let next_runs = upcoming_runs(&job, pauses, 3);
+        Self {
+            job: display_job(&job, pauses),
+            next_runs,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 struct SuccessBody {
     success: bool,
     message: String,
 }
 
-const SCHEDULES_FILE: &str = "schedules.json";
+/// Runs kept per job.
+const MAX_RUNS_PER_JOB: usize = 50;
+const MAX_RUN_OUTPUT: usize = 4000;
+/// A due run later than this counts as missed for the misfire policy.
+const MISFIRE_GRACE_SECS: i64 = 300;
+const MAX_WARNINGS: usize = 10;
+const MAX_WARNING_MINUTES: u32 = 24 * 60;
+const MAX_WARNING_MESSAGE: usize = 256;
+/// Flushed commands whose run is not in the history yet are retried this long.
+const FLUSHED_MATCH_SECS: i64 = 600;
 
 pub struct Scheduler {
     pub jobs: RwLock<Vec<ScheduledJob>>,
+    /// Ids of jobs currently executing (scheduled or manual).
+    running: std::sync::Mutex<HashSet<String>>,
+    /// Recent runs per job id, newest last.
+    history: RwLock<HashMap<String, VecDeque<JobRun>>>,
+    /// Webhook that gets a summary whenever a job fails.
+    notify_on_failure_url: Option<String>,
+    /// Which commands RCON command jobs may send.
+    console: ConsoleConfig,
+    /// Global pause windows, applied to every job.
+    pub pauses: RwLock<Vec<PauseWindow>>,
+    /// Every recorded run is published here as a job_finished event.
+    events: EventBus,
+    /// Where the writer task saves jobs, history and pauses.
+    store: Arc<dyn ScheduleStore>,
+    /// Jobs / history / pauses changed since the last write.
+    jobs_dirty: AtomicBool,
+    history_dirty: AtomicBool,
+    pauses_dirty: AtomicBool,
+    /// Wakes the writer task (see `spawn_writer`).
+    save_requested: Notify,
+    /// Warnings sent so far, by job id.
+    warned: std::sync::Mutex<HashMap<String, WarningsSent>>,
+}
+
+/// The run a job's warnings are counting down to, and the minutes already announced.
+type WarningsSent = (DateTime<Utc>, HashSet<u32>);
+
+/// Marks a job as running until dropped.
+pub struct RunGuard {
+    scheduler: Arc<Scheduler>,
+    job_id: String,
+}
+
+impl Drop for RunGuard {
+    fn drop(&mut self) {
+        self.scheduler.running.lock().unwrap().remove(&self.job_id);
+    }
 }
 
 impl Scheduler {
-    pub fn new() -> Self {
-        let jobs = Self::load_from_disk().unwrap_or_default();
+    pub fn new(
+        notify_on_failure_url: Option<String>,
+        console: ConsoleConfig,
+        events: EventBus,
+        store: Arc<dyn ScheduleStore>,
+    ) -> Self {
+        let (jobs, history, pauses) = load_state(&*store);
         Self {
             jobs: RwLock::new(jobs),
+            running: std::sync::Mutex::new(HashSet::new()),
+            history: RwLock::new(history),
+            notify_on_failure_url,
+            console,
+            pauses: RwLock::new(pauses),
+            events,
+            store,
+            jobs_dirty: AtomicBool::new(false),
+            history_dirty: AtomicBool::new(false),
+            pauses_dirty: AtomicBool::new(false),
+            save_requested: Notify::new(),
+            warned: std::sync::Mutex::new(HashMap::new()),
         }
     }
 
-    fn load_from_disk() -> anyhow::Result<Vec<ScheduledJob>> {
-        let path = Path::new(SCHEDULES_FILE);
-        if !path.exists() {
-            return Ok(Vec::new());
+    /// Replace jobs, history and pause windows with what the store holds now, after a
+    /// panel restore swapped its contents. Jobs already running still record their run.
+    pub async fn reload(&self) {
+        let (jobs, history, pauses) = load_state(&*self.store);
+        self.jobs_dirty.store(false, Ordering::SeqCst);
+        self.history_dirty.store(false, Ordering::SeqCst);
+        self.pauses_dirty.store(false, Ordering::SeqCst);
+        *self.jobs.write().await = jobs;
+        *self.history.write().await = history;
+        *self.pauses.write().await = pauses;
+    }
+
+    /// Append a run to the job's bounded history and schedule a save.
+    pub async fn record_run(&self, run: JobRun) {
+        self.events.publish(PanelEvent::JobFinished {
+            server_id: run.server_id.clone(),
+            job_id: run.job_id.clone(),
+            job_name: run.job_name.clone(),
+            success: run.success,
+            skipped: run.skipped,
+            message: run.output.lines().last().unwrap_or_default().to_string(),
+        });
+        let mut history = self.history.write().await;
+        let runs = history.entry(run.job_id.clone()).or_default();
+        runs.push_back(run);
+        while runs.len() > MAX_RUNS_PER_JOB {
+            runs.pop_front();
         }
-        let content = std::fs::read_to_string(path)?;
-        let jobs: Vec<ScheduledJob> = serde_json::from_str(&content)?;
-        Ok(jobs)
+        drop(history);
+        self.history_dirty.store(true, Ordering::SeqCst);
+        self.save_requested.notify_one();
     }
 
-    async fn save_to_disk(&self) -> anyhow::Result<()> {
+    /// Append the outcome of a command queued by `flushed.label`'s run to that run's
+    /// output; a failed command fails the run. False if the run isn't recorded (yet).
+    pub async fn record_flushed(&self, flushed: &FlushedCommand) -> bool {
+        let mut history = self.history.write().await;
+        let Some(run) = history
+            .values_mut()
+            .flatten()
+            .find(|run| run.run_id == flushed.label)
+        else {
+            return false;
+        };
+        let note = match flushed.result {
+            Ok(ref output) if output.trim().is_empty() => {
+                format!("Queued command '{}' sent after reconnect", flushed.command)
+            }
+            Ok(ref output) => format!(
+                "Queued command '{}' sent after reconnect: {}",
+                flushed.command, output
+            ),
+            Err(ref e) => {
+                run.success = false;
+                format!(
+                    "Queued command '{}' failed after reconnect: {}",
+                    flushed.command, e
+                )
+            }
+        };
+        run.output = truncate_output(&format!("{}\n{}", run.output, note));
+        drop(history);
+        self.history_dirty.store(true, Ordering::SeqCst);
+        self.save_requested.notify_one();
+        true
+    }
+
+    /// Every recorded run of every job, in no particular order.
+    pub async fn all_runs(&self) -> Vec<JobRun> {
+        self.history.read().await.values().flatten().cloned().collect()
+    }
+
+    /// Record that jobs changed; the writer task persists them.
+    pub fn mark_dirty(&self) {
+        self.jobs_dirty.store(true, Ordering::SeqCst);
+        self.save_requested.notify_one();
+    }
+
+    fn mark_pauses_dirty(&self) {
+        self.pauses_dirty.store(true, Ordering::SeqCst);
+        self.save_requested.notify_one();
+    }
+
+    /// Mark `job_id` as running; None if it already is.
+    pub fn try_start(self: &Arc<Self>, job_id: &str) -> Option<RunGuard> {
+        if !self.running.lock().unwrap().insert(job_id.to_string()) {
+            return None;
+        }
+        Some(RunGuard {
+            scheduler: self.clone(),
+            job_id: job_id.to_string(),
+        })
+    }
+
+    /// Forget the warnings sent for a job's next run, so none follow once it is disabled
+    /// or deleted.
+    pub fn cancel_warnings(&self, job_id: &str) {
+        self.warned.lock().unwrap().remove(job_id);
+    }
+
+    /// Warnings that have come due by `now`, each returned once per run: the job and the
+    /// rendered message. When a tick passes several offsets at once, one message goes out
+    /// with the actual time left.
+    async fn take_due_warnings(
+        &self,
+        pauses: &[PauseWindow],
+        now: DateTime<Utc>,
+    ) -> Vec<(ScheduledJob, String)> {
         let jobs = self.jobs.read().await;
-        let content = serde_json::to_string_pretty(&*jobs)?;
-        std::fs::write(SCHEDULES_FILE, content)?;
-        Ok(())
+        let mut warned = self.warned.lock().unwrap();
+        warned.retain(|id, _| jobs.iter().any(|j| j.id == *id && j.enabled));
+
+        let mut due = Vec::new();
+        for job in jobs.iter().filter(|j| j.enabled) {
+            let (Some(warnings), Some(next)) = (&job.warnings, job.next_run) else {
+                continue;
+            };
+            // A run a pause window will suppress isn't coming
+            if next <= now || active_pause(job, pauses, next).is_some() {
+                continue;
+            }
+            let (run, sent) = warned
+                .entry(job.id.clone())
+                .or_insert_with(|| (next, HashSet::new()));
+            if *run != next {
+                *run = next;
+                sent.clear();
+            }
+            let left = next - now;
+            let passed: Vec<u32> = warnings
+                .warn_minutes
+                .iter()
+                .copied()
+                .filter(|m| left <= chrono::Duration::minutes(*m as i64) && !sent.contains(m))
+                .collect();
+            if passed.is_empty() {
+                continue;
+            }
+            sent.extend(passed);
+            let minutes = (left.num_seconds() + 59) / 60;
+            let message = warnings.message.replace("{minutes}", &minutes.to_string());
+            due.push((job.clone(), message));
+        }
+        due
+    }
+
+    /// Number of jobs executing right now.
+    pub fn running_jobs(&self) -> usize {
+        self.running.lock().unwrap().len()
+    }
+
+    /// Earliest next run of an enabled wipe job (or pipeline with a wipe step), by server.
+    pub async fn next_wipes(&self) -> HashMap<String, DateTime<Utc>> {
+        let mut next: HashMap<String, DateTime<Utc>> = HashMap::new();
+        for job in self.jobs.read().await.iter().filter(|j| j.enabled) {
+            let Some(at) = job.next_run.filter(|_| wipe_type(job).is_some()) else {
+                continue;
+            };
+            next.entry(job.server_id.clone())
+                .and_modify(|earliest| *earliest = (*earliest).min(at))
+                .or_insert(at);
+        }
+        next
+    }
+
+    /// The enabled wipe job of a server that runs next.
+    pub async fn next_wipe(&self, server_id: &str) -> Option<ScheduledWipe> {
+        self.jobs
+            .read()
+            .await
+            .iter()
+            .filter(|j| j.enabled && j.server_id == server_id)
+            .filter_map(|job| {
+                Some(ScheduledWipe {
+                    wipe_type: wipe_type(job)?,
+                    at: job.next_run?,
+                    job_id: job.id.clone(),
+                    job_name: job.name.clone(),
+                })
+            })
+            .min_by_key(|wipe| wipe.at)
+    }
+
+    /// Write whatever is dirty. Only the writer task calls this, so saves never interleave.
+    async fn flush(&self) {
+        let mut failed = false;
+        if self.jobs_dirty.swap(false, Ordering::SeqCst) {
+            let saved = self.store.save_jobs(&self.jobs.read().await);
+            if let Err(e) = saved {
+                tracing::error!("Failed to save schedules: {}", e);
+                diagnostics::report_error(WRITER_TASK, format!("Saving schedules: {}", e));
+                self.jobs_dirty.store(true, Ordering::SeqCst);
+                failed = true;
+            }
+        }
+        if self.history_dirty.swap(false, Ordering::SeqCst) {
+            let saved = self.store.save_job_history(&*self.history.read().await);
+            if let Err(e) = saved {
+                tracing::error!("Failed to save job history: {}", e);
+                diagnostics::report_error(WRITER_TASK, format!("Saving job history: {}", e));
+                self.history_dirty.store(true, Ordering::SeqCst);
+                failed = true;
+            }
+        }
+        if self.pauses_dirty.swap(false, Ordering::SeqCst) {
+            let saved = self.store.save_pauses(&self.pauses.read().await);
+            if let Err(e) = saved {
+                tracing::error!("Failed to save pause windows: {}", e);
+                diagnostics::report_error(WRITER_TASK, format!("Saving pause windows: {}", e));
+                self.pauses_dirty.store(true, Ordering::SeqCst);
+                failed = true;
+            }
+        }
+        if !failed {
+            diagnostics::report_ok(WRITER_TASK);
+        }
     }
 }
 
-fn compute_next_run(schedule: &str) -> Option<DateTime<Utc>> {
-    let now = Utc::now();
-    let parts: Vec<&str> = schedule.trim().split_whitespace().collect();
+type ScheduleState = (
+    Vec<ScheduledJob>,
+    HashMap<String, VecDeque<JobRun>>,
+    Vec<PauseWindow>,
+);
+
+/// Jobs, run history and pause windows from `store`; whatever fails to load starts empty.
+fn load_state(store: &dyn ScheduleStore) -> ScheduleState {
+    let jobs = store.load_jobs().unwrap_or_else(|e| {
+        tracing::warn!("Failed to load scheduled jobs: {}", e);
+        Vec::new()
+    });
+    let history = store.load_job_history().unwrap_or_else(|e| {
+        tracing::warn!("Failed to load job history: {}", e);
+        HashMap::new()
+    });
+    let pauses = store.load_pauses().unwrap_or_else(|e| {
+        tracing::warn!("Failed to load pause windows: {}", e);
+        Vec::new()
+    });
+    (jobs, history, pauses)
+}
+
+const WRITER_TASK: &str = "scheduler writer";
+
+/// Single writer for scheduler state. Handlers and the scheduler
+/// loop only mark state dirty; bursts of changes coalesce into one write. On shutdown a
+/// write in progress completes, then anything still dirty is flushed once more.
+pub fn spawn_writer(scheduler: Arc<Scheduler>, shutdown: Shutdown) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        loop {
+            tokio::select! {
+                _ = scheduler.save_requested.notified() => scheduler.flush().await,
+                _ = shutdown.signal() => break,
+            }
+        }
+        scheduler.flush().await;
+    })
+}
+
+/// Accepted schedule formats, for error messages.
+pub const SCHEDULE_FORMATS: &str =
+    "\"HH:MM\", \"Day HH:MM\", \"every 45m\" (s/m/h/d) or a 5-field cron expression";
+
+/// Why a schedule string was rejected.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+pub enum ScheduleError {
+    #[error("Schedule is empty")]
+    Empty,
+    #[error("Invalid time '{0}': use HH:MM between 00:00 and 23:59")]
+    InvalidTime(String),
+    #[error("Unknown day '{0}': use Mon-Sun or Monday-Sunday")]
+    InvalidWeekday(String),
+    #[error("Invalid interval '{0}': use \"every <n>s|m|h|d\"")]
+    InvalidInterval(String),
+    #[error("Invalid cron expression: {0}")]
+    InvalidCron(String),
+    #[error("Cron expression never fires")]
+    NeverFires,
+    #[error("Interval must be at least {0} seconds")]
+    IntervalTooShort(u64),
+    #[error("Unrecognized schedule '{0}'")]
+    UnknownFormat(String),
+}
+
+/// Next run of `schedule` from now. Accepts "HH:MM", "Day HH:MM", "every 45m" or a
+/// 5-field cron expression.
+pub fn compute_next_run(schedule: &str) -> Result<DateTime<Utc>, ScheduleError> {
+    next_run_after(schedule, Utc::now())
+}
+
+/// Next run for a job. Interval schedules count from the last run (or creation for the
+/// first run) instead of wall-clock anchors.
+pub fn next_run_for(job: &ScheduledJob) -> Option<DateTime<Utc>> {
+    match parse_interval(&job.schedule) {
+        Ok(every) => Some(job.last_run.unwrap_or(job.created_at) + every),
+        Err(_) => compute_next_run(&job.schedule).ok(),
+    }
+}
+
+/// Parse "every 90s" / "every 45m" / "every 6h" / "every 1d".
+fn parse_interval(schedule: &str) -> Result<chrono::Duration, ScheduleError> {
+    let invalid = || ScheduleError::InvalidInterval(schedule.trim().to_string());
+    let rest = schedule
+        .trim()
+        .strip_prefix("every ")
+        .ok_or_else(invalid)?
+        .trim();
+    let split = rest.find(|c: char| !c.is_ascii_digit()).ok_or_else(invalid)?;
+    let (value, unit) = rest.split_at(split);
+    let value: i64 = value.parse().ok().filter(|v| *v > 0).ok_or_else(invalid)?;
+    match unit {
+        "s" => Ok(chrono::Duration::seconds(value)),
+        "m" => Ok(chrono::Duration::minutes(value)),
+        "h" => Ok(chrono::Duration::hours(value)),
+        "d" => Ok(chrono::Duration::days(value)),
+        _ => Err(invalid()),
+    }
+}
+
+fn parse_time(s: &str) -> Result<NaiveTime, ScheduleError> {
+    NaiveTime::parse_from_str(s, "%H:%M").map_err(|_| ScheduleError::InvalidTime(s.to_string()))
+}
+
+/// Next run of `schedule` strictly after `now`.
+fn next_run_after(schedule: &str, now: DateTime<Utc>) -> Result<DateTime<Utc>, ScheduleError> {
+    if schedule.trim().starts_with("every") {
+        return parse_interval(schedule).map(|every| now + every);
+    }
+
+    let parts: Vec<&str> = schedule.split_whitespace().collect();
 
     match parts.len() {
+        0 => Err(ScheduleError::Empty),
         1 => {
-            let time = NaiveTime::parse_from_str(parts[0], "%H:%M").ok()?;
-            let today = now.date_naive().and_time(time);
-            let today_utc = today.and_utc();
+            let time = parse_time(parts[0])?;
+            let today_utc = now.date_naive().and_time(time).and_utc();
             if today_utc > now {
-                Some(today_utc)
+                Ok(today_utc)
             } else {
-                let tomorrow = now.date_naive().succ_opt()?.and_time(time);
-                Some(tomorrow.and_utc())
+                Ok(today_utc + chrono::Duration::days(1))
             }
         }
         2 => {
-            let target_day = parse_weekday(parts[0])?;
-            let time = NaiveTime::parse_from_str(parts[1], "%H:%M").ok()?;
+            let target_day = parse_weekday(parts[0])
+                .ok_or_else(|| ScheduleError::InvalidWeekday(parts[0].to_string()))?;
+            let time = parse_time(parts[1])?;
 
             let current_day = now.weekday();
             let mut days_ahead = (target_day.num_days_from_monday() as i64)
@@ -133,141 +893,1229 @@ fn compute_next_run(schedule: &str) -> Option<DateTime<Utc>> {
             let target_date = now.date_naive() + chrono::Duration::days(days_ahead);
             let target_dt = target_date.and_time(time).and_utc();
 
-            if target_dt <= now {
-                let next_week = target_date + chrono::Duration::days(7);
-                Some(next_week.and_time(time).and_utc())
+            if target_dt <= now {
+                Ok(target_dt + chrono::Duration::days(7))
+            } else {
+                Ok(target_dt)
+            }
+        }
+        5 => CronSchedule::parse(schedule)
+            .map_err(ScheduleError::InvalidCron)?
+            .next_after(now)
+            .ok_or(ScheduleError::NeverFires),
+        _ => Err(ScheduleError::UnknownFormat(schedule.trim().to_string())),
+    }
+}
+
+/// Check that a schedule parses, will actually fire, and isn't below the minimum interval.
+fn validate_schedule(schedule: &str, min_interval_secs: u64) -> Result<(), ScheduleError> {
+    if let Ok(every) = parse_interval(schedule) {
+        if every.num_seconds() < min_interval_secs as i64 {
+            return Err(ScheduleError::IntervalTooShort(min_interval_secs));
+        }
+    }
+    compute_next_run(schedule).map(|_| ())
+}
+
+/// Error for a rejected schedule, listing the accepted formats. Every ScheduleError,
+/// cron syntax errors included, is sent as one 422 INVALID_SCHEDULE so clients handle a
+/// single code; the message carries the parse error.
+fn schedule_error(e: ScheduleError) -> ApiError {
+    ApiError::new(ErrorCode::InvalidSchedule, e.to_string())
+        .with_detail("formats", SCHEDULE_FORMATS)
+}
+
+/// The next `count` run times of a job outside pause windows, for previews in API responses.
+fn upcoming_runs(job: &ScheduledJob, pauses: &[PauseWindow], count: usize) -> Vec<DateTime<Utc>> {
+    let mut runs = Vec::with_capacity(count);
+    let mut next = skip_paused(job, pauses, next_run_for(job));
+    while let Some(run) = next {
+        runs.push(run);
+        if runs.len() >= count {
+            break;
+        }
+        next = skip_paused(job, pauses, next_run_after(&job.schedule, run).ok());
+    }
+    runs
+}
+
+/// The pause window (the job's own or a global one) covering `at`, if any.
+fn active_pause<'a>(
+    job: &'a ScheduledJob,
+    pauses: &'a [PauseWindow],
+    at: DateTime<Utc>,
+) -> Option<&'a PauseWindow> {
+    job.pause_windows
+        .iter()
+        .chain(pauses.iter())
+        .find(|w| w.contains(at))
+}
+
+/// Move `candidate` past any pause windows it falls in.
+fn skip_paused(
+    job: &ScheduledJob,
+    pauses: &[PauseWindow],
+    mut candidate: Option<DateTime<Utc>>,
+) -> Option<DateTime<Utc>> {
+    // Bounded in case of many back-to-back windows
+    for _ in 0..100 {
+        let at = candidate?;
+        let Some(end) = active_pause(job, pauses, at).and_then(PauseWindow::end_utc) else {
+            return Some(at);
+        };
+        candidate = next_run_after(&job.schedule, end - chrono::Duration::seconds(1)).ok();
+    }
+    None
+}
+
+/// The job as shown to the UI: next_run is the first run after any pause window.
+fn display_job(job: &ScheduledJob, pauses: &[PauseWindow]) -> ScheduledJob {
+    let mut job = job.clone();
+    job.next_run = skip_paused(&job, pauses, job.next_run);
+    job
+}
+
+fn parse_weekday(s: &str) -> Option<Weekday> {
+    match s.to_lowercase().as_str() {
+        "mon" | "monday" => Some(Weekday::Mon),
+        "tue" | "tuesday" => Some(Weekday::Tue),
+        "wed" | "wednesday" => Some(Weekday::Wed),
+        "thu" | "thursday" => Some(Weekday::Thu),
+        "fri" | "friday" => Some(Weekday::Fri),
+        "sat" | "saturday" => Some(Weekday::Sat),
+        "sun" | "sunday" => Some(Weekday::Sun),
+        _ => None,
+    }
+}
+
+pub fn spawn_scheduler(
+    scheduler: Arc<Scheduler>,
+    registry: Arc<ServerRegistry>,
+    map_cache: Arc<MapImageCache>,
+    shutdown: Shutdown,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut tick = interval(Duration::from_secs(30));
+        // Flushed commands whose run was still in progress, retried on the next tick
+        let mut unmatched: Vec<(String, FlushedCommand, DateTime<Utc>)> = Vec::new();
+
+        loop {
+            tokio::select! {
+                _ = tick.tick() => {}
+                _ = shutdown.signal() => break,
+            }
+
+            let now = Utc::now();
+            diagnostics::report_ok("scheduler");
+
+            // Collect due jobs and advance their schedules under the lock, then release it
+            // so long-running jobs don't block the API or each other.
+            let mut due: Vec<ScheduledJob> = Vec::new();
+            let mut skipped: Vec<JobRun> = Vec::new();
+            // Only persist when a job actually changed
+            let mut changed = false;
+            let pauses = scheduler.pauses.read().await.clone();
+            {
+                let mut jobs = scheduler.jobs.write().await;
+                for job in jobs.iter_mut() {
+                    if !job.enabled {
+                        continue;
+                    }
+
+                    if job.next_run.is_none() {
+                        job.next_run = next_run_for(job);
+                        changed |= job.next_run.is_some();
+                    }
+
+                    let next = match job.next_run {
+                        Some(next) if now >= next => next,
+                        _ => continue,
+                    };
+
+                    // Server deleted: disable the job instead of failing every tick
+                    let Some(def) = registry.get_definition(&job.server_id).await else {
+                        tracing::warn!(
+                            "Disabling job '{}': server '{}' no longer exists",
+                            job.name,
+                            job.server_id
+                        );
+                        job.enabled = false;
+                        job.next_run = None;
+                        job.disabled_reason =
+                            Some(format!("Server '{}' no longer exists", job.server_id));
+                        changed = true;
+                        continue;
+                    };
+
+                    // Missed while the panel was down: run once now or skip to the next slot
+                    let misfired = now - next > chrono::Duration::seconds(MISFIRE_GRACE_SECS);
+                    if misfired && job.misfire_policy == MisfirePolicy::Skip {
+                        tracing::info!(
+                            "Job '{}' missed its run at {}, skipping to next",
+                            job.name,
+                            next
+                        );
+                        skipped.push(skipped_run(job, "missed while the panel was down"));
+                        job.next_run = compute_next_run(&job.schedule).ok();
+                        changed = true;
+                        continue;
+                    }
+
+                    if let Some(window) = active_pause(job, &pauses, now) {
+                        tracing::info!("Job '{}' suppressed by pause window", job.name);
+                        skipped.push(suppressed_run(job, window));
+                        job.next_run = next_run_after(&job.schedule, now).ok();
+                        changed = true;
+                        continue;
+                    }
+
+                    // Don't bring back a server that was stopped on purpose
+                    if def.expected_down() && starts_server(job) {
+                        let reason = match def.active_maintenance() {
+                            Some(_) => "server is in maintenance",
+                            None => "server was stopped on purpose",
+                        };
+                        tracing::info!("Job '{}' skipped: {}", job.name, reason);
+                        skipped.push(skipped_run(job, reason));
+                        job.next_run = next_run_after(&job.schedule, now).ok();
+                        changed = true;
+                        continue;
+                    }
+
+                    job.last_run = Some(now);
+                    job.next_run = next_run_for(job);
+                    due.push(job.clone());
+                    changed = true;
+                }
+            }
+
+            for job in due {
+                let guard = match scheduler.try_start(&job.id) {
+                    Some(g) => g,
+                    None => {
+                        tracing::warn!("Job '{}' is still running, skipping this run", job.name);
+                        skipped.push(skipped_run(&job, "previous run still in progress"));
+                        continue;
+                    }
+                };
+                tracing::info!("Executing scheduled job: {} ({})", job.name, job.id);
+
+                let scheduler = scheduler.clone();
+                let registry = registry.clone();
+                let map_cache = map_cache.clone();
+                tokio::spawn(async move {
+                    let _guard = guard;
+                    let started_at = Utc::now();
+                    let run_id = Uuid::new_v4().to_string();
+                    let mut result =
+                        run_job(&job, &run_id, &registry, &map_cache, &scheduler.console).await;
+                    log_result(&job, &result);
+                    let rotation = std::mem::take(&mut result.rotation);
+                    let pending_update = result.pending_update;
+                    let run = build_run(&job, run_id, RunTrigger::Schedule, started_at, result);
+                    finish_run(&scheduler, run, rotation, pending_update).await;
+                });
+            }
+
+            for run in skipped {
+                scheduler.record_run(run).await;
+            }
+
+            for (job, message) in scheduler.take_due_warnings(&pauses, now).await {
+                send_warning(&registry, &job, &message).await;
+            }
+
+            // Record commands that were queued while a server was offline and have now been
+            // sent on the run that queued them
+            let runtimes = registry.runtimes.read().await;
+            for (server_id, runtime) in runtimes.iter() {
+                for flushed in runtime.rcon.take_flushed().await {
+                    match flushed.result {
+                        Ok(_) => tracing::info!(
+                            "Queued command '{}' for run {} on '{}' sent after reconnect",
+                            flushed.command,
+                            flushed.label,
+                            server_id
+                        ),
+                        Err(ref e) => tracing::warn!(
+                            "Queued command '{}' for run {} on '{}' failed after reconnect: {}",
+                            flushed.command,
+                            flushed.label,
+                            server_id,
+                            e
+                        ),
+                    }
+                    unmatched.push((server_id.clone(), flushed, now));
+                }
+            }
+            drop(runtimes);
+            let mut retry = Vec::new();
+            for (server_id, flushed, seen) in unmatched.drain(..) {
+                if scheduler.record_flushed(&flushed).await {
+                    continue;
+                }
+                if now - seen < chrono::Duration::seconds(FLUSHED_MATCH_SECS) {
+                    retry.push((server_id, flushed, seen));
+                } else {
+                    tracing::warn!(
+                        "No run {} on '{}' to record queued command '{}' on",
+                        flushed.label,
+                        server_id,
+                        flushed.command
+                    );
+                }
+            }
+            unmatched = retry;
+
+            if changed {
+                scheduler.mark_dirty();
+            }
+        }
+    })
+}
+
+/// Say a restart warning on the job's server, unless the server is down on purpose.
+async fn send_warning(registry: &ServerRegistry, job: &ScheduledJob, message: &str) {
+    let Some(def) = registry.get_definition(&job.server_id).await else {
+        return;
+    };
+    if def.expected_down() {
+        return;
+    }
+    let Some(rcon) = registry.get_rcon(&job.server_id).await else {
+        return;
+    };
+    match rcon.say(message).await {
+        Ok(_) => tracing::info!("Job '{}' warning sent: {}", job.name, message),
+        Err(e) => tracing::warn!("Job '{}' warning failed: {}", job.name, e),
+    }
+}
+
+/// How jobs are named as the initiator of what they do (LGSM actions, wipes, activity).
+pub fn job_initiator(job_name: &str) -> String {
+    format!("scheduler ({})", job_name)
+}
+
+/// Resolve the job's server from the registry at execution time and run it.
+async fn run_job(
+    job: &ScheduledJob,
+    run_id: &str,
+    registry: &ServerRegistry,
+    map_cache: &MapImageCache,
+    console: &ConsoleConfig,
+) -> JobOutcome {
+    let rcon = registry.get_rcon(&job.server_id).await;
+    let config = registry.get_config(&job.server_id).await;
+    let lgsm_lock = registry.get_lgsm_lock(&job.server_id).await;
+    let started_at = Utc::now();
+
+    let outcome = match (rcon, config, lgsm_lock) {
+        (Some(rcon), Some(config), Some(lgsm_lock)) if job.job_type == JobType::AutoUpdate => {
+            let monitor = registry.get_game_monitor(&job.server_id).await;
+            execute_auto_update(
+                job,
+                &rcon,
+                &config,
+                &lgsm_lock,
+                monitor.as_deref(),
+                &registry.events,
+            )
+            .await
+        }
+        (Some(rcon), Some(config), Some(lgsm_lock)) => {
+            let monitor = registry.get_game_monitor(&job.server_id).await;
+            let monitor = monitor.as_deref();
+            execute_job(job, run_id, &rcon, &config, &lgsm_lock, monitor, console).await
+        }
+        _ => JobOutcome::new(Err(format!("Server '{}' is not ready", job.server_id))),
+    };
+
+    let action = match job.job_type {
+        JobType::Pipeline => Some("pipeline"),
+        ref job_type => lgsm_action_name(job_type),
+    };
+    if let Some(action) = action {
+        let record = ActionRecord::finished(
+            action,
+            Some(job_initiator(&job.name)),
+            started_at,
+            outcome.result.is_ok(),
+        );
+        registry.record_action(&job.server_id, record);
+    }
+
+    let wipe_type = match job.job_type {
+        JobType::WipeMap if outcome.result.is_ok() => Some(WipeType::Map),
+        JobType::WipeFull if outcome.result.is_ok() => Some(WipeType::Full),
+        _ => {
+            let wiped = |t: JobType| outcome.steps.iter().any(|s| s.success && s.job_type == t);
+            if wiped(JobType::WipeFull) {
+                Some(WipeType::Full)
+            } else if wiped(JobType::WipeMap) {
+                Some(WipeType::Map)
+            } else {
+                None
+            }
+        }
+    };
+    if let Some(wipe_type) = wipe_type {
+        // The definition still has the seed from before a rotation (updated below)
+        let previous_seed = registry
+            .get_definition(&job.server_id)
+            .await
+            .map(|def| def.seed)
+            .filter(|&seed| seed != 0);
+        let seed = outcome.rotation.seed.or(previous_seed);
+        let wipe = WipeRecord::now(Some(wipe_type), seed, Some(job_initiator(&job.name)))
+            .with_previous_seed(previous_seed);
+        registry.record_wipe(&job.server_id, wipe).await;
+    }
+
+    // A wipe rotated the seed: keep the definition in sync and drop the old map image
+    if let Some(seed) = outcome.rotation.seed {
+        let old = registry
+            .update_definition(&job.server_id, |def| {
+                let old = (def.world_size, def.seed);
+                def.seed = seed;
+                old
+            })
+            .await;
+        registry.persist().await;
+        if let Some((world_size, old_seed)) = old {
+            map_cache.invalidate(world_size, old_seed).await;
+        }
+    }
+    outcome
+}
+
+fn build_run(
+    job: &ScheduledJob,
+    run_id: String,
+    trigger: RunTrigger,
+    started_at: DateTime<Utc>,
+    outcome: JobOutcome,
+) -> JobRun {
+    let (success, output) = match outcome.result {
+        Ok(output) => (true, output),
+        Err(e) => (false, e),
+    };
+    JobRun {
+        run_id,
+        job_id: job.id.clone(),
+        job_name: job.name.clone(),
+        server_id: job.server_id.clone(),
+        trigger,
+        request_id: requestid::current(),
+        started_at,
+        finished_at: Utc::now(),
+        success,
+        skipped: false,
+        suppressed: false,
+        output: truncate_output(&output),
+        steps: outcome.steps,
+        notification: None,
+    }
+}
+
+/// Keep the tail of long output, where LGSM prints its result.
+fn truncate_output(output: &str) -> String {
+    let output = output.trim();
+    if output.len() <= MAX_RUN_OUTPUT {
+        return output.to_string();
+    }
+    let mut start = output.len() - MAX_RUN_OUTPUT;
+    while !output.is_char_boundary(start) {
+        start += 1;
+    }
+    format!("...{}", &output[start..])
+}
+
+impl JobRun {
+    fn to_result(&self) -> JobResult {
+        let message = self.output.lines().last().unwrap_or_default().to_string();
+        JobResult {
+            success: self.success,
+            message,
+            at: self.finished_at,
+        }
+    }
+}
+
+/// Whether the job (or a step of it) brings the server up, and so must not run while it
+/// is stopped on purpose or in maintenance.
+fn starts_server(job: &ScheduledJob) -> bool {
+    let starts = |job_type: &JobType| {
+        matches!(
+            job_type,
+            JobType::Restart | JobType::WipeMap | JobType::WipeFull | JobType::AutoUpdate
+        )
+    };
+    match job.job_type {
+        JobType::Pipeline => parse_pipeline(job.payload.as_deref())
+            .is_ok_and(|steps| steps.iter().any(|step| starts(&step.job_type))),
+        ref job_type => starts(job_type),
+    }
+}
+
+/// Whether the job (or a step of it) takes the server down, so players can be warned.
+fn takes_server_down(job_type: &JobType, payload: Option<&str>) -> bool {
+    let down = |job_type: &JobType| {
+        matches!(
+            job_type,
+            JobType::Restart | JobType::Update | JobType::WipeMap | JobType::WipeFull
+        )
+    };
+    match job_type {
+        JobType::Pipeline => {
+            parse_pipeline(payload).is_ok_and(|steps| steps.iter().any(|step| down(&step.job_type)))
+        }
+        job_type => down(job_type),
+    }
+}
+
+fn validate_warnings(
+    job_type: &JobType,
+    payload: Option<&str>,
+    warnings: &RestartWarnings,
+) -> Result<(), String> {
+    if warnings.warn_minutes.is_empty() {
+        return Ok(());
+    }
+    if !takes_server_down(job_type, payload) {
+        return Err(
+            "Warnings are only sent before Restart, Update and wipe jobs (or pipelines with one)"
+                .to_string(),
+        );
+    }
+    if warnings.warn_minutes.len() > MAX_WARNINGS {
+        return Err(format!("At most {} warnings", MAX_WARNINGS));
+    }
+    if warnings
+        .warn_minutes
+        .iter()
+        .any(|m| !(1..=MAX_WARNING_MINUTES).contains(m))
+    {
+        return Err(format!(
+            "Warning minutes must be between 1 and {}",
+            MAX_WARNING_MINUTES
+        ));
+    }
+    let message = warnings.message.trim();
+    if message.is_empty() || message.chars().count() > MAX_WARNING_MESSAGE {
+        return Err(format!(
+            "Warning message must be 1 to {} characters",
+            MAX_WARNING_MESSAGE
+        ));
+    }
+    Ok(())
+}
+
+/// Warnings as stored on a job: None when there are no offsets.
+fn normalize_warnings(warnings: &RestartWarnings) -> Option<RestartWarnings> {
+    if warnings.warn_minutes.is_empty() {
+        return None;
+    }
+    let mut warnings = warnings.clone();
+    warnings.warn_minutes.sort_unstable_by(|a, b| b.cmp(a));
+    warnings.warn_minutes.dedup();
+    Some(warnings)
+}
+
+/// A history entry for a scheduled run that did not execute.
+fn skipped_run(job: &ScheduledJob, reason: &str) -> JobRun {
+    let now = Utc::now();
+    JobRun {
+        run_id: Uuid::new_v4().to_string(),
+        job_id: job.id.clone(),
+        job_name: job.name.clone(),
+        server_id: job.server_id.clone(),
+        trigger: RunTrigger::Schedule,
+        request_id: None,
+        started_at: now,
+        finished_at: now,
+        success: false,
+        skipped: true,
+        suppressed: false,
+        output: format!("Skipped: {}", reason),
+        steps: Vec::new(),
+        notification: None,
+    }
+}
+
+/// A history entry for a due run that a pause window suppressed.
+fn suppressed_run(job: &ScheduledJob, window: &PauseWindow) -> JobRun {
+    let mut run = skipped_run(job, "");
+    run.suppressed = true;
+    run.output = format!(
+        "Suppressed: paused until {} {}{}",
+        window.end,
+        window.timezone,
+        window
+            .reason
+            .as_deref()
+            .map(|r| format!(" ({})", r))
+            .unwrap_or_default()
+    );
+    run
+}
+
+/// Store a finished run in history and on the job's last_result, sending the failure
+/// notification first if one is configured. Runs in the job's own task.
+async fn finish_run(
+    scheduler: &Scheduler,
+    mut run: JobRun,
+    rotation: Rotation,
+    pending_update: Option<Option<DateTime<Utc>>>,
+) {
+    if !run.success {
+        if let Some(ref url) = scheduler.notify_on_failure_url {
+            let summary = format!(
+                "Job '{}' on server '{}' failed at {}:\n{}",
+                run.job_name,
+                run.server_id,
+                run.finished_at.format("%Y-%m-%d %H:%M UTC"),
+                run.output.lines().last().unwrap_or_default()
+            );
+            let delivery = crate::webhook::send(url, &summary).await;
+            if let Err(ref e) = delivery {
+                tracing::warn!("Failure notification for job '{}' failed: {}", run.job_name, e);
+            }
+            run.notification = Some(delivery.unwrap_or_else(|e| e));
+        }
+    }
+    {
+        let mut jobs = scheduler.jobs.write().await;
+        if let Some(job) = jobs.iter_mut().find(|j| j.id == run.job_id) {
+            job.last_result = Some(run.to_result());
+            if let Some(index) = rotation.next_seed_index {
+                job.seed_index = index;
+            }
+            if let Some(index) = rotation.last_announcement {
+                job.last_announcement = Some(index);
+            }
+            if let Some(pending) = pending_update {
+                job.update_pending_since = pending;
+            }
+        }
+    }
+    scheduler.record_run(run).await;
+    scheduler.mark_dirty();
+}
+
+fn log_result(job: &ScheduledJob, outcome: &JobOutcome) {
+    match &outcome.result {
+        Ok(output) => tracing::info!("Job '{}' completed: {}", job.name, output),
+        Err(e) => tracing::error!("Job '{}' failed: {}", job.name, e),
+    }
+}
+
+/// Result of executing a job: overall outcome plus per-step results for pipelines.
+struct JobOutcome {
+    result: Result<String, String>,
+    steps: Vec<StepResult>,
+    rotation: Rotation,
+    /// New value for the job's update_pending_since (None: leave as is).
+    pending_update: Option<Option<DateTime<Utc>>>,
+}
+
+impl JobOutcome {
+    fn new(result: Result<String, String>) -> Self {
+        Self {
+            result,
+            steps: Vec::new(),
+            rotation: Rotation::default(),
+            pending_update: None,
+        }
+    }
+}
+
+/// Rotation state advanced by a run, to store on the job: the seed a wipe step chose
+/// (and the seed list position), and the announcement sent from a message set.
+#[derive(Default)]
+struct Rotation {
+    seed: Option<u32>,
+    next_seed_index: Option<usize>,
+    last_announcement: Option<usize>,
+}
+
+/// What a job's steps run against.
+#[derive(Clone, Copy)]
+struct StepTarget<'a> {
+    job: &'a ScheduledJob,
+    /// Commands queued while RCON is offline are reported back to this run.
+    run_id: &'a str,
+    rcon: &'a RconClient,
+    config: &'a crate::config::GameServerConfig,
+    monitor: Option<&'a crate::monitor::GameMonitor>,
+    console: &'a ConsoleConfig,
+}
+
+async fn execute_job(
+    job: &ScheduledJob,
+    run_id: &str,
+    rcon: &RconClient,
+    config: &crate::config::GameServerConfig,
+    lgsm_lock: &LgsmLock,
+    monitor: Option<&crate::monitor::GameMonitor>,
+    console: &ConsoleConfig,
+) -> JobOutcome {
+    let target = StepTarget {
+        job,
+        run_id,
+        rcon,
+        config,
+        monitor,
+        console,
+    };
+    if job.job_type == JobType::Pipeline {
+        return execute_pipeline(target, lgsm_lock).await;
+    }
+
+    let _guard = match lgsm_action_name(&job.job_type) {
+        Some(action) => Some(
+            lgsm_lock
+                .acquire(action, Some(&job_initiator(&job.name)))
+                .await,
+        ),
+        None => None,
+    };
+    // Outside pipelines, {result} in webhook messages is the job's previous outcome
+    let prior_result = match job.last_result {
+        Some(ref r) if r.success => format!("succeeded: {}", r.message),
+        Some(ref r) => format!("failed: {}", r.message),
+        None => "no previous run".to_string(),
+    };
+    let mut rotation = Rotation::default();
+    let result = execute_step(
+        &job.job_type,
+        job.payload.as_deref(),
+        target,
+        &prior_result,
+        &mut rotation,
+    )
+    .await;
+    JobOutcome {
+        result,
+        steps: Vec::new(),
+        rotation,
+        pending_update: None,
+    }
+}
+
+/// Check for an update and apply it (announce, save, update, start) under the server's
+/// LGSM lock, or defer it while too many players are online. Every decision is written
+/// to the run output.
+async fn execute_auto_update(
+    job: &ScheduledJob,
+    rcon: &RconClient,
+    config: &crate::config::GameServerConfig,
+    lgsm_lock: &LgsmLock,
+    monitor: Option<&crate::monitor::GameMonitor>,
+    events: &EventBus,
+) -> JobOutcome {
+    let options = match parse_auto_update(job.payload.as_deref()) {
+        Ok(o) => o,
+        Err(e) => return JobOutcome::new(Err(e)),
+    };
+    let _guard = lgsm_lock
+        .acquire("auto-update", Some(&job_initiator(&job.name)))
+        .await;
+    let mut log: Vec<String> = Vec::new();
+
+    let (since, newly_found) = match job.update_pending_since {
+        Some(since) => {
+            log.push(format!(
+                "Update pending since {}",
+                since.format("%Y-%m-%d %H:%M UTC")
+            ));
+            (since, false)
+        }
+        None => match run_lgsm(config, "check-update").await {
+            Ok(output) if crate::lgsm::update_available(&output) => {
+                log.push("Found update".to_string());
+                events.publish(PanelEvent::UpdateAvailable {
+                    server_id: job.server_id.clone(),
+                });
+                (Utc::now(), true)
+            }
+            Ok(_) => return JobOutcome::new(Ok("No update available".to_string())),
+            Err(e) => return JobOutcome::new(Err(format!("check-update failed: {}", e))),
+        },
+    };
+
+    let players = match monitor {
+        Some(m) => m
+            .history
+            .read()
+            .await
+            .latest()
+            .filter(|s| s.online)
+            .map_or(0, |s| s.players),
+        None => 0,
+    };
+
+    if let Some(threshold) = options.player_threshold {
+        let waited = Utc::now() - since;
+        let max_delay = chrono::Duration::minutes(options.max_delay_mins as i64);
+        if players >= threshold && waited < max_delay {
+            log.push(format!(
+                "Deferred: {} players online (threshold {}), forcing in {} min",
+                players,
+                threshold,
+                (max_delay - waited).num_minutes()
+            ));
+            // Only the first deferral is pushed; later ones are in the history
+            if newly_found {
+                notify_update(&options, &job.name, &config.name, &mut log).await;
+            }
+            let mut outcome = JobOutcome::new(Ok(log.join("\n")));
+            outcome.pending_update = Some(Some(since));
+            return outcome;
+        }
+        if players >= threshold {
+            log.push(format!(
+                "Max delay of {} min reached with {} players online",
+                options.max_delay_mins, players
+            ));
+        }
+    }
+
+    if players > 0 && options.warning_secs > 0 {
+        let warning = format!(
+            "Server is restarting for an update in {} seconds",
+            options.warning_secs
+        );
+        if let Err(e) = rcon.say(&warning).await {
+            log.push(format!("Warning failed: {}", e));
+        }
+        tokio::time::sleep(Duration::from_secs(options.warning_secs)).await;
+    }
+    if let Err(e) = rcon.execute("server.save").await {
+        log.push(format!("Save failed: {}", e));
+    }
+
+    let success = match run_lgsm(config, "update").await {
+        Ok(_) => match run_lgsm(config, "start").await {
+            Ok(_) => {
+                log.push(format!("Updated and restarted ({} players online)", players));
+                true
+            }
+            Err(e) => {
+                log.push(format!("Updated, but start failed: {}", e));
+                false
+            }
+        },
+        Err(e) => {
+            log.push(format!("Update failed: {}", e));
+            false
+        }
+    };
+    notify_update(&options, &job.name, &config.name, &mut log).await;
+
+    let text = log.join("\n");
+    let mut outcome = JobOutcome::new(if success { Ok(text) } else { Err(text) });
+    outcome.pending_update = Some(None);
+    outcome
+}
+
+/// Push the auto-update log so far to the job's webhook, noting delivery in the log.
+async fn notify_update(
+    options: &AutoUpdateOptions,
+    job_name: &str,
+    server_name: &str,
+    log: &mut Vec<String>,
+) {
+    let Some(ref url) = options.webhook_url else {
+        return;
+    };
+    let message = format!("[{}] {}:\n{}", server_name, job_name, log.join("\n"));
+    match crate::webhook::send(url, &message).await {
+        Ok(delivery) => log.push(delivery),
+        Err(e) => log.push(format!("Notification failed: {}", e)),
+    }
+}
+
+fn parse_auto_update(payload: Option<&str>) -> Result<AutoUpdateOptions, String> {
+    match payload.map(str::trim) {
+        None | Some("") => Ok(AutoUpdateOptions::default()),
+        Some(p) => {
+            serde_json::from_str(p).map_err(|e| format!("Invalid auto-update options: {}", e))
+        }
+    }
+}
+
+/// Run pipeline steps in order under a single LGSM lock.
+async fn execute_pipeline(target: StepTarget<'_>, lgsm_lock: &LgsmLock) -> JobOutcome {
+    let steps = match parse_pipeline(target.job.payload.as_deref()) {
+        Ok(steps) => steps,
+        Err(e) => return JobOutcome::new(Err(e)),
+    };
+
+    let _guard = lgsm_lock
+        .acquire("pipeline", Some(&job_initiator(&target.job.name)))
+        .await;
+    let mut rotation = Rotation::default();
+    let mut results: Vec<StepResult> = Vec::with_capacity(steps.len());
+    let mut failed = false;
+    for (index, step) in steps.iter().enumerate() {
+        // In a pipeline, {result} summarises the steps before this one
+        let prior_result = step_summary(&results);
+        let result = execute_step(
+            &step.job_type,
+            step.payload.as_deref(),
+            target,
+            &prior_result,
+            &mut rotation,
+        )
+        .await;
+        let success = result.is_ok();
+        results.push(StepResult {
+            index,
+            job_type: step.job_type.clone(),
+            success,
+            output: truncate_output(&result.unwrap_or_else(|e| e)),
+        });
+        if !success && !step.continue_on_failure {
+            failed = true;
+            break;
+        }
+    }
+
+    let summary = step_summary(&results);
+    JobOutcome {
+        result: if failed { Err(summary) } else { Ok(summary) },
+        steps: results,
+        rotation,
+        pending_update: None,
+    }
+}
+
+/// LGSM lock label for job types that run LinuxGSM (None for RCON-only jobs).
+fn lgsm_action_name(job_type: &JobType) -> Option<&'static str> {
+    match job_type {
+        JobType::Restart => Some("restart"),
+        JobType::Update => Some("update"),
+        JobType::Backup => Some("backup"),
+        JobType::WipeMap => Some("wipe-map"),
+        JobType::WipeFull => Some("wipe-full"),
+        JobType::AutoUpdate => Some("auto-update"),
+        JobType::RconCommand
+        | JobType::Announce
+        | JobType::Pipeline
+        | JobType::Webhook
+        | JobType::PanelBackup => None,
+    }
+}
+
+/// Run a single action. The caller holds the LGSM lock where one is needed.
+async fn execute_step(
+    job_type: &JobType,
+    payload: Option<&str>,
+    target: StepTarget<'_>,
+    prior_result: &str,
+    rotation: &mut Rotation,
+) -> Result<String, String> {
+    let StepTarget {
+        job,
+        run_id,
+        rcon,
+        config,
+        monitor,
+        console,
+    } = target;
+    match job_type {
+        JobType::Restart => run_lgsm(config, "restart").await,
+        JobType::Update => run_lgsm(config, "update").await,
+        JobType::Backup => {
+            let policy = parse_retention(payload)?;
+            let dir = crate::backups::backup_dir(config);
+            let before: HashSet<String> = crate::backups::list_archives(&dir)
+                .into_iter()
+                .map(|a| a.name)
+                .collect();
+            let output = run_lgsm(config, "backup").await?;
+            let Some(policy) = policy else {
+                return Ok(output);
+            };
+
+            // Never prune what this run created, even if it alone exceeds maxTotalGb
+            let mut created: HashSet<String> = crate::backups::list_archives(&dir)
+                .into_iter()
+                .map(|a| a.name)
+                .filter(|name| !before.contains(name))
+                .collect();
+            if created.is_empty() {
+                if let Some(newest) = crate::backups::list_archives(&dir).into_iter().next() {
+                    created.insert(newest.name);
+                }
+            }
+            let pruned = crate::backups::prune(&dir, &policy, &created);
+            if pruned.is_empty() {
+                Ok(output)
+            } else {
+                Ok(format!("Pruned: {}\n{}", pruned.join(", "), output))
+            }
+        }
+        JobType::WipeMap | JobType::WipeFull => {
+            let full = *job_type == JobType::WipeFull;
+            let options = parse_wipe_options(payload)?;
+            let _ = run_lgsm(config, "stop").await;
+            delete_wipe_files(&config.paths.server_files, full);
+
+            let mut seed_note = String::new();
+            if let Some(options) = options {
+                let index = rotation.next_seed_index.unwrap_or(job.seed_index);
+                if let Some((seed, next_index)) = choose_seed(&options, index) {
+                    crate::servercfg::set_convars(
+                        &config.paths.server_cfg,
+                        &[("server.seed", seed.to_string())],
+                    )
+                    .map_err(|e| format!("Failed to set seed: {}", e))?;
+                    rotation.seed = Some(seed);
+                    rotation.next_seed_index = next_index;
+                    seed_note = format!("New seed: {}\n", seed);
+                }
+            }
+
+            run_lgsm(config, "start")
+                .await
+                .map(|output| format!("{}{}", seed_note, output))
+        }
+        JobType::RconCommand => {
+            let cmd = payload.unwrap_or("");
+            commandpolicy::enforce(
+                console,
+                &job.server_id,
+                cmd,
+                &job_initiator(&job.name),
+                None,
+            )?;
+            if job.queue_when_offline {
+                rcon.execute_queued(cmd, run_id).await
             } else {
-                Some(target_dt)
+                rcon.execute(cmd).await
             }
+            .map_err(|e| e.to_string())
         }
-        _ => None,
+        JobType::Announce => {
+            let Some(set) = parse_announcements(payload)? else {
+                let msg = payload.unwrap_or("Server announcement");
+                return announce(job, run_id, rcon, msg).await;
+            };
+            let players = match monitor {
+                Some(monitor) => monitor.history.read().await.latest().map(|s| s.players),
+                None => None,
+            };
+            if set.messages.is_empty() {
+                return Err("Announcement set has no messages".to_string());
+            }
+            if set.skip_when_empty && players == Some(0) {
+                return Ok("Skipped: no players online".to_string());
+            }
+            let last = rotation.last_announcement.or(job.last_announcement);
+            let index = set.rotation.next(set.messages.len(), last);
+            let message = set.render(index)?;
+            let reply = announce(job, run_id, rcon, &message).await?;
+            rotation.last_announcement = Some(index);
+            Ok(format!(
+                "Sent message {}/{}: {}\n{}",
+                index + 1,
+                set.messages.len(),
+                message,
+                reply
+            ))
+        }
+        JobType::Webhook => {
+            let webhook = crate::webhook::WebhookPayload::parse(payload)?;
+            let time = Utc::now().to_rfc3339();
+            let message = crate::webhook::render(
+                &webhook.message,
+                &[
+                    ("server", &config.name),
+                    ("server_id", &config.id),
+                    ("job", &job.name),
+                    ("result", prior_result),
+                    ("time", &time),
+                ],
+            );
+            crate::webhook::send(&webhook.url, &message).await
+        }
+        JobType::PanelBackup => crate::panelbackup::run_scheduled(payload).await,
+        JobType::AutoUpdate => Err("Auto-update cannot run as a pipeline step".to_string()),
+        JobType::Pipeline => Err("Pipelines cannot be nested".to_string()),
     }
 }
 
-fn parse_weekday(s: &str) -> Option<Weekday> {
-    match s.to_lowercase().as_str() {
-        "mon" | "monday" => Some(Weekday::Mon),
-        "tue" | "tuesday" => Some(Weekday::Tue),
-        "wed" | "wednesday" => Some(Weekday::Wed),
-        "thu" | "thursday" => Some(Weekday::Thu),
-        "fri" | "friday" => Some(Weekday::Fri),
-        "sat" | "saturday" => Some(Weekday::Sat),
-        "sun" | "sunday" => Some(Weekday::Sun),
-        _ => None,
+/// Say `message` in chat, queued for the next reconnect if the job asks for that.
+async fn announce(
+    job: &ScheduledJob,
+    run_id: &str,
+    rcon: &RconClient,
+    message: &str,
+) -> Result<String, String> {
+    if job.queue_when_offline {
+        rcon.execute_queued(&crate::rcon::say_command(message), run_id)
+            .await
+    } else {
+        rcon.say(message).await
     }
+    .map_err(|e| e.to_string())
 }
 
-pub fn spawn_scheduler(
-    scheduler: Arc<Scheduler>,
-    registry: Arc<ServerRegistry>,
-) -> tokio::task::JoinHandle<()> {
-    tokio::spawn(async move {
-        let mut tick = interval(Duration::from_secs(30));
-
-        loop {
-            tick.tick().await;
+fn step_summary(results: &[StepResult]) -> String {
+    results
+        .iter()
+        .map(|r| {
+            format!(
+                "{}. {:?}: {}",
+                r.index + 1,
+                r.job_type,
+                if r.success { "ok" } else { "failed" }
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
-            let now = Utc::now();
-            let mut jobs = scheduler.jobs.write().await;
+fn parse_wipe_options(payload: Option<&str>) -> Result<Option<WipeOptions>, String> {
+    match payload.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(p) => serde_json::from_str(p)
+            .map(Some)
+            .map_err(|e| format!("Invalid wipe options: {}", e)),
+    }
+}
 
-            for job in jobs.iter_mut() {
-                if !job.enabled {
-                    continue;
-                }
+/// Pick the seed for this wipe. Returns the seed and, for seed lists, the next index.
+fn choose_seed(options: &WipeOptions, index: usize) -> Option<(u32, Option<usize>)> {
+    if !options.seed_list.is_empty() {
+        let i = index % options.seed_list.len();
+        return Some((options.seed_list[i], Some(i + 1)));
+    }
+    if options.random_seed {
+        return Some((rand::random::<u32>() % crate::validation::MAX_SEED + 1, None));
+    }
+    None
+}
 
-                if job.next_run.is_none() {
-                    job.next_run = compute_next_run(&job.schedule);
-                }
+/// Most messages an announcement set may have.
+const MAX_ANNOUNCEMENTS: usize = 50;
 
-                if let Some(next) = job.next_run {
-                    if now >= next {
-                        tracing::info!(
-                            "Executing scheduled job: {} ({})",
-                            job.name,
-                            job.id
-                        );
+fn parse_announcements(payload: Option<&str>) -> Result<Option<AnnouncementSet>, String> {
+    let Some(payload) = payload.map(str::trim).filter(|p| p.starts_with('{')) else {
+        return Ok(None);
+    };
+    match serde_json::from_str::<serde_json::Value>(payload) {
+        Ok(value) if value.get("messages").is_some() => serde_json::from_value(value)
+            .map(Some)
+            .map_err(|e| format!("Invalid announcement set: {}", e)),
+        // Some other text that happens to start with a brace
+        _ => Ok(None),
+    }
+}
 
-                        let rcon = registry.get_rcon(&job.server_id).await;
-                        let config = registry.get_config(&job.server_id).await;
-                        let lgsm_lock = registry.get_lgsm_lock(&job.server_id).await;
-
-                        if let (Some(rcon), Some(config), Some(lgsm_lock)) =
-                            (rcon, config, lgsm_lock)
-                        {
-                            execute_job(job, &rcon, &config, &lgsm_lock).await;
-                        } else {
-                            tracing::warn!(
-                                "Job '{}' server '{}' not found, skipping",
-                                job.name,
-                                job.server_id
-                            );
-                        }
-
-                        job.last_run = Some(now);
-                        job.next_run = compute_next_run(&job.schedule);
-                    }
-                }
-            }
+fn parse_retention(
+    payload: Option<&str>,
+) -> Result<Option<crate::backups::RetentionPolicy>, String> {
+    match payload.map(str::trim) {
+        None | Some("") => Ok(None),
+        Some(p) => serde_json::from_str::<crate::backups::RetentionPolicy>(p)
+            .map(|policy| (!policy.is_empty()).then_some(policy))
+            .map_err(|e| format!("Invalid retention options: {}", e)),
+    }
+}
 
-            drop(jobs);
+/// Most steps a pipeline may have.
+const MAX_PIPELINE_STEPS: usize = 20;
 
-            if let Err(e) = scheduler.save_to_disk().await {
-                tracing::error!("Failed to save schedules: {}", e);
-            }
-        }
-    })
+fn parse_pipeline(payload: Option<&str>) -> Result<Vec<PipelineStep>, String> {
+    let payload = payload.ok_or("Pipeline payload must be a JSON list of steps")?;
+    serde_json::from_str(payload).map_err(|e| format!("Invalid pipeline steps: {}", e))
 }
 
-async fn execute_job(
-    job: &ScheduledJob,
-    rcon: &RconClient,
-    config: &crate::config::GameServerConfig,
-    lgsm_lock: &LgsmLock,
-) {
-    let result = match job.job_type {
-        JobType::Restart => {
-            let _guard = lgsm_lock.lock.lock().await;
-            run_lgsm(&config.paths.lgsm_script, "restart").await
+/// Check that a job's payload is well-formed for its type.
+fn validate_payload(job_type: &JobType, payload: Option<&str>) -> Result<(), String> {
+    match job_type {
+        JobType::RconCommand if payload.is_none_or(|p| p.trim().is_empty()) => {
+            return Err("RCON command jobs need a command in the payload".to_string());
+        }
+        JobType::WipeMap | JobType::WipeFull => {
+            if let Some(options) = parse_wipe_options(payload)? {
+                if options.random_seed && !options.seed_list.is_empty() {
+                    return Err("Use either randomSeed or seedList, not both".to_string());
+                }
+                for seed in &options.seed_list {
+                    crate::validation::validate_seed(*seed)
+                        .map_err(|e| format!("Seed {}: {}", seed, e))?;
+                }
+            }
         }
-        JobType::Update => {
-            let _guard = lgsm_lock.lock.lock().await;
-            run_lgsm(&config.paths.lgsm_script, "update").await
+        JobType::Announce => {
+            if let Some(set) = parse_announcements(payload)? {
+                if set.messages.is_empty() || set.messages.len() > MAX_ANNOUNCEMENTS {
+                    return Err(format!(
+                        "Announcement sets need 1-{} messages",
+                        MAX_ANNOUNCEMENTS
+                    ));
+                }
+                for (index, message) in set.messages.iter().enumerate() {
+                    if message.text().trim().is_empty() {
+                        return Err(format!("Message {} is empty", index + 1));
+                    }
+                    set.render(index)
+                        .map_err(|e| format!("Message {}: {}", index + 1, e))?;
+                }
+            }
         }
         JobType::Backup => {
-            let _guard = lgsm_lock.lock.lock().await;
-            run_lgsm(&config.paths.lgsm_script, "backup").await
+            if let Some(policy) = parse_retention(payload)? {
+                policy.validate()?;
+            }
         }
-        JobType::WipeMap => {
-            let _guard = lgsm_lock.lock.lock().await;
-            let _ = run_lgsm(&config.paths.lgsm_script, "stop").await;
-            delete_wipe_files(&config.paths.server_files, false);
-            run_lgsm(&config.paths.lgsm_script, "start").await
+        JobType::Webhook => {
+            crate::webhook::WebhookPayload::parse(payload)?;
         }
-        JobType::WipeFull => {
-            let _guard = lgsm_lock.lock.lock().await;
-            let _ = run_lgsm(&config.paths.lgsm_script, "stop").await;
-            delete_wipe_files(&config.paths.server_files, true);
-            run_lgsm(&config.paths.lgsm_script, "start").await
+        JobType::PanelBackup => {
+            crate::panelbackup::PanelBackupTarget::parse(payload)?;
         }
-        JobType::RconCommand => {
-            let cmd = job.payload.as_deref().unwrap_or("");
-            rcon.execute(cmd).await.map_err(|e| e.to_string())
+        JobType::AutoUpdate => {
+            let options = parse_auto_update(payload)?;
+            if let Some(ref url) = options.webhook_url {
+                crate::webhook::validate_url(url)?;
+            }
         }
-        JobType::Announce => {
-            let msg = job.payload.as_deref().unwrap_or("Server announcement");
-            rcon.say(msg).await.map_err(|e| e.to_string())
+        JobType::Pipeline => {
+            let steps = parse_pipeline(payload)?;
+            if steps.is_empty() || steps.len() > MAX_PIPELINE_STEPS {
+                return Err(format!(
+                    "Pipelines need 1-{} steps",
+                    MAX_PIPELINE_STEPS
+                ));
+            }
+            for (index, step) in steps.iter().enumerate() {
+                if step.job_type == JobType::Pipeline {
+                    return Err(format!("Step {}: pipelines cannot be nested", index + 1));
+                }
+                if step.job_type == JobType::AutoUpdate {
+                    return Err(format!(
+                        "Step {}: auto-update cannot run as a pipeline step",
+                        index + 1
+                    ));
+                }
+                validate_payload(&step.job_type, step.payload.as_deref())
+                    .map_err(|e| format!("Step {}: {}", index + 1, e))?;
+            }
         }
-    };
-
-    match result {
-        Ok(output) => tracing::info!("Job '{}' completed: {}", job.name, output),
-        Err(e) => tracing::error!("Job '{}' failed: {}", job.name, e),
+        _ => {}
     }
+    Ok(())
 }
 
-async fn run_lgsm(script: &str, action: &str) -> Result<String, String> {
-    let output = tokio::process::Command::new(script)
-        .arg(action)
+async fn run_lgsm(
+    config: &crate::config::GameServerConfig,
+    action: &str,
+) -> Result<String, String> {
+    let output = crate::resources::lgsm_command(config, action)
         .output()
         .await
         .map_err(|e| e.to_string())?;
@@ -294,31 +2142,115 @@ fn delete_wipe_files(server_files: &str, full: bool) {
     }
 }
 
+/// What a job wipes, if anything; a pipeline counts as a full wipe if any step is one.
+fn wipe_type(job: &ScheduledJob) -> Option<WipeType> {
+    match job.job_type {
+        JobType::WipeMap => Some(WipeType::Map),
+        JobType::WipeFull => Some(WipeType::Full),
+        JobType::Pipeline => {
+            let steps = parse_pipeline(job.payload.as_deref()).ok()?;
+            if steps.iter().any(|s| s.job_type == JobType::WipeFull) {
+                Some(WipeType::Full)
+            } else if steps.iter().any(|s| s.job_type == JobType::WipeMap) {
+                Some(WipeType::Map)
+            } else {
+                None
+            }
+        }
+        _ => None,
+    }
+}
+
 // --- API Endpoints ---
 
-/// GET /api/schedule
-pub async fn list_jobs(scheduler: web::Data<Arc<Scheduler>>) -> HttpResponse {
+/// GET /api/schedule?server_id=
+#[utoipa::path(
+    get,
+    path = "/api/schedule",
+    tag = "schedule",
+    params(ListJobsQuery),
+    responses((status = 200, body = [ScheduledJob]))
+)]
+pub async fn list_jobs(
+    query: web::Query<ListJobsQuery>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> HttpResponse {
+    let pauses = scheduler.pauses.read().await.clone();
     let jobs = scheduler.jobs.read().await;
-    HttpResponse::Ok().json(&*jobs)
+    let filtered: Vec<ScheduledJob> = jobs
+        .iter()
+        .filter(|j| query.server_id.as_ref().is_none_or(|id| &j.server_id == id))
+        .map(|j| display_job(j, &pauses))
+        .collect();
+    HttpResponse::Ok().json(filtered)
+}
+
+/// A job can only be pointed at a server that exists and has finished provisioning.
+async fn check_job_server(registry: &ServerRegistry, server_id: &str) -> Result<(), ApiError> {
+    match registry.get_definition(server_id).await {
+        None => Err(ApiError::validation(format!(
+            "Unknown server '{}'",
+            server_id
+        ))),
+        Some(def) if def.provisioning_status != ProvisioningStatus::Ready => {
+            Err(ApiError::validation(format!(
+                "Server '{}' is not ready (status: {:?})",
+                server_id, def.provisioning_status
+            )))
+        }
+        Some(_) => Ok(()),
+    }
 }
 
 /// POST /api/schedule
+#[utoipa::path(
+    post,
+    path = "/api/schedule",
+    tag = "schedule",
+    request_body = CreateJobRequest,
+    responses(
+        (status = 201, body = JobWithPreview),
+        (status = 400, body = ApiError),
+        (status = 422, description = "Invalid schedule, cron syntax errors included; see `details.formats`", body = ApiError),
+    )
+)]
 pub async fn create_job(
+    req: HttpRequest,
     body: web::Json<CreateJobRequest>,
     scheduler: web::Data<Arc<Scheduler>>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
     let server_id = if let Some(ref id) = body.server_id {
+        check_job_server(&registry, id).await?;
         id.clone()
     } else {
         let defs = registry.definitions.read().await;
-        defs.first()
-            .map(|d| d.id.clone())
-            .unwrap_or_else(|| "main".to_string())
+        match defs
+            .values()
+            .find(|d| d.provisioning_status == ProvisioningStatus::Ready)
+        {
+            Some(def) => def.id.clone(),
+            None => return Err(ApiError::validation("No ready servers configured")),
+        }
     };
 
-    let next_run = compute_next_run(&body.schedule);
-    let job = ScheduledJob {
+    validate_schedule(&body.schedule, config.scheduler.min_interval_secs)
+        .map_err(schedule_error)?;
+    if let Err(e) = validate_payload(&body.job_type, body.payload.as_deref()) {
+        return Err(ApiError::validation(e));
+    }
+    if let Some(ref windows) = body.pause_windows {
+        if let Err(e) = validate_pauses(windows) {
+            return Err(ApiError::validation(e));
+        }
+    }
+    if let Some(ref warnings) = body.warnings {
+        validate_warnings(&body.job_type, body.payload.as_deref(), warnings)
+            .map_err(ApiError::validation)?;
+    }
+
+    let mut job = ScheduledJob {
         id: Uuid::new_v4().to_string(),
         name: body.name.clone(),
         job_type: body.job_type.clone(),
@@ -326,39 +2258,83 @@ pub async fn create_job(
         schedule: body.schedule.clone(),
         payload: body.payload.clone(),
         last_run: None,
-        next_run,
+        next_run: None,
         created_at: Utc::now(),
         server_id,
+        queue_when_offline: body.queue_when_offline.unwrap_or(false),
+        disabled_reason: None,
+        last_result: None,
+        seed_index: 0,
+        last_announcement: None,
+        misfire_policy: body.misfire_policy.clone().unwrap_or_default(),
+        pause_windows: body.pause_windows.clone().unwrap_or_default(),
+        update_pending_since: None,
+        warnings: body.warnings.as_ref().and_then(normalize_warnings),
     };
+    job.next_run = next_run_for(&job);
+    audit::detail_for(&req, &job.server_id, job.name.clone());
 
     {
         let mut jobs = scheduler.jobs.write().await;
         jobs.push(job.clone());
     }
 
-    if let Err(e) = scheduler.save_to_disk().await {
-        tracing::error!("Failed to save schedules: {}", e);
-    }
+    scheduler.mark_dirty();
 
-    HttpResponse::Created().json(job)
+    let pauses = scheduler.pauses.read().await.clone();
+    Ok(HttpResponse::Created().json(JobWithPreview::new(job, &pauses)))
 }
 
 /// PUT /api/schedule/{id}
+#[utoipa::path(
+    put,
+    path = "/api/schedule/{id}",
+    tag = "schedule",
+    params(("id" = String, Path, description = "Job id")),
+    request_body = UpdateJobRequest,
+    responses(
+        (status = 200, body = JobWithPreview),
+        (status = 400, body = ApiError),
+        (status = 422, description = "Invalid schedule, cron syntax errors included; see `details.formats`", body = ApiError),
+        (status = 404, description = "Job not found", body = ApiError),
+    )
+)]
 pub async fn update_job(
+    req: HttpRequest,
     id: web::Path<String>,
     body: web::Json<UpdateJobRequest>,
     scheduler: web::Data<Arc<Scheduler>>,
-) -> HttpResponse {
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    if let Some(ref server_id) = body.server_id {
+        check_job_server(&registry, server_id).await?;
+    }
+    if let Some(ref schedule) = body.schedule {
+        validate_schedule(schedule, config.scheduler.min_interval_secs).map_err(schedule_error)?;
+    }
+    if let Some(ref windows) = body.pause_windows {
+        if let Err(e) = validate_pauses(windows) {
+            return Err(ApiError::validation(e));
+        }
+    }
+
+    let pauses = scheduler.pauses.read().await.clone();
     let mut jobs = scheduler.jobs.write().await;
     let job = match jobs.iter_mut().find(|j| j.id == *id) {
         Some(j) => j,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Job not found".to_string(),
-            })
-        }
+        None => return Err(ApiError::not_found("Job not found")),
     };
 
+    let job_type = body.job_type.as_ref().unwrap_or(&job.job_type);
+    let payload = body.payload.as_deref().or(job.payload.as_deref());
+    if let Err(e) = validate_payload(job_type, payload) {
+        return Err(ApiError::validation(e));
+    }
+    if let Some(warnings) = body.warnings.as_ref().or(job.warnings.as_ref()) {
+        validate_warnings(job_type, payload, warnings).map_err(ApiError::validation)?;
+    }
+
     if let Some(ref name) = body.name {
         job.name = name.clone();
     }
@@ -367,78 +2343,926 @@ pub async fn update_job(
     }
     if let Some(ref schedule) = body.schedule {
         job.schedule = schedule.clone();
-        job.next_run = compute_next_run(schedule);
+        job.next_run = next_run_for(job);
     }
     if let Some(ref payload) = body.payload {
         job.payload = Some(payload.clone());
     }
     if let Some(enabled) = body.enabled {
         job.enabled = enabled;
+        if enabled {
+            job.disabled_reason = None;
+        }
+    }
+    if let Some(ref server_id) = body.server_id {
+        job.server_id = server_id.clone();
+    }
+    if let Some(queue_when_offline) = body.queue_when_offline {
+        job.queue_when_offline = queue_when_offline;
+    }
+    if let Some(ref misfire_policy) = body.misfire_policy {
+        job.misfire_policy = misfire_policy.clone();
+    }
+    if let Some(ref windows) = body.pause_windows {
+        job.pause_windows = windows.clone();
+    }
+    if let Some(ref warnings) = body.warnings {
+        job.warnings = normalize_warnings(warnings);
     }
 
     let job = job.clone();
     drop(jobs);
-
-    if let Err(e) = scheduler.save_to_disk().await {
-        tracing::error!("Failed to save schedules: {}", e);
+    if !job.enabled {
+        scheduler.cancel_warnings(&job.id);
     }
+    audit::detail_for(&req, &job.server_id, job.name.clone());
+
+    scheduler.mark_dirty();
 
-    HttpResponse::Ok().json(job)
+    Ok(HttpResponse::Ok().json(JobWithPreview::new(job, &pauses)))
 }
 
 /// DELETE /api/schedule/{id}
+#[utoipa::path(
+    delete,
+    path = "/api/schedule/{id}",
+    tag = "schedule",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Job not found", body = ApiError),
+    )
+)]
 pub async fn delete_job(
+    req: HttpRequest,
     id: web::Path<String>,
     scheduler: web::Data<Arc<Scheduler>>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     let mut jobs = scheduler.jobs.write().await;
-    let original_len = jobs.len();
-    jobs.retain(|j| j.id != *id);
-
-    if jobs.len() == original_len {
-        return HttpResponse::NotFound().json(ErrorBody {
-            error: "Job not found".to_string(),
-        });
-    }
-
+    let Some(index) = jobs.iter().position(|j| j.id == *id) else {
+        return Err(ApiError::not_found("Job not found"));
+    };
+    let job = jobs.remove(index);
     drop(jobs);
+    scheduler.cancel_warnings(&job.id);
+    audit::detail_for(&req, &job.server_id, job.name);
 
-    if let Err(e) = scheduler.save_to_disk().await {
-        tracing::error!("Failed to save schedules: {}", e);
-    }
+    scheduler.mark_dirty();
 
-    HttpResponse::Ok().json(SuccessBody {
+    Ok(HttpResponse::Ok().json(SuccessBody {
         success: true,
         message: format!("Job {} deleted", id),
-    })
+    }))
 }
 
 /// POST /api/schedule/{id}/toggle
+#[utoipa::path(
+    post,
+    path = "/api/schedule/{id}/toggle",
+    tag = "schedule",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 200, body = ScheduledJob),
+        (status = 404, description = "Job not found", body = ApiError),
+    )
+)]
 pub async fn toggle_job(
+    req: HttpRequest,
     id: web::Path<String>,
     scheduler: web::Data<Arc<Scheduler>>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     let mut jobs = scheduler.jobs.write().await;
     let job = match jobs.iter_mut().find(|j| j.id == *id) {
         Some(j) => j,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Job not found".to_string(),
-            })
-        }
+        None => return Err(ApiError::not_found("Job not found")),
     };
 
     job.enabled = !job.enabled;
     if job.enabled {
-        job.next_run = compute_next_run(&job.schedule);
+        job.next_run = next_run_for(job);
+        job.disabled_reason = None;
     }
 
     let job = job.clone();
     drop(jobs);
+    if !job.enabled {
+        scheduler.cancel_warnings(&job.id);
+    }
+    let state = if job.enabled { "enabled" } else { "disabled" };
+    audit::detail_for(&req, &job.server_id, format!("{} ({})", job.name, state));
+
+    scheduler.mark_dirty();
+
+    Ok(HttpResponse::Ok().json(job))
+}
+
+/// POST /api/schedule/{id}/run — execute a job now without touching its schedule.
+#[utoipa::path(
+    post,
+    path = "/api/schedule/{id}/run",
+    tag = "schedule",
+    params(("id" = String, Path, description = "Job id")),
+    responses(
+        (status = 202, description = "`runId`, `jobId` and `status` of the started run", body = Object),
+        (status = 404, description = "Job not found", body = ApiError),
+        (status = 409, description = "Job is already running", body = ApiError),
+    )
+)]
+pub async fn run_job_now(
+    req: HttpRequest,
+    id: web::Path<String>,
+    scheduler: web::Data<Arc<Scheduler>>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    map_cache: web::Data<Arc<MapImageCache>>,
+) -> Result<HttpResponse, ApiError> {
+    let job = {
+        let jobs = scheduler.jobs.read().await;
+        match jobs.iter().find(|j| j.id == *id) {
+            Some(j) => j.clone(),
+            None => return Err(ApiError::not_found("Job not found")),
+        }
+    };
+    audit::detail_for(&req, &job.server_id, job.name.clone());
+
+    let scheduler = scheduler.into_inner().as_ref().clone();
+    let guard = match scheduler.try_start(&job.id) {
+        Some(g) => g,
+        None => {
+            return Err(ApiError::conflict(format!(
+                "Job '{}' is already running",
+                job.name
+            )))
+        }
+    };
+
+    let user = req
+        .extensions()
+        .get::<Claims>()
+        .map(|c| c.sub.clone())
+        .unwrap_or_default();
+    let run_id = Uuid::new_v4().to_string();
+    tracing::info!(
+        "Manual run {} of job '{}' ({}) requested by '{}'",
+        run_id,
+        job.name,
+        job.id,
+        user
+    );
+
+    let response = serde_json::json!({
+        "runId": run_id,
+        "jobId": job.id,
+        "status": "running",
+    });
+    spawn_run(
+        scheduler,
+        registry.into_inner().as_ref().clone(),
+        map_cache.into_inner().as_ref().clone(),
+        job,
+        guard,
+        run_id,
+        RunTrigger::Manual,
+    );
+
+    Ok(HttpResponse::Accepted().json(response))
+}
+
+/// Run a job outside its schedule in the background; `guard` marks it running until done.
+fn spawn_run(
+    scheduler: Arc<Scheduler>,
+    registry: Arc<ServerRegistry>,
+    map_cache: Arc<MapImageCache>,
+    job: ScheduledJob,
+    guard: RunGuard,
+    run_id: String,
+    trigger: RunTrigger,
+) {
+    tokio::spawn(requestid::propagate(async move {
+        let _guard = guard;
+        let started_at = Utc::now();
+        let mut result = run_job(&job, &run_id, &registry, &map_cache, &scheduler.console).await;
+        log_result(&job, &result);
+
+        let rotation = std::mem::take(&mut result.rotation);
+        let pending_update = result.pending_update;
+        let run = build_run(&job, run_id, trigger, started_at, result);
+        finish_run(&scheduler, run, rotation, pending_update).await;
+    }));
+}
+
+/// Start the server's enabled AutoUpdate jobs that opted into `onRelease`, skipping any
+/// already running. Returns the names of the jobs started.
+pub async fn run_release_updates(
+    scheduler: &Arc<Scheduler>,
+    registry: &Arc<ServerRegistry>,
+    map_cache: &Arc<MapImageCache>,
+    server_id: &str,
+) -> Vec<String> {
+    let jobs: Vec<ScheduledJob> = scheduler
+        .jobs
+        .read()
+        .await
+        .iter()
+        .filter(|j| j.enabled && j.job_type == JobType::AutoUpdate && j.server_id == server_id)
+        .filter(|j| parse_auto_update(j.payload.as_deref()).is_ok_and(|o| o.on_release))
+        .cloned()
+        .collect();
+
+    let mut started = Vec::new();
+    for job in jobs {
+        let Some(guard) = scheduler.try_start(&job.id) else {
+            continue;
+        };
+        let run_id = Uuid::new_v4().to_string();
+        tracing::info!(
+            "Release run {} of job '{}' ({}) for '{}'",
+            run_id,
+            job.name,
+            job.id,
+            server_id
+        );
+        started.push(job.name.clone());
+        spawn_run(
+            scheduler.clone(),
+            registry.clone(),
+            map_cache.clone(),
+            job,
+            guard,
+            run_id,
+            RunTrigger::Release,
+        );
+    }
+    started
+}
+
+fn validate_pauses(windows: &[PauseWindow]) -> Result<(), String> {
+    for (index, window) in windows.iter().enumerate() {
+        window
+            .validate()
+            .map_err(|e| format!("Pause window {}: {}", index + 1, e))?;
+    }
+    Ok(())
+}
+
+/// GET /api/schedule/pause — global pause windows.
+#[utoipa::path(
+    get,
+    path = "/api/schedule/pause",
+    tag = "schedule",
+    responses((status = 200, description = "Global pause `windows`", body = Object))
+)]
+pub async fn get_pauses(scheduler: web::Data<Arc<Scheduler>>) -> HttpResponse {
+    let pauses = scheduler.pauses.read().await;
+    HttpResponse::Ok().json(serde_json::json!({ "windows": &*pauses }))
+}
+
+/// PUT /api/schedule/pause — replace the global pause windows. Windows that have already
+/// ended are dropped.
+#[utoipa::path(
+    put,
+    path = "/api/schedule/pause",
+    tag = "schedule",
+    request_body = SetPausesRequest,
+    responses(
+        (status = 200, description = "The stored `windows`", body = Object),
+        (status = 400, body = ApiError),
+    )
+)]
+pub async fn set_pauses(
+    body: web::Json<SetPausesRequest>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> Result<HttpResponse, ApiError> {
+    if let Err(e) = validate_pauses(&body.windows) {
+        return Err(ApiError::validation(e));
+    }
+    let now = Utc::now();
+    let windows: Vec<PauseWindow> = body
+        .into_inner()
+        .windows
+        .into_iter()
+        .filter(|w| w.end_utc().is_some_and(|end| end > now))
+        .collect();
+
+    *scheduler.pauses.write().await = windows.clone();
+    scheduler.mark_pauses_dirty();
+    tracing::info!("Global pause windows updated ({} active)", windows.len());
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({ "windows": windows })))
+}
+
+/// GET /api/schedule/preview?schedule=...&timezone=... — next five runs of a schedule.
+/// Schedules run in UTC; `timezone` only changes how the times are displayed.
+#[utoipa::path(
+    get,
+    path = "/api/schedule/preview",
+    tag = "schedule",
+    params(PreviewQuery),
+    responses(
+        (status = 200, description = "`nextRuns`: the next five runs, in UTC and the requested zone", body = Object),
+        (status = 400, body = ApiError),
+        (status = 422, description = "Invalid schedule, cron syntax errors included; see `details.formats`", body = ApiError),
+    )
+)]
+pub async fn preview_schedule(
+    query: web::Query<PreviewQuery>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let tz: chrono_tz::Tz = match query.timezone.as_deref().unwrap_or("UTC").parse() {
+        Ok(tz) => tz,
+        Err(_) => {
+            return Err(ApiError::validation(format!(
+                "Unknown timezone '{}'",
+                query.timezone.as_deref().unwrap_or("")
+            )))
+        }
+    };
+    validate_schedule(&query.schedule, config.scheduler.min_interval_secs)
+        .map_err(schedule_error)?;
+
+    let mut runs = Vec::with_capacity(5);
+    let mut next = compute_next_run(&query.schedule).ok();
+    while let Some(run) = next {
+        runs.push(serde_json::json!({
+            "utc": run,
+            "local": run.with_timezone(&tz).to_rfc3339(),
+        }));
+        if runs.len() >= 5 {
+            break;
+        }
+        next = next_run_after(&query.schedule, run).ok();
+    }
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "schedule": query.schedule,
+        "timezone": tz.name(),
+        "nextRuns": runs,
+    })))
+}
+
+/// Sort keys of the run history endpoints.
+const RUN_SORTS: &[SortKey<JobRun>] = &[
+    ("startedAt", |a, b| a.started_at.cmp(&b.started_at)),
+    ("finishedAt", |a, b| a.finished_at.cmp(&b.finished_at)),
+    ("jobName", |a, b| a.job_name.cmp(&b.job_name)),
+    ("serverId", |a, b| a.server_id.cmp(&b.server_id)),
+];
+
+/// GET /api/schedule/{id}/history — recent runs of one job, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/schedule/{id}/history",
+    tag = "schedule",
+    params(("id" = String, Path, description = "Job id"), ListQuery),
+    responses(
+        (status = 200, description = "Runs, or a `Page` of them when paging", body = [JobRun]),
+        (status = 422, description = "Unknown sort key or field, see `details.allowed`", body = ApiError),
+    )
+)]
+pub async fn job_history(
+    id: web::Path<String>,
+    list: web::Query<ListQuery>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> Result<HttpResponse, ApiError> {
+    let history = scheduler.history.read().await;
+    let runs: Vec<JobRun> = history
+        .get(id.as_str())
+        .map(|runs| runs.iter().rev().cloned().collect())
+        .unwrap_or_default();
+    Ok(HttpResponse::Ok().json(pagination::apply(&list, runs, RUN_SORTS)?))
+}
+
+/// GET /api/schedule/history?since= — runs of all jobs, newest first.
+#[utoipa::path(
+    get,
+    path = "/api/schedule/history",
+    tag = "schedule",
+    params(HistoryQuery, ListQuery),
+    responses(
+        (status = 200, description = "Runs, or a `Page` of them when paging", body = [JobRun]),
+        (status = 422, description = "Unknown sort key or field, see `details.allowed`", body = ApiError),
+    )
+)]
+pub async fn all_history(
+    query: web::Query<HistoryQuery>,
+    list: web::Query<ListQuery>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> Result<HttpResponse, ApiError> {
+    let history = scheduler.history.read().await;
+    let mut runs: Vec<JobRun> = history
+        .values()
+        .flatten()
+        .filter(|r| query.since.is_none_or(|since| r.started_at >= since))
+        .cloned()
+        .collect();
+    runs.sort_by_key(|r| std::cmp::Reverse(r.started_at));
+    Ok(HttpResponse::Ok().json(pagination::apply(&list, runs, RUN_SORTS)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::SqliteStore;
+    use chrono::Timelike;
+
+    fn scheduler() -> Scheduler {
+        Scheduler::new(
+            None,
+            serde_yaml::from_str("{}").unwrap(),
+            EventBus::new(),
+            Arc::new(SqliteStore::in_memory().unwrap()),
+        )
+    }
+
+    fn run(run_id: &str) -> JobRun {
+        let mut run = skipped_run(
+            &serde_json::from_value(serde_json::json!({
+                "id": "job-1",
+                "name": "Wipe warning",
+                "serverId": "main",
+                "jobType": "announce",
+                "enabled": true,
+                "schedule": "daily 18:00",
+                "createdAt": "2026-01-01T00:00:00Z",
+            }))
+            .unwrap(),
+            "",
+        );
+        run.run_id = run_id.to_string();
+        run.skipped = false;
+        run.success = true;
+        run.output = "RCON offline, command queued (1 pending)".to_string();
+        run
+    }
+
+    fn flushed(label: &str, result: Result<String, String>) -> FlushedCommand {
+        FlushedCommand {
+            command: "say hi".to_string(),
+            label: label.to_string(),
+            result,
+        }
+    }
+
+    #[tokio::test]
+    async fn flushed_commands_are_recorded_on_the_run_that_queued_them() {
+        let scheduler = scheduler();
+        scheduler.record_run(run("run-a")).await;
+        scheduler.record_run(run("run-b")).await;
+
+        assert!(scheduler.record_flushed(&flushed("run-a", Ok(String::new()))).await);
+        assert!(
+            scheduler
+                .record_flushed(&flushed("run-b", Err("Expired while RCON was offline".into())))
+                .await
+        );
+        assert!(!scheduler.record_flushed(&flushed("run-c", Ok(String::new()))).await);
+
+        let runs = scheduler.all_runs().await;
+        let a = runs.iter().find(|r| r.run_id == "run-a").unwrap();
+        assert!(a.success);
+        assert!(a.output.ends_with("\nQueued command 'say hi' sent after reconnect"));
+        let b = runs.iter().find(|r| r.run_id == "run-b").unwrap();
+        assert!(!b.success);
+        assert!(b.output.contains("failed after reconnect: Expired while RCON was offline"));
+    }
+
+    fn ready_server(id: &str) -> crate::registry::ServerDefinition {
+        serde_json::from_value(serde_json::json!({
+            "id": id,
+            "name": id,
+            "serverType": "vanilla",
+            "source": "dynamic",
+            "provisioningStatus": "ready",
+            "provisioningLog": [],
+            "gamePort": 28015,
+            "rconPort": 28016,
+            "queryPort": 27015,
+            "maxPlayers": 100,
+            "worldSize": 4000,
+            "seed": 1,
+            "hostname": id,
+            "rconPassword": "secret",
+            "basePath": "/tmp/unused",
+            "createdAt": "2026-01-01T00:00:00Z",
+        }))
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn cron_syntax_errors_are_rejected_as_invalid_schedules() {
+        use actix_web::{test, App};
+
+        let store = Arc::new(SqliteStore::in_memory().unwrap());
+        let registry = ServerRegistry::new(
+            vec![ready_server("main")],
+            HashMap::new(),
+            store.clone(),
+            store,
+        );
+        let config: AppConfig = serde_yaml::from_str("{}").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(scheduler())))
+                .app_data(web::Data::new(Arc::new(registry)))
+                .app_data(web::Data::new(config))
+                .route("/api/schedule", web::post().to(create_job))
+                .route("/api/schedule/{id}", web::put().to(update_job)),
+        )
+        .await;
+        let create = |schedule: &str| {
+            test::TestRequest::post()
+                .uri("/api/schedule")
+                .set_json(serde_json::json!({
+                    "name": "Forced wipe",
+                    "serverId": "main",
+                    "jobType": "announce",
+                    "schedule": schedule,
+                    "payload": "Wipe tonight",
+                }))
+                .to_request()
+        };
+
+        let response = test::call_service(&app, create("0 */4 * * *")).await;
+        assert_eq!(response.status(), 201);
+        let job: serde_json::Value = test::read_body_json(response).await;
+        let next_run = at(job["nextRun"].as_str().unwrap());
+        assert_eq!(next_run.minute(), 0);
+        assert_eq!(next_run.hour() % 4, 0);
+        assert_eq!(job["nextRuns"].as_array().unwrap().len(), 3);
+
+        // Deliberately 422 INVALID_SCHEDULE like every other schedule error, not 400
+        let update = test::TestRequest::put()
+            .uri(&format!("/api/schedule/{}", job["id"].as_str().unwrap()))
+            .set_json(serde_json::json!({"schedule": "0 19 * * thu#6"}))
+            .to_request();
+        for req in [create("61 * * * *"), create("0 25 * * *"), update] {
+            let response = test::call_service(&app, req).await;
+            assert_eq!(response.status(), 422);
+            let body: serde_json::Value = test::read_body_json(response).await;
+            assert_eq!(body["code"], "INVALID_SCHEDULE");
+            let message = body["error"].as_str().unwrap();
+            assert!(
+                message.starts_with("Invalid cron expression: "),
+                "{}",
+                message
+            );
+            assert_eq!(body["details"]["formats"], SCHEDULE_FORMATS);
+        }
+    }
+
+    #[actix_web::test]
+    async fn reenabled_interval_jobs_count_from_their_last_run() {
+        use actix_web::{test, App};
+
+        let last_run = Utc::now() - chrono::Duration::hours(1);
+        let scheduler = Arc::new(scheduler());
+        scheduler.jobs.write().await.push(
+            serde_json::from_value(serde_json::json!({
+                "id": "job-1",
+                "name": "Save",
+                "serverId": "main",
+                "jobType": "rcon_command",
+                "enabled": false,
+                "schedule": "every 6h",
+                "payload": "server.save",
+                "lastRun": last_run,
+                "createdAt": "2026-01-01T00:00:00Z",
+            }))
+            .unwrap(),
+        );
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(scheduler.clone()))
+                .route("/api/schedule/{id}/toggle", web::post().to(toggle_job)),
+        )
+        .await;
+
+        let req = test::TestRequest::post()
+            .uri("/api/schedule/job-1/toggle")
+            .to_request();
+        let job: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(job["enabled"], true);
+        // Not six hours from now: the interval keeps its phase
+        let expected = last_run + chrono::Duration::hours(6);
+        assert_eq!(at(job["nextRun"].as_str().unwrap()), expected);
+        assert_eq!(scheduler.jobs.read().await[0].next_run, Some(expected));
+    }
+
+    #[actix_web::test]
+    async fn concurrent_edits_are_all_saved_by_the_writer() {
+        use actix_web::{test, App};
+        use futures_util::future::join_all;
+
+        let store = Arc::new(SqliteStore::in_memory().unwrap());
+        let scheduler = Arc::new(Scheduler::new(
+            None,
+            serde_yaml::from_str("{}").unwrap(),
+            EventBus::new(),
+            store.clone(),
+        ));
+        let registry = Arc::new(ServerRegistry::new(
+            vec![ready_server("main")],
+            HashMap::new(),
+            store.clone(),
+            store.clone(),
+        ));
+        let config: AppConfig = serde_yaml::from_str("{}").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(scheduler.clone()))
+                .app_data(web::Data::new(registry))
+                .app_data(web::Data::new(config))
+                .route("/api/schedule", web::post().to(create_job))
+                .route("/api/schedule/{id}", web::put().to(update_job)),
+        )
+        .await;
+
+        // Handlers only mark the jobs dirty; nothing is written until the writer runs
+        let created = join_all((0..20).map(|i| {
+            let req = test::TestRequest::post()
+                .uri("/api/schedule")
+                .set_json(serde_json::json!({
+                    "name": format!("job {}", i),
+                    "serverId": "main",
+                    "jobType": "announce",
+                    "schedule": "every 1h",
+                    "payload": "hello",
+                }))
+                .to_request();
+            test::call_and_read_body_json::<_, _, serde_json::Value>(&app, req)
+        }))
+        .await;
+        assert!(store.load_jobs().unwrap().is_empty());
+        assert_eq!(scheduler.jobs.read().await.len(), 20);
 
-    if let Err(e) = scheduler.save_to_disk().await {
-        tracing::error!("Failed to save schedules: {}", e);
+        let shutdown = Shutdown::new();
+        let writer = spawn_writer(scheduler.clone(), shutdown.clone());
+        let updated = join_all(created.iter().map(|job| {
+            let req = test::TestRequest::put()
+                .uri(&format!("/api/schedule/{}", job["id"].as_str().unwrap()))
+                .set_json(serde_json::json!({"name": format!("{} renamed", job["name"].as_str().unwrap())}))
+                .to_request();
+            test::call_service(&app, req)
+        }))
+        .await;
+        assert!(updated.iter().all(|r| r.status() == 200));
+
+        shutdown.trigger();
+        writer.await.unwrap();
+        let mut names: Vec<_> = store
+            .load_jobs()
+            .unwrap()
+            .into_iter()
+            .map(|j| j.name)
+            .collect();
+        names.sort();
+        let mut expected: Vec<_> = (0..20).map(|i| format!("job {} renamed", i)).collect();
+        expected.sort();
+        assert_eq!(names, expected);
+    }
+
+    #[actix_web::test]
+    async fn unknown_jobs_get_a_404_error_body() {
+        use actix_web::{test, App};
+
+        let store = Arc::new(SqliteStore::in_memory().unwrap());
+        let registry = ServerRegistry::new(Vec::new(), HashMap::new(), store.clone(), store);
+        let config: AppConfig = serde_yaml::from_str("{}").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(Arc::new(scheduler())))
+                .app_data(web::Data::new(Arc::new(registry)))
+                .app_data(web::Data::new(Arc::new(MapImageCache::new())))
+                .app_data(web::Data::new(config))
+                .route("/api/schedule/{id}", web::put().to(update_job))
+                .route("/api/schedule/{id}", web::delete().to(delete_job))
+                .route("/api/schedule/{id}/toggle", web::post().to(toggle_job))
+                .route("/api/schedule/{id}/run", web::post().to(run_job_now)),
+        )
+        .await;
+
+        let requests = [
+            test::TestRequest::put()
+                .uri("/api/schedule/missing")
+                .set_json(serde_json::json!({"name": "renamed"})),
+            test::TestRequest::delete().uri("/api/schedule/missing"),
+            test::TestRequest::post().uri("/api/schedule/missing/toggle"),
+            test::TestRequest::post().uri("/api/schedule/missing/run"),
+        ];
+        for req in requests {
+            let response = test::call_service(&app, req.to_request()).await;
+            assert_eq!(response.status(), 404);
+            let body: serde_json::Value = test::read_body_json(response).await;
+            assert_eq!(
+                body,
+                serde_json::json!({"code": "NOT_FOUND", "error": "Job not found"})
+            );
+        }
+    }
+
+    fn at(rfc3339: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn fixed_times_and_weekdays_run_next_in_utc() {
+        // Thursday 2026-03-05 10:00 UTC
+        let now = at("2026-03-05T10:00:00Z");
+        let cases = [
+            ("10:30", "2026-03-05T10:30:00Z"),
+            ("09:00", "2026-03-06T09:00:00Z"),
+            // Exactly now is already past
+            ("10:00", "2026-03-06T10:00:00Z"),
+            ("00:00", "2026-03-06T00:00:00Z"),
+            ("23:59", "2026-03-05T23:59:00Z"),
+            ("Thu 11:00", "2026-03-05T11:00:00Z"),
+            ("thursday 09:00", "2026-03-12T09:00:00Z"),
+            ("Mon 04:00", "2026-03-09T04:00:00Z"),
+            ("SUNDAY 18:30", "2026-03-08T18:30:00Z"),
+        ];
+        for (schedule, expected) in cases {
+            assert_eq!(
+                next_run_after(schedule, now),
+                Ok(at(expected)),
+                "{}",
+                schedule
+            );
+        }
+    }
+
+    #[test]
+    fn intervals_count_from_the_given_time() {
+        let now = at("2026-03-05T10:00:00Z");
+        let cases = [
+            ("every 90s", "2026-03-05T10:01:30Z"),
+            ("every 45m", "2026-03-05T10:45:00Z"),
+            ("every 6h", "2026-03-05T16:00:00Z"),
+            ("every 1d", "2026-03-06T10:00:00Z"),
+            ("  every 2h ", "2026-03-05T12:00:00Z"),
+        ];
+        for (schedule, expected) in cases {
+            assert_eq!(
+                next_run_after(schedule, now),
+                Ok(at(expected)),
+                "{}",
+                schedule
+            );
+        }
+    }
+
+    #[test]
+    fn cron_expressions_run_at_their_next_match() {
+        let now = at("2026-03-05T10:07:00Z");
+        let cases = [
+            ("*/15 * * * *", "2026-03-05T10:15:00Z"),
+            ("0 4 * * *", "2026-03-06T04:00:00Z"),
+            ("30 18 * * fri", "2026-03-06T18:30:00Z"),
+            // First Thursday of the month; today's run at 19:00 is still ahead
+            ("0 19 * * thu#1", "2026-03-05T19:00:00Z"),
+            ("0 0 1 jan *", "2027-01-01T00:00:00Z"),
+            ("0 12 29 2 *", "2028-02-29T12:00:00Z"),
+        ];
+        for (schedule, expected) in cases {
+            assert_eq!(
+                next_run_after(schedule, now),
+                Ok(at(expected)),
+                "{}",
+                schedule
+            );
+        }
+    }
+
+    #[test]
+    fn invalid_schedules_say_what_is_wrong() {
+        let now = at("2026-03-05T10:00:00Z");
+        let cases = [
+            ("", ScheduleError::Empty),
+            ("   ", ScheduleError::Empty),
+            ("25:00", ScheduleError::InvalidTime("25:00".into())),
+            ("4pm", ScheduleError::InvalidTime("4pm".into())),
+            ("Thur 04:00", ScheduleError::InvalidWeekday("Thur".into())),
+            ("Mon 24:00", ScheduleError::InvalidTime("24:00".into())),
+            (
+                "every 0m",
+                ScheduleError::InvalidInterval("every 0m".into()),
+            ),
+            (
+                "every 5x",
+                ScheduleError::InvalidInterval("every 5x".into()),
+            ),
+            ("every m", ScheduleError::InvalidInterval("every m".into())),
+            (
+                "everyday",
+                ScheduleError::InvalidInterval("everyday".into()),
+            ),
+            ("0 0 30 2 *", ScheduleError::NeverFires),
+            (
+                "daily at 18:00",
+                ScheduleError::UnknownFormat("daily at 18:00".into()),
+            ),
+        ];
+        for (schedule, expected) in cases {
+            assert_eq!(
+                next_run_after(schedule, now),
+                Err(expected),
+                "{:?}",
+                schedule
+            );
+        }
+        assert!(matches!(
+            next_run_after("61 * * * *", now),
+            Err(ScheduleError::InvalidCron(_))
+        ));
+
+        assert_eq!(
+            validate_schedule("every 30s", 60),
+            Err(ScheduleError::IntervalTooShort(60))
+        );
+        assert_eq!(validate_schedule("every 1m", 60), Ok(()));
+        assert_eq!(
+            validate_schedule("Thur 04:00", 60),
+            Err(ScheduleError::InvalidWeekday("Thur".into()))
+        );
+    }
+
+    #[test]
+    fn schedules_stay_in_utc_across_dst_changes() {
+        let berlin: chrono_tz::Tz = "Europe/Berlin".parse().unwrap();
+        // Clocks in Berlin go forward at 01:00 UTC on 2026-03-29
+        let first = next_run_after("02:30", at("2026-03-28T12:00:00Z")).unwrap();
+        let second = next_run_after("02:30", first).unwrap();
+        assert_eq!(first, at("2026-03-29T02:30:00Z"));
+        assert_eq!(second - first, chrono::Duration::hours(24));
+        assert_eq!(
+            first.with_timezone(&berlin).to_rfc3339(),
+            "2026-03-29T04:30:00+02:00"
+        );
+        let before = next_run_after("02:30", at("2026-03-27T12:00:00Z")).unwrap();
+        assert_eq!(
+            before.with_timezone(&berlin).to_rfc3339(),
+            "2026-03-28T03:30:00+01:00"
+        );
+
+        // An hourly cron has no doubled or missing run when New York falls back
+        let mut run = at("2026-11-01T04:30:00Z");
+        for _ in 0..4 {
+            let next = next_run_after("0 * * * *", run).unwrap();
+            assert_eq!(
+                next - run,
+                chrono::Duration::minutes(if run.minute() == 30 { 30 } else { 60 })
+            );
+            run = next;
+        }
+        assert_eq!(run, at("2026-11-01T08:00:00Z"));
+
+        // Intervals are plain durations, unaffected by the change
+        let start = at("2026-03-29T00:30:00Z");
+        assert_eq!(
+            next_run_after("every 1h", start).unwrap(),
+            at("2026-03-29T01:30:00Z")
+        );
     }
 
-    HttpResponse::Ok().json(job)
+    #[actix_web::test]
+    async fn preview_renders_runs_in_the_requested_timezone() {
+        use actix_web::{test, App};
+
+        let config: AppConfig = serde_yaml::from_str("{}").unwrap();
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(config))
+                .route("/api/schedule/preview", web::get().to(preview_schedule)),
+        )
+        .await;
+
+        let req = test::TestRequest::get()
+            .uri("/api/schedule/preview?schedule=04:00&timezone=America/New_York")
+            .to_request();
+        let body: serde_json::Value = test::call_and_read_body_json(&app, req).await;
+        assert_eq!(body["timezone"], "America/New_York");
+        let runs = body["nextRuns"].as_array().unwrap();
+        assert_eq!(runs.len(), 5);
+        for run in runs {
+            let utc = at(run["utc"].as_str().unwrap());
+            let local = DateTime::parse_from_rfc3339(run["local"].as_str().unwrap()).unwrap();
+            assert_eq!(local, utc);
+            assert_eq!(utc.hour(), 4);
+            assert!(matches!(local.offset().local_minus_utc(), -18000 | -14400));
+        }
+
+        let req = test::TestRequest::get()
+            .uri("/api/schedule/preview?schedule=04:00&timezone=Mars/Olympus")
+            .to_request();
+        assert_eq!(test::call_service(&app, req).await.status(), 400);
+
+        let req = test::TestRequest::get()
+            .uri("/api/schedule/preview?schedule=25:00")
+            .to_request();
+        let response = test::call_service(&app, req).await;
+        assert_eq!(response.status(), 422);
+        let body: serde_json::Value = test::read_body_json(response).await;
+        assert_eq!(body["code"], "INVALID_SCHEDULE");
+        assert_eq!(body["details"]["formats"], SCHEDULE_FORMATS);
+    }
 }
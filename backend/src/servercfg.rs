@@ -0,0 +1,367 @@
+use actix_web::{web, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Arc;
+use utoipa::ToSchema;
+
+use crate::apierror::ApiError;
+use crate::audit;
+use crate::registry::ServerRegistry;
+use crate::validation::{self, ValidationErrors, MAX_PLAYERS_LIMIT};
+
+/// Set convars in a server.cfg file, replacing existing lines for each key and
+/// appending keys that are missing. Other lines are preserved as-is.
+pub fn set_convars(cfg_path: &str, values: &[(&str, String)]) -> anyhow::Result<()> {
+    let content = if Path::new(cfg_path).exists() {
+        std::fs::read_to_string(cfg_path)?
+    } else {
+        String::new()
+    };
+    let mut lines: Vec<String> = content.lines().map(|l| l.to_string()).collect();
+
+    for (key, value) in values {
+        let new_line = format!("{} {}", key, format_value(value));
+        // Later duplicates would override the new value when the game runs the cfg
+        let mut found = false;
+        lines.retain_mut(|line| {
+            if !line_key(line).is_some_and(|k| k.eq_ignore_ascii_case(key)) {
+                return true;
+            }
+            if found {
+                return false;
+            }
+            found = true;
+            *line = new_line.clone();
+            true
+        });
+        if !found {
+            lines.push(new_line);
+        }
+    }
+
+    std::fs::write(cfg_path, lines.join("\n") + "\n")?;
+    Ok(())
+}
+
+/// Extract the convar name from a cfg line, ignoring comments and blank lines.
+fn line_key(line: &str) -> Option<&str> {
+    let trimmed = line.trim_start();
+    if trimmed.is_empty() || trimmed.starts_with("//") || trimmed.starts_with('#') {
+        return None;
+    }
+    trimmed.split_whitespace().next()
+}
+
+/// Quote a convar value for server.cfg. Embedded double quotes would end the value
+/// early, so they are replaced with single quotes.
+fn format_value(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "'"))
+}
+
+/// The value of a cfg line: the quoted string, or the rest of the line up to a `//`
+/// comment.
+fn line_value(line: &str, key: &str) -> String {
+    let rest = line.trim_start()[key.len()..].trim_start();
+    if let Some(quoted) = rest.strip_prefix('"') {
+        return quoted.split('"').next().unwrap_or_default().to_string();
+    }
+    rest.split("//")
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string()
+}
+
+/// Convars set in a server.cfg, keyed by lowercased name. When a key repeats, the last
+/// line wins, as it does when the game executes the file.
+pub fn parse(content: &str) -> BTreeMap<String, String> {
+    content
+        .lines()
+        .filter_map(|line| {
+            let key = line_key(line)?;
+            Some((key.to_ascii_lowercase(), line_value(line, key)))
+        })
+        .collect()
+}
+
+/// Convars the panel writes from the server definition; changed through the server
+/// settings and connection endpoints instead.
+const MANAGED_CONVARS: &[&str] = &[
+    "server.port",
+    "server.queryport",
+    "rcon.port",
+    "rcon.password",
+];
+
+/// Convars provisioning writes from a clone's own definition rather than copying.
+const CLONE_OWN_CONVARS: &[&str] = &[
+    "server.hostname",
+    "server.seed",
+    "server.worldsize",
+    "server.maxplayers",
+];
+
+/// The convars of a server.cfg a clone takes over: everything but ports, RCON settings
+/// and the ones it sets itself.
+pub fn clonable(content: &str) -> Vec<(String, String)> {
+    parse(content)
+        .into_iter()
+        .filter(|(key, _)| {
+            !key.starts_with("rcon.")
+                && !MANAGED_CONVARS.contains(&key.as_str())
+                && !CLONE_OWN_CONVARS.contains(&key.as_str())
+        })
+        .collect()
+}
+
+enum Kind {
+    Text(usize),
+    Bool,
+    Int(i64, i64),
+    Float(f64, f64),
+    WorldSize,
+    Seed,
+    Hostname,
+}
+
+/// Convars the editor accepts without `allowUnknown`, with the values each may take.
+const KNOWN_CONVARS: &[(&str, Kind)] = &[
+    ("server.hostname", Kind::Hostname),
+    ("server.description", Kind::Text(1024)),
+    ("server.url", Kind::Text(256)),
+    ("server.headerimage", Kind::Text(256)),
+    ("server.logoimage", Kind::Text(256)),
+    ("server.tags", Kind::Text(128)),
+    ("server.identity", Kind::Text(64)),
+    ("server.level", Kind::Text(64)),
+    ("server.levelurl", Kind::Text(256)),
+    ("server.maxplayers", Kind::Int(1, MAX_PLAYERS_LIMIT as i64)),
+    ("server.worldsize", Kind::WorldSize),
+    ("server.seed", Kind::Seed),
+    ("server.saveinterval", Kind::Int(60, 3600)),
+    ("server.tickrate", Kind::Int(10, 100)),
+    ("server.pve", Kind::Bool),
+    ("server.radiation", Kind::Bool),
+    ("server.secure", Kind::Bool),
+    ("server.globalchat", Kind::Bool),
+    ("server.stability", Kind::Bool),
+    ("server.events", Kind::Bool),
+    ("server.itemdespawn", Kind::Float(0.0, 3600.0)),
+    ("fps.limit", Kind::Int(10, 1000)),
+    ("decay.scale", Kind::Float(0.0, 100.0)),
+    ("craft.instant", Kind::Bool),
+    ("antihack.enabled", Kind::Bool),
+    ("ai.think", Kind::Bool),
+    ("env.time", Kind::Float(0.0, 24.0)),
+];
+
+/// Check `value` against the convar's kind, returning it as it should be written.
+fn validate_value(kind: &Kind, value: &str) -> Result<String, String> {
+    match kind {
+        Kind::Text(max) => {
+            if value.chars().count() > *max {
+                return Err(format!("Must be at most {} characters", max));
+            }
+            Ok(value.to_string())
+        }
+        Kind::Hostname => validation::sanitize_hostname(value),
+        Kind::Bool => match value.to_ascii_lowercase().as_str() {
+            "true" | "1" => Ok("true".to_string()),
+            "false" | "0" => Ok("false".to_string()),
+            _ => Err("Must be true or false".to_string()),
+        },
+        Kind::Int(min, max) => match value.parse::<i64>() {
+            Ok(n) if (*min..=*max).contains(&n) => Ok(n.to_string()),
+            _ => Err(format!("Must be a whole number from {} to {}", min, max)),
+        },
+        Kind::Float(min, max) => match value.parse::<f64>() {
+            Ok(n) if (*min..=*max).contains(&n) => Ok(value.to_string()),
+            _ => Err(format!("Must be a number from {} to {}", min, max)),
+        },
+        Kind::WorldSize => {
+            let size = value.parse::<u32>().map_err(|_| "Must be a whole number")?;
+            validation::validate_world_size(size).map(|_| size.to_string())
+        }
+        Kind::Seed => {
+            let seed = value.parse::<u32>().map_err(|_| "Must be a whole number")?;
+            validation::validate_seed(seed).map(|_| seed.to_string())
+        }
+    }
+}
+
+/// A server.cfg as convar name -> value.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCfg {
+    pub path: String,
+    pub values: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerCfgUpdate {
+    /// Convars to set; strings, numbers or booleans. Keys not listed are left alone.
+    #[schema(value_type = Object)]
+    pub values: BTreeMap<String, serde_json::Value>,
+    /// Accept convars the editor doesn't know, written without validation.
+    #[serde(default)]
+    pub allow_unknown: bool,
+}
+
+async fn cfg_path(registry: &ServerRegistry, server_id: &str) -> Result<String, ApiError> {
+    registry
+        .get_config(server_id)
+        .await
+        .map(|c| c.paths.server_cfg)
+        .ok_or_else(ApiError::server_not_found)
+}
+
+fn read_cfg(path: &str) -> Result<ServerCfg, ApiError> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => {
+            return Err(ApiError::internal(format!(
+                "Failed to read server.cfg: {}",
+                e
+            )))
+        }
+    };
+    Ok(ServerCfg {
+        path: path.to_string(),
+        values: parse(&content),
+    })
+}
+
+/// GET /api/servers/{server_id}/config/server-cfg
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/config/server-cfg",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Convars set in server.cfg; empty if the file doesn't exist", body = ServerCfg),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn get_server_cfg(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let path = cfg_path(&registry, &server_id).await?;
+    Ok(HttpResponse::Ok().json(read_cfg(&path)?))
+}
+
+/// PUT /api/servers/{server_id}/config/server-cfg — merge convars into server.cfg,
+/// keeping every other line and comment. The previous file is kept as server.cfg.bak.
+#[utoipa::path(
+    put,
+    path = "/api/servers/{server_id}/config/server-cfg",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = ServerCfgUpdate,
+    responses(
+        (status = 200, description = "The updated server.cfg", body = ServerCfg),
+        (status = 400, description = "Invalid or unknown convars, listed in `details.fields`", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn put_server_cfg(
+    req: HttpRequest,
+    server_id: web::Path<String>,
+    body: web::Json<ServerCfgUpdate>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let path = cfg_path(&registry, &server_id).await?;
+    if body.values.is_empty() {
+        return Err(ApiError::validation("No convars given"));
+    }
+
+    let mut errors = ValidationErrors::new();
+    let mut convars: Vec<(String, String)> = Vec::new();
+    for (key, value) in &body.values {
+        let name = key.trim().to_ascii_lowercase();
+        let value = match value {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Number(n) => n.to_string(),
+            serde_json::Value::Bool(b) => b.to_string(),
+            _ => {
+                errors.add(key, "Must be a string, number or boolean");
+                continue;
+            }
+        };
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || "._".contains(c))
+        {
+            errors.add(key, "Not a valid convar name");
+            continue;
+        }
+        if MANAGED_CONVARS.contains(&name.as_str()) {
+            errors.add(
+                key,
+                "Managed by the panel; change it in the server settings",
+            );
+            continue;
+        }
+        if value.chars().any(char::is_control) {
+            errors.add(key, "May not contain control characters or line breaks");
+            continue;
+        }
+        match KNOWN_CONVARS.iter().find(|(known, _)| *known == name) {
+            Some((_, kind)) => match validate_value(kind, &value) {
+                Ok(value) => convars.push((name, value)),
+                Err(e) => errors.add(key, e),
+            },
+            None if body.allow_unknown => convars.push((name, value)),
+            None => errors.add(key, "Unknown convar; set allowUnknown to write it anyway"),
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
+
+    if Path::new(&path).exists() {
+        if let Err(e) = std::fs::copy(&path, format!("{}.bak", path)) {
+            tracing::warn!("Failed to create backup: {}", e);
+        }
+    }
+    let values: Vec<(&str, String)> = convars
+        .iter()
+        .map(|(k, v)| (k.as_str(), v.clone()))
+        .collect();
+    set_convars(&path, &values)
+        .map_err(|e| ApiError::internal(format!("Failed to update server.cfg: {}", e)))?;
+
+    // Keep the definition in step with the settings it mirrors
+    let synced = registry
+        .update_definition(&server_id, |def| {
+            let mut changed = false;
+            for (key, value) in &convars {
+                changed |= match key.as_str() {
+                    "server.hostname" => {
+                        def.hostname = value.clone();
+                        true
+                    }
+                    "server.maxplayers" => value.parse().map(|n| def.max_players = n).is_ok(),
+                    "server.worldsize" => value.parse().map(|n| def.world_size = n).is_ok(),
+                    "server.seed" => value.parse().map(|n| def.seed = n).is_ok(),
+                    _ => false,
+                };
+            }
+            changed
+        })
+        .await;
+    if synced == Some(true) {
+        registry.persist().await;
+    }
+
+    let keys: Vec<&str> = convars.iter().map(|(k, _)| k.as_str()).collect();
+    audit::detail(&req, keys.join(", "));
+
+    Ok(HttpResponse::Ok().json(read_cfg(&path)?))
+}
@@ -1,19 +1,45 @@
-use actix_web::{web, HttpResponse};
+use actix_web::{web, HttpRequest, HttpResponse};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use utoipa::{IntoParams, ToSchema};
 
-use crate::config::AppConfig;
+use crate::apierror::ApiError;
+use crate::audit;
+use crate::auth::require_recent_auth;
+use crate::config::{AppConfig, ResourceLimits};
+use crate::consolehistory::ConsoleHistory;
+use crate::etag;
+use crate::events::PanelEvent;
+use crate::lgsm::{self, ActionRecord, LgsmHolder, LgsmLock};
+use crate::map::PositionStore;
+use crate::monitor::GameMonitor;
+use crate::pagination::{self, ListQuery, SortKey};
 use crate::provisioner;
+use crate::rcon::{quote_arg, RconClient};
 use crate::registry::{
-    ProvisioningStatus, ServerDefinition, ServerRegistry, ServerSource, ServerType,
+    CloneSource, DesiredState, Maintenance, ProvisioningStatus, ServerDefinition, ServerRegistry,
+    ServerRuntime, ServerSource, ServerType,
+};
+use crate::requestid;
+use crate::scheduler::Scheduler;
+use crate::servercfg;
+use crate::validation::{
+    self, sanitize_hostname, validate_settings, SettingsInput, ValidationErrors,
 };
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct ServerListEntry {
     id: String,
     name: String,
     online: bool,
+    /// online, offline, stopped (on purpose) or maintenance
+    state: String,
+    desired_state: DesiredState,
+    maintenance: Option<Maintenance>,
     server_type: String,
     game_port: u16,
     rcon_port: u16,
@@ -25,9 +51,104 @@ struct ServerListEntry {
     source: String,
     players: Option<u32>,
     created_at: String,
+    description: Option<String>,
+    tags: Vec<String>,
+    monitoring: String,
+    auto_start: bool,
+    rcon_connected: bool,
+    current_action: Option<LgsmHolder>,
+    provisioning_percent: Option<u8>,
+    last_snapshot_at: Option<String>,
+    fps: Option<f64>,
+    last_wipe: Option<String>,
+    /// Earliest next run of the server's enabled wipe jobs.
+    next_scheduled_wipe: Option<String>,
+}
+
+/// Runtime handles captured once per list request.
+struct RuntimeView {
+    game_monitor: Arc<GameMonitor>,
+    rcon: Arc<RconClient>,
+    lgsm_lock: Arc<LgsmLock>,
+}
+
+impl RuntimeView {
+    fn from_runtime(runtime: &ServerRuntime) -> Self {
+        Self {
+            game_monitor: runtime.game_monitor.clone(),
+            rcon: runtime.rcon.clone(),
+            lgsm_lock: runtime.lgsm_lock.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct ListServersQuery {
+    pub tag: Option<String>,
+    /// online, offline, stopped, maintenance, provisioning, pending or error. Offline
+    /// leaves out servers that are stopped on purpose or in maintenance.
+    pub status: Option<String>,
+    /// static or dynamic
+    pub source: Option<String>,
+}
+
+/// Sort keys of GET /api/servers.
+const SERVER_SORTS: &[SortKey<ServerListEntry>] = &[
+    ("name", |a, b| {
+        a.name.to_lowercase().cmp(&b.name.to_lowercase())
+    }),
+    ("players", |a, b| {
+        a.players.unwrap_or(0).cmp(&b.players.unwrap_or(0))
+    }),
+    ("created", |a, b| a.created_at.cmp(&b.created_at)),
+];
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct TagSummary {
+    tag: String,
+    servers: usize,
+    online: usize,
+    players: u32,
+    max_players: u32,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct BulkActionRequest {
+    /// LGSM action: start, stop or restart
+    pub action: String,
+    /// Apply to every server carrying this tag
+    pub tag: Option<String>,
+    /// Apply to these server ids
+    pub server_ids: Option<Vec<String>>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct BulkActionResult {
+    server_id: String,
+    success: bool,
+    output: String,
+}
+
+fn matches_status(entry: &ServerListEntry, status: &str) -> bool {
+    match status {
+        "online" => entry.online,
+        "offline" | "stopped" | "maintenance" => {
+            entry.state == status && entry.provisioning_status == "ready"
+        }
+        "provisioning" => !matches!(
+            entry.provisioning_status.as_str(),
+            "ready" | "error" | "pending_attach"
+        ),
+        "pending" => entry.provisioning_status == "pending_attach",
+        "error" => entry.provisioning_status == "error",
+        _ => true,
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CreateServerRequest {
     pub name: String,
@@ -36,14 +157,48 @@ pub struct CreateServerRequest {
     pub world_size: Option<u32>,
     pub seed: Option<u32>,
     pub hostname: Option<String>,
+    pub description: Option<String>,
+    pub auto_start: Option<bool>,
+    /// Ports to use instead of the next free slot; the others are still allocated.
+    pub game_port: Option<u16>,
+    pub rcon_port: Option<u16>,
+    pub query_port: Option<u16>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct CloneServerRequest {
+    pub name: String,
+    pub hostname: Option<String>,
+    /// Defaults to a new random seed.
+    pub seed: Option<u32>,
+    /// Defaults to the source server's.
+    pub description: Option<String>,
+    /// Also copy the source's server.cfg convars, except ports, RCON and world settings.
+    #[serde(default)]
+    pub copy_server_cfg: bool,
 }
 
-#[derive(Debug, Serialize)]
-struct ErrorBody {
-    error: String,
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdateServerRequest {
+    pub name: Option<String>,
+    pub hostname: Option<String>,
+    pub max_players: Option<u32>,
+    pub world_size: Option<u32>,
+    pub seed: Option<u32>,
+    pub description: Option<String>,
+    pub tags: Option<Vec<String>>,
+    pub auto_start: Option<bool>,
+    /// List on the public status page (a slug is generated from the name once).
+    pub public: Option<bool>,
+    /// CPU/memory caps, applied from the next start or restart. `{}` removes them.
+    pub resource_limits: Option<ResourceLimits>,
+    /// New RCON password; needs a recent login, like regenerating one.
+    pub rcon_password: Option<String>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 struct SuccessBody {
     success: bool,
     message: String,
@@ -56,7 +211,9 @@ fn status_to_string(status: &ProvisioningStatus) -> String {
         ProvisioningStatus::Downloading => "downloading",
         ProvisioningStatus::InstallingOxide => "installing_oxide",
         ProvisioningStatus::Configuring => "configuring",
+        ProvisioningStatus::CopyingFiles => "copying_files",
         ProvisioningStatus::Error => "error",
+        ProvisioningStatus::PendingAttach => "pending_attach",
     }
     .to_string()
 }
@@ -77,101 +234,392 @@ fn type_to_string(st: &ServerType) -> String {
     .to_string()
 }
 
+/// Build the public list entry for a definition from cached runtime state (no RCON calls).
+async fn build_entry(
+    def: &ServerDefinition,
+    runtime: Option<&RuntimeView>,
+    next_wipe: Option<chrono::DateTime<chrono::Utc>>,
+) -> ServerListEntry {
+    let snapshot = match runtime {
+        Some(rt) => rt.game_monitor.history.read().await.latest().cloned(),
+        None => None,
+    };
+    let rcon_connected = match runtime {
+        Some(rt) => rt.rcon.is_connected().await,
+        None => false,
+    };
+
+    let online = snapshot.as_ref().map(|s| s.online).unwrap_or(false);
+    ServerListEntry {
+        id: def.id.clone(),
+        name: def.name.clone(),
+        online,
+        state: def.state(online).to_string(),
+        desired_state: def.desired_state,
+        maintenance: def.active_maintenance().cloned(),
+        server_type: type_to_string(&def.server_type),
+        game_port: def.game_port,
+        rcon_port: def.rcon_port,
+        query_port: def.query_port,
+        max_players: snapshot
+            .as_ref()
+            .map(|s| s.max_players)
+            .filter(|&m| m > 0)
+            .unwrap_or(def.max_players),
+        world_size: def.world_size,
+        seed: def.seed,
+        provisioning_status: status_to_string(&def.provisioning_status),
+        source: source_to_string(&def.source),
+        players: snapshot.as_ref().map(|s| s.players),
+        created_at: def.created_at.to_rfc3339(),
+        description: def.description.clone(),
+        tags: def.tags.clone(),
+        monitoring: if def.monitoring_paused { "paused" } else { "active" }.to_string(),
+        auto_start: def.auto_start,
+        rcon_connected,
+        current_action: runtime.and_then(|rt| rt.lgsm_lock.current()),
+        provisioning_percent: def.provisioning_status.percent(),
+        last_snapshot_at: snapshot.as_ref().map(|s| s.timestamp.to_rfc3339()),
+        fps: snapshot.as_ref().filter(|s| s.online).map(|s| s.fps),
+        last_wipe: def.last_wipe_at.map(|t| t.to_rfc3339()),
+        next_scheduled_wipe: next_wipe.map(|t| t.to_rfc3339()),
+    }
+}
+
 /// GET /api/servers — list all servers with extended info.
-pub async fn list_servers(registry: web::Data<Arc<ServerRegistry>>) -> HttpResponse {
+#[utoipa::path(
+    get,
+    path = "/api/servers",
+    tag = "servers",
+    params(ListServersQuery, ListQuery),
+    responses(
+        (status = 200, description = "All servers, or a `Page` of them when paging; only the requested fields when `fields` is set", body = [ServerListEntry]),
+        (status = 304, description = "List unchanged since the If-None-Match ETag"),
+        (status = 422, description = "Unknown sort key or field, see `details.allowed`", body = ApiError),
+    )
+)]
+pub async fn list_servers(
+    req: HttpRequest,
+    query: web::Query<ListServersQuery>,
+    list: web::Query<ListQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> Result<HttpResponse, ApiError> {
     let defs = registry.all_definitions().await;
-    let mut entries = Vec::new();
+    let next_wipes = scheduler.next_wipes().await;
 
+    // Snapshot the runtimes once instead of taking the lock per server
+    let runtimes: HashMap<String, RuntimeView> = {
+        let runtimes = registry.runtimes.read().await;
+        runtimes
+            .iter()
+            .map(|(id, r)| (id.clone(), RuntimeView::from_runtime(r)))
+            .collect()
+    };
+
+    let tag = query.tag.as_deref().map(|t| t.to_lowercase());
+    let mut entries = Vec::with_capacity(defs.len());
     for def in &defs {
-        let (online, players, live_max_players) = if let Some(monitor) = registry.get_game_monitor(&def.id).await {
-            let history = monitor.history.read().await;
-            if let Some(snap) = history.latest() {
-                (snap.online, Some(snap.players), if snap.max_players > 0 { Some(snap.max_players) } else { None })
-            } else {
-                (false, None, None)
+        if let Some(ref tag) = tag {
+            if !def.tags.contains(tag) {
+                continue;
             }
-        } else {
-            (false, None, None)
-        };
+        }
+        let next_wipe = next_wipes.get(&def.id).copied();
+        let entry = build_entry(def, runtimes.get(&def.id), next_wipe).await;
+        if let Some(ref status) = query.status {
+            if !matches_status(&entry, status) {
+                continue;
+            }
+        }
+        if let Some(ref source) = query.source {
+            if entry.source != *source {
+                continue;
+            }
+        }
+        entries.push(entry);
+    }
+
+    // Entries carry live status, so there is no cheaper version key than the body itself
+    let body = pagination::apply(&list, entries, SERVER_SORTS)?;
+    Ok(etag::json(&req, &body))
+}
+
+/// GET /api/servers/summary — per-tag aggregate counts for the dashboard header.
+#[utoipa::path(
+    get,
+    path = "/api/servers/summary",
+    tag = "servers",
+    responses((status = 200, description = "Totals plus per-tag counts", body = Object))
+)]
+pub async fn servers_summary(registry: web::Data<Arc<ServerRegistry>>) -> HttpResponse {
+    let defs = registry.all_definitions().await;
+    let runtimes: HashMap<String, RuntimeView> = {
+        let runtimes = registry.runtimes.read().await;
+        runtimes
+            .iter()
+            .map(|(id, r)| (id.clone(), RuntimeView::from_runtime(r)))
+            .collect()
+    };
+
+    let mut summaries: Vec<TagSummary> = Vec::new();
+    let mut total = TagSummary {
+        tag: "all".to_string(),
+        servers: 0,
+        online: 0,
+        players: 0,
+        max_players: 0,
+    };
+
+    for def in &defs {
+        let entry = build_entry(def, runtimes.get(&def.id), None).await;
+        let players = entry.players.unwrap_or(0);
+        let mut buckets: Vec<&mut TagSummary> = Vec::new();
+        for tag in &def.tags {
+            if !summaries.iter().any(|s| &s.tag == tag) {
+                summaries.push(TagSummary {
+                    tag: tag.clone(),
+                    servers: 0,
+                    online: 0,
+                    players: 0,
+                    max_players: 0,
+                });
+            }
+        }
+        buckets.extend(summaries.iter_mut().filter(|s| def.tags.contains(&s.tag)));
+        buckets.push(&mut total);
+        for bucket in buckets {
+            bucket.servers += 1;
+            if entry.online {
+                bucket.online += 1;
+                bucket.players += players;
+                bucket.max_players += entry.max_players;
+            }
+        }
+    }
+
+    summaries.sort_by(|a, b| a.tag.cmp(&b.tag));
+    HttpResponse::Ok().json(serde_json::json!({
+        "total": total,
+        "tags": summaries,
+    }))
+}
+
+/// POST /api/servers/bulk — run an LGSM action on several servers selected by tag or id.
+#[utoipa::path(
+    post,
+    path = "/api/servers/bulk",
+    tag = "servers",
+    request_body = BulkActionRequest,
+    responses(
+        (status = 200, body = [BulkActionResult]),
+        (status = 400, body = ApiError),
+    )
+)]
+pub async fn bulk_action(
+    req: HttpRequest,
+    body: web::Json<BulkActionRequest>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    if !matches!(body.action.as_str(), "start" | "stop" | "restart") {
+        return Err(ApiError::validation(
+            "Invalid action. Use start, stop or restart",
+        ));
+    }
+
+    let targets: Vec<String> = {
+        let tag = body.tag.as_deref().map(|t| t.to_lowercase());
+        let defs = registry.definitions.read().await;
+        defs.values()
+            .filter(|d| d.provisioning_status == ProvisioningStatus::Ready)
+            .filter(|d| match (&tag, &body.server_ids) {
+                (Some(tag), _) => d.tags.contains(tag),
+                (None, Some(ids)) => ids.contains(&d.id),
+                (None, None) => false,
+            })
+            .map(|d| d.id.clone())
+            .collect()
+    };
 
-        entries.push(ServerListEntry {
-            id: def.id.clone(),
-            name: def.name.clone(),
-            online,
-            server_type: type_to_string(&def.server_type),
-            game_port: def.game_port,
-            rcon_port: def.rcon_port,
-            query_port: def.query_port,
-            max_players: live_max_players.unwrap_or(def.max_players),
-            world_size: def.world_size,
-            seed: def.seed,
-            provisioning_status: status_to_string(&def.provisioning_status),
-            source: source_to_string(&def.source),
-            players,
-            created_at: def.created_at.to_rfc3339(),
-        });
+    if targets.is_empty() {
+        return Err(ApiError::validation(
+            "No ready servers match the selector (provide tag or serverIds)",
+        ));
     }
 
-    HttpResponse::Ok().json(entries)
+    audit::detail(&req, format!("{} {}", body.action, targets.join(", ")));
+    let user = lgsm::initiator(&req);
+    let mut results = Vec::with_capacity(targets.len());
+    for server_id in targets {
+        let config = registry.get_config(&server_id).await;
+        let lock = registry.get_lgsm_lock(&server_id).await;
+        let result = match (config, lock) {
+            (Some(config), Some(lock)) => {
+                let _guard = lock.acquire(&body.action, user.as_deref()).await;
+                if let Some(state) = lgsm::desired_state_after(&body.action) {
+                    registry.set_desired_state(&server_id, state).await;
+                }
+                let started_at = chrono::Utc::now();
+                let outcome = lgsm::run_lgsm_command(&config, &body.action).await;
+                let record =
+                    ActionRecord::finished(&body.action, user.clone(), started_at, outcome.is_ok());
+                registry.record_action(&server_id, record);
+                match outcome {
+                    Ok(output) => BulkActionResult {
+                        server_id,
+                        success: true,
+                        output,
+                    },
+                    Err(e) => BulkActionResult {
+                        server_id,
+                        success: false,
+                        output: e.to_string(),
+                    },
+                }
+            }
+            _ => BulkActionResult {
+                server_id,
+                success: false,
+                output: "Server runtime not found".to_string(),
+            },
+        };
+        results.push(result);
+    }
+
+    Ok(HttpResponse::Ok().json(results))
+}
+
+/// Random 16-character lowercase alphanumeric RCON password.
+pub fn generate_rcon_password() -> String {
+    (0..16)
+        .map(|_| {
+            let idx = rand::random::<u8>() % 36;
+            if idx < 10 {
+                (b'0' + idx) as char
+            } else {
+                (b'a' + idx - 10) as char
+            }
+        })
+        .collect()
+}
+
+/// Unique id for a new dynamic server.
+fn new_server_id() -> String {
+    format!(
+        "srv-{}",
+        uuid::Uuid::new_v4().to_string().split('-').next().unwrap()
+    )
 }
 
 /// POST /api/servers — create a new server.
+#[utoipa::path(
+    post,
+    path = "/api/servers",
+    tag = "servers",
+    request_body = CreateServerRequest,
+    responses(
+        (status = 201, description = "Provisioning started", body = Object),
+        (status = 400, body = ApiError),
+        (status = 409, body = ApiError),
+    )
+)]
 pub async fn create_server(
+    req: HttpRequest,
     body: web::Json<CreateServerRequest>,
     registry: web::Data<Arc<ServerRegistry>>,
     config: web::Data<AppConfig>,
-) -> HttpResponse {
+) -> Result<HttpResponse, ApiError> {
     // Validate
     let defs = registry.all_definitions().await;
     if defs.len() >= config.provisioning.max_servers {
-        return HttpResponse::BadRequest().json(ErrorBody {
-            error: format!("Maximum of {} servers reached", config.provisioning.max_servers),
-        });
+        return Err(ApiError::validation(format!(
+            "Maximum of {} servers reached",
+            config.provisioning.max_servers
+        )));
     }
 
     let server_type = match body.server_type.to_lowercase().as_str() {
         "vanilla" => ServerType::Vanilla,
         "modded" => ServerType::Modded,
         _ => {
-            return HttpResponse::BadRequest().json(ErrorBody {
-                error: "Invalid server type. Use 'vanilla' or 'modded'".to_string(),
-            })
+            return Err(ApiError::validation(
+                "Invalid server type. Use 'vanilla' or 'modded'",
+            ))
         }
     };
 
-    // Generate unique ID
-    let id = format!(
-        "srv-{}",
-        uuid::Uuid::new_v4().to_string().split('-').next().unwrap()
-    );
+    let input = SettingsInput {
+        name: Some(&body.name),
+        hostname: body.hostname.as_deref(),
+        max_players: body.max_players,
+        world_size: body.world_size,
+        seed: body.seed,
+        tags: None,
+    };
+    let valid = validate_settings(&input, &defs, None)?;
+    let name = valid.name.unwrap_or_else(|| body.name.clone());
+
+    let requested = [
+        ("gamePort", body.game_port),
+        ("rconPort", body.rcon_port),
+        ("queryPort", body.query_port),
+    ];
+    let mut errors = ValidationErrors::new();
+    for (field, port) in requested {
+        if port.is_some_and(|p| p < 1024) {
+            errors.add(field, "Must be between 1024 and 65535");
+        }
+    }
+    if !errors.is_empty() {
+        return Err(errors.into());
+    }
 
-    // Allocate ports
-    let (game_port, rcon_port, query_port) =
-        provisioner::allocate_ports(&defs, &config.provisioning);
+    let id = new_server_id();
 
-    // Generate random RCON password
-    let rcon_password: String = (0..16)
-        .map(|_| {
-            let idx = rand::random::<u8>() % 36;
-            if idx < 10 {
-                (b'0' + idx) as char
-            } else {
-                (b'a' + idx - 10) as char
+    // Allocate ports, skipping any another server or process on the host holds. This
+    // reads the static servers' configs and binds sockets, so it runs off the workers.
+    let ports = (body.game_port, body.rcon_port, body.query_port);
+    let (static_registry, provisioning) = (registry.get_ref().clone(), config.provisioning.clone());
+    let (game_port, rcon_port, query_port) = tokio::task::spawn_blocking(move || {
+        let in_use = provisioner::ports_in_use(&defs, &static_registry.static_configs);
+        let chosen = match ports {
+            (Some(game), Some(rcon), Some(query)) => (game, rcon, query),
+            (game, rcon, query) => {
+                let allocated = provisioner::allocate_ports(&defs, &in_use, &provisioning)
+                    .ok_or_else(|| {
+                        ApiError::conflict("No free ports left in the provisioning range")
+                    })?;
+                (
+                    game.unwrap_or(allocated.0),
+                    rcon.unwrap_or(allocated.1),
+                    query.unwrap_or(allocated.2),
+                )
             }
-        })
-        .collect();
+        };
+        if ports != (None, None, None) {
+            let conflicts = provisioner::port_conflicts(chosen, &in_use);
+            if !conflicts.is_empty() {
+                return Err(ApiError::conflict(conflicts.join("; ")));
+            }
+        }
+        Ok(chosen)
+    })
+    .await
+    .map_err(|e| ApiError::internal(format!("Checking ports failed: {}", e)))??;
+
+    let rcon_password = generate_rcon_password();
 
     let seed = body.seed.unwrap_or_else(|| rand::random::<u32>() % 999999 + 1);
     let world_size = body.world_size.unwrap_or(4000);
     let max_players = body.max_players.unwrap_or(100);
-    let hostname = body
-        .hostname
-        .clone()
-        .unwrap_or_else(|| body.name.clone());
+    let hostname = match valid.hostname {
+        Some(h) => h,
+        None => sanitize_hostname(&name).unwrap_or_else(|_| name.clone()),
+    };
 
     let def = ServerDefinition {
         id: id.clone(),
-        name: body.name.clone(),
+        name: name.clone(),
         server_type,
         source: ServerSource::Dynamic,
         provisioning_status: ProvisioningStatus::Installing,
@@ -186,72 +634,652 @@ pub async fn create_server(
         rcon_password,
         base_path: config.provisioning.base_path.clone(),
         created_at: chrono::Utc::now(),
+        description: body.description.clone(),
+        tags: Vec::new(),
+        monitoring_paused: false,
+        auto_start: body.auto_start.unwrap_or(false),
+        public_slug: None,
+        last_wipe_at: None,
+        resource_limits: Default::default(),
+        desired_state: Default::default(),
+        maintenance: None,
+        clone_of: None,
     };
 
     // Add to registry
-    {
-        let mut defs = registry.definitions.write().await;
-        defs.push(def.clone());
+    audit::detail_for(&req, &id, name.clone());
+    if let Err(e) = registry.insert_definition(def.clone()).await {
+        return Err(ApiError::conflict(e));
     }
 
     // Save dynamic servers to disk
-    {
-        let defs = registry.definitions.read().await;
-        let dynamic: Vec<_> = defs
-            .iter()
-            .filter(|d| d.source == ServerSource::Dynamic)
-            .cloned()
-            .collect();
-        if let Err(e) = crate::persistence::save_servers(&dynamic) {
-            tracing::error!("Failed to save servers: {}", e);
+    registry.persist().await;
+
+    // Spawn provisioning task
+    provisioner::spawn_provisioning(
+        def,
+        registry.into_inner().as_ref().clone(),
+        config.into_inner().as_ref().clone(),
+    );
+
+    Ok(HttpResponse::Created().json(serde_json::json!({
+        "id": id,
+        "name": name,
+        "status": "installing",
+    })))
+}
+
+/// POST /api/servers/{server_id}/clone — create a new server like this one.
+/// The clone gets its own ports, RCON password and (unless given) seed, is provisioned
+/// like a new server and then gets the source's Oxide plugins and configs copied in.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/clone",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server to clone")),
+    request_body = CloneServerRequest,
+    responses(
+        (status = 201, description = "Provisioning of the clone started", body = Object),
+        (status = 400, body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "The server isn't ready or no ports are free", body = ApiError),
+    )
+)]
+pub async fn clone_server(
+    req: HttpRequest,
+    server_id: web::Path<String>,
+    body: web::Json<CloneServerRequest>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let source = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    match source.provisioning_status {
+        ProvisioningStatus::Ready => {}
+        ProvisioningStatus::Error => {
+            return Err(ApiError::conflict(
+                "Server failed to provision; retry or delete it instead",
+            ))
         }
+        ProvisioningStatus::PendingAttach => {
+            return Err(ApiError::conflict("Server is waiting to be attached"))
+        }
+        _ => return Err(ApiError::conflict("Server is still provisioning")),
     }
+    let source_config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
-    // Spawn provisioning task
-    let registry_clone = registry.into_inner().as_ref().clone();
-    let config_clone = config.into_inner().as_ref().clone();
-    let def_clone = def.clone();
-    tokio::spawn(async move {
-        provisioner::provision_server(def_clone, registry_clone, config_clone).await;
-    });
+    let defs = registry.all_definitions().await;
+    if defs.len() >= config.provisioning.max_servers {
+        return Err(ApiError::validation(format!(
+            "Maximum of {} servers reached",
+            config.provisioning.max_servers
+        )));
+    }
+
+    let input = SettingsInput {
+        name: Some(&body.name),
+        hostname: body.hostname.as_deref(),
+        max_players: None,
+        world_size: None,
+        seed: body.seed,
+        tags: None,
+    };
+    let valid = validate_settings(&input, &defs, None)?;
+    let name = valid.name.unwrap_or_else(|| body.name.clone());
+
+    // Static servers only know their world settings from server.cfg
+    let cfg = std::fs::read_to_string(&source_config.paths.server_cfg)
+        .map(|content| servercfg::parse(&content))
+        .unwrap_or_default();
+    let cfg_number = |key: &str| cfg.get(key).and_then(|v| v.parse::<u32>().ok());
+    let world_size = cfg_number("server.worldsize").unwrap_or(source.world_size);
+    let max_players = cfg_number("server.maxplayers").unwrap_or(source.max_players);
+    let server_type = if Path::new(&source_config.paths.oxide_plugins).is_dir() {
+        ServerType::Modded
+    } else {
+        source.server_type.clone()
+    };
+
+    let (static_registry, provisioning) = (registry.get_ref().clone(), config.provisioning.clone());
+    let (game_port, rcon_port, query_port) = tokio::task::spawn_blocking(move || {
+        let in_use = provisioner::ports_in_use(&defs, &static_registry.static_configs);
+        provisioner::allocate_ports(&defs, &in_use, &provisioning)
+    })
+    .await
+    .map_err(|e| ApiError::internal(format!("Checking ports failed: {}", e)))?
+    .ok_or_else(|| ApiError::conflict("No free ports left in the provisioning range"))?;
+
+    let id = new_server_id();
+    let hostname = match valid.hostname {
+        Some(h) => h,
+        None => sanitize_hostname(&name).unwrap_or_else(|_| name.clone()),
+    };
+
+    let def = ServerDefinition {
+        id: id.clone(),
+        name: name.clone(),
+        server_type,
+        source: ServerSource::Dynamic,
+        provisioning_status: ProvisioningStatus::Installing,
+        provisioning_log: vec![format!("Cloning '{}'", source.name)],
+        game_port,
+        rcon_port,
+        query_port,
+        max_players,
+        world_size,
+        seed: body
+            .seed
+            .unwrap_or_else(|| rand::random::<u32>() % 999999 + 1),
+        hostname,
+        rcon_password: generate_rcon_password(),
+        base_path: config.provisioning.base_path.clone(),
+        created_at: chrono::Utc::now(),
+        description: body.description.clone().or(source.description),
+        tags: source.tags,
+        monitoring_paused: false,
+        auto_start: false,
+        public_slug: None,
+        last_wipe_at: None,
+        resource_limits: source.resource_limits,
+        desired_state: Default::default(),
+        maintenance: None,
+        clone_of: Some(CloneSource {
+            server_id: source.id,
+            copy_server_cfg: body.copy_server_cfg,
+        }),
+    };
+
+    audit::detail(&req, format!("Cloned to {} ({})", id, name));
+    if let Err(e) = registry.insert_definition(def.clone()).await {
+        return Err(ApiError::conflict(e));
+    }
+    registry.persist().await;
+
+    provisioner::spawn_provisioning(
+        def,
+        registry.into_inner().as_ref().clone(),
+        config.into_inner().as_ref().clone(),
+    );
 
-    HttpResponse::Created().json(serde_json::json!({
+    Ok(HttpResponse::Created().json(serde_json::json!({
         "id": id,
-        "name": body.name,
+        "name": name,
         "status": "installing",
-    }))
+    })))
 }
 
-/// DELETE /api/servers/{server_id} — remove a dynamic server.
-pub async fn delete_server(
+/// PUT /api/servers/{server_id} — partially update mutable server settings.
+/// Static servers only accept a new display name, tags and auto-start; everything else lives
+/// in config.yaml.
+#[utoipa::path(
+    put,
+    path = "/api/servers/{server_id}",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = UpdateServerRequest,
+    responses(
+        (status = 200, description = "Updated definition", body = Object),
+        (status = 400, body = ApiError),
+        (status = 403, description = "Changing the RCON password needs a recent login", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn update_server(
+    req: HttpRequest,
     server_id: web::Path<String>,
+    body: web::Json<UpdateServerRequest>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
+    scheduler: web::Data<Arc<Scheduler>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
     let server_id = server_id.into_inner();
 
-    // Check if server exists and is dynamic
-    let def = match registry.get_definition(&server_id).await {
-        Some(d) => d,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+
+    let touches_game_settings = body.hostname.is_some()
+        || body.max_players.is_some()
+        || body.world_size.is_some()
+        || body.seed.is_some()
+        || body.description.is_some()
+        || body.rcon_password.is_some();
+    if def.source == ServerSource::Static && touches_game_settings {
+        return Err(ApiError::validation(
+            "Static servers (defined in config.yaml) only support changing the name, tags and autoStart",
+        ));
+    }
+
+    if def.source == ServerSource::Static && body.public.is_some() {
+        return Err(ApiError::validation(
+            "Static servers are listed publicly with public_slug in config.yaml",
+        ));
+    }
+
+    if let Some(ref limits) = body.resource_limits {
+        if def.source == ServerSource::Static {
+            return Err(ApiError::validation(
+                "Static servers set resource_limits in config.yaml",
+            ));
+        }
+        if let Err(e) = limits.validate() {
+            return Err(ApiError::validation(e));
+        }
+    }
+
+    let defs = registry.all_definitions().await;
+    let input = SettingsInput {
+        name: body.name.as_deref(),
+        hostname: body.hostname.as_deref(),
+        max_players: body.max_players,
+        world_size: body.world_size,
+        seed: body.seed,
+        tags: body.tags.as_deref(),
+    };
+    let valid = validate_settings(&input, &defs, Some(&server_id))?;
+    if let Some(ref password) = body.rcon_password {
+        require_recent_auth(&req, config.auth.reauth_window_mins)?;
+        if let Err(e) = validation::validate_rcon_password(password) {
+            let mut errors = ValidationErrors::new();
+            errors.add("rconPassword", e);
+            return Err(errors.into());
+        }
+    }
+
+    let valid_hostname = valid.hostname.is_some();
+    let taken_slugs: Vec<&str> = defs
+        .iter()
+        .filter(|d| d.id != server_id)
+        .filter_map(|d| d.public_slug.as_deref())
+        .collect();
+
+    // Apply to the definition and collect the convars that need to change
+    let mut convars: Vec<(&str, String)> = Vec::new();
+    let mut warnings: Vec<String> = Vec::new();
+    let updated = {
+        let mut defs = registry.definitions.write().await;
+        let def = defs
+            .get_mut(&server_id)
+            .ok_or_else(ApiError::server_not_found)?;
+        if let Some(name) = valid.name {
+            def.name = name;
+        }
+        if let Some(ref hostname) = valid.hostname {
+            def.hostname = hostname.clone();
+            convars.push(("server.hostname", hostname.clone()));
+        }
+        if let Some(max_players) = body.max_players {
+            def.max_players = max_players;
+            convars.push(("server.maxplayers", max_players.to_string()));
+        }
+        if let Some(world_size) = body.world_size {
+            if world_size != def.world_size {
+                warnings.push("worldSize takes effect after the next map wipe".to_string());
+            }
+            def.world_size = world_size;
+            convars.push(("server.worldsize", world_size.to_string()));
+        }
+        if let Some(seed) = body.seed {
+            if seed != def.seed {
+                warnings.push("seed takes effect after the next map wipe".to_string());
+            }
+            def.seed = seed;
+            convars.push(("server.seed", seed.to_string()));
+        }
+        if let Some(ref description) = body.description {
+            def.description = if description.is_empty() {
+                None
+            } else {
+                Some(description.clone())
+            };
+        }
+        if let Some(tags) = valid.tags {
+            def.tags = tags;
         }
+        if let Some(auto_start) = body.auto_start {
+            def.auto_start = auto_start;
+        }
+        match body.public {
+            Some(false) => def.public_slug = None,
+            Some(true) if def.public_slug.is_none() => {
+                def.public_slug = Some(crate::publicstatus::unique_slug(&def.name, &taken_slugs));
+            }
+            _ => {}
+        }
+        if let Some(ref limits) = body.resource_limits {
+            if *limits != def.resource_limits {
+                warnings
+                    .push("Resource limits take effect on the next start or restart".to_string());
+            }
+            def.resource_limits = limits.clone();
+        }
+        if let Some(ref password) = body.rcon_password {
+            def.rcon_password = password.clone();
+            convars.push(("rcon.password", password.clone()));
+        }
+        def.clone()
     };
 
+    registry.persist().await;
+
+    // Patch server.cfg so the change survives the next restart
+    if !convars.is_empty() {
+        if let Some(config) = registry.get_config(&server_id).await {
+            if let Err(e) = crate::servercfg::set_convars(&config.paths.server_cfg, &convars) {
+                tracing::warn!("Failed to update server.cfg for '{}': {}", server_id, e);
+                warnings.push(format!("Failed to update server.cfg: {}", e));
+            }
+        }
+    }
+
+    // hostname and maxplayers can also be applied live
+    let mut restart_required = body.world_size.is_some() || body.seed.is_some();
+    if body.hostname.is_some() || body.max_players.is_some() {
+        let mut applied_live = false;
+        if let Some(rcon) = registry.get_rcon(&server_id).await {
+            applied_live = true;
+            if valid_hostname {
+                applied_live &= rcon
+                    .execute(&format!("server.hostname {}", quote_arg(&updated.hostname)))
+                    .await
+                    .is_ok();
+            }
+            if let Some(max_players) = body.max_players {
+                applied_live &= rcon
+                    .execute(&format!("server.maxplayers {}", max_players))
+                    .await
+                    .is_ok();
+            }
+        }
+        if !applied_live {
+            restart_required = true;
+        }
+    }
+
+    // Like regenerate_rcon_password: the server switches first, then the panel's client
+    if let Some(ref password) = body.rcon_password {
+        if let Some(rcon) = registry.get_rcon(&server_id).await {
+            let applied_live = rcon
+                .execute(&format!("rcon.password {}", quote_arg(password)))
+                .await
+                .is_ok();
+            if !applied_live {
+                warnings.push(
+                    "Server is offline; the new RCON password applies on next start".to_string(),
+                );
+            }
+            rcon.set_password(password);
+        }
+    }
+
+    let runtime = registry
+        .runtimes
+        .read()
+        .await
+        .get(&server_id)
+        .map(RuntimeView::from_runtime);
+    let next_wipe = scheduler.next_wipes().await.remove(&server_id);
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "server": build_entry(&updated, runtime.as_ref(), next_wipe).await,
+        "restartRequired": restart_required,
+        "warnings": warnings,
+    })))
+}
+
+/// How long LGSM gets to stop a server before it is killed.
+const DELETE_STOP_TIMEOUT: Duration = Duration::from_secs(90);
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct DeleteServerQuery {
+    /// Skip the stop step (for broken installs where LGSM hangs or is missing).
+    #[serde(default)]
+    pub force: bool,
+    /// Also remove the server's directory from disk, in the background (see
+    /// GET /api/servers/{server_id}/delete-status). Files of a server that never finished
+    /// provisioning are always removed.
+    #[serde(default, rename = "deleteFiles")]
+    pub delete_files: bool,
+}
+
+/// How long the outcome of a file removal stays queryable after it finished.
+const REMOVAL_STATUS_TTL: chrono::Duration = chrono::Duration::minutes(10);
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum RemovalState {
+    Running,
+    Succeeded,
+    Failed,
+}
+
+/// Progress of removing a deleted server's files.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RemovalStatus {
+    pub path: String,
+    pub state: RemovalState,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub error: Option<String>,
+}
+
+/// File removals of deleted servers, kept for a few minutes after they finish. In
+/// memory: a restart forgets them.
+pub struct FileRemovals {
+    status: Mutex<HashMap<String, RemovalStatus>>,
+}
+
+impl FileRemovals {
+    pub fn new() -> Self {
+        Self {
+            status: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn get(&self, server_id: &str) -> Option<RemovalStatus> {
+        let mut status = self.status.lock().unwrap();
+        let cutoff = chrono::Utc::now() - REMOVAL_STATUS_TTL;
+        status.retain(|_, s| s.finished_at.is_none_or(|at| at > cutoff));
+        status.get(server_id).cloned()
+    }
+
+    /// Remove `dir` in the background, tracking it under `server_id`. The outcome also
+    /// goes into the server's action history, which outlives the status kept here: a
+    /// server created again under the id shows why files may have been left behind.
+    fn spawn(
+        self: Arc<Self>,
+        server_id: String,
+        dir: PathBuf,
+        registry: Arc<ServerRegistry>,
+        initiated_by: Option<String>,
+    ) {
+        let started_at = chrono::Utc::now();
+        let status = RemovalStatus {
+            path: dir.display().to_string(),
+            state: RemovalState::Running,
+            started_at,
+            finished_at: None,
+            error: None,
+        };
+        self.status
+            .lock()
+            .unwrap()
+            .insert(server_id.clone(), status);
+
+        tokio::spawn(requestid::propagate(async move {
+            tracing::info!("Removing server files at '{}'", dir.display());
+            let result = tokio::fs::remove_dir_all(&dir).await;
+            if let Err(ref e) = result {
+                tracing::error!(
+                    "Failed to remove server files at '{}': {}",
+                    dir.display(),
+                    e
+                );
+            }
+            registry.record_action(
+                &server_id,
+                ActionRecord::finished("remove files", initiated_by, started_at, result.is_ok()),
+            );
+            if let Some(status) = self.status.lock().unwrap().get_mut(&server_id) {
+                status.state = if result.is_ok() {
+                    RemovalState::Succeeded
+                } else {
+                    RemovalState::Failed
+                };
+                status.error = result.err().map(|e| e.to_string());
+                status.finished_at = Some(chrono::Utc::now());
+            }
+        }));
+    }
+}
+
+/// Per-server state held outside the registry, cleared when a server is deleted.
+pub struct ServerCleanup {
+    console_history: Arc<ConsoleHistory>,
+    positions: Arc<PositionStore>,
+    removals: Arc<FileRemovals>,
+}
+
+impl ServerCleanup {
+    pub fn new(
+        console_history: Arc<ConsoleHistory>,
+        positions: Arc<PositionStore>,
+        removals: Arc<FileRemovals>,
+    ) -> Self {
+        Self {
+            console_history,
+            positions,
+            removals,
+        }
+    }
+
+    async fn forget(&self, server_id: &str) {
+        self.console_history.forget(server_id);
+        self.positions.forget(server_id).await;
+    }
+}
+
+/// `base_dir` with symlinks and `..` resolved, if it really lies inside `base_path`.
+fn removable_dir(base_dir: &str, base_path: &str) -> Result<PathBuf, String> {
+    let dir = std::fs::canonicalize(base_dir)
+        .map_err(|e| format!("Cannot resolve '{}': {}", base_dir, e))?;
+    let root = std::fs::canonicalize(base_path).map_err(|e| {
+        format!(
+            "Cannot resolve provisioning.base_path '{}': {}",
+            base_path, e
+        )
+    })?;
+    if dir == root || !dir.starts_with(&root) {
+        return Err(format!(
+            "'{}' is not inside provisioning.base_path '{}'",
+            dir.display(),
+            root.display()
+        ));
+    }
+    Ok(dir)
+}
+
+/// DELETE /api/servers/{server_id} — stop and remove a dynamic server.
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{server_id}",
+    tag = "servers",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        DeleteServerQuery,
+    ),
+    responses(
+        (status = 200, body = Object),
+        (status = 400, body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "The server's files are outside provisioning.base_path", body = ApiError),
+    )
+)]
+pub async fn delete_server(
+    req: HttpRequest,
+    server_id: web::Path<String>,
+    query: web::Query<DeleteServerQuery>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    app_config: web::Data<AppConfig>,
+    cleanup: web::Data<Arc<ServerCleanup>>,
+) -> Result<HttpResponse, ApiError> {
+    let server_id = server_id.into_inner();
+
+    // Check if server exists and is dynamic
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+
+    audit::detail(&req, def.name.clone());
     if def.source == ServerSource::Static {
-        return HttpResponse::BadRequest().json(ErrorBody {
-            error: "Cannot delete a static server (defined in config.yaml)".to_string(),
-        });
+        return Err(ApiError::validation(
+            "Cannot delete a static server (defined in config.yaml)",
+        ));
     }
 
-    // Stop the game server via LGSM before cleanup
-    let base_dir = format!("{}/rustserver-{}", def.base_path, def.id);
-    let stop_cmd = format!("cd '{}' && ./rustserver stop 2>/dev/null || true", base_dir);
-    let _ = tokio::process::Command::new("su")
-        .args(["-", "gameserver", "-c", &stop_cmd])
-        .output()
-        .await;
+    let config = def.to_game_server_config();
+    let base_dir = config.paths.base_dir.clone();
+
+    // Checked before anything is stopped, so a refused removal leaves the server intact
+    let unfinished = def.provisioning_status.in_progress()
+        || def.provisioning_status == ProvisioningStatus::Error;
+    let remove_dir = if (query.delete_files || unfinished) && Path::new(&base_dir).exists() {
+        let dir = removable_dir(&base_dir, &app_config.provisioning.base_path)
+            .map_err(ApiError::conflict)?;
+        Some(dir)
+    } else {
+        None
+    };
+
+    // An install still running would keep writing under base_dir
+    registry.abort_provisioning(&server_id).await;
+
+    // Stop the game server under its LGSM lock so no other action races the delete.
+    // "clean": LGSM stopped it; "killed": it had to be SIGKILLed; "skipped": force=true or
+    // it never finished provisioning; "unconfirmed": LGSM failed and no running process
+    // was found.
+    let stop_result = if query.force || def.provisioning_status != ProvisioningStatus::Ready {
+        "skipped"
+    } else {
+        let lock = registry.get_lgsm_lock(&server_id).await;
+        let _guard = match lock {
+            Some(ref l) => Some(l.acquire("delete", lgsm::initiator(&req).as_deref()).await),
+            None => None,
+        };
+        let stopped =
+            match tokio::time::timeout(DELETE_STOP_TIMEOUT, lgsm::stop_as_game_user(&config)).await
+            {
+                Ok(Ok(_)) => true,
+                Ok(Err(e)) => {
+                    tracing::warn!("LGSM stop failed for '{}': {}", server_id, e);
+                    false
+                }
+                Err(_) => {
+                    tracing::warn!(
+                        "LGSM stop for '{}' timed out after {}s",
+                        server_id,
+                        DELETE_STOP_TIMEOUT.as_secs()
+                    );
+                    false
+                }
+            };
+        let killed = lgsm::kill_server_processes(&base_dir);
+        if killed > 0 {
+            "killed"
+        } else if stopped {
+            "clean"
+        } else {
+            // LGSM failed but no process was found either
+            "unconfirmed"
+        }
+    };
 
     // Remove runtime (stop collector)
     {
@@ -264,54 +1292,615 @@ pub async fn delete_server(
     }
 
     // Remove definition
-    {
-        let mut defs = registry.definitions.write().await;
-        defs.retain(|d| d.id != server_id);
-    }
+    registry.remove_definition(&server_id).await;
+    cleanup.forget(&server_id).await;
 
     // Save updated dynamic servers
-    {
-        let defs = registry.definitions.read().await;
-        let dynamic: Vec<_> = defs
-            .iter()
-            .filter(|d| d.source == ServerSource::Dynamic)
-            .cloned()
-            .collect();
-        if let Err(e) = crate::persistence::save_servers(&dynamic) {
-            tracing::error!("Failed to save servers: {}", e);
+    registry.persist().await;
+
+    // A 10 GB install takes a while to remove; report it through /delete-status
+    let (files, message) = match remove_dir {
+        Some(dir) => {
+            cleanup.removals.clone().spawn(
+                server_id.clone(),
+                dir,
+                registry.get_ref().clone(),
+                lgsm::initiator(&req),
+            );
+            (
+                "removing",
+                format!("Server '{}' deleted; removing its files", server_id),
+            )
         }
+        None => ("kept", format!("Server '{}' deleted", server_id)),
+    };
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "message": message,
+        "stop": stop_result,
+        "files": files,
+    })))
+}
+
+/// GET /api/servers/{server_id}/delete-status
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/delete-status",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Id of the deleted server")),
+    responses(
+        (status = 200, description = "Progress of removing the deleted server's files", body = RemovalStatus),
+        (status = 404, description = "No file removal for this server in the last few minutes", body = ApiError),
+    )
+)]
+pub async fn delete_status(
+    server_id: web::Path<String>,
+    removals: web::Data<Arc<FileRemovals>>,
+) -> Result<HttpResponse, ApiError> {
+    let status = removals
+        .get(&server_id)
+        .ok_or_else(|| ApiError::not_found("No recent file removal for this server"))?;
+    Ok(HttpResponse::Ok().json(status))
+}
+
+/// POST /api/servers/{server_id}/monitoring/pause — stop polling a server.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/monitoring/pause",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn pause_monitoring(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    if !registry.pause_monitoring(&server_id).await {
+        return Err(ApiError::server_not_found());
     }
+    tracing::info!("Monitoring paused for '{}'", server_id);
 
-    // Remove server files from disk
-    if std::path::Path::new(&base_dir).exists() {
-        tracing::info!("Removing server files at '{}'", base_dir);
-        if let Err(e) = tokio::fs::remove_dir_all(&base_dir).await {
-            tracing::error!("Failed to remove server files at '{}': {}", base_dir, e);
-        }
+    Ok(HttpResponse::Ok().json(SuccessBody {
+        success: true,
+        message: format!("Monitoring paused for '{}'", server_id),
+    }))
+}
+
+/// POST /api/servers/{server_id}/monitoring/resume — restart polling a server.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/monitoring/resume",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn resume_monitoring(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    if !registry.resume_monitoring(&server_id, &config.monitor).await {
+        return Err(ApiError::server_not_found());
     }
+    tracing::info!("Monitoring resumed for '{}'", server_id);
 
-    HttpResponse::Ok().json(SuccessBody {
+    Ok(HttpResponse::Ok().json(SuccessBody {
         success: true,
-        message: format!("Server '{}' deleted and files removed", server_id),
-    })
+        message: format!("Monitoring resumed for '{}'", server_id),
+    }))
+}
+
+/// Longest maintenance note accepted, in characters.
+const MAX_MAINTENANCE_NOTE: usize = 500;
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceRequest {
+    /// Shown on the public status page.
+    pub note: Option<String>,
+    /// Ends on its own at this time; until cleared when unset.
+    pub until: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// PUT /api/servers/{server_id}/maintenance — put a server in maintenance: no offline
+/// alerts, no auto-start or restarting jobs, and "maintenance" on the public status page.
+#[utoipa::path(
+    put,
+    path = "/api/servers/{server_id}/maintenance",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = MaintenanceRequest,
+    responses(
+        (status = 200, body = Maintenance),
+        (status = 400, description = "Note too long or end time in the past", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn set_maintenance(
+    req: HttpRequest,
+    server_id: web::Path<String>,
+    body: web::Json<MaintenanceRequest>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let body = body.into_inner();
+    let note = body
+        .note
+        .map(|n| n.trim().to_string())
+        .filter(|n| !n.is_empty());
+    if note
+        .as_ref()
+        .is_some_and(|n| n.chars().count() > MAX_MAINTENANCE_NOTE)
+    {
+        return Err(ApiError::validation(format!(
+            "Note is longer than {} characters",
+            MAX_MAINTENANCE_NOTE
+        )));
+    }
+    let now = chrono::Utc::now();
+    if body.until.is_some_and(|until| until <= now) {
+        return Err(ApiError::validation("until must be in the future"));
+    }
+
+    let maintenance = Maintenance {
+        note,
+        started_at: now,
+        until: body.until,
+        set_by: lgsm::initiator(&req),
+    };
+    if !registry
+        .set_maintenance(&server_id, Some(maintenance.clone()))
+        .await
+    {
+        return Err(ApiError::server_not_found());
+    }
+    let until = maintenance
+        .until
+        .map(|until| format!(" until {}", until.to_rfc3339()));
+    audit::detail(
+        &req,
+        format!(
+            "{}{}",
+            maintenance.note.as_deref().unwrap_or("no note"),
+            until.unwrap_or_default()
+        ),
+    );
+
+    Ok(HttpResponse::Ok().json(maintenance))
+}
+
+/// DELETE /api/servers/{server_id}/maintenance — end a server's maintenance.
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{server_id}/maintenance",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn clear_maintenance(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    if !registry.set_maintenance(&server_id, None).await {
+        return Err(ApiError::server_not_found());
+    }
+    Ok(HttpResponse::Ok().json(SuccessBody {
+        success: true,
+        message: format!("Maintenance ended for '{}'", server_id),
+    }))
 }
 
 /// GET /api/servers/{server_id}/provision-status
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/provision-status",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Status, percent and provisioning log", body = Object),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
 pub async fn provision_status(
     server_id: web::Path<String>,
     registry: web::Data<Arc<ServerRegistry>>,
-) -> HttpResponse {
-    let def = match registry.get_definition(&server_id).await {
-        Some(d) => d,
-        None => {
-            return HttpResponse::NotFound().json(ErrorBody {
-                error: "Server not found".to_string(),
-            })
-        }
-    };
+) -> Result<HttpResponse, ApiError> {
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
 
-    HttpResponse::Ok().json(serde_json::json!({
+    Ok(HttpResponse::Ok().json(serde_json::json!({
         "status": status_to_string(&def.provisioning_status),
         "log": def.provisioning_log,
+    })))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct ConnectionInfo {
+    server_id: String,
+    host: String,
+    game_address: String,
+    game_port: u16,
+    query_port: u16,
+    rcon_port: u16,
+    rcon_password: String,
+    /// F1 console command for the game client.
+    connect_command: String,
+    steam_uri: String,
+}
+
+/// Public address players should use: `provisioning.public_host`, else the request host.
+fn public_host(req: &HttpRequest, config: &AppConfig) -> String {
+    if let Some(ref host) = config.provisioning.public_host {
+        return host.clone();
+    }
+    // Strip the panel's own port; leave bare IPv6 literals intact
+    let host = req.connection_info().host().to_string();
+    match host.rsplit_once(':') {
+        Some((h, port)) if port.parse::<u16>().is_ok() && (!h.contains(':') || h.ends_with(']')) => {
+            h.to_string()
+        }
+        _ => host,
+    }
+}
+
+/// GET /api/servers/{server_id}/connection-info — reveal addresses and the RCON password.
+/// Requires a recently issued token.
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/connection-info",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = ConnectionInfo),
+        (status = 403, description = "Token too old; log in again", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn connection_info(
+    req: HttpRequest,
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = require_recent_auth(&req, config.auth.reauth_window_mins)?;
+    let server_id = server_id.into_inner();
+
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+
+    tracing::info!(
+        "Connection info for server '{}' revealed to '{}'",
+        server_id,
+        claims.sub
+    );
+
+    let host = public_host(&req, &config);
+    let game_address = format!("{}:{}", host, def.game_port);
+    Ok(HttpResponse::Ok().json(ConnectionInfo {
+        server_id: def.id,
+        connect_command: format!("client.connect {}", game_address),
+        steam_uri: format!("steam://connect/{}", game_address),
+        host,
+        game_address,
+        game_port: def.game_port,
+        query_port: def.query_port,
+        rcon_port: def.rcon_port,
+        rcon_password: def.rcon_password,
     }))
 }
+
+/// POST /api/servers/{server_id}/connection-info/regenerate-rcon-password
+/// Rotate the RCON password of a dynamic server: applied live over RCON, written to
+/// server.cfg and persisted, then used by the panel for future reconnects.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/connection-info/regenerate-rcon-password",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = Object),
+        (status = 400, body = ApiError),
+        (status = 403, description = "Token too old; log in again", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn regenerate_rcon_password(
+    req: HttpRequest,
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = require_recent_auth(&req, config.auth.reauth_window_mins)?;
+    let server_id = server_id.into_inner();
+
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    if def.source == ServerSource::Static {
+        return Err(ApiError::validation(
+            "Static servers take their RCON password from config.yaml",
+        ));
+    }
+
+    let password = generate_rcon_password();
+    let mut warnings: Vec<String> = Vec::new();
+
+    // Apply live first so the running server accepts the new password immediately
+    let rcon = registry.get_rcon(&server_id).await;
+    let applied_live = match rcon {
+        Some(ref rcon) => rcon
+            .execute(&format!("rcon.password {}", quote_arg(&password)))
+            .await
+            .is_ok(),
+        None => false,
+    };
+    if !applied_live {
+        warnings.push("Server is offline; the new password applies on next start".to_string());
+    }
+
+    let cfg_path = def.to_game_server_config().paths.server_cfg;
+    if let Err(e) = crate::servercfg::set_convars(&cfg_path, &[("rcon.password", password.clone())])
+    {
+        tracing::warn!("Failed to update server.cfg for '{}': {}", server_id, e);
+        warnings.push(format!("Failed to update server.cfg: {}", e));
+    }
+
+    registry
+        .update_definition(&server_id, |d| d.rcon_password = password.clone())
+        .await;
+    registry.persist().await;
+    if let Some(rcon) = rcon {
+        rcon.set_password(&password);
+    }
+
+    tracing::info!(
+        "RCON password for server '{}' regenerated by '{}'",
+        server_id,
+        claims.sub
+    );
+
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "rconPassword": password,
+        "appliedLive": applied_live,
+        "warnings": warnings,
+    })))
+}
+
+/// Look up a definition that is waiting in `pending_attach`.
+async fn pending_definition(
+    registry: &ServerRegistry,
+    server_id: &str,
+) -> Result<ServerDefinition, ApiError> {
+    match registry.get_definition(server_id).await {
+        Some(d) if d.provisioning_status == ProvisioningStatus::PendingAttach => Ok(d),
+        Some(_) => Err(ApiError::conflict(
+            "Server is not waiting to be attached or provisioned",
+        )),
+        None => Err(ApiError::server_not_found()),
+    }
+}
+
+/// POST /api/servers/{server_id}/attach — adopt already-copied server files for an
+/// imported definition: rewrite ports/password in server.cfg and mark it Ready.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/attach",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 400, description = "No LinuxGSM install at the server's path", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server is not pending attach", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn attach_server(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let server_id = server_id.into_inner();
+    let def = pending_definition(&registry, &server_id).await?;
+
+    let server_config = def.to_game_server_config();
+    if !std::path::Path::new(&server_config.paths.lgsm_script).exists() {
+        return Err(ApiError::validation(format!(
+            "No LinuxGSM install found at '{}'; copy the files there or re-provision",
+            server_config.paths.base_dir
+        )));
+    }
+
+    let convars = [
+        ("server.port", def.game_port.to_string()),
+        ("server.queryport", def.query_port.to_string()),
+        ("rcon.port", def.rcon_port.to_string()),
+        ("rcon.password", def.rcon_password.clone()),
+    ];
+    if let Err(e) = crate::servercfg::set_convars(&server_config.paths.server_cfg, &convars) {
+        return Err(ApiError::internal(format!(
+            "Failed to update server.cfg: {}",
+            e
+        )));
+    }
+
+    registry
+        .update_definition(&server_id, |d| {
+            d.provisioning_status = ProvisioningStatus::Ready;
+            d.provisioning_log
+                .push(format!("Attached to existing files at {}", server_config.paths.base_dir));
+        })
+        .await;
+    registry.events.publish(PanelEvent::ProvisioningStatus {
+        server_id: server_id.clone(),
+        status: ProvisioningStatus::Ready,
+        message: "Attached to existing files".to_string(),
+    });
+    let runtime = ServerRuntime::start(
+        &server_config,
+        &config.monitor,
+        &registry.events,
+        &registry.metrics,
+    );
+    registry
+        .runtimes
+        .write()
+        .await
+        .insert(server_id.clone(), runtime);
+    registry.persist().await;
+
+    Ok(HttpResponse::Ok().json(SuccessBody {
+        success: true,
+        message: format!("Server '{}' attached", server_id),
+    }))
+}
+
+/// POST /api/servers/{server_id}/provision — run a fresh install for an imported definition.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/provision",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 202, description = "Provisioning started", body = Object),
+        (status = 409, description = "Server is not pending attach", body = ApiError),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn provision_imported(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let server_id = server_id.into_inner();
+    let def = pending_definition(&registry, &server_id).await?;
+
+    registry
+        .update_definition(&server_id, |d| {
+            d.provisioning_status = ProvisioningStatus::Installing;
+        })
+        .await;
+    registry.persist().await;
+
+    provisioner::spawn_provisioning(
+        def,
+        registry.into_inner().as_ref().clone(),
+        config.into_inner().as_ref().clone(),
+    );
+
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "id": server_id,
+        "status": "installing",
+    })))
+}
+
+/// POST /api/servers/{server_id}/provision/cancel — abort a running install and mark it
+/// failed, e.g. when LinuxGSM hangs during a Steam outage.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/provision/cancel",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = SuccessBody),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server is not provisioning", body = ApiError),
+    )
+)]
+pub async fn cancel_provisioning(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let server_id = server_id.into_inner();
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    if !def.provisioning_status.in_progress() {
+        return Err(ApiError::conflict("Server is not provisioning"));
+    }
+
+    // Nothing to abort after a panel restart, but the definition still needs unsticking
+    registry.abort_provisioning(&server_id).await;
+    // The task may have finished while it was being aborted
+    if !registry
+        .get_definition(&server_id)
+        .await
+        .is_some_and(|d| d.provisioning_status.in_progress())
+    {
+        return Err(ApiError::conflict("Provisioning already finished"));
+    }
+    provisioner::update_status(
+        &registry,
+        &server_id,
+        ProvisioningStatus::Error,
+        "Provisioning cancelled",
+    )
+    .await;
+    registry.persist().await;
+
+    Ok(HttpResponse::Ok().json(SuccessBody {
+        success: true,
+        message: format!("Provisioning of '{}' cancelled", server_id),
+    }))
+}
+
+/// POST /api/servers/{server_id}/provision/retry — provision a failed or cancelled server
+/// again, keeping whatever the previous attempt already installed.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/provision/retry",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 202, description = "Provisioning started", body = Object),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 409, description = "Server provisioning has not failed", body = ApiError),
+    )
+)]
+pub async fn retry_provisioning(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let server_id = server_id.into_inner();
+    // Checked and flipped under one lock, so a double-click starts one task
+    let def = registry
+        .update_definition(&server_id, |d| {
+            if d.provisioning_status != ProvisioningStatus::Error {
+                return None;
+            }
+            d.provisioning_status = ProvisioningStatus::Installing;
+            Some(d.clone())
+        })
+        .await
+        .ok_or_else(ApiError::server_not_found)?
+        .ok_or_else(|| ApiError::conflict("Only failed provisioning can be retried"))?;
+    registry.persist().await;
+
+    provisioner::spawn_provisioning(
+        def,
+        registry.into_inner().as_ref().clone(),
+        config.into_inner().as_ref().clone(),
+    );
+
+    Ok(HttpResponse::Accepted().json(serde_json::json!({
+        "id": server_id,
+        "status": "installing",
+    })))
+}
@@ -0,0 +1,44 @@
+use std::sync::Arc;
+use tokio::signal::unix::{signal, SignalKind};
+use tokio::sync::watch;
+
+/// Shutdown coordinator. Background loops select on `signal()` and stop after their
+/// current iteration instead of being killed mid-write.
+#[derive(Clone)]
+pub struct Shutdown {
+    tx: Arc<watch::Sender<bool>>,
+}
+
+impl Shutdown {
+    pub fn new() -> Self {
+        let (tx, _) = watch::channel(false);
+        Self { tx: Arc::new(tx) }
+    }
+
+    pub fn trigger(&self) {
+        self.tx.send_replace(true);
+    }
+
+    /// Resolves once shutdown was triggered (immediately if it already was).
+    pub async fn signal(&self) {
+        let mut rx = self.tx.subscribe();
+        // The sender lives in self, so this only returns once the flag is set
+        let _ = rx.wait_for(|stopping| *stopping).await;
+    }
+}
+
+/// Resolves on SIGINT (Ctrl+C) or SIGTERM.
+pub async fn wait_for_signal() {
+    let mut term = match signal(SignalKind::terminate()) {
+        Ok(s) => s,
+        Err(e) => {
+            tracing::warn!("Failed to listen for SIGTERM: {}", e);
+            let _ = tokio::signal::ctrl_c().await;
+            return;
+        }
+    };
+    tokio::select! {
+        _ = tokio::signal::ctrl_c() => tracing::info!("Received SIGINT"),
+        _ = term.recv() => tracing::info!("Received SIGTERM"),
+    }
+}
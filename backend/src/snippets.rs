@@ -0,0 +1,606 @@
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::RwLock;
+use utoipa::ToSchema;
+use uuid::Uuid;
+
+use crate::apierror::{ApiError, ErrorCode};
+use crate::audit;
+use crate::auth::{Claims, ADMIN_ROLE};
+use crate::commandpolicy;
+use crate::config::AppConfig;
+use crate::persistence::SettingsStore;
+use crate::registry::ServerRegistry;
+
+const SETTINGS_KEY: &str = "console_snippets";
+const MAX_NAME_LEN: usize = 64;
+const MAX_COMMANDS: usize = 50;
+/// How long the token handed out for a `confirm` snippet stays valid.
+const CONFIRM_TTL: Duration = Duration::from_secs(60);
+
+/// A named list of console commands.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct Snippet {
+    pub name: String,
+    #[serde(default)]
+    pub description: Option<String>,
+    pub commands: Vec<String>,
+    /// Running it needs a second request carrying the token from the first.
+    #[serde(default)]
+    pub confirm: bool,
+}
+
+impl Snippet {
+    fn validate(&self) -> Result<(), String> {
+        if self.name.is_empty() || self.name.len() > MAX_NAME_LEN {
+            return Err(format!("name must be 1-{} characters", MAX_NAME_LEN));
+        }
+        if !self
+            .name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '-' | '_' | '.'))
+        {
+            return Err("name may only contain letters, digits, '-', '_' and '.'".to_string());
+        }
+        if self.commands.is_empty() || self.commands.len() > MAX_COMMANDS {
+            return Err(format!("commands must have 1-{} entries", MAX_COMMANDS));
+        }
+        if let Some(i) = self.commands.iter().position(|c| c.trim().is_empty()) {
+            return Err(format!("commands[{}] is empty", i));
+        }
+        if let Some(i) = self.commands.iter().position(|c| c.contains(['\n', '\r'])) {
+            return Err(format!("commands[{}] must be a single line", i));
+        }
+        Ok(())
+    }
+}
+
+/// Everything that gets saved: snippets offered on every server, and per-server ones.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct SnippetBook {
+    #[serde(default)]
+    global: Vec<Snippet>,
+    #[serde(default)]
+    servers: BTreeMap<String, Vec<Snippet>>,
+}
+
+impl SnippetBook {
+    fn list_mut(&mut self, server_id: Option<&str>) -> &mut Vec<Snippet> {
+        match server_id {
+            Some(id) => self.servers.entry(id.to_string()).or_default(),
+            None => &mut self.global,
+        }
+    }
+
+    /// The snippet a run on `server_id` means: the server's own first, then a global one.
+    fn resolve(&self, server_id: &str, name: &str) -> Option<Snippet> {
+        self.servers
+            .get(server_id)
+            .into_iter()
+            .flatten()
+            .chain(&self.global)
+            .find(|s| s.name == name)
+            .cloned()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SnippetScope {
+    Server,
+    Global,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct SnippetView {
+    #[serde(flatten)]
+    snippet: Snippet,
+    scope: SnippetScope,
+}
+
+/// A confirmation token handed out for one run of one snippet by one user.
+struct PendingRun {
+    server_id: String,
+    name: String,
+    user: Option<String>,
+    issued: Instant,
+}
+
+pub struct Snippets {
+    book: RwLock<SnippetBook>,
+    pending: Mutex<HashMap<String, PendingRun>>,
+    store: Arc<dyn SettingsStore>,
+}
+
+impl Snippets {
+    pub fn new(store: Arc<dyn SettingsStore>) -> Self {
+        Self {
+            book: RwLock::new(load_book(&*store)),
+            pending: Mutex::new(HashMap::new()),
+            store,
+        }
+    }
+
+    /// Re-read the snippets from the store, after a panel restore.
+    pub async fn reload(&self) {
+        *self.book.write().await = load_book(&*self.store);
+    }
+
+    /// Apply `change` and save; nothing changes in memory if either step fails.
+    async fn update(
+        &self,
+        change: impl FnOnce(&mut SnippetBook) -> Result<(), UpdateError>,
+    ) -> Result<(), UpdateError> {
+        let mut book = self.book.write().await;
+        let mut updated = book.clone();
+        change(&mut updated)?;
+        serde_json::to_value(&updated)
+            .map_err(anyhow::Error::from)
+            .and_then(|value| self.store.save_setting(SETTINGS_KEY, &value))
+            .map_err(|e| UpdateError::Save(e.to_string()))?;
+        *book = updated;
+        Ok(())
+    }
+
+    fn issue_token(&self, server_id: &str, name: &str, user: Option<&str>) -> String {
+        let token = Uuid::new_v4().to_string();
+        let mut pending = self.pending.lock().unwrap();
+        pending.retain(|_, p| p.issued.elapsed() < CONFIRM_TTL);
+        pending.insert(
+            token.clone(),
+            PendingRun {
+                server_id: server_id.to_string(),
+                name: name.to_string(),
+                user: user.map(str::to_string),
+                issued: Instant::now(),
+            },
+        );
+        token
+    }
+
+    /// Use up `token` if it was issued for this run and hasn't expired.
+    fn redeem_token(&self, token: &str, server_id: &str, name: &str, user: Option<&str>) -> bool {
+        let mut pending = self.pending.lock().unwrap();
+        match pending.get(token) {
+            Some(p)
+                if p.server_id == server_id
+                    && p.name == name
+                    && p.user.as_deref() == user
+                    && p.issued.elapsed() < CONFIRM_TTL =>
+            {
+                pending.remove(token);
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+fn load_book(store: &dyn SettingsStore) -> SnippetBook {
+    match store.load_setting(SETTINGS_KEY) {
+        Ok(Some(value)) => serde_json::from_value(value)
+            .map_err(|e| tracing::warn!("Ignoring unreadable console snippets: {}", e))
+            .unwrap_or_default(),
+        Ok(None) => SnippetBook::default(),
+        Err(e) => {
+            tracing::warn!("Failed to load console snippets: {}", e);
+            SnippetBook::default()
+        }
+    }
+}
+
+/// Why a change to the snippets was not made.
+enum UpdateError {
+    /// Another snippet already has this name.
+    Duplicate(String),
+    NotFound,
+    Save(String),
+}
+
+impl From<UpdateError> for ApiError {
+    fn from(e: UpdateError) -> Self {
+        match e {
+            UpdateError::Duplicate(name) => {
+                ApiError::conflict(format!("A snippet named '{}' already exists", name))
+            }
+            UpdateError::NotFound => snippet_not_found(),
+            UpdateError::Save(e) => ApiError::internal(format!("Failed to save snippets: {}", e)),
+        }
+    }
+}
+
+fn snippet_not_found() -> ApiError {
+    ApiError::not_found("Snippet not found")
+}
+
+async fn require_server(registry: &ServerRegistry, server_id: &str) -> Result<(), ApiError> {
+    if registry.definitions.read().await.contains_key(server_id) {
+        Ok(())
+    } else {
+        Err(ApiError::server_not_found())
+    }
+}
+
+fn list(book: &SnippetBook, server_id: Option<&str>) -> Vec<SnippetView> {
+    let own = server_id
+        .and_then(|id| book.servers.get(id))
+        .into_iter()
+        .flatten()
+        .map(|s| SnippetView {
+            snippet: s.clone(),
+            scope: SnippetScope::Server,
+        });
+    let global = book.global.iter().map(|s| SnippetView {
+        snippet: s.clone(),
+        scope: SnippetScope::Global,
+    });
+    own.chain(global).collect()
+}
+
+async fn create(
+    snippets: &Snippets,
+    server_id: Option<&str>,
+    snippet: Snippet,
+) -> Result<HttpResponse, ApiError> {
+    snippet.validate().map_err(ApiError::validation)?;
+    let created = snippet.clone();
+    snippets
+        .update(|book| {
+            let list = book.list_mut(server_id);
+            if list.iter().any(|s| s.name == snippet.name) {
+                return Err(UpdateError::Duplicate(snippet.name));
+            }
+            list.push(snippet);
+            Ok(())
+        })
+        .await?;
+    Ok(HttpResponse::Created().json(created))
+}
+
+async fn replace(
+    snippets: &Snippets,
+    server_id: Option<&str>,
+    name: &str,
+    snippet: Snippet,
+) -> Result<HttpResponse, ApiError> {
+    snippet.validate().map_err(ApiError::validation)?;
+    let updated = snippet.clone();
+    snippets
+        .update(|book| {
+            let list = book.list_mut(server_id);
+            if snippet.name != name && list.iter().any(|s| s.name == snippet.name) {
+                return Err(UpdateError::Duplicate(snippet.name));
+            }
+            match list.iter_mut().find(|s| s.name == name) {
+                Some(existing) => {
+                    *existing = snippet;
+                    Ok(())
+                }
+                None => Err(UpdateError::NotFound),
+            }
+        })
+        .await?;
+    Ok(HttpResponse::Ok().json(updated))
+}
+
+async fn remove(
+    snippets: &Snippets,
+    server_id: Option<&str>,
+    name: &str,
+) -> Result<HttpResponse, ApiError> {
+    snippets
+        .update(|book| {
+            let list = book.list_mut(server_id);
+            let before = list.len();
+            list.retain(|s| s.name != name);
+            if list.len() == before {
+                return Err(UpdateError::NotFound);
+            }
+            if let Some(id) = server_id {
+                if book.servers.get(id).is_some_and(Vec::is_empty) {
+                    book.servers.remove(id);
+                }
+            }
+            Ok(())
+        })
+        .await?;
+    Ok(HttpResponse::NoContent().finish())
+}
+
+/// GET /api/console/snippets — snippets offered on every server.
+#[utoipa::path(
+    get,
+    path = "/api/console/snippets",
+    tag = "console",
+    responses((status = 200, body = [Snippet]))
+)]
+pub async fn list_global(snippets: web::Data<Arc<Snippets>>) -> HttpResponse {
+    HttpResponse::Ok().json(&snippets.book.read().await.global)
+}
+
+/// POST /api/console/snippets
+#[utoipa::path(
+    post,
+    path = "/api/console/snippets",
+    tag = "console",
+    request_body = Snippet,
+    responses(
+        (status = 201, body = Snippet),
+        (status = 400, body = ApiError),
+        (status = 409, description = "Name already used", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn create_global(
+    body: web::Json<Snippet>,
+    snippets: web::Data<Arc<Snippets>>,
+) -> Result<HttpResponse, ApiError> {
+    create(&snippets, None, body.into_inner()).await
+}
+
+/// PUT /api/console/snippets/{name}
+#[utoipa::path(
+    put,
+    path = "/api/console/snippets/{name}",
+    tag = "console",
+    params(("name" = String, Path, description = "Snippet name")),
+    request_body = Snippet,
+    responses(
+        (status = 200, body = Snippet),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 409, description = "Name already used", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn update_global(
+    name: web::Path<String>,
+    body: web::Json<Snippet>,
+    snippets: web::Data<Arc<Snippets>>,
+) -> Result<HttpResponse, ApiError> {
+    replace(&snippets, None, &name, body.into_inner()).await
+}
+
+/// DELETE /api/console/snippets/{name}
+#[utoipa::path(
+    delete,
+    path = "/api/console/snippets/{name}",
+    tag = "console",
+    params(("name" = String, Path, description = "Snippet name")),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn delete_global(
+    name: web::Path<String>,
+    snippets: web::Data<Arc<Snippets>>,
+) -> Result<HttpResponse, ApiError> {
+    remove(&snippets, None, &name).await
+}
+
+/// GET /api/servers/{server_id}/console/snippets — the server's snippets, then global ones.
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/console/snippets",
+    tag = "console",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = [SnippetView]),
+        (status = 404, body = ApiError),
+    )
+)]
+pub async fn list_server(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    snippets: web::Data<Arc<Snippets>>,
+) -> Result<HttpResponse, ApiError> {
+    require_server(&registry, &server_id).await?;
+    Ok(HttpResponse::Ok().json(list(&*snippets.book.read().await, Some(&server_id))))
+}
+
+/// POST /api/servers/{server_id}/console/snippets
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/console/snippets",
+    tag = "console",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = Snippet,
+    responses(
+        (status = 201, body = Snippet),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 409, description = "Name already used", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn create_server(
+    server_id: web::Path<String>,
+    body: web::Json<Snippet>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    snippets: web::Data<Arc<Snippets>>,
+) -> Result<HttpResponse, ApiError> {
+    require_server(&registry, &server_id).await?;
+    create(&snippets, Some(&server_id), body.into_inner()).await
+}
+
+/// PUT /api/servers/{server_id}/console/snippets/{name}
+#[utoipa::path(
+    put,
+    path = "/api/servers/{server_id}/console/snippets/{name}",
+    tag = "console",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Snippet name"),
+    ),
+    request_body = Snippet,
+    responses(
+        (status = 200, body = Snippet),
+        (status = 400, body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 409, description = "Name already used", body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn update_server(
+    path: web::Path<(String, String)>,
+    body: web::Json<Snippet>,
+    snippets: web::Data<Arc<Snippets>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    replace(&snippets, Some(&server_id), &name, body.into_inner()).await
+}
+
+/// DELETE /api/servers/{server_id}/console/snippets/{name}
+#[utoipa::path(
+    delete,
+    path = "/api/servers/{server_id}/console/snippets/{name}",
+    tag = "console",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Snippet name"),
+    ),
+    responses(
+        (status = 204, description = "Deleted"),
+        (status = 404, body = ApiError),
+        (status = 500, body = ApiError),
+    )
+)]
+pub async fn delete_server(
+    path: web::Path<(String, String)>,
+    snippets: web::Data<Arc<Snippets>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    remove(&snippets, Some(&server_id), &name).await
+}
+
+#[derive(Debug, Default, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSnippetRequest {
+    /// Token from the 409 answer to a first run request; only `confirm` snippets need it.
+    #[serde(default)]
+    pub confirm_token: Option<String>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct CommandOutcome {
+    command: String,
+    success: bool,
+    output: String,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+struct RunResult {
+    snippet: String,
+    /// Every command succeeded.
+    success: bool,
+    results: Vec<CommandOutcome>,
+}
+
+/// POST /api/servers/{server_id}/console/snippets/{name}/run
+///
+/// Runs the commands in order over RCON. A snippet marked `confirm` answers 409 with a
+/// one-time token (and the commands it would run) until the request carries that token.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/console/snippets/{name}/run",
+    tag = "console",
+    params(
+        ("server_id" = String, Path, description = "Server id"),
+        ("name" = String, Path, description = "Snippet name, the server's own before global"),
+    ),
+    request_body = RunSnippetRequest,
+    responses(
+        (status = 200, description = "Per-command results", body = RunResult),
+        (status = 403, description = "A command is refused by the console policy", body = ApiError),
+        (status = 404, body = ApiError),
+        (status = 409, description = "Confirmation required: `details.confirmToken`, sent back within a minute, runs `details.commands`", body = ApiError),
+    )
+)]
+pub async fn run_snippet(
+    req: HttpRequest,
+    path: web::Path<(String, String)>,
+    body: Option<web::Json<RunSnippetRequest>>,
+    config: web::Data<AppConfig>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    snippets: web::Data<Arc<Snippets>>,
+) -> Result<HttpResponse, ApiError> {
+    let (server_id, name) = path.into_inner();
+    let body = body.map(web::Json::into_inner).unwrap_or_default();
+    let user = req.extensions().get::<Claims>().map(|c| c.sub.clone());
+
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let snippet = snippets
+        .book
+        .read()
+        .await
+        .resolve(&server_id, &name)
+        .ok_or_else(snippet_not_found)?;
+
+    let username = user.as_deref().unwrap_or("unknown");
+    for command in &snippet.commands {
+        let allowed = commandpolicy::enforce(
+            &config.console,
+            &server_id,
+            command,
+            username,
+            Some(ADMIN_ROLE),
+        );
+        if let Err(reason) = allowed {
+            audit::detail(&req, format!("{}: blocked {}", name, command));
+            return Err(
+                ApiError::new(ErrorCode::CommandBlocked, reason).with_detail("command", command)
+            );
+        }
+    }
+
+    if snippet.confirm {
+        let confirmed = body
+            .confirm_token
+            .as_deref()
+            .is_some_and(|token| snippets.redeem_token(token, &server_id, &name, user.as_deref()));
+        if !confirmed {
+            return Err(
+                ApiError::conflict(format!("Snippet '{}' needs confirmation", name))
+                    .with_detail(
+                        "confirmToken",
+                        snippets.issue_token(&server_id, &name, user.as_deref()),
+                    )
+                    .with_detail("commands", &snippet.commands),
+            );
+        }
+    }
+
+    audit::detail(&req, format!("{}: {}", name, snippet.commands.join("; ")));
+    let replies = rcon.execute_batch(&snippet.commands).await;
+    let results: Vec<CommandOutcome> = snippet
+        .commands
+        .into_iter()
+        .zip(replies)
+        .map(|(command, reply)| {
+            let success = reply.is_ok();
+            let output = reply.unwrap_or_else(|e| e);
+            CommandOutcome {
+                command,
+                success,
+                output,
+            }
+        })
+        .collect();
+    Ok(HttpResponse::Ok().json(RunResult {
+        snippet: name,
+        success: results.iter().all(|r| r.success),
+        results,
+    }))
+}
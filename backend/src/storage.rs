@@ -0,0 +1,881 @@
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection, OptionalExtension, Transaction};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::path::Path;
+use std::sync::Mutex;
+
+use crate::auth::RefreshToken;
+use crate::bans::BanRecord;
+use crate::lgsm::{ActionRecord, MAX_ACTIONS_PER_SERVER};
+use crate::persistence::{
+    MetricsStore, ScheduleStore, ServerStore, SessionStore, SettingsStore, StaticOverride,
+};
+use crate::playertracker::PlayerRecord;
+use crate::registry::ServerDefinition;
+use crate::rollup::{Bucket, Tier};
+use crate::scheduler::{JobRun, PauseWindow, ScheduledJob};
+use crate::wipes::WipeRecord;
+
+/// Panel database, next to config.yaml.
+pub const DB_FILE: &str = "panel.db";
+
+/// Schema steps; `PRAGMA user_version` records how many have been applied. Rows keep the
+/// serde JSON of each record, so adding a field with a serde default needs no migration.
+const MIGRATIONS: &[&str] = &[
+    "CREATE TABLE meta (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+     CREATE TABLE servers (id TEXT PRIMARY KEY, position INTEGER NOT NULL, data TEXT NOT NULL);
+     CREATE TABLE static_overrides (id TEXT PRIMARY KEY, data TEXT NOT NULL);
+     CREATE TABLE scheduled_jobs (id TEXT PRIMARY KEY, position INTEGER NOT NULL, data TEXT NOT NULL);
+     CREATE TABLE job_runs (
+         run_id TEXT PRIMARY KEY,
+         job_id TEXT NOT NULL,
+         started_at TEXT NOT NULL,
+         data TEXT NOT NULL
+     );
+     CREATE INDEX job_runs_by_job ON job_runs (job_id, started_at);
+     CREATE TABLE schedule_pauses (position INTEGER PRIMARY KEY, data TEXT NOT NULL);",
+    "CREATE TABLE settings (key TEXT PRIMARY KEY, data TEXT NOT NULL);",
+    "CREATE TABLE wipes (server_id TEXT NOT NULL, timestamp TEXT NOT NULL, data TEXT NOT NULL);
+     CREATE INDEX wipes_by_server ON wipes (server_id, timestamp);",
+    "CREATE TABLE actions (server_id TEXT NOT NULL, started_at TEXT NOT NULL, data TEXT NOT NULL);
+     CREATE INDEX actions_by_server ON actions (server_id, started_at);",
+    "CREATE TABLE refresh_tokens (
+         jti TEXT PRIMARY KEY,
+         family TEXT NOT NULL,
+         expires_at TEXT NOT NULL,
+         data TEXT NOT NULL
+     );
+     CREATE INDEX refresh_tokens_by_family ON refresh_tokens (family);",
+    // lift_at: when a temporary ban is still to be lifted; null for permanent or lifted bans
+    "CREATE TABLE bans (
+         server_id TEXT NOT NULL,
+         steam_id TEXT NOT NULL,
+         created_at TEXT NOT NULL,
+         lift_at TEXT,
+         data TEXT NOT NULL,
+         PRIMARY KEY (server_id, steam_id)
+     );
+     CREATE INDEX bans_by_lift_at ON bans (lift_at);",
+    "CREATE TABLE players (
+         server_id TEXT NOT NULL,
+         steam_id TEXT NOT NULL,
+         last_seen TEXT NOT NULL,
+         data TEXT NOT NULL,
+         PRIMARY KEY (server_id, steam_id)
+     );",
+    "CREATE TABLE metric_rollups (
+         source TEXT NOT NULL,
+         tier TEXT NOT NULL,
+         bucket INTEGER NOT NULL,
+         data TEXT NOT NULL,
+         PRIMARY KEY (source, tier, bucket)
+     );",
+];
+
+/// Tables `restore_from` keeps: restoring sessions would log out current users and
+/// bring back ones that had logged out.
+const NOT_RESTORED: &[&str] = &["refresh_tokens"];
+
+/// SQLite-backed store. One connection behind a mutex: writes are small and rare, and
+/// serializing them is what replaced the last-writer-wins JSON files.
+pub struct SqliteStore {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteStore {
+    /// Open (or create) the database at `path`, migrate it, and import the legacy JSON
+    /// files from the same directory the first time.
+    pub fn open(path: &Path) -> anyhow::Result<Self> {
+        let conn = Connection::open(path)?;
+        conn.pragma_update(None, "journal_mode", "WAL")?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        let store = Self::with_connection(conn)?;
+        let dir = path.parent().unwrap_or_else(|| Path::new("."));
+        store.import_legacy_json(dir)?;
+        Ok(store)
+    }
+
+    /// Empty database that lives as long as the store.
+    #[cfg(test)]
+    pub fn in_memory() -> anyhow::Result<Self> {
+        Self::with_connection(Connection::open_in_memory()?)
+    }
+
+    fn with_connection(mut conn: Connection) -> anyhow::Result<Self> {
+        migrate(&mut conn)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Run `f` in a transaction, committing if it succeeds.
+    fn write<T>(&self, f: impl FnOnce(&Transaction) -> anyhow::Result<T>) -> anyhow::Result<T> {
+        let mut conn = self.conn.lock().unwrap();
+        let tx = conn.transaction()?;
+        let value = f(&tx)?;
+        tx.commit()?;
+        Ok(value)
+    }
+
+    /// Rows of `data` from `sql`, decoded; rows that no longer parse are skipped.
+    fn query_json<T: DeserializeOwned>(&self, sql: &str) -> anyhow::Result<Vec<T>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(sql)?;
+        let rows = stmt.query_map([], |row| row.get::<_, String>(0))?;
+        let mut values = Vec::new();
+        for data in rows {
+            match serde_json::from_str(&data?) {
+                Ok(value) => values.push(value),
+                Err(e) => tracing::warn!("Skipping unreadable row ({}): {}", sql, e),
+            }
+        }
+        Ok(values)
+    }
+
+    /// Copy the pre-database JSON files in, once. Each imported file is renamed to
+    /// `<name>.imported` so it stays around as a backup but is never read again.
+    fn import_legacy_json(&self, dir: &Path) -> anyhow::Result<()> {
+        let done: Option<String> = self
+            .conn
+            .lock()
+            .unwrap()
+            .query_row(
+                "SELECT value FROM meta WHERE key = 'legacy_json_imported'",
+                [],
+                |row| row.get(0),
+            )
+            .optional()?;
+        if done.is_some() {
+            return Ok(());
+        }
+
+        self.import_file(dir, "servers.json", |store, json| {
+            store.save_servers(&serde_json::from_str::<Vec<_>>(json)?)
+        })?;
+        self.import_file(dir, "static_overrides.json", |store, json| {
+            store.save_static_overrides(&serde_json::from_str(json)?)
+        })?;
+        self.import_file(dir, "schedules.json", |store, json| {
+            store.save_jobs(&serde_json::from_str::<Vec<_>>(json)?)
+        })?;
+        self.import_file(dir, "job_history.json", |store, json| {
+            store.save_job_history(&serde_json::from_str(json)?)
+        })?;
+        self.import_file(dir, "schedule_pauses.json", |store, json| {
+            store.save_pauses(&serde_json::from_str::<Vec<_>>(json)?)
+        })?;
+
+        self.write(|tx| {
+            tx.execute(
+                "INSERT INTO meta (key, value) VALUES ('legacy_json_imported', ?1)",
+                params![chrono::Utc::now().to_rfc3339()],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn import_file(
+        &self,
+        dir: &Path,
+        name: &str,
+        save: impl FnOnce(&Self, &str) -> anyhow::Result<()>,
+    ) -> anyhow::Result<()> {
+        let path = dir.join(name);
+        if !path.exists() {
+            return Ok(());
+        }
+        let content = std::fs::read_to_string(&path)?;
+        save(self, &content).map_err(|e| anyhow::anyhow!("importing {}: {}", name, e))?;
+        let mut backup = path.clone().into_os_string();
+        backup.push(".imported");
+        std::fs::rename(&path, &backup)?;
+        tracing::info!("Imported {} into {}", name, DB_FILE);
+        Ok(())
+    }
+
+    /// Replace every table's rows with those of the database at `path` (a panel backup),
+    /// in one transaction. Backups from older panels are migrated first; newer ones are
+    /// refused, since their rows may not load here. Login sessions are left as they are.
+    pub fn restore_from(&self, path: &Path) -> anyhow::Result<()> {
+        {
+            let mut restored = Connection::open(path)?;
+            let version: usize =
+                restored.pragma_query_value(None, "user_version", |row| row.get(0))?;
+            if version > SCHEMA_VERSION {
+                anyhow::bail!(
+                    "backup has schema version {}, this panel only knows {}",
+                    version,
+                    SCHEMA_VERSION
+                );
+            }
+            migrate(&mut restored)?;
+        }
+
+        let conn = self.conn.lock().unwrap();
+        conn.execute(
+            "ATTACH DATABASE ?1 AS restored",
+            params![path.to_string_lossy()],
+        )?;
+        let copied = (|| -> anyhow::Result<()> {
+            let tables: Vec<String> = conn
+                .prepare("SELECT name FROM main.sqlite_master WHERE type = 'table'")?
+                .query_map([], |row| row.get(0))?
+                .collect::<Result<_, _>>()?;
+            let tx = conn.unchecked_transaction()?;
+            for table in tables
+                .iter()
+                .filter(|t| !t.starts_with("sqlite_") && !NOT_RESTORED.contains(&t.as_str()))
+            {
+                tx.execute_batch(&format!(
+                    "DELETE FROM main.{table}; INSERT INTO main.{table} SELECT * FROM restored.{table};"
+                ))?;
+            }
+            tx.commit()?;
+            Ok(())
+        })();
+        conn.execute("DETACH DATABASE restored", [])?;
+        copied
+    }
+}
+
+/// Schema version of a fully migrated database.
+pub const SCHEMA_VERSION: usize = MIGRATIONS.len();
+
+/// Write a consistent copy of the database at `path` to `dest` (which must not exist),
+/// without blocking the panel's own connection.
+pub fn snapshot(path: &Path, dest: &Path) -> anyhow::Result<()> {
+    let conn = Connection::open_with_flags(path, rusqlite::OpenFlags::SQLITE_OPEN_READ_ONLY)?;
+    conn.execute("VACUUM INTO ?1", params![dest.to_string_lossy()])?;
+    Ok(())
+}
+
+fn migrate(conn: &mut Connection) -> anyhow::Result<()> {
+    let applied: usize = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+    for (index, sql) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(sql)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+        tracing::info!("Applied database migration {}", index + 1);
+    }
+    Ok(())
+}
+
+fn to_json<T: Serialize>(value: &T) -> anyhow::Result<String> {
+    Ok(serde_json::to_string(value)?)
+}
+
+impl ServerStore for SqliteStore {
+    fn load_servers(&self) -> anyhow::Result<Vec<ServerDefinition>> {
+        self.query_json("SELECT data FROM servers ORDER BY position")
+    }
+
+    fn save_servers(&self, defs: &[ServerDefinition]) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute("DELETE FROM servers", [])?;
+            let mut insert =
+                tx.prepare("INSERT INTO servers (id, position, data) VALUES (?1, ?2, ?3)")?;
+            for (position, def) in defs.iter().enumerate() {
+                insert.execute(params![def.id, position as i64, to_json(def)?])?;
+            }
+            Ok(())
+        })
+    }
+
+    fn load_static_overrides(&self) -> anyhow::Result<HashMap<String, StaticOverride>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare("SELECT id, data FROM static_overrides")?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut overrides = HashMap::new();
+        for row in rows {
+            let (id, data) = row?;
+            match serde_json::from_str(&data) {
+                Ok(o) => {
+                    overrides.insert(id, o);
+                }
+                Err(e) => tracing::warn!("Skipping unreadable override for '{}': {}", id, e),
+            }
+        }
+        Ok(overrides)
+    }
+
+    fn save_static_overrides(
+        &self,
+        overrides: &HashMap<String, StaticOverride>,
+    ) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute("DELETE FROM static_overrides", [])?;
+            let mut insert =
+                tx.prepare("INSERT INTO static_overrides (id, data) VALUES (?1, ?2)")?;
+            for (id, o) in overrides {
+                insert.execute(params![id, to_json(o)?])?;
+            }
+            Ok(())
+        })
+    }
+
+    fn add_wipe(&self, server_id: &str, wipe: &WipeRecord) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "INSERT INTO wipes (server_id, timestamp, data) VALUES (?1, ?2, ?3)",
+                params![server_id, wipe.timestamp.to_rfc3339(), to_json(wipe)?],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn load_wipes(&self, server_id: &str) -> anyhow::Result<Vec<WipeRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT data FROM wipes WHERE server_id = ?1 ORDER BY timestamp DESC, rowid DESC",
+        )?;
+        let rows = stmt.query_map(params![server_id], |row| row.get::<_, String>(0))?;
+        let mut wipes = Vec::new();
+        for data in rows {
+            match serde_json::from_str(&data?) {
+                Ok(wipe) => wipes.push(wipe),
+                Err(e) => tracing::warn!("Skipping unreadable wipe for '{}': {}", server_id, e),
+            }
+        }
+        Ok(wipes)
+    }
+
+    fn delete_wipes(&self, server_id: &str) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute("DELETE FROM wipes WHERE server_id = ?1", params![server_id])?;
+            Ok(())
+        })
+    }
+
+    fn add_action(&self, server_id: &str, action: &ActionRecord) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "INSERT INTO actions (server_id, started_at, data) VALUES (?1, ?2, ?3)",
+                params![server_id, action.started_at.to_rfc3339(), to_json(action)?],
+            )?;
+            tx.execute(
+                "DELETE FROM actions WHERE server_id = ?1 AND rowid NOT IN (
+                     SELECT rowid FROM actions WHERE server_id = ?1
+                     ORDER BY started_at DESC, rowid DESC LIMIT ?2
+                 )",
+                params![server_id, MAX_ACTIONS_PER_SERVER],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn load_actions(&self, server_id: &str) -> anyhow::Result<Vec<ActionRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT data FROM actions WHERE server_id = ?1 ORDER BY started_at DESC, rowid DESC",
+        )?;
+        let rows = stmt.query_map(params![server_id], |row| row.get::<_, String>(0))?;
+        let mut actions = Vec::new();
+        for data in rows {
+            match serde_json::from_str(&data?) {
+                Ok(action) => actions.push(action),
+                Err(e) => tracing::warn!("Skipping unreadable action for '{}': {}", server_id, e),
+            }
+        }
+        Ok(actions)
+    }
+
+    fn delete_actions(&self, server_id: &str) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "DELETE FROM actions WHERE server_id = ?1",
+                params![server_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn save_ban(&self, server_id: &str, ban: &BanRecord) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "INSERT INTO bans (server_id, steam_id, created_at, lift_at, data)
+                 VALUES (?1, ?2, ?3, ?4, ?5)
+                 ON CONFLICT (server_id, steam_id) DO UPDATE SET
+                     created_at = excluded.created_at,
+                     lift_at = excluded.lift_at,
+                     data = excluded.data",
+                params![
+                    server_id,
+                    ban.steam_id,
+                    ban.created_at.to_rfc3339(),
+                    ban.lift_at().map(|at| at.to_rfc3339()),
+                    to_json(ban)?
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn load_bans(&self, server_id: &str) -> anyhow::Result<Vec<BanRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT data FROM bans WHERE server_id = ?1 ORDER BY created_at DESC, rowid DESC",
+        )?;
+        let rows = stmt.query_map(params![server_id], |row| row.get::<_, String>(0))?;
+        let mut bans = Vec::new();
+        for data in rows {
+            match serde_json::from_str(&data?) {
+                Ok(ban) => bans.push(ban),
+                Err(e) => tracing::warn!("Skipping unreadable ban for '{}': {}", server_id, e),
+            }
+        }
+        Ok(bans)
+    }
+
+    fn load_bans_due(&self, now: DateTime<Utc>) -> anyhow::Result<Vec<(String, BanRecord)>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT server_id, data FROM bans WHERE lift_at IS NOT NULL AND lift_at <= ?1",
+        )?;
+        let rows = stmt.query_map(params![now.to_rfc3339()], |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let mut due = Vec::new();
+        for row in rows {
+            let (server_id, data) = row?;
+            match serde_json::from_str(&data) {
+                Ok(ban) => due.push((server_id, ban)),
+                Err(e) => tracing::warn!("Skipping unreadable ban for '{}': {}", server_id, e),
+            }
+        }
+        Ok(due)
+    }
+
+    fn delete_ban(&self, server_id: &str, steam_id: &str) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "DELETE FROM bans WHERE server_id = ?1 AND steam_id = ?2",
+                params![server_id, steam_id],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn delete_bans(&self, server_id: &str) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute("DELETE FROM bans WHERE server_id = ?1", params![server_id])?;
+            Ok(())
+        })
+    }
+
+    fn save_players(&self, server_id: &str, players: &[PlayerRecord]) -> anyhow::Result<()> {
+        self.write(|tx| {
+            let mut upsert = tx.prepare(
+                "INSERT INTO players (server_id, steam_id, last_seen, data) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (server_id, steam_id) DO UPDATE SET
+                     last_seen = excluded.last_seen,
+                     data = excluded.data",
+            )?;
+            for player in players {
+                upsert.execute(params![
+                    server_id,
+                    player.steam_id,
+                    player.last_seen.to_rfc3339(),
+                    to_json(player)?
+                ])?;
+            }
+            Ok(())
+        })
+    }
+
+    fn load_player(&self, server_id: &str, steam_id: &str) -> anyhow::Result<Option<PlayerRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let data: Option<String> = conn
+            .query_row(
+                "SELECT data FROM players WHERE server_id = ?1 AND steam_id = ?2",
+                params![server_id, steam_id],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(data.map(|d| serde_json::from_str(&d)).transpose()?)
+    }
+
+    fn load_players(&self, server_id: &str) -> anyhow::Result<Vec<PlayerRecord>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt =
+            conn.prepare("SELECT data FROM players WHERE server_id = ?1 ORDER BY last_seen DESC")?;
+        let rows = stmt.query_map(params![server_id], |row| row.get::<_, String>(0))?;
+        let mut players = Vec::new();
+        for data in rows {
+            match serde_json::from_str(&data?) {
+                Ok(player) => players.push(player),
+                Err(e) => tracing::warn!("Skipping unreadable player for '{}': {}", server_id, e),
+            }
+        }
+        Ok(players)
+    }
+
+    fn delete_players(&self, server_id: &str) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "DELETE FROM players WHERE server_id = ?1",
+                params![server_id],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+impl MetricsStore for SqliteStore {
+    fn save_rollup(&self, source: &str, tier: Tier, bucket: &Bucket) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "INSERT OR REPLACE INTO metric_rollups (source, tier, bucket, data)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![
+                    source,
+                    tier.name(),
+                    bucket.timestamp.timestamp(),
+                    to_json(bucket)?
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn load_rollups(
+        &self,
+        source: &str,
+        tier: Tier,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> anyhow::Result<Vec<Bucket>> {
+        let conn = self.conn.lock().unwrap();
+        let mut stmt = conn.prepare(
+            "SELECT data FROM metric_rollups
+             WHERE source = ?1 AND tier = ?2 AND bucket >= ?3 AND bucket < ?4
+             ORDER BY bucket",
+        )?;
+        let rows = stmt.query_map(
+            params![source, tier.name(), from.timestamp(), to.timestamp()],
+            |row| row.get::<_, String>(0),
+        )?;
+        let mut buckets = Vec::new();
+        for data in rows {
+            match serde_json::from_str(&data?) {
+                Ok(bucket) => buckets.push(bucket),
+                Err(e) => tracing::warn!("Skipping unreadable rollup of {}: {}", source, e),
+            }
+        }
+        Ok(buckets)
+    }
+
+    fn prune_rollups(&self, source: &str, tier: Tier, before: DateTime<Utc>) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "DELETE FROM metric_rollups WHERE source = ?1 AND tier = ?2 AND bucket < ?3",
+                params![source, tier.name(), before.timestamp()],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn delete_rollups(&self, source: &str) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "DELETE FROM metric_rollups WHERE source = ?1",
+                params![source],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+impl ScheduleStore for SqliteStore {
+    fn load_jobs(&self) -> anyhow::Result<Vec<ScheduledJob>> {
+        self.query_json("SELECT data FROM scheduled_jobs ORDER BY position")
+    }
+
+    fn save_jobs(&self, jobs: &[ScheduledJob]) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute("DELETE FROM scheduled_jobs", [])?;
+            let mut insert =
+                tx.prepare("INSERT INTO scheduled_jobs (id, position, data) VALUES (?1, ?2, ?3)")?;
+            for (position, job) in jobs.iter().enumerate() {
+                insert.execute(params![job.id, position as i64, to_json(job)?])?;
+            }
+            Ok(())
+        })
+    }
+
+    fn load_job_history(&self) -> anyhow::Result<HashMap<String, VecDeque<JobRun>>> {
+        let runs: Vec<JobRun> =
+            self.query_json("SELECT data FROM job_runs ORDER BY job_id, started_at, rowid")?;
+        let mut history: HashMap<String, VecDeque<JobRun>> = HashMap::new();
+        for run in runs {
+            history
+                .entry(run.job_id.clone())
+                .or_default()
+                .push_back(run);
+        }
+        Ok(history)
+    }
+
+    fn save_job_history(&self, history: &HashMap<String, VecDeque<JobRun>>) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute("DELETE FROM job_runs", [])?;
+            let mut insert = tx.prepare(
+                "INSERT INTO job_runs (run_id, job_id, started_at, data) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            for run in history.values().flatten() {
+                insert.execute(params![
+                    run.run_id,
+                    run.job_id,
+                    run.started_at.to_rfc3339(),
+                    to_json(run)?
+                ])?;
+            }
+            Ok(())
+        })
+    }
+
+    fn load_pauses(&self) -> anyhow::Result<Vec<PauseWindow>> {
+        self.query_json("SELECT data FROM schedule_pauses ORDER BY position")
+    }
+
+    fn save_pauses(&self, pauses: &[PauseWindow]) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute("DELETE FROM schedule_pauses", [])?;
+            let mut insert =
+                tx.prepare("INSERT INTO schedule_pauses (position, data) VALUES (?1, ?2)")?;
+            for (position, window) in pauses.iter().enumerate() {
+                insert.execute(params![position as i64, to_json(window)?])?;
+            }
+            Ok(())
+        })
+    }
+}
+
+impl SettingsStore for SqliteStore {
+    fn load_setting(&self, key: &str) -> anyhow::Result<Option<serde_json::Value>> {
+        let conn = self.conn.lock().unwrap();
+        let data: Option<String> = conn
+            .query_row(
+                "SELECT data FROM settings WHERE key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(data.map(|d| serde_json::from_str(&d)).transpose()?)
+    }
+
+    fn save_setting(&self, key: &str, value: &serde_json::Value) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "INSERT INTO settings (key, data) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET data = excluded.data",
+                params![key, to_json(value)?],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+impl SessionStore for SqliteStore {
+    fn load_refresh_token(&self, jti: &str) -> anyhow::Result<Option<RefreshToken>> {
+        let conn = self.conn.lock().unwrap();
+        let data: Option<String> = conn
+            .query_row(
+                "SELECT data FROM refresh_tokens WHERE jti = ?1",
+                params![jti],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(data.map(|d| serde_json::from_str(&d)).transpose()?)
+    }
+
+    fn save_refresh_token(&self, token: &RefreshToken) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "DELETE FROM refresh_tokens WHERE expires_at < ?1",
+                params![chrono::Utc::now().to_rfc3339()],
+            )?;
+            tx.execute(
+                "INSERT INTO refresh_tokens (jti, family, expires_at, data) VALUES (?1, ?2, ?3, ?4)
+                 ON CONFLICT (jti) DO UPDATE SET data = excluded.data",
+                params![
+                    token.jti,
+                    token.family,
+                    token.expires_at.to_rfc3339(),
+                    to_json(token)?
+                ],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn delete_refresh_family(&self, family: &str) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "DELETE FROM refresh_tokens WHERE family = ?1",
+                params![family],
+            )?;
+            Ok(())
+        })
+    }
+
+    fn delete_refresh_tokens_for(&self, username: &str) -> anyhow::Result<()> {
+        self.write(|tx| {
+            tx.execute(
+                "DELETE FROM refresh_tokens WHERE json_extract(data, '$.username') = ?1",
+                params![username],
+            )?;
+            Ok(())
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// Scratch directory removed when dropped.
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn new() -> Self {
+            let dir = std::env::temp_dir().join(format!("store-test-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir(&dir).unwrap();
+            Self(dir)
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    fn jobs_json(name: &str) -> String {
+        serde_json::json!([{
+            "id": "job-1",
+            "name": name,
+            "serverId": "main",
+            "jobType": "restart",
+            "enabled": true,
+            "schedule": "daily 05:00",
+            "createdAt": "2026-01-01T00:00:00Z",
+        }])
+        .to_string()
+    }
+
+    fn user_version(conn: &Connection) -> usize {
+        conn.pragma_query_value(None, "user_version", |row| row.get(0))
+            .unwrap()
+    }
+
+    #[test]
+    fn migrates_a_new_database_to_the_latest_schema() {
+        let store = SqliteStore::in_memory().unwrap();
+        let conn = store.conn.lock().unwrap();
+        assert_eq!(user_version(&conn), SCHEMA_VERSION);
+        let count: i64 = conn
+            .query_row(
+                "SELECT count(*) FROM sqlite_master WHERE name = 'metric_rollups'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 1);
+    }
+
+    #[test]
+    fn migrates_an_older_database_and_keeps_its_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        let job = serde_json::from_str::<Vec<serde_json::Value>>(&jobs_json("Restart"))
+            .unwrap()
+            .remove(0);
+        conn.execute(
+            "INSERT INTO scheduled_jobs (id, position, data) VALUES ('job-1', 0, ?1)",
+            params![job.to_string()],
+        )
+        .unwrap();
+
+        let store = SqliteStore::with_connection(conn).unwrap();
+        assert_eq!(user_version(&store.conn.lock().unwrap()), SCHEMA_VERSION);
+        assert_eq!(store.load_jobs().unwrap()[0].name, "Restart");
+        assert!(store.load_setting("anything").unwrap().is_none());
+    }
+
+    #[test]
+    fn legacy_json_is_imported_once() {
+        let scratch = Scratch::new();
+        std::fs::write(scratch.0.join("schedules.json"), jobs_json("Restart")).unwrap();
+
+        let store = SqliteStore::open(&scratch.0.join(DB_FILE)).unwrap();
+        assert_eq!(store.load_jobs().unwrap()[0].name, "Restart");
+        assert!(!scratch.0.join("schedules.json").exists());
+        assert!(scratch.0.join("schedules.json.imported").exists());
+        drop(store);
+
+        // A JSON file showing up again later is left alone
+        std::fs::write(scratch.0.join("schedules.json"), jobs_json("Stale")).unwrap();
+        let store = SqliteStore::open(&scratch.0.join(DB_FILE)).unwrap();
+        assert_eq!(store.load_jobs().unwrap()[0].name, "Restart");
+        assert!(scratch.0.join("schedules.json").exists());
+    }
+
+    #[test]
+    fn restore_refuses_a_newer_schema() {
+        let scratch = Scratch::new();
+        let backup = scratch.0.join("backup.db");
+        let conn = Connection::open(&backup).unwrap();
+        conn.pragma_update(None, "user_version", SCHEMA_VERSION + 1)
+            .unwrap();
+        drop(conn);
+
+        let store = SqliteStore::in_memory().unwrap();
+        store
+            .save_jobs(&serde_json::from_str::<Vec<_>>(&jobs_json("Restart")).unwrap())
+            .unwrap();
+        let err = store.restore_from(&backup).unwrap_err();
+        assert!(err.to_string().contains("schema version"), "{err}");
+        assert_eq!(store.load_jobs().unwrap()[0].name, "Restart");
+    }
+
+    #[test]
+    fn restore_migrates_an_older_backup_and_keeps_sessions() {
+        let scratch = Scratch::new();
+        let backup = scratch.0.join("backup.db");
+        let conn = Connection::open(&backup).unwrap();
+        conn.execute_batch(MIGRATIONS[0]).unwrap();
+        conn.pragma_update(None, "user_version", 1).unwrap();
+        drop(conn);
+        let old = SqliteStore::with_connection(Connection::open(&backup).unwrap()).unwrap();
+        old.save_jobs(&serde_json::from_str::<Vec<_>>(&jobs_json("From backup")).unwrap())
+            .unwrap();
+        drop(old);
+
+        let store = SqliteStore::in_memory().unwrap();
+        store.write(|tx| {
+            tx.execute(
+                "INSERT INTO refresh_tokens (jti, family, expires_at, data) VALUES ('t', 'f', '', '{}')",
+                [],
+            )?;
+            Ok(())
+        })
+        .unwrap();
+        store.restore_from(&backup).unwrap();
+
+        assert_eq!(store.load_jobs().unwrap()[0].name, "From backup");
+        let sessions: i64 = store
+            .conn
+            .lock()
+            .unwrap()
+            .query_row("SELECT count(*) FROM refresh_tokens", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(sessions, 1);
+    }
+}
@@ -0,0 +1,513 @@
+use actix_files::NamedFile;
+use actix_web::http::header::{self, ContentDisposition, DispositionParam, DispositionType};
+use actix_web::{web, HttpMessage, HttpRequest, HttpResponse};
+use chrono::{DateTime, Utc};
+use futures_util::StreamExt;
+use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tokio::io::AsyncWriteExt;
+use utoipa::ToSchema;
+
+use crate::accesslog::client_ip;
+use crate::apierror::{ApiError, ErrorCode};
+use crate::audit;
+use crate::auth::Claims;
+use crate::config::AppConfig;
+use crate::filemanager::{get_base_dir, safe_resolve};
+use crate::registry::ServerRegistry;
+
+const DEFAULT_TTL_SECS: u64 = 3600;
+const MIN_TTL_SECS: u64 = 60;
+const MAX_TTL_SECS: u64 = 24 * 3600;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TransferMode {
+    Upload,
+    Download,
+}
+
+/// What a transfer token grants. Signed with a key derived from the JWT secret, so it
+/// can't pass as a login token (nor the other way round).
+#[derive(Debug, Serialize, Deserialize)]
+struct TransferClaims {
+    /// Token id, for single-use bookkeeping and the audit lines.
+    jti: String,
+    server_id: String,
+    /// Resolved absolute path; the token works for this file only.
+    path: String,
+    mode: TransferMode,
+    /// Panel user who issued the token.
+    issued_by: String,
+    exp: usize,
+    iat: usize,
+}
+
+fn signing_key(config: &AppConfig) -> Vec<u8> {
+    format!("{}:file-transfer", config.auth.jwt_secret).into_bytes()
+}
+
+/// Which upload tokens were used up or are in use. In memory: after a restart an
+/// unexpired upload token works once more.
+pub struct TransferTokens {
+    /// Completed uploads by token id, with the token's expiry.
+    used: Mutex<HashMap<String, usize>>,
+    /// Uploads with a request in flight; a token takes one request at a time.
+    busy: Mutex<HashSet<String>>,
+}
+
+impl TransferTokens {
+    pub fn new() -> Self {
+        Self {
+            used: Mutex::new(HashMap::new()),
+            busy: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn begin(self: &Arc<Self>, jti: &str) -> Result<UploadGuard, ApiError> {
+        if self.used.lock().unwrap().contains_key(jti) {
+            return Err(ApiError::conflict("This upload token was already used"));
+        }
+        if !self.busy.lock().unwrap().insert(jti.to_string()) {
+            return Err(ApiError::conflict(
+                "An upload with this token is in progress",
+            ));
+        }
+        Ok(UploadGuard {
+            tokens: self.clone(),
+            jti: jti.to_string(),
+        })
+    }
+
+    fn finish(&self, jti: &str, exp: usize) {
+        let now = Utc::now().timestamp() as usize;
+        let mut used = self.used.lock().unwrap();
+        used.retain(|_, exp| *exp > now);
+        used.insert(jti.to_string(), exp);
+    }
+}
+
+/// Marks an upload token in use until dropped.
+struct UploadGuard {
+    tokens: Arc<TransferTokens>,
+    jti: String,
+}
+
+impl Drop for UploadGuard {
+    fn drop(&mut self) {
+        self.tokens.busy.lock().unwrap().remove(&self.jti);
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferTokenRequest {
+    /// File to download, or to create or replace on upload; relative to the server directory.
+    pub path: String,
+    pub mode: TransferMode,
+    /// Lifetime in seconds (default 3600, 60 to 86400).
+    pub ttl_secs: Option<u64>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct TransferTokenResponse {
+    pub token: String,
+    /// Relative URL to GET (download) or PUT (upload); needs no other credentials.
+    pub url: String,
+    pub mode: TransferMode,
+    pub path: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// POST /api/servers/{server_id}/files/transfer-token
+///
+/// Upload tokens work until one upload completes; download tokens until they expire.
+#[utoipa::path(
+    post,
+    path = "/api/servers/{server_id}/files/transfer-token",
+    tag = "files",
+    params(("server_id" = String, Path, description = "Server id")),
+    request_body = TransferTokenRequest,
+    responses(
+        (status = 200, body = TransferTokenResponse),
+        (status = 400, description = "Upload target is a directory", body = ApiError),
+        (status = 403, description = "Path escapes the server directory", body = ApiError),
+        (status = 404, description = "Server or file not found", body = ApiError),
+    )
+)]
+pub async fn create_transfer_token(
+    req: HttpRequest,
+    server_id: web::Path<String>,
+    body: web::Json<TransferTokenRequest>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let base_dir = get_base_dir(&server_id, &registry).await?;
+    let path = safe_resolve(&base_dir, &body.path).map_err(ApiError::path_forbidden)?;
+    match body.mode {
+        TransferMode::Download if !path.is_file() => {
+            return Err(ApiError::not_found("File not found"));
+        }
+        TransferMode::Upload if path.is_dir() => {
+            return Err(ApiError::validation("Upload target is a directory"));
+        }
+        _ => {}
+    }
+
+    let issued_by = req
+        .extensions()
+        .get::<Claims>()
+        .map(|c| c.sub.clone())
+        .unwrap_or_default();
+    let ttl = body
+        .ttl_secs
+        .unwrap_or(DEFAULT_TTL_SECS)
+        .clamp(MIN_TTL_SECS, MAX_TTL_SECS);
+    let now = Utc::now();
+    let expires_at = now + chrono::Duration::seconds(ttl as i64);
+    let claims = TransferClaims {
+        jti: uuid::Uuid::new_v4().to_string(),
+        server_id: server_id.into_inner(),
+        path: path.display().to_string(),
+        mode: body.mode,
+        issued_by,
+        exp: expires_at.timestamp() as usize,
+        iat: now.timestamp() as usize,
+    };
+    let token = encode(
+        &Header::default(),
+        &claims,
+        &EncodingKey::from_secret(&signing_key(&config)),
+    )
+    .map_err(|e| ApiError::internal(format!("Token creation failed: {}", e)))?;
+
+    tracing::info!(
+        target: "audit",
+        token_id = %claims.jti,
+        server_id = %claims.server_id,
+        path = %claims.path,
+        mode = ?claims.mode,
+        user = %claims.issued_by,
+        expires_at = %expires_at,
+        "Transfer token issued"
+    );
+    audit::detail(&req, format!("{:?} {}", claims.mode, claims.path));
+    Ok(HttpResponse::Ok().json(TransferTokenResponse {
+        url: format!("/api/transfer/{}", token),
+        token,
+        mode: body.mode,
+        path: body.path.clone(),
+        expires_at,
+    }))
+}
+
+/// Decode `token` and check its file still resolves to the same path in its server's
+/// directory (the server may be gone, or a directory swapped for a symlink).
+async fn check_token(
+    token: &str,
+    registry: &Arc<ServerRegistry>,
+    config: &AppConfig,
+) -> Result<TransferClaims, ApiError> {
+    let claims = decode::<TransferClaims>(
+        token,
+        &DecodingKey::from_secret(&signing_key(config)),
+        &Validation::default(),
+    )
+    .map_err(|_| ApiError::unauthorized("Invalid or expired transfer token"))?
+    .claims;
+
+    let base_dir = get_base_dir(&claims.server_id, registry).await?;
+    let base = Path::new(&base_dir);
+    let base = base.canonicalize().unwrap_or_else(|_| base.to_path_buf());
+    let relative = Path::new(&claims.path)
+        .strip_prefix(&base)
+        .map_err(|_| ApiError::path_forbidden("Path is outside the server directory"))?;
+    let resolved =
+        safe_resolve(&base_dir, &relative.to_string_lossy()).map_err(ApiError::path_forbidden)?;
+    if resolved != Path::new(&claims.path) {
+        return Err(ApiError::path_forbidden(
+            "Path no longer resolves to the same file",
+        ));
+    }
+    Ok(claims)
+}
+
+fn wrong_mode(claims: &TransferClaims) -> ApiError {
+    ApiError::unauthorized(format!(
+        "This token is for {}s",
+        match claims.mode {
+            TransferMode::Upload => "upload",
+            TransferMode::Download => "download",
+        }
+    ))
+}
+
+fn audit_use(req: &HttpRequest, config: &AppConfig, claims: &TransferClaims, outcome: &str) {
+    tracing::info!(
+        target: "audit",
+        token_id = %claims.jti,
+        server_id = %claims.server_id,
+        path = %claims.path,
+        mode = ?claims.mode,
+        issued_by = %claims.issued_by,
+        client_ip = client_ip(req, &config.panel).as_deref(),
+        range = req.headers().get(header::RANGE).and_then(|v| v.to_str().ok()),
+        "Transfer token used: {}",
+        outcome
+    );
+}
+
+/// GET /api/transfer/{token}
+///
+/// Download the token's file. Supports Range requests for resuming.
+#[utoipa::path(
+    get,
+    path = "/api/transfer/{token}",
+    tag = "files",
+    security(),
+    params(("token" = String, Path, description = "Download token")),
+    responses(
+        (status = 200, description = "File contents", content_type = "application/octet-stream"),
+        (status = 206, description = "Requested range of the file"),
+        (status = 401, description = "Invalid, expired or upload token", body = ApiError),
+        (status = 404, description = "File no longer exists", body = ApiError),
+    )
+)]
+pub async fn download(
+    req: HttpRequest,
+    token: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = check_token(&token, &registry, &config).await?;
+    if claims.mode != TransferMode::Download {
+        return Err(wrong_mode(&claims));
+    }
+    let file = NamedFile::open_async(&claims.path).await.map_err(|_| {
+        audit_use(&req, &config, &claims, "file not found");
+        ApiError::not_found("File not found")
+    })?;
+    audit_use(&req, &config, &claims, "download");
+
+    let filename = Path::new(&claims.path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "download".to_string());
+    Ok(file
+        .set_content_disposition(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(filename)],
+        })
+        .into_response(&req))
+}
+
+/// Uploads are written next to the target and moved into place once complete, so the
+/// server never sees a half-written file.
+fn partial_path(path: &str) -> PathBuf {
+    PathBuf::from(format!("{}.part", path))
+}
+
+fn partial_len(path: &str) -> u64 {
+    std::fs::metadata(partial_path(path)).map_or(0, |m| m.len())
+}
+
+/// `Content-Range: bytes <first>-<last>/<total>` of a resumed upload chunk.
+fn parse_content_range(value: &str) -> Option<(u64, u64, u64)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (first, last) = range.split_once('-')?;
+    let (first, last, total) = (first.parse().ok()?, last.parse().ok()?, total.parse().ok()?);
+    (first <= last && last < total).then_some((first, last, total))
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadStatus {
+    /// Bytes received so far; resume from here.
+    pub received: u64,
+    /// The file is in place and the token used up.
+    pub complete: bool,
+}
+
+/// HEAD /api/transfer/{token}
+///
+/// For upload tokens, `Upload-Offset` is the number of bytes received so far: send the
+/// rest with `Content-Range: bytes <offset>-<last>/<total>`. Download tokens get the
+/// file's headers.
+#[utoipa::path(
+    head,
+    path = "/api/transfer/{token}",
+    tag = "files",
+    security(),
+    params(("token" = String, Path, description = "Upload or download token")),
+    responses(
+        (status = 200, description = "Upload progress in `Upload-Offset`, or the file's headers"),
+        (status = 401, description = "Invalid or expired token", body = ApiError),
+        (status = 409, description = "Upload token already used", body = ApiError),
+    )
+)]
+pub async fn transfer_status(
+    req: HttpRequest,
+    token: web::Path<String>,
+    tokens: web::Data<Arc<TransferTokens>>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = check_token(&token, &registry, &config).await?;
+    if claims.mode == TransferMode::Download {
+        let file = NamedFile::open_async(&claims.path)
+            .await
+            .map_err(|_| ApiError::not_found("File not found"))?;
+        return Ok(file.into_response(&req));
+    }
+    if tokens.used.lock().unwrap().contains_key(&claims.jti) {
+        return Err(ApiError::conflict("This upload token was already used"));
+    }
+    Ok(HttpResponse::Ok()
+        .insert_header(("Upload-Offset", partial_len(&claims.path).to_string()))
+        .finish())
+}
+
+/// PUT /api/transfer/{token}
+///
+/// Upload the token's file as the raw request body. Send it whole, or in chunks with
+/// `Content-Range: bytes <first>-<last>/<total>` starting at the current `Upload-Offset`;
+/// an interrupted upload resumes the same way. The file replaces the target when the last
+/// byte arrives, and the token is used up.
+#[utoipa::path(
+    put,
+    path = "/api/transfer/{token}",
+    tag = "files",
+    security(),
+    params(("token" = String, Path, description = "Upload token")),
+    request_body(content_type = "application/octet-stream", description = "File contents, or one chunk of them"),
+    responses(
+        (status = 200, body = UploadStatus),
+        (status = 400, description = "Bad Content-Range, or larger than `limits.transfer_max_bytes`", body = ApiError),
+        (status = 401, description = "Invalid, expired or download token", body = ApiError),
+        (status = 409, description = "Token used up or busy, or the chunk doesn't start at `details.offset`", body = ApiError),
+    )
+)]
+pub async fn upload(
+    req: HttpRequest,
+    token: web::Path<String>,
+    mut payload: web::Payload,
+    tokens: web::Data<Arc<TransferTokens>>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    config: web::Data<AppConfig>,
+) -> Result<HttpResponse, ApiError> {
+    let claims = check_token(&token, &registry, &config).await?;
+    if claims.mode != TransferMode::Upload {
+        return Err(wrong_mode(&claims));
+    }
+    let _guard = tokens.begin(&claims.jti)?;
+
+    let max_bytes = config.limits.transfer_max_bytes;
+    let range = match req.headers().get(header::CONTENT_RANGE) {
+        Some(value) => Some(
+            value
+                .to_str()
+                .ok()
+                .and_then(parse_content_range)
+                .ok_or_else(|| ApiError::validation("Invalid Content-Range"))?,
+        ),
+        None => None,
+    };
+    if range.is_some_and(|(_, _, total)| total > max_bytes) {
+        return Err(ApiError::validation(format!(
+            "File exceeds {} bytes",
+            max_bytes
+        )));
+    }
+
+    let part = partial_path(&claims.path);
+    let offset = match range {
+        Some((first, _, _)) => {
+            let received = partial_len(&claims.path);
+            if first != received {
+                return Err(ApiError::new(
+                    ErrorCode::Conflict,
+                    format!("Chunk starts at {}, expected {}", first, received),
+                )
+                .with_detail("offset", received));
+            }
+            first
+        }
+        None => 0,
+    };
+    let mut file = tokio::fs::OpenOptions::new()
+        .create(true)
+        .write(true)
+        .append(offset > 0)
+        .truncate(offset == 0)
+        .open(&part)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to open upload file: {}", e)))?;
+
+    // Stream to disk; whatever arrived stays in the .part file if the client goes away
+    let mut received = offset;
+    let mut failure = None;
+    while let Some(chunk) = payload.next().await {
+        let chunk = match chunk {
+            Ok(chunk) => chunk,
+            Err(e) => {
+                failure = Some(ApiError::validation(format!("Upload interrupted: {}", e)));
+                break;
+            }
+        };
+        received += chunk.len() as u64;
+        if received > max_bytes {
+            failure = Some(ApiError::validation(format!(
+                "File exceeds {} bytes",
+                max_bytes
+            )));
+            break;
+        }
+        if let Err(e) = file.write_all(&chunk).await {
+            failure = Some(ApiError::internal(format!("Failed to write upload: {}", e)));
+            break;
+        }
+    }
+    if let Err(e) = file.flush().await {
+        failure.get_or_insert(ApiError::internal(format!("Failed to write upload: {}", e)));
+    }
+    drop(file);
+    if let Some(e) = failure {
+        audit_use(
+            &req,
+            &config,
+            &claims,
+            &format!("upload failed at {} bytes", received),
+        );
+        return Err(e);
+    }
+
+    let complete = match range {
+        Some((_, _, total)) => received >= total,
+        None => true,
+    };
+    if !complete {
+        audit_use(
+            &req,
+            &config,
+            &claims,
+            &format!("upload chunk, {} bytes", received),
+        );
+        return Ok(HttpResponse::Ok().json(UploadStatus { received, complete }));
+    }
+
+    tokio::fs::rename(&part, &claims.path)
+        .await
+        .map_err(|e| ApiError::internal(format!("Failed to move upload into place: {}", e)))?;
+    tokens.finish(&claims.jti, claims.exp);
+    audit_use(
+        &req,
+        &config,
+        &claims,
+        &format!("upload complete, {} bytes", received),
+    );
+    Ok(HttpResponse::Ok().json(UploadStatus { received, complete }))
+}
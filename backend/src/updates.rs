@@ -0,0 +1,661 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::sync::RwLock;
+use tokio::time::{interval, Duration};
+use utoipa::ToSchema;
+
+use crate::config::{AppConfig, UpdatesConfig};
+use crate::diagnostics;
+use crate::events::PanelEvent;
+use crate::map::MapImageCache;
+use crate::registry::{ProvisioningStatus, ServerRegistry};
+use crate::scheduler::Scheduler;
+use crate::shutdown::Shutdown;
+
+/// Steam app id of the Rust dedicated server.
+pub const RUST_SERVER_APP_ID: &str = "258550";
+
+/// steamcmd logs in and refreshes its app info cache first, which can be slow.
+const STEAMCMD_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// A value in Valve's KeyValues text format (.acf/.vdf): a string or a nested section.
+#[derive(Debug, Clone, PartialEq)]
+pub enum AcfValue {
+    Text(String),
+    Section(AcfSection),
+}
+
+/// Entries of one `{ ... }` block, in file order.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct AcfSection {
+    pub entries: Vec<(String, AcfValue)>,
+}
+
+impl AcfSection {
+    /// First entry named `key`; Valve treats keys case-insensitively.
+    pub fn get(&self, key: &str) -> Option<&AcfValue> {
+        self.entries
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(key))
+            .map(|(_, v)| v)
+    }
+
+    pub fn section(&self, key: &str) -> Option<&AcfSection> {
+        match self.get(key)? {
+            AcfValue::Section(section) => Some(section),
+            AcfValue::Text(_) => None,
+        }
+    }
+
+    pub fn text(&self, key: &str) -> Option<&str> {
+        match self.get(key)? {
+            AcfValue::Text(text) => Some(text),
+            AcfValue::Section(_) => None,
+        }
+    }
+
+    /// Text at the end of a chain of nested sections, e.g. `["AppState", "buildid"]`.
+    pub fn lookup(&self, path: &[&str]) -> Option<&str> {
+        let (last, sections) = path.split_last()?;
+        let mut section = self;
+        for key in sections {
+            section = section.section(key)?;
+        }
+        section.text(last)
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Token {
+    Open,
+    Close,
+    Str(String),
+}
+
+/// Deeper nesting is rejected rather than recursed into; real manifests go about 6 deep.
+const MAX_ACF_DEPTH: usize = 64;
+
+struct AcfParser<'a> {
+    chars: std::iter::Peekable<std::str::Chars<'a>>,
+    line: usize,
+    depth: usize,
+}
+
+impl<'a> AcfParser<'a> {
+    fn new(input: &'a str) -> Self {
+        Self {
+            chars: input.chars().peekable(),
+            line: 1,
+            depth: 0,
+        }
+    }
+
+    fn next_token(&mut self) -> Result<Option<Token>, String> {
+        loop {
+            match self.chars.peek() {
+                None => return Ok(None),
+                Some('\n') => {
+                    self.line += 1;
+                    self.chars.next();
+                }
+                Some(c) if c.is_whitespace() => {
+                    self.chars.next();
+                }
+                Some('/') => {
+                    self.chars.next();
+                    if self.chars.peek() != Some(&'/') {
+                        return Err(format!("line {}: unexpected '/'", self.line));
+                    }
+                    while self.chars.peek().is_some_and(|c| *c != '\n') {
+                        self.chars.next();
+                    }
+                }
+                Some('{') => {
+                    self.chars.next();
+                    return Ok(Some(Token::Open));
+                }
+                Some('}') => {
+                    self.chars.next();
+                    return Ok(Some(Token::Close));
+                }
+                Some('"') => {
+                    self.chars.next();
+                    return self.quoted().map(|s| Some(Token::Str(s)));
+                }
+                Some(_) => return Ok(Some(Token::Str(self.bare()))),
+            }
+        }
+    }
+
+    fn quoted(&mut self) -> Result<String, String> {
+        let start = self.line;
+        let mut text = String::new();
+        loop {
+            match self.chars.next() {
+                None => return Err(format!("line {}: unterminated string", start)),
+                Some('"') => return Ok(text),
+                Some('\\') => match self.chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some(c) => text.push(c),
+                    None => return Err(format!("line {}: unterminated string", start)),
+                },
+                Some(c) => {
+                    if c == '\n' {
+                        self.line += 1;
+                    }
+                    text.push(c);
+                }
+            }
+        }
+    }
+
+    fn bare(&mut self) -> String {
+        let mut text = String::new();
+        while let Some(&c) = self.chars.peek() {
+            if c.is_whitespace() || matches!(c, '{' | '}' | '"') {
+                break;
+            }
+            text.push(c);
+            self.chars.next();
+        }
+        text
+    }
+
+    /// One `key value` or `key { ... }` pair; None at the end of input or of a section.
+    fn entry(&mut self) -> Result<Option<(String, AcfValue)>, String> {
+        let key = match self.next_token()? {
+            None | Some(Token::Close) => return Ok(None),
+            Some(Token::Open) => return Err(format!("line {}: expected a key", self.line)),
+            Some(Token::Str(key)) => key,
+        };
+        let value = self.value(&key)?;
+        Ok(Some((key, value)))
+    }
+
+    fn value(&mut self, key: &str) -> Result<AcfValue, String> {
+        match self.next_token()? {
+            Some(Token::Str(text)) => Ok(AcfValue::Text(text)),
+            Some(Token::Open) => {
+                if self.depth == MAX_ACF_DEPTH {
+                    return Err(format!("line {}: sections nested too deeply", self.line));
+                }
+                self.depth += 1;
+                let section = self.section(true)?;
+                self.depth -= 1;
+                Ok(AcfValue::Section(section))
+            }
+            _ => Err(format!("line {}: '{}' has no value", self.line, key)),
+        }
+    }
+
+    fn section(&mut self, nested: bool) -> Result<AcfSection, String> {
+        let mut section = AcfSection::default();
+        loop {
+            let key = match self.next_token()? {
+                Some(Token::Str(key)) => key,
+                Some(Token::Close) if nested => return Ok(section),
+                None if !nested => return Ok(section),
+                Some(Token::Close) => return Err(format!("line {}: unmatched '}}'", self.line)),
+                None => return Err("unexpected end of input inside a section".to_string()),
+                Some(Token::Open) => return Err(format!("line {}: expected a key", self.line)),
+            };
+            let value = self.value(&key)?;
+            section.entries.push((key, value));
+        }
+    }
+}
+
+/// Parse a whole .acf/.vdf document.
+pub fn parse_acf(input: &str) -> Result<AcfSection, String> {
+    AcfParser::new(input).section(false)
+}
+
+/// Build id of the installed server, from the app manifest steamcmd keeps next to the
+/// files it downloaded. Ok(None) when the manifest doesn't exist (not installed yet).
+pub fn installed_build(server_files: &str) -> Result<Option<u64>, String> {
+    let path = std::path::Path::new(server_files)
+        .join("steamapps")
+        .join(format!("appmanifest_{}.acf", RUST_SERVER_APP_ID));
+    let text = match std::fs::read_to_string(&path) {
+        Ok(text) => text,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(format!("{}: {}", path.display(), e)),
+    };
+    let manifest = parse_acf(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let build = manifest
+        .lookup(&["AppState", "buildid"])
+        .ok_or_else(|| format!("{}: no AppState.buildid", path.display()))?;
+    build
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("{}: invalid buildid '{}'", path.display(), build))
+}
+
+/// Current build id of `branch` on Steam, from `steamcmd +app_info_print`.
+async fn latest_build(config: &UpdatesConfig) -> Result<u64, String> {
+    let output = tokio::process::Command::new(&config.steamcmd_path)
+        .args(["+login", "anonymous", "+app_info_update", "1"])
+        .args(["+app_info_print", RUST_SERVER_APP_ID, "+quit"])
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(STEAMCMD_TIMEOUT, output)
+        .await
+        .map_err(|_| "steamcmd timed out".to_string())?
+        .map_err(|e| format!("can't run {}: {}", config.steamcmd_path, e))?;
+    build_from_app_info(&String::from_utf8_lossy(&output.stdout), &config.branch)
+}
+
+/// Build id of `branch` in the output of `steamcmd +app_info_print`.
+fn build_from_app_info(stdout: &str, branch: &str) -> Result<u64, String> {
+    // The app info block follows steamcmd's own log lines
+    let quoted_id = format!("\"{}\"", RUST_SERVER_APP_ID);
+    let start = stdout
+        .find(&quoted_id)
+        .ok_or("steamcmd printed no app info")?;
+    let (_, info) = AcfParser::new(&stdout[start..])
+        .entry()
+        .map_err(|e| format!("app info: {}", e))?
+        .ok_or("steamcmd printed no app info")?;
+    let AcfValue::Section(info) = info else {
+        return Err("app info is not a section".to_string());
+    };
+    let build = info
+        .lookup(&["depots", "branches", branch, "buildid"])
+        .ok_or_else(|| format!("no build id for branch '{}'", branch))?;
+    build
+        .parse()
+        .map_err(|_| format!("invalid build id '{}'", build))
+}
+
+#[derive(Default)]
+struct WatchState {
+    latest_build: Option<u64>,
+    checked_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    /// Latest build each server was reported out of date for, so each release is
+    /// announced (and acted on) once per server.
+    notified: HashMap<String, u64>,
+}
+
+pub struct UpdateWatcher {
+    state: RwLock<WatchState>,
+}
+
+impl UpdateWatcher {
+    pub fn new() -> Self {
+        Self {
+            state: RwLock::new(WatchState::default()),
+        }
+    }
+}
+
+pub fn spawn_watcher(
+    watcher: Arc<UpdateWatcher>,
+    config: UpdatesConfig,
+    registry: Arc<ServerRegistry>,
+    scheduler: Arc<Scheduler>,
+    map_cache: Arc<MapImageCache>,
+    shutdown: Shutdown,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        if !config.enabled {
+            return;
+        }
+        let mut ticker = interval(Duration::from_secs(config.interval_mins.max(1) * 60));
+        loop {
+            tokio::select! {
+                _ = ticker.tick() => {}
+                _ = shutdown.signal() => break,
+            }
+            let latest = match latest_build(&config).await {
+                Ok(build) => build,
+                Err(e) => {
+                    tracing::warn!("Update check failed: {}", e);
+                    diagnostics::report_error("update watcher", &e);
+                    watcher.state.write().await.last_error = Some(e);
+                    continue;
+                }
+            };
+            diagnostics::report_ok("update watcher");
+            {
+                let mut state = watcher.state.write().await;
+                state.latest_build = Some(latest);
+                state.checked_at = Some(Utc::now());
+                state.last_error = None;
+            }
+            check_servers(&watcher, latest, &registry, &scheduler, &map_cache).await;
+        }
+    })
+}
+
+/// Publish update_available for servers behind `latest` that weren't told about it yet,
+/// and start their opted-in AutoUpdate jobs.
+async fn check_servers(
+    watcher: &UpdateWatcher,
+    latest: u64,
+    registry: &Arc<ServerRegistry>,
+    scheduler: &Arc<Scheduler>,
+    map_cache: &Arc<MapImageCache>,
+) {
+    for def in registry.all_definitions().await {
+        if def.provisioning_status != ProvisioningStatus::Ready {
+            continue;
+        }
+        let Some(config) = registry.get_config(&def.id).await else {
+            continue;
+        };
+        let installed = match installed_build(&config.paths.server_files) {
+            Ok(Some(build)) => build,
+            Ok(None) => continue,
+            Err(e) => {
+                tracing::warn!("Update check for '{}': {}", def.id, e);
+                continue;
+            }
+        };
+        if installed >= latest {
+            continue;
+        }
+        {
+            let mut state = watcher.state.write().await;
+            if state.notified.get(&def.id) == Some(&latest) {
+                continue;
+            }
+            state.notified.insert(def.id.clone(), latest);
+        }
+
+        tracing::info!(
+            "Update available for '{}': build {} installed, {} released",
+            def.id,
+            installed,
+            latest
+        );
+        registry.events.publish(PanelEvent::UpdateAvailable {
+            server_id: def.id.clone(),
+        });
+        let started =
+            crate::scheduler::run_release_updates(scheduler, registry, map_cache, &def.id).await;
+        if !started.is_empty() {
+            tracing::info!(
+                "Started auto-update for '{}': {}",
+                def.id,
+                started.join(", ")
+            );
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerUpdateStatus {
+    pub server_id: String,
+    pub name: String,
+    /// From the server's appmanifest; null if not installed or unreadable.
+    pub installed_build: Option<u64>,
+    /// Null until both builds are known.
+    pub update_available: Option<bool>,
+    pub error: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct UpdatesResponse {
+    /// Whether the background watcher runs (`updates.enabled`).
+    pub enabled: bool,
+    pub branch: String,
+    pub latest_build: Option<u64>,
+    pub checked_at: Option<DateTime<Utc>>,
+    /// Why the last check failed, if it did.
+    pub last_error: Option<String>,
+    pub servers: Vec<ServerUpdateStatus>,
+}
+
+/// GET /api/updates
+///
+/// The latest build is from the watcher's last successful check; installed builds are
+/// read from each server's appmanifest now.
+#[utoipa::path(
+    get,
+    path = "/api/updates",
+    tag = "updates",
+    responses((status = 200, body = UpdatesResponse))
+)]
+pub async fn get_updates(
+    config: web::Data<AppConfig>,
+    watcher: web::Data<Arc<UpdateWatcher>>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> HttpResponse {
+    let (latest_build, checked_at, last_error) = {
+        let state = watcher.state.read().await;
+        (
+            state.latest_build,
+            state.checked_at,
+            state.last_error.clone(),
+        )
+    };
+
+    let mut servers = Vec::new();
+    for def in registry.all_definitions().await {
+        let Some(server_config) = registry.get_config(&def.id).await else {
+            continue;
+        };
+        let (installed_build, error) = match installed_build(&server_config.paths.server_files) {
+            Ok(build) => (build, None),
+            Err(e) => (None, Some(e)),
+        };
+        servers.push(ServerUpdateStatus {
+            server_id: def.id.clone(),
+            name: def.name.clone(),
+            installed_build,
+            update_available: installed_build
+                .zip(latest_build)
+                .map(|(installed, latest)| installed < latest),
+            error,
+        });
+    }
+
+    HttpResponse::Ok().json(UpdatesResponse {
+        enabled: config.updates.enabled,
+        branch: config.updates.branch.clone(),
+        latest_build,
+        checked_at,
+        last_error,
+        servers,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    const MANIFEST: &str = r#"
+"AppState"
+{
+	"appid"		"258550"
+	"name"		"Rust Dedicated Server"
+	"buildid"		"17023745"
+	// steamcmd doesn't write comments, but Valve's tools accept them
+	"InstalledDepots"
+	{
+		"258552"
+		{
+			"manifest"		"6457386218358209132"
+			"size"		"10275383421"
+		}
+	}
+	"UserConfig"
+	{
+		"betakey"		"staging"
+		"note"		"say \"hi\" \\ path\ttab"
+	}
+}
+"#;
+
+    const APP_INFO: &str = r#"Redirecting stderr to '/home/rust/Steam/logs/stderr.txt'
+Loading Steam API...OK
+Connecting anonymously to Steam Public...OK
+AppID : 258550, change number : 29000000/0, last change : Tue Oct 14 2026
+"258550"
+{
+	"common"
+	{
+		"name"		"Rust Dedicated Server"
+	}
+	"depots"
+	{
+		"branches"
+		{
+			"public"
+			{
+				"buildid"		"17023745"
+				"timeupdated"		"1791900000"
+			}
+			"staging"
+			{
+				"buildid"		"17040001"
+			}
+		}
+	}
+}
+"#;
+
+    struct Scratch(PathBuf);
+
+    impl Scratch {
+        fn with_manifest(text: &str) -> Self {
+            let dir = std::env::temp_dir().join(format!("updates-test-{}", uuid::Uuid::new_v4()));
+            std::fs::create_dir_all(dir.join("steamapps")).unwrap();
+            let manifest = format!("steamapps/appmanifest_{}.acf", RUST_SERVER_APP_ID);
+            std::fs::write(dir.join(manifest), text).unwrap();
+            Self(dir)
+        }
+
+        fn path(&self) -> &str {
+            self.0.to_str().unwrap()
+        }
+    }
+
+    impl Drop for Scratch {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn nested_blocks_and_escapes_are_parsed() {
+        let manifest = parse_acf(MANIFEST).unwrap();
+        assert_eq!(manifest.lookup(&["AppState", "buildid"]), Some("17023745"));
+        assert_eq!(manifest.lookup(&["appstate", "BUILDID"]), Some("17023745"));
+        assert_eq!(
+            manifest.lookup(&["AppState", "InstalledDepots", "258552", "size"]),
+            Some("10275383421")
+        );
+        assert_eq!(
+            manifest.lookup(&["AppState", "UserConfig", "note"]),
+            Some("say \"hi\" \\ path\ttab")
+        );
+        let state = manifest.section("AppState").unwrap();
+        let keys: Vec<_> = state.entries.iter().map(|(k, _)| k.as_str()).collect();
+        assert_eq!(
+            keys,
+            ["appid", "name", "buildid", "InstalledDepots", "UserConfig"]
+        );
+        // A section is not text and vice versa
+        assert_eq!(manifest.lookup(&["AppState", "UserConfig"]), None);
+        assert!(state.section("buildid").is_none());
+
+        let bare = parse_acf("key value\nother { inner 1 }").unwrap();
+        assert_eq!(bare.text("key"), Some("value"));
+        assert_eq!(bare.lookup(&["other", "inner"]), Some("1"));
+        assert_eq!(parse_acf("").unwrap(), AcfSection::default());
+    }
+
+    #[test]
+    fn build_ids_come_from_the_manifest_and_app_info() {
+        let installed = Scratch::with_manifest(MANIFEST);
+        assert_eq!(installed_build(installed.path()), Ok(Some(17023745)));
+
+        let missing = std::env::temp_dir().join(format!("updates-test-{}", uuid::Uuid::new_v4()));
+        assert_eq!(installed_build(missing.to_str().unwrap()), Ok(None));
+
+        assert_eq!(build_from_app_info(APP_INFO, "public"), Ok(17023745));
+        assert_eq!(build_from_app_info(APP_INFO, "staging"), Ok(17040001));
+        assert_eq!(
+            build_from_app_info(APP_INFO, "aux01"),
+            Err("no build id for branch 'aux01'".to_string())
+        );
+        assert_eq!(
+            build_from_app_info("Loading Steam API...OK\n", "public"),
+            Err("steamcmd printed no app info".to_string())
+        );
+    }
+
+    #[test]
+    fn missing_or_invalid_build_ids_are_errors() {
+        let no_build = Scratch::with_manifest("\"AppState\" { \"appid\" \"258550\" }");
+        let error = installed_build(no_build.path()).unwrap_err();
+        assert!(error.ends_with(": no AppState.buildid"), "{}", error);
+
+        let bad_build = Scratch::with_manifest("\"AppState\" { \"buildid\" \"17x\" }");
+        let error = installed_build(bad_build.path()).unwrap_err();
+        assert!(error.ends_with(": invalid buildid '17x'"), "{}", error);
+
+        let truncated = Scratch::with_manifest("\"AppState\" { \"buildid\" \"170");
+        let error = installed_build(truncated.path()).unwrap_err();
+        assert!(
+            error.ends_with(": line 1: unterminated string"),
+            "{}",
+            error
+        );
+    }
+
+    #[test]
+    fn malformed_input_is_an_error_not_a_panic() {
+        let cases = [
+            ("\"AppState\" {", "unexpected end of input inside a section"),
+            (
+                "\"AppState\" { \"buildid\" \"1\" } }",
+                "line 1: unmatched '}'",
+            ),
+            ("{ \"a\" \"b\" }", "line 1: expected a key"),
+            ("\"a\" { { } }", "line 1: expected a key"),
+            ("\"key\"", "line 1: 'key' has no value"),
+            ("\"key\" }", "line 1: 'key' has no value"),
+            ("\"a\"\n\"b\" \"unterminated", "line 2: unterminated string"),
+            (
+                "\"a\" \"trailing backslash\\",
+                "line 1: unterminated string",
+            ),
+            ("\"a\" \"b\"\n/ comment", "line 2: unexpected '/'"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(parse_acf(input), Err(expected.to_string()), "{:?}", input);
+        }
+
+        let deep = format!("{}{}", "a {".repeat(100_000), "}".repeat(100_000));
+        assert_eq!(
+            parse_acf(&deep),
+            Err("line 1: sections nested too deeply".to_string())
+        );
+        let ok = format!(
+            "{}{}",
+            "a {".repeat(MAX_ACF_DEPTH),
+            "}".repeat(MAX_ACF_DEPTH)
+        );
+        assert!(parse_acf(&ok).is_ok());
+
+        for input in [
+            "\u{0}\u{ffff}",
+            "\"\u{1F980}\" \"🦀\"",
+            "}{",
+            "////",
+            "\\\\\\",
+        ] {
+            let _ = parse_acf(input);
+        }
+    }
+}
@@ -0,0 +1,344 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::apierror::ApiError;
+use crate::registry::ServerDefinition;
+
+pub const MIN_WORLD_SIZE: u32 = 1000;
+pub const MAX_WORLD_SIZE: u32 = 6000;
+pub const WORLD_SIZE_STEP: u32 = 50;
+pub const MAX_PLAYERS_LIMIT: u32 = 500;
+/// Rust stores the seed as a signed 32-bit integer.
+pub const MAX_SEED: u32 = i32::MAX as u32;
+
+/// Per-field validation failures, sent as VALIDATION_FAILED with the messages in
+/// `details.fields`.
+#[derive(Debug, Default, Serialize)]
+pub struct ValidationErrors {
+    error: String,
+    fields: BTreeMap<String, String>,
+}
+
+impl ValidationErrors {
+    pub fn new() -> Self {
+        Self {
+            error: "Validation failed".to_string(),
+            fields: BTreeMap::new(),
+        }
+    }
+
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        self.fields.insert(field.to_string(), message.into());
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fields.is_empty()
+    }
+
+    /// Flatten into a single line, e.g. for per-item import reports.
+    pub fn summary(&self) -> String {
+        self.fields
+            .iter()
+            .map(|(field, message)| format!("{}: {}", field, message))
+            .collect::<Vec<_>>()
+            .join("; ")
+    }
+}
+
+impl From<ValidationErrors> for ApiError {
+    fn from(errors: ValidationErrors) -> Self {
+        ApiError::validation(errors.error).with_detail("fields", errors.fields)
+    }
+}
+
+/// Server settings submitted on create, update or import. `None` means "not provided".
+#[derive(Debug, Default)]
+pub struct SettingsInput<'a> {
+    pub name: Option<&'a str>,
+    pub hostname: Option<&'a str>,
+    pub max_players: Option<u32>,
+    pub world_size: Option<u32>,
+    pub seed: Option<u32>,
+    pub tags: Option<&'a [String]>,
+}
+
+/// Normalized string settings produced by `validate_settings`.
+#[derive(Debug, Default)]
+pub struct ValidSettings {
+    pub name: Option<String>,
+    pub hostname: Option<String>,
+    pub tags: Option<Vec<String>>,
+}
+
+/// Validate server settings against the rules Rust and server.cfg impose.
+/// `existing` is used for name uniqueness; `exclude_id` skips the server being edited.
+pub fn validate_settings(
+    input: &SettingsInput,
+    existing: &[ServerDefinition],
+    exclude_id: Option<&str>,
+) -> Result<ValidSettings, ValidationErrors> {
+    let mut errors = ValidationErrors::new();
+    let mut valid = ValidSettings::default();
+
+    if let Some(name) = input.name {
+        match validate_name(name, existing, exclude_id) {
+            Ok(n) => valid.name = Some(n),
+            Err(e) => errors.add("name", e),
+        }
+    }
+    if let Some(hostname) = input.hostname {
+        match sanitize_hostname(hostname) {
+            Ok(h) => valid.hostname = Some(h),
+            Err(e) => errors.add("hostname", e),
+        }
+    }
+    if let Some(max_players) = input.max_players {
+        if !(1..=MAX_PLAYERS_LIMIT).contains(&max_players) {
+            errors.add(
+                "maxPlayers",
+                format!("Must be between 1 and {}", MAX_PLAYERS_LIMIT),
+            );
+        }
+    }
+    if let Some(world_size) = input.world_size {
+        if let Err(e) = validate_world_size(world_size) {
+            errors.add("worldSize", e);
+        }
+    }
+    if let Some(seed) = input.seed {
+        if let Err(e) = validate_seed(seed) {
+            errors.add("seed", e);
+        }
+    }
+    if let Some(tags) = input.tags {
+        match normalize_tags(tags) {
+            Ok(t) => valid.tags = Some(t),
+            Err(e) => errors.add("tags", e),
+        }
+    }
+
+    if errors.is_empty() {
+        Ok(valid)
+    } else {
+        Err(errors)
+    }
+}
+
+/// Display names: 1-64 characters of letters, digits, spaces and `-_.()'#`, unique
+/// (case-insensitive) across all definitions.
+pub fn validate_name(
+    name: &str,
+    existing: &[ServerDefinition],
+    exclude_id: Option<&str>,
+) -> Result<String, String> {
+    let name = name.trim();
+    if name.is_empty() || name.chars().count() > 64 {
+        return Err("Must be 1-64 characters".to_string());
+    }
+    let allowed = |c: char| c.is_alphanumeric() || " -_.()'#".contains(c);
+    if !name.chars().all(allowed) {
+        return Err("May only contain letters, digits, spaces and -_.()'#".to_string());
+    }
+    let taken = existing
+        .iter()
+        .filter(|d| Some(d.id.as_str()) != exclude_id)
+        .any(|d| d.name.eq_ignore_ascii_case(name));
+    if taken {
+        return Err(format!("A server named '{}' already exists", name));
+    }
+    Ok(name.to_string())
+}
+
+/// Strip characters that break server.cfg quoting or that Rust renders badly
+/// (quotes, backslashes, control characters, emoji outside the BMP).
+pub fn sanitize_hostname(hostname: &str) -> Result<String, String> {
+    let cleaned: String = hostname
+        .chars()
+        .filter(|c| !c.is_control() && *c != '"' && *c != '\\' && (*c as u32) <= 0xFFFF)
+        .collect();
+    let cleaned = cleaned.trim().to_string();
+    if cleaned.is_empty() || cleaned.chars().count() > 128 {
+        return Err("Must be 1-128 printable characters".to_string());
+    }
+    Ok(cleaned)
+}
+
+pub fn validate_world_size(world_size: u32) -> Result<(), String> {
+    if !(MIN_WORLD_SIZE..=MAX_WORLD_SIZE).contains(&world_size) {
+        return Err(format!(
+            "Must be between {} and {}",
+            MIN_WORLD_SIZE, MAX_WORLD_SIZE
+        ));
+    }
+    if !world_size.is_multiple_of(WORLD_SIZE_STEP) {
+        return Err(format!("Must be a multiple of {}", WORLD_SIZE_STEP));
+    }
+    Ok(())
+}
+
+/// RCON passwords are written to server.cfg and sent as a command argument, so they
+/// are kept to printable characters without quotes or spaces.
+pub fn validate_rcon_password(password: &str) -> Result<(), String> {
+    let valid_chars = password
+        .chars()
+        .all(|c| c.is_ascii_graphic() && c != '"' && c != '\'');
+    if !(8..=64).contains(&password.len()) || !valid_chars {
+        return Err("Must be 8-64 printable ASCII characters without quotes".to_string());
+    }
+    Ok(())
+}
+
+pub fn validate_seed(seed: u32) -> Result<(), String> {
+    if seed == 0 || seed > MAX_SEED {
+        return Err(format!("Must be between 1 and {}", MAX_SEED));
+    }
+    Ok(())
+}
+
+/// Normalize and validate server tags: lowercase, 1-32 chars of [a-z0-9-_], at most 10.
+pub fn normalize_tags(tags: &[String]) -> Result<Vec<String>, String> {
+    if tags.len() > 10 {
+        return Err("At most 10 tags are allowed".to_string());
+    }
+    let mut normalized: Vec<String> = Vec::with_capacity(tags.len());
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        let valid_chars = tag
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if tag.is_empty() || tag.len() > 32 || !valid_chars {
+            return Err(format!(
+                "Invalid tag '{}': use 1-32 characters of a-z, 0-9, '-' or '_'",
+                tag
+            ));
+        }
+        if !normalized.contains(&tag) {
+            normalized.push(tag);
+        }
+    }
+    Ok(normalized)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn world_size_accepts_the_bounds_on_the_step() {
+        for size in [
+            MIN_WORLD_SIZE,
+            MIN_WORLD_SIZE + WORLD_SIZE_STEP,
+            4250,
+            MAX_WORLD_SIZE,
+        ] {
+            assert_eq!(validate_world_size(size), Ok(()), "{}", size);
+        }
+        let out_of_range = "Must be between 1000 and 6000".to_string();
+        for size in [
+            0,
+            MIN_WORLD_SIZE - WORLD_SIZE_STEP,
+            MIN_WORLD_SIZE - 1,
+            MAX_WORLD_SIZE + 1,
+        ] {
+            assert_eq!(
+                validate_world_size(size),
+                Err(out_of_range.clone()),
+                "{}",
+                size
+            );
+        }
+        for size in [MAX_WORLD_SIZE + WORLD_SIZE_STEP, u32::MAX] {
+            assert_eq!(
+                validate_world_size(size),
+                Err(out_of_range.clone()),
+                "{}",
+                size
+            );
+        }
+        let off_step = "Must be a multiple of 50".to_string();
+        for size in [MIN_WORLD_SIZE + 1, 4049, 4051, MAX_WORLD_SIZE - 1] {
+            assert_eq!(validate_world_size(size), Err(off_step.clone()), "{}", size);
+        }
+    }
+
+    #[test]
+    fn seed_is_a_positive_signed_32_bit_integer() {
+        for seed in [1, 12345, MAX_SEED] {
+            assert_eq!(validate_seed(seed), Ok(()), "{}", seed);
+        }
+        for seed in [0, MAX_SEED + 1, u32::MAX] {
+            assert_eq!(
+                validate_seed(seed),
+                Err("Must be between 1 and 2147483647".to_string()),
+                "{}",
+                seed
+            );
+        }
+    }
+
+    #[test]
+    fn settings_report_every_field_out_of_bounds() {
+        let valid = SettingsInput {
+            max_players: Some(MAX_PLAYERS_LIMIT),
+            world_size: Some(MAX_WORLD_SIZE),
+            seed: Some(MAX_SEED),
+            ..Default::default()
+        };
+        assert!(validate_settings(&valid, &[], None).is_ok());
+        let minimal = SettingsInput {
+            max_players: Some(1),
+            world_size: Some(MIN_WORLD_SIZE),
+            seed: Some(1),
+            ..Default::default()
+        };
+        assert!(validate_settings(&minimal, &[], None).is_ok());
+
+        for (max_players, world_size, seed) in [
+            (0, 0, 0),
+            (MAX_PLAYERS_LIMIT + 1, MAX_WORLD_SIZE + 1, MAX_SEED + 1),
+            (u32::MAX, u32::MAX, u32::MAX),
+        ] {
+            let input = SettingsInput {
+                max_players: Some(max_players),
+                world_size: Some(world_size),
+                seed: Some(seed),
+                ..Default::default()
+            };
+            let errors = validate_settings(&input, &[], None).unwrap_err();
+            let fields: Vec<_> = errors.fields.keys().map(String::as_str).collect();
+            assert_eq!(fields, ["maxPlayers", "seed", "worldSize"]);
+            assert_eq!(errors.fields["maxPlayers"], "Must be between 1 and 500");
+        }
+
+        let off_step = SettingsInput {
+            world_size: Some(3999),
+            ..Default::default()
+        };
+        let errors = validate_settings(&off_step, &[], None).unwrap_err();
+        assert_eq!(errors.summary(), "worldSize: Must be a multiple of 50");
+    }
+
+    #[test]
+    fn text_fields_are_checked_at_their_length_limits() {
+        assert!(validate_name(&"a".repeat(64), &[], None).is_ok());
+        assert!(validate_name(&"a".repeat(65), &[], None).is_err());
+        assert!(validate_name("   ", &[], None).is_err());
+
+        assert!(sanitize_hostname(&"h".repeat(128)).is_ok());
+        assert!(sanitize_hostname(&"h".repeat(129)).is_err());
+        assert!(sanitize_hostname("\"\\\n").is_err());
+
+        assert!(validate_rcon_password(&"p".repeat(8)).is_ok());
+        assert!(validate_rcon_password(&"p".repeat(7)).is_err());
+        assert!(validate_rcon_password(&"p".repeat(64)).is_ok());
+        assert!(validate_rcon_password(&"p".repeat(65)).is_err());
+
+        let tags: Vec<String> = (0..10).map(|i| format!("tag-{}", i)).collect();
+        assert_eq!(normalize_tags(&tags).unwrap().len(), 10);
+        let too_many: Vec<String> = (0..11).map(|i| format!("tag-{}", i)).collect();
+        assert!(normalize_tags(&too_many).is_err());
+        assert!(normalize_tags(&["t".repeat(32)]).is_ok());
+        assert!(normalize_tags(&["t".repeat(33)]).is_err());
+        assert!(normalize_tags(&[String::new()]).is_err());
+    }
+}
@@ -0,0 +1,112 @@
+use serde::Deserialize;
+use std::time::Duration;
+
+/// Per-attempt timeout for webhook requests.
+const WEBHOOK_TIMEOUT: Duration = Duration::from_secs(10);
+/// Per-attempt timeout for file uploads (panel backups).
+const FILE_TIMEOUT: Duration = Duration::from_secs(120);
+const WEBHOOK_ATTEMPTS: u32 = 3;
+/// Delay before the first retry; doubled for each further attempt.
+const RETRY_DELAY: Duration = Duration::from_secs(2);
+
+/// Payload for Webhook jobs.
+///
+/// `message` may use `{server}`, `{server_id}`, `{job}`, `{result}` and `{time}`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookPayload {
+    pub url: String,
+    pub message: String,
+}
+
+impl WebhookPayload {
+    pub fn parse(payload: Option<&str>) -> Result<Self, String> {
+        let payload = payload.ok_or("Webhook jobs need a payload with url and message")?;
+        let parsed: Self = serde_json::from_str(payload)
+            .map_err(|e| format!("Invalid webhook payload: {}", e))?;
+        validate_url(&parsed.url)?;
+        if parsed.message.trim().is_empty() {
+            return Err("Webhook message must not be empty".to_string());
+        }
+        Ok(parsed)
+    }
+}
+
+pub fn validate_url(url: &str) -> Result<(), String> {
+    if url.starts_with("https://") || url.starts_with("http://") {
+        Ok(())
+    } else {
+        Err("Webhook URL must start with http:// or https://".to_string())
+    }
+}
+
+/// Replace `{name}` placeholders in `template`.
+pub fn render(template: &str, vars: &[(&str, &str)]) -> String {
+    vars.iter().fold(template.to_string(), |text, (name, value)| {
+        text.replace(&format!("{{{}}}", name), value)
+    })
+}
+
+/// POST `content` to a webhook, retrying on network errors and 5xx/429 responses.
+///
+/// The body carries both `content` (Discord) and `text` (Slack and most others).
+pub async fn send(url: &str, content: &str) -> Result<String, String> {
+    let body = serde_json::json!({ "content": content, "text": content });
+    post_json(url, &body).await
+}
+
+/// POST a JSON `body` to a webhook, retrying on network errors and 5xx/429 responses.
+pub async fn post_json(url: &str, body: &serde_json::Value) -> Result<String, String> {
+    post(url, WEBHOOK_TIMEOUT, |request| request.json(body)).await
+}
+
+/// POST a file as the raw request body, named in Content-Disposition, with the same
+/// retries as messages but more time per attempt.
+pub async fn post_file(
+    url: &str,
+    filename: &str,
+    content_type: &str,
+    data: bytes::Bytes,
+) -> Result<String, String> {
+    let disposition = format!("attachment; filename=\"{}\"", filename);
+    post(url, FILE_TIMEOUT, |request| {
+        request
+            .header(reqwest::header::CONTENT_TYPE, content_type)
+            .header(reqwest::header::CONTENT_DISPOSITION, &disposition)
+            .body(data.clone())
+    })
+    .await
+}
+
+async fn post(
+    url: &str,
+    timeout: Duration,
+    build: impl Fn(reqwest::RequestBuilder) -> reqwest::RequestBuilder,
+) -> Result<String, String> {
+    let client = reqwest::Client::builder()
+        .timeout(timeout)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    let mut delay = RETRY_DELAY;
+    let mut last_error = String::new();
+    for attempt in 1..=WEBHOOK_ATTEMPTS {
+        match build(client.post(url)).send().await {
+            Ok(resp) if resp.status().is_success() => {
+                return Ok(format!("Webhook delivered ({})", resp.status()));
+            }
+            Ok(resp) => {
+                let status = resp.status();
+                last_error = format!("Webhook returned {}", status);
+                if !status.is_server_error() && status.as_u16() != 429 {
+                    break;
+                }
+            }
+            Err(e) => last_error = format!("Webhook request failed: {}", e),
+        }
+        if attempt < WEBHOOK_ATTEMPTS {
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+        }
+    }
+    Err(last_error)
+}
@@ -1,26 +1,589 @@
-use actix_web::{web, HttpRequest, HttpResponse};
+use actix_web::http::header::{HeaderValue, SEC_WEBSOCKET_PROTOCOL};
+use actix_web::http::StatusCode;
+use actix_web::{web, FromRequest, HttpRequest, HttpResponse, ResponseError};
 use actix_ws::Message;
+use chrono::{DateTime, Utc};
 use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::future::{ready, Ready};
 use std::sync::Arc;
-use tokio::time::{interval, Duration};
+use tokio::sync::{broadcast, Notify};
+use tokio::time::{interval, Duration, Instant, Interval};
+use utoipa::ToSchema;
+use uuid::Uuid;
 
-use crate::auth::validate_token;
-use crate::config::AppConfig;
-use crate::monitor::{GameSnapshot, SystemMonitor, SystemSnapshot};
-use crate::registry::ServerRegistry;
+use crate::apierror::ApiError;
+use crate::auth::{validate_token, Claims, ADMIN_ROLE};
+use crate::config::{AppConfig, WebSocketConfig};
+use crate::activity::{ActivityFrame, ActivityItem};
+use crate::audit::{AuditEntry, AuditLog};
+use crate::commandpolicy;
+use crate::consolehistory::{ConsoleExchange, ConsoleHistory};
+use crate::events::{PanelEvent, TimedEvent};
+use crate::logs::{allowed_log_files, LogFollower};
+use crate::monitor::{GameMonitor, GameSnapshot, PlayerUpdate, SystemMonitor, SystemSnapshot};
+use crate::rcon::{ConsoleLine, RconClient};
+use crate::registry::{ProvisioningStatus, ServerRegistry};
 
+/// Legacy `?token=`; prefer the `bearer` subprotocol (see `authenticate`).
 #[derive(Debug, Deserialize)]
 pub struct WsTokenQuery {
-    pub token: String,
+    pub token: Option<String>,
 }
 
+#[derive(Debug, Deserialize)]
+pub struct MonitorWsQuery {
+    pub token: Option<String>,
+    /// Seconds between pushes when nothing new arrives; clamped to the configured bounds.
+    pub interval: Option<u64>,
+    /// Send MessagePack binary frames instead of JSON text.
+    #[serde(default)]
+    pub compress: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct EventsWsQuery {
+    pub token: Option<String>,
+    /// Send MessagePack binary frames instead of JSON text.
+    #[serde(default)]
+    pub compress: bool,
+}
+
+/// Wire encoding for the monitor and events sockets. Both encodings serialize the same
+/// structs with the same field names, so they can't drift apart.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum WsEncoding {
+    Json,
+    MessagePack,
+}
+
+impl WsEncoding {
+    fn from_query(compress: bool) -> Self {
+        if compress {
+            WsEncoding::MessagePack
+        } else {
+            WsEncoding::Json
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            WsEncoding::Json => "json",
+            WsEncoding::MessagePack => "msgpack",
+        }
+    }
+
+    /// Sent first (always as JSON text) when the client asked for compression, so it
+    /// knows how to decode the frames that follow.
+    async fn hello(self, session: &mut actix_ws::Session) -> Result<(), actix_ws::Closed> {
+        let hello = serde_json::json!({ "type": "hello", "encoding": self.name() });
+        session.text(hello.to_string()).await
+    }
+
+    async fn send<T: Serialize>(
+        self,
+        session: &mut actix_ws::Session,
+        value: &T,
+    ) -> Result<(), actix_ws::Closed> {
+        match self {
+            WsEncoding::Json => match serde_json::to_string(value) {
+                Ok(json) => session.text(json).await,
+                Err(e) => {
+                    tracing::error!("Failed to serialize WebSocket frame: {}", e);
+                    Ok(())
+                }
+            },
+            // Named fields: tagged and flattened structs need map encoding
+            WsEncoding::MessagePack => match rmp_serde::to_vec_named(value) {
+                Ok(bytes) => session.binary(bytes).await,
+                Err(e) => {
+                    tracing::error!("Failed to encode WebSocket frame: {}", e);
+                    Ok(())
+                }
+            },
+        }
+    }
+}
+
+/// Bumped when MonitorPayload changes shape; fields are only ever added.
+const MONITOR_PAYLOAD_VERSION: u32 = 3;
+
 /// Combined stats payload pushed over the monitor WebSocket.
 #[derive(Debug, Serialize)]
 #[serde(rename_all = "camelCase")]
 struct MonitorPayload {
+    /// Always "monitor"
+    #[serde(rename = "type")]
+    kind: &'static str,
+    version: u32,
+    server_id: String,
     system: Option<SystemSnapshot>,
+    /// None until the server has a runtime (still provisioning, pending attach).
     game: Option<GameSnapshot>,
+    /// Present while the server is not ready yet.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    provisioning: Option<ProvisioningProgress>,
+    /// The LGSM action currently running, if any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    action: Option<ActionProgress>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ProvisioningProgress {
+    status: ProvisioningStatus,
+    percent: Option<u8>,
+    /// Latest provisioning log line.
+    step: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ActionProgress {
+    action: String,
+    started_at: DateTime<Utc>,
+    elapsed_secs: i64,
+}
+
+/// An open WebSocket, as listed by GET /api/ws/sessions.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WsSessionInfo {
+    pub id: String,
+    /// "console", "monitor", "players", "logs" or "events"
+    pub kind: &'static str,
+    pub server_id: Option<String>,
+    pub user: String,
+    pub connected_at: DateTime<Utc>,
+    pub last_seen: DateTime<Utc>,
+}
+
+struct SessionEntry {
+    info: WsSessionInfo,
+    close: Arc<Notify>,
+}
+
+/// Registry of open WebSockets, for per-user limits and admin force-close.
+#[derive(Default)]
+pub struct WsSessions {
+    sessions: std::sync::Mutex<HashMap<String, SessionEntry>>,
+}
+
+impl WsSessions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a socket; None if `user` already has `max_per_user` open.
+    fn open(
+        self: &Arc<Self>,
+        kind: &'static str,
+        server_id: Option<&str>,
+        user: &str,
+        max_per_user: usize,
+    ) -> Option<WsSessionGuard> {
+        let mut sessions = self.sessions.lock().unwrap();
+        if sessions.values().filter(|s| s.info.user == user).count() >= max_per_user {
+            return None;
+        }
+        let now = Utc::now();
+        let id = Uuid::new_v4().to_string();
+        let close = Arc::new(Notify::new());
+        sessions.insert(
+            id.clone(),
+            SessionEntry {
+                info: WsSessionInfo {
+                    id: id.clone(),
+                    kind,
+                    server_id: server_id.map(str::to_string),
+                    user: user.to_string(),
+                    connected_at: now,
+                    last_seen: now,
+                },
+                close: close.clone(),
+            },
+        );
+        Some(WsSessionGuard {
+            sessions: self.clone(),
+            id,
+            close,
+        })
+    }
+
+    pub fn list(&self) -> Vec<WsSessionInfo> {
+        let sessions = self.sessions.lock().unwrap();
+        let mut list: Vec<WsSessionInfo> = sessions.values().map(|s| s.info.clone()).collect();
+        list.sort_by_key(|s| s.connected_at);
+        list
+    }
+
+    /// Ask every session's task to close its socket, for panel shutdown.
+    pub fn close_all(&self) {
+        for entry in self.sessions.lock().unwrap().values() {
+            entry.close.notify_one();
+        }
+    }
+
+    /// Ask a session's task to close its socket. False if no such session.
+    pub fn close(&self, id: &str) -> bool {
+        match self.sessions.lock().unwrap().get(id) {
+            Some(entry) => {
+                entry.close.notify_one();
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+/// Keeps a socket registered until its task ends.
+struct WsSessionGuard {
+    sessions: Arc<WsSessions>,
+    id: String,
+    close: Arc<Notify>,
+}
+
+impl WsSessionGuard {
+    fn touch(&self) {
+        if let Some(entry) = self.sessions.sessions.lock().unwrap().get_mut(&self.id) {
+            entry.info.last_seen = Utc::now();
+        }
+    }
+
+    /// Resolves when an admin force-closes this session.
+    async fn closed(&self) {
+        self.close.notified().await
+    }
+}
+
+impl Drop for WsSessionGuard {
+    fn drop(&mut self) {
+        self.sessions.sessions.lock().unwrap().remove(&self.id);
+    }
+}
+
+/// Server-initiated pings plus idle detection, so dead clients (closed laptop lids)
+/// don't keep a task pushing into the void.
+struct Heartbeat {
+    ping: Interval,
+    idle_timeout: Duration,
+    last_seen: Instant,
+}
+
+impl Heartbeat {
+    fn new(config: &WebSocketConfig) -> Self {
+        let every = Duration::from_secs(config.ping_interval_secs.max(1));
+        Self {
+            ping: tokio::time::interval_at(Instant::now() + every, every),
+            idle_timeout: Duration::from_secs(config.idle_timeout_secs.max(1)),
+            last_seen: Instant::now(),
+        }
+    }
+
+    fn seen(&mut self) {
+        self.last_seen = Instant::now();
+    }
+
+    fn idle(&self) -> bool {
+        self.last_seen.elapsed() > self.idle_timeout
+    }
+}
+
+/// What a session loop should do with a frame read from the client.
+enum Incoming {
+    Text(String),
+    Ignored,
+    Closed,
+}
+
+/// Handle the frames every session treats alike: any frame counts as activity,
+/// pings are answered, and close, end of stream or a protocol error end the session.
+async fn receive(
+    msg: Option<Result<Message, actix_ws::ProtocolError>>,
+    session: &mut actix_ws::Session,
+    heartbeat: &mut Heartbeat,
+    guard: &WsSessionGuard,
+) -> Incoming {
+    if let Some(Ok(_)) = msg {
+        heartbeat.seen();
+        guard.touch();
+    }
+    match msg {
+        Some(Ok(Message::Text(text))) => Incoming::Text(text.to_string()),
+        Some(Ok(Message::Ping(bytes))) if session.pong(&bytes).await.is_err() => Incoming::Closed,
+        Some(Ok(Message::Close(_))) | None | Some(Err(_)) => Incoming::Closed,
+        _ => Incoming::Ignored,
+    }
+}
+
+/// Subprotocol offered next to the JWT: `new WebSocket(url, ["bearer", token])`.
+const TOKEN_PROTOCOL: &str = "bearer";
+
+/// A client that passed the pre-upgrade auth check.
+struct WsAuth {
+    claims: Claims,
+    /// The token came in Sec-WebSocket-Protocol, so the handshake must select "bearer".
+    via_protocol: bool,
+}
+
+impl WsAuth {
+    /// Echo the selected subprotocol; browsers drop the socket if it is missing.
+    fn accept(&self, response: &mut HttpResponse) {
+        if self.via_protocol {
+            response.headers_mut().insert(
+                SEC_WEBSOCKET_PROTOCOL,
+                HeaderValue::from_static(TOKEN_PROTOCOL),
+            );
+        }
+    }
+}
+
+/// Validate the JWT before upgrading, so unauthenticated clients never get a socket.
+///
+/// The token is read from the `bearer` subprotocol, falling back to `?token=` for
+/// older clients (query strings end up in proxy logs and browser history).
+fn authenticate(
+    req: &HttpRequest,
+    query_token: Option<&str>,
+    config: &AppConfig,
+    kind: &str,
+) -> Result<WsAuth, Rejection> {
+    let protocol_token = req
+        .headers()
+        .get(SEC_WEBSOCKET_PROTOCOL)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| {
+            let mut offered = v.split(',').map(str::trim);
+            offered.find(|p| *p == TOKEN_PROTOCOL)?;
+            offered.next().map(str::to_string)
+        });
+    let via_protocol = protocol_token.is_some();
+    let token = match protocol_token.or_else(|| query_token.map(str::to_string)) {
+        Some(t) => t,
+        None => return Err(Rejection::new(StatusCode::UNAUTHORIZED, "Missing token")),
+    };
+
+    match validate_token(&token, &config.auth.jwt_secret) {
+        Ok(claims) => Ok(WsAuth {
+            claims,
+            via_protocol,
+        }),
+        Err(e) => {
+            tracing::debug!("WebSocket {} auth failed: {}", kind, e);
+            Err(Rejection::new(
+                StatusCode::UNAUTHORIZED,
+                "Invalid or expired token",
+            ))
+        }
+    }
+}
+
+/// A WebSocket refused before the upgrade: the status and plain-text body to answer with.
+struct Rejection {
+    status: StatusCode,
+    message: String,
+}
+
+impl Rejection {
+    fn new(status: StatusCode, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+
+    fn response(self) -> HttpResponse {
+        HttpResponse::build(self.status).body(self.message)
+    }
+}
+
+/// Register a socket for `user`, or the 429 to return before upgrading.
+fn open_session(
+    sessions: &Arc<WsSessions>,
+    config: &AppConfig,
+    kind: &'static str,
+    server_id: Option<&str>,
+    user: &str,
+) -> Result<WsSessionGuard, Rejection> {
+    sessions
+        .open(
+            kind,
+            server_id,
+            user,
+            config.websocket.max_sessions_per_user,
+        )
+        .ok_or_else(|| {
+            Rejection::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                format!(
+                    "Too many open WebSocket sessions (max {})",
+                    config.websocket.max_sessions_per_user
+                ),
+            )
+        })
+}
+
+/// 404 for a server without a runtime, telling unknown ids apart from servers that
+/// exist but are not ready (still provisioning, failed, pending attach).
+async fn not_found(registry: &ServerRegistry, server_id: &str) -> HttpResponse {
+    match registry.get_definition(server_id).await {
+        Some(def) => ApiError::not_found("Server is not ready")
+            .with_detail("provisioningStatus", def.provisioning_status)
+            .error_response(),
+        None => ApiError::server_not_found().error_response(),
+    }
+}
+
+/// One message on the console WebSocket, told apart by `type`: live server output
+/// ("console"), the reply to a command this session sent ("response"), a command the
+/// console policy refused ("blocked"), or an error.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConsoleFrame {
+    #[serde(rename = "type")]
+    kind: &'static str,
+    /// The RCON message type of "console" frames (Generic, Chat, Warning, Error, ...).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rcon_type: Option<String>,
+    message: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    timestamp: Option<DateTime<Utc>>,
+    /// The refused command, on "blocked" frames.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    command: Option<String>,
+}
+
+impl ConsoleFrame {
+    fn line(line: ConsoleLine) -> Self {
+        Self {
+            kind: "console",
+            rcon_type: Some(line.msg_type),
+            message: line.message,
+            timestamp: Some(line.timestamp),
+            command: None,
+        }
+    }
+
+    fn response(kind: &'static str, message: String) -> Self {
+        Self {
+            kind,
+            rcon_type: None,
+            message,
+            timestamp: None,
+            command: None,
+        }
+    }
+
+    fn blocked(command: String, reason: String) -> Self {
+        Self {
+            kind: "blocked",
+            rcon_type: None,
+            message: reason,
+            timestamp: None,
+            command: Some(command),
+        }
+    }
+}
+
+/// One piece of app data for a context extractor; missing means the app was built wrong.
+fn app_data<T: ?Sized + 'static>(req: &HttpRequest) -> Result<web::Data<T>, actix_web::Error> {
+    req.app_data::<web::Data<T>>().cloned().ok_or_else(|| {
+        actix_web::error::ErrorInternalServerError(format!(
+            "{} is not configured",
+            std::any::type_name::<T>()
+        ))
+    })
+}
+
+/// The shared state a console WebSocket uses, taken from the app data in one extractor.
+#[derive(Clone)]
+pub struct ConsoleContext {
+    config: web::Data<AppConfig>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    sessions: web::Data<Arc<WsSessions>>,
+    audit_log: web::Data<Arc<AuditLog>>,
+    history: web::Data<Arc<ConsoleHistory>>,
+}
+
+impl FromRequest for ConsoleContext {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        ready((|| {
+            Ok(Self {
+                config: app_data(req)?,
+                registry: app_data(req)?,
+                sessions: app_data(req)?,
+                audit_log: app_data(req)?,
+                history: app_data(req)?,
+            })
+        })())
+    }
+}
+
+impl ConsoleContext {
+    /// Run a command typed into the console by `username`, unless the console policy
+    /// refuses it, and record it. Returns the frame to answer with.
+    async fn run_command(
+        &self,
+        rcon: &RconClient,
+        server_id: &str,
+        username: &str,
+        cmd: String,
+    ) -> ConsoleFrame {
+        tracing::debug!("RCON WS command: {}", cmd);
+        let allowed = commandpolicy::enforce(
+            &self.config.console,
+            server_id,
+            &cmd,
+            username,
+            Some(ADMIN_ROLE),
+        );
+        if let Err(reason) = allowed {
+            self.audit_log.record(AuditEntry {
+                timestamp: Utc::now(),
+                user: username.to_string(),
+                server_id: Some(server_id.to_string()),
+                action: "console.blocked".to_string(),
+                detail: Some(cmd.clone()),
+                status: 403,
+                request_id: None,
+            });
+            self.history.record(
+                server_id,
+                ConsoleExchange {
+                    command: cmd.clone(),
+                    response: reason.clone(),
+                    success: false,
+                    username: username.to_string(),
+                    timestamp: Utc::now(),
+                },
+            );
+            return ConsoleFrame::blocked(cmd, reason);
+        }
+
+        let reply = rcon.execute(&cmd).await.map_err(|e| e.to_string());
+        rcon.record_console_command(&cmd, &reply);
+        self.history.record(
+            server_id,
+            ConsoleExchange {
+                command: cmd,
+                response: match reply {
+                    Ok(ref text) => text.clone(),
+                    Err(ref e) => e.clone(),
+                },
+                success: reply.is_ok(),
+                username: username.to_string(),
+                timestamp: Utc::now(),
+            },
+        );
+        match reply {
+            Ok(response_text) => ConsoleFrame::response("response", response_text),
+            Err(e) => ConsoleFrame::response("error", format!("Error: {}", e)),
+        }
+    }
 }
 
 /// GET /ws/{server_id}/console
@@ -29,135 +592,1198 @@ pub async fn ws_console(
     stream: web::Payload,
     path: web::Path<String>,
     query: web::Query<WsTokenQuery>,
-    config: web::Data<AppConfig>,
-    registry: web::Data<Arc<ServerRegistry>>,
+    ctx: ConsoleContext,
 ) -> Result<HttpResponse, actix_web::Error> {
     let server_id = path.into_inner();
+    let ConsoleContext {
+        ref config,
+        ref registry,
+        ref sessions,
+        ..
+    } = ctx;
 
-    if let Err(e) = validate_token(&query.token, &config.auth.jwt_secret) {
-        tracing::debug!("WebSocket console auth failed: {}", e);
-        return Ok(HttpResponse::Unauthorized().body("Invalid or expired token"));
-    }
+    let auth = match authenticate(&req, query.token.as_deref(), config, "console") {
+        Ok(a) => a,
+        Err(rejected) => return Ok(rejected.response()),
+    };
 
     let rcon = match registry.get_rcon(&server_id).await {
         Some(r) => r,
-        None => return Ok(HttpResponse::NotFound().body("Server not found")),
+        None => return Ok(not_found(registry, &server_id).await),
     };
 
-    let (response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    let guard = match open_session(
+        sessions,
+        config,
+        "console",
+        Some(&server_id),
+        &auth.claims.sub,
+    ) {
+        Ok(g) => g,
+        Err(rejected) => return Ok(rejected.response()),
+    };
+
+    let (mut response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    auth.accept(&mut response);
+    let (backlog, mut lines) = rcon.subscribe_console();
+    let mut heartbeat = Heartbeat::new(&config.websocket);
+    let username = auth.claims.sub.clone();
 
     actix_web::rt::spawn(async move {
-        while let Some(Ok(msg)) = msg_stream.next().await {
-            match msg {
-                Message::Text(text) => {
-                    let cmd = text.to_string();
-                    tracing::debug!("RCON WS command: {}", cmd);
-
-                    match rcon.execute(&cmd).await {
-                        Ok(response_text) => {
-                            if session.text(response_text).await.is_err() {
-                                break;
-                            }
-                        }
-                        Err(e) => {
-                            let err_msg = format!("Error: {}", e);
-                            if session.text(err_msg).await.is_err() {
+        for line in backlog {
+            if send_frame(&mut session, &ConsoleFrame::line(line))
+                .await
+                .is_err()
+            {
+                return;
+            }
+        }
+
+        loop {
+            tokio::select! {
+                _ = heartbeat.ping.tick() => {
+                    if heartbeat.idle() {
+                        tracing::debug!("Closing idle console WebSocket {}", guard.id);
+                        break;
+                    }
+                    if session.ping(b"").await.is_err() {
+                        break;
+                    }
+                }
+                _ = guard.closed() => {
+                    tracing::info!("Console WebSocket {} closed by admin", guard.id);
+                    break;
+                }
+                line = lines.recv() => {
+                    let frame = match line {
+                        Ok(line) => ConsoleFrame::line(line),
+                        Err(broadcast::error::RecvError::Lagged(missed)) => ConsoleFrame {
+                            kind: "error",
+                            rcon_type: None,
+                            message: format!("{} console lines skipped (connection too slow)", missed),
+                            timestamp: None,
+                            command: None,
+                        },
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    if send_frame(&mut session, &frame).await.is_err() {
+                        break;
+                    }
+                }
+                msg = msg_stream.next() => {
+                    match receive(msg, &mut session, &mut heartbeat, &guard).await {
+                        Incoming::Text(cmd) => {
+                            let frame = ctx.run_command(&rcon, &server_id, &username, cmd).await;
+                            if send_frame(&mut session, &frame).await.is_err() {
                                 break;
                             }
                         }
+                        Incoming::Ignored => {}
+                        Incoming::Closed => break,
                     }
                 }
-                Message::Ping(bytes) => {
-                    if session.pong(&bytes).await.is_err() {
+            }
+        }
+
+        // Dropping `lines` unsubscribes this session from the console feed, and
+        // dropping `guard` removes it from the session list
+        let _ = session.close(None).await;
+        tracing::debug!("RCON WebSocket session closed");
+    });
+
+    Ok(response)
+}
+
+/// GET /api/servers/{server_id}/console/scrollback
+///
+/// The same lines a new console WebSocket replays, for clients that can't open one.
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/console/scrollback",
+    tag = "console",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "`lines`: the console scrollback, oldest first", body = Object),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn console_scrollback(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+) -> Result<HttpResponse, ApiError> {
+    let rcon = registry
+        .get_rcon(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "lines": rcon.console_scrollback(),
+    })))
+}
+
+async fn send_frame(
+    session: &mut actix_ws::Session,
+    frame: &ConsoleFrame,
+) -> Result<(), actix_ws::Closed> {
+    match serde_json::to_string(frame) {
+        Ok(json) => session.text(json).await,
+        Err(e) => {
+            tracing::error!("Failed to serialize console frame: {}", e);
+            Ok(())
+        }
+    }
+}
+
+/// The shared state a monitor WebSocket uses, taken from the app data in one extractor.
+pub struct MonitorContext {
+    config: web::Data<AppConfig>,
+    sys_monitor: web::Data<Arc<SystemMonitor>>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    sessions: web::Data<Arc<WsSessions>>,
+}
+
+impl FromRequest for MonitorContext {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut actix_web::dev::Payload) -> Self::Future {
+        ready((|| {
+            Ok(Self {
+                config: app_data(req)?,
+                sys_monitor: app_data(req)?,
+                registry: app_data(req)?,
+                sessions: app_data(req)?,
+            })
+        })())
+    }
+}
+
+/// GET /ws/{server_id}/monitor?interval=&compress=
+///
+/// Sends the latest snapshots right after the upgrade, then whenever a collector pushes a
+/// new one (at most once per `ws_min_interval_secs`), and at least every `interval` seconds.
+/// Provisioning progress and running LGSM actions ride along, and changes to them are
+/// pushed as soon as the event bus reports them.
+pub async fn ws_monitor(
+    req: HttpRequest,
+    stream: web::Payload,
+    path: web::Path<String>,
+    query: web::Query<MonitorWsQuery>,
+    ctx: MonitorContext,
+) -> Result<HttpResponse, actix_web::Error> {
+    let server_id = path.into_inner();
+    let MonitorContext {
+        config,
+        sys_monitor,
+        registry,
+        sessions,
+    } = ctx;
+
+    let auth = match authenticate(&req, query.token.as_deref(), &config, "monitor") {
+        Ok(a) => a,
+        Err(rejected) => return Ok(rejected.response()),
+    };
+
+    // Servers without a runtime are still watched, for their provisioning progress
+    let mut game_monitor = registry.get_game_monitor(&server_id).await;
+    if game_monitor.is_none() && registry.get_definition(&server_id).await.is_none() {
+        return Ok(ApiError::server_not_found().error_response());
+    }
+
+    let guard = match open_session(
+        &sessions,
+        &config,
+        "monitor",
+        Some(&server_id),
+        &auth.claims.sub,
+    ) {
+        Ok(g) => g,
+        Err(rejected) => return Ok(rejected.response()),
+    };
+
+    let min_interval = config.monitor.ws_min_interval_secs.max(1);
+    let max_interval = config.monitor.ws_max_interval_secs.max(min_interval);
+    let every = query
+        .interval
+        .unwrap_or(config.monitor.poll_interval_secs)
+        .clamp(min_interval, max_interval);
+    let min_gap = Duration::from_secs(min_interval);
+    let encoding = WsEncoding::from_query(query.compress);
+
+    let (mut response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    auth.accept(&mut response);
+
+    let sys_monitor = sys_monitor.into_inner().clone();
+    let registry = registry.into_inner().as_ref().clone();
+    let mut events = registry.events.subscribe();
+    let mut heartbeat = Heartbeat::new(&config.websocket);
+
+    actix_web::rt::spawn(async move {
+        // The first tick fires immediately, so the dashboard gets data right away
+        let mut tick = interval(Duration::from_secs(every));
+        let mut last_sent: Option<tokio::time::Instant> = None;
+        if encoding != WsEncoding::Json && encoding.hello(&mut session).await.is_err() {
+            return;
+        }
+
+        loop {
+            let changed = tokio::select! {
+                _ = tick.tick() => false,
+                _ = sys_monitor.updated.notified() => true,
+                _ = game_updated(game_monitor.as_deref()) => true,
+                // Provisioning steps and LGSM actions are sent right away, unthrottled
+                event = events.recv() => match event {
+                    Ok(event) if event.event.server_id() == server_id => false,
+                    Ok(_) | Err(broadcast::error::RecvError::Closed) => continue,
+                    Err(broadcast::error::RecvError::Lagged(_)) => false,
+                },
+                _ = heartbeat.ping.tick() => {
+                    if heartbeat.idle() {
+                        tracing::debug!("Closing idle monitor WebSocket {}", guard.id);
+                        break;
+                    }
+                    if session.ping(b"").await.is_err() {
                         break;
                     }
+                    continue;
                 }
-                Message::Close(_) => {
+                _ = guard.closed() => {
+                    tracing::info!("Monitor WebSocket {} closed by admin", guard.id);
                     break;
                 }
-                _ => {}
+                msg = msg_stream.next() => {
+                    if let Incoming::Closed = receive(msg, &mut session, &mut heartbeat, &guard).await {
+                        break;
+                    }
+                    continue;
+                }
+            };
+            if changed && last_sent.is_some_and(|at| at.elapsed() < min_gap) {
+                continue;
             }
+
+            // Picks up the runtime once provisioning finishes
+            if game_monitor.is_none() {
+                game_monitor = registry.get_game_monitor(&server_id).await;
+            }
+            let payload =
+                monitor_payload(&registry, &server_id, &sys_monitor, game_monitor.as_deref()).await;
+
+            if encoding.send(&mut session, &payload).await.is_err() {
+                break;
+            }
+            last_sent = Some(tokio::time::Instant::now());
+            tick.reset();
         }
 
         let _ = session.close(None).await;
-        tracing::debug!("RCON WebSocket session closed");
+        tracing::debug!("Monitor WebSocket session closed");
     });
 
     Ok(response)
 }
 
-/// GET /ws/{server_id}/monitor
-pub async fn ws_monitor(
+/// GET /ws/{server_id}/players
+///
+/// Sends the cached player list, then joins, leaves and list updates as the game
+/// collector sees them.
+pub async fn ws_players(
     req: HttpRequest,
     stream: web::Payload,
     path: web::Path<String>,
     query: web::Query<WsTokenQuery>,
     config: web::Data<AppConfig>,
-    sys_monitor: web::Data<Arc<SystemMonitor>>,
     registry: web::Data<Arc<ServerRegistry>>,
+    sessions: web::Data<Arc<WsSessions>>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let server_id = path.into_inner();
 
-    if let Err(e) = validate_token(&query.token, &config.auth.jwt_secret) {
-        tracing::debug!("WebSocket monitor auth failed: {}", e);
-        return Ok(HttpResponse::Unauthorized().body("Invalid or expired token"));
-    }
+    let auth = match authenticate(&req, query.token.as_deref(), &config, "players") {
+        Ok(a) => a,
+        Err(rejected) => return Ok(rejected.response()),
+    };
 
     let game_monitor = match registry.get_game_monitor(&server_id).await {
         Some(m) => m,
-        None => return Ok(HttpResponse::NotFound().body("Server not found")),
+        None => return Ok(not_found(&registry, &server_id).await),
     };
 
-    let (response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    let guard = match open_session(
+        &sessions,
+        &config,
+        "players",
+        Some(&server_id),
+        &auth.claims.sub,
+    ) {
+        Ok(g) => g,
+        Err(rejected) => return Ok(rejected.response()),
+    };
 
-    let sys_monitor = sys_monitor.into_inner().clone();
+    let (mut response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    auth.accept(&mut response);
+    let mut updates = game_monitor.player_updates.subscribe();
+    let mut heartbeat = Heartbeat::new(&config.websocket);
 
     actix_web::rt::spawn(async move {
-        let mut tick = interval(Duration::from_secs(5));
+        // Subscribed before reading the cache, so nothing falls between the two
+        let mut next = Some(current_players(&game_monitor).await);
 
         loop {
+            if let Some(update) = next.take() {
+                match serde_json::to_string(&update) {
+                    Ok(json) => {
+                        if session.text(json).await.is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => tracing::error!("Failed to serialize player update: {}", e),
+                }
+            }
+
             tokio::select! {
-                _ = tick.tick() => {
-                    let sys_history = sys_monitor.history.read().await;
-                    let system = sys_history.latest().cloned();
-                    drop(sys_history);
+                _ = heartbeat.ping.tick() => {
+                    if heartbeat.idle() {
+                        tracing::debug!("Closing idle players WebSocket {}", guard.id);
+                        break;
+                    }
+                    if session.ping(b"").await.is_err() {
+                        break;
+                    }
+                }
+                _ = guard.closed() => {
+                    tracing::info!("Players WebSocket {} closed by admin", guard.id);
+                    break;
+                }
+                update = updates.recv() => {
+                    next = match update {
+                        Ok(update) => Some(update),
+                        // Too slow to keep up: resync with the full list
+                        Err(broadcast::error::RecvError::Lagged(_)) => {
+                            Some(current_players(&game_monitor).await)
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                }
+                msg = msg_stream.next() => {
+                    if let Incoming::Closed = receive(msg, &mut session, &mut heartbeat, &guard).await {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let _ = session.close(None).await;
+        tracing::debug!("Players WebSocket session closed");
+    });
+
+    Ok(response)
+}
 
-                    let game_history = game_monitor.history.read().await;
-                    let game = game_history.latest().cloned();
-                    drop(game_history);
+async fn current_players(monitor: &GameMonitor) -> PlayerUpdate {
+    let players = monitor.players.read().await;
+    PlayerUpdate::List {
+        players: players
+            .as_ref()
+            .map(|s| s.players.clone())
+            .unwrap_or_default(),
+    }
+}
 
-                    let payload = MonitorPayload { system, game };
+/// How often followed log files are checked for new lines.
+const LOG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+/// Lines pushed per file per poll; a burst beyond this is dropped with a notice.
+const LOG_LINES_PER_POLL: usize = 500;
 
-                    match serde_json::to_string(&payload) {
-                        Ok(json) => {
-                            if session.text(json).await.is_err() {
-                                break;
+/// Sent by a logs client to choose the followed files; replaces the previous selection.
+#[derive(Debug, Deserialize)]
+struct LogSubscription {
+    files: Vec<String>,
+}
+
+/// One message on the logs WebSocket.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum LogFrame<'a> {
+    Line { file: &'a str, line: String },
+    Skipped { file: &'a str, count: usize },
+    Subscribed { files: Vec<String> },
+    Error { message: String },
+}
+
+/// GET /ws/{server_id}/logs
+///
+/// Follows the log files named in `{"files": ["console", "oxide"]}` messages, tagging
+/// each pushed line with its alias. Unknown aliases get an in-band error.
+pub async fn ws_logs(
+    req: HttpRequest,
+    stream: web::Payload,
+    path: web::Path<String>,
+    query: web::Query<WsTokenQuery>,
+    config: web::Data<AppConfig>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    sessions: web::Data<Arc<WsSessions>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let server_id = path.into_inner();
+
+    let auth = match authenticate(&req, query.token.as_deref(), &config, "logs") {
+        Ok(a) => a,
+        Err(rejected) => return Ok(rejected.response()),
+    };
+
+    let server_config = match registry.get_config(&server_id).await {
+        Some(c) => c,
+        None => return Ok(ApiError::server_not_found().error_response()),
+    };
+    let allowed = allowed_log_files(&server_config);
+
+    let guard = match open_session(
+        &sessions,
+        &config,
+        "logs",
+        Some(&server_id),
+        &auth.claims.sub,
+    ) {
+        Ok(g) => g,
+        Err(rejected) => return Ok(rejected.response()),
+    };
+
+    let (mut response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    auth.accept(&mut response);
+    let mut heartbeat = Heartbeat::new(&config.websocket);
+
+    actix_web::rt::spawn(async move {
+        // Alias -> follower, in the order the client asked for them
+        let mut followed: Vec<(String, LogFollower)> = Vec::new();
+        let mut poll = interval(LOG_POLL_INTERVAL);
+
+        'session: loop {
+            tokio::select! {
+                _ = poll.tick() => {
+                    for (alias, follower) in followed.iter_mut() {
+                        let new = match follower.poll(LOG_LINES_PER_POLL) {
+                            Ok(new) => new,
+                            Err(e) => {
+                                tracing::debug!("Following {} log failed: {}", alias, e);
+                                continue;
+                            }
+                        };
+                        if new.skipped > 0 {
+                            let frame = LogFrame::Skipped { file: alias, count: new.skipped };
+                            if WsEncoding::Json.send(&mut session, &frame).await.is_err() {
+                                break 'session;
                             }
                         }
-                        Err(e) => {
-                            tracing::error!("Failed to serialize monitor payload: {}", e);
+                        for line in new.lines {
+                            let frame = LogFrame::Line { file: alias, line };
+                            if WsEncoding::Json.send(&mut session, &frame).await.is_err() {
+                                break 'session;
+                            }
                         }
                     }
                 }
+                _ = heartbeat.ping.tick() => {
+                    if heartbeat.idle() {
+                        tracing::debug!("Closing idle logs WebSocket {}", guard.id);
+                        break;
+                    }
+                    if session.ping(b"").await.is_err() {
+                        break;
+                    }
+                }
+                _ = guard.closed() => {
+                    tracing::info!("Logs WebSocket {} closed by admin", guard.id);
+                    break;
+                }
                 msg = msg_stream.next() => {
-                    match msg {
-                        Some(Ok(Message::Ping(bytes))) => {
-                            if session.pong(&bytes).await.is_err() {
-                                break;
+                    match receive(msg, &mut session, &mut heartbeat, &guard).await {
+                        Incoming::Text(text) => {
+                            let frames = match serde_json::from_str::<LogSubscription>(&text) {
+                                Ok(sub) => resubscribe(&mut followed, &allowed, sub.files),
+                                Err(e) => vec![LogFrame::Error {
+                                    message: format!("Invalid subscription: {}", e),
+                                }],
+                            };
+                            for frame in &frames {
+                                if WsEncoding::Json.send(&mut session, frame).await.is_err() {
+                                    break 'session;
+                                }
                             }
                         }
-                        Some(Ok(Message::Close(_))) | None => {
-                            break;
+                        Incoming::Ignored => {}
+                        Incoming::Closed => break,
+                    }
+                }
+            }
+        }
+
+        let _ = session.close(None).await;
+        tracing::debug!("Logs WebSocket session closed");
+    });
+
+    Ok(response)
+}
+
+/// Switch to following `files`, keeping the read position of files already followed.
+/// Returns an error frame per rejected alias and the resulting selection.
+fn resubscribe(
+    followed: &mut Vec<(String, LogFollower)>,
+    allowed: &HashMap<String, std::path::PathBuf>,
+    files: Vec<String>,
+) -> Vec<LogFrame<'static>> {
+    let mut frames = Vec::new();
+    let mut previous = std::mem::take(followed);
+    for alias in files {
+        if followed.iter().any(|(a, _)| *a == alias) {
+            continue;
+        }
+        let Some(path) = allowed.get(&alias) else {
+            let mut available: Vec<&str> = allowed.keys().map(String::as_str).collect();
+            available.sort();
+            frames.push(LogFrame::Error {
+                message: format!(
+                    "Unknown log file '{}'. Available: {}",
+                    alias,
+                    available.join(", ")
+                ),
+            });
+            continue;
+        };
+        let follower = match previous.iter().position(|(a, _)| *a == alias) {
+            Some(index) => previous.swap_remove(index).1,
+            None => LogFollower::new(path.clone()),
+        };
+        followed.push((alias, follower));
+    }
+    frames.push(LogFrame::Subscribed {
+        files: followed.iter().map(|(a, _)| a.clone()).collect(),
+    });
+    frames
+}
+
+/// One message on the provisioning WebSocket.
+#[derive(Debug, Serialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+enum ProvisionFrame<'a> {
+    /// The status and whole log so far; sent on connect, and again if the log restarts.
+    Snapshot {
+        status: &'a ProvisioningStatus,
+        percent: Option<u8>,
+        log: &'a [String],
+    },
+    Line {
+        line: &'a str,
+    },
+    Status {
+        status: &'a ProvisioningStatus,
+        percent: Option<u8>,
+    },
+}
+
+/// GET /ws/{server_id}/provision
+///
+/// Replays the provisioning log, then pushes new lines and status changes as they
+/// happen. The server closes the socket once provisioning is ready or has failed.
+pub async fn ws_provision(
+    req: HttpRequest,
+    stream: web::Payload,
+    path: web::Path<String>,
+    query: web::Query<WsTokenQuery>,
+    config: web::Data<AppConfig>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    sessions: web::Data<Arc<WsSessions>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let server_id = path.into_inner();
+
+    let auth = match authenticate(&req, query.token.as_deref(), &config, "provision") {
+        Ok(a) => a,
+        Err(rejected) => return Ok(rejected.response()),
+    };
+
+    if registry.get_definition(&server_id).await.is_none() {
+        return Ok(ApiError::server_not_found().error_response());
+    }
+
+    let guard = match open_session(
+        &sessions,
+        &config,
+        "provision",
+        Some(&server_id),
+        &auth.claims.sub,
+    ) {
+        Ok(g) => g,
+        Err(rejected) => return Ok(rejected.response()),
+    };
+
+    let (mut response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    auth.accept(&mut response);
+    let mut events = registry.events.subscribe();
+    let mut heartbeat = Heartbeat::new(&config.websocket);
+    let registry = registry.get_ref().clone();
+
+    actix_web::rt::spawn(async move {
+        // Subscribed before the first read, so every later change arrives as an event
+        let mut seen = None;
+        let mut changed = true;
+
+        loop {
+            if changed {
+                changed = false;
+                if !push_provisioning(&mut session, &registry, &server_id, &mut seen).await {
+                    break;
+                }
+            }
+
+            tokio::select! {
+                _ = heartbeat.ping.tick() => {
+                    if heartbeat.idle() {
+                        tracing::debug!("Closing idle provision WebSocket {}", guard.id);
+                        break;
+                    }
+                    if session.ping(b"").await.is_err() {
+                        break;
+                    }
+                }
+                _ = guard.closed() => {
+                    tracing::info!("Provision WebSocket {} closed by admin", guard.id);
+                    break;
+                }
+                event = events.recv() => {
+                    changed = match event {
+                        Ok(event) => matches!(
+                            &event.event,
+                            PanelEvent::ProvisioningStatus { server_id: id, .. } if *id == server_id
+                        ),
+                        // The log is re-read from the definition, so missed events lose nothing
+                        Err(broadcast::error::RecvError::Lagged(_)) => true,
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                }
+                msg = msg_stream.next() => {
+                    if let Incoming::Closed = receive(msg, &mut session, &mut heartbeat, &guard).await {
+                        break;
+                    }
+                }
+            }
+        }
+
+        let _ = session.close(None).await;
+        tracing::debug!("Provision WebSocket session closed");
+    });
+
+    Ok(response)
+}
+
+/// Send what the client hasn't seen of the server's provisioning: a snapshot the first
+/// time, then the log lines and status change since `seen` (log length, status). False
+/// once there is nothing left to wait for (ready, failed or deleted) or the client left.
+async fn push_provisioning(
+    session: &mut actix_ws::Session,
+    registry: &ServerRegistry,
+    server_id: &str,
+    seen: &mut Option<(usize, ProvisioningStatus)>,
+) -> bool {
+    let Some(def) = registry.get_definition(server_id).await else {
+        return false;
+    };
+    let status = &def.provisioning_status;
+    let log = &def.provisioning_log;
+    let frames = match seen {
+        Some((lines, last)) if *lines <= log.len() => {
+            let mut frames: Vec<_> = log[*lines..]
+                .iter()
+                .map(|line| ProvisionFrame::Line { line })
+                .collect();
+            if last != status {
+                frames.push(ProvisionFrame::Status {
+                    status,
+                    percent: status.percent(),
+                });
+            }
+            frames
+        }
+        // First push, or the log was replaced (re-provisioned, imported)
+        _ => vec![ProvisionFrame::Snapshot {
+            status,
+            percent: status.percent(),
+            log,
+        }],
+    };
+    for frame in &frames {
+        if WsEncoding::Json.send(session, frame).await.is_err() {
+            return false;
+        }
+    }
+    *seen = Some((log.len(), status.clone()));
+    !matches!(
+        status,
+        ProvisioningStatus::Ready | ProvisioningStatus::Error
+    )
+}
+
+/// Filter sent by an events client; empty lists match everything.
+#[derive(Debug, Default, Deserialize)]
+struct EventSubscription {
+    #[serde(default)]
+    servers: Vec<String>,
+    #[serde(default)]
+    types: Vec<String>,
+    /// Send activity feed items (as GET /api/activity) instead of raw events.
+    #[serde(default)]
+    activity: bool,
+}
+
+impl EventSubscription {
+    fn matches(&self, event: &TimedEvent) -> bool {
+        (self.servers.is_empty() || self.servers.iter().any(|s| s == event.event.server_id()))
+            && (self.types.is_empty() || self.types.iter().any(|t| t == event.event.kind()))
+    }
+}
+
+/// GET /ws/events?compress=
+///
+/// Streams panel events for all servers. Clients narrow the stream by sending
+/// `{"servers": [...], "types": [...]}`, adding `"activity": true` to get activity feed
+/// items instead; each message replaces the previous filter.
+pub async fn ws_events(
+    req: HttpRequest,
+    stream: web::Payload,
+    query: web::Query<EventsWsQuery>,
+    config: web::Data<AppConfig>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    sessions: web::Data<Arc<WsSessions>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let auth = match authenticate(&req, query.token.as_deref(), &config, "events") {
+        Ok(a) => a,
+        Err(rejected) => return Ok(rejected.response()),
+    };
+
+    let guard = match open_session(&sessions, &config, "events", None, &auth.claims.sub) {
+        Ok(g) => g,
+        Err(rejected) => return Ok(rejected.response()),
+    };
+
+    let (mut response, mut session, mut msg_stream) = actix_ws::handle(&req, stream)?;
+    auth.accept(&mut response);
+    let mut events = registry.events.subscribe();
+    let mut heartbeat = Heartbeat::new(&config.websocket);
+    let encoding = WsEncoding::from_query(query.compress);
+
+    actix_web::rt::spawn(async move {
+        let mut filter = EventSubscription::default();
+        if encoding != WsEncoding::Json && encoding.hello(&mut session).await.is_err() {
+            return;
+        }
+
+        loop {
+            tokio::select! {
+                _ = heartbeat.ping.tick() => {
+                    if heartbeat.idle() {
+                        tracing::debug!("Closing idle events WebSocket {}", guard.id);
+                        break;
+                    }
+                    if session.ping(b"").await.is_err() {
+                        break;
+                    }
+                }
+                _ = guard.closed() => {
+                    tracing::info!("Events WebSocket {} closed by admin", guard.id);
+                    break;
+                }
+                event = events.recv() => {
+                    let sent = match event {
+                        Ok(event) if filter.matches(&event) => {
+                            if filter.activity {
+                                let item = ActivityItem::from_event(&event);
+                                encoding.send(&mut session, &ActivityFrame::Activity(&item)).await
+                            } else {
+                                encoding.send(&mut session, &event).await
+                            }
+                        }
+                        Ok(_) => continue,
+                        // The bus only keeps a bounded backlog per client
+                        Err(broadcast::error::RecvError::Lagged(missed)) => {
+                            let lagged = serde_json::json!({ "type": "lagged", "missed": missed });
+                            encoding.send(&mut session, &lagged).await
+                        }
+                        Err(broadcast::error::RecvError::Closed) => break,
+                    };
+                    if sent.is_err() {
+                        break;
+                    }
+                }
+                msg = msg_stream.next() => {
+                    match receive(msg, &mut session, &mut heartbeat, &guard).await {
+                        Incoming::Text(text) => {
+                            match serde_json::from_str::<EventSubscription>(&text) {
+                                Ok(sub) => filter = sub,
+                                Err(e) => {
+                                    let error = serde_json::json!({
+                                        "type": "error",
+                                        "message": format!("Invalid subscription: {}", e),
+                                    });
+                                    if session.text(error.to_string()).await.is_err() {
+                                        break;
+                                    }
+                                }
+                            }
                         }
-                        _ => {}
+                        Incoming::Ignored => {}
+                        Incoming::Closed => break,
                     }
                 }
             }
         }
 
         let _ = session.close(None).await;
-        tracing::debug!("Monitor WebSocket session closed");
+        tracing::debug!("Events WebSocket session closed");
     });
 
     Ok(response)
 }
+
+/// Resolves on the next game snapshot; never, for servers without a runtime yet.
+async fn game_updated(monitor: Option<&GameMonitor>) {
+    match monitor {
+        Some(m) => m.updated.notified().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn monitor_payload(
+    registry: &ServerRegistry,
+    server_id: &str,
+    sys_monitor: &SystemMonitor,
+    game_monitor: Option<&GameMonitor>,
+) -> MonitorPayload {
+    let system = sys_monitor.history.read().await.latest().cloned();
+    let game = match game_monitor {
+        Some(m) => m.history.read().await.latest().cloned(),
+        None => None,
+    };
+    let provisioning = registry
+        .get_definition(server_id)
+        .await
+        .filter(|d| d.provisioning_status != ProvisioningStatus::Ready)
+        .map(|d| ProvisioningProgress {
+            percent: d.provisioning_status.percent(),
+            status: d.provisioning_status,
+            step: d.provisioning_log.last().cloned(),
+        });
+    let action = match registry.get_lgsm_lock(server_id).await {
+        Some(lock) => lock.current().map(|holder| ActionProgress {
+            elapsed_secs: (Utc::now() - holder.started_at).num_seconds(),
+            action: holder.action,
+            started_at: holder.started_at,
+        }),
+        None => None,
+    };
+
+    MonitorPayload {
+        kind: "monitor",
+        version: MONITOR_PAYLOAD_VERSION,
+        server_id: server_id.to_string(),
+        system,
+        game,
+        provisioning,
+        action,
+    }
+}
+
+/// GET /api/ws/sessions — open WebSockets by type, server and user.
+#[utoipa::path(
+    get,
+    path = "/api/ws/sessions",
+    tag = "websockets",
+    responses((status = 200, body = [WsSessionInfo]))
+)]
+pub async fn list_sessions(sessions: web::Data<Arc<WsSessions>>) -> HttpResponse {
+    HttpResponse::Ok().json(sessions.list())
+}
+
+/// DELETE /api/ws/sessions/{id} — force-close one WebSocket.
+#[utoipa::path(
+    delete,
+    path = "/api/ws/sessions/{id}",
+    tag = "websockets",
+    params(("id" = String, Path, description = "Session id")),
+    responses(
+        (status = 200, description = "`success` and `message`", body = Object),
+        (status = 404, body = ApiError),
+    )
+)]
+pub async fn close_session(
+    id: web::Path<String>,
+    sessions: web::Data<Arc<WsSessions>>,
+) -> Result<HttpResponse, ApiError> {
+    if !sessions.close(&id) {
+        return Err(ApiError::not_found("Session not found"));
+    }
+    Ok(HttpResponse::Ok().json(serde_json::json!({
+        "success": true,
+        "message": format!("Session {} closed", id),
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn console_frames_use_the_type_envelope() {
+        let line = ConsoleFrame::line(ConsoleLine {
+            msg_type: "Chat".to_string(),
+            message: "[CHAT] hi".to_string(),
+            timestamp: Utc::now(),
+        });
+        let json = serde_json::to_value(line).unwrap();
+        assert_eq!(json["type"], "console");
+        assert_eq!(json["rconType"], "Chat");
+
+        let reply = serde_json::to_value(ConsoleFrame::response("response", "ok".into())).unwrap();
+        assert_eq!(
+            reply,
+            serde_json::json!({"type": "response", "message": "ok"})
+        );
+    }
+
+    /// A registry whose servers' game monitors each hold one snapshot named after them.
+    async fn registry_with(ids: &[&str]) -> Arc<ServerRegistry> {
+        use crate::lgsm::LgsmLock;
+        use crate::registry::ServerRuntime;
+        use crate::storage::SqliteStore;
+
+        let store = Arc::new(SqliteStore::in_memory().unwrap());
+        let registry = Arc::new(ServerRegistry::new(
+            Vec::new(),
+            HashMap::new(),
+            store.clone(),
+            store,
+        ));
+        for id in ids {
+            let game_monitor = Arc::new(GameMonitor::new(10));
+            game_monitor.history.write().await.push(GameSnapshot {
+                timestamp: Utc::now(),
+                online: true,
+                players: 0,
+                max_players: 100,
+                queued: 0,
+                fps: 60.0,
+                entities: 0,
+                uptime: 0,
+                map: "Procedural Map".to_string(),
+                hostname: format!("{id} server"),
+                cpu_percent: None,
+                memory_bytes: None,
+                threads: None,
+            });
+            let runtime = ServerRuntime {
+                rcon: crate::rcon::RconClient::new(serde_yaml::from_str("{}").unwrap()),
+                game_monitor,
+                lgsm_lock: Arc::new(LgsmLock::new()),
+                collector_handle: None,
+            };
+            registry
+                .runtimes
+                .write()
+                .await
+                .insert(id.to_string(), runtime);
+        }
+        registry
+    }
+
+    /// The frame as JSON and as MessagePack, after checking both decode to the same value.
+    fn both_encodings<T: Serialize>(frame: &T) -> (Vec<u8>, Vec<u8>) {
+        let json = serde_json::to_vec(frame).unwrap();
+        let msgpack = rmp_serde::to_vec_named(frame).unwrap();
+        let from_json: serde_json::Value = serde_json::from_slice(&json).unwrap();
+        let from_msgpack: serde_json::Value = rmp_serde::from_slice(&msgpack).unwrap();
+        assert_eq!(from_msgpack, from_json);
+        (json, msgpack)
+    }
+
+    #[actix_web::test]
+    async fn msgpack_frames_decode_to_the_json_payload_and_are_smaller() {
+        let registry = registry_with(&["alpha"]).await;
+        let sys_monitor = SystemMonitor::new(10);
+        sys_monitor.history.write().await.push(SystemSnapshot {
+            timestamp: Utc::now(),
+            cpu_percent: 12.5,
+            mem_total: 16 * 1024 * 1024 * 1024,
+            mem_used: 6 * 1024 * 1024 * 1024,
+            mem_percent: 37.5,
+            disk_total: 512 * 1024 * 1024 * 1024,
+            disk_used: 128 * 1024 * 1024 * 1024,
+            disk_percent: 25.0,
+            disks: Vec::new(),
+            rx_bytes_per_sec: 125_000,
+            tx_bytes_per_sec: 250_000,
+        });
+        let game_monitor = registry.get_game_monitor("alpha").await;
+        let payload =
+            monitor_payload(&registry, "alpha", &sys_monitor, game_monitor.as_deref()).await;
+        assert!(payload.system.is_some() && payload.game.is_some());
+        let (json, msgpack) = both_encodings(&payload);
+        assert!(
+            msgpack.len() < json.len(),
+            "{} >= {}",
+            msgpack.len(),
+            json.len()
+        );
+
+        let event = TimedEvent {
+            timestamp: Utc::now(),
+            event: PanelEvent::JobFinished {
+                server_id: "alpha".to_string(),
+                job_id: Uuid::new_v4().to_string(),
+                job_name: "Nightly restart".to_string(),
+                success: true,
+                skipped: false,
+                message: "Restarted".to_string(),
+            },
+            request_id: Some(Uuid::new_v4().to_string()),
+        };
+        let (json, msgpack) = both_encodings(&event);
+        assert!(
+            msgpack.len() < json.len(),
+            "{} >= {}",
+            msgpack.len(),
+            json.len()
+        );
+    }
+
+    fn token(config: &AppConfig) -> String {
+        let now = Utc::now().timestamp() as usize;
+        jsonwebtoken::encode(
+            &jsonwebtoken::Header::default(),
+            &Claims {
+                sub: "admin".to_string(),
+                exp: now + 600,
+                iat: now,
+                auth_time: now,
+            },
+            &jsonwebtoken::EncodingKey::from_secret(config.auth.jwt_secret.as_bytes()),
+        )
+        .unwrap()
+    }
+
+    #[actix_web::test]
+    async fn missing_servers_and_runtimes_get_a_404_error_body() {
+        use actix_web::test;
+
+        let config: AppConfig = serde_yaml::from_str("{}").unwrap();
+        let token = token(&config);
+        let registry = registry_with(&["alpha"]).await;
+        let installing = serde_json::from_value(serde_json::json!({
+            "id": "pending",
+            "name": "Pending",
+            "serverType": "vanilla",
+            "source": "dynamic",
+            "provisioningStatus": "installing",
+            "provisioningLog": [],
+            "gamePort": 28015,
+            "rconPort": 28016,
+            "queryPort": 27015,
+            "maxPlayers": 100,
+            "worldSize": 4000,
+            "seed": 1,
+            "hostname": "Pending",
+            "rconPassword": "secret",
+            "basePath": "/tmp/unused",
+            "createdAt": "2026-01-01T00:00:00Z",
+        }))
+        .unwrap();
+        registry.insert_definition(installing).await.unwrap();
+        let (audit_log, _entries) = AuditLog::new(&config.panel);
+
+        let app = test::init_service(
+            actix_web::App::new()
+                .app_data(web::Data::new(config))
+                .app_data(web::Data::new(registry))
+                .app_data(web::Data::new(Arc::new(SystemMonitor::new(10))))
+                .app_data(web::Data::new(Arc::new(WsSessions::new())))
+                .app_data(web::Data::new(Arc::new(audit_log)))
+                .app_data(web::Data::new(Arc::new(ConsoleHistory::new(10))))
+                .route("/ws/{server_id}/console", web::get().to(ws_console))
+                .route("/ws/{server_id}/monitor", web::get().to(ws_monitor))
+                .route("/ws/{server_id}/players", web::get().to(ws_players))
+                .route("/ws/{server_id}/logs", web::get().to(ws_logs))
+                .route("/ws/{server_id}/provision", web::get().to(ws_provision)),
+        )
+        .await;
+
+        let unknown = serde_json::json!({"code": "SERVER_NOT_FOUND", "error": "Server not found"});
+        let not_ready = serde_json::json!({
+            "code": "NOT_FOUND",
+            "error": "Server is not ready",
+            "details": {"provisioningStatus": "installing"},
+        });
+        let cases = [
+            ("gamma/console", &unknown),
+            ("gamma/monitor", &unknown),
+            ("gamma/players", &unknown),
+            ("gamma/logs", &unknown),
+            ("gamma/provision", &unknown),
+            ("pending/console", &not_ready),
+            ("pending/players", &not_ready),
+        ];
+        for (path, expected) in cases {
+            let req = test::TestRequest::get()
+                .uri(&format!("/ws/{path}?token={token}"))
+                .to_request();
+            let response = test::call_service(&app, req).await;
+            assert_eq!(response.status(), 404, "{}", path);
+            let body: serde_json::Value = test::read_body_json(response).await;
+            assert_eq!(&body, expected, "{}", path);
+        }
+    }
+
+    #[actix_web::test]
+    async fn monitor_sessions_follow_their_own_server() {
+        let config: AppConfig = serde_yaml::from_str("{}").unwrap();
+        let token = token(&config);
+
+        let registry = registry_with(&["alpha", "beta"]).await;
+        let sys_monitor = Arc::new(SystemMonitor::new(10));
+        let sessions = Arc::new(WsSessions::new());
+        let server = actix_web::HttpServer::new(move || {
+            actix_web::App::new()
+                .app_data(web::Data::new(config.clone()))
+                .app_data(web::Data::new(registry.clone()))
+                .app_data(web::Data::new(sys_monitor.clone()))
+                .app_data(web::Data::new(sessions.clone()))
+                .route("/ws/{server_id}/monitor", web::get().to(ws_monitor))
+        })
+        .workers(1)
+        .bind(("127.0.0.1", 0))
+        .unwrap();
+        let addr = server.addrs()[0];
+        let handle = server.run();
+        let stop = handle.handle();
+        actix_web::rt::spawn(handle);
+
+        let first_frame = |server_id: &'static str| {
+            let url = format!("ws://{addr}/ws/{server_id}/monitor?token={token}");
+            async move {
+                let (mut socket, _) = tokio_tungstenite::connect_async(url).await.unwrap();
+                let frame = socket.next().await.unwrap().unwrap();
+                serde_json::from_str::<serde_json::Value>(frame.to_text().unwrap()).unwrap()
+            }
+        };
+        let (alpha, beta) = tokio::join!(first_frame("alpha"), first_frame("beta"));
+        assert_eq!(alpha["serverId"], "alpha");
+        assert_eq!(alpha["game"]["hostname"], "alpha server");
+        assert_eq!(beta["serverId"], "beta");
+        assert_eq!(beta["game"]["hostname"], "beta server");
+
+        let missing =
+            tokio_tungstenite::connect_async(format!("ws://{addr}/ws/gamma/monitor?token={token}"))
+                .await;
+        match missing {
+            Err(tokio_tungstenite::tungstenite::Error::Http(response)) => {
+                assert_eq!(response.status(), 404)
+            }
+            other => panic!("expected a 404, got {:?}", other.map(|(_, r)| r.status())),
+        }
+
+        stop.stop(false).await;
+    }
+}
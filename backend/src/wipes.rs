@@ -0,0 +1,276 @@
+use actix_web::{web, HttpResponse};
+use chrono::{DateTime, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use tokio::sync::broadcast;
+use utoipa::ToSchema;
+
+use crate::apierror::ApiError;
+use crate::events::PanelEvent;
+use crate::registry::{ProvisioningStatus, ServerRegistry};
+use crate::scheduler::Scheduler;
+use crate::shutdown::Shutdown;
+
+/// A save created this long after the last recorded wipe is taken as a wipe done outside
+/// the panel. The server only writes the new save once it has generated the map.
+const SAVE_AFTER_WIPE_GRACE_MINS: i64 = 120;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WipeType {
+    Map,
+    /// Map and blueprints.
+    Full,
+}
+
+/// One wipe of a server.
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WipeRecord {
+    pub timestamp: DateTime<Utc>,
+    /// Null for wipes detected from the server's save, which doesn't say which kind.
+    pub wipe_type: Option<WipeType>,
+    /// Map seed after the wipe, when known.
+    pub seed: Option<u32>,
+    /// Map seed before the wipe, when known.
+    #[serde(default)]
+    pub previous_seed: Option<u32>,
+    /// Panel user or "scheduler (<job>)"; null for wipes done outside the panel.
+    pub initiator: Option<String>,
+}
+
+impl WipeRecord {
+    pub fn now(wipe_type: Option<WipeType>, seed: Option<u32>, initiator: Option<String>) -> Self {
+        Self {
+            timestamp: Utc::now(),
+            wipe_type,
+            seed,
+            previous_seed: None,
+            initiator,
+        }
+    }
+
+    pub fn with_previous_seed(mut self, seed: Option<u32>) -> Self {
+        self.previous_seed = seed;
+        self
+    }
+}
+
+/// serverinfo's SaveCreatedTime. Servers print it in UTC, but not always in the same format.
+pub fn parse_save_created(value: &str) -> Option<DateTime<Utc>> {
+    let value = value.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(value) {
+        return Some(time.with_timezone(&Utc));
+    }
+    [
+        "%Y-%m-%dT%H:%M:%S%.f",
+        "%Y-%m-%d %H:%M:%S",
+        "%m/%d/%Y %I:%M:%S %p",
+        "%m/%d/%Y %H:%M:%S",
+    ]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .map(|time| time.and_utc())
+}
+
+/// Record a wipe the panel didn't see, going by when the server's current save was made.
+async fn backfill(registry: &ServerRegistry, server_id: &str) {
+    let Some(rcon) = registry.get_rcon(server_id).await else {
+        return;
+    };
+    let Ok(info) = rcon.server_info().await else {
+        return;
+    };
+    let Some(created) = parse_save_created(&info.save_created_time) else {
+        return;
+    };
+    let Some(def) = registry.get_definition(server_id).await else {
+        return;
+    };
+    let grace = chrono::Duration::minutes(SAVE_AFTER_WIPE_GRACE_MINS);
+    if created > Utc::now() || def.last_wipe_at.is_some_and(|last| created <= last + grace) {
+        return;
+    }
+    tracing::info!(
+        "'{}' has a save from {}, newer than its last recorded wipe; recording it",
+        server_id,
+        created
+    );
+    let wipe = WipeRecord {
+        timestamp: created,
+        wipe_type: None,
+        seed: Some(info.seed).filter(|&s| s != 0),
+        previous_seed: None,
+        initiator: None,
+    };
+    registry.record_wipe(server_id, wipe).await;
+}
+
+/// Background task: check each server's save when the panel starts and whenever a server
+/// comes online, to catch wipes done outside the panel.
+pub fn spawn_wipe_detector(
+    registry: Arc<ServerRegistry>,
+    shutdown: Shutdown,
+) -> tokio::task::JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut events = registry.events.subscribe();
+        for def in registry.all_definitions().await {
+            if def.provisioning_status == ProvisioningStatus::Ready {
+                backfill(&registry, &def.id).await;
+            }
+        }
+        loop {
+            let event = tokio::select! {
+                event = events.recv() => match event {
+                    Ok(event) => event,
+                    Err(broadcast::error::RecvError::Lagged(_)) => continue,
+                    Err(broadcast::error::RecvError::Closed) => break,
+                },
+                _ = shutdown.signal() => break,
+            };
+            if let PanelEvent::ServerOnline { server_id } = &event.event {
+                backfill(&registry, server_id).await;
+            }
+        }
+    })
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WipesResponse {
+    pub last_wipe: Option<DateTime<Utc>>,
+    /// Earliest next run of the server's enabled wipe jobs.
+    pub next_scheduled_wipe: Option<DateTime<Utc>>,
+    /// Newest first.
+    pub wipes: Vec<WipeRecord>,
+}
+
+/// GET /api/servers/{server_id}/wipes
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/wipes",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, body = WipesResponse),
+        (status = 404, description = "Server not found", body = ApiError),
+        (status = 500, description = "Wipe history couldn't be read", body = ApiError),
+    )
+)]
+pub async fn list_wipes(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> Result<HttpResponse, ApiError> {
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let wipes = registry
+        .wipes(&server_id)
+        .map_err(|e| ApiError::internal(format!("Failed to read wipe history: {}", e)))?;
+    Ok(HttpResponse::Ok().json(WipesResponse {
+        last_wipe: def.last_wipe_at,
+        next_scheduled_wipe: scheduler.next_wipes().await.remove(def.id.as_str()),
+        wipes,
+    }))
+}
+
+/// The next run of a wipe job.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ScheduledWipe {
+    pub job_id: String,
+    pub job_name: String,
+    pub wipe_type: WipeType,
+    pub at: DateTime<Utc>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum LastWipeSource {
+    /// A wipe the panel recorded (see GET .../wipes).
+    Recorded,
+    /// The map file of the server's current save.
+    SaveFiles,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct WipeInfo {
+    pub last_wipe: Option<DateTime<Utc>>,
+    pub last_wipe_source: Option<LastWipeSource>,
+    pub next_wipe: Option<ScheduledWipe>,
+    /// Seconds until `next_wipe`.
+    pub countdown_secs: Option<i64>,
+}
+
+/// When the server's current map was generated: the newest .map file's modification
+/// time, or a .sav file's creation time where the filesystem keeps one. The save itself
+/// is rewritten on every server.save, so its modification time says nothing.
+fn save_files_created(server_files: &str) -> Option<DateTime<Utc>> {
+    let server_dir = format!("{}/server/rustserver", server_files);
+    std::fs::read_dir(server_dir)
+        .ok()?
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let metadata = entry.metadata().ok()?;
+            match path.extension().and_then(|e| e.to_str())? {
+                "map" => metadata.modified().ok(),
+                "sav" => metadata.created().ok(),
+                _ => None,
+            }
+        })
+        .max()
+        .map(DateTime::<Utc>::from)
+}
+
+/// GET /api/servers/{server_id}/wipe-info
+#[utoipa::path(
+    get,
+    path = "/api/servers/{server_id}/wipe-info",
+    tag = "servers",
+    params(("server_id" = String, Path, description = "Server id")),
+    responses(
+        (status = 200, description = "Last wipe, the next scheduled one and a countdown to it", body = WipeInfo),
+        (status = 404, description = "Server not found", body = ApiError),
+    )
+)]
+pub async fn wipe_info(
+    server_id: web::Path<String>,
+    registry: web::Data<Arc<ServerRegistry>>,
+    scheduler: web::Data<Arc<Scheduler>>,
+) -> Result<HttpResponse, ApiError> {
+    let def = registry
+        .get_definition(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+    let config = registry
+        .get_config(&server_id)
+        .await
+        .ok_or_else(ApiError::server_not_found)?;
+
+    // The map is generated a little after a recorded wipe; only a much newer one means
+    // the server was wiped outside the panel since
+    let grace = chrono::Duration::minutes(SAVE_AFTER_WIPE_GRACE_MINS);
+    let recorded = def.last_wipe_at.map(|at| (at, LastWipeSource::Recorded));
+    let from_files =
+        save_files_created(&config.paths.server_files).map(|at| (at, LastWipeSource::SaveFiles));
+    let last = match (recorded, from_files) {
+        (Some(r), Some(f)) if f.0 > r.0 + grace => Some(f),
+        (Some(r), _) => Some(r),
+        (None, f) => f,
+    };
+
+    let next_wipe = scheduler.next_wipe(&server_id).await;
+    let countdown_secs = next_wipe
+        .as_ref()
+        .map(|wipe| (wipe.at - Utc::now()).num_seconds().max(0));
+    Ok(HttpResponse::Ok().json(WipeInfo {
+        last_wipe: last.map(|(at, _)| at),
+        last_wipe_source: last.map(|(_, source)| source),
+        next_wipe,
+        countdown_secs,
+    }))
+}
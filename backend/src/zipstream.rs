@@ -0,0 +1,276 @@
+use chrono::{DateTime, Datelike, Timelike, Utc};
+use flate2::write::DeflateEncoder;
+use flate2::{Compression, Crc};
+use std::io::{Read, Write};
+
+/// Archives past these need ZIP64, which this writer doesn't do.
+pub const MAX_ARCHIVE_BYTES: u64 = u32::MAX as u64;
+pub const MAX_ENTRIES: usize = u16::MAX as usize;
+
+/// Fixed part of a local header, data descriptor, central header and end record.
+const LOCAL_HEADER_LEN: u64 = 30;
+const DATA_DESCRIPTOR_LEN: u64 = 16;
+const CENTRAL_HEADER_LEN: u64 = 46;
+const END_OF_CENTRAL_DIR_LEN: u64 = 22;
+
+const LOCAL_HEADER: u32 = 0x0403_4b50;
+const DATA_DESCRIPTOR: u32 = 0x0807_4b50;
+const CENTRAL_HEADER: u32 = 0x0201_4b50;
+const END_OF_CENTRAL_DIR: u32 = 0x0605_4b50;
+
+/// Zip 2.0, made on Unix (so external attributes carry the mode).
+const VERSION_NEEDED: u16 = 20;
+const VERSION_MADE_BY: u16 = (3 << 8) | 20;
+/// Names are UTF-8.
+const FLAG_UTF8: u16 = 1 << 11;
+/// CRC and sizes follow the data, so files are written without knowing them up front.
+const FLAG_DATA_DESCRIPTOR: u16 = 1 << 3;
+const METHOD_STORED: u16 = 0;
+const METHOD_DEFLATE: u16 = 8;
+
+/// A central directory record, written by `finish`.
+struct Entry {
+    name: String,
+    flags: u16,
+    method: u16,
+    time: u16,
+    date: u16,
+    crc: u32,
+    compressed: u32,
+    size: u32,
+    mode: u32,
+    offset: u32,
+}
+
+/// Counts what passes through, for entry offsets and compressed sizes.
+struct Counter<W> {
+    inner: W,
+    written: u64,
+}
+
+impl<W: Write> Write for Counter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let n = self.inner.write(buf)?;
+        self.written += n as u64;
+        Ok(n)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Writes a zip archive front to back without seeking, so it can go straight into a
+/// response body. Files are deflated; their CRC and sizes go in a trailing descriptor.
+pub struct ZipWriter<W: Write> {
+    out: Counter<W>,
+    entries: Vec<Entry>,
+}
+
+fn too_large() -> std::io::Error {
+    std::io::Error::other("archive too large for zip (4 GiB or 65535 entries)")
+}
+
+fn to_u32(value: u64) -> std::io::Result<u32> {
+    u32::try_from(value).map_err(|_| too_large())
+}
+
+/// Most bytes deflate can turn `size` bytes into (zlib's deflateBound).
+fn deflate_bound(size: u64) -> u64 {
+    size + (size >> 12) + (size >> 14) + (size >> 25) + 13
+}
+
+/// Upper bound on the size of an archive holding these entries: `(name, None)` for a
+/// directory, `(name, Some(size))` for a file. Lets callers refuse an archive that would
+/// need ZIP64 before anything is sent.
+pub fn archive_bound<'a>(entries: impl IntoIterator<Item = (&'a str, Option<u64>)>) -> u64 {
+    entries
+        .into_iter()
+        .map(|(name, size)| {
+            // Directory names gain a trailing '/'
+            let name = name.len() as u64 + 1;
+            let data = size.map_or(0, |size| deflate_bound(size) + DATA_DESCRIPTOR_LEN);
+            LOCAL_HEADER_LEN + CENTRAL_HEADER_LEN + 2 * name + data
+        })
+        .sum::<u64>()
+        + END_OF_CENTRAL_DIR_LEN
+}
+
+/// MS-DOS time and date, the only timestamp the basic zip headers hold.
+fn dos_datetime(at: DateTime<Utc>) -> (u16, u16) {
+    if at.year() < 1980 {
+        return (0, (1 << 5) | 1);
+    }
+    let time = (at.hour() << 11) | (at.minute() << 5) | (at.second() / 2);
+    let date = ((at.year() as u32 - 1980).min(127) << 9) | (at.month() << 5) | at.day();
+    (time as u16, date as u16)
+}
+
+impl<W: Write> ZipWriter<W> {
+    pub fn new(out: W) -> Self {
+        Self {
+            out: Counter {
+                inner: out,
+                written: 0,
+            },
+            entries: Vec::new(),
+        }
+    }
+
+    fn begin(
+        &mut self,
+        name: &str,
+        flags: u16,
+        method: u16,
+        at: DateTime<Utc>,
+    ) -> std::io::Result<Entry> {
+        if self.entries.len() >= MAX_ENTRIES {
+            return Err(too_large());
+        }
+        let (time, date) = dos_datetime(at);
+        let entry = Entry {
+            name: name.to_string(),
+            flags,
+            method,
+            time,
+            date,
+            crc: 0,
+            compressed: 0,
+            size: 0,
+            mode: 0,
+            offset: to_u32(self.out.written)?,
+        };
+        let out = &mut self.out;
+        out.write_all(&LOCAL_HEADER.to_le_bytes())?;
+        out.write_all(&VERSION_NEEDED.to_le_bytes())?;
+        out.write_all(&flags.to_le_bytes())?;
+        out.write_all(&method.to_le_bytes())?;
+        out.write_all(&time.to_le_bytes())?;
+        out.write_all(&date.to_le_bytes())?;
+        // CRC, compressed and uncompressed size: in the data descriptor, or zero
+        out.write_all(&[0; 12])?;
+        out.write_all(&(name.len() as u16).to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?;
+        out.write_all(name.as_bytes())?;
+        Ok(entry)
+    }
+
+    /// Add a directory entry; `name` gets a trailing '/'.
+    pub fn add_dir(&mut self, name: &str, modified: DateTime<Utc>) -> std::io::Result<()> {
+        let name = format!("{}/", name.trim_end_matches('/'));
+        let mut entry = self.begin(&name, FLAG_UTF8, METHOD_STORED, modified)?;
+        entry.mode = 0o40755;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Add a file, deflating `data` as it is read.
+    pub fn add_file(
+        &mut self,
+        name: &str,
+        modified: DateTime<Utc>,
+        mut data: impl Read,
+    ) -> std::io::Result<()> {
+        let flags = FLAG_UTF8 | FLAG_DATA_DESCRIPTOR;
+        let mut entry = self.begin(name, flags, METHOD_DEFLATE, modified)?;
+        let start = self.out.written;
+
+        let mut crc = Crc::new();
+        let mut encoder = DeflateEncoder::new(&mut self.out, Compression::default());
+        let mut buf = vec![0u8; 64 * 1024];
+        loop {
+            let n = data.read(&mut buf)?;
+            if n == 0 {
+                break;
+            }
+            crc.update(&buf[..n]);
+            encoder.write_all(&buf[..n])?;
+        }
+        encoder.finish()?;
+
+        entry.crc = crc.sum();
+        entry.size = to_u32(crc.amount() as u64)?;
+        entry.compressed = to_u32(self.out.written - start)?;
+        entry.mode = 0o100644;
+        self.out.write_all(&DATA_DESCRIPTOR.to_le_bytes())?;
+        self.out.write_all(&entry.crc.to_le_bytes())?;
+        self.out.write_all(&entry.compressed.to_le_bytes())?;
+        self.out.write_all(&entry.size.to_le_bytes())?;
+        self.entries.push(entry);
+        Ok(())
+    }
+
+    /// Write the central directory and return the underlying writer.
+    pub fn finish(mut self) -> std::io::Result<W> {
+        let start = to_u32(self.out.written)?;
+        for entry in &self.entries {
+            let out = &mut self.out;
+            out.write_all(&CENTRAL_HEADER.to_le_bytes())?;
+            out.write_all(&VERSION_MADE_BY.to_le_bytes())?;
+            out.write_all(&VERSION_NEEDED.to_le_bytes())?;
+            out.write_all(&entry.flags.to_le_bytes())?;
+            out.write_all(&entry.method.to_le_bytes())?;
+            out.write_all(&entry.time.to_le_bytes())?;
+            out.write_all(&entry.date.to_le_bytes())?;
+            out.write_all(&entry.crc.to_le_bytes())?;
+            out.write_all(&entry.compressed.to_le_bytes())?;
+            out.write_all(&entry.size.to_le_bytes())?;
+            out.write_all(&(entry.name.len() as u16).to_le_bytes())?;
+            // Extra field and comment lengths, disk number, internal attributes
+            out.write_all(&[0; 8])?;
+            out.write_all(&(entry.mode << 16).to_le_bytes())?;
+            out.write_all(&entry.offset.to_le_bytes())?;
+            out.write_all(entry.name.as_bytes())?;
+        }
+        let size = to_u32(self.out.written - start as u64)?;
+        let count = self.entries.len() as u16;
+
+        let out = &mut self.out;
+        out.write_all(&END_OF_CENTRAL_DIR.to_le_bytes())?;
+        // This disk and the disk the central directory starts on
+        out.write_all(&[0; 4])?;
+        out.write_all(&count.to_le_bytes())?;
+        out.write_all(&count.to_le_bytes())?;
+        out.write_all(&size.to_le_bytes())?;
+        out.write_all(&start.to_le_bytes())?;
+        out.write_all(&0u16.to_le_bytes())?;
+        out.flush()?;
+        Ok(self.out.inner)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_bound_covers_what_is_written() {
+        let at = Utc::now();
+        // Incompressible, so deflate's overhead shows
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let random: Vec<u8> = (0..200_000)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                state as u8
+            })
+            .collect();
+        let mut zip = ZipWriter::new(Vec::new());
+        zip.add_dir("logs", at).unwrap();
+        zip.add_file("logs/empty.txt", at, &[][..]).unwrap();
+        zip.add_file("logs/noise.bin", at, &random[..]).unwrap();
+        let written = zip.finish().unwrap().len() as u64;
+
+        let bound = archive_bound([
+            ("logs", None),
+            ("logs/empty.txt", Some(0)),
+            ("logs/noise.bin", Some(random.len() as u64)),
+        ]);
+        assert!(written <= bound, "wrote {written} bytes, bound {bound}");
+        assert!(
+            bound - written < 1_000,
+            "bound {bound} is far above {written}"
+        );
+    }
+}
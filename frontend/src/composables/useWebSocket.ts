@@ -1,4 +1,5 @@
 import { ref, onUnmounted } from 'vue'
+import { currentAccessToken } from '../services/api'
 
 export function useWebSocket(serverId: string, channel: string = 'console') {
   const messages = ref<string[]>([])
@@ -9,14 +10,16 @@ export function useWebSocket(serverId: string, channel: string = 'console') {
   let reconnectTimer: ReturnType<typeof setTimeout> | null = null
   let intentionalClose = false
 
-  function connect() {
+  async function connect() {
     if (ws && ws.readyState === WebSocket.OPEN) return
 
-    const token = localStorage.getItem('jwt_token')
+    const token = await currentAccessToken()
+    if (intentionalClose) return
     const protocol = window.location.protocol === 'https:' ? 'wss:' : 'ws:'
-    const wsUrl = `${protocol}//${window.location.host}/ws/${serverId}/${channel}${token ? `?token=${token}` : ''}`
+    const wsUrl = `${protocol}//${window.location.host}/ws/${serverId}/${channel}`
 
-    ws = new WebSocket(wsUrl)
+    // The token travels in Sec-WebSocket-Protocol so it stays out of URLs and proxy logs
+    ws = token ? new WebSocket(wsUrl, ['bearer', token]) : new WebSocket(wsUrl)
 
     ws.onopen = () => {
       connected.value = true
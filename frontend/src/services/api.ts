@@ -1,4 +1,5 @@
 import axios from 'axios'
+import type { LoginResponse } from '../types'
 
 const api = axios.create({
   baseURL: '/api',
@@ -16,11 +17,58 @@ api.interceptors.request.use((config) => {
   return config
 })
 
+/** One refresh at a time; requests that fail together wait for the same new token */
+let refreshing: Promise<string | null> | null = null
+
+/** Exchange the stored refresh token for a new pair; null if the session is over */
+export function refreshAccessToken(): Promise<string | null> {
+  if (!refreshing) {
+    const refreshToken = localStorage.getItem('refresh_token')
+    refreshing = (
+      refreshToken
+        ? axios
+            .post<LoginResponse>('/api/auth/refresh', { refreshToken })
+            .then(({ data }) => {
+              localStorage.setItem('jwt_token', data.token)
+              localStorage.setItem('refresh_token', data.refresh_token)
+              return data.token
+            })
+            .catch(() => null)
+        : Promise.resolve(null)
+    ).finally(() => {
+      refreshing = null
+    })
+  }
+  return refreshing
+}
+
+/** The stored access token, refreshed first if it has expired (for WebSocket handshakes) */
+export async function currentAccessToken(): Promise<string | null> {
+  const token = localStorage.getItem('jwt_token')
+  if (!token) return null
+  try {
+    const payload = JSON.parse(atob(token.split('.')[1].replace(/-/g, '+').replace(/_/g, '/')))
+    if (payload.exp * 1000 > Date.now() + 10_000) return token
+  } catch {
+    return token
+  }
+  return (await refreshAccessToken()) ?? token
+}
+
 api.interceptors.response.use(
   (response) => response,
-  (error) => {
-    if (error.response?.status === 401) {
+  async (error) => {
+    const original = error.config
+    const isAuthCall = original?.url?.startsWith('/auth/')
+    if (error.response?.status === 401 && original && !isAuthCall && !original._retried) {
+      const token = await refreshAccessToken()
+      if (token) {
+        original._retried = true
+        original.headers.Authorization = `Bearer ${token}`
+        return api(original)
+      }
       localStorage.removeItem('jwt_token')
+      localStorage.removeItem('refresh_token')
       window.location.href = '/login'
     }
     return Promise.reject(error)
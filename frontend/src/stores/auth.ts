@@ -17,6 +17,7 @@ export const useAuthStore = defineStore('auth', () => {
       const response = await api.post<LoginResponse>('/auth/login', credentials)
       token.value = response.data.token
       localStorage.setItem('jwt_token', response.data.token)
+      localStorage.setItem('refresh_token', response.data.refresh_token)
       return true
     } catch (err: unknown) {
       if (err && typeof err === 'object' && 'response' in err) {
@@ -32,8 +33,14 @@ export const useAuthStore = defineStore('auth', () => {
   }
 
   function logout() {
+    const refreshToken = localStorage.getItem('refresh_token')
+    if (refreshToken) {
+      // Revoke the session server-side; the local tokens go either way
+      api.post('/auth/logout', { refreshToken }).catch(() => {})
+    }
     token.value = null
     localStorage.removeItem('jwt_token')
+    localStorage.removeItem('refresh_token')
   }
 
   return {
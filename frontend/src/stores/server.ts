@@ -1,7 +1,7 @@
 import { defineStore } from 'pinia'
 import { ref } from 'vue'
 import api from '../services/api'
-import type { GameServer, CreateServerRequest } from '../types'
+import type { GameServer, CreateServerRequest, CloneServerRequest } from '../types'
 
 export const useServerStore = defineStore('server', () => {
   const servers = ref<GameServer[]>([])
@@ -29,9 +29,19 @@ export const useServerStore = defineStore('server', () => {
     }
   }
 
-  async function deleteServer(id: string): Promise<boolean> {
+  async function cloneServer(id: string, req: CloneServerRequest): Promise<{ id: string } | null> {
     try {
-      await api.delete(`/servers/${id}`)
+      const res = await api.post<{ id: string; name: string; status: string }>(`/servers/${id}/clone`, req)
+      await fetchServers()
+      return { id: res.data.id }
+    } catch {
+      return null
+    }
+  }
+
+  async function deleteServer(id: string, deleteFiles = false): Promise<boolean> {
+    try {
+      await api.delete(`/servers/${id}`, { params: deleteFiles ? { deleteFiles: true } : {} })
       await fetchServers()
       return true
     } catch {
@@ -48,6 +58,7 @@ export const useServerStore = defineStore('server', () => {
     loading,
     fetchServers,
     createServer,
+    cloneServer,
     deleteServer,
     getServer,
   }
@@ -2,6 +2,9 @@ export interface GameServer {
   id: string
   name: string
   online: boolean
+  state?: 'online' | 'offline' | 'stopped' | 'maintenance'
+  desiredState?: 'running' | 'stopped'
+  maintenance?: { note?: string | null; startedAt: string; until?: string | null; setBy?: string | null } | null
   serverType: 'vanilla' | 'modded'
   gamePort: number
   rconPort: number
@@ -9,10 +12,19 @@ export interface GameServer {
   maxPlayers: number
   worldSize: number
   seed: number
-  provisioningStatus: 'ready' | 'installing' | 'downloading' | 'installing_oxide' | 'configuring' | 'error'
+  provisioningStatus: 'ready' | 'installing' | 'downloading' | 'installing_oxide' | 'configuring' | 'copying_files' | 'error' | 'pending_attach'
   source: 'static' | 'dynamic'
   players?: number
   createdAt: string
+  description?: string | null
+  tags?: string[]
+  monitoring?: 'active' | 'paused'
+  autoStart?: boolean
+  rconConnected?: boolean
+  currentAction?: { action: string; startedAt: string; initiatedBy?: string | null } | null
+  provisioningPercent?: number | null
+  lastSnapshotAt?: string | null
+  fps?: number | null
 }
 
 export interface CreateServerRequest {
@@ -22,6 +34,20 @@ export interface CreateServerRequest {
   worldSize?: number
   seed?: number
   hostname?: string
+  /** Fixed ports instead of the next free slot */
+  gamePort?: number
+  rconPort?: number
+  queryPort?: number
+}
+
+export interface CloneServerRequest {
+  name: string
+  hostname?: string
+  /** A new random seed when left out */
+  seed?: number
+  description?: string
+  /** Also copy the source's server.cfg convars */
+  copyServerCfg?: boolean
 }
 
 export interface PlayerPosition {
@@ -30,6 +56,20 @@ export interface PlayerPosition {
   x: number
   y: number
   z: number
+  // Only pushed by newer versions of the position plugin
+  isSleeping?: boolean | null
+  isDead?: boolean | null
+  teamId?: number | null
+  health?: number | null
+}
+
+export interface TrackedPosition extends PlayerPosition {
+  lastUpdated: string
+}
+
+export interface PositionsResponse {
+  players: TrackedPosition[]
+  lastUpdate: string | null
 }
 
 export interface ServerInfo {
@@ -41,15 +81,24 @@ export interface ServerInfo {
   uptime: number
   map: string
   online: boolean
-  cpuPercent: number
+  // Usage of the server's own process; null when it isn't running
+  cpuPercent: number | null
   memTotal: number
-  memUsed: number
-  memPercent: number
+  memUsed: number | null
+  memPercent: number | null
+  threads: number | null
   diskTotal: number
   diskUsed: number
   diskPercent: number
 }
 
+export interface DiskStats {
+  mount: string
+  total: number
+  used: number
+  percent: number
+}
+
 export interface SystemStats {
   cpuPercent: number
   memTotal: number
@@ -58,6 +107,9 @@ export interface SystemStats {
   diskTotal: number
   diskUsed: number
   diskPercent: number
+  disks: DiskStats[]
+  rxBytesPerSec: number
+  txBytesPerSec: number
   timestamp: string
 }
 
@@ -70,6 +122,9 @@ export interface GameStats {
   uptime: number
   map: string
   hostname: string
+  cpuPercent: number | null
+  memoryBytes: number | null
+  threads: number | null
   timestamp: string
 }
 
@@ -78,6 +133,26 @@ export interface MonitorResponse<T> {
   history: T[]
 }
 
+export interface MetricStat {
+  avg: number
+  min: number
+  max: number
+  count: number
+}
+
+export interface MetricBucket {
+  timestamp: string
+  values: Record<string, MetricStat>
+}
+
+export interface MetricRange {
+  from: string
+  to: string
+  resolutionSecs: number
+  tier: 'raw' | '1m' | '1h'
+  buckets: MetricBucket[]
+}
+
 export interface Player {
   steamId: string
   displayName: string
@@ -102,6 +177,38 @@ export interface Plugin {
   size: number
   modified: string
   hasConfig: boolean
+  /** False while the file is parked in oxide/plugins_disabled */
+  enabled: boolean
+}
+
+export interface BanRecord {
+  steamId: string
+  name: string | null
+  reason: string
+  bannedBy: string | null
+  createdAt: string
+  /** Null for permanent bans */
+  expiresAt: string | null
+  /** Lifted by the panel when it ran out */
+  expired: boolean
+}
+
+export interface ConsoleExchange {
+  command: string
+  /** The reply, or why the command failed or was refused */
+  response: string
+  success: boolean
+  username: string
+  timestamp: string
+}
+
+export interface PlayerRecord {
+  steamId: string
+  name: string
+  firstSeen: string
+  lastSeen: string
+  totalPlaytimeSecs: number
+  sessionCount: number
 }
 
 export interface UModPlugin {
@@ -125,6 +232,44 @@ export interface ScheduledJob {
   nextRun: string | null
   createdAt: string
   serverId: string
+  queueWhenOffline?: boolean
+  disabledReason?: string | null
+  nextRuns?: string[]
+  lastResult?: { success: boolean; message: string; at: string } | null
+  misfirePolicy?: 'run_once_late' | 'skip'
+  pauseWindows?: PauseWindow[]
+  updatePendingSince?: string | null
+  warnings?: RestartWarnings | null
+}
+
+export interface RestartWarnings {
+  /** Minutes before the run to warn at, e.g. [15, 5, 1] */
+  warnMinutes: number[]
+  /** `{minutes}` is replaced with the minutes left */
+  message?: string
+}
+
+export interface PauseWindow {
+  start: string
+  end: string
+  timezone: string
+  reason?: string | null
+}
+
+export interface JobRun {
+  runId: string
+  jobId: string
+  jobName: string
+  serverId: string
+  trigger: 'schedule' | 'manual'
+  startedAt: string
+  finishedAt: string
+  success: boolean
+  skipped?: boolean
+  suppressed?: boolean
+  output: string
+  steps?: { index: number; jobType: string; success: boolean; output: string }[]
+  notification?: string
 }
 
 export interface LoginRequest {
@@ -135,7 +280,9 @@ export interface LoginRequest {
 export interface LoginResponse {
   token: string
   username: string
-  expiresAt: string
+  expires_at: string
+  refresh_token: string
+  refresh_expires_at: string
 }
 
 export interface ApiError {